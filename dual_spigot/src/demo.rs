@@ -66,8 +66,8 @@ fn main() {
         SpigotConfig::new(Constant::E,   2),
     );
     ds.left().drop(10); // advance live cursor
-    ds.snip("hex_pi_bin_e_0_to_8", 0, 8);  // absolute snapshot
-    ds.snip("hex_pi_bin_e_4_to_12", 4, 12);
+    ds.snip("hex_pi_bin_e_0_to_8", 0, 8).unwrap();  // absolute snapshot
+    ds.snip("hex_pi_bin_e_4_to_12", 4, 12).unwrap();
 
     println!("   Live cursors unaffected: L={}, R={}", ds.left_pos(), ds.right_pos());
     let s1 = ds.get_snippet("hex_pi_bin_e_0_to_8").unwrap();
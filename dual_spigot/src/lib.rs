@@ -5,17 +5,64 @@
 //! output base per side.
 //!
 //! Each side carries its own [`Constant`] *and* its own base, so you can
-//! zip, say, π in base 16 against e in base 2.
+//! zip, say, π in base 16 against e in base 2.  A side can also be fed by
+//! any [`DigitSource`] — a file, a PRNG, a live sensor — through
+//! [`DualStream::from_sources`]; [`CfDigits`] is one that plays a
+//! constant's continued-fraction terms.
 //!
-//! See [`DualStream`] for the full API.
+//! See [`DualStream`] for the full API, and [`SharedStream`] for one
+//! stream used from several threads.  Fallible calls return a
+//! [`SpigotError`].
+//!
+//! With the `async` feature, [`DualStream::into_async_zip`] and
+//! [`SharedStream::async_zip`] poll the zip as a `futures::Stream`, for
+//! async servers and GUI frameworks; see `spigot_stream::async_stream`.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+#[cfg(feature = "async")]
+pub use spigot_stream::async_stream::{AsyncBatches, AsyncDigits};
 use spigot_stream::{
     Constant,
     PiStream, EStream, Ln2Stream,
-    LiouvilleStream, ChampernowneStream, ThueMorseStream,
+    LiouvilleStream, ChampernowneStream, ThueMorseStream, Sqrt2Stream, PhiStream, RationalStream,
+    ContinuedFractionStream,
 };
 
+// ════════════════════════════════════════════════════════════════════════════
+// SpigotError
+// ════════════════════════════════════════════════════════════════════════════
+
+/// What went wrong in a [`SpigotConfig`] or [`DualStream`] call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpigotError {
+    /// A base outside 2–36.
+    Base(u8),
+    /// A rational `p/0`.
+    ZeroDenominator,
+    /// A snip range running backwards.
+    Range { from: usize, to: usize },
+    /// No snippet stored under this name.
+    NoSnippet(String),
+    /// A snip reaching back before the oldest digit a custom source keeps.
+    Forgotten(usize),
+}
+
+impl std::fmt::Display for SpigotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpigotError::Base(base)         => write!(f, "base must be 2–36, got {}", base),
+            SpigotError::ZeroDenominator    => write!(f, "a rational's denominator must be above 0"),
+            SpigotError::Range { from, to } => write!(f, "snip: from ({}) must be <= to ({})", from, to),
+            SpigotError::NoSnippet(key)     => write!(f, "no snippet named '{}'", key),
+            SpigotError::Forgotten(first)   => write!(f, "snip: digits before {} are no longer kept", first),
+        }
+    }
+}
+
+impl std::error::Error for SpigotError {}
+
 // ════════════════════════════════════════════════════════════════════════════
 // SpigotConfig — constant + base pair
 // ════════════════════════════════════════════════════════════════════════════
@@ -28,9 +75,19 @@ pub struct SpigotConfig {
 }
 
 impl SpigotConfig {
+    /// Panics if `base` is outside 2–36, or the constant is a rational
+    /// over 0; see [`try_new`](Self::try_new).
     pub fn new(constant: Constant, base: u8) -> Self {
-        assert!(base >= 2 && base <= 36, "base must be 2–36, got {}", base);
-        SpigotConfig { constant, base }
+        Self::try_new(constant, base).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// As [`new`](Self::new), for a base that may be out of range.
+    pub fn try_new(constant: Constant, base: u8) -> Result<Self, SpigotError> {
+        match (constant, base) {
+            (Constant::Rational(_, 0), _) => Err(SpigotError::ZeroDenominator),
+            (_, 2..=36) => Ok(SpigotConfig { constant, base }),
+            _           => Err(SpigotError::Base(base)),
+        }
     }
 
     /// Shorthand: decimal (base 10) for this constant.
@@ -38,38 +95,281 @@ impl SpigotConfig {
 }
 
 // ════════════════════════════════════════════════════════════════════════════
-// BoxedSpigot — type-erased cursor with (Constant, base, position)
+// DigitSource — digits from anywhere
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Digits for one side of a [`DualStream`] from outside the built-in
+/// constants: a file, a PRNG, a live sensor.
+///
+/// ```rust
+/// use dual_spigot::{DigitSource, DualStream};
+///
+/// /// 0, 1, 2, … counting round in base 8.
+/// struct Octal(u8);
+///
+/// impl DigitSource for Octal {
+///     fn next_digit(&mut self) -> Option<u8> { self.0 = (self.0 + 1) % 8; Some(self.0) }
+///     fn base(&self) -> u8 { 8 }
+///     fn name(&self) -> String { "octal counter".into() }
+/// }
+///
+/// let mut ds = DualStream::from_sources(Octal(0), Octal(3)).unwrap();
+/// assert_eq!(ds.zip_take(2), vec![(1, 4), (2, 5)]);
+/// ```
+pub trait DigitSource: Send {
+    /// The next digit, below [`base`](Self::base), or `None` once the
+    /// source has run dry.
+    fn next_digit(&mut self) -> Option<u8>;
+    /// The base the digits are in, 2–36.
+    fn base(&self) -> u8;
+    /// What the source is called in [`DualStream::status`].
+    fn name(&self) -> String;
+}
+
+/// An iterator of digits as a [`DigitSource`].
+pub struct DigitIter<I> {
+    name:   String,
+    base:   u8,
+    digits: I,
+}
+
+impl<I: Iterator<Item = u8> + Send> DigitIter<I> {
+    pub fn new(name: impl Into<String>, base: u8, digits: I) -> Self {
+        DigitIter { name: name.into(), base, digits }
+    }
+}
+
+impl<I: Iterator<Item = u8> + Send> DigitSource for DigitIter<I> {
+    fn next_digit(&mut self) -> Option<u8> { self.digits.next() }
+    fn base(&self) -> u8 { self.base }
+    fn name(&self) -> String { self.name.clone() }
+}
+
+/// A constant's continued-fraction terms as digits, each capped at
+/// `base − 1`, for a side that plays the terms instead of the positional
+/// digits.  A rational's terms run out.
+///
+/// ```rust
+/// use dual_spigot::{CfDigits, DualStream, SpigotConfig};
+/// use spigot_stream::Constant;
+///
+/// let mut ds = DualStream::from_sources(
+///     CfDigits::new(SpigotConfig::decimal(Constant::Sqrt2)),
+///     CfDigits::new(SpigotConfig::new(Constant::Rational(415, 93), 6)),
+/// ).unwrap();
+/// assert_eq!(ds.zip_take(5), vec![(1, 4), (2, 2), (2, 5), (2, 5)]);
+/// ```
+pub struct CfDigits {
+    terms:  ContinuedFractionStream,
+    config: SpigotConfig,
+}
+
+impl CfDigits {
+    pub fn new(config: SpigotConfig) -> Self {
+        CfDigits { terms: ContinuedFractionStream::of(config.constant, config.base), config }
+    }
+}
+
+impl DigitSource for CfDigits {
+    fn next_digit(&mut self) -> Option<u8> {
+        self.terms.next().map(|a| a.min(self.config.base as u64 - 1) as u8)
+    }
+    fn base(&self) -> u8 { self.config.base }
+    fn name(&self) -> String { format!("{} as a continued fraction", self.config.constant.name()) }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// BoxedSpigot — type-erased cursor with (source, base, position)
 // ════════════════════════════════════════════════════════════════════════════
 
 pub struct BoxedSpigot {
-    inner:    Box<dyn Iterator<Item = u8> + Send>,
-    pub config:   SpigotConfig,
+    source:       Source,
     pub position: usize,
 }
 
+enum Source {
+    /// A built-in constant, restarted from its config to go back.
+    Constant {
+        digits: Box<dyn Iterator<Item = u8> + Send>,
+        config: SpigotConfig,
+    },
+    /// A [`DigitSource`], which can't be restarted, so going back reads
+    /// again the digits it kept.
+    Custom {
+        source: Box<dyn DigitSource>,
+        kept:   Kept,
+        id:     u64,
+    },
+}
+
+/// The digits a custom source has given that may be read again: those
+/// read ahead of the cursor, and those behind it back to the oldest live
+/// [`Checkpoint`] — or all of them, when its history is kept.
+struct Kept {
+    digits:  VecDeque<u8>,
+    /// The position of `digits[0]`.
+    first:   usize,
+    /// The positions live checkpoints can rewind to.
+    holds:   Vec<Weak<usize>>,
+    history: bool,
+}
+
+impl Kept {
+    /// The digit at `at`, reading ahead as far as needed; `None` once the
+    /// source runs dry, or if the digit is no longer kept.
+    fn get(&mut self, source: &mut dyn DigitSource, at: usize) -> Option<u8> {
+        if at < self.first { return None; }
+        while self.first + self.digits.len() <= at {
+            self.digits.push_back(source.next_digit()?);
+        }
+        Some(self.digits[at - self.first])
+    }
+
+    /// Drop the digits behind `position` that no checkpoint needs.
+    fn trim(&mut self, position: usize) {
+        if self.history { return; }
+        self.holds.retain(|hold| hold.strong_count() > 0);
+        let floor = self.holds.iter().filter_map(Weak::upgrade).fold(position, |floor, at| floor.min(*at));
+        let stale = floor.saturating_sub(self.first).min(self.digits.len());
+        self.digits.drain(..stale);
+        self.first += stale;
+    }
+}
+
+/// Tells sides apart for [`Checkpoint`]s: a constant by its config, a
+/// custom source by the number it was given when boxed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SideKey {
+    Constant(SpigotConfig),
+    Custom(u64),
+}
+
+static NEXT_CUSTOM_ID: AtomicU64 = AtomicU64::new(0);
+
 impl BoxedSpigot {
-    fn from_config(cfg: SpigotConfig) -> Self {
-        let inner: Box<dyn Iterator<Item = u8> + Send> = match cfg.constant {
+    /// Open a fresh cursor at position 0 for `cfg`.
+    pub fn from_config(cfg: SpigotConfig) -> Self {
+        let digits: Box<dyn Iterator<Item = u8> + Send> = match cfg.constant {
             Constant::Pi           => Box::new(PiStream::with_base(cfg.base)),
             Constant::E            => Box::new(EStream::with_base(cfg.base)),
             Constant::Ln2          => Box::new(Ln2Stream::with_base(cfg.base)),
             Constant::Liouville    => Box::new(LiouvilleStream::with_base(cfg.base)),
             Constant::Champernowne => Box::new(ChampernowneStream::with_base(cfg.base)),
             Constant::ThueMorse    => Box::new(ThueMorseStream::with_base(cfg.base)),
+            Constant::Sqrt2        => Box::new(Sqrt2Stream::with_base(cfg.base)),
+            Constant::Phi          => Box::new(PhiStream::with_base(cfg.base)),
+            Constant::Rational(p, q) => Box::new(RationalStream::new(p, q, cfg.base)),
         };
-        BoxedSpigot { inner, config: cfg, position: 0 }
+        BoxedSpigot { source: Source::Constant { digits, config: cfg }, position: 0 }
+    }
+
+    /// Open a cursor at position 0 over `source`.  Digits behind the cursor
+    /// are kept only as far back as a live [`Checkpoint`] can rewind, so
+    /// scrubbing back stops there and snipping before it fails; see
+    /// [`from_source_with_history`](Self::from_source_with_history).
+    pub fn from_source(source: impl DigitSource + 'static) -> Result<Self, SpigotError> {
+        Self::custom(Box::new(source), false)
+    }
+
+    /// [`from_source`](Self::from_source), keeping every digit the source
+    /// gives, so that scrubbing and snipping reach back to the start.  The
+    /// history grows for as long as the stream plays.
+    pub fn from_source_with_history(source: impl DigitSource + 'static) -> Result<Self, SpigotError> {
+        Self::custom(Box::new(source), true)
+    }
+
+    fn custom(source: Box<dyn DigitSource>, history: bool) -> Result<Self, SpigotError> {
+        let base = source.base();
+        if !(2..=36).contains(&base) { return Err(SpigotError::Base(base)); }
+        let id = NEXT_CUSTOM_ID.fetch_add(1, Ordering::Relaxed);
+        let kept = Kept { digits: VecDeque::new(), first: 0, holds: Vec::new(), history };
+        Ok(BoxedSpigot { source: Source::Custom { source, kept, id }, position: 0 })
+    }
+
+    /// The constant and base, unless this is a custom source.
+    pub fn config(&self) -> Option<SpigotConfig> {
+        match &self.source {
+            Source::Constant { config, .. } => Some(*config),
+            Source::Custom { .. }           => None,
+        }
+    }
+
+    pub fn base(&self) -> u8 {
+        match &self.source {
+            Source::Constant { config, .. } => config.base,
+            Source::Custom { source, .. }   => source.base(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match &self.source {
+            Source::Constant { config, .. } => config.constant.name().to_string(),
+            Source::Custom { source, .. }   => source.name(),
+        }
+    }
+
+    fn key(&self) -> SideKey {
+        match &self.source {
+            Source::Constant { config, .. } => SideKey::Constant(*config),
+            Source::Custom { id, .. }       => SideKey::Custom(*id),
+        }
     }
 
     pub fn next_digit(&mut self) -> Option<u8> {
-        let d = self.inner.next();
+        let d = match &mut self.source {
+            Source::Constant { digits, .. } => digits.next(),
+            Source::Custom { source, kept, .. } => kept.get(source.as_mut(), self.position),
+        };
         if d.is_some() { self.position += 1; }
+        if let Source::Custom { kept, .. } = &mut self.source { kept.trim(self.position); }
         d
     }
 
+    /// Keep a custom source's digits from here on while the returned
+    /// handle lives.
+    fn hold(&mut self) -> Option<Arc<usize>> {
+        match &mut self.source {
+            Source::Custom { kept, .. } if !kept.history => {
+                let hold = Arc::new(self.position);
+                kept.holds.push(Arc::downgrade(&hold));
+                Some(hold)
+            }
+            _ => None,
+        }
+    }
+
     pub fn advance(&mut self, n: usize) {
         for _ in 0..n { self.next_digit(); }
     }
 
+    /// Move to absolute `position`.  Going back restarts a constant's
+    /// spigot and fast-forwards, as [`DualStream::snip`] does; a custom
+    /// source reads back what it kept, stopping at the oldest digit.
+    pub fn seek(&mut self, position: usize) {
+        if position < self.position {
+            match &self.source {
+                Source::Constant { config, .. } => *self = BoxedSpigot::from_config(*config),
+                Source::Custom { kept, .. }     => self.position = position.max(kept.first),
+            }
+        }
+        self.advance(position.saturating_sub(self.position));
+    }
+
+    /// The digits at positions `from..to`, leaving the cursor where it is.
+    fn digits_between(&mut self, from: usize, to: usize) -> Result<Vec<u8>, SpigotError> {
+        match &mut self.source {
+            Source::Constant { config, .. } => {
+                let mut fresh = BoxedSpigot::from_config(*config);
+                fresh.advance(from);
+                Ok(fresh.take_n(to - from))
+            }
+            Source::Custom { source, kept, .. } => {
+                if from < kept.first { return Err(SpigotError::Forgotten(kept.first)); }
+                Ok((from..to).map_while(|at| kept.get(source.as_mut(), at)).collect())
+            }
+        }
+    }
+
     pub fn take_n(&mut self, n: usize) -> Vec<u8> {
         (0..n).filter_map(|_| self.next_digit()).collect()
     }
@@ -86,8 +386,7 @@ impl BoxedSpigot {
 
 impl std::fmt::Debug for BoxedSpigot {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "BoxedSpigot {{ {:?} base {} @ pos {} }}",
-               self.config.constant, self.config.base, self.position)
+        write!(f, "BoxedSpigot {{ {} base {} @ pos {} }}", self.name(), self.base(), self.position)
     }
 }
 
@@ -103,9 +402,11 @@ pub struct SideCursor<'a> {
 impl<'a> SideCursor<'a> {
     fn new(spigot: &'a mut BoxedSpigot) -> Self { SideCursor { spigot } }
 
-    pub fn constant(&self) -> Constant { self.spigot.config.constant }
-    pub fn base(&self)     -> u8       { self.spigot.config.base }
-    pub fn position(&self) -> usize    { self.spigot.position }
+    /// `None` for a custom source.
+    pub fn constant(&self) -> Option<Constant> { self.spigot.config().map(|c| c.constant) }
+    pub fn name(&self)     -> String           { self.spigot.name() }
+    pub fn base(&self)     -> u8               { self.spigot.base() }
+    pub fn position(&self) -> usize            { self.spigot.position }
 
     /// Skip `n` digits.
     pub fn drop(&mut self, n: usize) -> &mut Self {
@@ -168,13 +469,55 @@ impl<'a> Iterator for ZipIter<'a> {
     }
 }
 
+/// The zip of a borrowed [`DualStream`], spliced pairs first; see
+/// [`DualStream::iter_pairs`].
+#[derive(Debug)]
+pub struct Pairs<'a>(&'a mut DualStream);
+
+impl Iterator for Pairs<'_> {
+    type Item = (u8, u8);
+    fn next(&mut self) -> Option<(u8, u8)> { self.0.zip_next() }
+}
+
+impl<'a> IntoIterator for &'a mut DualStream {
+    type Item = (u8, u8);
+    type IntoIter = Pairs<'a>;
+    fn into_iter(self) -> Pairs<'a> { self.iter_pairs() }
+}
+
+/// The zip of a [`DualStream`] it owns, spliced pairs first; see
+/// [`DualStream::into_zip`].
+#[derive(Debug)]
+pub struct IntoZip(DualStream);
+
+impl IntoZip {
+    /// The stream back, where the zip left it.
+    pub fn into_inner(self) -> DualStream { self.0 }
+}
+
+impl Iterator for IntoZip {
+    type Item = (u8, u8);
+    fn next(&mut self) -> Option<(u8, u8)> { self.0.zip_next() }
+}
+
+/// The zip of a [`SharedStream`], locking it for each pair; see
+/// [`SharedStream::zip_pairs`].
+#[derive(Clone, Debug)]
+pub struct SharedZip(SharedStream);
+
+impl Iterator for SharedZip {
+    type Item = (u8, u8);
+    fn next(&mut self) -> Option<(u8, u8)> { self.0.lock().zip_next() }
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // DualStream
 // ════════════════════════════════════════════════════════════════════════════
 
 /// A paired, independently-advanceable stream over two transcendental spigots.
 ///
-/// Each side has its own [`Constant`] and output **base** (2–36).
+/// Each side has its own [`Constant`] and output **base** (2–36), or is fed
+/// by a [`DigitSource`] of its own.
 ///
 /// # Construction
 ///
@@ -195,6 +538,8 @@ pub struct DualStream {
     left:     BoxedSpigot,
     right:    BoxedSpigot,
     snippets: HashMap<String, Vec<(u8, u8)>>,
+    /// Pairs spliced in ahead of the live cursors.
+    spliced:  VecDeque<(u8, u8)>,
 }
 
 impl DualStream {
@@ -205,10 +550,25 @@ impl DualStream {
 
     /// Full constructor — specify constant and base independently per side.
     pub fn from_configs(left: SpigotConfig, right: SpigotConfig) -> Self {
+        Self::from_spigots(BoxedSpigot::from_config(left), BoxedSpigot::from_config(right))
+    }
+
+    /// Both sides fed by custom sources; fails on a base outside 2–36.  See
+    /// [`BoxedSpigot::from_source`] for how far back they can go.
+    pub fn from_sources(left: impl DigitSource + 'static, right: impl DigitSource + 'static)
+        -> Result<Self, SpigotError>
+    {
+        Ok(Self::from_spigots(BoxedSpigot::from_source(left)?, BoxedSpigot::from_source(right)?))
+    }
+
+    /// From two cursors, e.g. a constant on one side and a custom source on
+    /// the other.
+    pub fn from_spigots(left: BoxedSpigot, right: BoxedSpigot) -> Self {
         DualStream {
-            left:     BoxedSpigot::from_config(left),
-            right:    BoxedSpigot::from_config(right),
+            left,
+            right,
             snippets: HashMap::new(),
+            spliced:  VecDeque::new(),
         }
     }
 
@@ -219,16 +579,23 @@ impl DualStream {
 
     pub fn left_pos(&self)      -> usize    { self.left.position }
     pub fn right_pos(&self)     -> usize    { self.right.position }
-    pub fn left_constant(&self) -> Constant { self.left.config.constant }
-    pub fn right_constant(&self)-> Constant { self.right.config.constant }
-    pub fn left_base(&self)     -> u8       { self.left.config.base }
-    pub fn right_base(&self)    -> u8       { self.right.config.base }
-    pub fn left_config(&self)   -> SpigotConfig { self.left.config }
-    pub fn right_config(&self)  -> SpigotConfig { self.right.config }
+    pub fn left_name(&self)     -> String   { self.left.name() }
+    pub fn right_name(&self)    -> String   { self.right.name() }
+    pub fn left_base(&self)     -> u8       { self.left.base() }
+    pub fn right_base(&self)    -> u8       { self.right.base() }
+
+    // A side fed by a custom source has no constant or config.
+    pub fn left_constant(&self) -> Option<Constant>     { self.left.config().map(|c| c.constant) }
+    pub fn right_constant(&self)-> Option<Constant>     { self.right.config().map(|c| c.constant) }
+    pub fn left_config(&self)   -> Option<SpigotConfig> { self.left.config() }
+    pub fn right_config(&self)  -> Option<SpigotConfig> { self.right.config() }
 
     // ── zip operations ───────────────────────────────────────────────────
 
     pub fn zip_next(&mut self) -> Option<(u8, u8)> {
+        if let Some(pair) = self.spliced.pop_front() {
+            return Some(pair);
+        }
         match (self.left.next_digit(), self.right.next_digit()) {
             (Some(l), Some(r)) => Some((l, r)),
             _ => None,
@@ -239,10 +606,36 @@ impl DualStream {
         (0..n).filter_map(|_| self.zip_next()).collect()
     }
 
+    /// The live cursors zipped, passing over any spliced pairs.
     pub fn zip_iter(&mut self) -> ZipIter<'_> {
         ZipIter { left: &mut self.left, right: &mut self.right }
     }
 
+    /// [`zip_next`](Self::zip_next) as an `Iterator`, spliced pairs first,
+    /// for `map`, `filter`, `take_while` and the rest; the stream is left
+    /// where the iterator stopped.  `&mut DualStream` iterates the same
+    /// way.
+    ///
+    /// ```rust
+    /// use dual_spigot::DualStream;
+    /// use spigot_stream::Constant;
+    ///
+    /// let mut ds = DualStream::new(Constant::Champernowne, Constant::Champernowne);
+    /// let sums: Vec<u8> = ds.iter_pairs().skip(1).map(|(l, r)| l + r).take_while(|&s| s < 8).collect();
+    /// assert_eq!(sums, [2, 4, 6]);
+    /// assert_eq!(ds.left_pos(), 5);
+    /// ```
+    pub fn iter_pairs(&mut self) -> Pairs<'_> { Pairs(self) }
+
+    /// Zip pairs, spliced ones first, from a stream given up to the
+    /// iterator.
+    pub fn into_zip(self) -> IntoZip { IntoZip(self) }
+
+    /// [`into_zip`](Self::into_zip) as a `futures::Stream`, computing each
+    /// pair only when it is polled for (`async` feature).
+    #[cfg(feature = "async")]
+    pub fn into_async_zip(self) -> AsyncDigits<IntoZip> { AsyncDigits::new(self.into_zip()) }
+
     pub fn zip_drop(&mut self, n: usize) {
         self.left.advance(n);
         self.right.advance(n);
@@ -262,61 +655,216 @@ impl DualStream {
         self.zip_take(n).into_iter().fold(init, f)
     }
 
+    /// Consume `n` pairs and return every run of `w` in a row, overlapping:
+    /// `n − w + 1` windows, or none if `n < w`.  Panics if `w` is 0, as
+    /// `slice::windows` does.
+    pub fn zip_windows(&mut self, n: usize, w: usize) -> Vec<Vec<(u8, u8)>> {
+        self.zip_take(n).windows(w).map(|win| win.to_vec()).collect()
+    }
+
+    /// Consume `n` pairs and return them `c` at a time, e.g. one phrase or
+    /// chord each; the last chunk is short if `c` doesn't divide `n`.
+    /// Panics if `c` is 0, as `slice::chunks` does.
+    pub fn zip_chunks(&mut self, n: usize, c: usize) -> Vec<Vec<(u8, u8)>> {
+        self.zip_take(n).chunks(c).map(|chunk| chunk.to_vec()).collect()
+    }
+
     // ── twist ─────────────────────────────────────────────────────────────
 
-    /// Swap Left and Right cursors (constant, base, and position all swap).
+    /// Swap Left and Right cursors (source, base, and position all swap).
     pub fn twist(&mut self) {
         std::mem::swap(&mut self.left, &mut self.right);
     }
 
+    // ── scrub ─────────────────────────────────────────────────────────────
+
+    /// Move both cursors `delta` digits forward, or back when negative
+    /// (stopping at 0).
+    pub fn scrub(&mut self, delta: isize) {
+        for side in [&mut self.left, &mut self.right] {
+            side.seek(side.position.saturating_add_signed(delta));
+        }
+    }
+
+    /// Move the cursors to absolute positions.
+    pub fn seek(&mut self, left: usize, right: usize) {
+        self.left.seek(left);
+        self.right.seek(right);
+    }
+
+    // ── checkpoint / rewind ───────────────────────────────────────────────
+
+    /// Record where both sides are (and what they stream), so a later
+    /// [`rewind`](Self::rewind) can return here.  Snippets aren't included.
+    /// A custom source keeps its digits from here on while the checkpoint
+    /// lives.
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        Checkpoint {
+            left:    (self.left.key(),  self.left.position),
+            right:   (self.right.key(), self.right.position),
+            spliced: self.spliced.iter().copied().collect(),
+            holds:   [self.left.hold(), self.right.hold()].into_iter().flatten().collect(),
+        }
+    }
+
+    /// Return to a checkpoint, undoing pulls, twists, scrubs and splices
+    /// made since.  A twist is undone by twisting back, and any other side
+    /// whose config changed is restarted.
+    pub fn rewind(&mut self, cp: &Checkpoint) {
+        if (self.left.key(), self.right.key()) == (cp.right.0, cp.left.0) {
+            self.twist();
+        }
+        for (side, (key, position)) in [(&mut self.left, cp.left), (&mut self.right, cp.right)] {
+            if let SideKey::Constant(config) = key {
+                if side.key() != key {
+                    *side = BoxedSpigot::from_config(config);
+                }
+            }
+            side.seek(position);
+        }
+        self.spliced = cp.spliced.iter().copied().collect();
+    }
+
+    /// Drop any spliced pairs still to come.
+    pub fn clear_spliced(&mut self) {
+        self.spliced.clear();
+    }
+
     // ── snip ──────────────────────────────────────────────────────────────
 
     /// Copy zipped pairs at absolute positions `from..to` into a named snippet.
     ///
     /// Fresh spigots are created (using each side's current config), fast-
-    /// forwarded to `from`, and `to−from` pairs are collected.  The live
-    /// cursors are **not** affected.
-    pub fn snip(&mut self, key: &str, from: usize, to: usize) {
-        assert!(from <= to, "snip: from ({}) must be <= to ({})", from, to);
-        let mut sl = BoxedSpigot::from_config(self.left.config);
-        let mut sr = BoxedSpigot::from_config(self.right.config);
-        sl.advance(from);
-        sr.advance(from);
-        let pairs: Vec<(u8, u8)> = (from..to)
-            .filter_map(|_| match (sl.next_digit(), sr.next_digit()) {
-                (Some(l), Some(r)) => Some((l, r)),
-                _ => None,
-            })
-            .collect();
+    /// forwarded to `from`, and `to−from` pairs are collected; a custom
+    /// source is read ahead or back instead, and fails if `from` is before
+    /// the oldest digit it keeps.  The live cursors are **not** affected.
+    pub fn snip(&mut self, key: &str, from: usize, to: usize) -> Result<(), SpigotError> {
+        if from > to { return Err(SpigotError::Range { from, to }); }
+        let left  = self.left.digits_between(from, to)?;
+        let right = self.right.digits_between(from, to)?;
+        let pairs: Vec<(u8, u8)> = left.into_iter().zip(right).collect();
         self.snippets.insert(key.to_string(), pairs);
+        Ok(())
     }
 
     pub fn get_snippet(&self, key: &str)        -> Option<&Vec<(u8,u8)>> { self.snippets.get(key) }
     pub fn remove_snippet(&mut self, key: &str) -> Option<Vec<(u8,u8)>> { self.snippets.remove(key) }
+
+    /// Store `pairs` under `key` directly, e.g. a snippet saved earlier.
+    pub fn insert_snippet(&mut self, key: &str, pairs: Vec<(u8, u8)>) {
+        self.snippets.insert(key.to_string(), pairs);
+    }
+
+    /// Move the snippet stored under `from` to `to`, replacing any snippet
+    /// already there.
+    pub fn rename_snippet(&mut self, from: &str, to: &str) -> Result<(), SpigotError> {
+        let pairs = self.snippets.remove(from).ok_or_else(|| SpigotError::NoSnippet(from.to_string()))?;
+        self.snippets.insert(to.to_string(), pairs);
+        Ok(())
+    }
     pub fn snippet_keys(&self) -> Vec<&str> {
         let mut k: Vec<&str> = self.snippets.keys().map(|s| s.as_str()).collect();
         k.sort(); k
     }
     pub fn snippet_count(&self) -> usize { self.snippets.len() }
 
+    // ── splice ────────────────────────────────────────────────────────────
+
+    /// Queue `pairs` to come out of [`zip_next`](Self::zip_next) before the
+    /// live digits resume.  The cursors don't move while spliced pairs play.
+    pub fn splice(&mut self, pairs: &[(u8, u8)]) {
+        self.spliced.extend(pairs);
+    }
+
+    /// Splice in the snippet stored under `key`.
+    pub fn splice_snippet(&mut self, key: &str) -> Result<(), SpigotError> {
+        let pairs = self.snippets.get(key).ok_or_else(|| SpigotError::NoSnippet(key.to_string()))?;
+        self.spliced.extend(pairs);
+        Ok(())
+    }
+
+    /// Spliced pairs still to come.
+    pub fn spliced_len(&self) -> usize { self.spliced.len() }
+
     // ── display ───────────────────────────────────────────────────────────
 
     pub fn status(&self) -> String {
         format!(
             "DualStream {{ left: {} (base {}) @ {}, right: {} (base {}) @ {}, snippets: {} }}",
-            self.left.config.constant.name(),  self.left.config.base,  self.left.position,
-            self.right.config.constant.name(), self.right.config.base, self.right.position,
+            self.left.name(),  self.left.base(),  self.left.position,
+            self.right.name(), self.right.base(), self.right.position,
             self.snippets.len(),
         )
     }
 }
 
+/// Saved cursor state of a [`DualStream`]; see [`DualStream::checkpoint`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    left:    (SideKey, usize),
+    right:   (SideKey, usize),
+    spliced: Vec<(u8, u8)>,
+    /// Keeps custom sources' digits back to here.
+    holds:   Vec<Arc<usize>>,
+}
+
 impl std::fmt::Debug for DualStream {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.status())
     }
 }
 
+// ════════════════════════════════════════════════════════════════════════════
+// SharedStream — one DualStream, many threads
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Thread-safe handle to a [`DualStream`].  Clones share the stream, so a
+/// UI advancing the cursors and a player zipping pairs on another thread
+/// see the same digits.
+///
+/// ```rust
+/// use dual_spigot::{DualStream, SharedStream};
+/// use spigot_stream::Constant;
+///
+/// let ui = SharedStream::new(DualStream::new(Constant::Champernowne, Constant::Champernowne));
+/// let player = ui.clone();
+/// ui.lock().left().drop(3);
+/// assert_eq!(player.lock().zip_next(), Some((3, 0)));
+/// ```
+#[derive(Clone)]
+pub struct SharedStream(Arc<Mutex<DualStream>>);
+
+impl SharedStream {
+    pub fn new(stream: DualStream) -> Self {
+        SharedStream(Arc::new(Mutex::new(stream)))
+    }
+
+    /// Lock the stream; hold the guard only briefly.  A thread that
+    /// panicked while holding it leaves the stream usable.
+    pub fn lock(&self) -> MutexGuard<'_, DualStream> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Zip pairs from the shared stream, taking the lock for each one, so
+    /// other handles can steer the cursors between pairs.
+    pub fn zip_pairs(&self) -> SharedZip { SharedZip(self.clone()) }
+
+    /// [`zip_pairs`](Self::zip_pairs) as a `futures::Stream` (`async`
+    /// feature).
+    #[cfg(feature = "async")]
+    pub fn async_zip(&self) -> AsyncDigits<SharedZip> { AsyncDigits::new(self.zip_pairs()) }
+}
+
+impl From<DualStream> for SharedStream {
+    fn from(stream: DualStream) -> Self { SharedStream::new(stream) }
+}
+
+impl std::fmt::Debug for SharedStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.lock().status())
+    }
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // Tests
 // ════════════════════════════════════════════════════════════════════════════
@@ -341,8 +889,8 @@ mod tests {
         );
         assert_eq!(ds.left_base(),  16);
         assert_eq!(ds.right_base(),  2);
-        assert_eq!(ds.left_constant(),  Constant::Pi);
-        assert_eq!(ds.right_constant(), Constant::E);
+        assert_eq!(ds.left_constant(),  Some(Constant::Pi));
+        assert_eq!(ds.right_constant(), Some(Constant::E));
     }
 
     #[test]
@@ -356,6 +904,20 @@ mod tests {
         assert_eq!(r, 1, "e binary integer part = 1");
     }
 
+    #[test]
+    fn rationals_mix_with_constants() {
+        let mut ds = DualStream::from_configs(SpigotConfig::new(Constant::Rational(1, 3), 10),
+                                              SpigotConfig::decimal(Constant::Champernowne));
+        assert_eq!(ds.zip_take(4), vec![(0, 0), (3, 1), (3, 2), (3, 3)]);
+        assert_eq!(SpigotConfig::try_new(Constant::Rational(1, 0), 10), Err(SpigotError::ZeroDenominator));
+    }
+
+    #[test]
+    fn zip_phi_against_sqrt2() {
+        let mut ds = DualStream::new(Constant::Phi, Constant::Sqrt2);
+        assert_eq!(ds.zip_take(5), vec![(1, 1), (6, 4), (1, 1), (8, 4), (0, 2)]);
+    }
+
     // ── independent side advancement ─────────────────────────────────────
     #[test]
     fn left_drop_shifts_zip() {
@@ -401,10 +963,10 @@ mod tests {
 
         ds.twist();
 
-        assert_eq!(ds.left_constant(),  Constant::E);
+        assert_eq!(ds.left_constant(),  Some(Constant::E));
         assert_eq!(ds.left_base(),       2);
         assert_eq!(ds.left_pos(),        0);
-        assert_eq!(ds.right_constant(), Constant::Pi);
+        assert_eq!(ds.right_constant(), Some(Constant::Pi));
         assert_eq!(ds.right_base(),     16);
         assert_eq!(ds.right_pos(),       4);
     }
@@ -419,9 +981,9 @@ mod tests {
         let lpos = ds.left_pos();
         ds.twist(); ds.twist();
         assert_eq!(ds.left_pos(),       lpos);
-        assert_eq!(ds.left_constant(),  Constant::Pi);
+        assert_eq!(ds.left_constant(),  Some(Constant::Pi));
         assert_eq!(ds.left_base(),      16);
-        assert_eq!(ds.right_constant(), Constant::E);
+        assert_eq!(ds.right_constant(), Some(Constant::E));
         assert_eq!(ds.right_base(),      2);
     }
 
@@ -432,7 +994,7 @@ mod tests {
             SpigotConfig::new(Constant::Pi, 16),
             SpigotConfig::new(Constant::E,  10),
         );
-        ds.snip("s", 0, 3);
+        ds.snip("s", 0, 3).unwrap();
         let s = ds.get_snippet("s").unwrap();
         // left = π hex[0..3] = [3,2,4], right = e dec[0..3] = [2,7,1]
         assert_eq!(s[0], (3, 2));
@@ -445,9 +1007,196 @@ mod tests {
         let mut ds = DualStream::new(Constant::Pi, Constant::E);
         ds.left().drop(5);
         let lpos = ds.left_pos();
-        ds.snip("x", 10, 20);
+        ds.snip("x", 10, 20).unwrap();
         assert_eq!(ds.left_pos(),  lpos);
         assert_eq!(ds.right_pos(), 0);
+        assert_eq!(ds.snip("y", 20, 10), Err(SpigotError::Range { from: 20, to: 10 }));
+        assert_eq!(SpigotConfig::try_new(Constant::Pi, 37), Err(SpigotError::Base(37)));
+    }
+
+    // ── scrub ─────────────────────────────────────────────────────────────
+    #[test]
+    fn scrub_back_replays_the_same_digits() {
+        let mut ds = DualStream::new(Constant::Champernowne, Constant::ThueMorse);
+        ds.zip_drop(3);
+        let ahead = ds.zip_take(4);
+        ds.scrub(-4);
+        assert_eq!((ds.left_pos(), ds.right_pos()), (3, 3));
+        assert_eq!(ds.zip_take(4), ahead);
+        ds.seek(5, 1);
+        assert_eq!((ds.left_pos(), ds.right_pos()), (5, 1));
+        ds.scrub(-100);
+        assert_eq!(ds.zip_next(), DualStream::new(Constant::Champernowne, Constant::ThueMorse).zip_next());
+    }
+
+    // ── shared stream ─────────────────────────────────────────────────────
+    #[test]
+    fn shared_stream_keeps_one_set_of_cursors_across_threads() {
+        let ui = SharedStream::new(DualStream::new(Constant::Champernowne, Constant::ThueMorse));
+        let player = ui.clone();
+        ui.lock().left().drop(2);
+        let played = std::thread::spawn(move || player.lock().zip_take(3)).join().unwrap();
+        let stream = ui.lock();
+        assert_eq!((stream.left_pos(), stream.right_pos()), (5, 3));
+        let mut fresh = DualStream::new(Constant::Champernowne, Constant::ThueMorse);
+        fresh.left().drop(2);
+        assert_eq!(played, fresh.zip_take(3));
+    }
+
+    #[test]
+    fn rename_snippet_moves_pairs() {
+        let mut ds = DualStream::new(Constant::Champernowne, Constant::ThueMorse);
+        ds.snip("a", 0, 3).unwrap();
+        let pairs = ds.get_snippet("a").cloned();
+        assert_eq!(ds.rename_snippet("a", "b"), Ok(()));
+        assert_eq!(ds.rename_snippet("a", "c"), Err(SpigotError::NoSnippet("a".to_string())));
+        assert_eq!(ds.get_snippet("b").cloned(), pairs);
+        assert_eq!(ds.snippet_keys(), vec!["b"]);
+    }
+
+    // ── checkpoint / rewind ───────────────────────────────────────────────
+    #[test]
+    fn rewind_undoes_pulls_twists_and_splices() {
+        let mut ds = DualStream::new(Constant::Champernowne, Constant::ThueMorse);
+        ds.left().drop(2);
+        let cp = ds.checkpoint();
+        let ahead = ds.zip_take(3);
+        ds.twist();
+        ds.left().drop(4);
+        ds.splice(&[(9, 9)]);
+        ds.rewind(&cp);
+        assert_eq!(ds.checkpoint(), cp);
+        assert_eq!(ds.left_constant(), Some(Constant::Champernowne));
+        assert_eq!(ds.zip_take(3), ahead);
+    }
+
+    // ── custom sources ────────────────────────────────────────────────────
+    #[test]
+    fn custom_source_scrubs_snips_and_rewinds() {
+        let squares = || DigitIter::new("squares mod 7", 7, (0u32..).map(|n| (n * n % 7) as u8));
+        let mut ds = DualStream::from_spigots(
+            BoxedSpigot::from_source(squares()).unwrap(),
+            BoxedSpigot::from_config(SpigotConfig::decimal(Constant::Champernowne)),
+        );
+        assert_eq!((ds.left_constant(), ds.left_name(), ds.left_base()), (None, "squares mod 7".to_string(), 7));
+        let cp = ds.checkpoint();
+        let ahead = ds.zip_take(5);
+        assert_eq!(ahead, vec![(0, 0), (1, 1), (4, 2), (2, 3), (2, 4)]);
+        ds.snip("late", 3, 8).unwrap();
+        assert_eq!(ds.left_pos(), 5);
+        ds.twist();
+        ds.scrub(-2);
+        ds.rewind(&cp);
+        assert_eq!(ds.checkpoint(), cp);
+        assert_eq!(ds.zip_take(5), ahead);
+        assert_eq!(ds.get_snippet("late").unwrap()[..3], [(2, 3), (2, 4), (4, 5)]);
+    }
+
+    #[test]
+    fn custom_source_keeps_digits_only_while_needed() {
+        let squares = || DigitIter::new("squares mod 7", 7, (0u32..).map(|n| (n * n % 7) as u8));
+        let kept = |ds: &DualStream| match &ds.left.source {
+            Source::Custom { kept, .. } => (kept.first, kept.digits.len()),
+            Source::Constant { .. }     => unreachable!(),
+        };
+        let mut ds = DualStream::from_sources(squares(), squares()).unwrap();
+        ds.zip_drop(4);
+        assert_eq!(kept(&ds), (4, 0));
+        ds.scrub(-2);
+        assert_eq!(ds.left_pos(), 4);
+        assert_eq!(ds.snip("gone", 2, 6), Err(SpigotError::Forgotten(4)));
+        ds.snip("ahead", 6, 8).unwrap();
+        assert_eq!(kept(&ds), (4, 4));
+
+        let cp = ds.checkpoint();
+        ds.zip_drop(6);
+        assert_eq!(kept(&ds), (4, 6));
+        ds.rewind(&cp);
+        assert_eq!(ds.zip_next(), Some((2, 2)));
+        drop(cp);
+        ds.zip_drop(1);
+        assert_eq!(kept(&ds), (6, 4));
+
+        let mut ds = DualStream::from_spigots(
+            BoxedSpigot::from_source_with_history(squares()).unwrap(),
+            BoxedSpigot::from_config(SpigotConfig::decimal(Constant::Champernowne)),
+        );
+        ds.zip_drop(4);
+        ds.scrub(-4);
+        assert_eq!(ds.zip_take(3), vec![(0, 0), (1, 1), (4, 2)]);
+        assert_eq!(kept(&ds), (0, 4));
+    }
+
+    #[test]
+    fn custom_source_base_is_checked() {
+        let unary = DigitIter::new("ones", 1, std::iter::repeat(0));
+        assert_eq!(BoxedSpigot::from_source(unary).err(), Some(SpigotError::Base(1)));
+    }
+
+    // ── windows and chunks ────────────────────────────────────────────────
+    #[test]
+    fn zip_windows_overlap_and_chunks_dont() {
+        let mut ds = DualStream::new(Constant::Champernowne, Constant::Champernowne);
+        let windows = ds.zip_windows(4, 3);
+        assert_eq!(windows, [vec![(0, 0), (1, 1), (2, 2)], vec![(1, 1), (2, 2), (3, 3)]]);
+        assert_eq!(ds.left_pos(), 4);
+        let chunks = ds.zip_chunks(5, 2);
+        assert_eq!(chunks, [vec![(4, 4), (5, 5)], vec![(6, 6), (7, 7)], vec![(8, 8)]]);
+        assert!(ds.zip_windows(2, 3).is_empty());
+    }
+
+    // ── iterators ─────────────────────────────────────────────────────────
+    #[test]
+    fn iter_pairs_plays_spliced_pairs_first() {
+        let mut ds = DualStream::new(Constant::Champernowne, Constant::ThueMorse);
+        ds.splice(&[(9, 9)]);
+        let odd: Vec<(u8, u8)> = ds.iter_pairs().filter(|&(l, _)| l % 2 == 1).take(3).collect();
+        assert_eq!(odd, [(9, 9), (1, 1), (3, 0)]);
+        assert_eq!((ds.left_pos(), ds.spliced_len()), (4, 0));
+        let mut rest = Vec::new();
+        for pair in &mut ds {
+            if pair.0 == 6 { break; }
+            rest.push(pair);
+        }
+        assert_eq!(rest, [(4, 1), (5, 0)]);
+    }
+
+    // ── splice ────────────────────────────────────────────────────────────
+    #[test]
+    fn splice_plays_before_live_digits() {
+        let mut ds = DualStream::new(Constant::Champernowne, Constant::ThueMorse);
+        let live = DualStream::new(Constant::Champernowne, Constant::ThueMorse).zip_take(2);
+        ds.snip("loop", 0, 2).unwrap();
+        ds.splice(&[(7, 7)]);
+        assert!(ds.splice_snippet("loop").is_ok());
+        assert!(ds.splice_snippet("missing").is_err());
+        assert_eq!(ds.spliced_len(), 3);
+        assert_eq!(ds.zip_take(3), [vec![(7, 7)], live.clone()].concat());
+        assert_eq!(ds.left_pos(), 0);
+        assert_eq!(ds.zip_take(2), live);
+    }
+
+    // ── owned and shared zips ─────────────────────────────────────────────
+    #[test]
+    fn shared_zip_sees_cursor_moves_between_pairs() {
+        let shared = SharedStream::new(DualStream::new(Constant::Champernowne, Constant::Champernowne));
+        let mut pairs = shared.zip_pairs();
+        assert_eq!(pairs.next(), Some((0, 0)));
+        shared.lock().left().drop(3);
+        assert_eq!(pairs.next(), Some((4, 1)));
+
+        let mut owned = DualStream::new(Constant::Champernowne, Constant::ThueMorse).into_zip();
+        assert_eq!(owned.nth(1), Some((1, 1)));
+        assert_eq!(owned.into_inner().left_pos(), 2);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_zip_batches_pairs() {
+        use futures::{executor::block_on, StreamExt};
+        let ds = DualStream::new(Constant::Champernowne, Constant::ThueMorse);
+        let mut batches = ds.into_async_zip().with_budget(2).batches(3);
+        assert_eq!(block_on(batches.next()), Some(vec![(0, 0), (1, 1), (2, 1)]));
     }
 
     // ── zip combinators ───────────────────────────────────────────────────
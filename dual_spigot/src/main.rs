@@ -65,11 +65,9 @@ fn main() {
                 let key  = read_line("  Snippet key: ").trim().to_string();
                 let from = read_line("  From position (inclusive): ").trim().parse::<usize>().unwrap_or(0);
                 let to   = read_line("  To   position (exclusive): ").trim().parse::<usize>().unwrap_or(10);
-                if from > to {
-                    println!("  ⚠  from must be ≤ to.");
-                } else {
-                    ds.snip(&key, from, to);
-                    println!("  Stored {} pairs as \"{}\".", to - from, key);
+                match ds.snip(&key, from, to) {
+                    Ok(())  => println!("  Stored {} pairs as \"{}\".", to - from, key),
+                    Err(e)  => println!("  ⚠  {}.", e),
                 }
             }
             "8" => {
@@ -126,23 +124,26 @@ fn pick_config(side: &str) -> SpigotConfig {
         for (i, c) in Constant::all().iter().enumerate() {
             println!("    {}. {}  ({})", i + 1, c.name(), c.approx());
         }
-        match read_line("  Choice (1–6): ").trim() {
+        match read_line("  Choice (1–8): ").trim() {
             "1" => break Constant::Pi,
             "2" => break Constant::E,
             "3" => break Constant::Ln2,
             "4" => break Constant::Liouville,
             "5" => break Constant::Champernowne,
             "6" => break Constant::ThueMorse,
-            _   => println!("  ⚠  Please enter 1–6.\n"),
+            "7" => break Constant::Sqrt2,
+            "8" => break Constant::Phi,
+            _   => println!("  ⚠  Please enter 1–8.\n"),
         }
     };
-    let base: u8 = loop {
+    loop {
         let b = read_line(&format!("  {} base (2–36, default 10): ", side))
             .trim().parse::<u8>().unwrap_or(10);
-        if b >= 2 && b <= 36 { break b; }
-        println!("  ⚠  Base must be 2–36.");
-    };
-    SpigotConfig::new(constant, base)
+        match SpigotConfig::try_new(constant, b) {
+            Ok(cfg) => break cfg,
+            Err(e)  => println!("  ⚠  {}.", e),
+        }
+    }
 }
 
 fn read_line(prompt: &str) -> String {
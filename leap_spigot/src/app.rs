@@ -1,20 +1,48 @@
 //! Top-level application state machine.
 //!
-//! `AppState` owns the `DualStream`, the two `RibbonState`s, the `Player`,
-//! and the `SnippetTray`.  It processes `GestureEvent`s and drives the
-//! visualizer each frame.
+//! `AppState` owns the `DualStream`, the `Player` and the [`Performer`]
+//! holding the two `RibbonState`s and the `SnippetTray`.  It processes
+//! `GestureEvent`s — the performer's, turning its cues into player
+//! commands, and those needing files, ports or the undo history — and
+//! drives the visualizer each frame.
+//!
+//! The ribbons and the player share the one stream (a [`SharedStream`]):
+//! playback carries on from wherever the pulls leave the cursors, and the
+//! digits it plays scroll onto the ribbons as they sound, so the ribbons
+//! always show exactly what is being played.
+//!
+//! With more ribbon pairs (`--pair`), each has its own streams, ribbons and
+//! player on a channel of its own, and all play together; `NextPair` (`Tab`)
+//! swaps the next one in as the pair the pull gestures act on, taking the
+//! tray's snippets and undo history along.  Play, stop, tempo, dynamics and
+//! the MIDI port apply to every pair; the instrument, saves and sessions to
+//! the pair being pulled.
 
 use std::sync::mpsc::{self, TryRecvError};
-use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use dual_spigot::{DualStream, SpigotConfig};
+use dual_spigot::{Checkpoint, DualStream, SharedStream, SpigotConfig};
 use spigot_stream::Constant;
-use spigot_midi::{PitchMap, DurationMap, GeneralMidi};
-
-use crate::gesture::{GestureEvent, SimInput, SimGestureSource, spawn_gesture_source};
-use crate::ribbon::{RibbonState, StitchPhase, SnippetTray, ScissorAnimation, Patch};
-use crate::player::Player;
-use crate::visualizer::{Visualizer, WIN_W};
+use spigot_midi::{PitchMap, DurationMap, GeneralMidi, VelocityMap, Curve};
+use spigot_midi::mapper::{DurationMapper, MapperArgs, MapperRegistry, PitchMapper};
+
+use crate::gesture::{
+    GestureEvent, SimInput, SimGestureSource, spawn_gesture_source_into,
+};
+use crate::midi_in::{JamPhrase, MidiInGestureSource, MidiMapping};
+use crate::script::{Script, ScriptedGestureSource};
+use crate::webcam::WebcamGestureSource;
+use crate::config::GestureConfig;
+use crate::error::AppError;
+use crate::capture::{self, GifRecorder};
+use crate::ribbon::{RibbonState, StitchPhase, SnippetTray, ScissorAnimation, PianoRoll};
+use crate::perform::{self, prefilled_ribbons, show_played, Cue, Performer, PlayState, MAX_ZOOM, MIN_ZOOM};
+use crate::link::{self, BeatSync};
+use crate::session::{self, SavedSide, Session};
+use crate::player::{self, Dynamics, Layer, Player, PortSelector, TempoDrift};
+use crate::visualizer::{HandGesture, Scene, StackedPair, Visualizer, FLAT_PATCH_W, WIN_W};
 
 // ════════════════════════════════════════════════════════════════════════════
 // AppConfig
@@ -24,14 +52,67 @@ use crate::visualizer::{Visualizer, WIN_W};
 pub struct AppConfig {
     pub left_config:   SpigotConfig,
     pub right_config:  SpigotConfig,
-    pub pitch_map:     PitchMap,
-    pub duration_map:  DurationMap,
+    /// Root note handed to the pitch mapper.
+    pub root:          u8,
+    /// Pitch and duration mappers as `NAME[:PARAM]` specs, looked up in
+    /// the built-in [`MapperRegistry`] for each stream pair.
+    pub pitch_mapper:  String,
+    pub duration_mapper: String,
+    /// Ticks per quarter note the duration mapper counts in.
+    pub ticks_per_quarter: u32,
     pub instrument:    u8,
     pub tempo_bpm:     u32,
     pub velocity:      u8,
+    /// Base velocity of each digit pair, scaled by `velocity` (at 100 as
+    /// mapped).
+    pub velocity_map:  VelocityMap,
+    /// Phrase-level dynamics on top of the velocity map.
+    pub dynamics:      Curve,
+    /// Total velocity swing of a shaped `dynamics` curve.
+    pub dynamics_depth: u8,
     pub channel:       u8,
+    /// A voice each for the (left, right) streams — channel, instrument
+    /// and transposition — instead of one line on `channel`.
+    pub layers:        Option<[Layer; 2]>,
+    /// More (left, right) stream pairs to play alongside, each on the next
+    /// free channel.
+    pub extra_pairs:   Vec<(SpigotConfig, SpigotConfig)>,
+    /// Let the digits nudge the tempo as they play.
+    pub tempo_drift:   Option<TempoDrift>,
     /// Number of patches kept in each ribbon's visible buffer.
     pub ribbon_capacity: usize,
+    /// Shared beat timeline to lock playback to (e.g. Ableton Link).
+    pub sync:          Option<Arc<dyn BeatSync>>,
+    /// MIDI output port to play through.
+    pub midi_port:     PortSelector,
+    /// MIDI controller input to take gestures from, if any.
+    pub midi_in:       Option<PortSelector>,
+    /// Echo the notes played on the `midi_in` keyboard and answer each
+    /// phrase from the stream.
+    pub jam:           bool,
+    /// Webcam hand-tracker command to take gestures from, if any.
+    pub webcam:        Option<String>,
+    /// Timed gestures played alongside the live ones, if any.
+    pub script:        Option<Script>,
+    /// Recognition thresholds and gesture / key bindings.
+    pub gestures:      GestureConfig,
+    /// File the snippet tray is loaded from at startup and saved to on exit.
+    pub tray_file:     Option<String>,
+    /// Session to restore at startup (its streams should match
+    /// `left_config` / `right_config`).
+    pub session:       Option<Session>,
+    /// File `SaveSession` writes to (`session.toml` if `None`).
+    pub session_file:  Option<String>,
+    /// UI scale for HiDPI screens; `None` asks the desktop.
+    pub ui_scale:      Option<f32>,
+    /// Start in fullscreen (toggle with F11).
+    pub fullscreen:    bool,
+    /// Start with the tray in a window of its own (toggle with F10).
+    pub detach_tray:   bool,
+    /// Screen size the fullscreen window covers.
+    pub display_size:  (usize, usize),
+    /// Film the ribbons into a GIF saved alongside the performance.
+    pub record_gif:    bool,
 }
 
 impl Default for AppConfig {
@@ -39,23 +120,141 @@ impl Default for AppConfig {
         AppConfig {
             left_config:     SpigotConfig::new(Constant::Pi, 10),
             right_config:    SpigotConfig::new(Constant::E,  10),
-            pitch_map:       PitchMap::major(60),
-            duration_map:    DurationMap::musical(480),
+            root:            60,
+            pitch_mapper:    "major".to_string(),
+            duration_mapper: "musical".to_string(),
+            ticks_per_quarter: 480,
             instrument:      GeneralMidi::AcousticGrandPiano.program(),
             tempo_bpm:       120,
             velocity:        100,
+            velocity_map:    VelocityMap::Fixed(100),
+            dynamics:        Curve::Flat,
+            dynamics_depth:  24,
             channel:         0,
-            ribbon_capacity: WIN_W / 48 + 2,
+            layers:          None,
+            extra_pairs:     Vec::new(),
+            tempo_drift:     None,
+            ribbon_capacity: WIN_W / FLAT_PATCH_W + 2,
+            sync:            None,
+            midi_port:       PortSelector::Auto,
+            midi_in:         None,
+            jam:             false,
+            webcam:          None,
+            script:          None,
+            gestures:        GestureConfig::default(),
+            tray_file:       None,
+            session:         None,
+            session_file:    None,
+            ui_scale:        None,
+            fullscreen:      false,
+            detach_tray:     false,
+            display_size:    crate::visualizer::DISPLAY_SIZE,
+            record_gif:      false,
         }
     }
 }
 
+/// A stream pair's pitch and duration mappers.
+pub type Mappers = (Box<dyn PitchMapper>, Box<dyn DurationMapper>);
+
+impl AppConfig {
+    /// The pitch and duration mappers for a stream pair in these bases.
+    pub fn mappers(&self, left_base: u8, right_base: u8) -> Result<Mappers, AppError> {
+        let registry = MapperRegistry::with_builtins();
+        let args = MapperArgs {
+            root: self.root,
+            left_base,
+            right_base,
+            ticks_per_quarter: self.ticks_per_quarter,
+        };
+        Ok((registry.pitch(&self.pitch_mapper, &args)?, registry.duration(&self.duration_mapper, &args)?))
+    }
+}
+
 // ════════════════════════════════════════════════════════════════════════════
-// Playback state
+// Undo history
 // ════════════════════════════════════════════════════════════════════════════
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum PlayState { Stopped, Playing }
+/// Undo steps kept.
+const UNDO_DEPTH: usize = 100;
+
+/// Streams and ribbons as they were before a gesture.
+struct Snapshot {
+    stream:       Checkpoint,
+    left_ribbon:  RibbonState,
+    right_ribbon: RibbonState,
+}
+
+/// A reversible change.
+enum Edit {
+    /// Return the streams and ribbons to a snapshot.
+    Stream(Box<Snapshot>),
+    /// Take back a splice of these pairs.
+    Splice(Vec<(u8, u8)>),
+    /// Splice these pairs again.
+    Unsplice(Vec<(u8, u8)>),
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Ribbon pairs
+// ════════════════════════════════════════════════════════════════════════════
+
+/// A ribbon pair playing alongside the one the gestures act on, with what
+/// goes with it; `NextPair` swaps it in.
+struct RibbonPair {
+    dual:           SharedStream,
+    left_ribbon:    RibbonState,
+    right_ribbon:   RibbonState,
+    player:         Player,
+    instrument:     u8,
+    channel:        u8,
+    sounding:       Option<(usize, usize, bool)>,
+    note_highlight: (Option<usize>, Option<usize>),
+    undo_stack:     Vec<Edit>,
+    redo_stack:     Vec<Edit>,
+}
+
+/// A player for `stream`, set up as `cfg` asks; commands that don't reach
+/// it go on `faults`.
+fn spawn_player(cfg: &AppConfig, stream: SharedStream, channel: u8, faults: &mut Vec<AppError>) -> Player {
+    let (left_base, right_base) = { let ds = stream.lock(); (ds.left_base(), ds.right_base()) };
+    let (pitch_map, duration_map) = cfg.mappers(left_base, right_base).unwrap_or_else(|e| {
+        faults.push(e);
+        (Box::new(PitchMap::major(cfg.root)), Box::new(DurationMap::musical(cfg.ticks_per_quarter)))
+    });
+    let mut player = Player::spawn(
+        stream,
+        pitch_map,
+        duration_map,
+        cfg.instrument,
+        cfg.tempo_bpm,
+        Dynamics {
+            velocity:     cfg.velocity,
+            velocity_map: cfg.velocity_map.clone(),
+            curve:        cfg.dynamics.clone(),
+            depth:        cfg.dynamics_depth,
+        },
+        channel,
+    );
+    if cfg.sync.is_some() { faults.extend(player.set_sync(cfg.sync.clone()).err()); }
+    if cfg.tempo_drift.is_some() { faults.extend(player.set_drift(cfg.tempo_drift).err()); }
+    if cfg.midi_port != PortSelector::Auto { faults.extend(player.set_port(cfg.midi_port.clone()).err()); }
+    player
+}
+
+/// Both sides' configs, which are always there: the app only streams the
+/// built-in constants.
+fn side_configs(dual: &DualStream) -> (SpigotConfig, SpigotConfig) {
+    let config = |c: Option<SpigotConfig>| c.expect("leap streams only built-in constants");
+    (config(dual.left_config()), config(dual.right_config()))
+}
+
+/// Channels for the extra pairs: the ones after those the first pair plays
+/// on, leaving out the GM drum channel (10, numbered 9 here).
+fn pair_channels(cfg: &AppConfig) -> Vec<u8> {
+    let used = cfg.layers.map_or(cfg.channel, |[l, r]| l.channel.max(r.channel));
+    (used + 1..16).filter(|&c| c != 9).take(cfg.extra_pairs.len()).collect()
+}
 
 // ════════════════════════════════════════════════════════════════════════════
 // AppState
@@ -63,311 +262,677 @@ pub enum PlayState { Stopped, Playing }
 
 pub struct AppState {
     // ── stream state ─────────────────────────────────────────────────────
-    dual:         DualStream,
-    left_ribbon:  RibbonState,
-    right_ribbon: RibbonState,
+    dual:         SharedStream,
+    /// The pair the gestures act on: its ribbons, the tray and the
+    /// playing settings.
+    perf:         Performer,
+    /// The other ribbon pairs, in the order `NextPair` reaches them.
+    pairs:        Vec<RibbonPair>,
+    /// Number of the pair the gestures act on, from 0 in launch order.
+    pair:         usize,
 
     // ── playback ─────────────────────────────────────────────────────────
     player:       Player,
-    play_state:   PlayState,
-    stitch:       StitchPhase,
-
-    // ── snippet ───────────────────────────────────────────────────────────
-    tray:         SnippetTray,
-    scissor_anim: Option<ScissorAnimation>,
-    snip_start:   usize,   // left-ribbon patch index where snip begins
 
-    // ── note highlight ────────────────────────────────────────────────────
-    note_highlight: Option<usize>,
+    // ── jam ───────────────────────────────────────────────────────────────
+    /// The phrase being played on the MIDI keyboard, waiting for an answer.
+    jam:          JamPhrase,
 
-    // ── status message ────────────────────────────────────────────────────
-    pub status:   String,
+    // ── recording ─────────────────────────────────────────────────────────
+    /// Whether a note has sounded yet, starting the performance recording.
+    played:       bool,
+    /// File the performance was saved to since the run loop last asked.
+    saved_performance: Option<String>,
 
-    // ── snippet name input ────────────────────────────────────────────────
-    /// True while waiting for the user to type a snippet name.
-    awaiting_snippet_name: bool,
-    snippet_name_buf:      String,
+    // ── downbeat pulse ────────────────────────────────────────────────────
+    /// 1.0 on a shared downbeat, fading to 0.0.
+    beat_pulse:   f32,
+    last_bar:     Option<f64>,
 
-    // ── instrument / tempo ───────────────────────────────────────────────
-    instrument: u8,
-    tempo_bpm:  u32,
+    // ── status message ────────────────────────────────────────────────────
+    /// Failed player commands, shown on the status line at the next tick.
+    faults:       Vec<AppError>,
+
+    // ── timbre / channel ──────────────────────────────────────────────────
+    /// Controller the hand roll's timbre level is sent on.
+    timbre_cc:  u8,
+    /// Channel of the pair the gestures act on.
+    channel:    u8,
+
+    // ── MIDI output ──────────────────────────────────────────────────────
+    /// Port chosen with `NextPort`, as an index into `output_ports()`.
+    port_index: Option<usize>,
+
+    // ── undo ──────────────────────────────────────────────────────────────
+    undo_stack:   Vec<Edit>,
+    redo_stack:   Vec<Edit>,
+
+    // ── persistence ───────────────────────────────────────────────────────
+    tray_file:    Option<String>,
+    session_file: Option<String>,
 }
 
 impl AppState {
     pub fn new(cfg: AppConfig) -> Self {
-        let left_label  = format!("{} base {}", cfg.left_config.constant.name(),  cfg.left_config.base);
-        let right_label = format!("{} base {}", cfg.right_config.constant.name(), cfg.right_config.base);
-
-        let dual = DualStream::from_configs(cfg.left_config, cfg.right_config);
-
-        // Player gets its own independent DualStream starting at position 0.
-        let player_dual = DualStream::from_configs(cfg.left_config, cfg.right_config);
-        let player = Player::spawn(
-            player_dual,
-            cfg.pitch_map.clone(),
-            cfg.duration_map.clone(),
-            cfg.instrument,
-            cfg.tempo_bpm,
-            cfg.velocity,
-            cfg.channel,
-        );
+        let dual = SharedStream::new(DualStream::from_configs(cfg.left_config, cfg.right_config));
+        let mut faults = Vec::new();
+        let player = spawn_player(&cfg, dual.clone(), cfg.channel, &mut faults);
+        if cfg.layers.is_some() { faults.extend(player.set_layers(cfg.layers).err()); }
 
-        let mut left_ribbon  = RibbonState::new(cfg.ribbon_capacity, cfg.left_config.base,  &left_label);
-        let mut right_ribbon = RibbonState::new(cfg.ribbon_capacity, cfg.right_config.base, &right_label);
+        let (left_ribbon, right_ribbon) =
+            prefilled_ribbons(cfg.left_config, cfg.right_config, cfg.ribbon_capacity);
 
-        // Pre-fill ribbons with initial digits so they're not empty on launch.
-        let mut pre = DualStream::from_configs(cfg.left_config, cfg.right_config);
-        for i in 0..cfg.ribbon_capacity {
-            if let Some((l, r)) = pre.zip_next() {
-                left_ribbon.push(l, i);
-                right_ribbon.push(r, i);
+        let channels = pair_channels(&cfg);
+        if channels.len() < cfg.extra_pairs.len() {
+            eprintln!("  ⚠  only {} more pairs fit on the free MIDI channels\n", channels.len());
+        }
+        let pairs = cfg.extra_pairs.iter().zip(channels).map(|(&(left, right), channel)| {
+            let (left_ribbon, right_ribbon) = prefilled_ribbons(left, right, cfg.ribbon_capacity);
+            let dual = SharedStream::new(DualStream::from_configs(left, right));
+            RibbonPair {
+                player: spawn_player(&cfg, dual.clone(), channel, &mut faults),
+                dual,
+                left_ribbon,
+                right_ribbon,
+                instrument: cfg.instrument,
+                channel,
+                sounding: None,
+                note_highlight: (None, None),
+                undo_stack: Vec::new(),
+                redo_stack: Vec::new(),
+            }
+        }).collect();
+
+        let mut tray = SnippetTray::default();
+        // A session brings its own tray.
+        let tray_file = cfg.tray_file.as_ref().filter(|p| cfg.session.is_none() && Path::new(p).exists());
+        if let Some(path) = tray_file {
+            match session::load_tray(path) {
+                Ok(saved) => {
+                    println!("  Snippet tray: {} snippets from {}\n", saved.len(), path);
+                    session::restore_tray(saved, &mut tray, &mut dual.lock());
+                }
+                Err(e) => eprintln!("  ⚠  {} — starting with an empty tray\n", e),
             }
         }
 
-        AppState {
+        let mut perf = Performer::new(left_ribbon, right_ribbon);
+        perf.tray       = tray;
+        perf.instrument = cfg.instrument;
+        perf.tempo_bpm  = cfg.tempo_bpm;
+        perf.velocity   = cfg.velocity;
+
+        let mut app = AppState {
             dual,
-            left_ribbon,
-            right_ribbon,
+            perf,
+            pairs,
+            pair: 0,
             player,
-            play_state:    PlayState::Stopped,
-            stitch:        StitchPhase::Unstitched,
-            tray:          SnippetTray::default(),
-            scissor_anim:  None,
-            snip_start:    0,
-            note_highlight: None,
-            status:        format!("Ready — Left: {}  Right: {}", left_label, right_label),
-            awaiting_snippet_name: false,
-            snippet_name_buf:      String::new(),
-            instrument: cfg.instrument,
-            tempo_bpm:  cfg.tempo_bpm,
+            jam:           JamPhrase::default(),
+            played:        false,
+            saved_performance: None,
+            beat_pulse:    0.0,
+            last_bar:      None,
+            faults,
+            timbre_cc:  cfg.gestures.timbre.cc,
+            channel:    cfg.channel,
+            port_index: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            tray_file:  cfg.tray_file,
+            session_file: cfg.session_file,
+        };
+        if let Some(session) = cfg.session {
+            app.restore_session(session);
         }
+        app
+    }
+
+    /// Snapshot of the performance setup.
+    pub fn session(&self) -> Session {
+        let dual = self.dual.lock();
+        let (left, right) = side_configs(&dual);
+        Session {
+            tempo_bpm:  self.perf.tempo_bpm,
+            instrument: self.perf.instrument,
+            velocity:   self.perf.velocity,
+            zoom:       self.perf.zoom,
+            left:  SavedSide::new(left,  dual.left_pos(),  &self.perf.left_ribbon),
+            right: SavedSide::new(right, dual.right_pos(), &self.perf.right_ribbon),
+            snippets:   session::saved_tray(&self.perf.tray, &dual),
+        }
+    }
+
+    /// Return to a saved setup.  Sides whose constant can't be read keep
+    /// their current stream.
+    pub fn restore_session(&mut self, s: Session) {
+        if let (Ok(l), Ok(r)) = (s.left.config(), s.right.config()) {
+            *self.dual.lock() = DualStream::from_configs(l, r);
+        }
+        self.dual.lock().seek(s.left.position, s.right.position);
+        let perf = &mut self.perf;
+        s.left.restore_ribbon(&mut perf.left_ribbon);
+        s.right.restore_ribbon(&mut perf.right_ribbon);
+        perf.recount_stats(&self.dual.lock());
+        perf.tray = SnippetTray::default();
+        session::restore_tray(s.snippets, &mut perf.tray, &mut self.dual.lock());
+
+        perf.tempo_bpm  = s.tempo_bpm.max(1);
+        perf.instrument = s.instrument.min(127);
+        perf.velocity   = s.velocity.clamp(1, 127);
+        perf.zoom       = s.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        let (bpm, velocity) = (perf.tempo_bpm, perf.velocity);
+        self.tell_players(|player| player.set_tempo(bpm).and(player.set_velocity(velocity)));
+        self.report(self.player.set_instrument(self.perf.instrument));
+        let dual = self.dual.lock();
+        self.perf.status = format!(
+            "SESSION restored — L-pos={}  R-pos={}  {} BPM",
+            dual.left_pos(), dual.right_pos(), self.perf.tempo_bpm
+        );
     }
 
     // ── process one GestureEvent ─────────────────────────────────────────
 
     pub fn handle_gesture(&mut self, event: GestureEvent) {
+        if matches!(event, GestureEvent::PullLeft { .. } | GestureEvent::PullRight { .. }
+                         | GestureEvent::Twist | GestureEvent::Scrub { .. }) {
+            self.record(Edit::Stream(Box::new(self.snapshot())));
+        }
+
         match event {
-            // ── Pull Left ─────────────────────────────────────────────────
-            GestureEvent::PullLeft { steps, velocity } => {
-                for _ in 0..steps {
-                    if let Some(d) = self.dual.left().next() {
-                        let pos = self.dual.left_pos();
-                        self.left_ribbon.push(d, pos);
+            // ── Save the performance so far ───────────────────────────────
+            GestureEvent::SavePerformance => {
+                let secs = SystemTime::now().duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs()).unwrap_or(0);
+                let path = format!("performance-{}.mid", secs);
+                let tempo = self.perf.tempo_bpm;
+                self.perf.status = match self.player.save_performance(&path, tempo) {
+                    Ok(n)  => {
+                        self.saved_performance = Some(path.clone());
+                        format!("SAVED {} notes to {}", n, path)
+                    }
+                    Err(e) => format!("Save failed: {}", e),
+                };
+                // The other pairs alongside, each to a file of its own
+                for (number, pair) in self.pair_numbers().zip(&self.pairs) {
+                    let path = format!("performance-{}-pair{}.mid", secs, number + 1);
+                    if let Ok(n) = pair.player.save_performance(&path, tempo) {
+                        self.perf.status += &format!("  + {} to {}", n, path);
                     }
                 }
-                self.left_ribbon.kick(velocity);
-                self.status = format!(
-                    "Pull LEFT ×{}  (vel={:.2})  pos={}",
-                    steps, velocity, self.dual.left_pos()
-                );
             }
 
-            // ── Pull Right ────────────────────────────────────────────────
-            GestureEvent::PullRight { steps, velocity } => {
-                for _ in 0..steps {
-                    if let Some(d) = self.dual.right().next() {
-                        let pos = self.dual.right_pos();
-                        self.right_ribbon.push(d, pos);
-                    }
+            // ── Save the whole setup ──────────────────────────────────────
+            GestureEvent::SaveSession => {
+                let path = self.session_file.as_deref().unwrap_or(session::SESSION_PATH);
+                self.perf.status = match self.session().save(path) {
+                    Ok(())  => format!("SESSION saved to {}", path),
+                    Err(e)  => format!("Session save failed: {}", e),
+                };
+            }
+
+            // ── Cycle MIDI output ports ───────────────────────────────────
+            GestureEvent::NextPort => {
+                let ports = player::output_ports();
+                if ports.is_empty() {
+                    self.perf.status = "No MIDI output ports found".to_string();
+                } else {
+                    let i = self.port_index.map_or(0, |i| (i + 1) % ports.len());
+                    self.port_index = Some(i);
+                    self.tell_players(|p| p.set_port(PortSelector::Index(i)));
+                    self.perf.status = format!("MIDI OUT → {} ({}/{})", ports[i], i + 1, ports.len());
                 }
-                self.right_ribbon.kick(velocity);
-                self.status = format!(
-                    "Pull RIGHT ×{}  (vel={:.2})  pos={}",
-                    steps, velocity, self.dual.right_pos()
-                );
             }
 
-            // ── Twist ─────────────────────────────────────────────────────
-            GestureEvent::Twist => {
-                self.dual.twist();
-                std::mem::swap(&mut self.left_ribbon, &mut self.right_ribbon);
-                // Update labels
-                let ll = format!("{} base {}", self.dual.left_constant().name(),
-                                              self.dual.left_base());
-                let rl = format!("{} base {}", self.dual.right_constant().name(),
-                                              self.dual.right_base());
-                self.left_ribbon.label  = ll.clone();
-                self.right_ribbon.label = rl.clone();
-                self.status = format!("TWIST — Left now: {}  Right now: {}", ll, rl);
+            // ── Hand roll → timbre controller ─────────────────────────────
+            GestureEvent::Timbre { level } => {
+                let value = (level.clamp(0.0, 1.0) * 127.0).round() as u8;
+                let cc = self.timbre_cc;
+                self.tell_players(|p| p.set_timbre(cc, value));
             }
 
-            // ── Clap → begin MIDI ─────────────────────────────────────────
-            GestureEvent::Clap => {
-                if self.play_state == PlayState::Stopped {
-                    self.play_state = PlayState::Playing;
-                    self.stitch = StitchPhase::Stitching { progress: 0.0 };
-                    self.player.play();
-                    self.status = "CLAP — MIDI playback started ♪".to_string();
-                }
+            // ── Jam along with the MIDI keyboard ──────────────────────────
+            GestureEvent::JamNote { pitch, velocity } => {
+                self.report(self.player.echo(pitch, velocity));
+                self.jam.note(pitch, velocity, Instant::now());
             }
 
-            // ── Unclap → stop MIDI ────────────────────────────────────────
-            GestureEvent::Unclap => {
-                if self.play_state == PlayState::Playing {
-                    self.play_state = PlayState::Stopped;
-                    self.stitch = StitchPhase::Unstitching { progress: 0.0 };
-                    self.player.stop();
-                    self.status = "UN-CLAP — MIDI playback stopped".to_string();
-                }
+            // ── Undo / redo ───────────────────────────────────────────────
+            GestureEvent::Undo => {
+                self.perf.status = match self.undo_stack.pop() {
+                    Some(edit) => {
+                        let undone = self.apply(edit);
+                        self.redo_stack.push(undone);
+                        {
+                            let dual = self.dual.lock();
+                            format!("UNDO — L-pos={}  R-pos={}", dual.left_pos(), dual.right_pos())
+                        }
+                    }
+                    None => "Nothing to undo".to_string(),
+                };
+            }
+
+            GestureEvent::Redo => {
+                self.perf.status = match self.redo_stack.pop() {
+                    Some(edit) => {
+                        let redone = self.apply(edit);
+                        self.undo_stack.push(redone);
+                        {
+                            let dual = self.dual.lock();
+                            format!("REDO — L-pos={}  R-pos={}", dual.left_pos(), dual.right_pos())
+                        }
+                    }
+                    None => "Nothing to redo".to_string(),
+                };
             }
 
-            // ── Scissors → snip ───────────────────────────────────────────
-            GestureEvent::Scissors { name } => {
-                self.do_snip(&name);
+            // ── Hand the pulls to the next ribbon pair ────────────────────
+            GestureEvent::NextPair => self.next_pair(),
+
+            GestureEvent::Fullscreen | GestureEvent::SpiralView | GestureEvent::Histogram
+            | GestureEvent::DetachTray | GestureEvent::Screenshot
+            | GestureEvent::Quit => { /* handled in run loop */ }
+
+            // ── The performance's own: pulls, play, tray, tempo… ──────────
+            event => {
+                let cue = self.perf.gesture(&mut self.dual.lock(), event);
+                match cue {
+                    Some(Cue::Play)  => self.tell_players(Player::play),
+                    Some(Cue::Stop)  => self.tell_players(Player::stop),
+                    Some(Cue::Panic) => self.tell_players(Player::panic),
+                    Some(Cue::Instrument(program)) => self.report(self.player.set_instrument(program)),
+                    Some(Cue::Velocity(velocity))  => self.tell_players(|p| p.set_velocity(velocity)),
+                    Some(Cue::Tempo(bpm))          => self.tell_players(|p| p.set_tempo(bpm)),
+                    Some(Cue::Splice(pairs)) => {
+                        self.report(self.player.splice(pairs.clone()));
+                        self.record(Edit::Splice(pairs));
+                    }
+                    None => {}
+                }
             }
+        }
+    }
 
-            GestureEvent::Quit => { /* handled in run loop */ }
+    /// Swap the next ribbon pair in as the one the gestures act on.
+    fn next_pair(&mut self) {
+        if self.pairs.is_empty() {
+            self.perf.status = "Only one ribbon pair — add more with --pair".to_string();
+            return;
         }
+        let mut next = self.pairs.remove(0);
+        // The tray's snippets go with the gestures.
+        {
+            let (mut current, mut other) = (self.dual.lock(), next.dual.lock());
+            let keys: Vec<String> = current.snippet_keys().into_iter().map(String::from).collect();
+            for key in keys {
+                if let Some(pairs) = current.remove_snippet(&key) { other.insert_snippet(&key, pairs); }
+            }
+        }
+        next.note_highlight = self.perf.note_highlight();
+        std::mem::swap(&mut self.dual,              &mut next.dual);
+        std::mem::swap(&mut self.perf.left_ribbon,  &mut next.left_ribbon);
+        std::mem::swap(&mut self.perf.right_ribbon, &mut next.right_ribbon);
+        std::mem::swap(&mut self.player,            &mut next.player);
+        std::mem::swap(&mut self.perf.instrument,   &mut next.instrument);
+        std::mem::swap(&mut self.channel,           &mut next.channel);
+        std::mem::swap(&mut self.perf.sounding,     &mut next.sounding);
+        std::mem::swap(&mut self.undo_stack,        &mut next.undo_stack);
+        std::mem::swap(&mut self.redo_stack,        &mut next.redo_stack);
+        self.perf.snip_marks = None;
+        self.pairs.push(next);
+        self.pair = (self.pair + 1) % (self.pairs.len() + 1);
+        self.perf.status = format!(
+            "PAIR {} of {} (ch {}) — Left: {}  Right: {}",
+            self.pair + 1, self.pairs.len() + 1, self.channel + 1,
+            self.perf.left_ribbon.label, self.perf.right_ribbon.label
+        );
+    }
+
+    /// Every pair's player, the one being pulled first.
+    fn players(&self) -> impl Iterator<Item = &Player> {
+        std::iter::once(&self.player).chain(self.pairs.iter().map(|p| &p.player))
+    }
+
+    /// Keep a failed player command to show at the next tick.
+    fn report(&mut self, sent: Result<(), AppError>) {
+        self.faults.extend(sent.err());
+    }
+
+    /// Send a command to every player, keeping the first failure.
+    fn tell_players(&mut self, send: impl Fn(&Player) -> Result<(), AppError>) {
+        let sent = self.players().map(send).fold(Ok(()), Result::and);
+        self.report(sent);
+    }
+
+    /// Numbers of the other pairs, in `pairs` order.
+    fn pair_numbers(&self) -> impl Iterator<Item = usize> {
+        let total = self.pairs.len() + 1;
+        let first = self.pair + 1;
+        (0..self.pairs.len()).map(move |i| (first + i) % total)
     }
 
-    /// Perform a snip: snapshot `from..to` absolute positions.
+    /// Snip the marked patches, or those in view, into the tray as `name`.
     pub fn do_snip(&mut self, name: &str) {
-        // Snip from left_pos to left_pos + visible_patches
-        let from = self.dual.left_pos().saturating_sub(self.left_ribbon.patches.len());
-        let to   = self.dual.left_pos();
-        let count = to - from;
+        self.perf.snip(&mut self.dual.lock(), name);
+    }
 
-        self.dual.snip(name, from, to);
+    // ── Undo / redo ───────────────────────────────────────────────────────
 
-        // Collect patch pairs for the tray
-        let pairs: Vec<(Patch, Patch)> = self.left_ribbon.patches.iter()
-            .zip(self.right_ribbon.patches.iter())
-            .map(|(l, r)| (l.clone(), r.clone()))
-            .collect();
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            stream:       self.dual.lock().checkpoint(),
+            left_ribbon:  self.perf.left_ribbon.clone(),
+            right_ribbon: self.perf.right_ribbon.clone(),
+        }
+    }
 
-        self.tray.deposit(name, pairs);
+    /// Push an edit that can be undone; anything undone before is lost.
+    fn record(&mut self, edit: Edit) {
+        if self.undo_stack.len() == UNDO_DEPTH { self.undo_stack.remove(0); }
+        self.undo_stack.push(edit);
+        self.redo_stack.clear();
+    }
 
-        // Trigger scissor animation
-        self.scissor_anim = Some(ScissorAnimation::new(0, count.min(self.left_ribbon.capacity)));
-        self.status = format!("SNIP \"{}\" — {} pairs [{}, {}) saved to tray", name, count, from, to);
+    /// Undo or redo `edit`, returning the edit that reverses it.
+    fn apply(&mut self, edit: Edit) -> Edit {
+        match edit {
+            Edit::Stream(s) => {
+                let current = self.snapshot();
+                self.dual.lock().rewind(&s.stream);
+                self.perf.left_ribbon  = s.left_ribbon;
+                self.perf.right_ribbon = s.right_ribbon;
+                Edit::Stream(Box::new(current))
+            }
+            Edit::Splice(pairs) => {
+                self.report(self.player.unsplice());
+                Edit::Unsplice(pairs)
+            }
+            Edit::Unsplice(pairs) => {
+                self.report(self.player.splice(pairs.clone()));
+                Edit::Splice(pairs)
+            }
+        }
+    }
+
+    /// Write the snippet tray to its file, if it has one.
+    pub fn save_tray(&self) -> Result<(), AppError> {
+        match &self.tray_file {
+            Some(path) => session::save_tray(session::saved_tray(&self.perf.tray, &self.dual.lock()), path),
+            None       => Ok(()),
+        }
     }
 
     // ── Per-frame tick ────────────────────────────────────────────────────
 
     pub fn tick(&mut self) {
-        // Animate ribbons
-        self.left_ribbon.tick(48.0);
-        self.right_ribbon.tick(48.0);
-
-        // Advance stitch animation
-        self.stitch.tick();
+        // Animate the ribbons, stitch, scissors, tray and piano roll
+        self.perf.tick();
+
+        // Pulse the stitched ribbons on each shared downbeat
+        self.beat_pulse = (self.beat_pulse - 0.06).max(0.0);
+        if let Some(beat) = self.player.beat() {
+            let bar = (beat / link::QUANTUM).floor();
+            if self.perf.stitch.is_stitched() && self.last_bar.is_some_and(|b| bar > b) {
+                self.beat_pulse = 1.0;
+            }
+            self.last_bar = Some(bar);
+        }
 
-        // Advance scissor animation
-        if let Some(ref mut sc) = self.scissor_anim {
-            sc.tick();
-            if sc.done() { self.scissor_anim = None; }
+        // Answer a jam phrase once it's over, if nothing else is playing
+        if let Some((notes, around)) = self.jam.answer_due(Instant::now()) {
+            if self.perf.play_state == PlayState::Stopped {
+                self.report(self.player.answer(notes, around));
+                self.perf.status = format!("JAM — answering {} notes around MIDI {}", notes, around);
+            }
         }
 
-        // Tray animations
-        self.tray.tick();
+        // Show the pairs on the ribbons as they play.
+        for note in self.player.drain_notes() {
+            self.played = true;
+            self.perf.show_note(&note);
+        }
 
-        // Drain note events from the player
-        let notes = self.player.drain_notes();
-        if let Some(last) = notes.last() {
-            // Find the ribbon patch closest to the left_pos
-            self.note_highlight = self.left_ribbon.patches.iter().position(|p| {
-                p.position + 1 >= last.left_pos
+        // The other pairs play along
+        let width = self.perf.patch_width();
+        for pair in &mut self.pairs {
+            pair.left_ribbon.tick(width);
+            pair.right_ribbon.tick(width);
+            for note in pair.player.drain_notes() {
+                self.perf.roll.push(note.pitch, note.velocity, perform::roll_frames(note.duration, note.bpm), note.spliced);
+                if note.first {
+                    show_played(&mut pair.left_ribbon, &mut pair.right_ribbon, &note);
+                }
+                self.played = true;
+                pair.sounding = Some((note.left_pos, note.right_pos, note.spliced));
+            }
+            if self.perf.play_state == PlayState::Stopped { pair.sounding = None; }
+            pair.note_highlight = pair.sounding.map_or((None, None), |(l, r, spliced)| {
+                (pair.left_ribbon.patch_at(l, spliced), pair.right_ribbon.patch_at(r, spliced))
             });
-            self.status = format!(
-                "♪ pitch={} duration={}t  L-pos={}  R-pos={}",
-                last.pitch, last.duration, last.left_pos, last.right_pos
-            );
+        }
+
+        // Faults last, so they aren't hidden behind the notes
+        let faults: Vec<AppError> = self.players().flat_map(Player::faults).collect();
+        self.faults.extend(faults);
+        if let Some(fault) = self.faults.pop() {
+            self.perf.status = format!("⚠  {}", fault);
+            self.faults.clear();
+        }
+    }
+
+    /// Size both ribbons to `capacity` patches as the window is resized or
+    /// the patches zoomed:
+    /// shrinking drops the oldest patches, growing refills from the streams.
+    pub fn set_ribbon_capacity(&mut self, capacity: usize) {
+        self.perf.fit_ribbons(&self.dual.lock(), capacity);
+        // The other pairs are drawn at the same patch width.
+        for pair in &mut self.pairs {
+            for ribbon in [&mut pair.left_ribbon, &mut pair.right_ribbon] {
+                perform::fit_ribbon(ribbon, capacity);
+            }
         }
     }
 
     // ── Accessors for the render loop ─────────────────────────────────────
 
-    pub fn left_ribbon(&self)     -> &RibbonState   { &self.left_ribbon }
-    pub fn right_ribbon(&self)    -> &RibbonState   { &self.right_ribbon }
-    pub fn stitch(&self)          -> &StitchPhase   { &self.stitch }
-    pub fn tray(&self)            -> &SnippetTray   { &self.tray }
-    pub fn scissor_anim(&self)    -> &Option<ScissorAnimation> { &self.scissor_anim }
-    pub fn note_highlight(&self)  -> Option<usize>  { self.note_highlight }
-    pub fn is_playing(&self)      -> bool           { self.play_state == PlayState::Playing }
+    pub fn left_ribbon(&self)     -> &RibbonState   { &self.perf.left_ribbon }
+    pub fn right_ribbon(&self)    -> &RibbonState   { &self.perf.right_ribbon }
+    pub fn stitch(&self)          -> &StitchPhase   { &self.perf.stitch }
+    pub fn tray(&self)            -> &SnippetTray   { &self.perf.tray }
+    pub fn scissor_anim(&self)    -> &Option<ScissorAnimation> { &self.perf.scissor }
+    pub fn note_highlight(&self)  -> (Option<usize>, Option<usize>) { self.perf.note_highlight() }
+    pub fn piano_roll(&self)      -> &PianoRoll     { &self.perf.roll }
+    pub fn is_playing(&self)      -> bool           { self.perf.play_state == PlayState::Playing }
+    pub fn zoom(&self)            -> f32            { self.perf.zoom }
+    pub fn status(&self)          -> &str           { &self.perf.status }
+
+    /// Patches `first..last` of the pulled ribbons marked for the next
+    /// snip, while they're still in view.
+    pub fn marked_patches(&self) -> Option<(usize, usize)> { self.perf.marked_patches() }
+    pub fn beat_pulse(&self)      -> f32            { self.beat_pulse }
+    pub fn has_played(&self)      -> bool           { self.played }
+
+    /// The other ribbon pairs, to draw below the one being pulled.
+    pub fn stacked_pairs(&self) -> Vec<StackedPair<'_>> {
+        self.pair_numbers().zip(&self.pairs).map(|(number, pair)| StackedPair {
+            left:      &pair.left_ribbon,
+            right:     &pair.right_ribbon,
+            highlight: pair.note_highlight,
+            label:     format!("pair {}  ch {}", number + 1, pair.channel + 1),
+        }).collect()
+    }
+
+    /// The file the performance was saved to, once, after a save.
+    pub fn take_saved_performance(&mut self) -> Option<String> {
+        self.saved_performance.take()
+    }
 }
 
 // ════════════════════════════════════════════════════════════════════════════
 // run() — the main application loop
 // ════════════════════════════════════════════════════════════════════════════
 
+/// What a name typed into the visualizer's prompt is for.
+enum Naming { Snip, Rename }
+
 /// Run the full application.
 ///
 /// This is the entry point called from `main.rs`.  It creates the visualizer,
 /// the gesture source (simulation by default, hardware with `--feature leap`),
 /// and drives the event/render loop at ~60 fps.
-pub fn run(cfg: AppConfig, layout: crate::visualizer::LayoutMode) -> Result<(), String> {
-    // ── Sim gesture channel ───────────────────────────────────────────────
+pub fn run(cfg: AppConfig, layout: crate::visualizer::LayoutMode) -> Result<(), AppError> {
+    // ── Gesture sources: keyboard sim, plus a controller / webcam / script ──
     let (sim_tx, sim_rx) = mpsc::channel::<SimInput>();
-    let gesture_rx = spawn_gesture_source(SimGestureSource { rx: sim_rx });
+    let (gesture_tx, gesture_rx) = mpsc::channel::<GestureEvent>();
+    if let Some(port) = cfg.midi_in.clone() {
+        let mapping = MidiMapping { jam: cfg.jam, ..MidiMapping::default() };
+        let source = MidiInGestureSource { port, mapping };
+        spawn_gesture_source_into(source, gesture_tx.clone());
+    }
+    if let Some(command) = cfg.webcam.clone() {
+        let source = WebcamGestureSource { command, config: cfg.gestures.clone() };
+        spawn_gesture_source_into(source, gesture_tx.clone());
+    }
+    if let Some(script) = cfg.script.clone() {
+        spawn_gesture_source_into(ScriptedGestureSource { script }, gesture_tx.clone());
+    }
+    spawn_gesture_source_into(SimGestureSource { rx: sim_rx }, gesture_tx);
 
     // ── Visualizer (owns the window and the sim input sender) ────────────
-    let mut vis = Visualizer::new(sim_tx, layout)?;
+    let scale = cfg.ui_scale.unwrap_or_else(crate::visualizer::detect_scale);
+    let mut vis = Visualizer::new(sim_tx, layout, scale).map_err(AppError::Device)?;
+    vis.set_display_size(cfg.display_size);
+    if cfg.fullscreen { vis.set_fullscreen(true).map_err(AppError::Device)?; }
+    if cfg.detach_tray { vis.set_detached(true).map_err(AppError::Device)?; }
+    vis.set_keys(&cfg.gestures.keys).map_err(AppError::Parameter)?;
 
     // ── App state ─────────────────────────────────────────────────────────
+    let mut filming = cfg.record_gif;
+    let mut recorder: Option<GifRecorder> = None;
     let mut app = AppState::new(cfg);
 
     // ── Main loop ─────────────────────────────────────────────────────────
-    while vis.is_open() {
+    let mut naming = None;
+    'frames: while vis.is_open() {
         // 1. Poll window input → translate to SimInput
         if !vis.poll_input() { break; }
 
+        // 2. A name typed into the on-screen prompt finishes a snip or rename
+        if let Some(name) = vis.take_entered_text() {
+            match (naming.take(), name.is_empty()) {
+                (_, true)                    => app.perf.status = "Cancelled".to_string(),
+                (Some(Naming::Snip), false)   => app.handle_gesture(GestureEvent::Scissors { name }),
+                (Some(Naming::Rename), false) => app.handle_gesture(GestureEvent::RenameSnippet { name }),
+                (None, false)                => {}
+            }
+        }
+
         // When S is pressed, poll_input sends SimInput::KeyDown(Scissors).
         // The SimGestureSource forwards it as GestureEvent::Scissors { name: "" }.
-        // We intercept that here to collect the name from stdin without
-        // blocking the gesture thread.
+        // We intercept that here and open the name prompt; the render loop
+        // keeps running while the performer types.
 
         // 3. Drain gesture events
         loop {
             match gesture_rx.try_recv() {
-                Ok(GestureEvent::Quit) => return Ok(()),
+                Ok(GestureEvent::Quit) => break 'frames,
+                Ok(GestureEvent::SpiralView) => vis.toggle_spiral(),
+                Ok(GestureEvent::Histogram)  => vis.toggle_histogram(),
+                Ok(GestureEvent::Screenshot) => {
+                    let secs = SystemTime::now().duration_since(UNIX_EPOCH)
+                        .map(|d| d.as_secs()).unwrap_or(0);
+                    let path = capture::screenshot_name(secs);
+                    app.perf.status = match vis.save_screenshot(&path) {
+                        Ok(())  => format!("SCREENSHOT saved to {}", path),
+                        Err(e)  => format!("Screenshot failed: {}", e),
+                    };
+                }
+                Ok(GestureEvent::Fullscreen) => {
+                    let on = !vis.is_fullscreen();
+                    if let Err(e) = vis.set_fullscreen(on) {
+                        app.perf.status = format!("Fullscreen failed: {}", e);
+                    }
+                }
+                Ok(GestureEvent::DetachTray) => {
+                    let on = !vis.is_detached();
+                    if let Err(e) = vis.set_detached(on) {
+                        app.perf.status = format!("Tray window failed: {}", e);
+                    }
+                }
                 Ok(GestureEvent::Scissors { name }) => {
-                    let n = if name.is_empty() {
-                        print!("  Snippet name: ");
-                        io::stdout().flush().ok();
-                        let mut buf = String::new();
-                        io::stdin().read_line(&mut buf).ok();
-                        buf.trim().to_string()
+                    vis.notify_gesture(HandGesture::Scissors);
+                    if name.is_empty() {
+                        vis.begin_text_entry("Snippet name:");
+                        naming = Some(Naming::Snip);
                     } else {
-                        name
-                    };
-                    vis.notify_gesture(crate::visualizer::HandGesture::Scissors);
-                    app.handle_gesture(GestureEvent::Scissors { name: n });
+                        app.handle_gesture(GestureEvent::Scissors { name });
+                    }
                 }
-                Ok(ref evt) => {
+                Ok(GestureEvent::RenameSnippet { name }) => {
+                    if name.is_empty() {
+                        vis.begin_text_entry("Rename snippet to:");
+                        naming = Some(Naming::Rename);
+                    } else {
+                        app.handle_gesture(GestureEvent::RenameSnippet { name });
+                    }
+                }
+                Ok(evt) => {
                     // Map gesture to hand pose for 3D ghost
-                    let hg = match evt {
-                        GestureEvent::PullLeft  { .. } => crate::visualizer::HandGesture::PullLeft,
-                        GestureEvent::PullRight { .. } => crate::visualizer::HandGesture::PullRight,
-                        GestureEvent::Twist            => crate::visualizer::HandGesture::Twist,
-                        GestureEvent::Clap             => crate::visualizer::HandGesture::Clap,
-                        GestureEvent::Unclap           => crate::visualizer::HandGesture::Idle,
-                        _                              => crate::visualizer::HandGesture::Idle,
-                    };
-                    vis.notify_gesture(hg);
-                    app.handle_gesture(evt.clone());
+                    vis.notify_gesture(HandGesture::of(&evt));
+                    app.handle_gesture(evt);
                 }
                 Err(TryRecvError::Empty)        => break,
-                Err(TryRecvError::Disconnected) => return Ok(()),
+                Err(TryRecvError::Disconnected) => break 'frames,
             }
         }
 
+        // A saved performance takes the film so far along with it
+        if let (Some(path), Some(rec)) = (app.take_saved_performance(), recorder.as_mut()) {
+            let gif = Path::new(&path).with_extension("gif");
+            app.perf.status = match rec.save(&gif, Instant::now()) {
+                Ok(n)  => format!("{}  + {} frames to {}", app.perf.status, n, gif.display()),
+                Err(e) => format!("{}  (GIF failed: {})", app.perf.status, e),
+            };
+        }
+
         // 4. Per-frame logic
+        vis.set_zoom(app.zoom());
+        app.set_ribbon_capacity(vis.ribbon_capacity());
         app.tick();
 
         // 5. Render
-        vis.render(
-            app.left_ribbon(),
-            app.right_ribbon(),
-            app.stitch(),
-            app.tray(),
-            app.scissor_anim(),
-            &app.status,
-            app.is_playing(),
-            app.note_highlight(),
-        );
+        vis.set_beat_pulse(app.beat_pulse());
+        vis.set_marked(app.marked_patches());
+        vis.render(&Scene {
+            left:           app.left_ribbon(),
+            right:          app.right_ribbon(),
+            stitch:         app.stitch(),
+            tray:           app.tray(),
+            scissor:        app.scissor_anim(),
+            status:         app.status(),
+            playing:        app.is_playing(),
+            note_highlight: app.note_highlight(),
+            roll:           app.piano_roll(),
+            stacked:        &app.stacked_pairs(),
+        });
+
+        // 6. Film what was drawn, from the first note on, as the MIDI
+        //    recording runs
+        if filming && app.has_played() {
+            let (pixels, w, h) = vis.frame();
+            let filmed = match recorder.take() {
+                Some(rec) => Ok(rec),
+                None      => GifRecorder::new(w, h),
+            }.and_then(|mut rec| rec.capture(pixels, w, h, Instant::now()).map(|()| rec));
+            match filmed {
+                Ok(rec) => recorder = Some(rec),
+                Err(e)  => { eprintln!("  ⚠  GIF recording stopped: {}", e); filming = false; }
+            }
+        }
     }
 
-    Ok(())
+    // ── Keep the tray for next time ───────────────────────────────────────
+    app.save_tray()
 }
 
 // ════════════════════════════════════════════════════════════════════════════
@@ -377,45 +942,53 @@ pub fn run(cfg: AppConfig, layout: crate::visualizer::LayoutMode) -> Result<(),
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gesture::SimKey;
+    use crate::perform::NoteEvent;
 
     fn make_app() -> AppState {
         AppState::new(AppConfig::default())
     }
 
+    /// The (left, right) stream positions.
+    fn positions(dual: &SharedStream) -> (usize, usize) {
+        let dual = dual.lock();
+        (dual.left_pos(), dual.right_pos())
+    }
+
     #[test]
     fn pull_left_advances_ribbon() {
         let mut app = make_app();
-        let before = app.dual.left_pos();
+        let before = app.dual.lock().left_pos();
         app.handle_gesture(GestureEvent::PullLeft { steps: 3, velocity: 0.5 });
-        assert_eq!(app.dual.left_pos(), before + 3);
+        assert_eq!(app.dual.lock().left_pos(), before + 3);
     }
 
     #[test]
     fn pull_right_does_not_move_left() {
         let mut app = make_app();
-        let lbefore = app.dual.left_pos();
+        let lbefore = app.dual.lock().left_pos();
         app.handle_gesture(GestureEvent::PullRight { steps: 5, velocity: 0.5 });
-        assert_eq!(app.dual.left_pos(), lbefore);
-        assert_eq!(app.dual.right_pos(), 5);
+        assert_eq!(app.dual.lock().left_pos(), lbefore);
+        assert_eq!(app.dual.lock().right_pos(), 5);
     }
 
     #[test]
     fn twist_swaps_labels() {
         let mut app = make_app();
-        let ll_before = app.left_ribbon.label.clone();
-        let rl_before = app.right_ribbon.label.clone();
+        let ll_before = app.perf.left_ribbon.label.clone();
+        let rl_before = app.perf.right_ribbon.label.clone();
         app.handle_gesture(GestureEvent::Twist);
-        assert_ne!(app.left_ribbon.label, ll_before);
+        assert_ne!(app.perf.left_ribbon.label, ll_before);
         // After twist, what was right is now left
-        assert_eq!(app.left_ribbon.label, rl_before);
+        assert_eq!(app.perf.left_ribbon.label, rl_before);
     }
 
     #[test]
     fn clap_starts_playing() {
         let mut app = make_app();
-        assert_eq!(app.play_state, PlayState::Stopped);
+        assert_eq!(app.perf.play_state, PlayState::Stopped);
         app.handle_gesture(GestureEvent::Clap);
-        assert_eq!(app.play_state, PlayState::Playing);
+        assert_eq!(app.perf.play_state, PlayState::Playing);
     }
 
     #[test]
@@ -423,16 +996,16 @@ mod tests {
         let mut app = make_app();
         app.handle_gesture(GestureEvent::Clap);
         app.handle_gesture(GestureEvent::Unclap);
-        assert_eq!(app.play_state, PlayState::Stopped);
+        assert_eq!(app.perf.play_state, PlayState::Stopped);
     }
 
     #[test]
     fn clap_unclap_stitch_phases() {
         let mut app = make_app();
         app.handle_gesture(GestureEvent::Clap);
-        assert!(matches!(app.stitch, StitchPhase::Stitching { .. }));
+        assert!(matches!(app.perf.stitch, StitchPhase::Stitching { .. }));
         app.handle_gesture(GestureEvent::Unclap);
-        assert!(matches!(app.stitch, StitchPhase::Unstitching { .. }));
+        assert!(matches!(app.perf.stitch, StitchPhase::Unstitching { .. }));
     }
 
     #[test]
@@ -442,8 +1015,8 @@ mod tests {
         app.handle_gesture(GestureEvent::PullLeft  { steps: 10, velocity: 0.5 });
         app.handle_gesture(GestureEvent::PullRight { steps: 10, velocity: 0.5 });
         app.do_snip("test_snip");
-        assert_eq!(app.tray.entries.len(), 1);
-        assert_eq!(app.tray.entries[0].name, "test_snip");
+        assert_eq!(app.perf.tray.entries.len(), 1);
+        assert_eq!(app.perf.tray.entries[0].name, "test_snip");
     }
 
     #[test]
@@ -451,7 +1024,7 @@ mod tests {
         let mut app = make_app();
         app.handle_gesture(GestureEvent::PullLeft  { steps: 5, velocity: 0.5 });
         app.do_snip("my_snip");
-        assert!(app.dual.get_snippet("my_snip").is_some());
+        assert!(app.dual.lock().get_snippet("my_snip").is_some());
     }
 
     #[test]
@@ -459,15 +1032,378 @@ mod tests {
         let mut app = make_app();
         app.handle_gesture(GestureEvent::PullLeft { steps: 5, velocity: 0.5 });
         app.do_snip("anim_test");
-        assert!(app.scissor_anim.is_some());
+        assert!(app.perf.scissor.is_some());
+    }
+
+    #[test]
+    fn cycle_instrument_wraps_and_names() {
+        let mut app = make_app();
+        app.handle_gesture(GestureEvent::CycleInstrument { step: -1 });
+        assert_eq!(app.perf.instrument, 127);
+        assert!(app.perf.status.contains("Gunshot"));
+        app.handle_gesture(GestureEvent::CycleInstrument { step: 8 });
+        assert_eq!(app.perf.instrument, 7);
+    }
+
+    #[test]
+    fn hand_height_sets_velocity() {
+        use crate::gesture::height_to_expression;
+        let mut app = make_app();
+        app.handle_gesture(GestureEvent::Expression { value: height_to_expression(50.0) });
+        assert_eq!(app.perf.velocity, 20);
+        app.handle_gesture(GestureEvent::Expression { value: height_to_expression(250.0) });
+        assert_eq!(app.perf.velocity, 74);
+        app.handle_gesture(GestureEvent::Expression { value: height_to_expression(900.0) });
+        assert_eq!(app.perf.velocity, 127);
+    }
+
+    #[test]
+    fn tempo_event_updates_tempo() {
+        let mut app = make_app();
+        app.handle_gesture(GestureEvent::Tempo { bpm: 96 });
+        assert_eq!(app.perf.tempo_bpm, 96);
+        assert_eq!(app.perf.status, "TEMPO 96 BPM");
+        app.handle_gesture(SimKey::TempoUp.event());
+        assert_eq!(app.perf.tempo_bpm, 100);
+        app.handle_gesture(GestureEvent::NudgeTempo { step: -400 });
+        assert_eq!(app.perf.tempo_bpm, 20);
+    }
+
+    #[test]
+    fn pause_keeps_the_stitch() {
+        let mut app = make_app();
+        app.handle_gesture(GestureEvent::Pause);
+        assert_eq!(app.perf.play_state, PlayState::Stopped);
+        app.handle_gesture(GestureEvent::Clap);
+        for _ in 0..100 { app.tick(); }
+        app.handle_gesture(GestureEvent::Pause);
+        assert_eq!(app.perf.play_state, PlayState::Paused);
+        assert_eq!(app.perf.stitch, StitchPhase::Stitched);
+        app.handle_gesture(GestureEvent::Pause);
+        assert!(app.is_playing());
+        app.handle_gesture(GestureEvent::Pause);
+        app.handle_gesture(GestureEvent::Unclap);
+        assert_eq!(app.perf.play_state, PlayState::Stopped);
+    }
+
+    #[test]
+    fn scrub_and_zoom() {
+        let mut app = AppState::new(AppConfig {
+            left_config:  SpigotConfig::new(Constant::Champernowne, 10),
+            right_config: SpigotConfig::new(Constant::ThueMorse, 2),
+            ..AppConfig::default()
+        });
+        app.handle_gesture(GestureEvent::PullLeft  { steps: 12, velocity: 0.5 });
+        app.handle_gesture(GestureEvent::PullRight { steps: 4,  velocity: 0.5 });
+        let shown = app.perf.left_ribbon.patches.last().cloned();
+        app.handle_gesture(GestureEvent::Scrub { steps: -8 });
+        assert_eq!(positions(&app.dual), (4, 0));
+        assert!(app.perf.right_ribbon.patches.is_empty());
+        app.handle_gesture(GestureEvent::Scrub { steps: 8 });
+        let last = app.perf.left_ribbon.patches.last().unwrap();
+        assert_eq!((last.digit, last.position), shown.map(|p| (p.digit, p.position)).unwrap());
+
+        for _ in 0..10 { app.handle_gesture(GestureEvent::Zoom { factor: 1.25 }); }
+        assert_eq!(app.zoom(), MAX_ZOOM);
+    }
+
+    #[test]
+    fn splice_uses_the_selected_snippet() {
+        let mut app = make_app();
+        app.handle_gesture(GestureEvent::Splice);
+        assert!(app.perf.status.starts_with("Tray is empty"));
+        app.handle_gesture(GestureEvent::PullRight { steps: 3, velocity: 0.5 });
+        app.do_snip("a");
+        app.do_snip("b");
+        app.handle_gesture(GestureEvent::SelectSnippet { step: 1 });
+        app.handle_gesture(GestureEvent::SelectSnippet { step: 1 });
+        assert_eq!(app.perf.status, "TRAY → \"a\"");
+        app.handle_gesture(GestureEvent::Splice);
+        assert!(app.perf.status.starts_with("SPLICE \"a\""));
+    }
+
+    #[test]
+    fn tray_entries_rename_and_delete_with_their_snippets() {
+        let mut app = make_app();
+        app.handle_gesture(GestureEvent::PullRight { steps: 3, velocity: 0.5 });
+        app.do_snip("a");
+        app.do_snip("b");
+        app.handle_gesture(GestureEvent::PickSnippet { index: 0 });
+        app.handle_gesture(GestureEvent::MoveSnippet { step: 1 });
+        assert_eq!(app.perf.tray.entries[1].name, "a");
+        app.handle_gesture(GestureEvent::RenameSnippet { name: "intro".to_string() });
+        assert_eq!(app.perf.tray.entries[1].name, "intro");
+        let dual = app.dual.lock();
+        assert!(dual.get_snippet("intro").is_some() && dual.get_snippet("a").is_none());
+        drop(dual);
+        app.handle_gesture(GestureEvent::DeleteSnippet);
+        assert_eq!(app.perf.tray.entries.len(), 1);
+        assert!(app.dual.lock().get_snippet("intro").is_none());
+    }
+
+    #[test]
+    fn session_restores_streams_ribbons_and_settings() {
+        let cfg = || AppConfig {
+            left_config:  SpigotConfig::new(Constant::Champernowne, 10),
+            right_config: SpigotConfig::new(Constant::ThueMorse, 2),
+            ..AppConfig::default()
+        };
+        let mut app = AppState::new(cfg());
+        app.handle_gesture(GestureEvent::Twist);
+        app.handle_gesture(GestureEvent::PullLeft { steps: 7, velocity: 0.5 });
+        app.handle_gesture(GestureEvent::Tempo { bpm: 90 });
+        app.handle_gesture(GestureEvent::CycleInstrument { step: 3 });
+        app.do_snip("riff");
+        let saved = app.session();
+
+        let restored = AppState::new(AppConfig { session: Some(saved.clone()), ..cfg() });
+        assert_eq!(restored.session(), saved);
+        assert_eq!(restored.dual.lock().left_constant(), Some(Constant::ThueMorse));
+        assert_eq!(positions(&restored.dual), (7, 0));
+        assert_eq!((restored.perf.tempo_bpm, restored.perf.instrument), (90, 3));
+        assert!(restored.dual.lock().get_snippet("riff").is_some());
+    }
+
+    #[test]
+    fn undo_and_redo_pulls_and_twists() {
+        let mut app = AppState::new(AppConfig {
+            left_config:  SpigotConfig::new(Constant::Champernowne, 10),
+            right_config: SpigotConfig::new(Constant::ThueMorse, 2),
+            ..AppConfig::default()
+        });
+        app.handle_gesture(GestureEvent::PullLeft { steps: 3, velocity: 0.5 });
+        let ribbon = app.perf.left_ribbon.patches.len();
+        app.handle_gesture(GestureEvent::PullLeft { steps: 20, velocity: 0.9 });
+        app.handle_gesture(GestureEvent::Twist);
+
+        app.handle_gesture(GestureEvent::Undo);
+        assert_eq!(app.dual.lock().left_constant(), Some(Constant::Champernowne));
+        app.handle_gesture(GestureEvent::Undo);
+        assert_eq!(app.dual.lock().left_pos(), 3);
+        assert_eq!(app.perf.left_ribbon.patches.len(), ribbon);
+
+        app.handle_gesture(GestureEvent::Redo);
+        assert_eq!(app.dual.lock().left_pos(), 23);
+        app.handle_gesture(GestureEvent::PullRight { steps: 1, velocity: 0.5 });
+        app.handle_gesture(GestureEvent::Redo);
+        assert_eq!(app.perf.status, "Nothing to redo");
+        assert_eq!(app.dual.lock().left_constant(), Some(Constant::Champernowne));
+    }
+
+    #[test]
+    fn ribbons_follow_the_window_width() {
+        let mut app = AppState::new(AppConfig {
+            left_config:     SpigotConfig::new(Constant::Champernowne, 10),
+            right_config:    SpigotConfig::new(Constant::ThueMorse, 2),
+            ribbon_capacity: 10,
+            ..AppConfig::default()
+        });
+        app.handle_gesture(GestureEvent::PullLeft { steps: 30, velocity: 0.5 });
+        app.set_ribbon_capacity(4);
+        assert_eq!(app.perf.left_ribbon.patches.len(), 4);
+        assert_eq!(app.perf.left_ribbon.patches.last().unwrap().position, app.dual.lock().left_pos());
+
+        app.set_ribbon_capacity(25);
+        assert_eq!(app.perf.left_ribbon.patches.len(), 25);
+        assert_eq!(app.perf.left_ribbon.patches.last().unwrap().position, app.dual.lock().left_pos());
+    }
+
+    #[test]
+    fn dragged_patches_are_what_the_snip_takes() {
+        let mut app = AppState::new(AppConfig {
+            left_config:     SpigotConfig::new(Constant::Champernowne, 10),
+            right_config:    SpigotConfig::new(Constant::ThueMorse, 2),
+            ribbon_capacity: 10,
+            ..AppConfig::default()
+        });
+        app.handle_gesture(GestureEvent::PullLeft { steps: 12, velocity: 0.5 });
+        app.handle_gesture(GestureEvent::PullRight { steps: 12, velocity: 0.5 });
+        let patches = app.perf.left_ribbon.patches.clone();
+
+        app.handle_gesture(GestureEvent::InspectPatch { right: false, index: 2 });
+        assert!(app.perf.status.contains(&format!("at position {}", patches[2].position)), "{}", app.perf.status);
+
+        app.handle_gesture(GestureEvent::MarkPatches { from: 2, to: 5 });
+        assert_eq!(app.marked_patches(), Some((2, 5)));
+        app.handle_gesture(GestureEvent::Scissors { name: "mid".into() });
+        assert_eq!(app.marked_patches(), None);
+        assert_eq!(app.dual.lock().get_snippet("mid").unwrap().len(), 3);
+        let entry = app.perf.tray.entries.last().unwrap();
+        let digits: Vec<u8> = entry.patches.iter().map(|(l, _)| l.digit).collect();
+        assert_eq!(digits, patches[2..5].iter().map(|p| p.digit).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn next_pair_hands_the_pulls_over() {
+        let champ  = SpigotConfig::new(Constant::Champernowne, 10);
+        let thue   = SpigotConfig::new(Constant::ThueMorse, 2);
+        let mut app = AppState::new(AppConfig {
+            left_config:  champ,
+            right_config: thue,
+            extra_pairs:  vec![(thue, champ)],
+            ..AppConfig::default()
+        });
+        app.handle_gesture(GestureEvent::PullLeft { steps: 3, velocity: 0.5 });
+        app.handle_gesture(GestureEvent::Scissors { name: "riff".into() });
+        assert_eq!(app.stacked_pairs()[0].label, "pair 2  ch 2");
+
+        app.handle_gesture(GestureEvent::NextPair);
+        app.handle_gesture(GestureEvent::PullLeft { steps: 2, velocity: 0.5 });
+        assert_eq!((app.pair, app.channel, app.dual.lock().left_pos()), (1, 1, 2));
+        assert_eq!(app.dual.lock().left_constant(), Some(Constant::ThueMorse));
+        assert!(app.dual.lock().get_snippet("riff").is_some());
+        assert_eq!(app.stacked_pairs()[0].label, "pair 1  ch 1");
+
+        // Undo stays with its pair.
+        app.handle_gesture(GestureEvent::NextPair);
+        app.handle_gesture(GestureEvent::Undo);
+        assert_eq!((app.pair, app.dual.lock().left_pos()), (0, 0));
+        assert_eq!(app.pairs[0].dual.lock().left_pos(), 2);
+    }
+
+    #[test]
+    fn histogram_counts_every_digit_pulled() {
+        let mut app = AppState::new(AppConfig {
+            left_config:  SpigotConfig::new(Constant::Champernowne, 10),
+            right_config: SpigotConfig::new(Constant::ThueMorse, 2),
+            ..AppConfig::default()
+        });
+        app.handle_gesture(GestureEvent::PullLeft { steps: 12, velocity: 0.5 });
+        assert_eq!(app.perf.left_ribbon.stats.total(), 12);
+        assert_eq!(app.perf.left_ribbon.stats.count(1), 2);   // 0.12345678910
+
+        app.handle_gesture(GestureEvent::Scrub { steps: 8 });
+        assert_eq!(app.perf.left_ribbon.stats.total(), 20);
+        assert_eq!(app.perf.right_ribbon.stats.counts(), [4, 4]);
+        app.handle_gesture(GestureEvent::Undo);
+        assert_eq!(app.perf.left_ribbon.stats.total(), 12);
+    }
+
+    #[test]
+    fn highlights_the_sounding_note_on_both_ribbons() {
+        let mut app = AppState::new(AppConfig {
+            left_config:  SpigotConfig::new(Constant::Champernowne, 10),
+            right_config: SpigotConfig::new(Constant::ThueMorse, 2),
+            ..AppConfig::default()
+        });
+        app.perf.play_state = PlayState::Playing;
+        app.perf.sounding = Some((3, 2, false));
+        app.tick();
+        assert_eq!(app.note_highlight(), (Some(2), Some(1)));
+
+        // A spliced pair is highlighted where it was pushed, not on the
+        // stream digit at the same position.
+        app.perf.left_ribbon.push(7, 3);
+        app.perf.right_ribbon.push(7, 2);
+        app.perf.sounding = Some((3, 2, true));
+        app.tick();
+        let last = app.perf.left_ribbon.patches.len() - 1;
+        assert_eq!(app.note_highlight(), (Some(last), Some(last)));
+
+        app.perf.play_state = PlayState::Stopped;
+        app.tick();
+        assert_eq!(app.note_highlight(), (None, None));
+    }
+
+    #[test]
+    fn playback_carries_on_from_the_pulls() {
+        let mut app = AppState::new(AppConfig {
+            left_config:  SpigotConfig::new(Constant::Champernowne, 10),
+            right_config: SpigotConfig::new(Constant::ThueMorse, 2),
+            ..AppConfig::default()
+        });
+        app.handle_gesture(GestureEvent::PullLeft { steps: 12, velocity: 0.5 });
+        // The player zips the same stream the pulls moved.
+        let pair = app.dual.lock().zip_next().unwrap();
+        assert_eq!(pair, (1, 0));   // 0.123456789101|1…
+        assert_eq!(positions(&app.dual), (13, 1));
+
+        let note = NoteEvent {
+            pitch: 60, duration: 480, velocity: 100, bpm: 120,
+            left_pos: 13, right_pos: 1, pair, spliced: false, first: true,
+        };
+        let right_len = app.perf.right_ribbon.patches.len();
+        show_played(&mut app.perf.left_ribbon, &mut app.perf.right_ribbon, &note);
+        let last = app.perf.left_ribbon.patches.last().unwrap();
+        assert_eq!((last.digit, last.position), (1, 13));
+        // The right ribbon already shows its first digit; it's only counted.
+        assert_eq!(app.perf.right_ribbon.patches.len(), right_len);
+        assert_eq!(app.perf.right_ribbon.stats.total(), 1);
+    }
+
+    #[test]
+    fn a_script_drives_the_app_headless() {
+        let script = Script::parse("
+            0    pull_left 3
+            +10  pull_right 2
+            +10  scissors opening
+            +10  scrub -1
+        ").unwrap();
+        let rx = crate::gesture::spawn_gesture_source(ScriptedGestureSource { script });
+        let mut app = make_app();
+        for event in rx { app.handle_gesture(event); }
+        assert_eq!(app.perf.tray.entries[0].name, "opening");
+        assert_eq!(app.dual.lock().right_pos(), 1);
+    }
+
+    #[test]
+    fn panic_stops_playing() {
+        let mut app = make_app();
+        app.handle_gesture(GestureEvent::Clap);
+        app.handle_gesture(GestureEvent::Panic);
+        assert!(!app.is_playing());
+        assert!(matches!(app.perf.stitch, StitchPhase::Unstitching { .. }));
     }
 
     #[test]
     fn tick_advances_stitch_animation() {
         let mut app = make_app();
         app.handle_gesture(GestureEvent::Clap);
-        assert!(matches!(app.stitch, StitchPhase::Stitching { .. }));
+        assert!(matches!(app.perf.stitch, StitchPhase::Stitching { .. }));
         for _ in 0..100 { app.tick(); }
-        assert_eq!(app.stitch, StitchPhase::Stitched);
+        assert_eq!(app.perf.stitch, StitchPhase::Stitched);
+    }
+
+    #[test]
+    fn a_stopped_player_shows_on_the_status_line() {
+        let mut app = make_app();
+        app.player.quit().unwrap();
+        let start = Instant::now();
+        while app.player.stop().is_ok() {
+            assert!(start.elapsed() < std::time::Duration::from_secs(2), "player thread still running");
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        app.handle_gesture(GestureEvent::Clap);
+        app.tick();
+        assert_eq!(app.perf.status, "⚠  the MIDI player has stopped");
+    }
+
+    /// A timeline whose current beat the test sets by hand.
+    struct ManualSync(std::sync::Mutex<f64>);
+    impl BeatSync for ManualSync {
+        fn tempo(&self) -> f64 { 120.0 }
+        fn set_tempo(&self, _bpm: f64) {}
+        fn beat_at(&self, _at: std::time::Instant) -> f64 { *self.0.lock().unwrap() }
+        fn time_at_beat(&self, _beat: f64) -> std::time::Instant { std::time::Instant::now() }
+    }
+
+    #[test]
+    fn stitched_ribbons_pulse_on_shared_downbeat() {
+        let sync = Arc::new(ManualSync(std::sync::Mutex::new(2.5)));
+        let mut app = AppState::new(AppConfig { sync: Some(sync.clone()), ..AppConfig::default() });
+
+        *sync.0.lock().unwrap() = 4.2;
+        app.tick();
+        assert_eq!(app.beat_pulse(), 0.0, "no pulse while unstitched");
+
+        app.handle_gesture(GestureEvent::Clap);
+        *sync.0.lock().unwrap() = 7.9;
+        app.tick();
+        assert_eq!(app.beat_pulse(), 0.0, "no pulse mid-bar");
+        *sync.0.lock().unwrap() = 8.05;
+        app.tick();
+        assert_eq!(app.beat_pulse(), 1.0);
+        app.tick();
+        assert!(app.beat_pulse() < 1.0);
     }
 }
@@ -0,0 +1,192 @@
+//! Gesture calibration wizard.
+//!
+//! `--calibrate` walks the player through each gesture on the console while
+//! recording their tracked hands, then derives personalised [`Thresholds`]
+//! from what it saw — how close *their* clap brings the palms, how fast
+//! *their* pulls and swipes are, against how much their hands drift at
+//! rest — and saves them to the gesture config.
+//!
+//! The recording is separated from the arithmetic: [`run_wizard`] collects
+//! [`CalibrationSamples`] from any stream of tracked-hand frames, and
+//! [`derive_thresholds`] turns them into thresholds.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::config::Thresholds;
+use crate::gesture::TrackedHand;
+
+/// One tracked-hand frame: `(left, right)`.
+pub type Frame = (Option<TrackedHand>, Option<TrackedHand>);
+
+/// Frames recorded while the player performed each step.
+#[derive(Clone, Debug, Default)]
+pub struct CalibrationSamples {
+    /// Both hands held still, comfortably apart.
+    pub rest:  Vec<Frame>,
+    /// Palms brought together and held.
+    pub clap:  Vec<Frame>,
+    /// Repeated pulls toward the body with either hand.
+    pub pull:  Vec<Frame>,
+    /// One hand held above the other.
+    pub twist: Vec<Frame>,
+    /// Sideways swipes with the right hand.
+    pub swipe: Vec<Frame>,
+}
+
+/// The wizard's steps: instruction and how long to record.
+const STEPS: [(&str, Duration); 5] = [
+    ("Hold both hands still, comfortably apart.",          Duration::from_secs(3)),
+    ("Bring your palms together, as in a clap, and hold.", Duration::from_secs(3)),
+    ("Pull each hand toward you, firmly, a few times.",    Duration::from_secs(5)),
+    ("Raise one hand well above the other and hold.",      Duration::from_secs(3)),
+    ("Swipe your right hand sideways a few times.",        Duration::from_secs(4)),
+];
+
+/// Prompt for each gesture in turn and record `frames` while it is
+/// performed.  Returns `None` if the frames run out first.
+pub fn run_wizard(mut frames: impl Iterator<Item = Frame>) -> Option<CalibrationSamples> {
+    println!();
+    println!("  ── Gesture calibration ──────────────────────────────────────");
+    let mut recorded: Vec<Vec<Frame>> = Vec::new();
+    for (i, (instruction, length)) in STEPS.iter().enumerate() {
+        println!("  {}/{}  {}", i + 1, STEPS.len(), instruction);
+        print!("       Press Enter when ready… ");
+        io::stdout().flush().ok();
+        io::stdin().read_line(&mut String::new()).ok();
+
+        // Frames queue up while we wait for Enter; start from fresh ones.
+        let start = Instant::now();
+        let mut step = Vec::new();
+        for frame in frames.by_ref() {
+            if start.elapsed() > length.mul_f32(0.1) { step.push(frame); }
+            if start.elapsed() >= *length { break; }
+        }
+        if start.elapsed() < *length { return None; }
+        println!("       Recorded {} frames.", step.len());
+        recorded.push(step);
+    }
+    let mut steps = recorded.into_iter();
+    let mut next = || steps.next().unwrap_or_default();
+    Some(CalibrationSamples {
+        rest: next(), clap: next(), pull: next(), twist: next(), swipe: next(),
+    })
+}
+
+/// Personalised thresholds from the recorded samples; settings the wizard
+/// doesn't measure are kept from `base`.
+pub fn derive_thresholds(s: &CalibrationSamples, base: &Thresholds) -> Result<Thresholds, String> {
+    let palm_dist = |(l, r): &Frame| {
+        let (l, r) = (l.as_ref()?, r.as_ref()?);
+        Some((0..3).map(|i| (l.palm[i] - r.palm[i]).powi(2)).sum::<f32>().sqrt())
+    };
+    let height_gap = |(l, r): &Frame| Some((l.as_ref()?.palm[1] - r.as_ref()?.palm[1]).abs());
+    let hands = |f: &Frame| [f.0, f.1].into_iter().flatten().collect::<Vec<_>>();
+    let need = |v: Vec<f32>, step: &str| {
+        if v.is_empty() {
+            Err(format!("no hands were tracked during the {} step", step))
+        } else {
+            Ok(v)
+        }
+    };
+
+    // Resting hands: how far apart they sit and how much they drift.
+    let apart = median(need(s.rest.iter().filter_map(palm_dist).collect(), "rest")?);
+    let drift = s.rest.iter().flat_map(hands)
+        .map(|h| h.velocity[0].abs().max(h.velocity[2].abs()))
+        .fold(0.0, f32::max);
+
+    let together = median(need(s.clap.iter().filter_map(palm_dist).collect(), "clap")?);
+    if together >= apart {
+        return Err("the clap didn't bring your hands closer than at rest".to_string());
+    }
+    let clap_dist   = (together * 1.5).min(together + (apart - together) * 0.4);
+    let unclap_dist = clap_dist + (apart - clap_dist) * 0.5;
+
+    // Pulls and swipes trigger at half of a typical (90th percentile) peak,
+    // but always above resting drift.
+    let pull_peak = percentile(
+        need(s.pull.iter().flat_map(hands).map(|h| h.velocity[2]).collect(), "pull")?, 0.9);
+    let pull_min = (pull_peak * 0.5).max(drift * 1.5).max(50.0);
+    if pull_min >= pull_peak {
+        return Err("the pulls were no faster than your hands at rest".to_string());
+    }
+
+    let twist = median(need(s.twist.iter().filter_map(height_gap).collect(), "twist")?);
+
+    let swipe_peak = percentile(
+        need(s.swipe.iter().filter_map(|f| f.1).map(|h| h.velocity[0].abs()).collect(), "swipe")?,
+        0.9);
+    let swipe_min = (swipe_peak * 0.6).max(pull_min * 1.5).max(drift * 1.5);
+
+    let round = |v: f32| (v * 10.0).round() / 10.0;
+    Ok(Thresholds {
+        clap_dist_mm:       round(clap_dist),
+        unclap_dist_mm:     round(unclap_dist),
+        pull_velocity_min:  round(pull_min),
+        // A firm pull advances five digits.
+        pull_step_divisor:  round((pull_peak / 5.0).max(1.0)),
+        twist_height_mm:    round((twist * 0.6).max(20.0)),
+        swipe_velocity_min: round(swipe_min),
+        ..base.clone()
+    })
+}
+
+fn percentile(mut v: Vec<f32>, p: f32) -> f32 {
+    v.sort_by(f32::total_cmp);
+    v[((v.len() - 1) as f32 * p).round() as usize]
+}
+
+fn median(v: Vec<f32>) -> f32 {
+    percentile(v, 0.5)
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand(palm: [f32; 3], velocity: [f32; 3]) -> Option<TrackedHand> {
+        Some(TrackedHand { palm, velocity, ..TrackedHand::default() })
+    }
+
+    fn pair(gap: f32, dy: f32) -> Frame {
+        (hand([-gap / 2.0, 200.0 + dy, 0.0], [0.0; 3]), hand([gap / 2.0, 200.0, 0.0], [0.0; 3]))
+    }
+
+    fn samples() -> CalibrationSamples {
+        CalibrationSamples {
+            rest:  vec![pair(300.0, 0.0); 10],
+            clap:  vec![pair(40.0, 0.0); 10],
+            pull:  (0..10).map(|i| (hand([0.0, 200.0, 0.0], [0.0, 0.0, 100.0 * i as f32]), None)).collect(),
+            twist: vec![pair(200.0, 120.0); 10],
+            swipe: vec![(None, hand([0.0, 200.0, 0.0], [-1200.0, 0.0, 0.0])); 10],
+        }
+    }
+
+    #[test]
+    fn derives_thresholds_from_samples() {
+        let base = Thresholds::default();
+        let t = derive_thresholds(&samples(), &base).unwrap();
+        assert_eq!(t.clap_dist_mm, 60.0);
+        assert_eq!(t.unclap_dist_mm, 180.0);
+        assert_eq!(t.pull_velocity_min, 400.0);
+        assert_eq!(t.pull_step_divisor, 160.0);
+        assert_eq!(t.twist_height_mm, 72.0);
+        assert_eq!(t.swipe_velocity_min, 720.0);
+        assert_eq!(t.scissors_hold_frames, base.scissors_hold_frames);
+    }
+
+    #[test]
+    fn rejects_unusable_samples() {
+        let mut s = samples();
+        s.clap = vec![pair(400.0, 0.0); 10];
+        assert!(derive_thresholds(&s, &Thresholds::default()).is_err());
+        let mut s = samples();
+        s.swipe.clear();
+        assert!(derive_thresholds(&s, &Thresholds::default()).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Capturing what the visualizer shows.
+//!
+//! `F12` saves the frame on screen as a timestamped PNG
+//! (`screenshot-<unix secs>.png` in the working directory), so a ribbon
+//! state can be kept for slides or handouts without a screen-capture tool.
+//!
+//! With `--record-gif`, a [`GifRecorder`] films the ribbons from the first
+//! note played, as the MIDI recording does, and saving the performance
+//! (`W`) writes the film so far next to the MIDI file — `performance-<secs>.gif`
+//! beside `performance-<secs>.mid` — with the same timeline, so the two can
+//! be put together for a demo.  Frames are taken `GIF_FPS` times a second,
+//! shrunk to `GIF_WIDTH` pixels across and drawn in a fixed 216-color
+//! palette; a frame that doesn't change just lengthens the one before.
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Write `pixels` (0xAARRGGBB, row by row, `w` × `h`) to `path` as an RGB
+/// PNG.
+pub fn save_png(path: impl AsRef<Path>, pixels: &[u32], w: usize, h: usize) -> Result<(), String> {
+    let path = path.as_ref();
+    if pixels.len() != w * h {
+        return Err(format!("frame is {} pixels, not {}×{}", pixels.len(), w, h));
+    }
+    let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), w as u32, h as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| format!("{}: {}", path.display(), e))?;
+    writer.write_image_data(&rgb_bytes(pixels)).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Name for a screenshot taken at `secs` past the Unix epoch.
+pub fn screenshot_name(secs: u64) -> String {
+    format!("screenshot-{}.png", secs)
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// GifRecorder — an animated GIF of the performance
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Frames a second the recorder keeps.
+pub const GIF_FPS: u32 = 10;
+/// Width of the recorded frames; the height keeps the window's shape.
+pub const GIF_WIDTH: usize = 480;
+
+/// Films the visualizer into an animated GIF, compressed as it goes.
+pub struct GifRecorder {
+    encoder: gif::Encoder<Vec<u8>>,
+    size:    (usize, usize),
+    /// Newest frame, written once the next one shows how long it lasted.
+    pending: Option<(Vec<u8>, Instant)>,
+    /// When a frame was last looked at.
+    looked:  Option<Instant>,
+    /// Frames written so far.
+    frames:  usize,
+}
+
+impl GifRecorder {
+    /// Start a recording of frames shaped like a `w` × `h` window.
+    pub fn new(w: usize, h: usize) -> Result<Self, String> {
+        let size = (GIF_WIDTH, (GIF_WIDTH * h / w.max(1)).max(1));
+        let mut encoder = gif::Encoder::new(Vec::new(), size.0 as u16, size.1 as u16, &palette())
+            .map_err(|e| e.to_string())?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+        Ok(GifRecorder { encoder, size, pending: None, looked: None, frames: 0 })
+    }
+
+    /// Offer the frame on screen at `now`; it's kept if a `1/GIF_FPS` s
+    /// has passed since the last one kept and it shows something new.
+    pub fn capture(&mut self, pixels: &[u32], w: usize, h: usize, now: Instant) -> Result<(), String> {
+        let interval = Duration::from_secs(1) / GIF_FPS;
+        if self.looked.is_some_and(|t| now.saturating_duration_since(t) < interval) {
+            return Ok(());
+        }
+        self.looked = Some(now);
+        let frame = shrink(pixels, w, h, self.size);
+        match self.pending.take() {
+            Some((last, at)) if last == frame => self.pending = Some((last, at)),
+            Some((last, at)) => {
+                self.write(&last, now.saturating_duration_since(at))?;
+                self.pending = Some((frame, now));
+            }
+            None => self.pending = Some((frame, now)),
+        }
+        Ok(())
+    }
+
+    /// Write the film up to `now` to `path`, returning the frames in it.
+    /// Recording carries on.
+    pub fn save(&mut self, path: impl AsRef<Path>, now: Instant) -> Result<usize, String> {
+        let path = path.as_ref();
+        if let Some((last, at)) = self.pending.take() {
+            self.write(&last, now.saturating_duration_since(at))?;
+            self.pending = Some((last, now));
+        }
+        if self.frames == 0 {
+            return Err("nothing has been filmed yet".to_string());
+        }
+        let mut bytes = self.encoder.get_ref().clone();
+        bytes.push(0x3B);   // trailer
+        fs::write(path, bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(self.frames)
+    }
+
+    fn write(&mut self, indices: &[u8], lasted: Duration) -> Result<(), String> {
+        let mut frame = gif::Frame {
+            width:  self.size.0 as u16,
+            height: self.size.1 as u16,
+            delay:  (lasted.as_millis() / 10).clamp(1, u16::MAX as u128) as u16,
+            ..gif::Frame::default()
+        };
+        frame.buffer = indices.into();
+        self.encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+/// The 6×6×6 color cube, as GIF palette bytes.
+fn palette() -> Vec<u8> {
+    (0..216u32).flat_map(|i| [i / 36, i / 6 % 6, i % 6].map(|c| (c * 51) as u8)).collect()
+}
+
+/// Nearest palette entry for a 0xAARRGGBB pixel.
+fn palette_index(p: u32) -> u8 {
+    let level = |c: u32| ((c & 0xFF) + 25) / 51;
+    (level(p >> 16) * 36 + level(p >> 8) * 6 + level(p)) as u8
+}
+
+/// Sample a `w` × `h` frame down to `size`, as palette indices.
+fn shrink(pixels: &[u32], w: usize, h: usize, size: (usize, usize)) -> Vec<u8> {
+    let (dw, dh) = size;
+    (0..dh).flat_map(|y| (0..dw).map(move |x| (x * w / dw, y * h / dh)))
+        .map(|(sx, sy)| palette_index(pixels[sy * w + sx]))
+        .collect()
+}
+
+/// 0xAARRGGBB pixels → packed R, G, B bytes.
+fn rgb_bytes(pixels: &[u32]) -> Vec<u8> {
+    pixels.iter().flat_map(|&p| [(p >> 16) as u8, (p >> 8) as u8, p as u8]).collect()
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_png_reads_back() {
+        let path = std::env::temp_dir().join(format!("leap_spigot_shot_{}.png", std::process::id()));
+        let frame = [0xFF1A1A2E, 0xFFFFD700, 0xFF000000, 0xFFFFFFFF, 0xFF0F3460, 0xFFAADDFF];
+        save_png(&path, &frame, 3, 2).unwrap();
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(&buf[..info.buffer_size()], rgb_bytes(&frame));
+        assert_eq!(&buf[3..6], [0xFF, 0xD7, 0x00]);
+    }
+
+    #[test]
+    fn rejects_a_frame_of_the_wrong_size() {
+        assert!(save_png("unused.png", &[0; 5], 3, 2).is_err());
+        assert_eq!(screenshot_name(42), "screenshot-42.png");
+    }
+
+    #[test]
+    fn recorder_keeps_changed_frames_with_their_timing() {
+        let t0 = Instant::now();
+        let ms = |n: u64| t0 + Duration::from_millis(n);
+        let (black, white) = (vec![0xFF000000; 960 * 540], vec![0xFFFFFFFF; 960 * 540]);
+        let mut rec = GifRecorder::new(960, 540).unwrap();
+        rec.capture(&black, 960, 540, ms(0)).unwrap();
+        rec.capture(&white, 960, 540, ms(50)).unwrap();     // too soon
+        rec.capture(&black, 960, 540, ms(100)).unwrap();    // unchanged
+        rec.capture(&white, 960, 540, ms(300)).unwrap();
+
+        let path = std::env::temp_dir().join(format!("leap_spigot_film_{}.gif", std::process::id()));
+        assert_eq!(rec.save(&path, ms(1000)).unwrap(), 2);
+        let mut decoder = gif::DecodeOptions::new().read_info(File::open(&path).unwrap()).unwrap();
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!((frame.width, frame.height), (480, 270));
+            delays.push(frame.delay);
+        }
+        std::fs::remove_file(&path).ok();
+        assert_eq!(delays, [30, 70]);
+    }
+
+    #[test]
+    fn palette_matches_colors_to_the_cube() {
+        assert_eq!(palette().len(), 216 * 3);
+        assert_eq!(palette_index(0xFF000000), 0);
+        assert_eq!(palette_index(0xFFFFFFFF), 215);
+        assert_eq!(palette_index(0xFFFFD700), 5 * 36 + 4 * 6);
+    }
+}
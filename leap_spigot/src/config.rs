@@ -0,0 +1,443 @@
+//! Gesture mapping config file.
+//!
+//! `gestures.toml` in the working directory (or the file given with
+//! `--gestures PATH`) tunes the hand recogniser to the player's setup and
+//! rebinds gestures and keyboard keys to actions.  Every table and field is
+//! optional — anything left out keeps its default:
+//!
+//! ```toml
+//! keyboard = "azerty"             # default keys for the layout: qwerty | azerty
+//!
+//! [thresholds]
+//! clap_dist_mm         = 80.0    # hands this close = clap
+//! unclap_dist_mm       = 150.0   # hands this far after a clap = unclap
+//! pull_velocity_min    = 150.0   # mm/s toward the body to count as a pull
+//! pull_step_divisor    = 100.0   # mm/s per digit advanced
+//! pull_cooldown_ms     = 80
+//! twist_height_mm      = 40.0    # one palm this far above the other = twist
+//! twist_hold_frames    = 6
+//! scissors_hold_frames = 4
+//! scissors_cooldown_ms = 500
+//! swipe_velocity_min   = 900.0   # mm/s sideways
+//! swipe_cooldown_ms    = 600
+//! fist_hold_frames     = 4
+//! pinch_dist_mm        = 30.0    # thumb and index tips this close = pinch
+//! pinch_zoom_step_mm   = 30.0    # pinched hand rise/fall per zoom step
+//! stretch_height_mm    = 300.0   # both open palms this high = stretch
+//! stretch_min_mm       = 100.0   # palm distance for stretch_min_bpm
+//! stretch_max_mm       = 500.0   # palm distance for stretch_max_bpm
+//! stretch_min_bpm      = 60
+//! stretch_max_bpm      = 180
+//!
+//! [gestures]                      # hand gesture → action
+//! swipe_left       = "instrument_prev"   # right hand
+//! swipe_right      = "instrument_next"
+//! left_swipe_left  = "scrub_back"
+//! left_swipe_right = "scrub_forward"
+//! fist             = "pause"
+//!
+//! [timbre]                        # hand roll → a MIDI controller
+//! hand           = "right"        # left | right | off
+//! cc             = 74             # 74 brightness (filter cutoff), 11 expression…
+//! roll_range_deg = 60.0           # roll either way that reaches 0 / 127
+//!
+//! [keys]                          # key → action (merged over the defaults)
+//! Z = "twist"
+//! ```
+//!
+//! Actions are the [`SimKey`] names in snake_case (`pull_left`, `clap`,
+//! `save`, `next_port`, …).  Key names may carry a `Ctrl+` prefix.
+//!
+//! The AZERTY defaults keep the keys where they sit on a QWERTY board:
+//! `Q` / `D` pull, `Z` saves, `A` quits and `;` scrubs forward.  `[` and
+//! `]` need AltGr there, so use `PageUp` / `PageDown` for the instrument.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::gesture::SimKey;
+
+/// Default config file, looked for in the working directory.
+pub const DEFAULT_PATH: &str = "gestures.toml";
+
+// ════════════════════════════════════════════════════════════════════════════
+// Thresholds
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Recognition thresholds for the hand-tracking sources (mm, mm/s, ms).
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    pub clap_dist_mm:         f32,
+    pub unclap_dist_mm:       f32,
+    pub pull_velocity_min:    f32,
+    pub pull_step_divisor:    f32,
+    pub pull_cooldown_ms:     u64,
+    pub twist_height_mm:      f32,
+    pub twist_hold_frames:    u32,
+    pub scissors_hold_frames: u32,
+    pub scissors_cooldown_ms: u64,
+    /// Finger extension (0–1) above which a finger counts as straight.
+    pub scissors_extended:    f32,
+    /// Angle (radians) between index and middle finger for a scissors pose.
+    pub scissors_spread:      f32,
+    pub swipe_velocity_min:   f32,
+    pub swipe_cooldown_ms:    u64,
+    pub fist_hold_frames:     u32,
+    pub pinch_dist_mm:        f32,
+    pub pinch_zoom_step_mm:   f32,
+    pub stretch_height_mm:    f32,
+    pub stretch_min_mm:       f32,
+    pub stretch_max_mm:       f32,
+    pub stretch_min_bpm:      u32,
+    pub stretch_max_bpm:      u32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            clap_dist_mm:         80.0,
+            unclap_dist_mm:       150.0,
+            pull_velocity_min:    150.0,
+            pull_step_divisor:    100.0,
+            pull_cooldown_ms:     80,
+            twist_height_mm:      40.0,
+            twist_hold_frames:    6,
+            scissors_hold_frames: 4,
+            scissors_cooldown_ms: 500,
+            scissors_extended:    0.4,
+            scissors_spread:      0.35,
+            swipe_velocity_min:   900.0,
+            swipe_cooldown_ms:    600,
+            fist_hold_frames:     4,
+            pinch_dist_mm:        30.0,
+            pinch_zoom_step_mm:   30.0,
+            stretch_height_mm:    300.0,
+            stretch_min_mm:       100.0,
+            stretch_max_mm:       500.0,
+            stretch_min_bpm:      60,
+            stretch_max_bpm:      180,
+        }
+    }
+}
+
+impl Thresholds {
+    pub fn pull_cooldown(&self) -> Duration { Duration::from_millis(self.pull_cooldown_ms) }
+    pub fn scissors_cooldown(&self) -> Duration { Duration::from_millis(self.scissors_cooldown_ms) }
+    pub fn swipe_cooldown(&self) -> Duration { Duration::from_millis(self.swipe_cooldown_ms) }
+
+    /// Palm distance (mm) during a stretch → tempo (BPM).
+    pub fn stretch_bpm(&self, dist_mm: f32) -> u32 {
+        let span = (self.stretch_max_mm - self.stretch_min_mm).max(1.0);
+        let t = ((dist_mm - self.stretch_min_mm) / span).clamp(0.0, 1.0);
+        let (lo, hi) = (self.stretch_min_bpm as f32, self.stretch_max_bpm as f32);
+        (lo + t * (hi - lo)).round() as u32
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Gesture and key bindings
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Action for each discrete hand gesture.  Pulls always advance their own
+/// hand's stream and pinches always zoom; `swipe_*` are right-hand swipes.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GestureBindings {
+    pub twist:            SimKey,
+    pub clap:             SimKey,
+    pub unclap:           SimKey,
+    pub scissors:         SimKey,
+    pub swipe_left:       SimKey,
+    pub swipe_right:      SimKey,
+    pub left_swipe_left:  SimKey,
+    pub left_swipe_right: SimKey,
+    pub fist:             SimKey,
+}
+
+impl Default for GestureBindings {
+    fn default() -> Self {
+        GestureBindings {
+            twist:            SimKey::Twist,
+            clap:             SimKey::Clap,
+            unclap:           SimKey::Unclap,
+            scissors:         SimKey::Scissors,
+            swipe_left:       SimKey::InstrumentPrev,
+            swipe_right:      SimKey::InstrumentNext,
+            left_swipe_left:  SimKey::ScrubBack,
+            left_swipe_right: SimKey::ScrubForward,
+            fist:             SimKey::Pause,
+        }
+    }
+}
+
+/// Keyboard bindings for simulation mode: key name → action.
+pub fn default_keys() -> BTreeMap<String, SimKey> {
+    [
+        ("A", SimKey::PullLeft),
+        ("D", SimKey::PullRight),
+        ("T", SimKey::Twist),
+        ("Space", SimKey::Clap),
+        ("Escape", SimKey::Unclap),
+        ("S", SimKey::Scissors),
+        ("W", SimKey::Save),
+        ("P", SimKey::Panic),
+        ("O", SimKey::NextPort),
+        ("[", SimKey::InstrumentPrev),
+        ("]", SimKey::InstrumentNext),
+        ("PageUp", SimKey::InstrumentPrev),
+        ("PageDown", SimKey::InstrumentNext),
+        ("End", SimKey::Panic),
+        ("Left", SimKey::TempoDown),
+        ("Right", SimKey::TempoUp),
+        ("F", SimKey::Pause),
+        ("=", SimKey::ZoomIn),
+        ("-", SimKey::ZoomOut),
+        ("NumPad+", SimKey::ZoomIn),
+        ("NumPad-", SimKey::ZoomOut),
+        (",", SimKey::ScrubBack),
+        (".", SimKey::ScrubForward),
+        ("Up", SimKey::TrayUp),
+        ("Down", SimKey::TrayDown),
+        ("Enter", SimKey::Splice),
+        ("R", SimKey::TrayRename),
+        ("Delete", SimKey::TrayDelete),
+        ("F5", SimKey::SaveSession),
+        ("U", SimKey::Undo),
+        ("Ctrl+Z", SimKey::Undo),
+        ("Tab", SimKey::NextPair),
+        ("F10", SimKey::DetachTray),
+        ("F11", SimKey::Fullscreen),
+        ("V", SimKey::SpiralView),
+        ("H", SimKey::Histogram),
+        ("F12", SimKey::Screenshot),
+        ("Q", SimKey::Quit),
+    ].into_iter().map(|(k, a)| (k.to_string(), a)).collect()
+}
+
+/// Physical keyboard layout, choosing where the default keys sit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Azerty,
+}
+
+impl KeyboardLayout {
+    /// Default keys for this layout.
+    pub fn keys(self) -> BTreeMap<String, SimKey> {
+        let mut keys = default_keys();
+        if self == KeyboardLayout::Azerty {
+            for (a, b) in [("A", "Q"), ("W", "Z"), (".", ";")] {
+                let (was_a, was_b) = (keys.remove(a), keys.remove(b));
+                if let Some(action) = was_a { keys.insert(b.to_string(), action); }
+                if let Some(action) = was_b { keys.insert(a.to_string(), action); }
+            }
+        }
+        keys
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Timbre control
+// ════════════════════════════════════════════════════════════════════════════
+
+/// The hand whose roll controls the timbre.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimbreHand {
+    Left,
+    #[default]
+    Right,
+    Off,
+}
+
+/// Continuous timbre control: a hand's roll, sent as a MIDI controller
+/// while playing.  A flat palm is the middle of the controller's range.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimbreControl {
+    pub hand:           TimbreHand,
+    /// Controller number.
+    pub cc:             u8,
+    /// Roll either way (degrees) that reaches the controller's ends.
+    pub roll_range_deg: f32,
+}
+
+impl Default for TimbreControl {
+    fn default() -> Self {
+        TimbreControl { hand: TimbreHand::Right, cc: 74, roll_range_deg: 60.0 }
+    }
+}
+
+impl TimbreControl {
+    /// Palm roll (radians) → timbre level 0.0–1.0.
+    pub fn level(&self, roll: f32) -> f32 {
+        let range = self.roll_range_deg.max(1.0);
+        ((roll.to_degrees() / range + 1.0) / 2.0).clamp(0.0, 1.0)
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// GestureConfig
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Everything read from the gesture mapping file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GestureConfig {
+    pub keyboard:   KeyboardLayout,
+    pub thresholds: Thresholds,
+    pub gestures:   GestureBindings,
+    pub timbre:     TimbreControl,
+    pub keys:       BTreeMap<String, SimKey>,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        GestureConfig {
+            keyboard:   KeyboardLayout::default(),
+            thresholds: Thresholds::default(),
+            gestures:   GestureBindings::default(),
+            timbre:     TimbreControl::default(),
+            keys:       default_keys(),
+        }
+    }
+}
+
+/// On-disk layout; `[keys]` is merged over the layout's defaults.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    keyboard:   KeyboardLayout,
+    #[serde(default)]
+    thresholds: Thresholds,
+    #[serde(default)]
+    gestures:   GestureBindings,
+    #[serde(default)]
+    timbre:     TimbreControl,
+    #[serde(default)]
+    keys:       BTreeMap<String, SimKey>,
+}
+
+impl GestureConfig {
+    /// Parse a config from TOML text.
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        let file: ConfigFile = toml::from_str(text).map_err(|e| e.to_string())?;
+        let mut keys = file.keyboard.keys();
+        keys.extend(file.keys);
+        Ok(GestureConfig {
+            keyboard:   file.keyboard,
+            thresholds: file.thresholds,
+            gestures:   file.gestures,
+            timbre:     file.timbre,
+            keys,
+        })
+    }
+
+    /// Read a config file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_toml(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// The config as TOML, every field written out.
+    pub fn to_toml(&self) -> String {
+        let file = ConfigFile {
+            keyboard:   self.keyboard,
+            thresholds: self.thresholds.clone(),
+            gestures:   self.gestures.clone(),
+            timbre:     self.timbre.clone(),
+            keys:       self.keys.clone(),
+        };
+        toml::to_string_pretty(&file).expect("gesture config serializes")
+    }
+
+    /// Write the config file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_toml()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_file_gives_defaults() {
+        assert_eq!(GestureConfig::from_toml("").unwrap(), GestureConfig::default());
+    }
+
+    #[test]
+    fn overrides_merge_over_defaults() {
+        let cfg = GestureConfig::from_toml(r#"
+            [thresholds]
+            clap_dist_mm = 60.0
+            twist_hold_frames = 3
+
+            [gestures]
+            swipe_right = "next_port"
+
+            [keys]
+            Z = "twist"
+            "[" = "panic"
+        "#).unwrap();
+        assert_eq!(cfg.thresholds.clap_dist_mm, 60.0);
+        assert_eq!(cfg.thresholds.twist_hold_frames, 3);
+        assert_eq!(cfg.thresholds.unclap_dist_mm, 150.0);
+        assert_eq!(cfg.gestures.swipe_right, SimKey::NextPort);
+        assert_eq!(cfg.gestures.swipe_left, SimKey::InstrumentPrev);
+        assert_eq!(cfg.keys["Z"], SimKey::Twist);
+        assert_eq!(cfg.keys["["], SimKey::Panic);
+        assert_eq!(cfg.keys["T"], SimKey::Twist);
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let mut cfg = GestureConfig::default();
+        cfg.thresholds.pull_velocity_min = 210.5;
+        cfg.keys.insert("Z".to_string(), SimKey::Save);
+        assert_eq!(GestureConfig::from_toml(&cfg.to_toml()).unwrap(), cfg);
+    }
+
+    #[test]
+    fn azerty_moves_the_defaults() {
+        let cfg = GestureConfig::from_toml("keyboard = \"azerty\"\n[keys]\nX = \"twist\"").unwrap();
+        assert_eq!(cfg.keyboard, KeyboardLayout::Azerty);
+        assert_eq!(cfg.keys["Q"], SimKey::PullLeft);
+        assert_eq!(cfg.keys["A"], SimKey::Quit);
+        assert_eq!(cfg.keys["Z"], SimKey::Save);
+        assert_eq!(cfg.keys[";"], SimKey::ScrubForward);
+        assert_eq!(cfg.keys["X"], SimKey::Twist);
+        assert!(!cfg.keys.contains_key("W") && !cfg.keys.contains_key("."));
+        assert_eq!(cfg.keys.len(), default_keys().len() + 1);
+        assert_eq!(GestureConfig::from_toml(&cfg.to_toml()).unwrap(), cfg);
+    }
+
+    #[test]
+    fn timbre_follows_the_roll() {
+        let cfg = GestureConfig::from_toml("[timbre]\nhand = \"left\"\ncc = 11").unwrap();
+        assert_eq!((cfg.timbre.hand, cfg.timbre.cc, cfg.timbre.roll_range_deg), (TimbreHand::Left, 11, 60.0));
+        let t = TimbreControl::default();
+        assert_eq!(t.level(0.0), 0.5);
+        assert_eq!(t.level(30f32.to_radians()), 0.75);
+        assert_eq!(t.level(-90f32.to_radians()), 0.0);
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        assert!(GestureConfig::from_toml("[thresholds]\nclap_dist = 1.0").is_err());
+        assert!(GestureConfig::from_toml("[keys]\nZ = \"dance\"").is_err());
+    }
+}
@@ -0,0 +1,80 @@
+//! The app's error type.
+//!
+//! [`AppError`] sorts failures by what the performer can do about them:
+//! fix a setting, check a file, or plug in (or restart) a device.  Errors
+//! from the stream and MIDI libraries convert into it, so `?` works across
+//! them.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use dual_spigot::SpigotError;
+use spigot_midi::MidiError;
+
+/// What went wrong in the app, its player or its launch settings.
+#[derive(Debug)]
+pub enum AppError {
+    /// A setting, flag or file entry that doesn't make sense.
+    Parameter(String),
+    /// Reading or writing a file (`path` is empty if it isn't known).
+    Io { path: PathBuf, source: io::Error },
+    /// The window, an audio device or a MIDI port.
+    Device(String),
+    /// The playback thread has ended, so commands go nowhere.
+    PlayerStopped,
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Parameter(msg) => f.write_str(msg),
+            AppError::Io { path, source } if path.as_os_str().is_empty() => write!(f, "{}", source),
+            AppError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            AppError::Device(msg)    => f.write_str(msg),
+            AppError::PlayerStopped  => f.write_str("the MIDI player has stopped"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Io { source, .. } => Some(source),
+            _                           => None,
+        }
+    }
+}
+
+impl From<MidiError> for AppError {
+    fn from(e: MidiError) -> Self {
+        match e {
+            MidiError::Io(source)  => AppError::Io { path: PathBuf::new(), source },
+            MidiError::Device(msg) => AppError::Device(msg),
+            other                  => AppError::Parameter(other.to_string()),
+        }
+    }
+}
+
+impl From<SpigotError> for AppError {
+    fn from(e: SpigotError) -> Self { AppError::Parameter(e.to_string()) }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn library_errors_keep_their_kind() {
+        let device: AppError = MidiError::Device("no port".into()).into();
+        assert!(matches!(device, AppError::Device(_)));
+        let scale: AppError = "bebop".parse::<spigot_midi::Scale>().unwrap_err().into();
+        assert_eq!(scale.to_string(), "unknown scale 'bebop'");
+        let io = AppError::Io { path: "tray.toml".into(), source: io::ErrorKind::NotFound.into() };
+        assert!(io.to_string().starts_with("tray.toml: "));
+    }
+}
@@ -8,6 +8,10 @@ use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
 use std::time::{Duration, Instant};
 
+use serde::{Deserialize, Serialize};
+
+use crate::config::{GestureBindings, GestureConfig, Thresholds, TimbreControl, TimbreHand};
+
 // ════════════════════════════════════════════════════════════════════════════
 // GestureEvent
 // ════════════════════════════════════════════════════════════════════════════
@@ -35,6 +39,101 @@ pub enum GestureEvent {
     /// The `name` is collected interactively from the user.
     Scissors { name: String },
 
+    /// Write everything played so far to a MIDI file.
+    SavePerformance,
+
+    /// Write the whole performance setup to the session file.
+    SaveSession,
+
+    /// Take back the last pull, twist, scrub or splice.
+    Undo,
+
+    /// Replay the last undone gesture.
+    Redo,
+
+    /// Stop playback and silence every MIDI channel.
+    Panic,
+
+    /// Switch playback to the next MIDI output port.
+    NextPort,
+
+    /// Step the GM program by `step` (wrapping around 0–127).
+    CycleInstrument { step: i8 },
+
+    /// Expressive level 0.0–1.0 from hand height, setting the velocity of
+    /// upcoming notes.
+    Expression { value: f32 },
+
+    /// Timbre level 0.0–1.0 from a hand's roll, sent on the configured MIDI
+    /// controller while playing (see [`TimbreControl`]).
+    Timbre { level: f32 },
+
+    /// A key played on the MIDI keyboard in jam mode, to be echoed and
+    /// answered (velocity 0 when it's let go).
+    JamNote { pitch: u8, velocity: u8 },
+
+    /// Set the playback tempo.
+    Tempo { bpm: u32 },
+
+    /// Speed the tempo up (or down, if negative) by `step` BPM.
+    NudgeTempo { step: i32 },
+
+    /// Pause playback, or resume it if paused.  The stitch is kept.
+    Pause,
+
+    /// Scale the ribbon patch width by `factor`.
+    Zoom { factor: f32 },
+
+    /// Move both streams `steps` digits back (negative) or forward.
+    Scrub { steps: isize },
+
+    /// Move the tray selection `step` entries down (up if negative).
+    SelectSnippet { step: i8 },
+
+    /// Select tray entry `index` (e.g. clicked with the mouse).
+    PickSnippet { index: usize },
+
+    /// Show the digit and stream position of patch `index` on the right
+    /// ribbon (or the left), e.g. clicked with the mouse.
+    InspectPatch { right: bool, index: usize },
+
+    /// Mark patches `from..to` of the pulled ribbons as the range the next
+    /// snip takes, e.g. dragged across with the mouse.
+    MarkPatches { from: usize, to: usize },
+
+    /// Move the selected tray entry `step` places down (up if negative).
+    MoveSnippet { step: i8 },
+
+    /// Rename the selected tray entry.  An empty `name` means the app
+    /// should ask for one.
+    RenameSnippet { name: String },
+
+    /// Remove the selected tray entry.
+    DeleteSnippet,
+
+    /// Splice the selected tray snippet into the performance at the next
+    /// beat.
+    Splice,
+
+    /// Hand the pull gestures to the next ribbon pair.
+    NextPair,
+
+    /// Switch the window between borderless fullscreen and a normal window.
+    Fullscreen,
+
+    /// Switch to the spiral view and back.
+    SpiralView,
+
+    /// Show or hide the digit histogram panel.
+    Histogram,
+
+    /// Move the tray, piano roll and histogram to a window of their own,
+    /// or back beside the ribbons.
+    DetachTray,
+
+    /// Save the current frame as a PNG.
+    Screenshot,
+
     /// Quit the application.
     Quit,
 }
@@ -55,63 +154,359 @@ pub trait GestureSource: Send + 'static {
 /// Spawn a gesture source on its own thread and return the receiving end.
 pub fn spawn_gesture_source<G: GestureSource>(source: G) -> Receiver<GestureEvent> {
     let (tx, rx) = mpsc::channel();
-    thread::spawn(move || Box::new(source).run(tx));
+    spawn_gesture_source_into(source, tx);
     rx
 }
 
+/// Spawn a gesture source that sends into an existing channel, so several
+/// sources can feed one receiver.
+pub fn spawn_gesture_source_into<G: GestureSource>(source: G, tx: Sender<GestureEvent>) {
+    thread::spawn(move || Box::new(source).run(tx));
+}
+
 // ════════════════════════════════════════════════════════════════════════════
-// LeapGestureSource — real hardware (feature = "leap")
+// HandRecognizer — shared by every hand-tracking source
 // ════════════════════════════════════════════════════════════════════════════
 
-/// Gesture source backed by a real LeapMotion controller.
-///
-/// Requires the `leap` feature flag and the LeapC shared library installed.
+/// One tracked hand in LeapMotion coordinates: millimetres above the
+/// sensor, X to the user's right, Y up, Z toward the user.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TrackedHand {
+    /// Palm centre (mm).
+    pub palm:     [f32; 3],
+    /// Palm velocity (mm/s).
+    pub velocity: [f32; 3],
+    pub pose:     HandPose,
+    /// Palm roll (radians): 0 flat, positive as the palm turns to the right.
+    pub roll:     f32,
+}
+
+/// Finger pose of a tracked hand.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HandPose {
+    #[default]
+    Open,
+    /// Index and middle extended and spread, ring and pinky curled.
+    Scissors,
+    /// Every finger curled.
+    Fist,
+    /// Thumb and index tips touching.
+    Pinch,
+}
+
+/// Turns a stream of tracked-hand frames into [`GestureEvent`]s.
 ///
 /// # Algorithm
 ///
-/// Each polling frame we examine hand palm positions and velocities:
+/// Each frame we examine hand palm positions and velocities, against the
+/// [`Thresholds`] from the gesture config:
 ///
-/// * **Pull**: palm velocity along the Z-axis (toward camera) > threshold.
-///   Steps = floor(|vz| / STEP_DIVISOR), capped to avoid jumps.
+/// * **Pull**: palm velocity along the Z-axis (toward the user) >
+///   `pull_velocity_min`.  Steps = floor(|vz| / `pull_step_divisor`),
+///   capped to avoid jumps.
 /// * **Twist**: left-hand palm Y > right-hand palm Y (left over right) or
-///   vice-versa, sustained for TWIST_FRAMES consecutive frames.
-/// * **Clap**: inter-palm distance < CLAP_DIST_MM and decreasing.
-/// * **Unclap**: inter-palm distance > UNCLAP_DIST_MM after a clap.
-/// * **Scissors**: index and middle fingers extended, others curled,
-///   with spread angle > SCISSORS_ANGLE_DEG, sustained for SCISSORS_FRAMES.
+///   vice-versa by `twist_height_mm`, sustained for `twist_hold_frames`.
+/// * **Clap**: inter-palm distance < `clap_dist_mm`.
+/// * **Unclap**: inter-palm distance > `unclap_dist_mm` after a clap,
+///   unless stretching.
+/// * **Stretch**: both palms open and above `stretch_height_mm`.  The
+///   distance between them sets the tempo, from `stretch_min_bpm` at
+///   `stretch_min_mm` to `stretch_max_bpm` at `stretch_max_mm`, sent each
+///   time it changes — conduct with the hands held high, and lower them
+///   before pulling apart to unclap.
+/// * **Scissors**: the hand's scissors pose sustained for
+///   `scissors_hold_frames`.  A snip carries an empty name, so the app asks
+///   for one.
+/// * **Expression**: with every pull, the pulling palm's height (Y) is sent
+///   as an expression level — a high hand plays loud, a low one soft.
+/// * **Swipe**: palm velocity along the X-axis > `swipe_velocity_min`,
+///   rightward or leftward, with either hand.
+/// * **Fist**: a fist on either hand sustained for `fist_hold_frames`.
+/// * **Pinch** (either hand): while pinched, every `pinch_zoom_step_mm` the
+///   hand rises zooms the ribbons in, and every step it falls zooms out.
+/// * **Roll** (the [`TimbreControl`] hand): the palm's roll is sent as a
+///   timbre level whenever it moves a controller step, a flat palm sitting
+///   mid-range.
+///
+/// Twist, clap, unclap, scissors, fist and the swipes send whichever action
+/// the config's [`GestureBindings`] give them (by default, right-hand swipes
+/// step through the instruments, left-hand swipes scrub the streams and a
+/// fist pauses).
+pub struct HandRecognizer {
+    thresholds:    Thresholds,
+    bindings:      GestureBindings,
+    timbre:        TimbreControl,
+    /// Controller value (0–127) of the last timbre level sent.
+    timbre_value:  u8,
+    clapped:       bool,
+    twist_counter: u32,
+    /// Last tempo sent while stretching; `None` when not stretching.
+    stretch_bpm:   Option<u32>,
+    scissors_l:    u32,
+    scissors_r:    u32,
+    fist_l:        u32,
+    fist_r:        u32,
+    /// Palm height where the current pinch last zoomed, per hand.
+    pinch_l:       Option<f32>,
+    pinch_r:       Option<f32>,
+    last_pull_l:   Option<Instant>,
+    last_pull_r:   Option<Instant>,
+    last_scissors: Option<Instant>,
+    last_swipe:    Option<Instant>,
+    last_swipe_l:  Option<Instant>,
+}
+
+impl Default for HandRecognizer {
+    fn default() -> Self { Self::new(&GestureConfig::default()) }
+}
+
+impl HandRecognizer {
+    pub fn new(config: &GestureConfig) -> Self {
+        HandRecognizer {
+            thresholds:    config.thresholds.clone(),
+            bindings:      config.gestures.clone(),
+            timbre:        config.timbre.clone(),
+            timbre_value:  64,
+            clapped:       false,
+            twist_counter: 0,
+            stretch_bpm:   None,
+            scissors_l:    0,
+            scissors_r:    0,
+            fist_l:        0,
+            fist_r:        0,
+            pinch_l:       None,
+            pinch_r:       None,
+            last_pull_l:   None,
+            last_pull_r:   None,
+            last_scissors: None,
+            last_swipe:    None,
+            last_swipe_l:  None,
+        }
+    }
+
+    /// Feed one frame (taken at `now`) and return the gestures it completes.
+    pub fn update(
+        &mut self,
+        left:  Option<&TrackedHand>,
+        right: Option<&TrackedHand>,
+        now:   Instant,
+    ) -> Vec<GestureEvent> {
+        let t = &self.thresholds;
+        let mut events = Vec::new();
+        let cooled = |last: Option<Instant>, cooldown: Duration| {
+            last.is_none_or(|t| now.duration_since(t) > cooldown)
+        };
+
+        // ── Clap / Unclap / Stretch / Twist ───────────────────────────────
+        if let (Some(lh), Some(rh)) = (left, right) {
+            let [lx, ly, lz] = lh.palm;
+            let [rx, ry, rz] = rh.palm;
+            let dist = ((lx - rx).powi(2) + (ly - ry).powi(2) + (lz - rz).powi(2)).sqrt();
+            let stretching = lh.pose == HandPose::Open && rh.pose == HandPose::Open
+                && ly.min(ry) > t.stretch_height_mm;
+
+            if !self.clapped && dist < t.clap_dist_mm {
+                self.clapped = true;
+                events.push(self.bindings.clap.event());
+            } else if self.clapped && !stretching && dist > t.unclap_dist_mm {
+                self.clapped = false;
+                events.push(self.bindings.unclap.event());
+            }
+
+            if stretching {
+                let bpm = t.stretch_bpm(dist);
+                if self.stretch_bpm != Some(bpm) {
+                    self.stretch_bpm = Some(bpm);
+                    events.push(GestureEvent::Tempo { bpm });
+                }
+            } else {
+                self.stretch_bpm = None;
+            }
+
+            // Left hand Y > Right hand Y means left is "over" right.
+            if (ly - ry).abs() > t.twist_height_mm {
+                self.twist_counter += 1;
+                if self.twist_counter == t.twist_hold_frames {
+                    events.push(self.bindings.twist.event());
+                }
+            } else {
+                self.twist_counter = 0;
+            }
+        } else {
+            self.twist_counter = 0;
+            self.stretch_bpm = None;
+        }
+
+        // ── Pull Left, Swipe ──────────────────────────────────────────────
+        if let Some(lh) = left {
+            let vz = lh.velocity[2];
+            if vz > t.pull_velocity_min && cooled(self.last_pull_l, t.pull_cooldown()) {
+                self.last_pull_l = Some(now);
+                let (steps, velocity) = pull_amount(vz, t.pull_step_divisor);
+                events.push(GestureEvent::PullLeft { steps, velocity });
+                events.push(GestureEvent::Expression { value: height_to_expression(lh.palm[1]) });
+            }
+            // Swipe sideways (by default, scrubs the streams)
+            let vx = lh.velocity[0];
+            if vx.abs() > t.swipe_velocity_min && cooled(self.last_swipe_l, t.swipe_cooldown()) {
+                self.last_swipe_l = Some(now);
+                let action = if vx > 0.0 {
+                    self.bindings.left_swipe_right
+                } else {
+                    self.bindings.left_swipe_left
+                };
+                events.push(action.event());
+            }
+        }
+
+        // ── Pull Right, Swipe ─────────────────────────────────────────────
+        if let Some(rh) = right {
+            let vz = rh.velocity[2];
+            if vz > t.pull_velocity_min && cooled(self.last_pull_r, t.pull_cooldown()) {
+                self.last_pull_r = Some(now);
+                let (steps, velocity) = pull_amount(vz, t.pull_step_divisor);
+                events.push(GestureEvent::PullRight { steps, velocity });
+                events.push(GestureEvent::Expression { value: height_to_expression(rh.palm[1]) });
+            }
+            // Swipe sideways (by default, changes instrument)
+            let vx = rh.velocity[0];
+            if vx.abs() > t.swipe_velocity_min && cooled(self.last_swipe, t.swipe_cooldown()) {
+                self.last_swipe = Some(now);
+                let action = if vx > 0.0 { self.bindings.swipe_right } else { self.bindings.swipe_left };
+                events.push(action.event());
+            }
+        }
+
+        // ── Scissors on either hand ───────────────────────────────────────
+        for (hand, held) in [(left, &mut self.scissors_l), (right, &mut self.scissors_r)] {
+            if hand.is_some_and(|h| h.pose == HandPose::Scissors) {
+                *held += 1;
+                if *held == t.scissors_hold_frames
+                    && cooled(self.last_scissors, t.scissors_cooldown())
+                {
+                    self.last_scissors = Some(now);
+                    events.push(self.bindings.scissors.event());
+                }
+            } else {
+                *held = 0;
+            }
+        }
+
+        // ── Fist on either hand ───────────────────────────────────────────
+        for (hand, held) in [(left, &mut self.fist_l), (right, &mut self.fist_r)] {
+            if hand.is_some_and(|h| h.pose == HandPose::Fist) {
+                *held += 1;
+                if *held == t.fist_hold_frames {
+                    events.push(self.bindings.fist.event());
+                }
+            } else {
+                *held = 0;
+            }
+        }
+
+        // ── Pinch zoom on either hand ─────────────────────────────────────
+        for (hand, anchor) in [(left, &mut self.pinch_l), (right, &mut self.pinch_r)] {
+            match hand.filter(|h| h.pose == HandPose::Pinch) {
+                Some(h) => {
+                    let y = h.palm[1];
+                    let from = *anchor.get_or_insert(y);
+                    if (y - from).abs() >= t.pinch_zoom_step_mm {
+                        *anchor = Some(y);
+                        let key = if y > from { SimKey::ZoomIn } else { SimKey::ZoomOut };
+                        events.push(key.event());
+                    }
+                }
+                None => *anchor = None,
+            }
+        }
+
+        // ── Roll → timbre ─────────────────────────────────────────────────
+        let rolling = match self.timbre.hand {
+            TimbreHand::Left  => left,
+            TimbreHand::Right => right,
+            TimbreHand::Off   => None,
+        };
+        if let Some(h) = rolling {
+            let level = self.timbre.level(h.roll);
+            let value = (level * 127.0).round() as u8;
+            if value != self.timbre_value {
+                self.timbre_value = value;
+                events.push(GestureEvent::Timbre { level });
+            }
+        }
+
+        events
+    }
+}
+
+/// Pull speed (mm/s) → digits to advance and normalised velocity.
+fn pull_amount(vz: f32, step_divisor: f32) -> (usize, f32) {
+    let steps = ((vz / step_divisor) as usize).clamp(1, 20);
+    (steps, (vz / 600.0).min(1.0))
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// LeapGestureSource — real hardware (feature = "leap")
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Gesture source backed by a real LeapMotion controller.
+///
+/// Requires the `leap` feature flag and the LeapC shared library installed.
+/// Each tracking frame is fed to a [`HandRecognizer`] built from `config`.
 #[cfg(feature = "leap")]
-pub struct LeapGestureSource;
+pub struct LeapGestureSource {
+    pub config: GestureConfig,
+}
 
 #[cfg(feature = "leap")]
 impl GestureSource for LeapGestureSource {
     fn run(self: Box<Self>, tx: Sender<GestureEvent>) {
-        use leaprs::*;
+        let mut recognizer = HandRecognizer::new(&self.config);
+        for (left, right) in LeapFrames::open(self.config.thresholds.clone()) {
+            let events = recognizer.update(left.as_ref(), right.as_ref(), Instant::now());
+            if !events.into_iter().all(|e| tx.send(e).is_ok()) { return; }
+        }
+    }
+}
 
-        // Thresholds (empirically tuned)
-        const CLAP_DIST:       f32 = 80.0;   // mm — hands this close = clap
-        const UNCLAP_DIST:     f32 = 150.0;  // mm — hands this far  = unclap
-        const PULL_VZ_MIN:     f32 = 150.0;  // mm/s — minimum pull velocity
-        const STEP_DIVISOR:    f32 = 100.0;  // mm/s per step
-        const SCISSORS_ANGLE:  f32 = 25.0;   // degrees between index/middle
-        const TWIST_HOLD:      u32 = 6;      // frames to confirm twist
-        const SCISSORS_HOLD:   u32 = 4;      // frames to confirm scissors
-        const PULL_COOLDOWN:   Duration = Duration::from_millis(80);
-        const SCISSORS_COOLDOWN: Duration = Duration::from_millis(500);
+/// Tracked hands, one `(left, right)` pair per LeapMotion tracking frame
+/// with at least one hand in view.  Never ends.
+#[cfg(feature = "leap")]
+pub struct LeapFrames {
+    connection: leaprs::Connection,
+    thresholds: Thresholds,
+}
 
+#[cfg(feature = "leap")]
+impl LeapFrames {
+    /// Connect to the controller; `thresholds` judge the hand poses.
+    pub fn open(thresholds: Thresholds) -> Self {
+        use leaprs::*;
         let mut connection = Connection::create(ConnectionConfig::default())
             .expect("Failed to open LeapC connection");
         connection.open().expect("Failed to open LeapMotion device");
+        LeapFrames { connection, thresholds }
+    }
+}
 
-        let mut clappped      = false;
-        let mut twist_counter = 0u32;
-        let mut scissors_l    = 0u32;
-        let mut scissors_r    = 0u32;
-        let mut last_pull_l   = Instant::now() - PULL_COOLDOWN;
-        let mut last_pull_r   = Instant::now() - PULL_COOLDOWN;
-        let mut last_scissors = Instant::now() - SCISSORS_COOLDOWN;
+#[cfg(feature = "leap")]
+impl Iterator for LeapFrames {
+    type Item = (Option<TrackedHand>, Option<TrackedHand>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use leaprs::*;
+        let tracked = |h: &Hand| {
+            let (p, v, n) = (h.palm().position(), h.palm().velocity(), h.palm().normal());
+            TrackedHand {
+                palm:     [p.x, p.y, p.z],
+                velocity: [v.x, v.y, v.z],
+                pose:     hand_pose(h, &self.thresholds),
+                roll:     n.x.atan2(-n.y),
+            }
+        };
 
         loop {
-            let msg = match connection.poll(100) {
+            let msg = match self.connection.poll(100) {
                 Ok(m)  => m,
                 Err(_) => continue,
             };
@@ -120,108 +515,67 @@ impl GestureSource for LeapGestureSource {
                 let hands: Vec<_> = frame.hands().collect();
                 if hands.is_empty() { continue; }
 
-                // ── separate left/right ───────────────────────────────────
-                let left  = hands.iter().find(|h| h.hand_type() == HandType::Left);
-                let right = hands.iter().find(|h| h.hand_type() == HandType::Right);
-
-                // ── Clap / Unclap ─────────────────────────────────────────
-                if let (Some(lh), Some(rh)) = (left, right) {
-                    let lp = lh.palm().position();
-                    let rp = rh.palm().position();
-                    let dx = lp.x - rp.x;
-                    let dy = lp.y - rp.y;
-                    let dz = lp.z - rp.z;
-                    let dist = (dx*dx + dy*dy + dz*dz).sqrt();
-
-                    if !clappped && dist < CLAP_DIST {
-                        clappped = true;
-                        let _ = tx.send(GestureEvent::Clap);
-                    } else if clappped && dist > UNCLAP_DIST {
-                        clappped = false;
-                        let _ = tx.send(GestureEvent::Unclap);
-                    }
-
-                    // ── Twist ─────────────────────────────────────────────
-                    // Left hand Y > Right hand Y means left is "over" right.
-                    let lh_over_rh = lp.y > rp.y + 40.0;
-                    let rh_over_lh = rp.y > lp.y + 40.0;
-                    if lh_over_rh || rh_over_lh {
-                        twist_counter += 1;
-                        if twist_counter == TWIST_HOLD {
-                            let _ = tx.send(GestureEvent::Twist);
-                        }
-                    } else {
-                        twist_counter = 0;
-                    }
-                } else {
-                    twist_counter = 0;
-                }
-
-                // ── Pull Left ─────────────────────────────────────────────
-                if let Some(lh) = left {
-                    let vz = lh.palm().velocity().z;
-                    if vz > PULL_VZ_MIN && last_pull_l.elapsed() > PULL_COOLDOWN {
-                        last_pull_l = Instant::now();
-                        let steps = ((vz / STEP_DIVISOR) as usize).max(1).min(20);
-                        let vel   = (vz / 600.0).min(1.0);
-                        let _ = tx.send(GestureEvent::PullLeft { steps, velocity: vel });
-                    }
-                    // Scissors on left hand
-                    if is_scissors(lh) {
-                        scissors_l += 1;
-                        if scissors_l == SCISSORS_HOLD
-                            && last_scissors.elapsed() > SCISSORS_COOLDOWN
-                        {
-                            last_scissors = Instant::now();
-                            let name = prompt_snippet_name();
-                            let _ = tx.send(GestureEvent::Scissors { name });
-                        }
-                    } else {
-                        scissors_l = 0;
-                    }
-                }
-
-                // ── Pull Right ────────────────────────────────────────────
-                if let Some(rh) = right {
-                    let vz = rh.palm().velocity().z;
-                    if vz > PULL_VZ_MIN && last_pull_r.elapsed() > PULL_COOLDOWN {
-                        last_pull_r = Instant::now();
-                        let steps = ((vz / STEP_DIVISOR) as usize).max(1).min(20);
-                        let vel   = (vz / 600.0).min(1.0);
-                        let _ = tx.send(GestureEvent::PullRight { steps, velocity: vel });
-                    }
-                    // Scissors on right hand
-                    if is_scissors(rh) {
-                        scissors_r += 1;
-                        if scissors_r == SCISSORS_HOLD
-                            && last_scissors.elapsed() > SCISSORS_COOLDOWN
-                        {
-                            last_scissors = Instant::now();
-                            let name = prompt_snippet_name();
-                            let _ = tx.send(GestureEvent::Scissors { name });
-                        }
-                    } else {
-                        scissors_r = 0;
-                    }
-                }
+                let left  = hands.iter().find(|h| h.hand_type() == HandType::Left).map(tracked);
+                let right = hands.iter().find(|h| h.hand_type() == HandType::Right).map(tracked);
+                return Some((left, right));
             }
         }
     }
 }
 
+/// Palm height above the controller (mm) → expression level 0.0–1.0.
+///
+/// The comfortable playing range, about 100–400 mm, spans the whole scale.
+pub fn height_to_expression(y_mm: f32) -> f32 {
+    const LOW:  f32 = 100.0;
+    const HIGH: f32 = 400.0;
+    ((y_mm - LOW) / (HIGH - LOW)).clamp(0.0, 1.0)
+}
+
+/// Classify the hand's finger pose.
+#[cfg(feature = "leap")]
+fn hand_pose(hand: &leaprs::Hand, t: &Thresholds) -> HandPose {
+    if is_pinch(hand, t) {
+        HandPose::Pinch
+    } else if is_scissors(hand, t) {
+        HandPose::Scissors
+    } else if is_fist(hand) {
+        HandPose::Fist
+    } else {
+        HandPose::Open
+    }
+}
+
+/// Returns true if every finger is curled.
+#[cfg(feature = "leap")]
+fn is_fist(hand: &leaprs::Hand) -> bool {
+    let fingers: Vec<_> = hand.digits().collect();
+    fingers.len() == 5 && fingers[1..].iter().all(|f| finger_extension(f) < 0.2)
+}
+
+/// Returns true if the thumb and index tips are within `pinch_dist_mm`.
+#[cfg(feature = "leap")]
+fn is_pinch(hand: &leaprs::Hand, t: &Thresholds) -> bool {
+    let fingers: Vec<_> = hand.digits().collect();
+    if fingers.len() < 2 { return false; }
+    let thumb = fingers[0].distal().next_joint();
+    let index = fingers[1].distal().next_joint();
+    let dist  = ((thumb.x - index.x).powi(2) + (thumb.y - index.y).powi(2)
+        + (thumb.z - index.z).powi(2)).sqrt();
+    dist < t.pinch_dist_mm
+}
+
 /// Returns true if the hand shows a scissors gesture:
 /// index + middle extended and spread, ring + pinky curled.
 #[cfg(feature = "leap")]
-fn is_scissors(hand: &leaprs::Hand) -> bool {
+fn is_scissors(hand: &leaprs::Hand, t: &Thresholds) -> bool {
     use leaprs::FingerType::*;
-    const EXTENDED_ANGLE: f32 = 0.4; // radians — finger considered "straight"
-    const SPREAD_ANGLE:   f32 = 0.35;
 
     let fingers: Vec<_> = hand.digits().collect();
     if fingers.len() < 5 { return false; }
 
-    let index_ext  = finger_extension(&fingers[1]) > EXTENDED_ANGLE;
-    let middle_ext = finger_extension(&fingers[2]) > EXTENDED_ANGLE;
+    let index_ext  = finger_extension(&fingers[1]) > t.scissors_extended;
+    let middle_ext = finger_extension(&fingers[2]) > t.scissors_extended;
     let ring_curl  = finger_extension(&fingers[3]) < 0.2;
     let pinky_curl = finger_extension(&fingers[4]) < 0.2;
 
@@ -244,7 +598,7 @@ fn is_scissors(hand: &leaprs::Hand) -> bool {
     if il < 1e-6 || ml < 1e-6 { return false; }
     let cos_a  = (dot / (il * ml)).clamp(-1.0, 1.0);
     let angle  = cos_a.acos();
-    angle > SPREAD_ANGLE
+    angle > t.scissors_spread
 }
 
 #[cfg(feature = "leap")]
@@ -281,10 +635,20 @@ pub enum SimInput {
     KeyUp(SimKey),
     /// Snippet name typed by the user after a scissors key press.
     SnippetName(String),
+    /// Tray entry clicked with the mouse.
+    TrayClick(usize),
+    /// Ribbon patch clicked with the mouse, on the right ribbon or the left.
+    PatchClick { right: bool, index: usize },
+    /// Patches `from..to` dragged across with the mouse.
+    PatchDrag { from: usize, to: usize },
+    /// Scroll wheel or trackpad turned over the right ribbon or the left.
+    WheelPull { right: bool, steps: usize, velocity: f32 },
 }
 
-/// Simulated key codes (mapped from minifb Key).
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Simulated key codes (mapped from minifb Key).  These double as the
+/// action names of the gesture config (`pull_left`, `next_port`, …).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SimKey {
     PullLeft,       // A
     PullRight,      // D
@@ -294,39 +658,177 @@ pub enum SimKey {
     Clap,           // Space
     Unclap,         // Escape
     Scissors,       // S
+    Save,           // W
+    Panic,          // P
+    NextPort,       // O
+    InstrumentPrev,       // [
+    InstrumentNext,       // ]
+    InstrumentPrevFamily, // Shift+[
+    InstrumentNextFamily, // Shift+]
+    TempoDown,      // Left
+    TempoUp,        // Right
+    TempoDownFast,  // Shift+Left
+    TempoUpFast,    // Shift+Right
+    Pause,          // F
+    ZoomIn,         // =
+    ZoomOut,        // -
+    ScrubBack,      // ,
+    ScrubForward,   // .
+    TrayUp,         // Up
+    TrayDown,       // Down
+    TrayMoveUp,     // Shift+Up
+    TrayMoveDown,   // Shift+Down
+    TrayRename,     // R
+    TrayDelete,     // Delete
+    Splice,         // Enter
+    SaveSession,    // F5
+    Undo,           // U, Ctrl+Z
+    Redo,           // Shift+U, Ctrl+Shift+Z
+    NextPair,       // Tab
+    Fullscreen,     // F11
+    SpiralView,     // V
+    Histogram,      // H
+    DetachTray,     // F10
+    Screenshot,     // F12
     Quit,           // Q
 }
 
+impl SimKey {
+    /// The gesture this key stands for.  A snip carries an empty name, so
+    /// the app asks for one.
+    pub fn event(self) -> GestureEvent {
+        match self {
+            SimKey::PullLeft      => GestureEvent::PullLeft  { steps: 1, velocity: 0.3 },
+            SimKey::PullLeftFast  => GestureEvent::PullLeft  { steps: 5, velocity: 0.9 },
+            SimKey::PullRight     => GestureEvent::PullRight { steps: 1, velocity: 0.3 },
+            SimKey::PullRightFast => GestureEvent::PullRight { steps: 5, velocity: 0.9 },
+            SimKey::Twist         => GestureEvent::Twist,
+            SimKey::Clap          => GestureEvent::Clap,
+            SimKey::Unclap        => GestureEvent::Unclap,
+            SimKey::Scissors      => GestureEvent::Scissors { name: String::new() },
+            SimKey::Save          => GestureEvent::SavePerformance,
+            SimKey::Panic         => GestureEvent::Panic,
+            SimKey::NextPort      => GestureEvent::NextPort,
+            SimKey::InstrumentPrev       => GestureEvent::CycleInstrument { step: -1 },
+            SimKey::InstrumentNext       => GestureEvent::CycleInstrument { step: 1 },
+            SimKey::InstrumentPrevFamily => GestureEvent::CycleInstrument { step: -8 },
+            SimKey::InstrumentNextFamily => GestureEvent::CycleInstrument { step: 8 },
+            SimKey::TempoDown     => GestureEvent::NudgeTempo { step: -4 },
+            SimKey::TempoUp       => GestureEvent::NudgeTempo { step: 4 },
+            SimKey::TempoDownFast => GestureEvent::NudgeTempo { step: -16 },
+            SimKey::TempoUpFast   => GestureEvent::NudgeTempo { step: 16 },
+            SimKey::Pause         => GestureEvent::Pause,
+            SimKey::ZoomIn        => GestureEvent::Zoom { factor: 1.25 },
+            SimKey::ZoomOut       => GestureEvent::Zoom { factor: 0.8 },
+            SimKey::ScrubBack     => GestureEvent::Scrub { steps: -8 },
+            SimKey::ScrubForward  => GestureEvent::Scrub { steps: 8 },
+            SimKey::TrayUp        => GestureEvent::SelectSnippet { step: -1 },
+            SimKey::TrayDown      => GestureEvent::SelectSnippet { step: 1 },
+            SimKey::TrayMoveUp    => GestureEvent::MoveSnippet { step: -1 },
+            SimKey::TrayMoveDown  => GestureEvent::MoveSnippet { step: 1 },
+            SimKey::TrayRename    => GestureEvent::RenameSnippet { name: String::new() },
+            SimKey::TrayDelete    => GestureEvent::DeleteSnippet,
+            SimKey::Splice        => GestureEvent::Splice,
+            SimKey::SaveSession   => GestureEvent::SaveSession,
+            SimKey::Undo          => GestureEvent::Undo,
+            SimKey::Redo          => GestureEvent::Redo,
+            SimKey::NextPair      => GestureEvent::NextPair,
+            SimKey::Fullscreen    => GestureEvent::Fullscreen,
+            SimKey::SpiralView    => GestureEvent::SpiralView,
+            SimKey::Histogram     => GestureEvent::Histogram,
+            SimKey::DetachTray    => GestureEvent::DetachTray,
+            SimKey::Screenshot    => GestureEvent::Screenshot,
+            SimKey::Quit          => GestureEvent::Quit,
+        }
+    }
+
+    /// The key's action with Shift held (faster pulls, whole GM families,
+    /// bigger tempo steps, moving tray entries, redo).
+    pub fn shifted(self) -> SimKey {
+        match self {
+            SimKey::PullLeft       => SimKey::PullLeftFast,
+            SimKey::PullRight      => SimKey::PullRightFast,
+            SimKey::InstrumentPrev => SimKey::InstrumentPrevFamily,
+            SimKey::InstrumentNext => SimKey::InstrumentNextFamily,
+            SimKey::TempoDown      => SimKey::TempoDownFast,
+            SimKey::TempoUp        => SimKey::TempoUpFast,
+            SimKey::TrayUp         => SimKey::TrayMoveUp,
+            SimKey::TrayDown       => SimKey::TrayMoveDown,
+            SimKey::Undo           => SimKey::Redo,
+            other                  => other,
+        }
+    }
+
+    /// Whether holding the key repeats the action.
+    pub fn repeats(self) -> bool {
+        matches!(self, SimKey::PullLeft | SimKey::PullLeftFast
+                     | SimKey::PullRight | SimKey::PullRightFast
+                     | SimKey::ScrubBack | SimKey::ScrubForward
+                     | SimKey::TempoDown | SimKey::TempoDownFast
+                     | SimKey::TempoUp | SimKey::TempoUpFast)
+    }
+
+    /// Short name for the on-screen key legend.
+    pub fn label(self) -> &'static str {
+        match self {
+            SimKey::PullLeft | SimKey::PullLeftFast   => "pull L",
+            SimKey::PullRight | SimKey::PullRightFast => "pull R",
+            SimKey::Twist    => "twist",
+            SimKey::Clap     => "clap",
+            SimKey::Unclap   => "unclap",
+            SimKey::Scissors => "snip",
+            SimKey::Save     => "save",
+            SimKey::Panic    => "panic",
+            SimKey::NextPort => "port",
+            SimKey::InstrumentPrev | SimKey::InstrumentPrevFamily => "instr-",
+            SimKey::InstrumentNext | SimKey::InstrumentNextFamily => "instr+",
+            SimKey::TempoDown | SimKey::TempoDownFast => "tempo-",
+            SimKey::TempoUp | SimKey::TempoUpFast     => "tempo+",
+            SimKey::Pause        => "pause",
+            SimKey::ZoomIn       => "zoom+",
+            SimKey::ZoomOut      => "zoom-",
+            SimKey::ScrubBack    => "scrub-",
+            SimKey::ScrubForward => "scrub+",
+            SimKey::TrayUp | SimKey::TrayMoveUp     => "tray up",
+            SimKey::TrayDown | SimKey::TrayMoveDown => "tray dn",
+            SimKey::TrayRename   => "rename",
+            SimKey::TrayDelete   => "delete",
+            SimKey::Splice       => "splice",
+            SimKey::SaveSession  => "session",
+            SimKey::Undo         => "undo",
+            SimKey::Redo         => "redo",
+            SimKey::NextPair     => "pair",
+            SimKey::Fullscreen   => "fullscreen",
+            SimKey::SpiralView   => "spiral",
+            SimKey::Histogram    => "histogram",
+            SimKey::DetachTray   => "detach",
+            SimKey::Screenshot   => "screenshot",
+            SimKey::Quit     => "quit",
+        }
+    }
+}
+
 impl GestureSource for SimGestureSource {
     fn run(self: Box<Self>, tx: Sender<GestureEvent>) {
         for input in self.rx {
             let event = match input {
-                SimInput::KeyDown(SimKey::PullLeft)      =>
-                    GestureEvent::PullLeft  { steps: 1,  velocity: 0.3 },
-                SimInput::KeyDown(SimKey::PullLeftFast)  =>
-                    GestureEvent::PullLeft  { steps: 5,  velocity: 0.9 },
-                SimInput::KeyDown(SimKey::PullRight)     =>
-                    GestureEvent::PullRight { steps: 1,  velocity: 0.3 },
-                SimInput::KeyDown(SimKey::PullRightFast) =>
-                    GestureEvent::PullRight { steps: 5,  velocity: 0.9 },
-                SimInput::KeyDown(SimKey::Twist)         => GestureEvent::Twist,
-                SimInput::KeyDown(SimKey::Clap)          => GestureEvent::Clap,
-                SimInput::KeyDown(SimKey::Unclap)        => GestureEvent::Unclap,
-                SimInput::SnippetName(name)              =>
-                    GestureEvent::Scissors { name },
-                SimInput::KeyDown(SimKey::Quit)          => {
-                    let _ = tx.send(GestureEvent::Quit);
-                    return;
-                }
-                _ => continue,
+                SimInput::KeyDown(key)      => key.event(),
+                SimInput::SnippetName(name) => GestureEvent::Scissors { name },
+                SimInput::TrayClick(index)  => GestureEvent::PickSnippet { index },
+                SimInput::PatchClick { right, index } => GestureEvent::InspectPatch { right, index },
+                SimInput::PatchDrag { from, to }      => GestureEvent::MarkPatches { from, to },
+                SimInput::WheelPull { right: false, steps, velocity } => GestureEvent::PullLeft { steps, velocity },
+                SimInput::WheelPull { right: true, steps, velocity }  => GestureEvent::PullRight { steps, velocity },
+                SimInput::KeyUp(_)          => continue,
             };
-            if tx.send(event).is_err() { return; }
+            let quit = event == GestureEvent::Quit;
+            if tx.send(event).is_err() || quit { return; }
         }
     }
 }
 
 // ════════════════════════════════════════════════════════════════════════════
-// Shared snippet-name prompt (used by LeapGestureSource in hw mode)
+// Shared snippet-name prompt
 // ════════════════════════════════════════════════════════════════════════════
 
 /// Prompt the user for a snippet name on stdout/stdin.
@@ -339,3 +841,97 @@ pub fn prompt_snippet_name() -> String {
     io::stdin().read_line(&mut buf).ok();
     buf.trim().to_string()
 }
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand(palm: [f32; 3]) -> TrackedHand {
+        TrackedHand { palm, ..TrackedHand::default() }
+    }
+
+    #[test]
+    fn recognizer_claps_and_unclaps_once() {
+        let mut r = HandRecognizer::default();
+        let now = Instant::now();
+        let apart    = (hand([-200.0, 200.0, 0.0]), hand([200.0, 200.0, 0.0]));
+        let together = (hand([-30.0, 200.0, 0.0]),  hand([30.0, 200.0, 0.0]));
+        assert!(r.update(Some(&apart.0), Some(&apart.1), now).is_empty());
+        assert_eq!(r.update(Some(&together.0), Some(&together.1), now), vec![GestureEvent::Clap]);
+        assert!(r.update(Some(&together.0), Some(&together.1), now).is_empty());
+        assert_eq!(r.update(Some(&apart.0), Some(&apart.1), now), vec![GestureEvent::Unclap]);
+    }
+
+    #[test]
+    fn recognizer_twists_after_hold() {
+        let mut r = HandRecognizer::default();
+        let (high, low) = (hand([-200.0, 300.0, 0.0]), hand([200.0, 150.0, 0.0]));
+        let events: Vec<_> = (0..Thresholds::default().twist_hold_frames * 2)
+            .flat_map(|_| r.update(Some(&high), Some(&low), Instant::now()))
+            .collect();
+        assert_eq!(events, vec![GestureEvent::Twist]);
+    }
+
+    #[test]
+    fn recognizer_uses_configured_bindings() {
+        let mut config = GestureConfig::default();
+        config.thresholds.swipe_velocity_min = 500.0;
+        config.gestures.swipe_right = SimKey::NextPort;
+        let mut r = HandRecognizer::new(&config);
+        let swipe = TrackedHand { velocity: [600.0, 0.0, 0.0], ..hand([0.0, 200.0, 0.0]) };
+        assert_eq!(r.update(None, Some(&swipe), Instant::now()), vec![GestureEvent::NextPort]);
+    }
+
+    #[test]
+    fn recognizer_stretch_sets_tempo_without_unclapping() {
+        let mut r = HandRecognizer::default();
+        let mut frame = |gap: f32, y| {
+            let (l, rh) = (hand([-gap / 2.0, y, 0.0]), hand([gap / 2.0, y, 0.0]));
+            r.update(Some(&l), Some(&rh), Instant::now())
+        };
+        assert_eq!(frame(40.0, 200.0), vec![GestureEvent::Clap]);
+        assert_eq!(frame(100.0, 350.0), vec![GestureEvent::Tempo { bpm: 60 }]);
+        assert!(frame(100.0, 350.0).is_empty());
+        assert_eq!(frame(300.0, 350.0), vec![GestureEvent::Tempo { bpm: 120 }]);
+        assert_eq!(frame(800.0, 350.0), vec![GestureEvent::Tempo { bpm: 180 }]);
+        assert_eq!(frame(800.0, 200.0), vec![GestureEvent::Unclap]);
+    }
+
+    #[test]
+    fn recognizer_pauses_on_fist_and_zooms_on_pinch() {
+        let mut r = HandRecognizer::default();
+        let posed = |y, pose| TrackedHand { pose, ..hand([0.0, y, 0.0]) };
+        let fist = posed(200.0, HandPose::Fist);
+        let events: Vec<_> = (0..Thresholds::default().fist_hold_frames * 2)
+            .flat_map(|_| r.update(Some(&fist), None, Instant::now()))
+            .collect();
+        assert_eq!(events, vec![GestureEvent::Pause]);
+
+        let pinch = |y| r.update(None, Some(&posed(y, HandPose::Pinch)), Instant::now());
+        let events: Vec<_> = [200.0, 220.0, 235.0, 270.0, 200.0].into_iter().flat_map(pinch).collect();
+        assert_eq!(events, vec![
+            GestureEvent::Zoom { factor: 1.25 },
+            GestureEvent::Zoom { factor: 1.25 },
+            GestureEvent::Zoom { factor: 0.8 },
+        ]);
+    }
+
+    #[test]
+    fn rolling_the_right_hand_sets_the_timbre() {
+        let mut r = HandRecognizer::default();
+        let rolled = |x, deg: f32| TrackedHand { roll: deg.to_radians(), ..hand([x, 200.0, 0.0]) };
+        let mut roll = |left: Option<f32>, right: f32| {
+            let left = left.map(|deg| rolled(-150.0, deg));
+            r.update(left.as_ref(), Some(&rolled(150.0, right)), Instant::now())
+        };
+        // Flat is mid-range already; the left hand doesn't count.
+        assert_eq!(roll(Some(45.0), 0.0), vec![]);
+        assert_eq!(roll(None, 30.0), vec![GestureEvent::Timbre { level: 0.75 }]);
+        assert_eq!(roll(None, 30.1), vec![]);
+        assert_eq!(roll(None, -80.0), vec![GestureEvent::Timbre { level: 0.0 }]);
+    }
+}
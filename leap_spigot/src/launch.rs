@@ -0,0 +1,225 @@
+//! Launch settings — the same choices as the start-up questionnaire, from
+//! command-line flags or a `leap.toml` file, so a launch can be repeated
+//! exactly.
+//!
+//! `leap.toml` in the working directory (or the file given with
+//! `--config PATH`) may set any of these; every field is optional, and a
+//! flag of the same name (`--pitch-mapper`, `--tempo`, …) wins over the
+//! file:
+//!
+//! ```toml
+//! left            = "Pi/16"              # constant[/base] → note lengths
+//! right           = "E"                  # constant[/base] → pitches
+//! pitch_mapper    = "dorian"             # NAME[:PARAM], as spigot_midi's --pitch-mapper
+//! root            = 62                   # MIDI note for digit 0
+//! duration_mapper = "values:s e q q. h"  # NAME[:PARAM] (musical if left out)
+//! tpq             = 480
+//! instrument      = "vibraphone"         # GM program, by number or name
+//! tempo           = 96
+//! velocity        = 100
+//! channel         = 0
+//! port            = "virtual"            # as --port
+//! layout          = "spiral"             # flat | 2d | 3d | spiral
+//!
+//! [keys]                                  # key → action, as in gestures.toml
+//! Z = "twist"
+//! ```
+//!
+//! Given any of them, the questionnaire is skipped and anything left out
+//! keeps the `--quick` default.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use spigot_midi::GeneralMidi;
+
+use crate::app::AppConfig;
+use crate::error::AppError;
+use crate::gesture::SimKey;
+use crate::perform::{MAX_TEMPO, MIN_TEMPO};
+use crate::player::PortSelector;
+use crate::session;
+use crate::visualizer::LayoutMode;
+
+/// Default launch file, looked for in the working directory.
+pub const DEFAULT_PATH: &str = "leap.toml";
+
+/// Launch settings, each left as it is when not given.
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Duration stream as CONSTANT[/BASE], e.g. `Pi/16`.
+    #[arg(long)]
+    pub left:        Option<String>,
+    /// Pitch stream as CONSTANT[/BASE], e.g. `E`.
+    #[arg(long)]
+    pub right:       Option<String>,
+    /// Pitch mapper as NAME[:PARAM], e.g. `dorian` or `custom:0 3 5 7 10`.
+    #[arg(long)]
+    pub pitch_mapper: Option<String>,
+    /// Root note as a MIDI number (60 = middle C).
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=127))]
+    pub root:        Option<u8>,
+    /// Duration mapper as NAME[:PARAM], e.g. `"values:s e q q. h"`
+    /// (default: musical).
+    #[arg(long)]
+    pub duration_mapper: Option<String>,
+    /// Ticks per quarter note.
+    #[arg(long)]
+    pub tpq:         Option<u32>,
+    /// General MIDI program, by number or name.
+    #[arg(long)]
+    pub instrument:  Option<String>,
+    /// Tempo in BPM (20–300).
+    #[arg(long)]
+    pub tempo:       Option<u32>,
+    /// Note velocity 0–127.
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=127))]
+    pub velocity:    Option<u8>,
+    /// MIDI channel 0–15 for the lead voice.
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=15))]
+    pub channel:     Option<u8>,
+    /// MIDI output: a port number, part of a name, `auto`, `virtual`,
+    /// `jack[:NAME]` or `alsa[:CLIENT:PORT]`.
+    #[arg(long)]
+    pub port:        Option<String>,
+    /// Ribbon layout: flat, 2d, 3d or spiral.
+    #[arg(long)]
+    pub layout:      Option<String>,
+    /// Key → action bindings, merged over the gesture mapping's.
+    #[arg(skip)]
+    pub keys:        BTreeMap<String, SimKey>,
+}
+
+impl Settings {
+    /// Parse settings from TOML text.
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// Read a launch file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_toml(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// These settings with anything they leave out taken from `file`.
+    pub fn or(self, file: Settings) -> Settings {
+        let mut keys = file.keys;
+        keys.extend(self.keys);
+        Settings {
+            left:        self.left.or(file.left),
+            right:       self.right.or(file.right),
+            pitch_mapper: self.pitch_mapper.or(file.pitch_mapper),
+            root:        self.root.or(file.root),
+            duration_mapper: self.duration_mapper.or(file.duration_mapper),
+            tpq:         self.tpq.or(file.tpq),
+            instrument:  self.instrument.or(file.instrument),
+            tempo:       self.tempo.or(file.tempo),
+            velocity:    self.velocity.or(file.velocity),
+            channel:     self.channel.or(file.channel),
+            port:        self.port.or(file.port),
+            layout:      self.layout.or(file.layout),
+            keys,
+        }
+    }
+
+    /// Whether nothing is set, so the questionnaire should ask.
+    pub fn is_empty(&self) -> bool {
+        *self == Settings::default()
+    }
+
+    /// The layout to open, flat if not given.
+    pub fn layout(&self) -> LayoutMode {
+        self.layout.as_deref().map_or(LayoutMode::Flat, LayoutMode::from_str)
+    }
+
+    /// Set everything given on `cfg`.
+    pub fn apply(&self, cfg: &mut AppConfig) -> Result<(), AppError> {
+        let side = |s: &str| session::parse_side(s).ok_or_else(|| AppError::Parameter(
+            format!("stream '{}': expected CONSTANT[/BASE], e.g. Pi/16", s)));
+        if let Some(s) = &self.left  { cfg.left_config  = side(s)?; }
+        if let Some(s) = &self.right { cfg.right_config = side(s)?; }
+
+        if let Some(spec) = &self.pitch_mapper    { cfg.pitch_mapper = spec.clone(); }
+        if let Some(root) = self.root             { cfg.root = root; }
+        if let Some(spec) = &self.duration_mapper { cfg.duration_mapper = spec.clone(); }
+        if let Some(tpq)  = self.tpq              { cfg.ticks_per_quarter = tpq.clamp(24, 9600); }
+        // Look the specs up now, rather than when the players start.
+        cfg.mappers(cfg.left_config.base, cfg.right_config.base)?;
+
+        if let Some(s) = &self.instrument {
+            cfg.instrument = s.parse::<GeneralMidi>()?.program();
+        }
+        if let Some(bpm) = self.tempo    { cfg.tempo_bpm = bpm.clamp(MIN_TEMPO, MAX_TEMPO); }
+        if let Some(v)   = self.velocity { cfg.velocity  = v.min(127); }
+        if let Some(ch)  = self.channel  { cfg.channel   = ch.min(15); }
+        if let Some(p)   = &self.port    { cfg.midi_port = PortSelector::parse(p); }
+        cfg.gestures.keys.extend(self.keys.clone());
+        Ok(())
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spigot_stream::Constant;
+
+    #[test]
+    fn launch_file_sets_the_config() {
+        let settings = Settings::from_toml(r#"
+            left        = "ln2/16"
+            right       = "E"
+            pitch_mapper = "dorian"
+            root        = 62
+            duration_mapper = "values:e q h"
+            instrument  = "vibraphone"
+            tempo       = 96
+            port        = "virtual"
+            layout      = "spiral"
+
+            [keys]
+            Z = "twist"
+        "#).unwrap();
+        let mut cfg = AppConfig::default();
+        settings.apply(&mut cfg).unwrap();
+
+        assert_eq!((cfg.left_config.constant, cfg.left_config.base), (Constant::Ln2, 16));
+        assert_eq!(cfg.right_config.constant, Constant::E);
+        assert_eq!((cfg.root, cfg.pitch_mapper.as_str()), (62, "dorian"));
+        let (pitch, duration) = cfg.mappers(16, 10).unwrap();
+        assert_eq!((pitch.note_for(0), pitch.note_for(1)), (62, 64));
+        assert_eq!((0..3).map(|d| duration.ticks_for(d)).collect::<Vec<_>>(), [240, 480, 960]);
+        assert_eq!((cfg.instrument, cfg.tempo_bpm), (11, 96));
+        assert_eq!(cfg.midi_port, PortSelector::Virtual);
+        assert_eq!(cfg.gestures.keys["Z"], SimKey::Twist);
+        assert_eq!(cfg.gestures.keys["T"], SimKey::Twist);
+        assert_eq!(settings.layout(), LayoutMode::Spiral);
+    }
+
+    #[test]
+    fn flags_win_over_the_file() {
+        let file = Settings::from_toml("tempo = 96\ninstrument = \"cello\"").unwrap();
+        let flags = Settings { tempo: Some(140), ..Settings::default() };
+        let merged = flags.or(file);
+        assert_eq!((merged.tempo, merged.instrument.as_deref()), (Some(140), Some("cello")));
+        assert!(!merged.is_empty());
+        assert!(Settings::default().is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_settings() {
+        assert!(Settings::from_toml("bpm = 96").is_err());
+        let mut cfg = AppConfig::default();
+        for bad in ["left = \"Tau\"", "pitch_mapper = \"blues\"", "instrument = \"kazoo\"", "duration_mapper = \"values:q z\""] {
+            assert!(Settings::from_toml(bad).unwrap().apply(&mut cfg).is_err(), "{}", bad);
+        }
+    }
+}
@@ -13,34 +13,137 @@
 //! | Right hand over Left | Either | `twist()` — swap streams |
 //! | Clap (hands together) | Both | Begin MIDI playback from current zip position |
 //! | Un-clap (hands apart) | Both | Stop MIDI playback |
+//! | Stretch (open palms held high) | Both | Palm distance sets the tempo |
 //! | Scissors (index+middle spread) | Either | Invoke `snip()` — user types key name |
+//! | Swipe sideways | Right | Next (→) / previous (←) GM instrument |
+//! | Swipe sideways | Left | Scrub both streams forward (→) / back (←) |
+//! | Fist | Either | Pause / resume playback, keeping the stitch |
+//! | Pinch, raise / lower | Either | Zoom the ribbons in / out |
+//! | Roll the palm | Right | Timbre — a MIDI controller (CC74 by default) |
+//!
+//! Pitches come from the right stream and lengths from the left; with
+//! `--layers` the left stream plays a bass line of its own under the right
+//! (see [`player::Layer`]), and `--drift` lets the digits nudge the tempo
+//! as they go (see [`player::TempoDrift`]).  Each `--pair LEFT:RIGHT` adds
+//! another ribbon pair playing alongside on a channel of its own, and `Tab`
+//! picks the pair the pulls act on (see [`app`]).  `--velocity-map` and
+//! `--dynamics` shape the velocities as file export does (see
+//! [`player::Dynamics`]).
+//!
+//! Without Leap hardware, `--webcam` tracks the same gestures with an ordinary
+//! camera (see [`webcam`]), and a MIDI controller can drive the same actions
+//! (see [`midi_in`]).  With `--jam`, notes played on that keyboard are echoed
+//! and each phrase is answered from the streams, in the same scale.
+//! `--script FILE` plays a timed list of gestures, for demos that go the
+//! same way every time (see [`script`]).
+//!
+//! Every launch choice — streams, maps, instrument, tempo, keys — can be
+//! given as flags or kept in a `leap.toml` file instead of answering the
+//! start-up questions (see [`launch`]).
+//!
+//! Thresholds and bindings for all of the above can be tuned in a
+//! `gestures.toml` file (see [`config`]), and `--calibrate` measures your
+//! own gestures to set the thresholds (see [`calibrate`]).
+//!
+//! Everything the player sounds is recorded; saving writes the performance
+//! to a standard MIDI file (`W` in either mode), and with `--record-gif` an
+//! animated GIF of the ribbons to go with it (see [`capture`]).  The snippet tray is kept
+//! between sessions, and a whole performance setup can be saved and
+//! restored (see [`session`]).
+//!
+//! Failures come back as an [`error::AppError`], which says whether a
+//! setting, a file or a device is to blame.
 //!
 //! ## Visualization
 //!
 //! Two horizontal ribbons of colored digit-patches scroll left as the stream
 //! advances.  When playing, the ribbons animate toward each other and are
 //! "stitched" with a connecting thread.  Scissors/snip highlights a section
-//! in gold and deposits it into the **Snippet Tray** on the right side.
+//! in gold and deposits it into the **Snippet Tray** on the right side, from
+//! where a snippet can be spliced back into the performance on the beat —
+//! a live looper.  Below the tray, a piano roll scrolls the notes as they
+//! play.
+//!
+//! The drawing is the [`visualizer::Renderer`]'s, into a pixel buffer; on
+//! `wasm32` only it and the streams, ribbons, gestures and the
+//! [`perform::Performer`] that acts on them build, for `leap_web` to show
+//! on a canvas and play through Web MIDI.
 //!
 //! ## Feature flags
 //!
 //! * (default) — **Simulation mode**: keyboard shortcuts drive all gestures.
 //! * `leap` — **Hardware mode**: polls a real LeapMotion controller via LeapC.
+//! * `link` — **Ableton Link**: `--link` locks tempo and beat phase to other
+//!   Link apps on the network; the stitched ribbons pulse on each downbeat.
+//! * `audio` — **Built-in synth**: with no MIDI output port to play to, the
+//!   player sounds through a simple synth on the default audio device
+//!   instead of falling silent (see [`synth`]).
+//! * `jack` — **JACK**: `--port jack[:NAME]` plays through a JACK MIDI
+//!   port, and `--jack-transport` follows the JACK transport's tempo,
+//!   beats, start and stop (see [`seq`]).
+//! * `alsa-seq` — **ALSA sequencer** (Linux): `--port alsa[:CLIENT:PORT]`
+//!   plays through a sequencer port of its own.
 //!
 //! ### Simulation keyboard shortcuts
 //!
+//! These are the defaults; `[keys]` in `gestures.toml` rebinds them, and
+//! `keyboard = "azerty"` there moves them for an AZERTY board (see
+//! [`config`]).
+//!
 //! | Key | Gesture |
 //! |---|---|
 //! | `A` / hold | Pull Left stream (faster with Shift) |
 //! | `D` / hold | Pull Right stream (faster with Shift) |
+//! | Scroll wheel over a ribbon | Pull that stream, faster the faster it turns |
 //! | `T` | Twist |
 //! | `Space` | Clap / start MIDI |
 //! | `Escape` | Un-clap / stop MIDI |
 //! | `S` | Scissors / snip |
+//! | `W` | Save performance as MIDI |
+//! | `P` or `End` | Panic — all notes off on every channel |
+//! | `O` | Next MIDI output port |
+//! | `[` / `]`, or `PageUp` / `PageDown` | Previous / next instrument (a GM family of 8 with Shift) |
+//! | `Left` / `Right` hold | Tempo down / up 4 BPM (16 with Shift) |
+//! | `F` | Fist / pause or resume |
+//! | `=` (`+`) / `-`, or keypad `+` / `-` | Widen / narrow the ribbon patches |
+//! | `,` / `.` hold | Scrub back / forward |
+//! | `Up` / `Down` | Select a tray snippet (or click it); move it with Shift |
+//! | `R` / `Delete` | Rename / delete the selected snippet |
+//! | `Enter` | Splice the selected snippet in at the next beat |
+//! | `F5` | Save the session (restore it with `--session PATH`) |
+//! | `U` or `Ctrl+Z` | Undo the last pull, twist, scrub or splice (redo with Shift) |
+//! | `Tab` | Pull the next ribbon pair (with `--pair`) |
+//! | `F10` | Tray, notes and histogram in a window of their own, or back |
+//! | `F11` | Fullscreen on / off, for stage projection |
+//! | `V` | Spiral view on / off |
+//! | `H` | Digit histogram on / off |
+//! | `F12` | Save a screenshot (see [`capture`]) |
 //! | `Q` | Quit |
 
+pub mod error;
+pub mod config;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod launch;
+pub mod calibrate;
 pub mod gesture;
 pub mod ribbon;
+pub mod perform;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod player;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod synth;
+pub mod performance;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod link;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod seq;
+pub mod session;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod midi_in;
+pub mod webcam;
+pub mod script;
+pub mod font;
+pub mod capture;
 pub mod visualizer;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod app;
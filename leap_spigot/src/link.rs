@@ -0,0 +1,121 @@
+//! Tempo and beat-phase sync with other applications.
+//!
+//! Given a [`BeatSync`], the player places every note on the shared beat
+//! grid instead of its own clock, so it stays in time and in phase with the
+//! other participants, and the visualizer pulses the stitched ribbons on each
+//! shared downbeat.  With the `link` feature, [`LinkSync`] provides the grid
+//! through Ableton Link; with the `jack` feature,
+//! [`JackTransport`](crate::seq::JackTransport) provides it from the JACK
+//! transport, which also starts and stops the player.
+
+use std::time::Instant;
+
+/// Beats per bar: notes start on a bar line, and the first beat of each bar
+/// is the downbeat.
+pub const QUANTUM: f64 = 4.0;
+
+/// A shared tempo and beat timeline.
+pub trait BeatSync: Send + Sync {
+    /// Current session tempo in BPM.
+    fn tempo(&self) -> f64;
+    /// Propose a new tempo to the session.
+    fn set_tempo(&self, bpm: f64);
+    /// Beat position (in quarter notes) at `at`.
+    fn beat_at(&self, at: Instant) -> f64;
+    /// When the session reaches `beat`.
+    fn time_at_beat(&self, beat: f64) -> Instant;
+    /// Whether a shared transport is rolling, for timelines that have one;
+    /// the player starts and stops with it.  `None` leaves starting and
+    /// stopping to the performer.
+    fn rolling(&self) -> Option<bool> { None }
+}
+
+/// First bar line at or after `beat`.
+pub fn next_bar(beat: f64) -> f64 {
+    (beat / QUANTUM).ceil() * QUANTUM
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// LinkSync — Ableton Link (feature = "link")
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Joins the Ableton Link session on the local network.
+///
+/// Requires the `link` feature; building it compiles the Link C++ SDK, so
+/// CMake, a C++ compiler and libclang must be installed.
+#[cfg(feature = "link")]
+pub struct LinkSync {
+    link: rusty_link::AblLink,
+}
+
+#[cfg(feature = "link")]
+impl LinkSync {
+    /// Enable Link, proposing `bpm` if no other peers are playing.
+    pub fn new(bpm: f64) -> Self {
+        let link = rusty_link::AblLink::new(bpm);
+        link.enable(true);
+        LinkSync { link }
+    }
+
+    /// Number of other Link apps in the session.
+    pub fn peers(&self) -> u64 {
+        self.link.num_peers()
+    }
+
+    fn session(&self) -> rusty_link::SessionState {
+        let mut state = rusty_link::SessionState::new();
+        self.link.capture_app_session_state(&mut state);
+        state
+    }
+
+    /// `at` on the Link clock, in microseconds.
+    fn micros(&self, at: Instant) -> i64 {
+        let (now, clock) = (Instant::now(), self.link.clock_micros());
+        if at >= now {
+            clock + (at - now).as_micros() as i64
+        } else {
+            clock - (now - at).as_micros() as i64
+        }
+    }
+}
+
+#[cfg(feature = "link")]
+impl BeatSync for LinkSync {
+    fn tempo(&self) -> f64 {
+        self.session().tempo()
+    }
+
+    fn set_tempo(&self, bpm: f64) {
+        let mut state = self.session();
+        state.set_tempo(bpm, self.link.clock_micros());
+        self.link.commit_app_session_state(&state);
+    }
+
+    fn beat_at(&self, at: Instant) -> f64 {
+        self.session().beat_at_time(self.micros(at), QUANTUM)
+    }
+
+    fn time_at_beat(&self, beat: f64) -> Instant {
+        let target = self.session().time_at_beat(beat, QUANTUM);
+        let (now, clock) = (Instant::now(), self.link.clock_micros());
+        let offset = std::time::Duration::from_micros(target.abs_diff(clock));
+        if target >= clock { now + offset } else { now.checked_sub(offset).unwrap_or(now) }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_bar_rounds_up_to_quantum() {
+        assert_eq!(next_bar(0.0), 0.0);
+        assert_eq!(next_bar(0.1), 4.0);
+        assert_eq!(next_bar(7.99), 8.0);
+        assert_eq!(next_bar(8.0), 8.0);
+    }
+}
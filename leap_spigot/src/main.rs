@@ -1,13 +1,125 @@
 //! leap_spigot — interactive entry point.
+//!
+//! Launch choices can come from flags or a `leap.toml` file (see
+//! [`leap_spigot::launch`]); with none of them the questionnaire asks.
 
+use clap::Parser;
 use leap_spigot::app::{AppConfig, run};
-use leap_spigot::visualizer::LayoutMode;
+use leap_spigot::error::AppError;
+use leap_spigot::player::{self, PortSelector};
+use leap_spigot::{calibrate, launch, session, webcam};
+use leap_spigot::script::Script;
+use leap_spigot::launch::Settings;
+use leap_spigot::perform::{MAX_TEMPO, MIN_TEMPO};
+use leap_spigot::session::Session;
+use leap_spigot::config::{self, GestureConfig};
+use leap_spigot::visualizer::{self, LayoutMode};
 use dual_spigot::SpigotConfig;
 use spigot_stream::Constant;
-use spigot_midi::{PitchMap, DurationMap, Scale};
+use spigot_midi::{Scale, VelocityMap, Curve};
 use std::io::{self, Write};
 
+/// Play two spigot streams as MIDI by pulling their ribbons with your
+/// hands (or the keyboard).  Run without flags for the questionnaire.
+#[derive(Parser)]
+#[command(name = "leap_spigot")]
+struct Args {
+    /// Launch settings file (default: leap.toml, if there is one).
+    #[arg(long)]
+    config:      Option<String>,
+    #[command(flatten)]
+    settings:    Settings,
+    /// Skip the questionnaire: π/e, C major, piano, 120 BPM.
+    #[arg(long)]
+    quick:       bool,
+    /// Restore a saved performance setup.
+    #[arg(long)]
+    session:     Option<String>,
+    /// Drive the app from a MIDI controller: N, NAME, auto or virtual.
+    #[arg(long)]
+    midi_in:     Option<String>,
+    /// Jam along: echo the --midi-in keyboard and answer each phrase.
+    #[arg(long, requires = "midi_in")]
+    jam:         bool,
+    /// Gesture mapping file (default: gestures.toml, if there is one).
+    #[arg(long)]
+    gestures:    Option<String>,
+    /// UI scale 1–4 for HiDPI screens (default: the desktop's).
+    #[arg(long, value_parser = ui_scale)]
+    scale:       Option<f32>,
+    /// Borderless fullscreen for projection (also F11).
+    #[arg(long)]
+    fullscreen:  bool,
+    /// Tray, notes and histogram in a second window, e.g. on the
+    /// laptop while the ribbons are projected (also F10).
+    #[arg(long)]
+    detach_tray: bool,
+    /// Size of the screen to cover fullscreen, as WIDTHxHEIGHT.
+    #[arg(long, value_parser = display_size)]
+    display:     Option<(usize, usize)>,
+    /// Film the ribbons alongside the MIDI recording.
+    #[arg(long)]
+    record_gif:  bool,
+    /// Play the left stream as a bass line (GM PROGRAM, Acoustic Bass by
+    /// default) on channel 1 under the right as the lead.
+    #[arg(long, value_name = "PROGRAM", num_args = 0..=1,
+          value_parser = clap::value_parser!(u8).range(0..=127))]
+    layers:      Option<Option<u8>>,
+    /// Another ribbon pair as LEFT:RIGHT, e.g. `Ln2:ThueMorse/2`
+    /// (repeatable); Tab switches which one is pulled.
+    #[arg(long, value_parser = ribbon_pair)]
+    pair:        Vec<(SpigotConfig, SpigotConfig)>,
+    /// Let the digits nudge the tempo every N notes (8).
+    #[arg(long, value_name = "N", num_args = 0..=1)]
+    drift:       Option<Option<usize>>,
+    /// Louder notes for higher digits of that stream, as in file export.
+    #[arg(long, value_parser = ["left", "right"])]
+    velocity_map: Option<String>,
+    /// Phrase shaping: flat, crescendo, decrescendo or swell[:N].
+    #[arg(long, value_parser = phrase_curve)]
+    dynamics:    Option<Curve>,
+    /// Where the snippet tray is kept between sessions.
+    #[arg(long, default_value = session::TRAY_PATH)]
+    tray:        String,
+    /// Track hands with a camera instead of a Leap, via a tracker CMD.
+    #[arg(long, value_name = "CMD", num_args = 0..=1,
+          default_missing_value = webcam::DEFAULT_TRACKER)]
+    webcam:      Option<String>,
+    /// Play the timed gestures in FILE alongside the live ones.
+    #[arg(long, value_name = "FILE")]
+    script:      Option<String>,
+    /// Measure your gestures and save the thresholds.
+    #[arg(long)]
+    calibrate:   bool,
+    /// Lock tempo and beat phase to Ableton Link peers.
+    #[arg(long)]
+    link:        bool,
+    /// Follow the JACK transport: its tempo and beats, and its start and
+    /// stop.
+    #[arg(long, conflicts_with = "link")]
+    jack_transport: bool,
+}
+
+fn ui_scale(s: &str) -> Result<f32, String> {
+    visualizer::parse_scale(s).ok_or_else(|| "expected a number from 1 to 4".to_string())
+}
+
+fn display_size(s: &str) -> Result<(usize, usize), String> {
+    visualizer::parse_size(s).ok_or_else(|| "expected WIDTHxHEIGHT, e.g. 1920x1080".to_string())
+}
+
+fn ribbon_pair(s: &str) -> Result<(SpigotConfig, SpigotConfig), String> {
+    session::parse_pair(s).ok_or_else(|| "expected LEFT:RIGHT constants, e.g. Ln2:ThueMorse/2".to_string())
+}
+
+fn phrase_curve(s: &str) -> Result<Curve, String> {
+    player::parse_curve(s)
+        .ok_or_else(|| "expected flat, crescendo, decrescendo or swell, e.g. swell:16".to_string())
+}
+
 fn main() {
+    let args = Args::parse();
+
     println!();
     println!("╔══════════════════════════════════════════════════════════════╗");
     println!("║     Leap Spigot — Transcendental MIDI Ribbon Controller      ║");
@@ -19,35 +131,219 @@ fn main() {
     #[cfg(not(feature = "leap"))]
     println!("  Mode: Keyboard simulation  (use --features leap for hardware)");
 
-    let args: Vec<String> = std::env::args().collect();
-
-    // Parse --layout flat|2d|3d
-    let layout = args.windows(2)
-        .find(|w| w[0] == "--layout")
-        .map(|w| LayoutMode::from_str(&w[1]))
-        .unwrap_or(LayoutMode::Flat);
+    // --config PATH, else leap.toml if there is one; flags win over it
+    let config_path = args.config.clone()
+        .or_else(|| std::path::Path::new(launch::DEFAULT_PATH).exists()
+            .then(|| launch::DEFAULT_PATH.to_string()));
+    let settings = match config_path {
+        Some(path) => match Settings::load(&path) {
+            Ok(file) => { println!("  Launch settings: {}\n", path); args.settings.clone().or(file) }
+            Err(e)   => { eprintln!("  ⚠  {} — ignoring it\n", e); args.settings.clone() }
+        },
+        None => args.settings.clone(),
+    };
 
+    let layout = settings.layout();
     let layout_name = match layout {
         LayoutMode::Flat   => "flat (horizontal ribbons)",
         LayoutMode::TwoD   => "2d  (vertical ribbons from bottom)",
         LayoutMode::ThreeD => "3d  (perspective + hand ghosts)",
+        LayoutMode::Spiral => "spiral (double spiral, V toggles)",
     };
-    println!("  Layout: {}  (change with --layout flat|2d|3d)\n", layout_name);
+    println!("  Layout: {}  (change with --layout flat|2d|3d|spiral)\n", layout_name);
+
+    // --session PATH — restore a saved performance setup
+    let session = args.session.as_ref().and_then(|path| match Session::load(path) {
+        Ok(s)  => { println!("  Session: {}\n", path); Some(s) }
+        Err(e) => { eprintln!("  ⚠  {} — starting a fresh session\n", e); None }
+    });
 
-    let cfg = if args.iter().any(|a| a == "--quick") {
+    let mut cfg = if let Some(s) = session {
+        let mut cfg = AppConfig::default();
+        if let (Ok(l), Ok(r)) = (s.left.config(), s.right.config()) {
+            cfg.left_config  = l;
+            cfg.right_config = r;
+        }
+        cfg.tempo_bpm  = s.tempo_bpm;
+        cfg.instrument = s.instrument;
+        cfg.velocity   = s.velocity;
+        cfg.session    = Some(s);
+        cfg
+    } else if args.quick {
         println!("  Quick-start: π/e, C major, piano, 120 BPM\n");
         AppConfig::default()
+    } else if !settings.is_empty() {
+        AppConfig::default()
     } else {
         configure_interactively()
     };
 
+    // --midi-in N|NAME|auto|virtual — drive the app from a controller
+    if let Some(port) = &args.midi_in {
+        cfg.midi_in = Some(PortSelector::parse(port));
+        println!("  MIDI control: CC1=tempo  CC7=velocity  pads 36–39=twist/snip/clap/unclap\n");
+        if args.jam {
+            cfg.jam = true;
+            println!("  Jam: other keys are echoed, and each phrase answered from the stream\n");
+        }
+    }
+
+    // --gestures PATH, else gestures.toml if there is one
+    let gestures_path = args.gestures.clone()
+        .or_else(|| std::path::Path::new(config::DEFAULT_PATH).exists()
+            .then(|| config::DEFAULT_PATH.to_string()));
+    if let Some(path) = gestures_path {
+        match GestureConfig::load(&path) {
+            Ok(g)  => { println!("  Gesture mapping: {}\n", path); cfg.gestures = g; }
+            Err(e) => eprintln!("  ⚠  {} — using default gesture mapping\n", e),
+        }
+    }
+
+    // Streams, maps, instrument, tempo, port and keys from flags / leap.toml
+    if let Err(e) = settings.apply(&mut cfg) {
+        eprintln!("Error: {}", e);
+        std::process::exit(2);
+    }
+
+    cfg.session_file = args.session.clone();
+
+    if let Some(s) = args.scale {
+        println!("  UI scale: {}\n", s);
+        cfg.ui_scale = Some(s);
+    }
+
+    cfg.fullscreen = args.fullscreen;
+    cfg.detach_tray = args.detach_tray;
+    if let Some(size) = args.display {
+        cfg.display_size = size;
+    }
+
+    if args.record_gif {
+        println!("  Recording: the ribbons are filmed from the first note; W saves a GIF with the MIDI\n");
+        cfg.record_gif = true;
+    }
+
+    if let Some(program) = args.layers {
+        let bass = program.unwrap_or(player::DEFAULT_BASS);
+        println!("  Layers: left → bass (program {}, channel 2), right → lead (channel 1)\n", bass);
+        cfg.layers = Some(player::bass_and_lead(bass, cfg.instrument));
+    }
+
+    cfg.extra_pairs = args.pair.clone();
+    if !cfg.extra_pairs.is_empty() {
+        println!("  Ribbon pairs: {} (Tab switches the one you pull)\n", cfg.extra_pairs.len() + 1);
+    }
+
+    if let Some(every) = args.drift {
+        let mut drift = player::TempoDrift::default();
+        if let Some(n) = every.filter(|&n| n > 0) {
+            drift.every = n;
+        }
+        println!("  Tempo drift: nudged every {} notes, within ±{}% of the set tempo\n",
+                 drift.every, (drift.range * 100.0).round());
+        cfg.tempo_drift = Some(drift);
+    }
+
+    match args.velocity_map.as_deref() {
+        Some("left")  => cfg.velocity_map = VelocityMap::linear_left(40, 120, cfg.left_config.base),
+        Some("right") => cfg.velocity_map = VelocityMap::linear_right(40, 120, cfg.right_config.base),
+        _             => {}
+    }
+
+    if let Some(c) = args.dynamics {
+        println!("  Dynamics: {:?}\n", c);
+        cfg.dynamics = c;
+    }
+
+    cfg.tray_file = Some(args.tray.clone());
+
+    if let Some(command) = &args.webcam {
+        println!("  Webcam hand tracking: {}\n", command);
+        cfg.webcam = Some(command.clone());
+    }
+
+    if let Some(path) = &args.script {
+        match Script::load(path) {
+            Ok(script) => {
+                println!("  Script: {} gestures over {:.1} s\n", script.events.len(), script.length().as_secs_f32());
+                cfg.script = Some(script);
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // --calibrate: measure the player's gestures and save the thresholds
+    if args.calibrate {
+        let path = args.gestures.clone().unwrap_or_else(|| config::DEFAULT_PATH.to_string());
+        calibrate(&mut cfg, &path);
+    }
+
+    if args.link {
+        #[cfg(feature = "link")]
+        {
+            let link = leap_spigot::link::LinkSync::new(cfg.tempo_bpm as f64);
+            println!("  Ableton Link: on ({} peers)\n", link.peers());
+            cfg.sync = Some(std::sync::Arc::new(link));
+        }
+        #[cfg(not(feature = "link"))]
+        println!("  Ableton Link: not built in  (use --features link)\n");
+    }
+
+    if args.jack_transport {
+        #[cfg(feature = "jack")]
+        match leap_spigot::seq::JackTransport::new(cfg.tempo_bpm as f64) {
+            Ok(transport) => {
+                println!("  JACK transport: following\n");
+                cfg.sync = Some(std::sync::Arc::new(transport));
+            }
+            Err(e) => println!("  JACK transport: {}\n", e),
+        }
+        #[cfg(not(feature = "jack"))]
+        println!("  JACK transport: not built in  (use --features jack)\n");
+    }
+
     println!();
     println!("  Opening visualizer window…");
     println!();
 
     if let Err(e) = run(cfg, layout) {
         eprintln!("Error: {}", e);
-        std::process::exit(1);
+        // A bad setting exits as bad flags do.
+        std::process::exit(match e { AppError::Parameter(_) => 2, _ => 1 });
+    }
+}
+
+fn calibrate(cfg: &mut AppConfig, path: &str) {
+    let frames: Box<dyn Iterator<Item = calibrate::Frame>> = match &cfg.webcam {
+        Some(command) => match webcam::TrackerFrames::spawn(command) {
+            Ok(f)  => Box::new(f),
+            Err(e) => { eprintln!("  ⚠  Calibration skipped: {}", e); return; }
+        },
+        #[cfg(feature = "leap")]
+        None => Box::new(leap_spigot::gesture::LeapFrames::open(cfg.gestures.thresholds.clone())),
+        #[cfg(not(feature = "leap"))]
+        None => {
+            eprintln!("  ⚠  Calibration needs hand tracking: add --webcam (or build with --features leap)");
+            return;
+        }
+    };
+
+    let Some(samples) = calibrate::run_wizard(frames) else {
+        eprintln!("  ⚠  Hand tracking stopped — calibration abandoned");
+        return;
+    };
+    match calibrate::derive_thresholds(&samples, &cfg.gestures.thresholds) {
+        Ok(thresholds) => {
+            cfg.gestures.thresholds = thresholds;
+            match cfg.gestures.save(path) {
+                Ok(())  => println!("  Calibrated thresholds saved to {}\n", path),
+                Err(e)  => eprintln!("  ⚠  Couldn't save calibration: {}", e),
+            }
+        }
+        Err(e) => eprintln!("  ⚠  Calibration failed: {} — keeping previous thresholds", e),
     }
 }
 
@@ -60,37 +356,62 @@ fn configure_interactively() -> AppConfig {
     let bpm: u32 = {
         let b = read_line("  Tempo BPM (default 120): ")
             .trim().parse().unwrap_or(120);
-        b.max(20).min(300)
+        b.clamp(MIN_TEMPO, MAX_TEMPO)
     };
 
     let instrument: u8 = pick_instrument();
-    let pitch_map       = pick_pitch_map();
-    let duration_map    = pick_duration_map();
+    let (root, pitch_mapper)    = pick_pitch_map();
+    let (tpq, duration_mapper)  = pick_duration_map();
     let velocity: u8 = read_line("  Velocity 0–127 (default 100): ")
         .trim().parse().unwrap_or(100).min(127);
 
     AppConfig {
         left_config,
         right_config,
-        pitch_map,
-        duration_map,
+        root,
+        pitch_mapper,
+        duration_mapper,
+        ticks_per_quarter: tpq,
         instrument,
         tempo_bpm: bpm,
         velocity,
+        velocity_map: VelocityMap::Fixed(100),
+        dynamics: Curve::Flat,
+        dynamics_depth: 24,
         channel: 0,
+        layers: None,
+        extra_pairs: Vec::new(),
+        tempo_drift: None,
         ribbon_capacity: 26,
+        sync: None,
+        midi_port: pick_port(),
+        midi_in: None,
+        jam: false,
+        webcam: None,
+        script: None,
+        gestures: GestureConfig::default(),
+        tray_file: None,
+        session: None,
+        session_file: None,
+        ui_scale: None,
+        fullscreen: false,
+        detach_tray: false,
+        display_size: visualizer::DISPLAY_SIZE,
+        record_gif: false,
     }
 }
 
 fn pick_config() -> SpigotConfig {
     let constant = loop {
-        println!("    1.π  2.e  3.ln2  4.Liouville  5.Champernowne  6.ThueMorse");
-        match read_line("    Choice (1–6, default 1): ").trim() {
+        println!("    1.π  2.e  3.ln2  4.Liouville  5.Champernowne  6.ThueMorse  7.√2  8.φ");
+        match read_line("    Choice (1–8, default 1): ").trim() {
             "2" => break Constant::E,
             "3" => break Constant::Ln2,
             "4" => break Constant::Liouville,
             "5" => break Constant::Champernowne,
             "6" => break Constant::ThueMorse,
+            "7" => break Constant::Sqrt2,
+            "8" => break Constant::Phi,
             _   => break Constant::Pi,
         }
     };
@@ -110,31 +431,50 @@ fn pick_instrument() -> u8 {
     read_line("  Program (default 0): ").trim().parse::<u8>().unwrap_or(0).min(127)
 }
 
-fn pick_pitch_map() -> PitchMap {
+/// The root note and a pitch mapper spec.
+fn pick_pitch_map() -> (u8, String) {
     let root: u8 = read_line("  Root note MIDI# (default 60 = C4): ")
         .trim().parse::<u8>().unwrap_or(60).min(127);
     println!("  Scale: 1=Major 2=Minor 3=PentaMaj 4=PentaMin 5=Dorian 6=WholeTone 7=Chromatic");
-    match read_line("  Choice (default 1): ").trim() {
-        "2" => PitchMap::minor(root),
-        "3" => PitchMap::pentatonic_major(root),
-        "4" => PitchMap::pentatonic_minor(root),
-        "5" => PitchMap::dorian(root),
-        "6" => PitchMap::whole_tone(root),
-        "7" => PitchMap::chromatic(root),
-        _   => PitchMap::major(root),
-    }
+    let scale = match read_line("  Choice (default 1): ").trim() {
+        "2" => Scale::minor(),
+        "3" => Scale::pentatonic_major(),
+        "4" => Scale::pentatonic_minor(),
+        "5" => Scale::dorian(),
+        "6" => Scale::whole_tone(),
+        "7" => Scale::chromatic(),
+        _   => Scale::major(),
+    };
+    (root, scale.name.to_string())
 }
 
-fn pick_duration_map() -> DurationMap {
+/// Ticks per quarter note and a duration mapper spec.
+fn pick_duration_map() -> (u32, String) {
     let tpq: u32 = read_line("  Ticks/quarter (default 480): ")
         .trim().parse().unwrap_or(480).max(24).min(9600);
     println!("  Duration: 1=Musical  2=Linear  3=Exponential  4=Fixed");
-    match read_line("  Choice (default 1): ").trim() {
-        "2" => DurationMap::linear(tpq / 4, 10),
-        "3" => DurationMap::exponential(tpq / 8, 10),
-        "4" => DurationMap::fixed(tpq, 10),
-        _   => DurationMap::musical(tpq),
+    let spec = match read_line("  Choice (default 1): ").trim() {
+        "2" => "linear",
+        "3" => "exponential",
+        "4" => "fixed",
+        _   => "musical",
+    };
+    (tpq, spec.to_string())
+}
+
+fn pick_port() -> PortSelector {
+    let ports = player::output_ports();
+    println!("  MIDI output ports:");
+    for (i, name) in ports.iter().enumerate() {
+        println!("    {}. {}", i, name);
     }
+    #[cfg(unix)]
+    println!("    v. new virtual port \"{}\" (for recording in a DAW)", player::VIRTUAL_PORT_NAME);
+    #[cfg(feature = "jack")]
+    println!("    jack[:NAME]. a JACK MIDI port, connected to NAME");
+    #[cfg(all(feature = "alsa-seq", target_os = "linux"))]
+    println!("    alsa[:CLIENT:PORT]. an ALSA sequencer port, connected to CLIENT:PORT");
+    PortSelector::parse(&read_line("  Port number or name (default auto): "))
 }
 
 fn read_line(prompt: &str) -> String {
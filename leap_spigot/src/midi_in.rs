@@ -0,0 +1,268 @@
+//! MIDI controller input.
+//!
+//! [`MidiInGestureSource`] listens on a MIDI input port and turns controller
+//! messages into the same [`GestureEvent`]s the Leap and keyboard sources
+//! produce, so a hardware controller can drive the app on its own.  The
+//! default [`MidiMapping`] is:
+//!
+//! | Message | Action |
+//! |---|---|
+//! | CC1 (mod wheel) | Tempo, 40–240 BPM |
+//! | CC7 (volume) | Velocity of upcoming notes |
+//! | Note 36 | Twist |
+//! | Note 37 | Scissors / snip |
+//! | Note 38 | Clap / start MIDI |
+//! | Note 39 | Un-clap / stop MIDI |
+//!
+//! In jam mode (`--jam`) every other note is a [`GestureEvent::JamNote`]:
+//! the app echoes it and, once the keyboard falls quiet, answers the
+//! phrase with as many notes of the stream, in the same scale and near the
+//! same pitches (see [`JamPhrase`]).
+
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::gesture::{GestureEvent, GestureSource};
+use crate::player::PortSelector;
+
+/// Name of the port created for [`PortSelector::Virtual`].
+pub const VIRTUAL_IN_NAME: &str = "Leap Spigot In";
+
+/// What an incoming MIDI message does.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MidiMapping {
+    /// Controller that sets the tempo across `tempo_range`.
+    pub tempo_cc:    u8,
+    pub tempo_range: (u32, u32),
+    /// Controller that sets the velocity of upcoming notes.
+    pub velocity_cc: u8,
+    /// Note numbers (e.g. drum pads) that trigger one-shot gestures.
+    pub pads:        Vec<(u8, GestureEvent)>,
+    /// Pass every other note on as a [`GestureEvent::JamNote`].
+    pub jam:         bool,
+}
+
+impl Default for MidiMapping {
+    fn default() -> Self {
+        MidiMapping {
+            tempo_cc:    1,
+            tempo_range: (40, 240),
+            velocity_cc: 7,
+            pads: vec![
+                (36, GestureEvent::Twist),
+                (37, GestureEvent::Scissors { name: String::new() }),
+                (38, GestureEvent::Clap),
+                (39, GestureEvent::Unclap),
+            ],
+            jam: false,
+        }
+    }
+}
+
+impl MidiMapping {
+    /// The gesture for one raw MIDI message, if it is mapped.  Channels are
+    /// ignored; Note Offs (including Note On with velocity 0) do nothing
+    /// except release a jam note.
+    pub fn translate(&self, msg: &[u8]) -> Option<GestureEvent> {
+        let (&status, data) = msg.split_first()?;
+        match (status & 0xF0, data) {
+            (0xB0, &[cc, value, ..]) if cc == self.tempo_cc => {
+                let (lo, hi) = self.tempo_range;
+                let bpm = lo as f32 + (hi as f32 - lo as f32) * value as f32 / 127.0;
+                Some(GestureEvent::Tempo { bpm: bpm.round() as u32 })
+            }
+            (0xB0, &[cc, value, ..]) if cc == self.velocity_cc => {
+                Some(GestureEvent::Expression { value: value as f32 / 127.0 })
+            }
+            (0x90, &[note, velocity, ..]) if velocity > 0 => {
+                match self.pads.iter().find(|(n, _)| *n == note) {
+                    Some((_, g)) => Some(g.clone()),
+                    None         => self.jam.then_some(GestureEvent::JamNote { pitch: note, velocity }),
+                }
+            }
+            (0x80 | 0x90, &[note, ..]) if self.jam && !self.pads.iter().any(|(n, _)| *n == note) => {
+                Some(GestureEvent::JamNote { pitch: note, velocity: 0 })
+            }
+            _ => None,
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// JamPhrase — listening for a phrase to answer
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Quiet after the last key is let go that ends a jam phrase.
+pub const PHRASE_GAP: Duration = Duration::from_millis(1200);
+
+/// Most notes a phrase is answered with.
+pub const MAX_ANSWER: usize = 16;
+
+/// The phrase being played on the keyboard in jam mode.
+#[derive(Clone, Debug, Default)]
+pub struct JamPhrase {
+    /// Pitches struck so far.
+    pitches: Vec<u8>,
+    /// Keys still down.
+    held:    Vec<u8>,
+    /// When a key last went down or up.
+    last:    Option<Instant>,
+}
+
+impl JamPhrase {
+    /// Note a key going down, or up if `velocity` is 0, at `now`.
+    pub fn note(&mut self, pitch: u8, velocity: u8, now: Instant) {
+        self.held.retain(|&p| p != pitch);
+        if velocity > 0 {
+            self.pitches.push(pitch);
+            self.held.push(pitch);
+        }
+        self.last = Some(now);
+    }
+
+    /// Once every key has been up for [`PHRASE_GAP`], the answer the
+    /// phrase is due — how many notes, and the pitch to keep them near
+    /// (the phrase's average) — after which listening starts afresh.
+    pub fn answer_due(&mut self, now: Instant) -> Option<(usize, u8)> {
+        let quiet = self.last.is_some_and(|t| now.duration_since(t) >= PHRASE_GAP);
+        if !quiet || !self.held.is_empty() || self.pitches.is_empty() { return None; }
+        let sum: usize = self.pitches.iter().map(|&p| p as usize).sum();
+        let around = ((sum + self.pitches.len() / 2) / self.pitches.len()) as u8;
+        let notes = self.pitches.len().min(MAX_ANSWER);
+        *self = JamPhrase::default();
+        Some((notes, around))
+    }
+}
+
+/// Gesture source driven by a MIDI controller.
+pub struct MidiInGestureSource {
+    pub port:    PortSelector,
+    pub mapping: MidiMapping,
+}
+
+impl GestureSource for MidiInGestureSource {
+    fn run(self: Box<Self>, tx: Sender<GestureEvent>) {
+        let MidiInGestureSource { port, mapping } = *self;
+        let callback = move |_stamp: u64, msg: &[u8], _: &mut ()| {
+            if let Some(event) = mapping.translate(msg) {
+                let _ = tx.send(event);
+            }
+        };
+
+        let mut midi_in = match midir::MidiInput::new("spigot_midi_input") {
+            Ok(m)  => m,
+            Err(e) => {
+                eprintln!("[midi-in] MIDI init error: {} — controller input disabled", e);
+                return;
+            }
+        };
+        midi_in.ignore(midir::Ignore::All);
+
+        let conn = if port == PortSelector::Virtual {
+            connect_virtual(midi_in, callback)
+        } else {
+            let ports = midi_in.ports();
+            let names: Vec<String> = ports.iter()
+                .map(|p| midi_in.port_name(p).unwrap_or_default())
+                .collect();
+            // Auto would prefer a synth; for input just take the first port.
+            let chosen = match port {
+                PortSelector::Auto => (!names.is_empty()).then_some(0),
+                _                  => port.choose(&names),
+            };
+            let Some(i) = chosen else {
+                eprintln!("[midi-in] No MIDI input port matches {:?} — controller input disabled", port);
+                eprintln!("[midi-in] Available: {}", names.join(", "));
+                return;
+            };
+            eprintln!("[midi-in] Listening on: {}", names[i]);
+            midi_in.connect(&ports[i], "spigot-control", callback, ())
+                .map_err(|e| e.to_string())
+        };
+
+        match conn {
+            // The connection stops when dropped, so hold it for the life of
+            // the app.
+            Ok(_conn) => loop { thread::sleep(Duration::from_secs(3600)); },
+            Err(e)    => eprintln!("[midi-in] Failed to connect: {} — controller input disabled", e),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn connect_virtual<F>(midi_in: midir::MidiInput, callback: F)
+    -> Result<midir::MidiInputConnection<()>, String>
+where
+    F: FnMut(u64, &[u8], &mut ()) + Send + 'static,
+{
+    use midir::os::unix::VirtualInput;
+    eprintln!("[midi-in] Created virtual MIDI port: {}", VIRTUAL_IN_NAME);
+    midi_in.create_virtual(VIRTUAL_IN_NAME, callback, ()).map_err(|e| e.to_string())
+}
+
+#[cfg(not(unix))]
+fn connect_virtual<F>(_midi_in: midir::MidiInput, _callback: F)
+    -> Result<midir::MidiInputConnection<()>, String>
+where
+    F: FnMut(u64, &[u8], &mut ()) + Send + 'static,
+{
+    Err("virtual MIDI ports are not supported on this platform".to_string())
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn controllers_map_to_tempo_and_velocity() {
+        let m = MidiMapping::default();
+        assert_eq!(m.translate(&[0xB0, 1, 0]),   Some(GestureEvent::Tempo { bpm: 40 }));
+        assert_eq!(m.translate(&[0xB3, 1, 127]), Some(GestureEvent::Tempo { bpm: 240 }));
+        assert_eq!(m.translate(&[0xB0, 7, 127]), Some(GestureEvent::Expression { value: 1.0 }));
+        assert_eq!(m.translate(&[0xB0, 64, 127]), None);
+    }
+
+    #[test]
+    fn pads_trigger_gestures_on_press_only() {
+        let m = MidiMapping::default();
+        assert_eq!(m.translate(&[0x99, 36, 100]), Some(GestureEvent::Twist));
+        assert_eq!(m.translate(&[0x90, 38, 1]),   Some(GestureEvent::Clap));
+        assert_eq!(m.translate(&[0x90, 38, 0]),   None);
+        assert_eq!(m.translate(&[0x80, 38, 64]),  None);
+        assert_eq!(m.translate(&[0x90, 60, 100]), None);
+        assert_eq!(m.translate(&[0xF8]),          None);
+    }
+
+    #[test]
+    fn jam_mode_passes_other_notes_on() {
+        let m = MidiMapping { jam: true, ..MidiMapping::default() };
+        assert_eq!(m.translate(&[0x90, 60, 100]), Some(GestureEvent::JamNote { pitch: 60, velocity: 100 }));
+        assert_eq!(m.translate(&[0x80, 60, 64]),  Some(GestureEvent::JamNote { pitch: 60, velocity: 0 }));
+        assert_eq!(m.translate(&[0x90, 60, 0]),   Some(GestureEvent::JamNote { pitch: 60, velocity: 0 }));
+        assert_eq!(m.translate(&[0x90, 38, 100]), Some(GestureEvent::Clap));
+        assert_eq!(m.translate(&[0x80, 38, 64]),  None);
+    }
+
+    #[test]
+    fn a_phrase_is_answered_once_the_keys_fall_quiet() {
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        let mut phrase = JamPhrase::default();
+        for (i, pitch) in [60, 64, 67].into_iter().enumerate() {
+            phrase.note(pitch, 90, at(i as u64 * 200));
+            phrase.note(pitch, 0, at(i as u64 * 200 + 150));
+        }
+        phrase.note(72, 90, at(600));
+        // Held down, the phrase isn't over however long it lasts.
+        assert_eq!(phrase.answer_due(at(5000)), None);
+        phrase.note(72, 0, at(5000));
+        assert_eq!(phrase.answer_due(at(5000) + PHRASE_GAP / 2), None);
+        assert_eq!(phrase.answer_due(at(5000) + PHRASE_GAP), Some((4, 66)));
+        assert_eq!(phrase.answer_due(at(9000)), None);
+    }
+}
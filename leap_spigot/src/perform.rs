@@ -0,0 +1,625 @@
+//! The performance itself, whichever frontend plays it.
+//!
+//! A [`Performer`] holds the pulled ribbon pair, the snippet tray and the
+//! playing settings, and turns each [`GestureEvent`] into changes to them
+//! and to the stream, and into a [`Cue`] for whatever sounds the notes:
+//! the desktop app hands cues to its player threads, and `leap_web`
+//! schedules the notes itself on the page clock.  Both time the notes with
+//! [`ticks_to_duration`] and [`release_gap`] and show them with
+//! [`Performer::show_note`].
+//!
+//! Gestures that need files, ports, windows, the undo history or other
+//! ribbon pairs are left to the app.
+
+use std::time::Duration;
+
+use dual_spigot::{BoxedSpigot, DualStream, SpigotConfig};
+use spigot_midi::GeneralMidi;
+use spigot_stream::stats::DigitStats;
+
+use crate::gesture::GestureEvent;
+use crate::ribbon::{Patch, PianoRoll, RibbonState, ScissorAnimation, SnippetTray, StitchPhase};
+use crate::visualizer::FLAT_PATCH_W;
+
+/// Ticks per quarter note the notes are counted in.
+pub const TPQ: u32 = 480;
+
+/// Tempo range the tempo gestures and launch settings keep to, in BPM.
+pub const MIN_TEMPO: u32 = 20;
+pub const MAX_TEMPO: u32 = 300;
+
+/// Zoom range for the ribbon patches.
+pub const MIN_ZOOM: f32 = 0.5;
+pub const MAX_ZOOM: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayState { Stopped, Playing, Paused }
+
+/// What a gesture asks of whatever sounds the notes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cue {
+    /// Start playing, or resume.
+    Play,
+    /// Stop or pause, releasing the notes sounding.
+    Stop,
+    /// All notes off on every channel, now.
+    Panic,
+    /// Play on this GM program.
+    Instrument(u8),
+    Velocity(u8),
+    Tempo(u32),
+    /// Play these (left, right) pairs next.
+    Splice(Vec<(u8, u8)>),
+}
+
+/// Emitted by the player for each note played, so the visualizer can
+/// animate the currently-playing patch.  With layers there is one for each
+/// layer's note, the right's last.
+#[derive(Clone, Debug)]
+pub struct NoteEvent {
+    pub pitch:    u8,
+    pub duration: u32,   // ticks
+    pub velocity: u8,
+    /// Tempo the note is played at, drift included.
+    pub bpm:      u32,
+    /// Stream positions at the time of play.
+    pub left_pos:  usize,
+    pub right_pos: usize,
+    /// The (left, right) digits played.
+    pub pair:      (u8, u8),
+    /// Whether the pair came from a spliced snippet rather than the stream.
+    pub spliced:   bool,
+    /// Whether this is the pair's first note (layers send two per pair).
+    pub first:     bool,
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Performer
+// ════════════════════════════════════════════════════════════════════════════
+
+pub struct Performer {
+    // ── ribbons ──────────────────────────────────────────────────────────
+    pub left_ribbon:  RibbonState,
+    pub right_ribbon: RibbonState,
+    pub stitch:       StitchPhase,
+
+    // ── snippet ───────────────────────────────────────────────────────────
+    pub tray:         SnippetTray,
+    pub scissor:      Option<ScissorAnimation>,
+    /// Left-stream positions `from..to` the next snip takes.
+    pub snip_marks:   Option<(usize, usize)>,
+    /// Snips taken, for naming those given no name.
+    snips:            usize,
+
+    // ── playback ─────────────────────────────────────────────────────────
+    pub play_state:   PlayState,
+    pub instrument:   u8,
+    pub tempo_bpm:    u32,
+    pub velocity:     u8,
+    /// Stream positions (left, right) of the note sounding now, and
+    /// whether it was spliced.
+    pub sounding:     Option<(usize, usize, bool)>,
+    /// Notes played recently, for the piano-roll panel.
+    pub roll:         PianoRoll,
+
+    // ── view ──────────────────────────────────────────────────────────────
+    /// Ribbon patch size multiplier, `MIN_ZOOM`–`MAX_ZOOM`.
+    pub zoom:         f32,
+    pub status:       String,
+}
+
+impl Performer {
+    /// A performance on `left_ribbon` and `right_ribbon`, stopped, played
+    /// on a piano at 120 BPM until told otherwise.
+    pub fn new(left_ribbon: RibbonState, right_ribbon: RibbonState) -> Self {
+        Performer {
+            status:       format!("Ready — Left: {}  Right: {}", left_ribbon.label, right_ribbon.label),
+            left_ribbon,
+            right_ribbon,
+            stitch:       StitchPhase::Unstitched,
+            tray:         SnippetTray::default(),
+            scissor:      None,
+            snip_marks:   None,
+            snips:        0,
+            play_state:   PlayState::Stopped,
+            instrument:   GeneralMidi::AcousticGrandPiano.program(),
+            tempo_bpm:    120,
+            velocity:     100,
+            sounding:     None,
+            roll:         PianoRoll::default(),
+            zoom:         1.0,
+        }
+    }
+
+    // ── process one GestureEvent ─────────────────────────────────────────
+
+    /// Act on `event` with the streams in `dual`, returning what the notes
+    /// should do about it.  Events for the app alone are ignored.
+    pub fn gesture(&mut self, dual: &mut DualStream, event: GestureEvent) -> Option<Cue> {
+        match event {
+            // ── Pull Left ─────────────────────────────────────────────────
+            GestureEvent::PullLeft { steps, velocity } => {
+                for _ in 0..steps {
+                    if let Some(d) = dual.left().next() {
+                        self.left_ribbon.pull(d, dual.left_pos());
+                    }
+                }
+                self.left_ribbon.kick(velocity);
+                self.status = format!("Pull LEFT ×{}  (vel={:.2})  pos={}", steps, velocity, dual.left_pos());
+                None
+            }
+
+            // ── Pull Right ────────────────────────────────────────────────
+            GestureEvent::PullRight { steps, velocity } => {
+                for _ in 0..steps {
+                    if let Some(d) = dual.right().next() {
+                        self.right_ribbon.pull(d, dual.right_pos());
+                    }
+                }
+                self.right_ribbon.kick(velocity);
+                self.status = format!("Pull RIGHT ×{}  (vel={:.2})  pos={}", steps, velocity, dual.right_pos());
+                None
+            }
+
+            // ── Twist ─────────────────────────────────────────────────────
+            GestureEvent::Twist => {
+                dual.twist();
+                std::mem::swap(&mut self.left_ribbon, &mut self.right_ribbon);
+                self.left_ribbon.label  = format!("{} base {}", dual.left_name(), dual.left_base());
+                self.right_ribbon.label = format!("{} base {}", dual.right_name(), dual.right_base());
+                self.status = format!("TWIST — Left now: {}  Right now: {}", self.left_ribbon.label, self.right_ribbon.label);
+                None
+            }
+
+            // ── Clap → begin MIDI ─────────────────────────────────────────
+            GestureEvent::Clap if self.play_state == PlayState::Stopped => {
+                self.play_state = PlayState::Playing;
+                self.stitch = StitchPhase::Stitching { progress: 0.0 };
+                self.status = "CLAP — MIDI playback started ♪".to_string();
+                Some(Cue::Play)
+            }
+
+            // ── Unclap → stop MIDI ────────────────────────────────────────
+            GestureEvent::Unclap if self.play_state != PlayState::Stopped => {
+                self.play_state = PlayState::Stopped;
+                self.stitch = StitchPhase::Unstitching { progress: 0.0 };
+                self.status = "UN-CLAP — MIDI playback stopped".to_string();
+                Some(Cue::Stop)
+            }
+
+            // ── Fist → pause / resume, keeping the stitch ─────────────────
+            GestureEvent::Pause => match self.play_state {
+                PlayState::Playing => {
+                    self.play_state = PlayState::Paused;
+                    self.status = "PAUSED — fist again to resume".to_string();
+                    Some(Cue::Stop)
+                }
+                PlayState::Paused => {
+                    self.play_state = PlayState::Playing;
+                    self.status = "RESUMED ♪".to_string();
+                    Some(Cue::Play)
+                }
+                PlayState::Stopped => None,
+            },
+
+            // ── Panic → silence everything ────────────────────────────────
+            GestureEvent::Panic => {
+                if self.play_state != PlayState::Stopped {
+                    self.play_state = PlayState::Stopped;
+                    self.stitch = StitchPhase::Unstitching { progress: 0.0 };
+                }
+                self.status = "PANIC — all notes off".to_string();
+                Some(Cue::Panic)
+            }
+
+            // ── Scissors → snip ───────────────────────────────────────────
+            GestureEvent::Scissors { name } => {
+                self.snip(dual, &name);
+                None
+            }
+
+            // ── Step through GM programs ──────────────────────────────────
+            GestureEvent::CycleInstrument { step } => {
+                self.instrument = (self.instrument as i16 + step as i16).rem_euclid(128) as u8;
+                let name = GeneralMidi::from_program(self.instrument).map_or("?", |g| g.name());
+                self.status = format!("INSTRUMENT {} — {}", self.instrument, name);
+                Some(Cue::Instrument(self.instrument))
+            }
+
+            // ── Hand height → velocity ────────────────────────────────────
+            GestureEvent::Expression { value } => {
+                self.velocity = expression_velocity(value);
+                Some(Cue::Velocity(self.velocity))
+            }
+
+            // ── Tempo from a controller ───────────────────────────────────
+            GestureEvent::Tempo { bpm } => {
+                self.tempo_bpm = bpm.max(1);
+                self.status = format!("TEMPO {} BPM", self.tempo_bpm);
+                Some(Cue::Tempo(self.tempo_bpm))
+            }
+            GestureEvent::NudgeTempo { step } => {
+                let bpm = (self.tempo_bpm as i32 + step).clamp(MIN_TEMPO as i32, MAX_TEMPO as i32) as u32;
+                self.gesture(dual, GestureEvent::Tempo { bpm })
+            }
+
+            // ── Pinch → ribbon zoom ───────────────────────────────────────
+            GestureEvent::Zoom { factor } => {
+                self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+                self.status = format!("ZOOM ×{:.2} — {} px patches", self.zoom, self.patch_width().round());
+                None
+            }
+
+            // ── Swipe → scrub both streams ────────────────────────────────
+            GestureEvent::Scrub { steps } => {
+                dual.scrub(steps);
+                self.refill_ribbons(dual);
+                self.recount_stats(dual);
+                self.status = format!("SCRUB {:+}  L-pos={}  R-pos={}", steps, dual.left_pos(), dual.right_pos());
+                None
+            }
+
+            // ── Tray selection ────────────────────────────────────────────
+            GestureEvent::SelectSnippet { step } => {
+                self.tray.select(step);
+                self.show_selected();
+                None
+            }
+            GestureEvent::PickSnippet { index } => {
+                self.tray.pick(index);
+                self.show_selected();
+                None
+            }
+
+            // ── Mouse on the ribbons ──────────────────────────────────────
+            GestureEvent::InspectPatch { right, index } => {
+                let (side, ribbon) = if right { ("RIGHT", &self.right_ribbon) }
+                                     else     { ("LEFT", &self.left_ribbon) };
+                if let Some(patch) = ribbon.patches.get(index) {
+                    self.status = format!("{} {} — digit {} at position {} (seen {}× in {})",
+                        side, ribbon.label, patch.digit, patch.position,
+                        ribbon.stats.count(patch.digit), ribbon.stats.total());
+                }
+                None
+            }
+            GestureEvent::MarkPatches { from, to } => {
+                let patches = &self.left_ribbon.patches;
+                let to = to.min(patches.len());
+                if from < to {
+                    let marks = (patches[from].position, patches[to - 1].position + 1);
+                    self.snip_marks = Some(marks);
+                    self.status = format!("MARKED {} pairs [{}, {}) — snip to keep them", to - from, marks.0, marks.1);
+                }
+                None
+            }
+
+            // ── Tray management ───────────────────────────────────────────
+            GestureEvent::MoveSnippet { step } => {
+                self.tray.move_selected(step);
+                None
+            }
+            GestureEvent::RenameSnippet { name } => {
+                if let Some(old) = self.tray.rename_selected(&name) {
+                    if !self.tray.entries.iter().any(|e| e.name == old) {
+                        if let Err(e) = dual.rename_snippet(&old, &name) {
+                            self.status = format!("Rename failed: {}", e);
+                            return None;
+                        }
+                    }
+                    self.status = format!("RENAMED \"{}\" → \"{}\"", old, name);
+                }
+                None
+            }
+            GestureEvent::DeleteSnippet => {
+                if let Some(entry) = self.tray.delete_selected() {
+                    if !self.tray.entries.iter().any(|e| e.name == entry.name) {
+                        dual.remove_snippet(&entry.name);
+                    }
+                    self.status = format!("DELETED \"{}\"", entry.name);
+                }
+                None
+            }
+
+            // ── Splice a tray snippet into the performance ────────────────
+            GestureEvent::Splice => {
+                let Some(entry) = self.tray.selected_entry() else {
+                    self.status = "Tray is empty — snip something first".to_string();
+                    return None;
+                };
+                let pairs: Vec<_> = entry.patches.iter().map(|(l, r)| (l.digit, r.digit)).collect();
+                self.status = format!("SPLICE \"{}\" — {} pairs next", entry.name, pairs.len());
+                Some(Cue::Splice(pairs))
+            }
+
+            // A clap while playing, an unclap while stopped, and what's
+            // the app's: files, ports, windows, undo and the other pairs.
+            _ => None,
+        }
+    }
+
+    fn show_selected(&mut self) {
+        if let Some(entry) = self.tray.selected_entry() {
+            self.status = format!("TRAY → \"{}\"", entry.name);
+        }
+    }
+
+    /// Snip the marked patches into the tray as `name` if they're still in
+    /// view, else the pairs from the left position back across the
+    /// ribbons.  Snips with no name are numbered.
+    pub fn snip(&mut self, dual: &mut DualStream, name: &str) {
+        self.snips += 1;
+        let name = if name.is_empty() { format!("snip {}", self.snips) } else { name.to_string() };
+        let marked = self.marked_patches().zip(self.snip_marks.take());
+        let ((first, last), (from, to)) = marked.unwrap_or_else(|| {
+            let len = self.left_ribbon.patches.len();
+            let pos = dual.left_pos();
+            ((0, len), (pos.saturating_sub(len), pos))
+        });
+        let count = to - from;
+
+        if let Err(e) = dual.snip(&name, from, to) {
+            self.status = format!("Snip failed: {}", e);
+            return;
+        }
+
+        let pairs: Vec<(Patch, Patch)> = self.left_ribbon.patches[first..last].iter()
+            .zip(self.right_ribbon.patches.iter().skip(first))
+            .map(|(l, r)| (l.clone(), r.clone()))
+            .collect();
+        self.tray.deposit(&name, pairs);
+        self.scissor = Some(ScissorAnimation::new(first, count.min(self.left_ribbon.capacity)));
+        self.status = format!("SNIP \"{}\" — {} pairs [{}, {}) saved to tray", name, count, from, to);
+    }
+
+    /// Patches `first..last` of the ribbons marked for the next snip, while
+    /// they're still in view.
+    pub fn marked_patches(&self) -> Option<(usize, usize)> {
+        let (from, to) = self.snip_marks?;
+        let first = self.left_ribbon.patch_at(from, false)?;
+        let last  = self.left_ribbon.patch_at(to - 1, false)?;
+        Some((first, last + 1))
+    }
+
+    // ── ribbons ───────────────────────────────────────────────────────────
+
+    /// Rebuild both ribbons from the digits leading up to the current
+    /// stream positions, as after a scrub.  Sides fed by a custom source
+    /// are left as they are.
+    pub fn refill_ribbons(&mut self, dual: &DualStream) {
+        let sides = [
+            (&mut self.left_ribbon,  dual.left_config(),  dual.left_pos()),
+            (&mut self.right_ribbon, dual.right_config(), dual.right_pos()),
+        ];
+        for (ribbon, config, pos) in sides {
+            let Some(config) = config else { continue };
+            let start = pos.saturating_sub(ribbon.capacity);
+            let mut spigot = BoxedSpigot::from_config(config);
+            spigot.seek(start);
+            ribbon.patches.clear();
+            ribbon.scroll_px = 0.0;
+            for p in start..pos {
+                if let Some(d) = spigot.next_digit() { ribbon.push(d, p + 1); }
+            }
+        }
+    }
+
+    /// Count the digits of both streams up to their current positions
+    /// afresh, as after a jump.
+    pub fn recount_stats(&mut self, dual: &DualStream) {
+        let sides = [
+            (&mut self.left_ribbon,  dual.left_config(),  dual.left_pos()),
+            (&mut self.right_ribbon, dual.right_config(), dual.right_pos()),
+        ];
+        for (ribbon, config, pos) in sides {
+            let Some(config) = config else { continue };
+            let digits = BoxedSpigot::from_config(config).take_n(pos);
+            ribbon.stats = DigitStats::from_digits(config.base, digits);
+        }
+    }
+
+    /// Size both ribbons to `capacity` patches as the frame is resized or
+    /// the patches zoomed: shrinking drops the oldest patches, growing
+    /// refills from the streams.
+    pub fn fit_ribbons(&mut self, dual: &DualStream, capacity: usize) {
+        let grew = capacity > self.left_ribbon.capacity || capacity > self.right_ribbon.capacity;
+        for ribbon in [&mut self.left_ribbon, &mut self.right_ribbon] {
+            fit_ribbon(ribbon, capacity);
+        }
+        if grew { self.refill_ribbons(dual); }
+    }
+
+    /// Width of a flat ribbon patch at the current zoom, in pixels.
+    pub fn patch_width(&self) -> f32 {
+        FLAT_PATCH_W as f32 * self.zoom
+    }
+
+    // ── notes ─────────────────────────────────────────────────────────────
+
+    /// Show a note as it starts: on the piano roll, its pair on the
+    /// ribbons, and on the status line.
+    pub fn show_note(&mut self, note: &NoteEvent) {
+        self.roll.push(note.pitch, note.velocity, roll_frames(note.duration, note.bpm), note.spliced);
+        if note.first {
+            show_played(&mut self.left_ribbon, &mut self.right_ribbon, note);
+        }
+        self.sounding = Some((note.left_pos, note.right_pos, note.spliced));
+        self.status = format!(
+            "♪ pitch={} duration={}t  vel={}  L-pos={}  R-pos={}  {} BPM",
+            note.pitch, note.duration, note.velocity, note.left_pos, note.right_pos, note.bpm
+        );
+    }
+
+    /// The sounding note's patches on the (left, right) ribbons, where
+    /// they're in view.
+    pub fn note_highlight(&self) -> (Option<usize>, Option<usize>) {
+        // Pulls and scrubs move the patches; find the note's afresh.
+        self.sounding.map_or((None, None), |(l, r, spliced)| {
+            (self.left_ribbon.patch_at(l, spliced), self.right_ribbon.patch_at(r, spliced))
+        })
+    }
+
+    // ── per frame ─────────────────────────────────────────────────────────
+
+    /// Animate the ribbons, stitch, scissors, tray and piano roll a frame.
+    pub fn tick(&mut self) {
+        let width = self.patch_width();
+        self.left_ribbon.tick(width);
+        self.right_ribbon.tick(width);
+        self.stitch.tick();
+        if let Some(sc) = self.scissor.as_mut() {
+            sc.tick();
+            if sc.done() { self.scissor = None; }
+        }
+        self.tray.tick();
+        self.roll.tick();
+        if self.play_state == PlayState::Stopped { self.sounding = None; }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Ribbons
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Ribbons for `left` and `right`, filled with their first digits so
+/// they're not empty on launch.
+pub fn prefilled_ribbons(left: SpigotConfig, right: SpigotConfig, capacity: usize) -> (RibbonState, RibbonState) {
+    let mut left_ribbon  = RibbonState::new(capacity, left.base,  &side_label(left));
+    let mut right_ribbon = RibbonState::new(capacity, right.base, &side_label(right));
+    let mut pre = DualStream::from_configs(left, right);
+    for i in 0..capacity {
+        if let Some((l, r)) = pre.zip_next() {
+            left_ribbon.push(l, i + 1);
+            right_ribbon.push(r, i + 1);
+        }
+    }
+    (left_ribbon, right_ribbon)
+}
+
+/// Put a pair the player has just played on the ribbons: a spliced pair
+/// after the stream digit it follows, a stream pair unless already shown.
+pub fn show_played(left: &mut RibbonState, right: &mut RibbonState, note: &NoteEvent) {
+    if note.spliced {
+        left.push(note.pair.0, note.left_pos);
+        right.push(note.pair.1, note.right_pos);
+    } else {
+        left.show_played(note.pair.0, note.left_pos);
+        right.show_played(note.pair.1, note.right_pos);
+    }
+}
+
+/// Size `ribbon` to `capacity` patches, dropping the oldest.
+pub fn fit_ribbon(ribbon: &mut RibbonState, capacity: usize) {
+    ribbon.capacity = capacity;
+    let excess = ribbon.patches.len().saturating_sub(capacity);
+    ribbon.patches.drain(..excess);
+}
+
+pub fn side_label(config: SpigotConfig) -> String {
+    format!("{} base {}", config.constant.name(), config.base)
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Note timing
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Length of `ticks` given TPQ and BPM, floored at 50 ms.
+pub fn ticks_to_duration(ticks: u32, tpq: u32, bpm: u32) -> Duration {
+    // µs = ticks * 60_000_000 / (bpm * tpq), without rounding per beat
+    let micros = ticks as u64 * 60_000_000 / (bpm.max(1) as u64 * tpq.max(1) as u64);
+    Duration::from_micros(micros).max(Duration::from_millis(50))
+}
+
+/// Silence left before the next note, so repeated pitches re-strike: 5% of
+/// the note's `length`, at least 5 ms and at most half the note.
+pub fn release_gap(length: Duration) -> Duration {
+    (length / 20).max(Duration::from_millis(5)).min(length / 2)
+}
+
+/// Frames (at 60 fps) a note of `ticks` lasts at `bpm`.
+pub fn roll_frames(ticks: u32, bpm: u32) -> u32 {
+    (ticks as u64 * 3600 / (bpm.max(1) as u64 * TPQ as u64)) as u32
+}
+
+/// Expression level 0.0–1.0 → note velocity, from pianissimo (20) to
+/// fortissimo (127).
+pub fn expression_velocity(value: f32) -> u8 {
+    (20.0 + value.clamp(0.0, 1.0) * 107.0).round() as u8
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spigot_stream::Constant;
+
+    fn performer() -> (Performer, DualStream) {
+        let (left, right) = (SpigotConfig::new(Constant::Champernowne, 10), SpigotConfig::new(Constant::ThueMorse, 2));
+        let (l, r) = prefilled_ribbons(left, right, 10);
+        (Performer::new(l, r), DualStream::from_configs(left, right))
+    }
+
+    #[test]
+    fn ticks_to_duration_quarter_at_120bpm() {
+        // At 120 BPM, 480 ticks (quarter note) = 500 ms
+        assert_eq!(ticks_to_duration(480, 480, 120), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn ticks_to_duration_eighth_at_120bpm() {
+        // 240 ticks at 120 BPM = 250 ms
+        assert_eq!(ticks_to_duration(240, 480, 120), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn ticks_to_duration_min_floor() {
+        // Very short durations floor to 50ms
+        assert_eq!(ticks_to_duration(1, 480, 120), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn ticks_to_duration_keeps_sub_millisecond_precision() {
+        // 70 BPM: a beat is 857.142… ms, not a rounded 857 ms
+        assert_eq!(ticks_to_duration(480, 480, 70), Duration::from_micros(857_142));
+    }
+
+    #[test]
+    fn release_gaps_stay_inside_the_note() {
+        assert_eq!(release_gap(Duration::from_millis(500)), Duration::from_millis(25));
+        assert_eq!(release_gap(Duration::from_millis(50)), Duration::from_millis(5));
+        assert_eq!(release_gap(Duration::from_millis(4)), Duration::from_millis(2));
+    }
+
+    #[test]
+    fn gestures_cue_the_notes() {
+        let (mut p, mut dual) = performer();
+        assert_eq!(p.gesture(&mut dual, GestureEvent::Clap), Some(Cue::Play));
+        assert_eq!(p.gesture(&mut dual, GestureEvent::Clap), None);
+        assert_eq!(p.gesture(&mut dual, GestureEvent::NudgeTempo { step: 400 }), Some(Cue::Tempo(MAX_TEMPO)));
+        assert_eq!(p.gesture(&mut dual, GestureEvent::CycleInstrument { step: -1 }), Some(Cue::Instrument(127)));
+        assert_eq!(p.gesture(&mut dual, GestureEvent::Pause), Some(Cue::Stop));
+        assert_eq!(p.gesture(&mut dual, GestureEvent::Pause), Some(Cue::Play));
+        // What's the app's is left to it.
+        assert_eq!(p.gesture(&mut dual, GestureEvent::Undo), None);
+        assert_eq!(p.play_state, PlayState::Playing);
+    }
+
+    #[test]
+    fn unnamed_snips_are_numbered() {
+        let (mut p, mut dual) = performer();
+        p.gesture(&mut dual, GestureEvent::PullLeft { steps: 4, velocity: 0.5 });
+        p.gesture(&mut dual, GestureEvent::Scissors { name: String::new() });
+        p.gesture(&mut dual, GestureEvent::Scissors { name: "riff".into() });
+        p.gesture(&mut dual, GestureEvent::Scissors { name: String::new() });
+        let names: Vec<&str> = p.tray.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["snip 1", "riff", "snip 3"]);
+        assert_eq!(dual.get_snippet("snip 1").map(|s| s.len()), Some(4));
+
+        p.gesture(&mut dual, GestureEvent::PickSnippet { index: 1 });
+        let riff = p.tray.entries[1].patches.len();
+        let cue = p.gesture(&mut dual, GestureEvent::Splice);
+        assert!(matches!(cue, Some(Cue::Splice(pairs)) if pairs.len() == riff));
+    }
+}
@@ -0,0 +1,136 @@
+//! Recording of live playback.
+//!
+//! The player thread logs every note it sounds, with the wall-clock times
+//! of its Note On and Note Off, into a [`Performance`].  Saving converts
+//! the log to a spigot_midi [`MidiTrack`], so a gesture-driven
+//! improvisation can be kept as a standard MIDI file — pauses between
+//! clap and un-clap included.
+
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use spigot_midi::{MidiTrack, Note};
+
+/// Ticks-per-quarter of saved performances (matches the player).
+const TPQ: u16 = 480;
+
+/// One note as it was actually played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlayedNote {
+    /// Time from the first recorded note to this Note On.
+    pub onset:    Duration,
+    /// Time from Note On to Note Off.
+    pub length:   Duration,
+    pub pitch:    u8,
+    pub velocity: u8,
+}
+
+/// Every note played since the recording was started or last cleared.
+#[derive(Clone, Debug)]
+pub struct Performance {
+    start:          Option<Instant>,
+    notes:          Vec<PlayedNote>,
+    /// Program written to the saved file: the one in effect when saving.
+    pub instrument: u8,
+    pub channel:    u8,
+}
+
+impl Performance {
+    pub fn new(instrument: u8, channel: u8) -> Self {
+        Performance { start: None, notes: Vec::new(), instrument, channel }
+    }
+
+    /// Log a note sounded from `on` to `off`.
+    pub fn record(&mut self, on: Instant, off: Instant, pitch: u8, velocity: u8) {
+        let start = *self.start.get_or_insert(on);
+        self.notes.push(PlayedNote {
+            onset:  on.saturating_duration_since(start),
+            length: off.saturating_duration_since(on),
+            pitch,
+            velocity,
+        });
+    }
+
+    pub fn notes(&self) -> &[PlayedNote] { &self.notes }
+    pub fn len(&self) -> usize           { self.notes.len() }
+    pub fn is_empty(&self) -> bool       { self.notes.is_empty() }
+
+    /// Forget everything recorded so far.
+    pub fn clear(&mut self) {
+        self.start = None;
+        self.notes.clear();
+    }
+
+    /// The recording as a track at `tempo_bpm`.
+    ///
+    /// Times are converted at that tempo, so the file plays back exactly as
+    /// it was heard; the tempo only decides where the barlines fall.
+    pub fn to_track(&self, tempo_bpm: u32) -> MidiTrack {
+        let ticks_per_sec = tempo_bpm.max(1) as f64 / 60.0 * TPQ as f64;
+        let ticks = |d: Duration| (d.as_secs_f64() * ticks_per_sec).round() as u64;
+
+        let notes = self.notes.iter().enumerate().map(|(i, n)| {
+            let gate = ticks(n.length).max(1) as u32;
+            let duration = match self.notes.get(i + 1) {
+                Some(next) => (ticks(next.onset) - ticks(n.onset)) as u32,
+                None       => gate,
+            };
+            Note { gate, ..Note::new(n.pitch, duration, n.velocity) }
+        }).collect();
+
+        let mut track = MidiTrack::new(notes, TPQ, tempo_bpm as f64);
+        track.instrument  = self.instrument;
+        track.channel     = self.channel;
+        track.description = format!("leap_spigot performance – {} notes", self.len());
+        track
+    }
+
+    /// Write the recording to `path` as a standard MIDI file.
+    pub fn save<P: AsRef<Path>>(&self, path: P, tempo_bpm: u32) -> io::Result<()> {
+        if self.is_empty() {
+            return Err(io::Error::other("nothing has been played yet"));
+        }
+        self.to_track(tempo_bpm).write_file(path)
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn real_timing_becomes_ticks() {
+        // 120 BPM: 500 ms = one quarter = 480 ticks
+        let t0 = Instant::now();
+        let ms = |n: u64| t0 + Duration::from_millis(n);
+        let mut perf = Performance::new(11, 2);
+        perf.record(ms(0),    ms(250),  60, 100);
+        perf.record(ms(500),  ms(1000), 64, 90);
+        perf.record(ms(3000), ms(3125), 67, 80);   // after a pause
+
+        let track = perf.to_track(120);
+        let n: Vec<(u8, u32, u32, u8)> = track.notes.iter()
+            .map(|n| (n.pitch, n.duration, n.gate, n.velocity))
+            .collect();
+        assert_eq!(n, vec![(60, 480, 240, 100), (64, 2400, 480, 90), (67, 120, 120, 80)]);
+        assert_eq!((track.instrument, track.channel), (11, 2));
+        assert!((track.seconds_at(track.end_tick()) - 3.125).abs() < 1e-9);
+    }
+
+    #[test]
+    fn clear_restarts_the_clock() {
+        let t0 = Instant::now();
+        let mut perf = Performance::new(0, 0);
+        perf.record(t0, t0 + Duration::from_millis(100), 60, 100);
+        perf.clear();
+        perf.record(t0 + Duration::from_secs(5), t0 + Duration::from_secs(6), 62, 100);
+        assert_eq!(perf.notes()[0].onset, Duration::ZERO);
+        assert!(perf.save("/nonexistent/x.mid", 120).is_err());
+        assert!(Performance::new(0, 0).save("unused.mid", 120).is_err());
+    }
+}
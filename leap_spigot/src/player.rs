@@ -2,13 +2,67 @@
 //!
 //! Notes are generated on the fly from the DualStream zip and sent to a
 //! MIDI output port.  Playback can be started and stopped via channels.
+//! The stream is a [`SharedStream`] the ribbons pull from too, so the
+//! player carries on from wherever the pulls have left the cursors and
+//! every digit is either pulled or played, never both.
+//! Everything played is also recorded into a shared [`Performance`].
+//!
+//! Notes are scheduled against absolute deadlines on the monotonic clock:
+//! each onset is the previous onset plus the note's length, so oversleeping,
+//! command handling and MIDI I/O never push later notes back.  Note Offs are
+//! scheduled too rather than slept through, so notes may overlap, and
+//! between deadlines the thread waits on its command channel: commands
+//! take effect as they arrive, and Stop silences at once instead of after
+//! the current note.  The final stretch before a deadline is spun rather
+//! than slept for sub-millisecond accuracy.
+//!
+//! While playing, the player also sends MIDI beat clock (24 pulses per
+//! quarter note) on the same schedule, with Start, Continue and Stop on
+//! clap and un-clap, so external drum machines and DAWs can follow.
+//!
+//! Velocities come from [`Dynamics`]: a [`VelocityMap`] and phrase
+//! [`Curve`] as `spigot_midi` uses for file export, so live playback has the
+//! same dynamics as a composed track, scaled by the live velocity (hand
+//! height, CC7).
+//!
+//! With [`Layer`]s set, the two streams play a voice each instead of one
+//! merged line: each pair sounds the left digit's note on the left layer's
+//! channel and instrument and the right digit's on the right layer's (say,
+//! a bass line under a lead), both for the length the left digit gives.
+//!
+//! With a [`TempoDrift`] set, the tempo breathes with the digits: every few
+//! notes it is nudged up by high digits and down by low ones, never
+//! straying further than a set range from the tempo asked for.
+//!
+//! A timbre controller (say CC74, brightness) set from a hand's roll is
+//! sent at most every [`TIMBRE_INTERVAL`] while playing, whenever it has
+//! changed, on every channel in use.
+//!
+//! For jamming, notes from an input keyboard are echoed on the lead channel
+//! as they're played, and [`Player::answer`] replies to a phrase with the
+//! next few pairs of the stream, each moved by octaves to lie within a
+//! tritone of the phrase so the reply sits in the player's register.
+//!
+//! With a [`BeatSync`] attached, the schedule follows the shared timeline
+//! instead: playback starts on the next bar line and each onset falls on its
+//! beat of the session.  If the timeline has a transport (JACK's), the
+//! player starts and stops as it does.
 
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use spigot_midi::{PitchMap, DurationMap};
-use dual_spigot::DualStream;
+use spigot_midi::{GeneralMidi, VelocityMap, Curve};
+use spigot_midi::mapper::{DurationMapper, PitchMapper};
+use dual_spigot::SharedStream;
+
+use crate::error::AppError;
+use crate::link::{self, BeatSync};
+use crate::performance::Performance;
+use crate::perform::{release_gap, ticks_to_duration, NoteEvent, TPQ};
 
 // ════════════════════════════════════════════════════════════════════════════
 // PlayerCommand — sent to the playback thread
@@ -17,57 +71,246 @@ use dual_spigot::DualStream;
 pub enum PlayerCommand {
     /// Begin streaming notes.
     Play,
-    /// Stop after the current note.
+    /// Stop, releasing any notes still sounding.
     Stop,
     /// Change instrument (MIDI program 0–127).
     SetInstrument(u8),
-    /// Change tempo (BPM).
+    /// Change the velocity of upcoming notes (1–127), the level the
+    /// velocity map is scaled to.
+    SetVelocity(u8),
+    /// Change tempo (BPM); with a sync attached, proposes it to the session.
     SetTempo(u32),
+    /// Follow a shared beat timeline, or the player's own clock if `None`.
+    Sync(Option<Arc<dyn BeatSync>>),
+    /// Stop, and send All Notes Off and All Sound Off on every channel.
+    Panic,
+    /// Switch to another MIDI output port.
+    SetPort(PortSelector),
+    /// Play these (left, right) digit pairs from the next beat, then carry
+    /// on with the stream where it left off.
+    Splice(Vec<(u8, u8)>),
+    /// Take back the latest splice: drop it if it is still waiting for its
+    /// beat, otherwise skip whatever of it is left to play.
+    Unsplice,
+    /// Give the (left, right) streams a voice each, or play one merged
+    /// line if `None`.
+    SetLayers(Option<[Layer; 2]>),
+    /// Let the digits nudge the tempo, or hold it steady if `None`.
+    SetDrift(Option<TempoDrift>),
+    /// Set MIDI controller `cc` to `value` on the channels in use, sent
+    /// while playing.
+    SetTimbre { cc: u8, value: u8 },
+    /// Sound (or with velocity 0 release) a note played on an input
+    /// keyboard, on the lead channel.
+    Echo { pitch: u8, velocity: u8 },
+    /// If stopped, play the next `notes` pairs, each moved by octaves to
+    /// lie within a tritone of `around`, then stop again.
+    Answer { notes: usize, around: u8 },
     /// Terminate the thread.
     Quit,
 }
 
 // ════════════════════════════════════════════════════════════════════════════
-// NoteEvent — sent back to the visualizer for highlighting
+// Dynamics — how loud each note plays
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Note velocities, worked out as the composer does for file export and
+/// then scaled by a live level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dynamics {
+    /// Level the mapped velocities are scaled to (1–127); at 100 they play
+    /// as mapped.
+    pub velocity:     u8,
+    /// Base velocity for each digit pair.
+    pub velocity_map: VelocityMap,
+    /// Phrase-level shaping on top of the map.
+    pub curve:        Curve,
+    /// Total velocity swing of a shaped curve.
+    pub depth:        u8,
+}
+
+impl Dynamics {
+    /// Every note at `velocity`, unshaped.
+    pub fn fixed(velocity: u8) -> Self {
+        Dynamics { velocity, velocity_map: VelocityMap::Fixed(100), curve: Curve::Flat, depth: 24 }
+    }
+
+    /// Velocity of the `index`-th note, from the pair `(left, right)`,
+    /// kept within 1–127.
+    pub fn velocity_for(&self, index: usize, (left, right): (u8, u8)) -> u8 {
+        let base = self.velocity_map.velocity_for(left, right) as i32 * self.velocity as i32 / 100;
+        (base + self.curve.offset(index, self.depth)).clamp(1, 127) as u8
+    }
+}
+
+/// Parse a phrase curve: `flat`, or `crescendo`, `decrescendo` or `swell`
+/// with an optional phrase length in notes (`swell:16`; 8 if left out).
+pub fn parse_curve(s: &str) -> Option<Curve> {
+    let (name, len) = s.split_once(':').unwrap_or((s, "8"));
+    let len: usize = len.trim().parse().ok().filter(|&n| n > 0)?;
+    match name.trim().to_ascii_lowercase().as_str() {
+        "flat"        => Some(Curve::Flat),
+        "crescendo"   => Some(Curve::Crescendo(len)),
+        "decrescendo" => Some(Curve::Decrescendo(len)),
+        "swell"       => Some(Curve::Swell(len)),
+        _             => None,
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Layer — one stream's own voice
+// ════════════════════════════════════════════════════════════════════════════
+
+/// The voice one stream plays when the streams are layered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layer {
+    /// GM program (0–127).
+    pub instrument: u8,
+    /// MIDI channel (0–15).
+    pub channel:    u8,
+    /// Semitones added to the pitch map's notes, e.g. −24 for a bass line.
+    pub transpose:  i8,
+}
+
+impl Layer {
+    /// `pitch` moved by `transpose`, kept within MIDI's 0–127.
+    pub fn pitch(&self, pitch: u8) -> u8 {
+        (pitch as i16 + self.transpose as i16).clamp(0, 127) as u8
+    }
+}
+
+/// A bass line from the left stream on channel 1 under a lead from the
+/// right on channel 0 with `lead` as its instrument.
+pub fn bass_and_lead(bass: u8, lead: u8) -> [Layer; 2] {
+    [
+        Layer { instrument: bass, channel: 1, transpose: -24 },
+        Layer { instrument: lead, channel: 0, transpose: 0 },
+    ]
+}
+
+/// GM program [`bass_and_lead`] is usually given for the bass.
+pub const DEFAULT_BASS: u8 = GeneralMidi::AcousticBass as u8;
+
+// ════════════════════════════════════════════════════════════════════════════
+// TempoDrift — tempo automation from the digits
 // ════════════════════════════════════════════════════════════════════════════
 
-/// Emitted by the player for each note played, so the visualizer can
-/// animate the currently-playing patch.
-#[derive(Clone, Debug)]
-pub struct NoteEvent {
-    pub pitch:    u8,
-    pub duration: u32,   // ticks
-    pub velocity: u8,
-    /// Stream positions at the time of play.
-    pub left_pos:  usize,
-    pub right_pos: usize,
+/// How the digits move the tempo while free-running (a shared timeline
+/// keeps its own).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TempoDrift {
+    /// Notes between nudges.
+    pub every: usize,
+    /// Largest nudge, in BPM, when the last `every` pairs were all at the
+    /// top (or bottom) of their bases.
+    pub step:  f64,
+    /// Furthest the tempo may drift from the one set, as a fraction of it
+    /// (0.2 = ±20%).
+    pub range: f64,
+}
+
+impl Default for TempoDrift {
+    fn default() -> Self {
+        TempoDrift { every: 8, step: 4.0, range: 0.2 }
+    }
+}
+
+impl TempoDrift {
+    /// `tempo` nudged by `levels` — recent pairs, each 0.0 (lowest digits)
+    /// to 1.0 (highest) — and kept within `range` of `set`.
+    pub fn nudge(&self, tempo: f64, set: u32, levels: &[f64]) -> f64 {
+        if levels.is_empty() { return tempo; }
+        let mean = levels.iter().sum::<f64>() / levels.len() as f64;
+        let set = set.max(1) as f64;
+        (tempo + (mean - 0.5) * 2.0 * self.step)
+            .clamp(set * (1.0 - self.range), set * (1.0 + self.range))
+            .max(1.0)
+    }
+}
+
+/// Where a pair sits between its bases' lowest and highest digits, 0.0–1.0.
+fn pair_level((left, right): (u8, u8), (left_base, right_base): (u8, u8)) -> f64 {
+    let level = |d: u8, base: u8| d as f64 / (base.max(2) - 1) as f64;
+    (level(left, left_base) + level(right, right_base)) / 2.0
 }
 
 // ════════════════════════════════════════════════════════════════════════════
 // MidiOutput — abstraction over midir / null (for testing)
 // ════════════════════════════════════════════════════════════════════════════
 
-trait MidiOut: Send {
+pub(crate) trait MidiOut: Send {
     fn program_change(&mut self, channel: u8, program: u8);
     fn note_on(&mut self,  channel: u8, note: u8, velocity: u8);
     fn note_off(&mut self, channel: u8, note: u8);
+    fn control_change(&mut self, channel: u8, controller: u8, value: u8);
+    /// Send a one-byte system real-time message (clock, start, stop…).
+    fn realtime(&mut self, status: u8);
+    /// Why sending failed, if it has since the last call.
+    fn take_fault(&mut self) -> Option<String> { None }
+}
+
+// System real-time status bytes
+const CLOCK:    u8 = 0xF8;
+const START:    u8 = 0xFA;
+const CONTINUE: u8 = 0xFB;
+const STOP:     u8 = 0xFC;
+
+// Channel mode controllers
+const ALL_SOUND_OFF: u8 = 120;
+const ALL_NOTES_OFF: u8 = 123;
+
+/// Silence every channel, including notes started by other programs or
+/// left hanging by a killed player.
+fn panic(midi: &mut dyn MidiOut) {
+    for ch in 0..16 {
+        midi.control_change(ch, ALL_NOTES_OFF, 0);
+        midi.control_change(ch, ALL_SOUND_OFF, 0);
+    }
 }
 
 // ── midir backend ─────────────────────────────────────────────────────────
 
 struct MidirOut {
-    conn: midir::MidiOutputConnection,
+    conn:    midir::MidiOutputConnection,
+    /// The first failed send, until taken; later ones aren't reported, so
+    /// an unplugged port doesn't report every note.
+    fault:   Option<String>,
+    faulted: bool,
+}
+
+impl MidirOut {
+    fn new(conn: midir::MidiOutputConnection) -> Self {
+        MidirOut { conn, fault: None, faulted: false }
+    }
+
+    fn send(&mut self, msg: &[u8]) {
+        if let Err(e) = self.conn.send(msg) {
+            if !self.faulted {
+                self.faulted = true;
+                self.fault = Some(format!("MIDI output stopped taking messages: {}", e));
+            }
+        }
+    }
 }
 
 impl MidiOut for MidirOut {
     fn program_change(&mut self, channel: u8, program: u8) {
-        let _ = self.conn.send(&[0xC0 | (channel & 0x0F), program]);
+        self.send(&[0xC0 | (channel & 0x0F), program]);
     }
     fn note_on(&mut self, channel: u8, note: u8, velocity: u8) {
-        let _ = self.conn.send(&[0x90 | (channel & 0x0F), note, velocity]);
+        self.send(&[0x90 | (channel & 0x0F), note, velocity]);
     }
     fn note_off(&mut self, channel: u8, note: u8) {
-        let _ = self.conn.send(&[0x80 | (channel & 0x0F), note, 0]);
+        self.send(&[0x80 | (channel & 0x0F), note, 0]);
+    }
+    fn control_change(&mut self, channel: u8, controller: u8, value: u8) {
+        self.send(&[0xB0 | (channel & 0x0F), controller, value]);
+    }
+    fn realtime(&mut self, status: u8) {
+        self.send(&[status]);
+    }
+    fn take_fault(&mut self) -> Option<String> {
+        self.fault.take()
     }
 }
 
@@ -78,45 +321,142 @@ impl MidiOut for NullOut {
     fn program_change(&mut self, _ch: u8, _p: u8)   {}
     fn note_on(&mut self, _ch: u8, _n: u8, _v: u8)  {}
     fn note_off(&mut self, _ch: u8, _n: u8)          {}
+    fn control_change(&mut self, _ch: u8, _c: u8, _v: u8) {}
+    fn realtime(&mut self, _status: u8)              {}
 }
 
 // ════════════════════════════════════════════════════════════════════════════
-// open_midi_output — enumerate ports and pick first available
+// PortSelector — which MIDI output to open
 // ════════════════════════════════════════════════════════════════════════════
 
-/// Try to open the first available MIDI output port.
-/// Falls back to `NullOut` with a warning if none found.
-fn open_midi_output() -> Box<dyn MidiOut> {
-    let midi_out = match midir::MidiOutput::new("spigot_midi_player") {
-        Ok(m)  => m,
-        Err(e) => {
-            eprintln!("[player] MIDI init error: {} — using null output", e);
-            return Box::new(NullOut);
+/// Which MIDI output port the player connects to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum PortSelector {
+    /// The first softsynth-looking port (FluidSynth, TiMidity, GS
+    /// Wavetable…), otherwise the first port.
+    #[default]
+    Auto,
+    /// Port by position in [`output_ports`].
+    Index(usize),
+    /// First port whose name contains this text (case-insensitive).
+    Name(String),
+    /// A new virtual port named [`VIRTUAL_PORT_NAME`] that DAWs can record
+    /// from directly (CoreMIDI and ALSA only).
+    Virtual,
+    /// A JACK MIDI port of our own, connected to the first JACK MIDI input
+    /// whose name contains the text, if given (`jack` feature; see
+    /// [`crate::seq`]).
+    Jack(Option<String>),
+    /// A port of our own on the ALSA sequencer, subscribed to `client:port`
+    /// or a port whose name contains the text, if given (`alsa-seq`
+    /// feature).
+    AlsaSeq(Option<String>),
+}
+
+/// Name of the port created for [`PortSelector::Virtual`].
+pub const VIRTUAL_PORT_NAME: &str = "Leap Spigot Out";
+
+impl PortSelector {
+    /// Parse a command-line or prompt answer: empty or `auto`, `virtual`,
+    /// `jack[:NAME]`, `alsa[:CLIENT:PORT or NAME]`, a port number, or part
+    /// of a port name.
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+        let (backend, to) = s.split_once(':').unwrap_or((s, ""));
+        let to = (!to.trim().is_empty()).then(|| to.trim().to_string());
+        if backend.eq_ignore_ascii_case("jack") {
+            PortSelector::Jack(to)
+        } else if backend.eq_ignore_ascii_case("alsa") {
+            PortSelector::AlsaSeq(to)
+        } else if s.is_empty() || s.eq_ignore_ascii_case("auto") {
+            PortSelector::Auto
+        } else if s.eq_ignore_ascii_case("virtual") || s.eq_ignore_ascii_case("v") {
+            PortSelector::Virtual
+        } else if let Ok(i) = s.parse() {
+            PortSelector::Index(i)
+        } else {
+            PortSelector::Name(s.to_string())
         }
-    };
+    }
+
+    /// Index of the selected port among `names`, if any matches.  A
+    /// virtual, JACK or ALSA sequencer port is never among them.
+    pub fn choose(&self, names: &[String]) -> Option<usize> {
+        match self {
+            PortSelector::Auto => {
+                let synth = names.iter().position(|n| {
+                    let n = n.to_lowercase();
+                    n.contains("fluid") || n.contains("timidity") ||
+                    n.contains("microsoft") || n.contains("gm") ||
+                    n.contains("synth")
+                });
+                synth.or(if names.is_empty() { None } else { Some(0) })
+            }
+            PortSelector::Index(i) => (*i < names.len()).then_some(*i),
+            PortSelector::Name(s)  => {
+                let s = s.to_lowercase();
+                names.iter().position(|n| n.to_lowercase().contains(&s))
+            }
+            PortSelector::Virtual | PortSelector::Jack(_) | PortSelector::AlsaSeq(_) => None,
+        }
+    }
+}
+
+/// Names of the available MIDI output ports, in [`PortSelector::Index`] order.
+pub fn output_ports() -> Vec<String> {
+    match midir::MidiOutput::new("spigot_midi_player") {
+        Ok(m)  => m.ports().iter()
+            .map(|p| m.port_name(p).unwrap_or_else(|_| "Unknown".to_string()))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// open_midi_output — connect to the selected port
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Open the selected MIDI output port, or fall back to
+/// [`fallback_output`], reporting why on `faults`.
+fn open_output(port: &PortSelector, faults: &Sender<AppError>) -> Box<dyn MidiOut> {
+    open_midi_output(port).unwrap_or_else(|e| {
+        eprintln!("[player] {}", e);
+        // No one to tell once the Player is dropped.
+        faults.send(e).ok();
+        fallback_output()
+    })
+}
+
+/// Try to open the selected MIDI output port.
+fn open_midi_output(port: &PortSelector) -> Result<Box<dyn MidiOut>, AppError> {
+    match port {
+        PortSelector::Jack(to)    => return crate::seq::open_jack(to.as_deref()),
+        PortSelector::AlsaSeq(to) => return crate::seq::open_alsa(to.as_deref()),
+        _ => {}
+    }
+    let midi_out = midir::MidiOutput::new("spigot_midi_player")
+        .map_err(|e| AppError::Device(format!("MIDI init error: {}", e)))?;
+
+    if *port == PortSelector::Virtual {
+        return open_virtual_output(midi_out);
+    }
 
     let ports = midi_out.ports();
     if ports.is_empty() {
-        eprintln!("[player] No MIDI output ports found — using null output.");
+        eprintln!("[player] No MIDI output ports found.");
         eprintln!("[player] Install a MIDI synthesiser such as:");
         eprintln!("         • macOS: built-in CoreMIDI (always available)");
         eprintln!("         • Linux: `timidity -iA` or `fluidsynth`");
         eprintln!("         • Windows: built-in GS Wavetable Synth");
-        return Box::new(NullOut);
-    }
-
-    // Prefer a softsynth if visible
-    let port_idx = ports.iter().enumerate()
-        .find(|(_, p)| {
-            midi_out.port_name(p).map(|n| {
-                let n = n.to_lowercase();
-                n.contains("fluid") || n.contains("timidity") ||
-                n.contains("microsoft") || n.contains("gm") ||
-                n.contains("synth")
-            }).unwrap_or(false)
-        })
-        .map(|(i, _)| i)
-        .unwrap_or(0);
+        eprintln!("         or build with `--features audio` for the built-in synth.");
+        return Err(AppError::Device("no MIDI output ports found".to_string()));
+    }
+
+    let names: Vec<String> = ports.iter()
+        .map(|p| midi_out.port_name(p).unwrap_or_default())
+        .collect();
+    let port_idx = port.choose(&names).ok_or_else(|| AppError::Device(format!(
+        "no MIDI output port matches {:?} (available: {})", port, names.join(", "))))?;
 
     let port = &ports[port_idx];
     let name = midi_out.port_name(port)
@@ -124,12 +464,41 @@ fn open_midi_output() -> Box<dyn MidiOut> {
     eprintln!("[player] Opening MIDI port: {}", name);
 
     match midi_out.connect(port, "spigot-play") {
-        Ok(conn) => Box::new(MidirOut { conn }),
-        Err(e) => {
-            eprintln!("[player] Failed to connect: {} — using null output", e);
-            Box::new(NullOut)
+        Ok(conn) => Ok(Box::new(MidirOut::new(conn))),
+        Err(e)   => Err(AppError::Device(format!("failed to connect to {}: {}", name, e))),
+    }
+}
+
+/// What to play through when no MIDI port can be opened: the built-in
+/// synth with the `audio` feature (see [`crate::synth`]), otherwise
+/// nothing at all.
+fn fallback_output() -> Box<dyn MidiOut> {
+    #[cfg(feature = "audio")]
+    match crate::synth::SynthOut::open() {
+        Ok(synth) => {
+            eprintln!("[player] Playing through the built-in synth.");
+            return Box::new(synth);
         }
+        Err(e) => eprintln!("[player] Built-in synth unavailable: {}", e),
     }
+    eprintln!("[player] Using null output — nothing will sound.");
+    Box::new(NullOut)
+}
+
+/// Create the [`VIRTUAL_PORT_NAME`] port for other applications to read.
+#[cfg(unix)]
+fn open_virtual_output(midi_out: midir::MidiOutput) -> Result<Box<dyn MidiOut>, AppError> {
+    use midir::os::unix::VirtualOutput;
+    let conn = midi_out.create_virtual(VIRTUAL_PORT_NAME)
+        .map_err(|e| AppError::Device(format!("failed to create virtual port: {}", e)))?;
+    eprintln!("[player] Created virtual MIDI port: {}", VIRTUAL_PORT_NAME);
+    Ok(Box::new(MidirOut::new(conn)))
+}
+
+#[cfg(not(unix))]
+fn open_virtual_output(_midi_out: midir::MidiOutput) -> Result<Box<dyn MidiOut>, AppError> {
+    eprintln!("[player] Use a loopback driver such as loopMIDI and select it by name instead.");
+    Err(AppError::Device("virtual MIDI ports are not supported on this platform".to_string()))
 }
 
 // ════════════════════════════════════════════════════════════════════════════
@@ -140,45 +509,117 @@ fn open_midi_output() -> Box<dyn MidiOut> {
 pub struct Player {
     pub cmd_tx:   Sender<PlayerCommand>,
     pub note_rx:  Receiver<NoteEvent>,
+    fault_rx:     Receiver<AppError>,
+    performance:  Arc<Mutex<Performance>>,
+    sync:         Option<Arc<dyn BeatSync>>,
 }
 
 impl Player {
     /// Spawn the playback thread.
     ///
-    /// `stream` is consumed by the thread; `pitch_map` and `duration_map`
-    /// configure how zip pairs are turned into notes.
+    /// The thread plays on from `stream`'s cursors, shared with whoever
+    /// else holds it; `pitch_map`, `duration_map` and `dynamics` configure
+    /// how zip pairs are turned into notes.
     pub fn spawn(
-        stream:       DualStream,
-        pitch_map:    PitchMap,
-        duration_map: DurationMap,
+        stream:       SharedStream,
+        pitch_map:    Box<dyn PitchMapper>,
+        duration_map: Box<dyn DurationMapper>,
         instrument:   u8,
         tempo_bpm:    u32,
-        velocity:     u8,
+        dynamics:     Dynamics,
         channel:      u8,
     ) -> Self {
         let (cmd_tx, cmd_rx) = mpsc::channel::<PlayerCommand>();
         let (note_tx, note_rx) = mpsc::channel::<NoteEvent>();
+        let (fault_tx, fault_rx) = mpsc::channel::<AppError>();
+        let performance = Arc::new(Mutex::new(Performance::new(instrument, channel)));
 
-        thread::spawn(move || {
-            player_thread(
-                stream, pitch_map, duration_map,
-                instrument, tempo_bpm, velocity, channel,
-                cmd_rx, note_tx,
-            );
-        });
+        let recorder = Arc::clone(&performance);
+        let setup = PlayerSetup {
+            stream, pitch_map, duration_map,
+            instrument, tempo_bpm, dynamics, channel,
+            cmd_rx, note_tx, fault_tx, performance: recorder,
+        };
+        thread::spawn(move || player_thread(setup));
+
+        Player { cmd_tx, note_rx, fault_rx, performance, sync: None }
+    }
 
-        Player { cmd_tx, note_rx }
+    /// Pass `cmd` to the playback thread; [`AppError::PlayerStopped`] if it
+    /// has ended.
+    pub fn send(&self, cmd: PlayerCommand) -> Result<(), AppError> {
+        self.cmd_tx.send(cmd).map_err(|_| AppError::PlayerStopped)
     }
 
-    pub fn play(&self)  { let _ = self.cmd_tx.send(PlayerCommand::Play);  }
-    pub fn stop(&self)  { let _ = self.cmd_tx.send(PlayerCommand::Stop);  }
-    pub fn quit(&self)  { let _ = self.cmd_tx.send(PlayerCommand::Quit);  }
+    pub fn play(&self)  -> Result<(), AppError> { self.send(PlayerCommand::Play)  }
+    pub fn stop(&self)  -> Result<(), AppError> { self.send(PlayerCommand::Stop)  }
+    pub fn quit(&self)  -> Result<(), AppError> { self.send(PlayerCommand::Quit)  }
+    pub fn panic(&self) -> Result<(), AppError> { self.send(PlayerCommand::Panic) }
 
-    pub fn set_instrument(&self, prog: u8) {
-        let _ = self.cmd_tx.send(PlayerCommand::SetInstrument(prog));
+    /// Use another MIDI output port from now on.
+    pub fn set_port(&self, port: PortSelector) -> Result<(), AppError> {
+        self.send(PlayerCommand::SetPort(port))
     }
-    pub fn set_tempo(&self, bpm: u32) {
-        let _ = self.cmd_tx.send(PlayerCommand::SetTempo(bpm));
+
+    pub fn set_instrument(&self, prog: u8) -> Result<(), AppError> {
+        self.send(PlayerCommand::SetInstrument(prog))
+    }
+    pub fn set_velocity(&self, velocity: u8) -> Result<(), AppError> {
+        self.send(PlayerCommand::SetVelocity(velocity))
+    }
+    pub fn set_tempo(&self, bpm: u32) -> Result<(), AppError> {
+        self.send(PlayerCommand::SetTempo(bpm))
+    }
+
+    /// Splice `pairs` into the stream at the next beat.
+    pub fn splice(&self, pairs: Vec<(u8, u8)>) -> Result<(), AppError> {
+        self.send(PlayerCommand::Splice(pairs))
+    }
+    pub fn unsplice(&self) -> Result<(), AppError> {
+        self.send(PlayerCommand::Unsplice)
+    }
+
+    /// Send `value` on controller `cc` from now on, while playing.
+    pub fn set_timbre(&self, cc: u8, value: u8) -> Result<(), AppError> {
+        self.send(PlayerCommand::SetTimbre { cc, value })
+    }
+
+    /// Echo a note from an input keyboard (velocity 0 releases it).
+    pub fn echo(&self, pitch: u8, velocity: u8) -> Result<(), AppError> {
+        self.send(PlayerCommand::Echo { pitch, velocity })
+    }
+    /// Reply to a phrase of `notes` notes centred on `around`, unless
+    /// already playing.
+    pub fn answer(&self, notes: usize, around: u8) -> Result<(), AppError> {
+        self.send(PlayerCommand::Answer { notes, around })
+    }
+
+    /// Play the (left, right) streams as a voice each (`None` to merge
+    /// them into one line again).
+    pub fn set_layers(&self, layers: Option<[Layer; 2]>) -> Result<(), AppError> {
+        self.send(PlayerCommand::SetLayers(layers))
+    }
+
+    /// Let the stream nudge the tempo (`None` to hold it where it's set).
+    pub fn set_drift(&self, drift: Option<TempoDrift>) -> Result<(), AppError> {
+        self.send(PlayerCommand::SetDrift(drift))
+    }
+
+    /// Lock playback to a shared beat timeline (`None` to free-run).
+    pub fn set_sync(&mut self, sync: Option<Arc<dyn BeatSync>>) -> Result<(), AppError> {
+        self.sync = sync.clone();
+        self.send(PlayerCommand::Sync(sync))
+    }
+
+    /// Current position on the shared timeline, if synced.
+    pub fn beat(&self) -> Option<f64> {
+        self.sync.as_ref().map(|s| s.beat_at(Instant::now()))
+    }
+
+    /// Device faults since the last call — a port that couldn't be opened
+    /// or stopped taking messages (non-blocking).
+    pub fn faults(&self) -> Vec<AppError> {
+        self.fault_rx.try_iter().collect()
     }
 
     /// Drain any pending note events (non-blocking).
@@ -187,90 +628,518 @@ impl Player {
         while let Ok(n) = self.note_rx.try_recv() { out.push(n); }
         out
     }
+
+    /// Write everything played so far to `path` as a MIDI file at
+    /// `tempo_bpm`, returning the number of notes saved.
+    pub fn save_performance<P: AsRef<Path>>(&self, path: P, tempo_bpm: u32) -> io::Result<usize> {
+        let perf = self.performance.lock().unwrap_or_else(|e| e.into_inner());
+        perf.save(path, tempo_bpm)?;
+        Ok(perf.len())
+    }
+
+    /// Discard the recording and start a new one with the next note.
+    pub fn clear_performance(&self) {
+        self.performance.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
 }
 
 // ════════════════════════════════════════════════════════════════════════════
 // player_thread — the actual loop
 // ════════════════════════════════════════════════════════════════════════════
 
-fn player_thread(
-    mut stream:       DualStream,
-    pitch_map:        PitchMap,
-    duration_map:     DurationMap,
-    mut instrument:   u8,
-    mut tempo_bpm:    u32,
-    velocity:         u8,
-    channel:          u8,
-    cmd_rx:           Receiver<PlayerCommand>,
-    note_tx:          Sender<NoteEvent>,
-) {
-    let mut midi = open_midi_output();
-    let mut playing = false;
+/// What [`Player::spawn`] hands the playback thread: how to play, and the
+/// channels and recording it reports through.
+struct PlayerSetup {
+    stream:       SharedStream,
+    pitch_map:    Box<dyn PitchMapper>,
+    duration_map: Box<dyn DurationMapper>,
+    instrument:   u8,
+    tempo_bpm:    u32,
+    dynamics:     Dynamics,
+    channel:      u8,
+    cmd_rx:       Receiver<PlayerCommand>,
+    note_tx:      Sender<NoteEvent>,
+    fault_tx:     Sender<AppError>,
+    performance:  Arc<Mutex<Performance>>,
+}
 
-    // Ticks-per-quarter (matches spigot_midi default)
-    const TPQ: u32 = 480;
+fn player_thread(setup: PlayerSetup) {
+    let PlayerSetup {
+        stream, pitch_map, duration_map,
+        mut instrument, mut tempo_bpm, mut dynamics, channel,
+        cmd_rx, note_tx, fault_tx, performance,
+    } = setup;
 
-    midi.program_change(channel, instrument);
+    // The port is opened on first use, so a `SetPort` sent right after
+    // spawning takes effect without connecting elsewhere first.
+    let mut midi: Box<dyn MidiOut> = Box::new(NullOut);
+    let mut port = PortSelector::Auto;
+    let mut opened = false;
+    let mut playing = false;
+    // Whether Start has been sent; later plays resume with Continue.
+    let mut started = false;
+    // Deadline of the next Note On.
+    let mut next_on = Instant::now();
+    // The next notes, once generated.
+    let mut upcoming: Option<Upcoming> = None;
+    // Pairs played, for phrase shaping.
+    let mut note_index = 0;
+    // A voice for each stream, if layered.
+    let mut layers: Option<[Layer; 2]> = None;
+    // Tempo automation, the tempo it has drifted to, and the levels of the
+    // pairs since its last nudge.
+    let mut drift: Option<TempoDrift> = None;
+    let mut drifted = tempo_bpm as f64;
+    let mut levels: Vec<f64> = Vec::new();
+    // Notes sounding, each waiting for its Note Off.
+    let mut voices = Voices::default();
+    let mut clock = Clock::new(next_on, tempo_bpm);
+    // Shared timeline, and the beat of the next Note On on it (or on the
+    // player's own clock when free-running).
+    let mut sync: Option<Arc<dyn BeatSync>> = None;
+    let mut beat = 0.0;
+    // Splices waiting for their beat.
+    let mut splices: Vec<(f64, Vec<(u8, u8)>)> = Vec::new();
+    // Timbre controller (cc, value) wanted and last sent, and when it may
+    // next be sent.
+    let mut timbre: Option<(u8, u8)> = None;
+    let mut timbre_sent: Option<(u8, u8)> = None;
+    let mut timbre_next = Instant::now();
+    // Pairs left to answer a jam phrase with, and the pitch to keep them
+    // near.
+    let mut answer: Option<(usize, u8)> = None;
+    // Command that ended the last wait.
+    let mut woken: Option<PlayerCommand> = None;
+    // Whether the shared transport was rolling, when it has one.
+    let mut rolling: Option<bool> = None;
 
     loop {
-        // ── drain commands ────────────────────────────────────────────────
-        loop {
-            match cmd_rx.try_recv() {
-                Ok(PlayerCommand::Play)  => {
+        if let Some(e) = midi.take_fault() {
+            fault_tx.send(AppError::Device(e)).ok();
+        }
+
+        // ── follow a shared transport as it starts and stops ──────────────
+        let transport = sync.as_ref().and_then(|s| s.rolling());
+        let follow = match transport {
+            Some(true)  if rolling != transport && !playing => Some(PlayerCommand::Play),
+            Some(false) if rolling != transport && playing  => Some(PlayerCommand::Stop),
+            _ => None,
+        };
+        rolling = transport;
+
+        // ── handle commands ───────────────────────────────────────────────
+        let commands = follow.into_iter().chain(woken.take())
+            .chain(std::iter::from_fn(|| cmd_rx.try_recv().ok()));
+        for cmd in commands {
+            match cmd {
+                cmd @ (PlayerCommand::Play | PlayerCommand::Answer { .. }) => {
+                    // An answer only fills a silence; a clap takes over one.
+                    answer = match cmd {
+                        PlayerCommand::Answer { .. } if playing => continue,
+                        PlayerCommand::Answer { notes, around } => Some((notes, around)),
+                        _ => None,
+                    };
+                    if !opened {
+                        midi = open_output(&port, &fault_tx);
+                        opened = true;
+                    }
+                    if !playing {
+                        next_on = Instant::now();
+                        upcoming = None;
+                        if let Some(s) = &sync {
+                            beat = link::next_bar(s.beat_at(next_on));
+                            next_on = s.time_at_beat(beat);
+                        }
+                        clock.restart(next_on);
+                        midi.realtime(if started { CONTINUE } else { START });
+                        started = true;
+                    }
                     playing = true;
-                    midi.program_change(channel, instrument);
+                    programs(midi.as_mut(), channel, instrument, layers);
+                }
+                PlayerCommand::Stop  => {
+                    if playing { midi.realtime(STOP); }
+                    playing = false;
+                    answer = None;
+                    voices.release(midi.as_mut(), None, &performance);
+                }
+                PlayerCommand::SetInstrument(p) => {
+                    // Layered, it's the lead (right) voice that changes.
+                    match &mut layers {
+                        Some([_, lead]) => lead.instrument = p,
+                        None            => instrument = p,
+                    }
+                    programs(midi.as_mut(), channel, instrument, layers);
+                    if let Ok(mut perf) = performance.lock() { perf.instrument = p; }
+                }
+                PlayerCommand::SetVelocity(v) => { dynamics.velocity = v.clamp(1, 127); }
+                PlayerCommand::SetTempo(b) => {
+                    tempo_bpm = b;
+                    drifted = b as f64;
+                    levels.clear();
+                    clock.set_tempo(b);
+                    if let Some(s) = &sync { s.set_tempo(b as f64); }
+                }
+                PlayerCommand::Sync(s) => { sync = s; }
+                PlayerCommand::Panic => {
+                    if !opened {
+                        midi = open_output(&port, &fault_tx);
+                        opened = true;
+                    }
+                    if playing { midi.realtime(STOP); }
+                    playing = false;
+                    answer = None;
+                    voices.release(midi.as_mut(), None, &performance);
+                    panic(midi.as_mut());
+                }
+                PlayerCommand::SetPort(p) => {
+                    port = p;
+                    if opened {
+                        if playing { midi.realtime(STOP); }
+                        voices.release(midi.as_mut(), None, &performance);
+                        panic(midi.as_mut());
+                        midi = open_output(&port, &fault_tx);
+                        programs(midi.as_mut(), channel, instrument, layers);
+                        timbre_sent = None;
+                        if playing { midi.realtime(CONTINUE); }
+                    }
                 }
-                Ok(PlayerCommand::Stop)  => { playing = false; }
-                Ok(PlayerCommand::SetInstrument(p)) => {
-                    instrument = p;
-                    midi.program_change(channel, instrument);
+                PlayerCommand::Splice(pairs) => {
+                    splices.push((next_beat(beat), pairs));
+                }
+                PlayerCommand::Unsplice => {
+                    if splices.pop().is_none() { stream.lock().clear_spliced(); }
+                }
+                PlayerCommand::SetDrift(d) => {
+                    drift = d;
+                    drifted = tempo_bpm as f64;
+                    levels.clear();
+                    clock.set_tempo(tempo_bpm);
+                }
+                PlayerCommand::SetLayers(l) => {
+                    layers = l;
+                    programs(midi.as_mut(), channel, instrument, layers);
+                }
+                PlayerCommand::SetTimbre { cc, value } => { timbre = Some((cc, value.min(127))); }
+                PlayerCommand::Echo { pitch, velocity } => {
+                    if !opened {
+                        midi = open_output(&port, &fault_tx);
+                        programs(midi.as_mut(), channel, instrument, layers);
+                        opened = true;
+                    }
+                    let lead = layers.map_or(channel, |[_, lead]| lead.channel);
+                    if velocity > 0 {
+                        let off = Instant::now() + ECHO_HOLD;
+                        voices.start(midi.as_mut(), lead, pitch, velocity, off, &performance);
+                    } else {
+                        voices.stop(midi.as_mut(), lead, pitch, &performance);
+                    }
+                }
+                PlayerCommand::Quit  => {
+                    if playing { midi.realtime(STOP); }
+                    voices.release(midi.as_mut(), None, &performance);
+                    panic(midi.as_mut());
+                    return;
                 }
-                Ok(PlayerCommand::SetTempo(b)) => { tempo_bpm = b; }
-                Ok(PlayerCommand::Quit)  => return,
-                Err(_) => break,
             }
         }
 
-        if !playing {
-            thread::sleep(Duration::from_millis(10));
-            continue;
+        // ── release notes whose time is up ────────────────────────────────
+        let now = Instant::now();
+        voices.release(midi.as_mut(), Some(now), &performance);
+
+        if playing && upcoming.is_none() && now >= next_on {
+            // ── splice snippets due on this beat ──────────────────────────
+            let mut stream = stream.lock();
+            while splices.first().is_some_and(|(at, _)| beat >= *at) {
+                let (_, pairs) = splices.remove(0);
+                stream.splice(&pairs);
+            }
+
+            // ── generate next note ────────────────────────────────────────
+            let spliced = stream.spliced_len() > 0;
+            let next = match &mut answer {
+                Some((0, _))    => None,
+                Some((left, _)) => { *left -= 1; stream.zip_next() }
+                None            => stream.zip_next(),
+            };
+            match next {
+                Some((left, right)) => {
+                    let mut notes = layer_notes(pitch_map.as_ref(), channel, layers, (left, right));
+                    if let Some((_, around)) = answer {
+                        for (_, pitch) in &mut notes { *pitch = fold_toward(*pitch, around); }
+                    }
+                    let ticks = duration_map.ticks_for(left);
+                    let velocity = dynamics.velocity_for(note_index, (left, right));
+                    note_index += 1;
+
+                    // Nudge the tempo every `every` pairs
+                    if let Some(d) = drift.filter(|_| sync.is_none()) {
+                        levels.push(pair_level((left, right), (stream.left_base(), stream.right_base())));
+                        if levels.len() >= d.every.max(1) {
+                            drifted = d.nudge(drifted, tempo_bpm, &levels);
+                            levels.clear();
+                            clock.set_tempo(drifted.round() as u32);
+                        }
+                    }
+                    let bpm = match &sync {
+                        Some(s) => s.tempo().round() as u32,
+                        None    => drifted.round() as u32,
+                    };
+
+                    // Notify visualizer
+                    for (i, &(_, pitch)) in notes.iter().enumerate() {
+                        let _ = note_tx.send(NoteEvent {
+                            pitch, duration: ticks, velocity, bpm,
+                            left_pos:  stream.left_pos(),
+                            right_pos: stream.right_pos(),
+                            pair:      (left, right),
+                            spliced,
+                            first:     i == 0,
+                        });
+                    }
+
+                    // After a stall start afresh rather than rushing
+                    // through the missed notes.
+                    let length = match &sync {
+                        Some(s) => {
+                            if now > s.time_at_beat(beat) + MAX_LAG { beat = s.beat_at(now).ceil(); }
+                            next_on = s.time_at_beat(beat);
+                            // Keep the clock's pulses on the session's beats.
+                            clock.set_tempo(s.tempo().round() as u32);
+                            if beat.fract() == 0.0 { clock.restart(next_on); }
+                            beat += ticks as f64 / TPQ as f64;
+                            s.time_at_beat(beat).saturating_duration_since(next_on)
+                        }
+                        None => {
+                            if now > next_on + MAX_LAG {
+                                next_on = now;
+                                clock.restart(now);
+                            }
+                            beat += ticks as f64 / TPQ as f64;
+                            ticks_to_duration(ticks, TPQ, bpm)
+                        }
+                    };
+                    upcoming = Some(Upcoming { notes, velocity, length });
+                }
+                None => {
+                    midi.realtime(STOP);
+                    playing = false;
+                    answer = None;
+                }
+            }
         }
 
-        // ── generate next note ────────────────────────────────────────────
-        let (left, right) = match stream.zip_next() {
-            Some(p) => p,
-            None    => { playing = false; continue; }
-        };
+        if playing {
+            clock.send_due(now, midi.as_mut());
+
+            // ── the timbre controller, if it has moved ────────────────────
+            if now >= timbre_next && timbre != timbre_sent {
+                if let Some((cc, value)) = timbre {
+                    match layers {
+                        Some(layers) => for l in layers { midi.control_change(l.channel, cc, value); },
+                        None         => midi.control_change(channel, cc, value),
+                    }
+                }
+                timbre_sent = timbre;
+                timbre_next = now + TIMBRE_INTERVAL;
+            }
+
+            // ── play it when its onset comes, releasing a brief gap before
+            //    the next ─────────────────────────────────────────────────
+            if let Some(Upcoming { notes, velocity, length }) = upcoming.take_if(|_| now >= next_on) {
+                let gap = release_gap(length);
+                for (ch, pitch) in notes {
+                    voices.start(midi.as_mut(), ch, pitch, velocity, next_on + length - gap, &performance);
+                }
+                next_on += length;
+            }
+        }
 
-        let pitch    = pitch_map.note_for(right);
-        let ticks    = duration_map.ticks_for(left);
-        let millis   = ticks_to_ms(ticks, TPQ, tempo_bpm);
+        // ── sleep until something is due, or a command arrives ───────────
+        let mut deadline = voices.next_off().unwrap_or(now + IDLE_WAIT);
+        if playing { deadline = deadline.min(next_on).min(clock.next); }
+        if playing && timbre != timbre_sent { deadline = deadline.min(timbre_next); }
+        woken = wait_for(&cmd_rx, deadline);
+    }
+}
+
+/// Notes generated for the next onset.
+struct Upcoming {
+    /// (channel, pitch) of each.
+    notes:    Vec<(u8, u8)>,
+    velocity: u8,
+    length:   Duration,
+}
 
-        // Notify visualizer
-        let _ = note_tx.send(NoteEvent {
-            pitch, duration: ticks, velocity,
-            left_pos:  stream.left_pos(),
-            right_pos: stream.right_pos(),
-        });
+/// Set the instrument of every channel in use.
+fn programs(midi: &mut dyn MidiOut, channel: u8, instrument: u8, layers: Option<[Layer; 2]>) {
+    match layers {
+        Some(layers) => for l in layers { midi.program_change(l.channel, l.instrument); },
+        None         => midi.program_change(channel, instrument),
+    }
+}
+
+/// `pitch` moved by whole octaves to lie within a tritone of `around`,
+/// keeping its place in the scale.
+fn fold_toward(pitch: u8, around: u8) -> u8 {
+    let octaves = (around as i32 - pitch as i32 + 6).div_euclid(12);
+    let folded = pitch as i32 + 12 * octaves;
+    // Stay on the keyboard, an octave back if need be.
+    if folded > 127 { (folded - 12) as u8 } else if folded < 0 { (folded + 12) as u8 } else { folded as u8 }
+}
 
-        // Play it
+/// The (channel, pitch) notes a pair sounds: the right digit's alone on
+/// `channel`, or with layers one for each digit on its layer, left first.
+fn layer_notes(pitch_map: &dyn PitchMapper, channel: u8, layers: Option<[Layer; 2]>,
+               (left, right): (u8, u8)) -> Vec<(u8, u8)> {
+    match layers {
+        Some([l, r]) => vec![
+            (l.channel, l.pitch(pitch_map.note_for(left))),
+            (r.channel, r.pitch(pitch_map.note_for(right))),
+        ],
+        None => vec![(channel, pitch_map.note_for(right))],
+    }
+}
+
+/// A note sounding now, released at `off`.
+struct Sounding {
+    channel:  u8,
+    pitch:    u8,
+    velocity: u8,
+    on:       Instant,
+    off:      Instant,
+}
+
+/// Notes that are sounding, each with its own Note Off time, so they can
+/// overlap and be cut short together.
+#[derive(Default)]
+struct Voices(Vec<Sounding>);
+
+impl Voices {
+    /// Sound `pitch` until `off`, first releasing it if it's still sounding
+    /// on `channel`.
+    fn start(&mut self, midi: &mut dyn MidiOut, channel: u8, pitch: u8, velocity: u8,
+             off: Instant, performance: &Mutex<Performance>) {
+        self.stop(midi, channel, pitch, performance);
         midi.note_on(channel, pitch, velocity);
-        thread::sleep(Duration::from_millis(millis));
-        midi.note_off(channel, pitch);
+        self.0.push(Sounding { channel, pitch, velocity, on: Instant::now(), off });
+    }
 
-        // Brief gap between notes (5% of duration, min 5ms)
-        let gap = (millis / 20).max(5);
-        thread::sleep(Duration::from_millis(gap));
+    /// Release `pitch` on `channel` now, if it's sounding.
+    fn stop(&mut self, midi: &mut dyn MidiOut, channel: u8, pitch: u8, performance: &Mutex<Performance>) {
+        if let Some(i) = self.0.iter().position(|v| v.channel == channel && v.pitch == pitch) {
+            let v = self.0.remove(i);
+            Self::end(midi, v, performance);
+        }
+    }
+
+    /// Release every note due by `until`, or all of them if `None`.
+    fn release(&mut self, midi: &mut dyn MidiOut, until: Option<Instant>, performance: &Mutex<Performance>) {
+        let (due, sounding) = std::mem::take(&mut self.0).into_iter()
+            .partition(|v| until.is_none_or(|t| v.off <= t));
+        self.0 = sounding;
+        for v in due { Self::end(midi, v, performance); }
+    }
+
+    /// The next Note Off due.
+    fn next_off(&self) -> Option<Instant> {
+        self.0.iter().map(|v| v.off).min()
+    }
+
+    fn end(midi: &mut dyn MidiOut, v: Sounding, performance: &Mutex<Performance>) {
+        midi.note_off(v.channel, v.pitch);
+        if let Ok(mut perf) = performance.lock() {
+            perf.record(v.on, Instant::now(), v.pitch, v.velocity);
+        }
     }
 }
 
-/// Convert ticks to milliseconds given TPQ and BPM.
-fn ticks_to_ms(ticks: u32, tpq: u32, bpm: u32) -> u64 {
-    // ms = ticks * (60_000 / bpm) / tpq
-    let ms_per_beat = 60_000u64 / bpm.max(1) as u64;
-    (ticks as u64 * ms_per_beat / tpq.max(1) as u64).max(50)
+/// The first whole beat at or after `beat`.
+fn next_beat(beat: f64) -> f64 {
+    // Tolerate rounding from summing note lengths.
+    (beat - 1e-6).ceil()
 }
 
+/// How far behind schedule the player may fall before it resynchronises.
+const MAX_LAG: Duration = Duration::from_millis(100);
+
+/// Remaining time before a deadline that is busy-waited instead of slept,
+/// covering the OS scheduler's wake-up latency.
+const SPIN_WINDOW: Duration = Duration::from_micros(1500);
+
+/// Shortest time between timbre controller messages (50 a second).
+pub const TIMBRE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Longest an echoed note is held if its Note Off never comes.
+const ECHO_HOLD: Duration = Duration::from_secs(8);
+
+/// Longest the player waits for a command when nothing is due.
+const IDLE_WAIT: Duration = Duration::from_millis(250);
+
+/// Wait for a command until `deadline`, spinning through the last stretch.
+/// Returns the command if one arrived first; a closed channel reads as
+/// `Quit`.
+fn wait_for(cmd_rx: &Receiver<PlayerCommand>, deadline: Instant) -> Option<PlayerCommand> {
+    let now = Instant::now();
+    if deadline > now + SPIN_WINDOW {
+        match cmd_rx.recv_timeout(deadline - now - SPIN_WINDOW) {
+            Ok(cmd)                               => return Some(cmd),
+            Err(RecvTimeoutError::Disconnected)   => return Some(PlayerCommand::Quit),
+            Err(RecvTimeoutError::Timeout)        => {}
+        }
+    }
+    wait_until(deadline);
+    None
+}
+
+/// Block until `deadline`: sleep for most of the wait, then spin.
+fn wait_until(deadline: Instant) {
+    loop {
+        let now = Instant::now();
+        if now >= deadline { return; }
+        let left = deadline - now;
+        if left > SPIN_WINDOW {
+            thread::sleep(left - SPIN_WINDOW);
+        } else {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// MIDI beat clock: 24 pulses per quarter note on the player's schedule.
+struct Clock {
+    next:     Instant,
+    interval: Duration,
+}
+
+impl Clock {
+    const PPQ: u32 = 24;
+
+    fn new(start: Instant, bpm: u32) -> Self {
+        Clock { next: start, interval: pulse_interval(bpm) }
+    }
+
+    /// Put the next pulse at `at`, e.g. on the first note after a Start.
+    fn restart(&mut self, at: Instant) { self.next = at; }
+
+    fn set_tempo(&mut self, bpm: u32) { self.interval = pulse_interval(bpm); }
+
+    /// Send every pulse due by `now`.
+    fn send_due(&mut self, now: Instant, midi: &mut dyn MidiOut) {
+        while self.next <= now {
+            midi.realtime(CLOCK);
+            self.next += self.interval;
+        }
+    }
+}
+
+/// Time between clock pulses at `bpm`.
+fn pulse_interval(bpm: u32) -> Duration {
+    Duration::from_nanos(60_000_000_000 / (bpm.max(1) as u64 * Clock::PPQ as u64))
+}
+
+
 // ════════════════════════════════════════════════════════════════════════════
 // Tests
 // ════════════════════════════════════════════════════════════════════════════
@@ -278,22 +1147,175 @@ fn ticks_to_ms(ticks: u32, tpq: u32, bpm: u32) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use spigot_midi::PitchMap;
 
     #[test]
-    fn ticks_to_ms_quarter_at_120bpm() {
-        // At 120 BPM, 480 ticks (quarter note) = 500 ms
-        assert_eq!(ticks_to_ms(480, 480, 120), 500);
+    fn answers_fold_into_the_phrase_register() {
+        assert_eq!(fold_toward(84, 60), 60);
+        assert_eq!(fold_toward(50, 60), 62);   // D3 → D4, a tone above
+        assert_eq!(fold_toward(67, 60), 55);   // a fifth above → a fourth below
+        assert_eq!(fold_toward(66, 60), 66);   // a tritone away stays
+        assert_eq!(fold_toward(2, 126), 122);
+        assert_eq!(fold_toward(125, 1), 5);
     }
 
     #[test]
-    fn ticks_to_ms_eighth_at_120bpm() {
-        // 240 ticks at 120 BPM = 250 ms
-        assert_eq!(ticks_to_ms(240, 480, 120), 250);
+    fn splices_wait_for_the_next_whole_beat() {
+        assert_eq!(next_beat(0.0), 0.0);
+        assert_eq!(next_beat(2.5), 3.0);
+        assert_eq!(next_beat(4.000_000_1), 4.0);
+    }
+
+    /// Records real-time messages with their send times, and notes and
+    /// controllers.
+    #[derive(Default)]
+    struct Log(Vec<(Instant, u8)>, Vec<[u8; 3]>);
+    impl MidiOut for Log {
+        fn program_change(&mut self, _ch: u8, _p: u8)   {}
+        fn note_on(&mut self, ch: u8, n: u8, v: u8)  { self.1.push([0x90 | ch, n, v]); }
+        fn note_off(&mut self, ch: u8, n: u8)        { self.1.push([0x80 | ch, n, 0]); }
+        fn control_change(&mut self, ch: u8, c: u8, v: u8) { self.1.push([0xB0 | ch, c, v]); }
+        fn realtime(&mut self, status: u8) { self.0.push((Instant::now(), status)); }
     }
 
     #[test]
-    fn ticks_to_ms_min_floor() {
-        // Very short durations floor to 50ms
-        assert_eq!(ticks_to_ms(1, 480, 120), 50);
+    fn port_selector_parses_and_chooses() {
+        let names: Vec<String> = ["Midi Through", "FLUID Synth (1234)", "USB Keys"]
+            .iter().map(|s| s.to_string()).collect();
+        assert_eq!(PortSelector::parse(""), PortSelector::Auto);
+        assert_eq!(PortSelector::parse(" 2 "), PortSelector::Index(2));
+        assert_eq!(PortSelector::parse("usb"), PortSelector::Name("usb".into()));
+        assert_eq!(PortSelector::parse("Virtual"), PortSelector::Virtual);
+        assert_eq!(PortSelector::Virtual.choose(&names), None);
+        assert_eq!(PortSelector::parse("jack"), PortSelector::Jack(None));
+        assert_eq!(PortSelector::parse("JACK:fluidsynth"), PortSelector::Jack(Some("fluidsynth".into())));
+        assert_eq!(PortSelector::parse("alsa:128:0"), PortSelector::AlsaSeq(Some("128:0".into())));
+        assert_eq!(PortSelector::parse("alsa:").choose(&names), None);
+
+        assert_eq!(PortSelector::Auto.choose(&names), Some(1));
+        assert_eq!(PortSelector::Auto.choose(&names[..1]), Some(0));
+        assert_eq!(PortSelector::Auto.choose(&[]), None);
+        assert_eq!(PortSelector::Index(2).choose(&names), Some(2));
+        assert_eq!(PortSelector::Index(3).choose(&names), None);
+        assert_eq!(PortSelector::parse("usb").choose(&names), Some(2));
+    }
+
+    #[test]
+    fn panic_silences_every_channel() {
+        let mut log = Log::default();
+        panic(&mut log);
+        assert_eq!(log.1.len(), 32);
+        for ch in 0..16u8 {
+            assert!(log.1.contains(&[0xB0 | ch, 123, 0]));
+            assert!(log.1.contains(&[0xB0 | ch, 120, 0]));
+        }
+    }
+
+    #[test]
+    fn clock_pulses_24_per_quarter_on_schedule() {
+        // 120 BPM: a pulse every 500/24 ≈ 20.83 ms
+        let start = Instant::now();
+        let mut clock = Clock::new(start, 120);
+        let mut log = Log::default();
+        clock.send_due(start + Duration::from_millis(240), &mut log);
+        assert_eq!(log.0.len(), 12);
+        assert!(log.0.iter().all(|&(_, s)| s == CLOCK));
+        clock.send_due(start + Duration::from_millis(240), &mut log);
+        assert_eq!(log.0.len(), 12);
+        // Later calls carry on from the same grid.
+        clock.send_due(start + Duration::from_millis(480), &mut log);
+        assert_eq!(log.0.len(), 24);
+        assert_eq!(clock.next, start + pulse_interval(120) * 24);
+    }
+
+    #[test]
+    fn voices_overlap_and_release_on_time() {
+        let perf = Mutex::new(Performance::new(0, 0));
+        let mut log = Log::default();
+        let mut voices = Voices::default();
+        let t = Instant::now();
+        let ms = |n: u64| t + Duration::from_millis(n);
+        voices.start(&mut log, 0, 60, 100, ms(200), &perf);
+        voices.start(&mut log, 1, 64, 90,  ms(100), &perf);
+        voices.start(&mut log, 0, 60, 80,  ms(300), &perf);    // retriggered
+        assert_eq!(voices.next_off(), Some(ms(100)));
+
+        voices.release(&mut log, Some(ms(150)), &perf);
+        assert_eq!(voices.next_off(), Some(ms(300)));
+        voices.release(&mut log, None, &perf);
+        assert_eq!(voices.next_off(), None);
+        assert_eq!(log.1, [[0x90, 60, 100], [0x91, 64, 90], [0x80, 60, 0], [0x90, 60, 80],
+                           [0x81, 64, 0], [0x80, 60, 0]]);
+        assert_eq!(perf.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn commands_end_the_wait_early() {
+        let (tx, rx) = mpsc::channel();
+        let start = Instant::now();
+        tx.send(PlayerCommand::Stop).unwrap();
+        assert!(matches!(wait_for(&rx, start + Duration::from_secs(5)), Some(PlayerCommand::Stop)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        assert!(wait_for(&rx, Instant::now() + Duration::from_millis(20)).is_none());
+        drop(tx);
+        assert!(matches!(wait_for(&rx, Instant::now() + Duration::from_secs(5)), Some(PlayerCommand::Quit)));
+    }
+
+    #[test]
+    fn layers_give_each_stream_its_own_voice() {
+        let map = PitchMap::major(60);
+        assert_eq!(layer_notes(&map, 0, None, (3, 2)), [(0, map.note_for(2))]);
+
+        let layers = bass_and_lead(DEFAULT_BASS, 0);
+        assert_eq!(layer_notes(&map, 0, Some(layers), (3, 2)),
+                   [(1, map.note_for(3) - 24), (0, map.note_for(2))]);
+        assert_eq!(Layer { instrument: 0, channel: 0, transpose: -24 }.pitch(10), 0);
+        assert_eq!(Layer { instrument: 0, channel: 0, transpose: 24 }.pitch(120), 127);
+    }
+
+    #[test]
+    fn drift_follows_the_digits_within_its_range() {
+        let drift = TempoDrift { every: 4, step: 4.0, range: 0.1 };
+        assert_eq!(drift.nudge(120.0, 120, &[1.0; 4]), 124.0);
+        assert_eq!(drift.nudge(120.0, 120, &[0.0, 0.5, 0.5, 0.0]), 118.0);
+        assert_eq!(drift.nudge(130.0, 120, &[1.0; 4]), 132.0);   // capped at +10%
+        assert_eq!(drift.nudge(120.0, 120, &[]), 120.0);
+
+        assert_eq!(pair_level((9, 1), (10, 2)), 1.0);
+        assert_eq!(pair_level((0, 0), (10, 16)), 0.0);
+    }
+
+    #[test]
+    fn dynamics_scale_the_composer_velocities() {
+        assert_eq!(Dynamics::fixed(64).velocity_for(5, (3, 2)), 64);
+
+        let dynamics = Dynamics {
+            velocity:     50,
+            velocity_map: VelocityMap::linear_left(20, 110, 10),
+            curve:        Curve::Crescendo(4),
+            depth:        30,
+        };
+        assert_eq!(dynamics.velocity_for(0, (9, 0)), 55 - 15);
+        assert_eq!(dynamics.velocity_for(3, (9, 0)), 55 + 15);
+        assert_eq!(dynamics.velocity_for(0, (0, 0)), 1);
+    }
+
+    #[test]
+    fn parses_phrase_curves() {
+        assert_eq!(parse_curve("swell"), Some(Curve::Swell(8)));
+        assert_eq!(parse_curve("Crescendo:16"), Some(Curve::Crescendo(16)));
+        assert_eq!(parse_curve("flat"), Some(Curve::Flat));
+        assert_eq!(parse_curve("swell:0"), None);
+        assert_eq!(parse_curve("loud"), None);
+    }
+
+    #[test]
+    fn wait_until_never_wakes_early() {
+        for ms in [0, 3, 20] {
+            let deadline = Instant::now() + Duration::from_millis(ms);
+            wait_until(deadline);
+            assert!(Instant::now() >= deadline);
+        }
     }
 }
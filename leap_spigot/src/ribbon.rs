@@ -4,6 +4,11 @@
 //! state tracks scrolling, stitching (when playing), and highlighting
 //! (when snipping).
 
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use spigot_stream::stats::DigitStats;
+
 // ════════════════════════════════════════════════════════════════════════════
 // Color palette — digit → RGB
 // ════════════════════════════════════════════════════════════════════════════
@@ -48,7 +53,7 @@ fn hsv_to_argb(h: f32, s: f32, v: f32) -> u32 {
 // ════════════════════════════════════════════════════════════════════════════
 
 /// A single digit-patch on the ribbon.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Patch {
     pub digit:    u8,
     pub color:    u32,
@@ -65,7 +70,7 @@ pub struct Patch {
 /// `capacity` patches are kept; the head always shows the most-recently
 /// generated digit on the right, and the ribbon scrolls left as new digits
 /// arrive.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct RibbonState {
     pub patches:  Vec<Patch>,
     pub capacity: usize,
@@ -76,6 +81,8 @@ pub struct RibbonState {
     pub scroll_vel: f32,
     /// Label for display (e.g. "π base 16")
     pub label:    String,
+    /// How often each digit has turned up in the stream so far.
+    pub stats:    DigitStats,
 }
 
 impl RibbonState {
@@ -87,9 +94,27 @@ impl RibbonState {
             scroll_px:  0.0,
             scroll_vel: 0.0,
             label:      label.to_string(),
+            stats:      DigitStats::new(base),
         }
     }
 
+    /// Show a digit the player has just taken from the stream: counted in
+    /// `stats`, and pushed unless the ribbon already shows it, as it does
+    /// the digits filled in on launch or after a scrub.
+    pub fn show_played(&mut self, digit: u8, position: usize) {
+        if self.patches.last().is_some_and(|p| p.position >= position) {
+            self.stats.add(digit);
+        } else {
+            self.pull(digit, position);
+        }
+    }
+
+    /// Push a digit just pulled from the stream, counting it in `stats`.
+    pub fn pull(&mut self, digit: u8, position: usize) {
+        self.stats.add(digit);
+        self.push(digit, position);
+    }
+
     /// Push a new digit onto the right end of the ribbon (oldest falls off left).
     pub fn push(&mut self, digit: u8, position: usize) {
         if self.patches.len() >= self.capacity {
@@ -102,6 +127,15 @@ impl RibbonState {
         });
     }
 
+    /// Index of the patch for the digit at stream `position`.  A spliced
+    /// digit is pushed as it plays, after the stream digit it shares a
+    /// position with, so it's looked for from the newest end.
+    pub fn patch_at(&self, position: usize, spliced: bool) -> Option<usize> {
+        let mut positions = self.patches.iter().map(|p| p.position);
+        if spliced { positions.rposition(|p| p == position) }
+        else       { positions.position(|p| p == position) }
+    }
+
     /// Advance the scroll animation by one frame.
     /// `patch_width` is the pixel width of each patch.
     pub fn tick(&mut self, patch_width: f32) {
@@ -183,6 +217,8 @@ pub struct TrayEntry {
 #[derive(Debug, Default)]
 pub struct SnippetTray {
     pub entries: Vec<TrayEntry>,
+    /// Entry picked for splicing into the performance.
+    pub selected: Option<usize>,
 }
 
 impl SnippetTray {
@@ -192,10 +228,57 @@ impl SnippetTray {
             patches:  pairs,
             slide_in: 0.0,
         });
-        // Keep at most 8 entries visible
-        if self.entries.len() > 8 {
-            self.entries.remove(0);
-        }
+    }
+
+    /// Move the selection `step` entries down (up if negative), wrapping.
+    /// With nothing selected, starts from the newest entry.
+    pub fn select(&mut self, step: i8) {
+        let n = self.entries.len();
+        if n == 0 { return; }
+        self.selected = Some(match self.selected {
+            Some(i) => (i as isize + step as isize).rem_euclid(n as isize) as usize,
+            None    => n - 1,
+        });
+    }
+
+    /// Select entry `index`, if there is one.
+    pub fn pick(&mut self, index: usize) {
+        if index < self.entries.len() { self.selected = Some(index); }
+    }
+
+    /// The selected entry, or the newest if none is selected.
+    pub fn selected_entry(&self) -> Option<&TrayEntry> {
+        self.selected.map_or(self.entries.last(), |i| self.entries.get(i))
+    }
+
+    /// Swap the selected entry with its neighbour `step` places down (up if
+    /// negative), keeping it selected.  Stops at either end.
+    pub fn move_selected(&mut self, step: i8) {
+        let Some(i) = self.selected else { return };
+        let j = (i as isize + step as isize).clamp(0, self.entries.len() as isize - 1) as usize;
+        self.entries.swap(i, j);
+        self.selected = Some(j);
+    }
+
+    /// Rename the selected entry, returning its old name.
+    pub fn rename_selected(&mut self, name: &str) -> Option<String> {
+        let entry = self.entries.get_mut(self.selected?)?;
+        Some(std::mem::replace(&mut entry.name, name.to_string()))
+    }
+
+    /// Remove the selected entry; the selection moves to the next one.
+    pub fn delete_selected(&mut self) -> Option<TrayEntry> {
+        let i = self.selected?;
+        let entry = self.entries.remove(i);
+        self.selected = if self.entries.is_empty() { None } else { Some(i.min(self.entries.len() - 1)) };
+        Some(entry)
+    }
+
+    /// Index of the first entry shown when `rows` fit on screen, scrolled so
+    /// the selection (or, without one, the newest entry) is visible.
+    pub fn first_visible(&self, rows: usize) -> usize {
+        let anchor = self.selected.unwrap_or(self.entries.len().saturating_sub(1));
+        (anchor + 1).saturating_sub(rows.max(1))
     }
 
     /// Advance slide-in animations.
@@ -208,6 +291,53 @@ impl SnippetTray {
     }
 }
 
+// ════════════════════════════════════════════════════════════════════════════
+// PianoRoll — recently played notes, scrolling left
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Frames of history the piano roll shows (4 s at 60 fps).
+pub const ROLL_SPAN: u32 = 240;
+
+/// A note on the piano roll.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RollNote {
+    pub pitch:    u8,
+    pub velocity: u8,
+    /// Frames since the note sounded.
+    pub age:      u32,
+    /// Frames the note lasts.
+    pub length:   u32,
+    /// Whether it came from a spliced snippet.
+    pub spliced:  bool,
+}
+
+/// The notes played over the last `ROLL_SPAN` frames, oldest first.
+#[derive(Debug, Default)]
+pub struct PianoRoll {
+    pub notes: VecDeque<RollNote>,
+}
+
+impl PianoRoll {
+    pub fn push(&mut self, pitch: u8, velocity: u8, length: u32, spliced: bool) {
+        self.notes.push_back(RollNote { pitch, velocity, age: 0, length: length.max(1), spliced });
+    }
+
+    /// Age every note by a frame, dropping those that have scrolled off.
+    pub fn tick(&mut self) {
+        for n in &mut self.notes { n.age += 1; }
+        self.notes.retain(|n| n.age < ROLL_SPAN + n.length);
+    }
+
+    /// Lowest and highest pitch to show: the notes on the roll, widened to
+    /// at least an octave.
+    pub fn pitch_range(&self) -> (u8, u8) {
+        let lo = self.notes.iter().map(|n| n.pitch).min().unwrap_or(60);
+        let hi = self.notes.iter().map(|n| n.pitch).max().unwrap_or(60);
+        let pad = 12u8.saturating_sub(hi - lo).div_ceil(2);
+        (lo.saturating_sub(pad), hi.saturating_add(pad).min(127))
+    }
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // ScissorAnimation — visual highlight during snip
 // ════════════════════════════════════════════════════════════════════════════
@@ -262,6 +392,17 @@ mod tests {
         assert_eq!(r.patches.last().unwrap().digit, 7);
     }
 
+    #[test]
+    fn finds_stream_and_spliced_patches() {
+        let mut r = RibbonState::new(10, 10, "test");
+        for p in 1..=5 { r.pull(p as u8, p); }
+        r.push(9, 5);
+        assert_eq!(r.patch_at(3, false), Some(2));
+        assert_eq!(r.patch_at(5, false), Some(4));
+        assert_eq!(r.patch_at(5, true),  Some(5));
+        assert_eq!(r.patch_at(42, false), None);
+    }
+
     #[test]
     fn ribbon_scroll_friction() {
         let mut r = RibbonState::new(10, 10, "test");
@@ -282,12 +423,75 @@ mod tests {
         assert_eq!(p, StitchPhase::Stitched);
     }
 
-    #[test]
-    fn tray_max_entries() {
+    fn tray_of(n: usize) -> SnippetTray {
         let mut tray = SnippetTray::default();
-        for i in 0..10 {
-            tray.deposit(&format!("s{}", i), vec![]);
-        }
-        assert!(tray.entries.len() <= 8);
+        for i in 0..n { tray.deposit(&format!("s{}", i), vec![]); }
+        tray
+    }
+
+    fn names(tray: &SnippetTray) -> Vec<&str> {
+        tray.entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn tray_keeps_every_entry_and_scrolls() {
+        let mut tray = tray_of(10);
+        assert_eq!(tray.entries.len(), 10);
+        assert_eq!(tray.first_visible(8), 2);
+        tray.pick(3);
+        assert_eq!(tray.first_visible(8), 0);
+        tray.pick(42);
+        assert_eq!(tray.selected, Some(3));
+    }
+
+    #[test]
+    fn tray_selection_wraps() {
+        let mut tray = tray_of(3);
+        assert_eq!(tray.selected_entry().unwrap().name, "s2");
+        tray.select(1);
+        assert_eq!(tray.selected, Some(2));
+        tray.select(1);
+        assert_eq!(tray.selected, Some(0));
+        tray.select(-1);
+        assert_eq!(tray.selected, Some(2));
+    }
+
+    #[test]
+    fn tray_reorders_renames_and_deletes() {
+        let mut tray = tray_of(3);
+        tray.pick(0);
+        tray.move_selected(1);
+        assert_eq!(names(&tray), ["s1", "s0", "s2"]);
+        tray.move_selected(-5);
+        assert_eq!(names(&tray), ["s0", "s1", "s2"]);
+        assert_eq!(tray.rename_selected("intro").as_deref(), Some("s0"));
+        tray.pick(2);
+        assert_eq!(tray.delete_selected().unwrap().name, "s2");
+        assert_eq!(tray.selected, Some(1));
+        assert_eq!(names(&tray), ["intro", "s1"]);
+    }
+
+    #[test]
+    fn piano_roll_scrolls_notes_off() {
+        let mut roll = PianoRoll::default();
+        roll.push(60, 100, 30, false);
+        for _ in 0..ROLL_SPAN { roll.tick(); }
+        roll.push(64, 80, 0, true);
+        assert_eq!(roll.notes.len(), 2);
+        assert_eq!(roll.notes[1].length, 1);
+        for _ in 0..30 { roll.tick(); }
+        assert_eq!(roll.notes.len(), 1);
+        assert_eq!(roll.notes[0].pitch, 64);
+    }
+
+    #[test]
+    fn piano_roll_spans_at_least_an_octave() {
+        let mut roll = PianoRoll::default();
+        assert_eq!(roll.pitch_range(), (54, 66));
+        roll.push(60, 90, 10, false);
+        roll.push(62, 90, 10, false);
+        assert_eq!(roll.pitch_range(), (55, 67));
+        roll.push(84, 90, 10, false);
+        assert_eq!(roll.pitch_range(), (60, 84));
     }
 }
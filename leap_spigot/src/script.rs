@@ -0,0 +1,195 @@
+//! Scripted gestures.
+//!
+//! [`ScriptedGestureSource`] plays a timed list of gestures through the same
+//! channel as the live sources, so a performance can be rehearsed, demoed
+//! reproducibly, or driven headless in tests.  A script has one gesture per
+//! line:
+//!
+//! ```text
+//! # time (ms from the start, or +ms after the last line)   action [args]
+//! 0       clap
+//! +500    pull_right 3
+//! +250    pull_left 2 0.8
+//! 2000    tempo 140
+//! +1000   scissors lick
+//! 6000    quit
+//! ```
+//!
+//! Actions are the names of the gesture config (`twist`, `splice`,
+//! `next_pair`, …, see [`SimKey`]), doing what their key does.  A few take
+//! arguments:
+//!
+//! | Action | Arguments |
+//! |---|---|
+//! | `pull_left`, `pull_right` | digits (1), velocity 0.0–1.0 (0.3) |
+//! | `scissors` | snippet name (asked for if left out) |
+//! | `tempo` | BPM |
+//! | `expression`, `timbre` | level 0.0–1.0 |
+//! | `scrub` | digits, negative to go back |
+//! | `zoom` | factor |
+//! | `pick_snippet` | tray entry |
+//!
+//! Blank lines and `#` comments are ignored.
+
+use std::path::Path;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde::de::value::{Error as DeError, StrDeserializer};
+
+use crate::gesture::{GestureEvent, GestureSource, SimKey};
+
+/// A timed list of gestures, each at its offset from the start.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Script {
+    pub events: Vec<(Duration, GestureEvent)>,
+}
+
+impl Script {
+    /// Parse a script; errors name the offending line.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut events = Vec::new();
+        let mut last = Duration::ZERO;
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() { continue; }
+            let at_line = |e: String| format!("line {}: {}", n + 1, e);
+            let (time, action) = line.split_once(char::is_whitespace)
+                .ok_or_else(|| at_line(format!("expected a time and an action in {:?}", line)))?;
+            let ms = |s: &str| s.parse::<u64>().map(Duration::from_millis)
+                .map_err(|_| at_line(format!("bad time {:?}", time)));
+            let at = match time.strip_prefix('+') {
+                Some(delay) => last + ms(delay)?,
+                None        => ms(time)?,
+            };
+            if at < last {
+                return Err(at_line(format!("{} ms is before the line above", at.as_millis())));
+            }
+            events.push((at, parse_action(action.trim()).map_err(at_line)?));
+            last = at;
+        }
+        Ok(Script { events })
+    }
+
+    /// Read and parse a script file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        Script::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Offset of the last gesture.
+    pub fn length(&self) -> Duration {
+        self.events.last().map_or(Duration::ZERO, |(at, _)| *at)
+    }
+}
+
+/// One action and its arguments → the gesture.
+fn parse_action(text: &str) -> Result<GestureEvent, String> {
+    let mut words = text.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let args: Vec<&str> = words.collect();
+    let arg = |i| number(name, args.get(i).copied(), None);
+    let or  = |i, default| number(name, args.get(i).copied(), Some(default));
+
+    let event = match name {
+        "pull_left"    => GestureEvent::PullLeft  { steps: or(0, 1.0)? as usize, velocity: or(1, 0.3)? as f32 },
+        "pull_right"   => GestureEvent::PullRight { steps: or(0, 1.0)? as usize, velocity: or(1, 0.3)? as f32 },
+        "scissors"     => GestureEvent::Scissors { name: args.join(" ") },
+        "tempo"        => GestureEvent::Tempo { bpm: arg(0)? as u32 },
+        "expression"   => GestureEvent::Expression { value: (arg(0)? as f32).clamp(0.0, 1.0) },
+        "timbre"       => GestureEvent::Timbre { level: (arg(0)? as f32).clamp(0.0, 1.0) },
+        "scrub"        => GestureEvent::Scrub { steps: arg(0)? as isize },
+        "zoom"         => GestureEvent::Zoom { factor: arg(0)? as f32 },
+        "pick_snippet" => GestureEvent::PickSnippet { index: arg(0)? as usize },
+        _ => {
+            let key = SimKey::deserialize(StrDeserializer::<DeError>::new(name))
+                .map_err(|_| format!("unknown action {:?}", name))?;
+            if !args.is_empty() { return Err(format!("{} takes no arguments", name)); }
+            key.event()
+        }
+    };
+    Ok(event)
+}
+
+/// A numeric argument of `action`, or `default` if it's left out.
+fn number(action: &str, arg: Option<&str>, default: Option<f64>) -> Result<f64, String> {
+    let Some(text) = arg else {
+        return default.ok_or_else(|| format!("{} needs an argument", action));
+    };
+    text.parse::<f64>().ok().filter(|n| n.is_finite())
+        .ok_or_else(|| format!("bad argument {:?} to {}", text, action))
+}
+
+/// Gesture source that plays a [`Script`], each gesture at its time after
+/// the source starts.  Ends after the last one.
+pub struct ScriptedGestureSource {
+    pub script: Script,
+}
+
+impl GestureSource for ScriptedGestureSource {
+    fn run(self: Box<Self>, tx: Sender<GestureEvent>) {
+        let start = Instant::now();
+        for (at, event) in self.script.events {
+            if let Some(wait) = at.checked_sub(start.elapsed()) { thread::sleep(wait); }
+            if tx.send(event).is_err() { return; }
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_times_actions_and_arguments() {
+        let script = Script::parse("
+            # warm up
+            0     clap
+            +500  pull_right 3
+            +250  pull_left 2 0.8   # faster
+            2000  scissors two words
+            2000  next_pair
+        ").unwrap();
+        let ms = Duration::from_millis;
+        assert_eq!(script.events, vec![
+            (ms(0),    GestureEvent::Clap),
+            (ms(500),  GestureEvent::PullRight { steps: 3, velocity: 0.3 }),
+            (ms(750),  GestureEvent::PullLeft  { steps: 2, velocity: 0.8 }),
+            (ms(2000), GestureEvent::Scissors { name: "two words".into() }),
+            (ms(2000), GestureEvent::NextPair),
+        ]);
+        assert_eq!(script.length(), ms(2000));
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(Script::parse("0 clap\n\n10 wave").unwrap_err(), "line 3: unknown action \"wave\"");
+        assert_eq!(Script::parse("0 tempo fast").unwrap_err(), "line 1: bad argument \"fast\" to tempo");
+        assert_eq!(Script::parse("500 clap\n100 unclap").unwrap_err(),
+                   "line 2: 100 ms is before the line above");
+        assert!(Script::parse("0 twist 2").is_err());
+        assert!(Script::parse("soon clap").is_err());
+    }
+
+    #[test]
+    fn plays_in_order_then_ends() {
+        let script = Script::parse("0 clap\n+20 pull_right\n+20 unclap").unwrap();
+        let rx = crate::gesture::spawn_gesture_source(ScriptedGestureSource { script });
+        let start = Instant::now();
+        let events: Vec<_> = rx.iter().collect();
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(events, vec![
+            GestureEvent::Clap,
+            GestureEvent::PullRight { steps: 1, velocity: 0.3 },
+            GestureEvent::Unclap,
+        ]);
+    }
+}
@@ -0,0 +1,403 @@
+//! JACK and ALSA sequencer outputs.
+//!
+//! On Linux pro-audio setups the synths and DAWs often live on the JACK
+//! graph, or expect a sequencer client to connect to, rather than behind
+//! the ports midir lists.  Both backends are picked at run time with the
+//! port selector, like any other port:
+//!
+//! | `--port` | Output |
+//! |---|---|
+//! | `jack` | a JACK MIDI port of our own, for the patchbay to connect |
+//! | `jack:fluid` | the same, connected to the first JACK MIDI input matching `fluid` |
+//! | `alsa` | an ALSA sequencer port of our own |
+//! | `alsa:128:0`, `alsa:qsynth` | the same, subscribed to that client:port or name |
+//!
+//! and each needs its feature built in (`jack`, `alsa-seq`).
+//!
+//! [`JackTransport`] follows the JACK transport as a [`BeatSync`]: notes
+//! fall on its beats at its tempo, and starting or stopping the transport
+//! starts or stops the player.  On either backend the player's MIDI clock,
+//! Start, Continue and Stop go out too, for gear that follows those.
+
+#[cfg(feature = "jack")]
+use std::time::Instant;
+
+use crate::error::AppError;
+use crate::player::MidiOut;
+#[cfg(feature = "jack")]
+use crate::link::BeatSync;
+
+/// JACK client name, and ALSA sequencer client name.
+pub const CLIENT_NAME: &str = "leap_spigot";
+
+#[cfg(any(feature = "jack", all(feature = "alsa-seq", target_os = "linux")))]
+fn device(what: &str, e: impl std::fmt::Display) -> AppError {
+    AppError::Device(format!("{}: {}", what, e))
+}
+
+/// An ALSA sequencer address written `client:port`, e.g. `128:0`.
+pub fn parse_alsa_addr(s: &str) -> Option<(i32, i32)> {
+    let (client, port) = s.trim().split_once(':')?;
+    Some((client.trim().parse().ok()?, port.trim().parse().ok()?))
+}
+
+/// Quarter notes from the top of the piece at a JACK bar, beat and tick
+/// (bars and beats count from 1), in a `beats_per_bar`/`beat_type` metre.
+pub fn bbt_quarters(bar: usize, beat: usize, tick: usize, ticks_per_beat: f64,
+                    beats_per_bar: f32, beat_type: f32) -> f64 {
+    let beats = (bar.max(1) - 1) as f64 * beats_per_bar as f64
+        + (beat.max(1) - 1) as f64
+        + tick as f64 / ticks_per_beat.max(1.0);
+    beats * 4.0 / beat_type.max(1.0) as f64
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// JACK (feature = "jack")
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Open a JACK MIDI output, connected to the first JACK MIDI input whose
+/// name contains `to` if given.
+#[cfg(feature = "jack")]
+pub(crate) fn open_jack(to: Option<&str>) -> Result<Box<dyn MidiOut>, AppError> {
+    let (client, _) = jack::Client::new(CLIENT_NAME, jack::ClientOptions::NO_START_SERVER)
+        .map_err(|e| device("cannot reach the JACK server", e))?;
+    let port = client.register_port("midi_out", jack::MidiOut::default())
+        .map_err(|e| device("cannot register a JACK MIDI port", e))?;
+    let ours = port.name().map_err(|e| device("JACK port", e))?;
+    let dest = match to {
+        Some(text) => {
+            let text = text.to_lowercase();
+            let inputs = client.ports(None, Some("midi"), jack::PortFlags::IS_INPUT);
+            let found = inputs.iter().find(|n| n.to_lowercase().contains(&text)).cloned();
+            Some(found.ok_or_else(|| AppError::Device(format!(
+                "no JACK MIDI input matches {:?} (available: {})", text, inputs.join(", "))))?)
+        }
+        None => None,
+    };
+
+    let (reader, writer) = jack::RingBuffer::new(JACK_QUEUE)
+        .map_err(|e| device("JACK ring buffer", e))?
+        .into_reader_writer();
+    let active = client.activate_async((), JackProcess { port, queue: reader })
+        .map_err(|e| device("cannot start the JACK client", e))?;
+    match dest {
+        Some(dest) => {
+            active.as_client().connect_ports_by_name(&ours, &dest)
+                .map_err(|e| device(&format!("cannot connect {} to {}", ours, dest), e))?;
+            eprintln!("[player] JACK: {} → {}", ours, dest);
+        }
+        None => eprintln!("[player] JACK: created {} (connect it in your patchbay)", ours),
+    }
+    Ok(Box::new(JackOut { queue: writer, _client: active, fault: None, faulted: false }))
+}
+
+#[cfg(not(feature = "jack"))]
+pub(crate) fn open_jack(_to: Option<&str>) -> Result<Box<dyn MidiOut>, AppError> {
+    Err(AppError::Device("built without JACK support (use --features jack)".to_string()))
+}
+
+/// Bytes of messages queued for the process callback: a few seconds of
+/// dense playing.
+#[cfg(feature = "jack")]
+const JACK_QUEUE: usize = 16 * 1024;
+
+/// Each queued message is its length and up to three bytes.
+#[cfg(feature = "jack")]
+type Record = [u8; 4];
+
+/// Plays through a JACK MIDI port.  Messages go to the process callback
+/// through a lock-free ring buffer and out at the start of its next cycle.
+#[cfg(feature = "jack")]
+struct JackOut {
+    queue:   jack::RingBufferWriter,
+    _client: jack::AsyncClient<(), JackProcess>,
+    fault:   Option<String>,
+    faulted: bool,
+}
+
+#[cfg(feature = "jack")]
+impl JackOut {
+    fn send(&mut self, msg: &[u8]) {
+        let mut record: Record = [0; 4];
+        record[0] = msg.len() as u8;
+        record[1..=msg.len()].copy_from_slice(msg);
+        if self.queue.space() < record.len() {
+            if !self.faulted {
+                self.faulted = true;
+                self.fault = Some("JACK isn't taking MIDI (is the server still running?)".to_string());
+            }
+            return;
+        }
+        self.queue.write_buffer(&record);
+    }
+}
+
+#[cfg(feature = "jack")]
+impl MidiOut for JackOut {
+    fn program_change(&mut self, channel: u8, program: u8) {
+        self.send(&[0xC0 | (channel & 0x0F), program]);
+    }
+    fn note_on(&mut self, channel: u8, note: u8, velocity: u8) {
+        self.send(&[0x90 | (channel & 0x0F), note, velocity]);
+    }
+    fn note_off(&mut self, channel: u8, note: u8) {
+        self.send(&[0x80 | (channel & 0x0F), note, 0]);
+    }
+    fn control_change(&mut self, channel: u8, controller: u8, value: u8) {
+        self.send(&[0xB0 | (channel & 0x0F), controller, value]);
+    }
+    fn realtime(&mut self, status: u8) {
+        self.send(&[status]);
+    }
+    fn take_fault(&mut self) -> Option<String> {
+        self.fault.take()
+    }
+}
+
+#[cfg(feature = "jack")]
+struct JackProcess {
+    port:  jack::Port<jack::MidiOut>,
+    queue: jack::RingBufferReader,
+}
+
+#[cfg(feature = "jack")]
+impl jack::ProcessHandler for JackProcess {
+    fn process(&mut self, _: &jack::Client, ps: &jack::ProcessScope) -> jack::Control {
+        let mut out = self.port.writer(ps);
+        let mut record: Record = [0; 4];
+        while self.queue.peek(&mut record) == record.len() {
+            let len = (record[0] as usize).min(3);
+            // A full buffer leaves the rest for the next cycle.
+            if out.write(&jack::RawMidi { time: 0, bytes: &record[1..=len] }).is_err() { break; }
+            self.queue.advance(record.len());
+        }
+        jack::Control::Continue
+    }
+}
+
+/// The JACK transport as a shared timeline.
+///
+/// Tempo and position come from the transport's bar, beat and tick when a
+/// timebase master provides them, otherwise from its frame count at the
+/// tempo given to [`new`](Self::new).  Tempo changes are left to the
+/// timebase master, so [`BeatSync::set_tempo`] does nothing.
+#[cfg(feature = "jack")]
+pub struct JackTransport {
+    client: jack::Client,
+    bpm:    f64,
+}
+
+#[cfg(feature = "jack")]
+impl JackTransport {
+    /// Join the JACK server, counting at `bpm` if no timebase master does.
+    pub fn new(bpm: f64) -> Result<Self, AppError> {
+        let name = format!("{}_transport", CLIENT_NAME);
+        let (client, _) = jack::Client::new(&name, jack::ClientOptions::NO_START_SERVER)
+            .map_err(|e| device("cannot reach the JACK server", e))?;
+        Ok(JackTransport { client, bpm })
+    }
+
+    /// Position in quarter notes and tempo in quarters a minute, now, and
+    /// whether the transport is rolling.
+    fn position(&self) -> (f64, f64, Instant, bool) {
+        let now = Instant::now();
+        let Ok(state) = self.client.transport().query() else { return (0.0, self.bpm, now, false) };
+        let rolling = state.state == jack::TransportState::Rolling;
+        let (beat, bpm) = match state.pos.bbt() {
+            Some(b) => (
+                bbt_quarters(b.bar, b.beat, b.tick, b.ticks_per_beat, b.sig_num, b.sig_denom),
+                b.bpm * 4.0 / b.sig_denom.max(1.0) as f64,
+            ),
+            None => {
+                let rate = state.pos.frame_rate().unwrap_or(48_000).max(1) as f64;
+                (state.pos.frame() as f64 / rate * self.bpm / 60.0, self.bpm)
+            }
+        };
+        (beat, bpm.max(1.0), now, rolling)
+    }
+}
+
+#[cfg(feature = "jack")]
+impl BeatSync for JackTransport {
+    fn tempo(&self) -> f64 {
+        self.position().1
+    }
+
+    fn set_tempo(&self, _bpm: f64) {}
+
+    fn beat_at(&self, at: Instant) -> f64 {
+        let (beat, bpm, now, rolling) = self.position();
+        if !rolling { return beat; }
+        let secs = match at >= now {
+            true  => (at - now).as_secs_f64(),
+            false => -(now - at).as_secs_f64(),
+        };
+        beat + secs * bpm / 60.0
+    }
+
+    fn time_at_beat(&self, beat: f64) -> Instant {
+        let (now_beat, bpm, now, _) = self.position();
+        let secs = (beat - now_beat) * 60.0 / bpm;
+        let offset = std::time::Duration::from_secs_f64(secs.abs());
+        if secs >= 0.0 { now + offset } else { now.checked_sub(offset).unwrap_or(now) }
+    }
+
+    fn rolling(&self) -> Option<bool> {
+        Some(self.position().3)
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// ALSA sequencer (feature = "alsa-seq", Linux)
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Open an ALSA sequencer port, subscribed to `to` — `client:port` or part
+/// of a client or port name — if given.
+#[cfg(all(feature = "alsa-seq", target_os = "linux"))]
+pub(crate) fn open_alsa(to: Option<&str>) -> Result<Box<dyn MidiOut>, AppError> {
+    use std::ffi::CString;
+    use alsa::seq::{Addr, PortCap, PortSubscribe, PortType, Seq};
+
+    let seq = Seq::open(None, Some(alsa::Direction::Playback), false)
+        .map_err(|e| device("cannot open the ALSA sequencer", e))?;
+    let name = CString::new(CLIENT_NAME).unwrap_or_default();
+    seq.set_client_name(&name).map_err(|e| device("ALSA sequencer", e))?;
+    let port_name = CString::new(crate::player::VIRTUAL_PORT_NAME).unwrap_or_default();
+    let port = seq.create_simple_port(&port_name, PortCap::READ | PortCap::SUBS_READ,
+                                      PortType::MIDI_GENERIC | PortType::APPLICATION)
+        .map_err(|e| device("cannot create an ALSA sequencer port", e))?;
+    let me = seq.client_id().map_err(|e| device("ALSA sequencer", e))?;
+
+    if let Some(to) = to {
+        let dest = match parse_alsa_addr(to) {
+            Some((client, port)) => Addr { client, port },
+            None => alsa_port_named(&seq, me, to).ok_or_else(|| AppError::Device(format!(
+                "no ALSA sequencer port matches {:?}", to)))?,
+        };
+        let sub = PortSubscribe::empty().map_err(|e| device("ALSA sequencer", e))?;
+        sub.set_sender(Addr { client: me, port });
+        sub.set_dest(dest);
+        seq.subscribe_port(&sub)
+            .map_err(|e| device(&format!("cannot connect to {}:{}", dest.client, dest.port), e))?;
+        eprintln!("[player] ALSA sequencer: {}:{} → {}:{}", me, port, dest.client, dest.port);
+    } else {
+        eprintln!("[player] ALSA sequencer: created {}:{} (connect it with aconnect)", me, port);
+    }
+    Ok(Box::new(AlsaSeqOut { seq, port, fault: None, faulted: false }))
+}
+
+#[cfg(not(all(feature = "alsa-seq", target_os = "linux")))]
+pub(crate) fn open_alsa(_to: Option<&str>) -> Result<Box<dyn MidiOut>, AppError> {
+    Err(AppError::Device(
+        "built without ALSA sequencer support (use --features alsa-seq, on Linux)".to_string()))
+}
+
+/// First writable port, other than our own client's, whose client or port
+/// name contains `text` (case-insensitive).
+#[cfg(all(feature = "alsa-seq", target_os = "linux"))]
+fn alsa_port_named(seq: &alsa::Seq, me: i32, text: &str) -> Option<alsa::seq::Addr> {
+    use alsa::seq::{Addr, ClientIter, PortCap, PortIter};
+    let text = text.to_lowercase();
+    let writable = PortCap::WRITE | PortCap::SUBS_WRITE;
+    ClientIter::new(seq).filter(|c| c.get_client() != me).find_map(|c| {
+        let client_name = c.get_name().unwrap_or_default().to_lowercase();
+        PortIter::new(seq, c.get_client())
+            .filter(|p| p.get_capability().contains(writable))
+            .find(|p| client_name.contains(&text)
+                || p.get_name().unwrap_or_default().to_lowercase().contains(&text))
+            .map(|p| Addr { client: p.get_client(), port: p.get_port() })
+    })
+}
+
+/// Plays through a port of our own on the ALSA sequencer, to whoever is
+/// subscribed.
+#[cfg(all(feature = "alsa-seq", target_os = "linux"))]
+struct AlsaSeqOut {
+    seq:     alsa::Seq,
+    port:    i32,
+    fault:   Option<String>,
+    faulted: bool,
+}
+
+#[cfg(all(feature = "alsa-seq", target_os = "linux"))]
+impl AlsaSeqOut {
+    fn send<D: alsa::seq::EventData>(&mut self, kind: alsa::seq::EventType, data: &D) {
+        let mut ev = alsa::seq::Event::new(kind, data);
+        ev.set_source(self.port);
+        ev.set_subs();
+        ev.set_direct();
+        if let Err(e) = self.seq.event_output_direct(&mut ev) {
+            if !self.faulted {
+                self.faulted = true;
+                self.fault = Some(format!("ALSA sequencer stopped taking events: {}", e));
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "alsa-seq", target_os = "linux"))]
+impl MidiOut for AlsaSeqOut {
+    fn program_change(&mut self, channel: u8, program: u8) {
+        let ctrl = alsa::seq::EvCtrl { channel: channel & 0x0F, param: 0, value: program as i32 };
+        self.send(alsa::seq::EventType::Pgmchange, &ctrl);
+    }
+    fn note_on(&mut self, channel: u8, note: u8, velocity: u8) {
+        let ev = alsa::seq::EvNote { channel: channel & 0x0F, note, velocity, off_velocity: 0, duration: 0 };
+        self.send(alsa::seq::EventType::Noteon, &ev);
+    }
+    fn note_off(&mut self, channel: u8, note: u8) {
+        let ev = alsa::seq::EvNote { channel: channel & 0x0F, note, velocity: 0, off_velocity: 0, duration: 0 };
+        self.send(alsa::seq::EventType::Noteoff, &ev);
+    }
+    fn control_change(&mut self, channel: u8, controller: u8, value: u8) {
+        let ctrl = alsa::seq::EvCtrl { channel: channel & 0x0F, param: controller as u32, value: value as i32 };
+        self.send(alsa::seq::EventType::Controller, &ctrl);
+    }
+    fn realtime(&mut self, status: u8) {
+        use alsa::seq::EventType;
+        let kind = match status {
+            0xF8 => EventType::Clock,
+            0xFA => EventType::Start,
+            0xFB => EventType::Continue,
+            0xFC => EventType::Stop,
+            _    => return,
+        };
+        self.send(kind, &alsa::seq::EvQueueControl { queue: 0, value: () });
+    }
+    fn take_fault(&mut self) -> Option<String> {
+        self.fault.take()
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_alsa_addresses() {
+        assert_eq!(parse_alsa_addr("128:0"), Some((128, 0)));
+        assert_eq!(parse_alsa_addr(" 20 : 1 "), Some((20, 1)));
+        assert_eq!(parse_alsa_addr("qsynth"), None);
+        assert_eq!(parse_alsa_addr("fluid:x"), None);
+    }
+
+    #[test]
+    fn bar_beat_tick_counts_in_quarter_notes() {
+        assert_eq!(bbt_quarters(1, 1, 0, 1920.0, 4.0, 4.0), 0.0);
+        assert_eq!(bbt_quarters(3, 2, 960, 1920.0, 4.0, 4.0), 9.5);
+        // 6/8: a bar is six eighths, three quarters.
+        assert_eq!(bbt_quarters(2, 1, 0, 1920.0, 6.0, 8.0), 3.0);
+    }
+
+    #[test]
+    fn missing_backends_say_how_to_build_them() {
+        #[cfg(not(feature = "jack"))]
+        assert!(open_jack(None).err().unwrap().to_string().contains("--features jack"));
+        #[cfg(not(all(feature = "alsa-seq", target_os = "linux")))]
+        assert!(open_alsa(None).err().unwrap().to_string().contains("--features alsa-seq"));
+    }
+}
@@ -0,0 +1,276 @@
+//! Saving a performer's material between sessions.
+//!
+//! The snippet tray is written to `tray.toml` in the working directory (or
+//! the file given with `--tray PATH`) when the app exits and read back at
+//! startup.  Each entry keeps its ribbon patches, as drawn in the tray, and
+//! the [`DualStream`] snippet stored under its name, so it can still be
+//! spliced into the performance.
+//!
+//! A [`Session`] goes further and captures the whole performance setup —
+//! both streams with their positions, the ribbons, the tray, tempo,
+//! instrument and dynamics.  `F5` saves one (to the `--session` file, or
+//! `session.toml`) and `--session PATH` restores it at startup:
+//!
+//! ```toml
+//! tempo_bpm  = 96
+//! instrument = 0
+//! velocity   = 100
+//! zoom       = 1.0
+//!
+//! [left]
+//! constant = "Pi"
+//! base     = 10
+//! position = 42
+//! label    = "π  (pi) base 10"
+//! patches  = [{ digit = 3, color = 4294923605, position = 41 }, …]
+//!
+//! [right]
+//! …
+//!
+//! [[snippet]]
+//! name    = "riff"
+//! patches = …
+//! ```
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use dual_spigot::{DualStream, SpigotConfig};
+use spigot_stream::Constant;
+
+use crate::error::AppError;
+use crate::ribbon::{Patch, RibbonState, SnippetTray, TrayEntry};
+
+/// Default tray file, looked for in the working directory.
+pub const TRAY_PATH: &str = "tray.toml";
+
+/// Session file written when no `--session` file was given.
+pub const SESSION_PATH: &str = "session.toml";
+
+/// One tray entry on disk.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SavedSnippet {
+    pub name:    String,
+    pub patches: Vec<(Patch, Patch)>,
+    /// The stream snippet stored under `name`, if there is one.
+    #[serde(default)]
+    pub pairs:   Vec<(u8, u8)>,
+}
+
+/// On-disk layout.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct TrayFile {
+    #[serde(default, rename = "snippet")]
+    snippets: Vec<SavedSnippet>,
+}
+
+/// The tray's entries, with their stream snippets from `dual`.
+pub fn saved_tray(tray: &SnippetTray, dual: &DualStream) -> Vec<SavedSnippet> {
+    tray.entries.iter().map(|e| SavedSnippet {
+        name:    e.name.clone(),
+        patches: e.patches.clone(),
+        pairs:   dual.get_snippet(&e.name).cloned().unwrap_or_default(),
+    }).collect()
+}
+
+/// Put saved entries back into the tray (sliding in afresh) and their
+/// snippets back into `dual`.
+pub fn restore_tray(saved: Vec<SavedSnippet>, tray: &mut SnippetTray, dual: &mut DualStream) {
+    for s in saved {
+        if !s.pairs.is_empty() { dual.insert_snippet(&s.name, s.pairs); }
+        tray.entries.push(TrayEntry { name: s.name, patches: s.patches, slide_in: 0.0 });
+    }
+}
+
+/// Write the tray file.
+pub fn save_tray(saved: Vec<SavedSnippet>, path: impl AsRef<Path>) -> Result<(), AppError> {
+    write_toml(&TrayFile { snippets: saved }, path.as_ref())
+}
+
+/// Read a tray file.
+pub fn load_tray(path: impl AsRef<Path>) -> Result<Vec<SavedSnippet>, AppError> {
+    let file: TrayFile = read_toml(path.as_ref())?;
+    Ok(file.snippets)
+}
+
+fn write_toml<T: Serialize>(value: &T, path: &Path) -> Result<(), AppError> {
+    let text = toml::to_string(value).map_err(|e| AppError::Parameter(e.to_string()))?;
+    std::fs::write(path, text).map_err(|source| AppError::Io { path: path.into(), source })
+}
+
+fn read_toml<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, AppError> {
+    let text = std::fs::read_to_string(path).map_err(|source| AppError::Io { path: path.into(), source })?;
+    toml::from_str(&text).map_err(|e| AppError::Parameter(format!("{}: {}", path.display(), e)))
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Session
+// ════════════════════════════════════════════════════════════════════════════
+
+/// One stream and its ribbon.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SavedSide {
+    /// [`Constant`] variant name, e.g. `"Pi"`.
+    pub constant: String,
+    pub base:     u8,
+    pub position: usize,
+    pub label:    String,
+    pub patches:  Vec<Patch>,
+}
+
+impl SavedSide {
+    pub fn new(config: SpigotConfig, position: usize, ribbon: &RibbonState) -> Self {
+        SavedSide {
+            constant: format!("{:?}", config.constant),
+            base:     config.base,
+            position,
+            label:    ribbon.label.clone(),
+            patches:  ribbon.patches.clone(),
+        }
+    }
+
+    pub fn config(&self) -> Result<SpigotConfig, AppError> {
+        let constant = constant_named(&self.constant)
+            .ok_or_else(|| AppError::Parameter(format!("unknown constant {:?}", self.constant)))?;
+        Ok(SpigotConfig::try_new(constant, self.base)?)
+    }
+
+    /// Show the saved patches and label on `ribbon`.
+    pub fn restore_ribbon(&self, ribbon: &mut RibbonState) {
+        ribbon.base    = self.base;
+        ribbon.label   = self.label.clone();
+        ribbon.patches = self.patches.clone();
+        let excess = ribbon.patches.len().saturating_sub(ribbon.capacity);
+        ribbon.patches.drain(..excess);
+    }
+}
+
+/// A whole performance setup.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Session {
+    pub tempo_bpm:  u32,
+    pub instrument: u8,
+    pub velocity:   u8,
+    pub zoom:       f32,
+    pub left:       SavedSide,
+    pub right:      SavedSide,
+    #[serde(default, rename = "snippet")]
+    pub snippets:   Vec<SavedSnippet>,
+}
+
+impl Session {
+    /// Read a session file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AppError> {
+        let path = path.as_ref();
+        let session: Session = read_toml(path)?;
+        session.left.config().and(session.right.config())
+            .map_err(|e| AppError::Parameter(format!("{}: {}", path.display(), e)))?;
+        Ok(session)
+    }
+
+    /// Write the session file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AppError> {
+        write_toml(self, path.as_ref())
+    }
+}
+
+/// Every constant a side can play.
+const CONSTANTS: [Constant; 8] = [
+    Constant::Pi, Constant::E, Constant::Ln2,
+    Constant::Liouville, Constant::Champernowne, Constant::ThueMorse,
+    Constant::Sqrt2, Constant::Phi,
+];
+
+/// The constant whose variant name is `name`.
+pub fn constant_named(name: &str) -> Option<Constant> {
+    CONSTANTS.into_iter().find(|c| format!("{:?}", c) == name)
+}
+
+/// Parse a stream given as a constant name with an optional base —
+/// `ThueMorse/2`, or `Ln2` for base 10.  Names are matched ignoring case.
+pub fn parse_side(s: &str) -> Option<SpigotConfig> {
+    let (name, base) = s.split_once('/').unwrap_or((s, "10"));
+    let name = name.trim();
+    let constant = CONSTANTS.into_iter().find(|c| format!("{:?}", c).eq_ignore_ascii_case(name))?;
+    let base = base.trim().parse().ok().filter(|b| (2..=36).contains(b))?;
+    Some(SpigotConfig::new(constant, base))
+}
+
+/// Parse a ribbon pair given as `LEFT:RIGHT` streams, e.g.
+/// `Ln2:ThueMorse/2`.
+pub fn parse_pair(s: &str) -> Option<(SpigotConfig, SpigotConfig)> {
+    let (left, right) = s.split_once(':')?;
+    Some((parse_side(left)?, parse_side(right)?))
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tray_round_trips_through_a_file() {
+        let patch = |digit, position| Patch { digit, color: 0xFF112233, position };
+        let mut tray = SnippetTray::default();
+        tray.deposit("riff", vec![(patch(1, 4), patch(0, 4)), (patch(2, 5), patch(1, 5))]);
+        tray.deposit("loose", vec![]);
+        let mut dual = DualStream::new(Constant::Champernowne, Constant::ThueMorse);
+        dual.snip("riff", 0, 2);
+
+        let path = std::env::temp_dir().join(format!("leap_spigot_tray_{}.toml", std::process::id()));
+        save_tray(saved_tray(&tray, &dual), &path).unwrap();
+        let loaded = load_tray(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded, saved_tray(&tray, &dual));
+
+        let (mut tray2, mut dual2) = (SnippetTray::default(), DualStream::new(Constant::Pi, Constant::E));
+        restore_tray(loaded, &mut tray2, &mut dual2);
+        assert_eq!(tray2.entries.len(), 2);
+        assert_eq!(tray2.entries[0].patches[1].0.digit, 2);
+        assert_eq!(dual2.get_snippet("riff"), dual.get_snippet("riff"));
+        assert_eq!(dual2.snippet_keys(), vec!["riff"]);
+    }
+
+    #[test]
+    fn parses_ribbon_pairs() {
+        assert_eq!(parse_pair("Ln2:thuemorse/2"),
+                   Some((SpigotConfig::new(Constant::Ln2, 10), SpigotConfig::new(Constant::ThueMorse, 2))));
+        assert_eq!(parse_pair("Pi"), None);
+        assert_eq!(parse_pair("Pi:Tau"), None);
+        assert_eq!(parse_pair("Pi/1:E"), None);
+    }
+
+    #[test]
+    fn session_round_trips_through_a_file() {
+        let mut ribbon = RibbonState::new(3, 16, "π  (pi) base 16");
+        for p in 0..5 { ribbon.push(p as u8, p); }
+        let side = SavedSide::new(SpigotConfig::new(Constant::Pi, 16), 5, &ribbon);
+        let session = Session {
+            tempo_bpm:  96,
+            instrument: 19,
+            velocity:   80,
+            zoom:       1.25,
+            right:      SavedSide { constant: "ThueMorse".to_string(), base: 2, ..side.clone() },
+            left:       side,
+            snippets:   vec![],
+        };
+        let path = std::env::temp_dir().join(format!("leap_spigot_session_{}.toml", std::process::id()));
+        session.save(&path).unwrap();
+        let loaded = Session::load(&path).unwrap();
+        assert_eq!(loaded, session);
+        assert_eq!(loaded.right.config().unwrap(), SpigotConfig::new(Constant::ThueMorse, 2));
+
+        std::fs::write(&path, toml::to_string(&Session {
+            left: SavedSide { constant: "Tau".to_string(), ..session.left.clone() }, ..session
+        }).unwrap()).unwrap();
+        assert!(Session::load(&path).unwrap_err().to_string().contains("Tau"));
+        std::fs::remove_file(&path).ok();
+    }
+}
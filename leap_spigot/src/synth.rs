@@ -0,0 +1,350 @@
+//! Built-in software synthesizer.
+//!
+//! A small polyphonic [`Synth`] with one simple sound per General MIDI
+//! family — a waveform and an attack / decay / sustain / release envelope —
+//! and noise bursts on the drum channel.  It is no substitute for a real
+//! GM synth, but it means the app is never silent.
+//!
+//! With the `audio` feature, [`SynthOut`] plays it through the default
+//! sound device (via cpal), and the player falls back to it when no MIDI
+//! output port can be opened.
+
+use std::f32::consts::TAU;
+
+/// The GM percussion channel (10, numbered 9 here).
+pub const DRUM_CHANNEL: u8 = 9;
+
+/// Most notes sounding at once; the oldest is cut off for another.
+pub const MAX_VOICES: usize = 32;
+
+/// Overall level of one full-velocity voice.
+const VOICE_GAIN: f32 = 0.2;
+
+// ════════════════════════════════════════════════════════════════════════════
+// Sound — how one family sounds
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Oscillator shape.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wave { Sine, Triangle, Saw, Square, Noise }
+
+impl Wave {
+    /// Value at `phase` (0–1) of the cycle; `noise` is the noise state.
+    fn sample(self, phase: f32, noise: &mut u32) -> f32 {
+        match self {
+            Wave::Sine     => (TAU * phase).sin(),
+            Wave::Triangle => 4.0 * (phase - 0.5).abs() - 1.0,
+            Wave::Saw      => 2.0 * phase - 1.0,
+            Wave::Square   => if phase < 0.5 { 1.0 } else { -1.0 },
+            Wave::Noise    => {
+                // xorshift32
+                *noise ^= *noise << 13;
+                *noise ^= *noise >> 17;
+                *noise ^= *noise << 5;
+                *noise as f32 / u32::MAX as f32 * 2.0 - 1.0
+            }
+        }
+    }
+}
+
+/// A waveform and its envelope; times in seconds, `sustain` a level 0–1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sound {
+    pub wave:    Wave,
+    pub attack:  f32,
+    pub decay:   f32,
+    pub sustain: f32,
+    pub release: f32,
+}
+
+const fn sound(wave: Wave, attack: f32, decay: f32, sustain: f32, release: f32) -> Sound {
+    Sound { wave, attack, decay, sustain, release }
+}
+
+/// One sound for each GM family of 8 programs, in program order.
+const FAMILIES: [Sound; 16] = [
+    sound(Wave::Triangle, 0.005, 1.2,  0.15, 0.3),   // piano
+    sound(Wave::Sine,     0.002, 0.6,  0.0,  0.3),   // chromatic percussion
+    sound(Wave::Square,   0.01,  0.05, 0.8,  0.08),  // organ
+    sound(Wave::Saw,      0.003, 0.8,  0.1,  0.2),   // guitar
+    sound(Wave::Triangle, 0.005, 0.4,  0.5,  0.1),   // bass
+    sound(Wave::Saw,      0.15,  0.2,  0.8,  0.4),   // strings
+    sound(Wave::Saw,      0.25,  0.3,  0.8,  0.6),   // ensemble
+    sound(Wave::Square,   0.04,  0.2,  0.7,  0.15),  // brass
+    sound(Wave::Square,   0.03,  0.1,  0.8,  0.1),   // reed
+    sound(Wave::Sine,     0.05,  0.1,  0.9,  0.15),  // pipe
+    sound(Wave::Saw,      0.01,  0.1,  0.8,  0.1),   // synth lead
+    sound(Wave::Triangle, 0.4,   0.5,  0.8,  1.0),   // synth pad
+    sound(Wave::Sine,     0.2,   0.5,  0.6,  0.8),   // synth effects
+    sound(Wave::Triangle, 0.005, 0.5,  0.2,  0.2),   // ethnic
+    sound(Wave::Sine,     0.001, 0.25, 0.0,  0.1),   // percussive
+    sound(Wave::Noise,    0.05,  0.3,  0.5,  0.3),   // sound effects
+];
+
+/// Every drum is a burst of noise.
+const DRUM: Sound = sound(Wave::Noise, 0.001, 0.12, 0.0, 0.05);
+
+/// The sound of GM `program`'s family.
+pub fn family_sound(program: u8) -> Sound {
+    FAMILIES[(program as usize / 8).min(15)]
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Synth — the voices
+// ════════════════════════════════════════════════════════════════════════════
+
+/// One note sounding.
+#[derive(Clone, Debug)]
+struct Voice {
+    channel: u8,
+    pitch:   u8,
+    sound:   Sound,
+    /// Cycles per sample.
+    step:    f32,
+    phase:   f32,
+    amp:     f32,
+    /// Seconds since Note On.
+    age:     f32,
+    /// Level at Note Off and seconds since, once let go.
+    released: Option<(f32, f32)>,
+    noise:   u32,
+}
+
+impl Voice {
+    /// Envelope level now; `None` once the voice has died away.
+    fn level(&self) -> Option<f32> {
+        let s = self.sound;
+        match self.released {
+            Some((from, t)) => (t < s.release).then(|| from * (1.0 - t / s.release)),
+            None if self.age < s.attack => Some(self.age / s.attack),
+            None if self.age < s.attack + s.decay => {
+                Some(1.0 - (1.0 - s.sustain) * (self.age - s.attack) / s.decay)
+            }
+            None => (s.sustain > 0.0).then_some(s.sustain),
+        }
+    }
+}
+
+/// A polyphonic synth driven by MIDI-style calls, rendering mono samples.
+#[derive(Clone, Debug)]
+pub struct Synth {
+    sample_rate: f32,
+    programs:    [u8; 16],
+    voices:      Vec<Voice>,
+    notes:       u32,
+}
+
+impl Synth {
+    pub fn new(sample_rate: f32) -> Self {
+        Synth { sample_rate, programs: [0; 16], voices: Vec::new(), notes: 0 }
+    }
+
+    pub fn program_change(&mut self, channel: u8, program: u8) {
+        self.programs[channel as usize & 0x0F] = program & 0x7F;
+    }
+
+    /// Start `pitch` on `channel`; velocity 0 lets it go instead.
+    pub fn note_on(&mut self, channel: u8, pitch: u8, velocity: u8) {
+        if velocity == 0 { return self.note_off(channel, pitch); }
+        let channel = channel & 0x0F;
+        let sound = match channel {
+            DRUM_CHANNEL => DRUM,
+            _            => family_sound(self.programs[channel as usize]),
+        };
+        if self.voices.len() >= MAX_VOICES { self.voices.remove(0); }
+        let freq = 440.0 * 2f32.powf((pitch as f32 - 69.0) / 12.0);
+        self.notes = self.notes.wrapping_add(1);
+        self.voices.push(Voice {
+            channel, pitch, sound,
+            step:     freq / self.sample_rate,
+            phase:    0.0,
+            amp:      velocity.min(127) as f32 / 127.0,
+            age:      0.0,
+            released: None,
+            noise:    0x9E37_79B9 ^ self.notes.wrapping_mul(0x85EB_CA6B),
+        });
+    }
+
+    /// Let go of `pitch` on `channel`; it fades over its release.
+    pub fn note_off(&mut self, channel: u8, pitch: u8) {
+        for v in &mut self.voices {
+            if v.channel == channel & 0x0F && v.pitch == pitch && v.released.is_none() {
+                v.released = Some((v.level().unwrap_or(0.0), 0.0));
+            }
+        }
+    }
+
+    /// Silence `channel` at once.
+    pub fn all_off(&mut self, channel: u8) {
+        self.voices.retain(|v| v.channel != channel & 0x0F);
+    }
+
+    /// Number of notes still sounding.
+    pub fn active(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// The next sample of the mix, in −1–1.
+    pub fn next_sample(&mut self) -> f32 {
+        let dt = 1.0 / self.sample_rate;
+        let mut mix = 0.0;
+        self.voices.retain_mut(|v| {
+            let Some(level) = v.level() else { return false };
+            mix += v.sound.wave.sample(v.phase, &mut v.noise) * level * v.amp * VOICE_GAIN;
+            v.phase = (v.phase + v.step).fract();
+            v.age += dt;
+            if let Some((_, t)) = &mut v.released { *t += dt; }
+            true
+        });
+        mix.tanh()
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// SynthOut — the synth on the sound device (feature = "audio")
+// ════════════════════════════════════════════════════════════════════════════
+
+/// The player's output when it falls back to the built-in synth.
+///
+/// The cpal stream lives on a thread of its own (it can't be sent between
+/// threads on every platform) and stops when this is dropped.
+#[cfg(feature = "audio")]
+pub(crate) struct SynthOut {
+    synth: std::sync::Arc<std::sync::Mutex<Synth>>,
+    _stop: std::sync::mpsc::Sender<()>,
+}
+
+#[cfg(feature = "audio")]
+impl SynthOut {
+    /// Start playing the synth through the default output device.
+    pub(crate) fn open() -> Result<Self, String> {
+        use std::sync::{mpsc, Arc, Mutex};
+        use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<Arc<Mutex<Synth>>, String>>();
+        std::thread::spawn(move || {
+            let opened = (|| {
+                let device = cpal::default_host().default_output_device()
+                    .ok_or("no audio output device")?;
+                let supported = device.default_output_config().map_err(|e| e.to_string())?;
+                let config: cpal::StreamConfig = supported.config();
+                let synth = Arc::new(Mutex::new(Synth::new(config.sample_rate.0 as f32)));
+                let stream = match supported.sample_format() {
+                    cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config, &synth),
+                    cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config, &synth),
+                    cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config, &synth),
+                    other => Err(format!("unsupported sample format {:?}", other)),
+                }?;
+                stream.play().map_err(|e| e.to_string())?;
+                Ok((stream, synth))
+            })();
+            match opened {
+                Ok((stream, synth)) => {
+                    let _ = ready_tx.send(Ok(synth));
+                    // Until the SynthOut is dropped.
+                    let _ = stop_rx.recv();
+                    drop(stream);
+                }
+                Err(e) => { let _ = ready_tx.send(Err(e)); }
+            }
+        });
+
+        let synth = ready_rx.recv().map_err(|e| e.to_string())??;
+        Ok(SynthOut { synth, _stop: stop_tx })
+    }
+
+    fn synth(&self) -> std::sync::MutexGuard<'_, Synth> {
+        self.synth.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// An output stream writing the synth to every channel of each frame.
+#[cfg(feature = "audio")]
+fn build_stream<T>(device: &cpal::Device, config: &cpal::StreamConfig,
+                   synth: &std::sync::Arc<std::sync::Mutex<Synth>>) -> Result<cpal::Stream, String>
+where
+    T: cpal::SizedSample + cpal::FromSample<f32>,
+{
+    use cpal::traits::DeviceTrait;
+    let channels = config.channels as usize;
+    let synth = std::sync::Arc::clone(synth);
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            let mut synth = synth.lock().unwrap_or_else(|e| e.into_inner());
+            for frame in data.chunks_mut(channels) {
+                let s = T::from_sample(synth.next_sample());
+                frame.fill(s);
+            }
+        },
+        |e| eprintln!("[synth] audio stream error: {}", e),
+        None,
+    ).map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "audio")]
+impl crate::player::MidiOut for SynthOut {
+    fn program_change(&mut self, channel: u8, program: u8) {
+        self.synth().program_change(channel, program);
+    }
+    fn note_on(&mut self, channel: u8, note: u8, velocity: u8) {
+        self.synth().note_on(channel, note, velocity);
+    }
+    fn note_off(&mut self, channel: u8, note: u8) {
+        self.synth().note_off(channel, note);
+    }
+    fn control_change(&mut self, channel: u8, controller: u8, _value: u8) {
+        // All Notes Off / All Sound Off
+        if controller == 120 || controller == 123 { self.synth().all_off(channel); }
+    }
+    fn realtime(&mut self, _status: u8) {}
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_family_has_its_own_sound() {
+        assert_eq!(family_sound(0).wave, Wave::Triangle);    // Acoustic Grand Piano
+        assert_eq!(family_sound(19).wave, Wave::Square);     // Church Organ
+        assert_eq!(family_sound(48).attack, 0.25);           // String Ensemble 1
+        assert_eq!(family_sound(127).wave, Wave::Noise);     // Gunshot
+    }
+
+    #[test]
+    fn notes_sound_until_released_and_faded() {
+        let rate = 1000.0;
+        let mut synth = Synth::new(rate);
+        synth.program_change(0, 16);   // organ: sustains
+        synth.note_on(0, 69, 127);
+        synth.note_on(1, 60, 100);
+        synth.note_on(1, 64, 0);       // velocity 0 is a Note Off
+        assert_eq!(synth.active(), 2);
+
+        let peak = (0..500).map(|_| synth.next_sample().abs()).fold(0.0, f32::max);
+        assert!(peak > 0.1 && peak <= 1.0, "{}", peak);
+        assert_eq!(synth.active(), 2);
+
+        synth.note_off(0, 69);
+        for _ in 0..100 { synth.next_sample(); }   // organ release: 80 ms
+        assert_eq!(synth.active(), 1);
+        synth.all_off(1);
+        assert_eq!((synth.active(), synth.next_sample()), (0, 0.0));
+    }
+
+    #[test]
+    fn drums_die_away_by_themselves() {
+        let mut synth = Synth::new(1000.0);
+        synth.note_on(DRUM_CHANNEL, 38, 127);
+        for _ in 0..200 { synth.next_sample(); }
+        assert_eq!(synth.active(), 0);
+        for _ in 0..MAX_VOICES + 4 { synth.note_on(0, 60, 90); }
+        assert_eq!(synth.active(), MAX_VOICES);
+    }
+}
@@ -1,6 +1,10 @@
 //! Software-rendered visualizer using `minifb`.
 //!
-//! Three layout modes selected at startup via `--layout`:
+//! A [`Renderer`] draws every frame into a pixel buffer of its own; the
+//! [`Visualizer`] shows its frames in a window, and the browser build
+//! (`leap_web`, where there is no `minifb`) on a canvas.
+//!
+//! Four layout modes selected at startup via `--layout`:
 //!
 //! **flat** (default) — horizontal ribbons, classic left-to-right view.
 //!
@@ -8,8 +12,9 @@
 //! ┌──────────────────────────────────────────┬──────────┐
 //! │  [LEFT RIBBON  ─────────────────────────]│  SNIPPET │
 //! │  [stitch threads when playing]           │  TRAY    │
-//! │  [RIGHT RIBBON ─────────────────────────]│          │
-//! │  status bar                              │          │
+//! │  [RIGHT RIBBON ─────────────────────────]│──────────│
+//! │                                          │  PIANO   │
+//! │  status bar                              │  ROLL    │
 //! └──────────────────────────────────────────┴──────────┘
 //! ```
 //!
@@ -39,12 +44,72 @@
 //!      □ □ □ □ □ □ □ □ □ · · · ·
 //!  RIGHT ─────────────────────────────────── vanishing pt
 //! ```
-
-use minifb::{Key, Window, WindowOptions, KeyRepeat};
-use crate::gesture::{SimInput, SimKey, GestureEvent};
+//!
+//! **spiral** — each stream wound onto one arm of a double spiral, newest
+//!              digit outermost, slowly turning; `V` switches to it and back
+//!              from any other layout.
+//!
+//! ```text
+//!          ● ● ●
+//!       ●  ○ ○ ○  ●
+//!      ● ○  ● ●  ○ ●      ● left arm
+//!      ● ○ ●   ● ○ ●      ○ right arm
+//!       ●  ○ ○ ○  ●
+//!          ● ● ●
+//! ```
+//!
+//! In every layout the bottom of the tray column holds a piano roll of the
+//! notes just played — pitch up, time scrolling left, brighter and redder
+//! the louder the note — so the audience sees the music as well as the
+//! digits.
+//!
+//! With more than one ribbon pair, the flat layout stacks the others small
+//! below the pair being pulled, each labelled with its number and channel
+//! and lit as it plays.
+//!
+//! In the flat layout the mouse works on the pulled ribbons: clicking a
+//! patch shows its digit and stream position in the status bar, and
+//! dragging across patches marks them (outlined in yellow) as the range the
+//! next snip takes instead of the whole ribbon.  Clicking a tray entry
+//! selects it in any layout.
+//!
+//! Turning the scroll wheel (or swiping two fingers on a trackpad) over a
+//! ribbon pulls it, a digit per notch and harder the faster it turns.  In
+//! the spiral, whose arms wind together, the left half of the picture
+//! stands for the left stream and the right half for the right.
+//!
+//! `H` lays a histogram over the ribbons, left of the tray: how often each
+//! digit has turned up in either stream so far, against the `1/base` share
+//! every digit would have if the constant is normal.
+//!
+//! The window opens at 1280×720 and can be resized; every layout, the tray
+//! and the number of patches on each ribbon follow its size.
+//!
+//! `F11` (or `--fullscreen`) swaps the window for a borderless one covering
+//! the screen — `--display WxH`, 1920×1080 by default — for projection.
+//!
+//! `F10` (or `--detach-tray`) moves the tray, piano roll, histogram, status
+//! bar and name prompt to a second window, so the projection shows only the
+//! ribbons while the performer keeps the controls on a laptop screen.  Keys
+//! work in either window; closing the second one brings the tray back.
+//!
+//! On HiDPI screens everything is drawn at a UI scale — `--scale N`, or
+//! the desktop's `GDK_SCALE` / `QT_SCALE_FACTOR` — and enlarged to fit, so
+//! patches, tray and the 8×8 font stay legible.
+
+#[cfg(not(target_arch = "wasm32"))]
+use minifb::{Key, Window, WindowOptions, KeyRepeat, MouseButton, MouseMode};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::capture;
+use crate::font::{self, digit_char, GLYPH_H, GLYPH_W};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::gesture::SimInput;
+use crate::gesture::{SimKey, GestureEvent};
+use std::collections::BTreeMap;
 use crate::ribbon::{
-    RibbonState, StitchPhase, SnippetTray, ScissorAnimation,
+    digit_color, RibbonState, StitchPhase, SnippetTray, ScissorAnimation, PianoRoll, ROLL_SPAN,
 };
+#[cfg(not(target_arch = "wasm32"))]
 use std::sync::mpsc::Sender;
 
 // ════════════════════════════════════════════════════════════════════════════
@@ -60,14 +125,27 @@ pub enum LayoutMode {
     TwoD,
     /// Perspective ribbons receding into the screen with hand ghosts.
     ThreeD,
+    /// Both streams wound onto a double spiral.
+    Spiral,
 }
 
 impl LayoutMode {
     pub fn from_str(s: &str) -> Self {
         match s.to_ascii_lowercase().as_str() {
-            "2d"   => LayoutMode::TwoD,
-            "3d"   => LayoutMode::ThreeD,
-            _      => LayoutMode::Flat,
+            "2d"     => LayoutMode::TwoD,
+            "3d"     => LayoutMode::ThreeD,
+            "spiral" => LayoutMode::Spiral,
+            _        => LayoutMode::Flat,
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn title(self) -> &'static str {
+        match self {
+            LayoutMode::Flat   => "Leap Spigot — Flat View",
+            LayoutMode::TwoD   => "Leap Spigot — 2D View",
+            LayoutMode::ThreeD => "Leap Spigot — 3D View",
+            LayoutMode::Spiral => "Leap Spigot — Spiral View",
         }
     }
 }
@@ -76,35 +154,48 @@ impl LayoutMode {
 // Window / buffer constants
 // ════════════════════════════════════════════════════════════════════════════
 
+/// Initial window size (before UI scaling); the window can be resized
+/// from there.
 pub const WIN_W: usize = 1280;
 pub const WIN_H: usize = 720;
+/// Screen covered in fullscreen unless `--display` says otherwise.
+pub const DISPLAY_SIZE: (usize, usize) = (1920, 1080);
+/// Smallest drawing surface — a smaller window shows it scaled down.
+const MIN_W:           usize = 640;
+const MIN_H:           usize = 360;
+#[cfg(not(target_arch = "wasm32"))]
+const PANEL_W:         usize = 480;                   // detached tray window
+#[cfg(not(target_arch = "wasm32"))]
+const PANEL_MIN_W:     usize = 360;
+const STATUS_H:        usize = 36;
 
 const BG_COLOR:        u32 = 0xFF1A1A2E;
 const TRAY_BG:         u32 = 0xFF16213E;
 const STITCH_COLOR:    u32 = 0xFFFFD700;
 const HIGHLIGHT_COLOR: u32 = 0xFFFFFF00;
 const TEXT_BG:         u32 = 0xFF0F3460;
-const TRAY_W:          usize = 220;
+const ROLL_BG:         u32 = 0xFF101828;
+const ROLL_QUIET:      u32 = 0xFF3060C0;                 // pianissimo note
+const ROLL_LOUD:       u32 = 0xFFFF5030;                 // fortissimo note
+const STACK_PATCH_H:   usize = 22;                    // stacked pairs' patches
+const STACK_ROW_H:     usize = 12 + 2 * STACK_PATCH_H + 12;
+const TRAY_TOP:        usize = 32;
+const TRAY_ROW_H:      usize = 56;
 
 // ── Flat layout ────────────────────────────────────────────────────────────
-const FLAT_RIBBON_W:   usize = WIN_W - TRAY_W;
-const FLAT_PATCH_W:    usize = 48;
+/// Scroll in one frame (in wheel notches) that pulls at full speed.
+#[cfg(not(target_arch = "wasm32"))]
+const WHEEL_FULL_SPEED: f32 = 6.0;
+
+pub const FLAT_PATCH_W: usize = 48;
 const FLAT_PATCH_H:    usize = 90;
-const FLAT_LEFT_Y:     usize = 60;
-const FLAT_RIGHT_Y:    usize = 340;
-const FLAT_STATUS_Y:   usize = WIN_H - 36;
 
 // ── 2D layout ──────────────────────────────────────────────────────────────
 const TD_PATCH_W:      usize = 80;
 const TD_PATCH_H:      usize = 48;
-const TD_LEFT_X:       usize = 120;
-const TD_RIGHT_X:      usize = 520;
 const TD_RIBBON_W:     usize = TD_PATCH_W;
-const TD_BOTTOM_Y:     usize = WIN_H - 80;
 
 // ── 3D layout ──────────────────────────────────────────────────────────────
-const P3_VPX:          f32   = WIN_W as f32 / 2.0;   // vanishing point x
-const P3_VPY:          f32   = WIN_H as f32 / 2.0;   // vanishing point y
 const P3_FOCAL:        f32   = 600.0;                 // focal length
 const P3_NEAR_Z:       f32   = 0.5;                   // nearest patch z
 const P3_FAR_Z:        f32   = 20.0;                  // farthest patch z
@@ -113,6 +204,233 @@ const P3_RIGHT_WORLD_Y:f32   =  1.4;                  // world-Y of right ribbon
 const P3_PATCH_DEPTH:  f32   = 0.9;                   // z-spacing between patches
 const P3_PATCH_HALF_W: f32   = 0.55;                  // half-width of patch in world units
 
+// ── Spiral layout ──────────────────────────────────────────────────────────
+const SPIRAL_SPACING:  f32   = 28.0;                  // patch pitch along and across the arms
+const SPIRAL_R_MIN:    f32   = 36.0;                  // innermost radius
+const SPIRAL_SPIN:     f32   = 0.002;                 // radians turned per frame
+
+// ════════════════════════════════════════════════════════════════════════════
+// UI scale
+// ════════════════════════════════════════════════════════════════════════════
+
+/// A UI scale factor as given on the command line or in the environment.
+pub fn parse_scale(s: &str) -> Option<f32> {
+    s.trim().parse::<f32>().ok().filter(|x| (1.0..=4.0).contains(x))
+}
+
+/// A screen size written `WIDTHxHEIGHT`, e.g. `1920x1080`.
+pub fn parse_size(s: &str) -> Option<(usize, usize)> {
+    let (w, h) = s.trim().split_once(['x', 'X', '×'])?;
+    let size = (w.parse().ok()?, h.parse().ok()?);
+    (size.0 >= MIN_W && size.1 >= MIN_H).then_some(size)
+}
+
+/// The desktop's UI scale (`GDK_SCALE`, then `QT_SCALE_FACTOR`), or 1.0.
+pub fn detect_scale() -> f32 {
+    ["GDK_SCALE", "QT_SCALE_FACTOR"].iter()
+        .find_map(|var| parse_scale(&std::env::var(var).ok()?))
+        .unwrap_or(1.0)
+}
+
+/// Patch places along one arm of a double Archimedean spiral, outermost
+/// first, as (angle, radius): `spacing` apart along the arm and from the
+/// other arm (which is turned half a revolution), winding in from `r_max`
+/// to `SPIRAL_R_MIN`.
+fn spiral_arm(r_max: f32, spacing: f32, phase: f32) -> Vec<(f32, f32)> {
+    let pitch = spacing / std::f32::consts::PI;   // radius lost per radian
+    let mut places = Vec::new();
+    let mut turned = 0.0;
+    loop {
+        let r = r_max - pitch * turned;
+        if r < SPIRAL_R_MIN { return places; }
+        places.push((phase + turned, r));
+        turned += spacing / r;
+    }
+}
+
+/// Show frame `buf`, laid out by `lay`, in `window`, enlarged by `scale`
+/// through the scratch buffer `screen`.
+#[cfg(not(target_arch = "wasm32"))]
+fn present(window: &mut Window, buf: &[u32], lay: &Layout, scale: f32, screen: &mut Vec<u32>) {
+    if scale == 1.0 {
+        window.update_with_buffer(buf, lay.w, lay.h).ok();
+    } else {
+        let (w, h) = upscale(buf, lay.w, lay.h, scale, screen);
+        window.update_with_buffer(screen, w, h).ok();
+    }
+}
+
+/// Enlarge a `w`×`h` frame by `scale` into `dst` (nearest pixel),
+/// returning the enlarged size.
+#[cfg(not(target_arch = "wasm32"))]
+fn upscale(src: &[u32], w: usize, h: usize, scale: f32, dst: &mut Vec<u32>) -> (usize, usize) {
+    let (dw, dh) = ((w as f32 * scale) as usize, (h as f32 * scale) as usize);
+    let cols: Vec<usize> = (0..dw).map(|x| ((x as f32 / scale) as usize).min(w - 1)).collect();
+    dst.resize(dw * dh, 0);
+    for (y, row) in dst.chunks_exact_mut(dw).enumerate() {
+        let sy = ((y as f32 / scale) as usize).min(h - 1);
+        for (px, &sx) in row.iter_mut().zip(&cols) { *px = src[sy * w + sx]; }
+    }
+    (dw, dh)
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Layout — positions that follow the window size
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Where things go in a window of a given size.  Recomputed whenever the
+/// window is resized; the proportions are those of the default 1280×720.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Layout {
+    /// Drawing surface size.
+    w:            usize,
+    h:            usize,
+    tray_w:       usize,
+    /// Width left for the flat ribbons beside the tray.
+    ribbon_w:     usize,
+    /// Tray entries that fit above the piano roll.
+    tray_rows:    usize,
+    /// Piano-roll panel at the foot of the tray column.
+    roll_y:       usize,
+    roll_h:       usize,
+    status_y:     usize,
+    flat_left_y:  usize,
+    flat_right_y: usize,
+    td_left_x:    usize,
+    td_right_x:   usize,
+    td_bottom_y:  usize,
+    /// Vanishing point.
+    vpx:          f32,
+    vpy:          f32,
+}
+
+impl Layout {
+    fn new(w: usize, h: usize) -> Self {
+        let (w, h) = (w.max(MIN_W), h.max(MIN_H));
+        Layout::with_tray(w, h, (w * 11 / 64).clamp(180, 360), STATUS_H)
+    }
+
+    /// The ribbons alone, filling the window, while the tray is detached.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ribbons_only(w: usize, h: usize) -> Self {
+        Layout::with_tray(w.max(MIN_W), h.max(MIN_H), 0, 0)
+    }
+
+    /// The detached tray window: the tray column, with the histogram
+    /// beside it when shown, over the status bar.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn panel(w: usize, h: usize, histogram: bool) -> Self {
+        let (w, h) = (w.max(PANEL_MIN_W), h.max(MIN_H));
+        Layout::with_tray(w, h, if histogram { w / 2 } else { w }, STATUS_H)
+    }
+
+    fn with_tray(w: usize, h: usize, tray_w: usize, status_h: usize) -> Self {
+        let status_y = h - status_h;
+        let roll_h   = status_y / 4;
+        let roll_y   = status_y - roll_h;
+        Layout {
+            w, h, tray_w,
+            ribbon_w:     w - tray_w,
+            tray_rows:    (roll_y - TRAY_TOP) / TRAY_ROW_H,
+            roll_y,
+            roll_h,
+            status_y,
+            flat_left_y:  h / 12,
+            flat_right_y: h * 17 / 36,
+            td_left_x:    w * 3 / 32,
+            td_right_x:   w * 13 / 32,
+            td_bottom_y:  h - 80,
+            vpx:          w as f32 / 2.0,
+            vpy:          h as f32 / 2.0,
+        }
+    }
+
+    /// Project a 3D world point to screen pixel coordinates.
+    /// World: X = screen-right, Y = screen-up (inverted for screen), Z = depth (into screen).
+    fn project(&self, wx: f32, wy: f32, wz: f32) -> (f32, f32) {
+        let z = wz.max(0.001);
+        let sx = self.vpx + wx * P3_FOCAL / z;
+        let sy = self.vpy - wy * P3_FOCAL / z;   // Y flipped: world-up = screen-up
+        (sx, sy)
+    }
+
+    /// Patches that fit along a ribbon in `mode` with patches scaled by
+    /// `zoom`, plus two scrolling in at the edge.
+    fn ribbon_capacity(&self, mode: LayoutMode, zoom: f32) -> usize {
+        let fit = match mode {
+            LayoutMode::Flat   => self.ribbon_w as f32 / (FLAT_PATCH_W as f32 * zoom),
+            LayoutMode::TwoD   => self.td_bottom_y as f32 / (TD_PATCH_H as f32 * zoom),
+            LayoutMode::ThreeD => (P3_FAR_Z - P3_NEAR_Z) / (P3_PATCH_DEPTH * zoom),
+            LayoutMode::Spiral => {
+                let (_, _, r_max) = self.spiral_frame();
+                spiral_arm(r_max, SPIRAL_SPACING * zoom, 0.0).len() as f32
+            }
+        };
+        fit as usize + 2
+    }
+
+    /// Centre and outer radius of the spiral, in the space left of the tray.
+    fn spiral_frame(&self) -> (f32, f32, f32) {
+        let (cx, cy) = (self.ribbon_w as f32 / 2.0, self.status_y as f32 / 2.0);
+        (cx, cy, cx.min(cy) - 24.0)
+    }
+
+    /// The ribbon (0 left, 1 right) nearer window point `(x, y)` in
+    /// `mode`, if it's over the ribbons at all.
+    fn ribbon_side_at(&self, mode: LayoutMode, x: usize, y: usize) -> Option<usize> {
+        if x >= self.ribbon_w || y >= self.status_y { return None; }
+        let right = match mode {
+            LayoutMode::Flat   => 2 * y >= self.flat_left_y + self.flat_right_y + FLAT_PATCH_H,
+            LayoutMode::TwoD   => 2 * x >= self.td_left_x + self.td_right_x + TD_RIBBON_W,
+            LayoutMode::ThreeD => (y as f32) < self.vpy,
+            LayoutMode::Spiral => 2 * x >= self.ribbon_w,
+        };
+        Some(right as usize)
+    }
+
+    /// The tray row (counted from the first one drawn) under window point
+    /// `(x, y)`, if any.
+    fn tray_row(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.ribbon_w || y < TRAY_TOP { return None; }
+        let (row, within) = ((y - TRAY_TOP) / TRAY_ROW_H, (y - TRAY_TOP) % TRAY_ROW_H);
+        (row < self.tray_rows && within < 50).then_some(row)
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// StackedPair — a ribbon pair in the background
+// ════════════════════════════════════════════════════════════════════════════
+
+/// A ribbon pair playing alongside the one being pulled, drawn small below
+/// it in the flat layout.
+pub struct StackedPair<'a> {
+    pub left:      &'a RibbonState,
+    pub right:     &'a RibbonState,
+    /// Patches of the note sounding on (left, right), if in view.
+    pub highlight: (Option<usize>, Option<usize>),
+    pub label:     String,
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Scene — what a frame shows
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Everything one frame shows, as the app holds it.
+pub struct Scene<'a> {
+    pub left:           &'a RibbonState,
+    pub right:          &'a RibbonState,
+    pub stitch:         &'a StitchPhase,
+    pub tray:           &'a SnippetTray,
+    pub scissor:        &'a Option<ScissorAnimation>,
+    pub status:         &'a str,
+    pub playing:        bool,
+    /// Patches of the note sounding on (left, right), if in view.
+    pub note_highlight: (Option<usize>, Option<usize>),
+    pub roll:           &'a PianoRoll,
+    /// The other ribbon pairs, drawn small below in the flat layout.
+    pub stacked:        &'a [StackedPair<'a>],
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // GestureState — tracked for hand ghost animation
 // ════════════════════════════════════════════════════════════════════════════
@@ -128,95 +446,582 @@ pub enum HandGesture {
     Scissors,
 }
 
+impl HandGesture {
+    /// The hand pose that goes with `event`.
+    pub fn of(event: &GestureEvent) -> Self {
+        match event {
+            GestureEvent::PullLeft  { .. } => HandGesture::PullLeft,
+            GestureEvent::PullRight { .. } => HandGesture::PullRight,
+            GestureEvent::Twist            => HandGesture::Twist,
+            GestureEvent::Clap             => HandGesture::Clap,
+            GestureEvent::Scissors  { .. } => HandGesture::Scissors,
+            _                              => HandGesture::Idle,
+        }
+    }
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // Visualizer
 // ════════════════════════════════════════════════════════════════════════════
 
+/// The `minifb` window (and the tray's, while detached) showing the
+/// [`Renderer`]'s frames, turning keys, clicks and the wheel into
+/// [`SimInput`]s.
+#[cfg(not(target_arch = "wasm32"))]
 pub struct Visualizer {
     window:        Window,
-    buf:           Vec<u32>,
+    title:         &'static str,
+    /// Size and position of the window before going fullscreen, while
+    /// fullscreen.
+    windowed:      Option<((usize, usize), (isize, isize))>,
+    /// Screen size the fullscreen window covers.
+    display:       (usize, usize),
+    /// Draws each frame at the unscaled window size.
+    renderer:      Renderer,
+    /// Frame enlarged by `scale`, as shown.
+    screen:        Vec<u32>,
+    /// UI scale, 1.0 = one window pixel per drawn pixel.
+    scale:         f32,
     sim_tx:        Sender<SimInput>,
-    pub layout:    LayoutMode,
-    /// Last known gesture for hand ghost animation.
-    hand_gesture:  HandGesture,
-    /// Frame counter — drives subtle animations.
-    frame:         u64,
+    /// Key bindings (key, with Ctrl, action), ordered by action.
+    keys:          Vec<(Key, bool, SimKey)>,
+    /// Whether the left mouse button was down last poll.
+    mouse_down:    bool,
+    /// Patch drag in progress: ribbon (0 left, 1 right), first and latest
+    /// patch under the mouse.
+    drag:          Option<(usize, usize, usize)>,
+    /// Scroll turned over the (left, right) ribbon not yet pulled.
+    wheel:         [f32; 2],
+    /// Name finished since the app last asked (empty if cancelled).
+    entered:       Option<String>,
+    /// The tray's own window, while it's detached.
+    panel:         Option<Panel>,
+}
+
+/// Second window holding the tray, piano roll, histogram and status bar.
+#[cfg(not(target_arch = "wasm32"))]
+struct Panel {
+    window:     Window,
+    /// Frame at the unscaled size of `lay`.
+    buf:        Vec<u32>,
+    lay:        Layout,
+    /// Whether the left mouse button was down last poll.
+    mouse_down: bool,
+}
+
+/// The on-screen name prompt.
+struct TextEntry {
+    prompt: String,
+    text:   String,
+}
+
+/// Longest name the prompt accepts.
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_NAME_LEN: usize = 32;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TextEntry {
+    /// Apply one key press.  Returns the finished name on Enter, or an
+    /// empty one on Escape.
+    fn press(&mut self, key: Key, shift: bool) -> Option<String> {
+        match key {
+            Key::Enter     => Some(self.text.trim().to_string()),
+            Key::Escape    => Some(String::new()),
+            Key::Backspace => { self.text.pop(); None }
+            _ => {
+                let c = key_char(key, shift)?;
+                if self.text.len() < MAX_NAME_LEN { self.text.push(c); }
+                None
+            }
+        }
+    }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl Visualizer {
-    pub fn new(sim_tx: Sender<SimInput>, layout: LayoutMode) -> Result<Self, String> {
-        let title = match layout {
-            LayoutMode::Flat   => "Leap Spigot — Flat View",
-            LayoutMode::TwoD   => "Leap Spigot — 2D View",
-            LayoutMode::ThreeD => "Leap Spigot — 3D View",
-        };
+    pub fn new(sim_tx: Sender<SimInput>, layout: LayoutMode, scale: f32) -> Result<Self, String> {
+        let title = layout.title();
         let mut window = Window::new(
-            title, WIN_W, WIN_H,
-            WindowOptions { resize: false, ..WindowOptions::default() },
+            title, (WIN_W as f32 * scale) as usize, (WIN_H as f32 * scale) as usize,
+            WindowOptions { resize: true, ..WindowOptions::default() },
         ).map_err(|e| e.to_string())?;
 
         window.set_target_fps(60);
 
         Ok(Visualizer {
             window,
-            buf: vec![BG_COLOR; WIN_W * WIN_H],
+            title,
+            windowed: None,
+            display: DISPLAY_SIZE,
+            renderer: Renderer::new(layout, WIN_W, WIN_H),
+            screen: Vec::new(),
+            scale,
             sim_tx,
-            layout,
-            hand_gesture: HandGesture::Idle,
-            frame: 0,
+            keys: Vec::new(),
+            mouse_down: false,
+            drag: None,
+            wheel: [0.0; 2],
+            entered: None,
+            panel: None,
         })
     }
 
+    /// Bind keys (by name, as in the gesture config) to actions.
+    pub fn set_keys(&mut self, keys: &BTreeMap<String, SimKey>) -> Result<(), String> {
+        let mut bound = keys.iter()
+            .map(|(name, &action)| {
+                let (key, ctrl) = parse_binding(name).ok_or_else(|| format!("unknown key {:?}", name))?;
+                Ok((key, ctrl, action))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        bound.sort_by_key(|&(_, _, action)| action);
+        self.renderer.set_legend(key_legend(keys));
+        self.keys = bound;
+        Ok(())
+    }
+
     pub fn is_open(&self) -> bool { self.window.is_open() }
 
+    /// The last frame drawn, at its unscaled size: pixels, width, height.
+    pub fn frame(&self) -> (&[u32], usize, usize) {
+        self.renderer.frame()
+    }
+
+    /// Save the last frame drawn, at its unscaled size, as a PNG.
+    pub fn save_screenshot(&self, path: &str) -> Result<(), String> {
+        let (pixels, w, h) = self.frame();
+        capture::save_png(path, pixels, w, h)
+    }
+
+    /// Screen size for fullscreen.
+    pub fn set_display_size(&mut self, size: (usize, usize)) {
+        self.display = size;
+    }
+
+    /// Switch to the spiral view, or back to the layout before it.
+    pub fn toggle_spiral(&mut self) {
+        self.renderer.toggle_spiral();
+        self.title = self.renderer.layout.title();
+        self.window.set_title(self.title);
+    }
+
+    /// Show or hide the digit histogram panel.
+    pub fn toggle_histogram(&mut self) {
+        self.renderer.toggle_histogram();
+    }
+
+    pub fn is_fullscreen(&self) -> bool { self.windowed.is_some() }
+
+    /// Replace the window with a borderless one covering the screen, or
+    /// go back to the window as it was.  The layout follows on the next
+    /// frame.
+    pub fn set_fullscreen(&mut self, on: bool) -> Result<(), String> {
+        if on == self.is_fullscreen() { return Ok(()); }
+        let ((w, h), (x, y), options) = match self.windowed {
+            None => {
+                let windowed = (self.window.get_size(), self.window.get_position());
+                self.windowed = Some(windowed);
+                let options = WindowOptions { borderless: true, title: false, topmost: true,
+                                              ..WindowOptions::default() };
+                (self.display, (0, 0), options)
+            }
+            Some((size, pos)) => {
+                self.windowed = None;
+                (size, pos, WindowOptions { resize: true, ..WindowOptions::default() })
+            }
+        };
+        let mut window = Window::new(self.title, w, h, options).map_err(|e| e.to_string())?;
+        window.set_position(x, y);
+        window.set_target_fps(60);
+        self.window = window;
+        Ok(())
+    }
+
+    pub fn is_detached(&self) -> bool { self.panel.is_some() }
+
+    /// Open the tray, piano roll, histogram and status bar in a window of
+    /// their own, leaving the ribbons the whole of this one, or close it
+    /// and put them back.
+    pub fn set_detached(&mut self, on: bool) -> Result<(), String> {
+        if on == self.is_detached() { return Ok(()); }
+        self.panel = match on {
+            true => {
+                let size = |n: usize| (n as f32 * self.scale) as usize;
+                let mut window = Window::new(
+                    "Leap Spigot — Tray", size(PANEL_W), size(WIN_H),
+                    WindowOptions { resize: true, ..WindowOptions::default() },
+                ).map_err(|e| e.to_string())?;
+                window.set_target_fps(60);
+                let lay = Layout::panel(PANEL_W, WIN_H, self.renderer.histogram);
+                Some(Panel { window, buf: vec![TRAY_BG; lay.w * lay.h], lay, mouse_down: false })
+            }
+            false => None,
+        };
+        Ok(())
+    }
+
     /// Note the most recent gesture so hand ghosts can animate.
     pub fn notify_gesture(&mut self, g: HandGesture) {
-        self.hand_gesture = g;
+        self.renderer.notify_gesture(g);
+    }
+
+    /// Brightness of the downbeat pulse for the next frame.
+    pub fn set_beat_pulse(&mut self, pulse: f32) {
+        self.renderer.set_beat_pulse(pulse);
+    }
+
+    /// Outline patches `first..last` of the pulled ribbons as marked for
+    /// the next snip.
+    pub fn set_marked(&mut self, marked: Option<(usize, usize)>) {
+        self.renderer.set_marked(marked);
+    }
+
+    /// Patch size multiplier for the next frame.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.renderer.set_zoom(zoom);
+    }
+
+    /// Open the on-screen prompt.  Until Enter or Escape, keystrokes type
+    /// into it instead of triggering gestures.
+    pub fn begin_text_entry(&mut self, prompt: &str) {
+        self.renderer.text_entry = Some(TextEntry { prompt: prompt.to_string(), text: String::new() });
+        self.entered = None;
+    }
+
+    /// The name typed into the prompt once it has been closed — empty if
+    /// it was cancelled.
+    pub fn take_entered_text(&mut self) -> Option<String> {
+        self.entered.take()
+    }
+
+    /// Patches a ribbon needs to fill its space at the current window
+    /// size and zoom.
+    pub fn ribbon_capacity(&self) -> usize {
+        self.renderer.ribbon_capacity()
+    }
+
+    /// Follow the windows to their current sizes.
+    fn fit_window(&mut self) {
+        let unscaled = |(w, h): (usize, usize)| ((w as f32 / self.scale) as usize, (h as f32 / self.scale) as usize);
+        let (w, h) = unscaled(self.window.get_size());
+        let lay = if self.is_detached() { Layout::ribbons_only(w, h) } else { Layout::new(w, h) };
+        self.renderer.set_layout(lay);
+        if let Some(panel) = self.panel.as_mut() {
+            let (w, h) = unscaled(panel.window.get_size());
+            let lay = Layout::panel(w, h, self.renderer.histogram);
+            if lay != panel.lay {
+                panel.lay = lay;
+                panel.buf.resize(lay.w * lay.h, TRAY_BG);
+            }
+        }
     }
 
     // ── input polling ─────────────────────────────────────────────────────
 
     pub fn poll_input(&mut self) -> bool {
         if !self.window.is_open() { return false; }
+        // Closing the tray's window puts the tray back.
+        if self.panel.as_ref().is_some_and(|p| !p.window.is_open()) { self.panel = None; }
+
+        // Keys count in either window.
+        let windows: Vec<&Window> = std::iter::once(&self.window)
+            .chain(self.panel.as_ref().map(|p| &p.window))
+            .collect();
+        let down = |k: Key| windows.iter().any(|w| w.is_key_down(k));
+        let shift = down(Key::LeftShift) || down(Key::RightShift);
+
+        // While the name prompt is open it takes every keystroke.
+        if let Some(entry) = self.renderer.text_entry.as_mut() {
+            let pressed: Vec<Key> = windows.iter().flat_map(|w| w.get_keys_pressed(KeyRepeat::Yes)).collect();
+            for key in pressed {
+                if let Some(name) = entry.press(key, shift) {
+                    self.entered = Some(name);
+                    self.renderer.text_entry = None;
+                    break;
+                }
+            }
+            return true;
+        }
 
-        let shift = self.window.is_key_down(Key::LeftShift)
-                 || self.window.is_key_down(Key::RightShift);
-        let one_shot = |k: Key| self.window.is_key_pressed(k, KeyRepeat::No);
-        let held     = |k: Key| self.window.is_key_pressed(k, KeyRepeat::Yes);
+        let ctrl  = down(Key::LeftCtrl) || down(Key::RightCtrl);
+        let one_shot = |k: Key| windows.iter().any(|w| w.is_key_pressed(k, KeyRepeat::No));
+        let held     = |k: Key| windows.iter().any(|w| w.is_key_pressed(k, KeyRepeat::Yes));
+
+        for &(key, with_ctrl, action) in &self.keys {
+            if with_ctrl != ctrl { continue; }
+            let pressed = if action.repeats() { held(key) } else { one_shot(key) };
+            if !pressed { continue; }
+            let action = if shift { action.shifted() } else { action };
+            let _ = self.sim_tx.send(SimInput::KeyDown(action));
+            if action == SimKey::Quit { return false; }
+        }
 
-        if one_shot(Key::Q) {
-            let _ = self.sim_tx.send(SimInput::KeyDown(SimKey::Quit));
-            return false;
+        // Clicks in the tray's own window select entries.
+        if let Some(panel) = self.panel.as_mut() {
+            let down = panel.window.get_mouse_down(MouseButton::Left);
+            if down && !panel.mouse_down {
+                let at = panel.window.get_mouse_pos(MouseMode::Discard)
+                    .map(|(x, y)| ((x / self.scale) as usize, (y / self.scale) as usize));
+                if let Some(row) = at.and_then(|(x, y)| panel.lay.tray_row(x, y)) {
+                    let _ = self.sim_tx.send(SimInput::TrayClick(self.renderer.tray_first + row));
+                }
+            }
+            panel.mouse_down = down;
         }
-        if one_shot(Key::T) { let _ = self.sim_tx.send(SimInput::KeyDown(SimKey::Twist)); }
-        if one_shot(Key::Space)  { let _ = self.sim_tx.send(SimInput::KeyDown(SimKey::Clap)); }
-        if one_shot(Key::Escape) { let _ = self.sim_tx.send(SimInput::KeyDown(SimKey::Unclap)); }
-        if one_shot(Key::S)      { let _ = self.sim_tx.send(SimInput::KeyDown(SimKey::Scissors)); }
 
-        if held(Key::A) {
-            let k = if shift { SimKey::PullLeftFast } else { SimKey::PullLeft };
-            let _ = self.sim_tx.send(SimInput::KeyDown(k));
+        // A click on a tray entry selects it; on a flat ribbon patch it
+        // inspects the patch, and a drag across patches marks them.
+        let down = self.window.get_mouse_down(MouseButton::Left);
+        let at = self.window.get_mouse_pos(MouseMode::Clamp)
+            .map(|(x, y)| ((x / self.scale) as usize, (y / self.scale) as usize));
+        match (down, self.mouse_down, at) {
+            (true, false, Some((x, y))) => {
+                if let Some(entry) = self.renderer.tray_entry_at(x, y) {
+                    let _ = self.sim_tx.send(SimInput::TrayClick(entry));
+                } else if let Some(side) = self.renderer.flat_ribbon_at(y) {
+                    self.drag = self.renderer.flat_patch_at(side, x).map(|i| (side, i, i));
+                }
+            }
+            (true, true, Some((x, _))) => {
+                if let Some((side, _, latest)) = self.drag {
+                    let i = self.renderer.flat_patch_at(side, x.min(self.renderer.lay.ribbon_w - 1));
+                    self.drag = self.drag.map(|(s, first, _)| (s, first, i.unwrap_or(latest)));
+                }
+            }
+            (false, _, _) => if let Some((side, first, latest)) = self.drag.take() {
+                let input = if first == latest {
+                    SimInput::PatchClick { right: side == 1, index: first }
+                } else {
+                    SimInput::PatchDrag { from: first.min(latest), to: first.max(latest) + 1 }
+                };
+                let _ = self.sim_tx.send(input);
+            },
+            _ => {}
         }
-        if held(Key::D) {
-            let k = if shift { SimKey::PullRightFast } else { SimKey::PullRight };
-            let _ = self.sim_tx.send(SimInput::KeyDown(k));
+        self.mouse_down = down;
+
+        // The wheel over a ribbon pulls it, faster the faster it turns.
+        if let (Some((dx, dy)), Some((x, y))) = (self.window.get_scroll_wheel(), at) {
+            if let Some(side) = self.renderer.ribbon_at(x, y) {
+                let turned = dx.abs().max(dy.abs());
+                self.wheel[side] += turned;
+                let steps = self.wheel[side] as usize;
+                if steps > 0 {
+                    self.wheel[side] -= steps as f32;
+                    let velocity = (turned / WHEEL_FULL_SPEED).min(1.0);
+                    let _ = self.sim_tx.send(SimInput::WheelPull { right: side == 1, steps, velocity });
+                }
+            }
         }
         true
     }
 
     // ── master render dispatch ────────────────────────────────────────────
 
-    pub fn render(
-        &mut self,
-        left:           &RibbonState,
-        right:          &RibbonState,
-        stitch:         &StitchPhase,
-        tray:           &SnippetTray,
-        scissor:        &Option<ScissorAnimation>,
-        status:         &str,
-        playing:        bool,
-        note_highlight: Option<usize>,
-    ) {
+    pub fn render(&mut self, scene: &Scene) {
+        self.fit_window();
+        self.renderer.dragged = self.drag.map(|(_, a, b)| (a.min(b), a.max(b) + 1));
+        self.renderer.draw_ribbons(scene);
+
+        let r = &mut self.renderer;
+        let Some(mut panel) = self.panel.take() else {
+            r.draw_controls(scene);
+            present(&mut self.window, &r.buf, &r.lay, self.scale, &mut self.screen);
+            return;
+        };
+        present(&mut self.window, &r.buf, &r.lay, self.scale, &mut self.screen);
+
+        // Detached: draw the tray window with the same primitives, on its
+        // own buffer and layout.
+        std::mem::swap(&mut r.buf, &mut panel.buf);
+        std::mem::swap(&mut r.lay, &mut panel.lay);
+        r.buf.fill(TRAY_BG);
+        r.draw_tray(scene.tray, r.lay.ribbon_w);
+        r.draw_controls(scene);
+        present(&mut panel.window, &r.buf, &r.lay, self.scale, &mut self.screen);
+        std::mem::swap(&mut r.buf, &mut panel.buf);
+        std::mem::swap(&mut r.lay, &mut panel.lay);
+        self.panel = Some(panel);
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Renderer
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Draws the ribbons, tray, piano roll and status bar into a frame of
+/// 0xAARRGGBB pixels of its own, with no window attached: the
+/// [`Visualizer`] shows its frames in a `minifb` window, and the browser
+/// build copies them onto a canvas.
+pub struct Renderer {
+    /// Frame at the size of `lay`.
+    buf:           Vec<u32>,
+    /// Geometry for the current frame size.
+    lay:           Layout,
+    pub layout:    LayoutMode,
+    /// Layout to go back to when the spiral is toggled off.
+    unspiral:      LayoutMode,
+    /// Whether the digit histogram panel is shown.
+    histogram:     bool,
+    /// Last known gesture for hand ghost animation.
+    hand_gesture:  HandGesture,
+    /// Frame counter — drives subtle animations.
+    frame:         u64,
+    /// Downbeat flash on the stitch threads, 0.0–1.0.
+    beat_pulse:    f32,
+    /// Patch size multiplier (pinch zoom), 1.0 = default.
+    zoom:          f32,
+    /// Key legend shown under the status bar.
+    legend:        String,
+    /// First tray entry drawn last frame (the tray scrolls).
+    tray_first:    usize,
+    /// Scroll offset and patch count of the (left, right) flat ribbons as
+    /// last drawn, for finding the patch under the mouse.
+    flat_view:     [(f32, usize); 2],
+    /// Patches `first..last` marked for the next snip.
+    marked:        Option<(usize, usize)>,
+    /// Patches `first..last` being dragged across, outlined instead.
+    dragged:       Option<(usize, usize)>,
+    /// Name being typed into the on-screen prompt, if one is open.
+    text_entry:    Option<TextEntry>,
+}
+
+impl Renderer {
+    /// A renderer drawing `layout` on a `w`×`h` frame (at least 640×360).
+    pub fn new(layout: LayoutMode, w: usize, h: usize) -> Self {
+        let lay = Layout::new(w, h);
+        Renderer {
+            buf: vec![BG_COLOR; lay.w * lay.h],
+            lay,
+            layout,
+            unspiral: if layout == LayoutMode::Spiral { LayoutMode::Flat } else { layout },
+            histogram: false,
+            hand_gesture: HandGesture::Idle,
+            frame: 0,
+            beat_pulse: 0.0,
+            zoom: 1.0,
+            legend: String::new(),
+            tray_first: 0,
+            flat_view: [(0.0, 0); 2],
+            marked: None,
+            dragged: None,
+            text_entry: None,
+        }
+    }
+
+    /// Draw the next frames at `w`×`h` (at least 640×360).
+    pub fn resize(&mut self, w: usize, h: usize) {
+        self.set_layout(Layout::new(w, h));
+    }
+
+    fn set_layout(&mut self, lay: Layout) {
+        if lay != self.lay {
+            self.lay = lay;
+            self.buf.resize(lay.w * lay.h, BG_COLOR);
+        }
+    }
+
+    /// The last frame drawn: pixels, width, height.
+    pub fn frame(&self) -> (&[u32], usize, usize) {
+        (&self.buf, self.lay.w, self.lay.h)
+    }
+
+    /// Key legend for the foot of the frame (see [`key_legend`]).
+    pub fn set_legend(&mut self, legend: String) {
+        self.legend = legend;
+    }
+
+    /// Switch to the spiral view, or back to the layout before it.
+    pub fn toggle_spiral(&mut self) {
+        if self.layout == LayoutMode::Spiral {
+            self.layout = self.unspiral;
+        } else {
+            self.unspiral = self.layout;
+            self.layout = LayoutMode::Spiral;
+        }
+    }
+
+    /// Show or hide the digit histogram panel.
+    pub fn toggle_histogram(&mut self) {
+        self.histogram = !self.histogram;
+    }
+
+    /// Note the most recent gesture so hand ghosts can animate.
+    pub fn notify_gesture(&mut self, g: HandGesture) {
+        self.hand_gesture = g;
+    }
+
+    /// Brightness of the downbeat pulse for the next frame.
+    pub fn set_beat_pulse(&mut self, pulse: f32) {
+        self.beat_pulse = pulse;
+    }
+
+    /// Outline patches `first..last` of the pulled ribbons as marked for
+    /// the next snip.
+    pub fn set_marked(&mut self, marked: Option<(usize, usize)>) {
+        self.marked = marked;
+    }
+
+    /// Patch size multiplier for the next frame.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom;
+    }
+
+    /// Patches a ribbon needs to fill its space at the current frame size
+    /// and zoom.
+    pub fn ribbon_capacity(&self) -> usize {
+        self.lay.ribbon_capacity(self.layout, self.zoom)
+    }
+
+    /// The tray entry drawn at frame point `(x, y)`, if any.
+    pub fn tray_entry_at(&self, x: usize, y: usize) -> Option<usize> {
+        self.lay.tray_row(x, y).map(|row| self.tray_first + row)
+    }
+
+    /// The ribbon (0 left, 1 right) nearer frame point `(x, y)`, if it's
+    /// over the ribbons at all.
+    pub fn ribbon_at(&self, x: usize, y: usize) -> Option<usize> {
+        self.lay.ribbon_side_at(self.layout, x, y)
+    }
+
+    fn flat_patch_w(&self) -> usize { (FLAT_PATCH_W as f32 * self.zoom).round() as usize }
+    fn td_patch_h(&self)   -> usize { (TD_PATCH_H as f32 * self.zoom).round() as usize }
+    fn p3_depth(&self)     -> f32   { P3_PATCH_DEPTH * self.zoom }
+
+    /// Stitch thread color, brightened by the downbeat pulse.
+    fn stitch_color(&self) -> u32 {
+        blend(STITCH_COLOR, 0xFFFFFFFF, self.beat_pulse * 0.7)
+    }
+
+    /// The flat ribbon (0 left, 1 right) at frame row `y`, if any.
+    pub fn flat_ribbon_at(&self, y: usize) -> Option<usize> {
+        if self.layout != LayoutMode::Flat { return None; }
+        [self.lay.flat_left_y, self.lay.flat_right_y].iter()
+            .position(|&top| (top..top + FLAT_PATCH_H).contains(&y))
+    }
+
+    /// Index of the patch of flat ribbon `side` at frame column `x`, as
+    /// last drawn.
+    pub fn flat_patch_at(&self, side: usize, x: usize) -> Option<usize> {
+        if x >= self.lay.ribbon_w { return None; }
+        let (scroll, count) = self.flat_view[side];
+        let i = ((x as f32 + scroll) / self.flat_patch_w() as f32).max(0.0) as usize;
+        (i < count).then_some(i)
+    }
+
+    // ── master render dispatch ────────────────────────────────────────────
+
+    /// Draw a whole frame: the ribbons in the current layout, with the
+    /// tray, piano roll, histogram and status bar.
+    pub fn render(&mut self, scene: &Scene) {
+        self.draw_ribbons(scene);
+        self.draw_controls(scene);
+    }
+
+    /// The ribbons in the current layout, with the tray beside them unless
+    /// it's detached.
+    fn draw_ribbons(&mut self, scene: &Scene) {
+        let &Scene { left, right, stitch, tray, scissor, status, playing, note_highlight, stacked, .. } = scene;
         self.buf.fill(BG_COLOR);
         self.frame = self.frame.wrapping_add(1);
 
@@ -227,18 +1032,30 @@ impl Visualizer {
                                                   status, playing, note_highlight),
             LayoutMode::ThreeD => self.render_3d(left, right, stitch, tray, scissor,
                                                   status, playing, note_highlight),
+            LayoutMode::Spiral => self.render_spiral(left, right, stitch, tray, scissor, note_highlight),
         }
+        if self.layout == LayoutMode::Flat { self.draw_stacked_pairs(stacked); }
+    }
 
-        // Status bar and legend are common to all modes
-        let legend_y = WIN_H - 16;
-        self.fill_rect(0, WIN_H - 36, WIN_W, 36, TEXT_BG);
-        self.draw_label(status, 10, WIN_H - 30, 0xFFEEEEEE);
-        self.draw_label(
-            "A/D=pull  Shift+A/D=fast  T=twist  Space=clap  Esc=unclap  S=snip  Q=quit",
-            10, legend_y, 0xFF888888,
-        );
+    /// Piano roll, histogram, status bar and name prompt, in whichever
+    /// window holds the tray.
+    fn draw_controls(&mut self, scene: &Scene) {
+        let &Scene { left, right, roll, status, .. } = scene;
+        self.draw_piano_roll(roll);
+        if self.histogram { self.draw_histogram(left, right); }
 
-        self.window.update_with_buffer(&self.buf, WIN_W, WIN_H).ok();
+        // Status bar and legend are common to all modes
+        let legend_y = self.lay.h - 16;
+        self.fill_rect(0, self.lay.status_y, self.lay.w, 36, TEXT_BG);
+        self.draw_label(status, 10, self.lay.h - 30, 0xFFEEEEEE);
+        let legend = std::mem::take(&mut self.legend);
+        self.draw_label(&legend, 10, legend_y, 0xFF888888);
+        self.legend = legend;
+
+        if let Some(entry) = self.text_entry.take() {
+            self.draw_text_entry(&entry);
+            self.text_entry = Some(entry);
+        }
     }
 
     // ════════════════════════════════════════════════════════════════════════
@@ -254,15 +1071,17 @@ impl Visualizer {
         scissor:        &Option<ScissorAnimation>,
         _status:        &str,
         playing:        bool,
-        note_highlight: Option<usize>,
+        note_highlight: (Option<usize>, Option<usize>),
     ) {
-        self.fill_rect(FLAT_RIBBON_W, 0, TRAY_W, WIN_H, TRAY_BG);
+        self.fill_rect(self.lay.ribbon_w, 0, self.lay.tray_w, self.lay.h, TRAY_BG);
 
-        self.draw_ribbon_flat(left,  FLAT_LEFT_Y,  note_highlight);
-        self.draw_ribbon_flat(right, FLAT_RIGHT_Y, None);
+        self.draw_ribbon_flat(left,  self.lay.flat_left_y,  FLAT_PATCH_H, note_highlight.0);
+        self.draw_ribbon_flat(right, self.lay.flat_right_y, FLAT_PATCH_H, note_highlight.1);
+        self.flat_view = [(left.scroll_px, left.patches.len()), (right.scroll_px, right.patches.len())];
+        if let Some(range) = self.dragged.or(self.marked) { self.draw_flat_marks(range); }
 
-        self.draw_label(&left.label,  8, FLAT_LEFT_Y  - 22, 0xFFAADDFF);
-        self.draw_label(&right.label, 8, FLAT_RIGHT_Y - 22, 0xFFFFBBAA);
+        self.draw_label(&left.label,  8, self.lay.flat_left_y  - 22, 0xFFAADDFF);
+        self.draw_label(&right.label, 8, self.lay.flat_right_y - 22, 0xFFFFBBAA);
 
         if stitch.is_stitched() {
             let prog = stitch_progress(stitch);
@@ -270,53 +1089,88 @@ impl Visualizer {
         }
         if let Some(sc) = scissor { self.draw_flat_scissor(sc); }
         if playing {
-            self.draw_border(0, FLAT_LEFT_Y,  FLAT_RIBBON_W, FLAT_PATCH_H, STITCH_COLOR);
-            self.draw_border(0, FLAT_RIGHT_Y, FLAT_RIBBON_W, FLAT_PATCH_H, STITCH_COLOR);
+            self.draw_border(0, self.lay.flat_left_y,  self.lay.ribbon_w, FLAT_PATCH_H, STITCH_COLOR);
+            self.draw_border(0, self.lay.flat_right_y, self.lay.ribbon_w, FLAT_PATCH_H, STITCH_COLOR);
         }
-        self.draw_tray(tray, FLAT_RIBBON_W);
+        self.draw_tray(tray, self.lay.ribbon_w);
     }
 
-    fn draw_ribbon_flat(&mut self, ribbon: &RibbonState, y: usize, highlight: Option<usize>) {
+    fn draw_ribbon_flat(&mut self, ribbon: &RibbonState, y: usize, h: usize, highlight: Option<usize>) {
+        let pw = self.flat_patch_w();
         let scroll = ribbon.scroll_px as isize;
         for (i, patch) in ribbon.patches.iter().enumerate() {
-            let px = (i * FLAT_PATCH_W) as isize - scroll;
-            if px + FLAT_PATCH_W as isize <= 0 { continue; }
-            if px >= FLAT_RIBBON_W as isize     { break;    }
+            let px = (i * pw) as isize - scroll;
+            if px + pw as isize <= 0         { continue; }
+            if px >= self.lay.ribbon_w as isize { break;    }
             let x0 = px.max(0) as usize;
-            let x1 = (px + FLAT_PATCH_W as isize).min(FLAT_RIBBON_W as isize) as usize;
+            let x1 = (px + pw as isize).min(self.lay.ribbon_w as isize) as usize;
             let color = if highlight == Some(i) { blend(patch.color, 0xFFFFFFFF, 0.35) }
                         else { patch.color };
-            self.fill_rect(x0, y, x1 - x0, FLAT_PATCH_H, color);
-            let lx = x0 + (x1 - x0).saturating_sub(6) / 2;
-            self.draw_label(&format!("{}", patch.digit), lx, y + FLAT_PATCH_H/2 - 4, 0xFF000000);
-            self.draw_border(x0, y, x1 - x0, FLAT_PATCH_H, 0xFF000000);
+            self.fill_rect(x0, y, x1 - x0, h, color);
+            let lx = x0 + (x1 - x0).saturating_sub(GLYPH_W) / 2;
+            self.draw_label(&digit_char(patch.digit).to_string(), lx, y + (h - GLYPH_H) / 2, 0xFF000000);
+            self.draw_border(x0, y, x1 - x0, h, 0xFF000000);
+        }
+    }
+
+    /// The other ribbon pairs, small, below the right ribbon — as many as
+    /// fit above the status bar.
+    fn draw_stacked_pairs(&mut self, stacked: &[StackedPair]) {
+        let mut y = self.lay.flat_right_y + FLAT_PATCH_H + 16;
+        for pair in stacked {
+            if y + STACK_ROW_H > self.lay.status_y { break; }
+            let label = format!("{}  {} / {}", pair.label, pair.left.label, pair.right.label);
+            self.draw_label(&label, 8, y, 0xFF888888);
+            self.draw_ribbon_flat(pair.left,  y + 12, STACK_PATCH_H, pair.highlight.0);
+            self.draw_ribbon_flat(pair.right, y + 12 + STACK_PATCH_H, STACK_PATCH_H, pair.highlight.1);
+            y += STACK_ROW_H;
         }
     }
 
     fn draw_flat_stitch(&mut self, progress: f32) {
-        let y_top    = FLAT_LEFT_Y  + FLAT_PATCH_H;
-        let y_bottom = FLAT_RIGHT_Y;
+        let pw = self.flat_patch_w();
+        let y_top    = self.lay.flat_left_y  + FLAT_PATCH_H;
+        let y_bottom = self.lay.flat_right_y;
         let mid_y    = (y_top + y_bottom) / 2;
-        let visible  = FLAT_RIBBON_W / FLAT_PATCH_W;
+        let visible  = self.lay.ribbon_w / pw;
+        let color    = self.stitch_color();
         for i in 0..visible {
-            let cx = i * FLAT_PATCH_W + FLAT_PATCH_W / 2;
+            let cx = i * pw + pw / 2;
             let thread_bottom = y_top + ((y_bottom - y_top) as f32 * progress) as usize;
             for y in y_top..thread_bottom {
-                self.set_pixel(cx,     y, STITCH_COLOR);
-                self.set_pixel(cx + 1, y, STITCH_COLOR);
+                self.set_pixel(cx,     y, color);
+                self.set_pixel(cx + 1, y, color);
+            }
+            if progress > 0.9 { self.draw_diamond(cx, mid_y, 4, color); }
+        }
+    }
+
+    /// Outline patches `first..last` on both flat ribbons.
+    fn draw_flat_marks(&mut self, (first, last): (usize, usize)) {
+        let pw = self.flat_patch_w() as isize;
+        for (side, y) in [(0, self.lay.flat_left_y), (1, self.lay.flat_right_y)] {
+            let scroll = self.flat_view[side].0 as isize;
+            for i in first..last.min(self.flat_view[side].1) {
+                let px = i as isize * pw - scroll;
+                if px + pw <= 0 { continue; }
+                if px >= self.lay.ribbon_w as isize { break; }
+                let x0 = px.max(0) as usize;
+                let x1 = (px + pw).min(self.lay.ribbon_w as isize) as usize;
+                self.draw_border(x0, y, x1 - x0, FLAT_PATCH_H, HIGHLIGHT_COLOR);
+                self.draw_border(x0 + 1, y + 1, (x1 - x0).saturating_sub(2), FLAT_PATCH_H - 2, HIGHLIGHT_COLOR);
             }
-            if progress > 0.9 { self.draw_diamond(cx, mid_y, 4, STITCH_COLOR); }
         }
     }
 
     fn draw_flat_scissor(&mut self, sc: &ScissorAnimation) {
+        let pw = self.flat_patch_w();
         let end = sc.start_patch + (sc.count as f32 * sc.progress) as usize;
         for i in sc.start_patch..end {
-            let x0 = i * FLAT_PATCH_W;
-            if x0 >= FLAT_RIBBON_W { break; }
-            let w = FLAT_PATCH_W.min(FLAT_RIBBON_W - x0);
-            self.draw_border(x0, FLAT_LEFT_Y,  w, FLAT_PATCH_H, HIGHLIGHT_COLOR);
-            self.draw_border(x0, FLAT_RIGHT_Y, w, FLAT_PATCH_H, HIGHLIGHT_COLOR);
+            let x0 = i * pw;
+            if x0 >= self.lay.ribbon_w { break; }
+            let w = pw.min(self.lay.ribbon_w - x0);
+            self.draw_border(x0, self.lay.flat_left_y,  w, FLAT_PATCH_H, HIGHLIGHT_COLOR);
+            self.draw_border(x0, self.lay.flat_right_y, w, FLAT_PATCH_H, HIGHLIGHT_COLOR);
         }
     }
 
@@ -333,32 +1187,34 @@ impl Visualizer {
         scissor:        &Option<ScissorAnimation>,
         _status:        &str,
         playing:        bool,
-        note_highlight: Option<usize>,
+        note_highlight: (Option<usize>, Option<usize>),
     ) {
+        let patch_h = self.td_patch_h();
         // Tray on the right
-        let content_w = WIN_W - TRAY_W;
-        self.fill_rect(content_w, 0, TRAY_W, WIN_H, TRAY_BG);
+        let content_w = self.lay.w - self.lay.tray_w;
+        self.fill_rect(content_w, 0, self.lay.tray_w, self.lay.h, TRAY_BG);
 
-        self.draw_ribbon_2d(left,  TD_LEFT_X,  note_highlight, 0xFFAADDFF);
-        self.draw_ribbon_2d(right, TD_RIGHT_X, None,           0xFFFFBBAA);
+        self.draw_ribbon_2d(left,  self.lay.td_left_x,  note_highlight.0, 0xFFAADDFF);
+        self.draw_ribbon_2d(right, self.lay.td_right_x, note_highlight.1, 0xFFFFBBAA);
 
         // Labels at top of columns
-        self.draw_label(&left.label,  TD_LEFT_X,  30, 0xFFAADDFF);
-        self.draw_label(&right.label, TD_RIGHT_X, 30, 0xFFFFBBAA);
+        self.draw_label(&left.label,  self.lay.td_left_x,  30, 0xFFAADDFF);
+        self.draw_label(&right.label, self.lay.td_right_x, 30, 0xFFFFBBAA);
 
         // Stitch threads: horizontal lines connecting the two columns
         if stitch.is_stitched() {
             let prog   = stitch_progress(stitch);
-            let mid_x1 = TD_LEFT_X  + TD_RIBBON_W;
-            let mid_x2 = TD_RIGHT_X;
-            let visible  = (TD_BOTTOM_Y - 60) / TD_PATCH_H;
+            let mid_x1 = self.lay.td_left_x  + TD_RIBBON_W;
+            let mid_x2 = self.lay.td_right_x;
+            let visible  = (self.lay.td_bottom_y - 60) / patch_h;
+            let color    = self.stitch_color();
             for i in 0..((visible as f32 * prog) as usize) {
-                let patch_y = TD_BOTTOM_Y.saturating_sub(i * TD_PATCH_H + TD_PATCH_H / 2);
+                let patch_y = self.lay.td_bottom_y.saturating_sub(i * patch_h + patch_h / 2);
                 for x in mid_x1..mid_x2 {
-                    self.set_pixel(x, patch_y, STITCH_COLOR);
-                    self.set_pixel(x, patch_y + 1, STITCH_COLOR);
+                    self.set_pixel(x, patch_y, color);
+                    self.set_pixel(x, patch_y + 1, color);
                 }
-                self.draw_diamond((mid_x1 + mid_x2) / 2, patch_y, 4, STITCH_COLOR);
+                self.draw_diamond((mid_x1 + mid_x2) / 2, patch_y, 4, color);
             }
         }
 
@@ -366,16 +1222,16 @@ impl Visualizer {
         if let Some(sc) = scissor {
             let end = sc.start_patch + (sc.count as f32 * sc.progress) as usize;
             for i in sc.start_patch..end {
-                let py = TD_BOTTOM_Y.saturating_sub(i * TD_PATCH_H);
-                self.draw_border(TD_LEFT_X,  py, TD_RIBBON_W, TD_PATCH_H, HIGHLIGHT_COLOR);
-                self.draw_border(TD_RIGHT_X, py, TD_RIBBON_W, TD_PATCH_H, HIGHLIGHT_COLOR);
+                let py = self.lay.td_bottom_y.saturating_sub(i * patch_h);
+                self.draw_border(self.lay.td_left_x,  py, TD_RIBBON_W, patch_h, HIGHLIGHT_COLOR);
+                self.draw_border(self.lay.td_right_x, py, TD_RIBBON_W, patch_h, HIGHLIGHT_COLOR);
             }
         }
 
         // Playing pulse
         if playing {
-            self.draw_border(TD_LEFT_X,  0, TD_RIBBON_W, WIN_H, STITCH_COLOR);
-            self.draw_border(TD_RIGHT_X, 0, TD_RIBBON_W, WIN_H, STITCH_COLOR);
+            self.draw_border(self.lay.td_left_x,  0, TD_RIBBON_W, self.lay.h, STITCH_COLOR);
+            self.draw_border(self.lay.td_right_x, 0, TD_RIBBON_W, self.lay.h, STITCH_COLOR);
         }
 
         self.draw_tray(tray, content_w);
@@ -388,20 +1244,22 @@ impl Visualizer {
         highlight: Option<usize>,
         label_color: u32,
     ) {
+        let patch_h = self.td_patch_h();
         let scroll = ribbon.scroll_px as isize;
         for (i, patch) in ribbon.patches.iter().enumerate() {
             // Patches stack upward from bottom; newest = bottommost
-            let raw_py = TD_BOTTOM_Y as isize - (i as isize + 1) * TD_PATCH_H as isize
+            let raw_py = self.lay.td_bottom_y as isize - (i as isize + 1) * patch_h as isize
                        + scroll;
-            if raw_py + TD_PATCH_H as isize <= 0 { break; }
-            if raw_py >= WIN_H as isize           { continue; }
+            if raw_py + patch_h as isize <= 0 { break; }
+            if raw_py >= self.lay.h as isize      { continue; }
             let py = raw_py.max(0) as usize;
-            let ph = TD_PATCH_H.min(WIN_H - py);
+            let ph = patch_h.min(self.lay.h - py);
 
             let color = if highlight == Some(i) { blend(patch.color, 0xFFFFFFFF, 0.35) }
                         else { patch.color };
             self.fill_rect(x, py, TD_RIBBON_W, ph, color);
-            self.draw_label(&format!("{}", patch.digit), x + 4, py + ph/2 - 2, 0xFF000000);
+            let ly = (py + ph / 2).saturating_sub(GLYPH_H / 2);
+            self.draw_label(&digit_char(patch.digit).to_string(), x + (TD_RIBBON_W - GLYPH_W) / 2, ly, 0xFF000000);
             self.draw_border(x, py, TD_RIBBON_W, ph, 0xFF000000);
         }
     }
@@ -419,17 +1277,17 @@ impl Visualizer {
         scissor:        &Option<ScissorAnimation>,
         _status:        &str,
         playing:        bool,
-        note_highlight: Option<usize>,
+        note_highlight: (Option<usize>, Option<usize>),
     ) {
         // Sky gradient — deep space feel
-        self.draw_vertical_gradient(0, WIN_H, 0xFF0A0A1A, 0xFF1A1A3E);
+        self.draw_vertical_gradient(0, self.lay.h, 0xFF0A0A1A, 0xFF1A1A3E);
 
         // Vanishing-point grid lines
         self.draw_vp_grid();
 
         // Ribbons receding into the screen
-        self.draw_ribbon_3d(left,  P3_LEFT_WORLD_Y,  note_highlight, 0xFFAADDFF);
-        self.draw_ribbon_3d(right, P3_RIGHT_WORLD_Y, None,           0xFFFFBBAA);
+        self.draw_ribbon_3d(left,  P3_LEFT_WORLD_Y,  note_highlight.0, 0xFFAADDFF);
+        self.draw_ribbon_3d(right, P3_RIGHT_WORLD_Y, note_highlight.1, 0xFFFFBBAA);
 
         // Stitch threads — arcs/lines in 3D connecting the two ribbons
         if stitch.is_stitched() {
@@ -446,21 +1304,21 @@ impl Visualizer {
         if playing {
             let glow = pulse_alpha(self.frame, 0.4, 0.9);
             let c    = blend(BG_COLOR, STITCH_COLOR, glow);
-            self.draw_border(2, 2, WIN_W - 4, WIN_H - 40, c);
-            self.draw_border(4, 4, WIN_W - 8, WIN_H - 44, c);
+            self.draw_border(2, 2, self.lay.w - 4, self.lay.h - 40, c);
+            self.draw_border(4, 4, self.lay.w - 8, self.lay.h - 44, c);
         }
 
         // Hand ghosts — always drawn in 3D mode
         self.draw_hand_ghosts();
 
         // Tray (right side, semi-transparent feel)
-        let content_w = WIN_W - TRAY_W;
-        self.fill_rect(content_w, 0, TRAY_W, WIN_H - 36, TRAY_BG);
+        let content_w = self.lay.w - self.lay.tray_w;
+        self.fill_rect(content_w, 0, self.lay.tray_w, self.lay.status_y, TRAY_BG);
         self.draw_tray(tray, content_w);
 
         // Labels near the near edge of each ribbon
-        let (lsx, lsy) = project_3d(P3_PATCH_HALF_W * 2.0, P3_LEFT_WORLD_Y, P3_NEAR_Z);
-        let (rsx, rsy) = project_3d(P3_PATCH_HALF_W * 2.0, P3_RIGHT_WORLD_Y, P3_NEAR_Z);
+        let (lsx, lsy) = self.lay.project(P3_PATCH_HALF_W * 2.0, P3_LEFT_WORLD_Y, P3_NEAR_Z);
+        let (rsx, rsy) = self.lay.project(P3_PATCH_HALF_W * 2.0, P3_RIGHT_WORLD_Y, P3_NEAR_Z);
         self.draw_label(&left.label,  lsx as usize, lsy as usize + 8, 0xFFAADDFF);
         self.draw_label(&right.label, rsx as usize, rsy as usize + 8, 0xFFFFBBAA);
     }
@@ -472,29 +1330,30 @@ impl Visualizer {
         highlight: Option<usize>,
         _tint:     u32,
     ) {
+        let depth = self.p3_depth();
         // Patches are laid out along the Z axis; patch 0 = nearest (z=NEAR_Z),
-        // each subsequent patch is P3_PATCH_DEPTH units farther away.
+        // each subsequent patch is P3_PATCH_DEPTH (× zoom) units farther away.
         let n = ribbon.patches.len();
         if n == 0 { return; }
 
         for (i, patch) in ribbon.patches.iter().enumerate() {
-            let z = P3_NEAR_Z + i as f32 * P3_PATCH_DEPTH;
+            let z = P3_NEAR_Z + i as f32 * depth;
             if z > P3_FAR_Z { break; }
 
             // Perspective project the four corners of this patch
-            let z_back = z + P3_PATCH_DEPTH * 0.98;
+            let z_back = z + depth * 0.98;
             let hw     = P3_PATCH_HALF_W;
             let hy     = 0.45_f32;
 
-            let (x0s, y0s) = project_3d(-hw, world_y - hy, z);
-            let (x1s, y1s) = project_3d( hw, world_y - hy, z);
-            let (x2s, y2s) = project_3d( hw, world_y + hy, z);
-            let (x3s, y3s) = project_3d(-hw, world_y + hy, z);
+            let (x0s, y0s) = self.lay.project(-hw, world_y - hy, z);
+            let (x1s, y1s) = self.lay.project( hw, world_y - hy, z);
+            let (x2s, y2s) = self.lay.project( hw, world_y + hy, z);
+            let (x3s, y3s) = self.lay.project(-hw, world_y + hy, z);
 
-            let (x0b, y0b) = project_3d(-hw, world_y - hy, z_back);
-            let (x1b, y1b) = project_3d( hw, world_y - hy, z_back);
-            let (x2b, y2b) = project_3d( hw, world_y + hy, z_back);
-            let (x3b, y3b) = project_3d(-hw, world_y + hy, z_back);
+            let (x0b, y0b) = self.lay.project(-hw, world_y - hy, z_back);
+            let (x1b, y1b) = self.lay.project( hw, world_y - hy, z_back);
+            let (x2b, y2b) = self.lay.project( hw, world_y + hy, z_back);
+            let (x3b, y3b) = self.lay.project(-hw, world_y + hy, z_back);
 
             // Depth-fade: distant patches fade toward background color
             let t_fade = (z / P3_FAR_Z).min(1.0);
@@ -528,8 +1387,9 @@ impl Visualizer {
             // Digit label at centre of front face
             let cx = ((x0s + x1s) / 2.0) as usize;
             let cy = ((y0s + y3s) / 2.0) as usize;
-            if cx + 4 < WIN_W && cy + 4 < WIN_H {
-                self.draw_label(&format!("{}", patch.digit), cx.saturating_sub(2), cy, 0xFF000000);
+            if cx + GLYPH_W < self.lay.w && cy + GLYPH_H < self.lay.h {
+                self.draw_label(&digit_char(patch.digit).to_string(),
+                                cx.saturating_sub(GLYPH_W / 2), cy.saturating_sub(GLYPH_H / 2), 0xFF000000);
             }
         }
     }
@@ -538,21 +1398,23 @@ impl Visualizer {
         // Subtle converging grid lines toward vanishing point
         for i in 0..8 {
             let t   = i as f32 / 7.0;
-            let x0  = (WIN_W as f32 * t) as usize;
+            let x0  = (self.lay.w as f32 * t) as usize;
             let col = blend(0xFF0D0D20, 0xFF1F1F40, t);
-            self.draw_line(x0 as f32, WIN_H as f32 - 36.0, P3_VPX, P3_VPY, col);
+            self.draw_line(x0 as f32, self.lay.h as f32 - 36.0, self.lay.vpx, self.lay.vpy, col);
         }
     }
 
     fn draw_3d_stitch(&mut self, progress: f32) {
-        let patches = (P3_FAR_Z / P3_PATCH_DEPTH) as usize;
+        let depth = self.p3_depth();
+        let patches = (P3_FAR_Z / depth) as usize;
         let visible  = (patches as f32 * progress) as usize;
+        let color    = self.stitch_color();
         for i in 0..visible {
-            let z    = P3_NEAR_Z + i as f32 * P3_PATCH_DEPTH;
-            let (lx, ly) = project_3d(0.0, P3_LEFT_WORLD_Y, z);
-            let (rx, ry) = project_3d(0.0, P3_RIGHT_WORLD_Y, z);
+            let z    = P3_NEAR_Z + i as f32 * depth;
+            let (lx, ly) = self.lay.project(0.0, P3_LEFT_WORLD_Y, z);
+            let (rx, ry) = self.lay.project(0.0, P3_RIGHT_WORLD_Y, z);
             let t_fade   = (z / P3_FAR_Z).min(1.0);
-            let c = blend(STITCH_COLOR, BG_COLOR, t_fade * 0.85);
+            let c = blend(color, BG_COLOR, t_fade * 0.85);
             self.draw_line(lx, ly, rx, ry, c);
             if i % 4 == 0 {
                 let mx = ((lx + rx) / 2.0) as usize;
@@ -563,20 +1425,101 @@ impl Visualizer {
     }
 
     fn draw_3d_scissor(&mut self, sc: &ScissorAnimation) {
+        let depth = self.p3_depth();
         let end = sc.start_patch + (sc.count as f32 * sc.progress) as usize;
         for i in sc.start_patch..end {
-            let z = P3_NEAR_Z + i as f32 * P3_PATCH_DEPTH;
+            let z = P3_NEAR_Z + i as f32 * depth;
             let hw = P3_PATCH_HALF_W;
             let hy = 0.45;
-            let (x0, y0) = project_3d(-hw, P3_LEFT_WORLD_Y - hy, z);
-            let (x1, y1) = project_3d( hw, P3_LEFT_WORLD_Y + hy, z);
-            let (x2, y2) = project_3d(-hw, P3_RIGHT_WORLD_Y - hy, z);
-            let (x3, y3) = project_3d( hw, P3_RIGHT_WORLD_Y + hy, z);
+            let (x0, y0) = self.lay.project(-hw, P3_LEFT_WORLD_Y - hy, z);
+            let (x1, y1) = self.lay.project( hw, P3_LEFT_WORLD_Y + hy, z);
+            let (x2, y2) = self.lay.project(-hw, P3_RIGHT_WORLD_Y - hy, z);
+            let (x3, y3) = self.lay.project( hw, P3_RIGHT_WORLD_Y + hy, z);
             self.draw_line(x0, y0, x1, y1, HIGHLIGHT_COLOR);
             self.draw_line(x2, y2, x3, y3, HIGHLIGHT_COLOR);
         }
     }
 
+    // ════════════════════════════════════════════════════════════════════════
+    // Spiral layout renderer  — a double spiral, newest digits outermost
+    // ════════════════════════════════════════════════════════════════════════
+
+    fn render_spiral(
+        &mut self,
+        left:           &RibbonState,
+        right:          &RibbonState,
+        stitch:         &StitchPhase,
+        tray:           &SnippetTray,
+        scissor:        &Option<ScissorAnimation>,
+        note_highlight: (Option<usize>, Option<usize>),
+    ) {
+        self.fill_rect(self.lay.ribbon_w, 0, self.lay.tray_w, self.lay.h, TRAY_BG);
+
+        let (cx, cy, r_max) = self.lay.spiral_frame();
+        let spacing = SPIRAL_SPACING * self.zoom;
+        let spin    = self.frame as f32 * SPIRAL_SPIN;
+        let to_xy   = move |&(a, r): &(f32, f32)| (cx + r * a.cos(), cy + r * a.sin());
+        let left_xy:  Vec<_> = spiral_arm(r_max, spacing, spin).iter().map(to_xy).collect();
+        let right_xy: Vec<_> = spiral_arm(r_max, spacing, spin + std::f32::consts::PI)
+            .iter().map(to_xy).collect();
+        // Patch i of a ribbon sits at place `len - 1 - i`, so the newest
+        // digit is outermost.
+        let place = |xy: &[(f32, f32)], ribbon: &RibbonState, i: usize| {
+            xy.get(ribbon.patches.len().checked_sub(i + 1)?).copied()
+        };
+
+        // Stitch threads join the two arms patch by patch
+        if stitch.is_stitched() {
+            let n = (left.patches.len().min(right.patches.len()) as f32 * stitch_progress(stitch)) as usize;
+            let color = self.stitch_color();
+            for k in 0..n {
+                let (Some(a), Some(b)) = (left_xy.get(k), right_xy.get(k)) else { break };
+                self.draw_line(a.0, a.1, b.0, b.1, color);
+            }
+        }
+
+        self.draw_ribbon_spiral(left,  &left_xy,  spacing, note_highlight.0);
+        self.draw_ribbon_spiral(right, &right_xy, spacing, note_highlight.1);
+
+        if let Some(sc) = scissor {
+            let end = sc.start_patch + (sc.count as f32 * sc.progress) as usize;
+            let r = (spacing * 0.5) as usize + 1;
+            for i in sc.start_patch..end {
+                for (xy, ribbon) in [(&left_xy, left), (&right_xy, right)] {
+                    if let Some((x, y)) = place(xy, ribbon, i) {
+                        self.draw_circle(x as usize, y as usize, r, HIGHLIGHT_COLOR);
+                    }
+                }
+            }
+        }
+
+        self.draw_label(&left.label,  10, 10, 0xFFAADDFF);
+        self.draw_label(&right.label, 10, 22, 0xFFFFBBAA);
+        self.draw_tray(tray, self.lay.ribbon_w);
+    }
+
+    fn draw_ribbon_spiral(
+        &mut self,
+        ribbon:    &RibbonState,
+        places:    &[(f32, f32)],
+        spacing:   f32,
+        highlight: Option<usize>,
+    ) {
+        let r = (spacing * 0.42).max(1.0) as usize;
+        let n = ribbon.patches.len();
+        for (i, patch) in ribbon.patches.iter().enumerate() {
+            let Some(&(x, y)) = places.get(n - 1 - i) else { continue };
+            let (x, y) = (x as usize, y as usize);
+            let color = if highlight == Some(i) { blend(patch.color, 0xFFFFFFFF, 0.35) }
+                        else { patch.color };
+            self.fill_disc(x, y, r, color);
+            if r * 2 > GLYPH_W + 2 {
+                self.draw_label(&digit_char(patch.digit).to_string(),
+                                x.saturating_sub(GLYPH_W / 2), y.saturating_sub(GLYPH_H / 2), 0xFF000000);
+            }
+        }
+    }
+
     // ════════════════════════════════════════════════════════════════════════
     // Hand ghost renderer  (3D mode only)
     // ════════════════════════════════════════════════════════════════════════
@@ -593,11 +1536,11 @@ impl Visualizer {
         let gesture = self.hand_gesture;
 
         // Left hand: lower-left area
-        let left_cx  = (WIN_W as f32 * 0.20) as isize;
-        let left_cy  = (WIN_H as f32 * 0.72) as isize;
+        let left_cx  = (self.lay.w as f32 * 0.20) as isize;
+        let left_cy  = (self.lay.h as f32 * 0.72) as isize;
         // Right hand: lower-right area
-        let right_cx = (WIN_W as f32 * 0.65) as isize;
-        let right_cy = (WIN_H as f32 * 0.72) as isize;
+        let right_cx = (self.lay.w as f32 * 0.65) as isize;
+        let right_cy = (self.lay.h as f32 * 0.72) as isize;
 
         let (left_pose, right_pose) = gesture_poses(gesture, frame);
 
@@ -625,8 +1568,8 @@ impl Visualizer {
             let sx2 = cx + (bx * scale * mir) as isize;
             let sy2 = cy + (by * scale) as isize;
             if sx1 >= 0 && sy1 >= 0 && sx2 >= 0 && sy2 >= 0
-               && (sx1 as usize) < WIN_W && (sy1 as usize) < WIN_H
-               && (sx2 as usize) < WIN_W && (sy2 as usize) < WIN_H {
+               && (sx1 as usize) < self.lay.w && (sy1 as usize) < self.lay.h
+               && (sx2 as usize) < self.lay.w && (sy2 as usize) < self.lay.h {
                 self.draw_line(sx1 as f32, sy1 as f32, sx2 as f32, sy2 as f32, color);
             }
         }
@@ -640,53 +1583,164 @@ impl Visualizer {
     // ════════════════════════════════════════════════════════════════════════
 
     fn draw_tray(&mut self, tray: &SnippetTray, x_origin: usize) {
-        self.draw_label("SNIPPETS", x_origin + 8, 10, STITCH_COLOR);
-        let mut ey = 32usize;
-        for entry in &tray.entries {
+        if self.lay.tray_w == 0 { return; }   // in a window of its own
+        let first = tray.first_visible(self.lay.tray_rows);
+        self.tray_first = first;
+        let title = if tray.entries.len() > self.lay.tray_rows {
+            format!("SNIPPETS {}-{}/{}", first + 1,
+                    (first + self.lay.tray_rows).min(tray.entries.len()), tray.entries.len())
+        } else {
+            "SNIPPETS".to_string()
+        };
+        self.draw_label(&title, x_origin + 8, 10, STITCH_COLOR);
+        let mut ey = TRAY_TOP;
+        for (i, entry) in tray.entries.iter().enumerate().skip(first) {
             let slide  = entry.slide_in;
-            let ex     = x_origin + (TRAY_W as f32 * (1.0 - slide)) as usize;
-            if ex < WIN_W {
-                self.fill_rect(ex, ey, WIN_W - ex, 50, TEXT_BG);
+            let ex     = x_origin + (self.lay.tray_w as f32 * (1.0 - slide)) as usize;
+            if ex < self.lay.w {
+                self.fill_rect(ex, ey, self.lay.w - ex, 50, TEXT_BG);
+                if tray.selected == Some(i) {
+                    self.draw_border(ex, ey, self.lay.w - ex, 50, STITCH_COLOR);
+                }
                 self.draw_label(&entry.name, ex + 4, ey + 4, STITCH_COLOR);
                 let max_p = 8;
-                let pw    = (TRAY_W - 16) / max_p;
+                let pw    = (self.lay.tray_w - 16) / max_p;
                 for (j, (lp, rp)) in entry.patches.iter().take(max_p).enumerate() {
                     let px = ex + 4 + j * pw;
                     self.fill_rect(px, ey + 16, pw.saturating_sub(2), 13, lp.color);
                     self.fill_rect(px, ey + 31, pw.saturating_sub(2), 13, rp.color);
                 }
             }
-            ey += 56;
-            if ey + 56 > WIN_H - 36 { break; }
+            ey += TRAY_ROW_H;
+            if ey + TRAY_ROW_H > self.lay.roll_y { break; }
         }
     }
 
+    // ════════════════════════════════════════════════════════════════════════
+    // Digit histogram (shared across all modes)
+    // ════════════════════════════════════════════════════════════════════════
+
+    /// Per-digit frequencies of both streams, one above the other, in a
+    /// panel just left of the tray.
+    fn draw_histogram(&mut self, left: &RibbonState, right: &RibbonState) {
+        let w  = self.lay.tray_w;
+        let x0 = self.lay.ribbon_w - w;
+        let h  = (self.lay.status_y - 16) / 2;
+        self.draw_stream_histogram(left,  x0, 8,     w, h, 0xFFAADDFF);
+        self.draw_stream_histogram(right, x0, 8 + h, w, h, 0xFFFFBBAA);
+    }
+
+    /// One stream's bars, scaled so the share every digit would have in a
+    /// normal number (dotted) sits halfway up.
+    fn draw_stream_histogram(&mut self, ribbon: &RibbonState, x0: usize, y0: usize,
+                             w: usize, h: usize, label_color: u32) {
+        let stats = &ribbon.stats;
+        self.fill_rect(x0, y0, w, h, TRAY_BG);
+        self.draw_border(x0, y0, w, h, 0xFF2A3448);
+        self.draw_label(&ribbon.label, x0 + 6, y0 + 6, label_color);
+        self.draw_label(&format!("n={}  chi2={:.1}", stats.total(), stats.chi_squared()),
+                        x0 + 6, y0 + 18, 0xFFAAAAAA);
+
+        let base   = stats.base() as usize;
+        let bar_w  = ((w - 12) / base).max(1);
+        let top    = y0 + 32;
+        let bottom = y0 + h - 14;
+        let full   = (bottom - top) as f64;
+        for d in 0..base {
+            let share = stats.frequency(d as u8) / (2.0 * stats.expected());
+            let bar_h = (share.min(1.0) * full) as usize;
+            let x     = x0 + 6 + d * bar_w;
+            self.fill_rect(x, bottom - bar_h, bar_w.saturating_sub(1).max(1), bar_h,
+                           digit_color(d as u8, stats.base()));
+            if bar_w >= GLYPH_W {
+                self.draw_label(&digit_char(d as u8).to_string(),
+                                x + (bar_w - GLYPH_W) / 2, bottom + 3, 0xFFAAAAAA);
+            }
+        }
+        let y = bottom - (full / 2.0) as usize;
+        for x in (x0 + 6..x0 + 6 + base * bar_w).step_by(4) {
+            self.fill_rect(x, y, 2, 1, 0xFFFFFFFF);
+        }
+    }
+
+    // ════════════════════════════════════════════════════════════════════════
+    // Piano roll (shared across all modes)
+    // ════════════════════════════════════════════════════════════════════════
+
+    /// Recently played notes at the foot of the tray column: pitch up, the
+    /// newest at the right edge, colored by velocity.
+    fn draw_piano_roll(&mut self, roll: &PianoRoll) {
+        let (x0, y0) = (self.lay.ribbon_w, self.lay.roll_y);
+        let (w, h)   = (self.lay.tray_w, self.lay.roll_h);
+        self.fill_rect(x0, y0, w, h, ROLL_BG);
+        self.draw_label("NOTES", x0 + 8, y0 + 4, STITCH_COLOR);
+
+        // Rows below the title, one per semitone, with a line at each C
+        let (lo, hi) = roll.pitch_range();
+        let top  = y0 + 16;
+        let row  = ((y0 + h - 4 - top) / (hi - lo + 1) as usize).max(1);
+        let base = top + row * (hi - lo) as usize;
+        let bottom = y0 + h;
+        for p in (lo..=hi).filter(|p| p % 12 == 0) {
+            let y = base - row * (p - lo) as usize + row / 2;
+            if y < bottom { self.fill_rect(x0, y, w, 1, 0xFF2A3448); }
+        }
+
+        let px_per_frame = w as f32 / ROLL_SPAN as f32;
+        let right = (x0 + w) as f32;
+        for note in &roll.notes {
+            let start = right - note.age as f32 * px_per_frame;
+            let end   = (start + note.length as f32 * px_per_frame).min(right);
+            let start = start.max(x0 as f32);
+            let y     = base - row * (note.pitch - lo) as usize;
+            if end <= start || y >= bottom { continue; }
+            let color = blend(ROLL_QUIET, ROLL_LOUD, note.velocity as f32 / 127.0);
+            let color = blend(color, 0xFFFFFFFF, if note.age < 6 { 0.5 } else { 0.0 });
+            let (nx, nw) = (start as usize, ((end - start) as usize).max(2));
+            self.fill_rect(nx, y, nw, row.max(2), color);
+            if note.spliced { self.draw_border(nx, y, nw, row.max(2), STITCH_COLOR); }
+        }
+        self.draw_border(x0, y0, w, h, 0xFF2A3448);
+    }
+
     // ════════════════════════════════════════════════════════════════════════
     // Primitive drawing
     // ════════════════════════════════════════════════════════════════════════
 
+    /// The name prompt, centred over the ribbons.
+    fn draw_text_entry(&mut self, entry: &TextEntry) {
+        let (w, h) = (300, 44);
+        let (x, y) = ((self.lay.w - w) / 2, (self.lay.h - h) / 2);
+        self.fill_rect(x, y, w, h, TEXT_BG);
+        self.draw_border(x, y, w, h, STITCH_COLOR);
+        self.draw_label(&entry.prompt, x + 10, y + 8, 0xFFAAAAAA);
+        let cursor = if (self.frame / 30).is_multiple_of(2) { "_" } else { "" };
+        self.draw_label(&format!("{}{}", entry.text, cursor), x + 10, y + 20, 0xFFEEEEEE);
+        self.draw_label("Enter=ok  Esc=cancel", x + 10, y + 32, 0xFF888888);
+    }
+
     fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize, color: u32) {
-        for row in y..(y+h).min(WIN_H) {
-            for col in x..(x+w).min(WIN_W) {
-                self.buf[row * WIN_W + col] = color;
+        for row in y..(y+h).min(self.lay.h) {
+            for col in x..(x+w).min(self.lay.w) {
+                self.buf[row * self.lay.w + col] = color;
             }
         }
     }
 
     fn draw_border(&mut self, x: usize, y: usize, w: usize, h: usize, color: u32) {
-        for col in x..(x+w).min(WIN_W) {
-            if y < WIN_H         { self.buf[y           * WIN_W + col] = color; }
-            if y+h > 0 && y+h-1 < WIN_H { self.buf[(y+h-1) * WIN_W + col] = color; }
+        for col in x..(x+w).min(self.lay.w) {
+            if y < self.lay.h         { self.buf[y           * self.lay.w + col] = color; }
+            if y+h > 0 && y+h-1 < self.lay.h { self.buf[(y+h-1) * self.lay.w + col] = color; }
         }
-        for row in y..(y+h).min(WIN_H) {
-            if x < WIN_W         { self.buf[row * WIN_W + x    ] = color; }
-            if x+w > 0 && x+w-1 < WIN_W { self.buf[row * WIN_W + x+w-1] = color; }
+        for row in y..(y+h).min(self.lay.h) {
+            if x < self.lay.w         { self.buf[row * self.lay.w + x    ] = color; }
+            if x+w > 0 && x+w-1 < self.lay.w { self.buf[row * self.lay.w + x+w-1] = color; }
         }
     }
 
     fn set_pixel(&mut self, x: usize, y: usize, color: u32) {
-        if x < WIN_W && y < WIN_H {
-            self.buf[y * WIN_W + x] = color;
+        if x < self.lay.w && y < self.lay.h {
+            self.buf[y * self.lay.w + x] = color;
         }
     }
 
@@ -699,8 +1753,8 @@ impl Visualizer {
         let sy = if y0 < y1 { 1isize } else { -1 };
         let mut err = dx + dy;
         loop {
-            if x0 >= 0 && y0 >= 0 && (x0 as usize) < WIN_W && (y0 as usize) < WIN_H {
-                self.buf[y0 as usize * WIN_W + x0 as usize] = color;
+            if x0 >= 0 && y0 >= 0 && (x0 as usize) < self.lay.w && (y0 as usize) < self.lay.h {
+                self.buf[y0 as usize * self.lay.w + x0 as usize] = color;
             }
             if x0 == x1 && y0 == y1 { break; }
             let e2 = 2 * err;
@@ -725,6 +1779,17 @@ impl Visualizer {
         }
     }
 
+    fn fill_disc(&mut self, cx: usize, cy: usize, r: usize, color: u32) {
+        let r2 = (r * r) as isize;
+        for dy in -(r as isize)..=r as isize {
+            let half = ((r2 - dy * dy) as f32).sqrt() as isize;
+            let y = cy as isize + dy;
+            if y < 0 { continue; }
+            let x0 = (cx as isize - half).max(0) as usize;
+            self.fill_rect(x0, y as usize, (cx as isize + half + 1) as usize - x0, 1, color);
+        }
+    }
+
     fn draw_diamond(&mut self, cx: usize, cy: usize, r: usize, color: u32) {
         for dy in 0..=r as isize {
             let dx = r as isize - dy;
@@ -751,9 +1816,9 @@ impl Visualizer {
     /// Barycentric triangle fill.
     fn fill_triangle(&mut self, p0: (f32,f32), p1: (f32,f32), p2: (f32,f32), color: u32) {
         let min_x = p0.0.min(p1.0).min(p2.0).max(0.0) as usize;
-        let max_x = p0.0.max(p1.0).max(p2.0).min((WIN_W-1) as f32) as usize;
+        let max_x = p0.0.max(p1.0).max(p2.0).min((self.lay.w-1) as f32) as usize;
         let min_y = p0.1.min(p1.1).min(p2.1).max(0.0) as usize;
-        let max_y = p0.1.max(p1.1).max(p2.1).min((WIN_H-1) as f32) as usize;
+        let max_y = p0.1.max(p1.1).max(p2.1).min((self.lay.h-1) as f32) as usize;
 
         let denom = (p1.1 - p2.1)*(p0.0 - p2.0) + (p2.0 - p1.0)*(p0.1 - p2.1);
         if denom.abs() < 1e-6 { return; }
@@ -766,33 +1831,32 @@ impl Visualizer {
                 let w1 = ((p2.1-p0.1)*(fx-p2.0) + (p0.0-p2.0)*(fy-p2.1)) / denom;
                 let w2 = 1.0 - w0 - w1;
                 if w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0 {
-                    self.buf[py * WIN_W + px] = color;
+                    self.buf[py * self.lay.w + px] = color;
                 }
             }
         }
     }
 
     fn draw_vertical_gradient(&mut self, y0: usize, y1: usize, top: u32, bot: u32) {
-        for y in y0..y1.min(WIN_H) {
+        for y in y0..y1.min(self.lay.h) {
             let t = (y - y0) as f32 / (y1 - y0) as f32;
             let c = blend(top, bot, t);
-            for x in 0..WIN_W { self.buf[y * WIN_W + x] = c; }
+            for x in 0..self.lay.w { self.buf[y * self.lay.w + x] = c; }
         }
     }
 
     fn draw_label(&mut self, text: &str, x: usize, y: usize, color: u32) {
         let mut cx = x;
         for ch in text.chars() {
-            let glyph = char_glyph(ch);
-            for (row, &bits) in glyph.iter().enumerate() {
-                for col in 0..3usize {
-                    if bits & (1 << (2 - col)) != 0 {
+            if cx + GLYPH_W > self.lay.w { break; }
+            for (row, &bits) in font::glyph(ch).iter().enumerate() {
+                for col in 0..GLYPH_W {
+                    if bits >> col & 1 != 0 {
                         self.set_pixel(cx + col, y + row, color);
                     }
                 }
             }
-            cx += 4;
-            if cx + 4 > WIN_W { break; }
+            cx += GLYPH_W;
         }
     }
 }
@@ -937,19 +2001,6 @@ fn scissors_hand() -> HandPose {
     ]}
 }
 
-// ════════════════════════════════════════════════════════════════════════════
-// Perspective projection
-// ════════════════════════════════════════════════════════════════════════════
-
-/// Project a 3D world point to screen pixel coordinates.
-/// World: X = screen-right, Y = screen-up (inverted for screen), Z = depth (into screen).
-fn project_3d(wx: f32, wy: f32, wz: f32) -> (f32, f32) {
-    let z = wz.max(0.001);
-    let sx = P3_VPX + wx * P3_FOCAL / z;
-    let sy = P3_VPY - wy * P3_FOCAL / z;   // Y flipped: world-up = screen-up
-    (sx, sy)
-}
-
 // ════════════════════════════════════════════════════════════════════════════
 // Helper: extract stitch progress from StitchPhase
 // ════════════════════════════════════════════════════════════════════════════
@@ -986,33 +2037,243 @@ fn blend(a: u32, b: u32, t: f32) -> u32 {
 }
 
 // ════════════════════════════════════════════════════════════════════════════
-// Minimal 3×5 bitmap font
+// Key names (gesture config)
 // ════════════════════════════════════════════════════════════════════════════
 
-fn char_glyph(c: char) -> [u8; 5] {
-    match c {
-        '0'=>[0b111,0b101,0b101,0b101,0b111], '1'=>[0b010,0b110,0b010,0b010,0b111],
-        '2'=>[0b111,0b001,0b111,0b100,0b111], '3'=>[0b111,0b001,0b111,0b001,0b111],
-        '4'=>[0b101,0b101,0b111,0b001,0b001], '5'=>[0b111,0b100,0b111,0b001,0b111],
-        '6'=>[0b111,0b100,0b111,0b101,0b111], '7'=>[0b111,0b001,0b001,0b001,0b001],
-        '8'=>[0b111,0b101,0b111,0b101,0b111], '9'=>[0b111,0b101,0b111,0b001,0b111],
-        'a'|'A'=>[0b111,0b101,0b111,0b101,0b101], 'b'|'B'=>[0b110,0b101,0b110,0b101,0b110],
-        'c'|'C'=>[0b111,0b100,0b100,0b100,0b111], 'd'|'D'=>[0b110,0b101,0b101,0b101,0b110],
-        'e'|'E'=>[0b111,0b100,0b111,0b100,0b111], 'f'|'F'=>[0b111,0b100,0b111,0b100,0b100],
-        'g'|'G'=>[0b111,0b100,0b101,0b101,0b111], 'h'|'H'=>[0b101,0b101,0b111,0b101,0b101],
-        'i'|'I'=>[0b111,0b010,0b010,0b010,0b111], 'j'|'J'=>[0b001,0b001,0b001,0b101,0b111],
-        'k'|'K'=>[0b101,0b101,0b110,0b101,0b101], 'l'|'L'=>[0b100,0b100,0b100,0b100,0b111],
-        'm'|'M'=>[0b101,0b111,0b101,0b101,0b101], 'n'|'N'=>[0b111,0b101,0b101,0b101,0b101],
-        'o'|'O'=>[0b111,0b101,0b101,0b101,0b111], 'p'|'P'=>[0b111,0b101,0b111,0b100,0b100],
-        'r'|'R'=>[0b110,0b101,0b110,0b101,0b101], 's'|'S'=>[0b111,0b100,0b111,0b001,0b111],
-        't'|'T'=>[0b111,0b010,0b010,0b010,0b010], 'u'|'U'=>[0b101,0b101,0b101,0b101,0b111],
-        'v'|'V'=>[0b101,0b101,0b101,0b010,0b010], 'w'|'W'=>[0b101,0b101,0b101,0b111,0b101],
-        'x'|'X'=>[0b101,0b101,0b010,0b101,0b101], 'y'|'Y'=>[0b101,0b101,0b111,0b010,0b010],
-        'z'|'Z'=>[0b111,0b001,0b010,0b100,0b111],
-        '/'=>[0b001,0b001,0b010,0b100,0b100], '-'=>[0b000,0b000,0b111,0b000,0b000],
-        '.'=>[0b000,0b000,0b000,0b000,0b010], ','=>[0b000,0b000,0b000,0b010,0b100],
-        ':'=>[0b000,0b010,0b000,0b010,0b000], '='=>[0b000,0b111,0b000,0b111,0b000],
-        '+'=>[0b000,0b010,0b111,0b010,0b000], ' '=>[0b000,0b000,0b000,0b000,0b000],
-        _  =>[0b000,0b000,0b010,0b000,0b000],
+/// The legend of `keys` shown under the status bar: each key name with
+/// its action's label, in action order.
+pub fn key_legend(keys: &BTreeMap<String, SimKey>) -> String {
+    let mut bound: Vec<_> = keys.iter().collect();
+    bound.sort_by_key(|&(_, &action)| action);
+    bound.iter()
+        .map(|(name, action)| format!("{}={}", name, action.label()))
+        .chain(std::iter::once("Shift=fast".to_string()))
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// A config key name with an optional `Ctrl+` prefix → (key, with Ctrl).
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_binding(name: &str) -> Option<(Key, bool)> {
+    let prefix = name.get(..5).filter(|p| p.eq_ignore_ascii_case("ctrl+"));
+    match prefix {
+        Some(_) => Some((parse_key(&name[5..])?, true)),
+        None    => Some((parse_key(name)?, false)),
+    }
+}
+
+/// minifb key for a config key name: a letter or digit, a punctuation
+/// character, or a named key such as `Space`, `Escape`, `F1` or `NumPad+`.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_key(name: &str) -> Option<Key> {
+    const LETTERS: [Key; 26] = [
+        Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I,
+        Key::J, Key::K, Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R,
+        Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    ];
+    const DIGITS: [Key; 10] = [
+        Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4,
+        Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    ];
+    const FUNCTION: [Key; 12] = [
+        Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6,
+        Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    ];
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => Some(LETTERS[c as usize - 'A' as usize]),
+            c @ '0'..='9' => Some(DIGITS[c as usize - '0' as usize]),
+            '[' => Some(Key::LeftBracket),
+            ']' => Some(Key::RightBracket),
+            '-' => Some(Key::Minus),
+            '=' => Some(Key::Equal),
+            ',' => Some(Key::Comma),
+            '.' => Some(Key::Period),
+            '/' => Some(Key::Slash),
+            ';' => Some(Key::Semicolon),
+            '\'' => Some(Key::Apostrophe),
+            '`' => Some(Key::Backquote),
+            '\\' => Some(Key::Backslash),
+            _   => None,
+        };
+    }
+    match name.to_ascii_lowercase().as_str() {
+        "space"            => Some(Key::Space),
+        "escape" | "esc"   => Some(Key::Escape),
+        "enter" | "return" => Some(Key::Enter),
+        "tab"              => Some(Key::Tab),
+        "backspace"        => Some(Key::Backspace),
+        "delete" | "del"   => Some(Key::Delete),
+        "up"               => Some(Key::Up),
+        "down"             => Some(Key::Down),
+        "left"             => Some(Key::Left),
+        "right"            => Some(Key::Right),
+        "pageup"           => Some(Key::PageUp),
+        "pagedown"         => Some(Key::PageDown),
+        "home"             => Some(Key::Home),
+        "end"              => Some(Key::End),
+        "numpad+"          => Some(Key::NumPadPlus),
+        "numpad-"          => Some(Key::NumPadMinus),
+        f if f.starts_with('f') => f[1..].parse::<usize>().ok()
+            .and_then(|n| FUNCTION.get(n.checked_sub(1)?).copied()),
+        _                  => None,
+    }
+}
+
+/// The character a key types into the name prompt.
+#[cfg(not(target_arch = "wasm32"))]
+fn key_char(key: Key, shift: bool) -> Option<char> {
+    let c = match key {
+        Key::Space  => ' ',
+        Key::Minus  => if shift { '_' } else { '-' },
+        Key::Period => '.',
+        _ => {
+            let name = format!("{:?}", key);
+            let c = name.strip_prefix("Key").unwrap_or(&name);
+            let mut chars = c.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_alphanumeric() => c,
+                _ => return None,
+            }
+        }
+    };
+    Some(if shift { c } else { c.to_ascii_lowercase() })
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_config_key_names() {
+        assert_eq!(parse_key("a"), Some(Key::A));
+        assert_eq!(parse_key("7"), Some(Key::Key7));
+        assert_eq!(parse_key("["), Some(Key::LeftBracket));
+        assert_eq!(parse_key("Escape"), Some(Key::Escape));
+        assert_eq!(parse_key("F12"), Some(Key::F12));
+        assert_eq!(parse_key("PageDown"), Some(Key::PageDown));
+        assert_eq!(parse_key("F0"), None);
+        assert_eq!(parse_key("NumPad+"), Some(Key::NumPadPlus));
+        assert_eq!(parse_key("Hyper"), None);
+        assert_eq!(parse_binding("Ctrl+Z"), Some((Key::Z, true)));
+        assert_eq!(parse_binding("ctrl+["), Some((Key::LeftBracket, true)));
+        assert_eq!(parse_binding("Z"), Some((Key::Z, false)));
+        assert_eq!(parse_binding("Ctrl+"), None);
+    }
+
+    #[test]
+    fn name_prompt_types_edits_and_finishes() {
+        let mut entry = TextEntry { prompt: String::new(), text: String::new() };
+        for (key, shift) in [(Key::R, true), (Key::I, false), (Key::F, false), (Key::X, false),
+                             (Key::Backspace, false), (Key::Minus, true), (Key::Key2, false),
+                             (Key::Up, false)] {
+            assert_eq!(entry.press(key, shift), None);
+        }
+        assert_eq!(entry.text, "Rif_2");
+        assert_eq!(entry.press(Key::Enter, false), Some("Rif_2".to_string()));
+        assert_eq!(entry.press(Key::Escape, false), Some(String::new()));
+    }
+
+    #[test]
+    fn layout_follows_the_window_size() {
+        let lay = Layout::new(WIN_W, WIN_H);
+        assert_eq!((lay.tray_w, lay.flat_left_y, lay.flat_right_y), (220, 60, 340));
+        assert_eq!((lay.td_left_x, lay.td_right_x, lay.td_bottom_y), (120, 520, 640));
+        assert_eq!((lay.tray_rows, lay.roll_y, lay.roll_h), (8, 513, 171));
+
+        let big = Layout::new(3840, 2160);
+        assert_eq!(big.tray_w, 360);
+        assert!(big.ribbon_w > 3 * lay.ribbon_w && big.tray_rows > lay.tray_rows);
+        assert_eq!(Layout::new(200, 100), Layout::new(MIN_W, MIN_H));
+    }
+
+    #[test]
+    fn detaching_gives_the_ribbons_the_whole_window() {
+        let lay = Layout::ribbons_only(WIN_W, WIN_H);
+        assert_eq!((lay.ribbon_w, lay.tray_w, lay.status_y), (WIN_W, 0, WIN_H));
+        assert!(lay.ribbon_capacity(LayoutMode::Flat, 1.0) > Layout::new(WIN_W, WIN_H).ribbon_capacity(LayoutMode::Flat, 1.0));
+
+        let panel = Layout::panel(PANEL_W, WIN_H, false);
+        assert_eq!((panel.ribbon_w, panel.tray_w), (0, PANEL_W));
+        assert_eq!(panel.tray_row(10, TRAY_TOP), Some(0));
+        // With the histogram, it takes the left half.
+        let split = Layout::panel(PANEL_W, WIN_H, true);
+        assert_eq!((split.ribbon_w, split.tray_w), (PANEL_W / 2, PANEL_W / 2));
+        assert_eq!(split.tray_row(10, TRAY_TOP), None);
+    }
+
+    #[test]
+    fn scaling_enlarges_every_pixel() {
+        let mut dst = Vec::new();
+        assert_eq!(upscale(&[1, 2, 3, 4], 2, 2, 2.0, &mut dst), (4, 4));
+        assert_eq!(dst, [1, 1, 2, 2, 1, 1, 2, 2, 3, 3, 4, 4, 3, 3, 4, 4]);
+        assert_eq!(upscale(&[1, 2], 2, 1, 1.5, &mut dst), (3, 1));
+        assert_eq!(dst, [1, 1, 2]);
+    }
+
+    #[test]
+    fn parses_scales_and_screen_sizes() {
+        assert_eq!(parse_scale("2"), Some(2.0));
+        assert_eq!(parse_scale(" 1.5 "), Some(1.5));
+        assert_eq!(parse_scale("0.5"), None);
+        assert_eq!(parse_scale("big"), None);
+
+        assert_eq!(parse_size("1920x1080"), Some((1920, 1080)));
+        assert_eq!(parse_size("3840X2160"), Some((3840, 2160)));
+        assert_eq!(parse_size("1920"), None);
+        assert_eq!(parse_size("100x100"), None);
+    }
+
+    #[test]
+    fn zooming_out_fits_more_patches() {
+        let lay = Layout::new(WIN_W, WIN_H);
+        assert_eq!(lay.ribbon_capacity(LayoutMode::Flat, 1.0), 1060 / 48 + 2);
+        assert_eq!(lay.ribbon_capacity(LayoutMode::Flat, 0.5), 1060 / 24 + 2);
+        assert_eq!(lay.ribbon_capacity(LayoutMode::TwoD, 2.0), 640 / 96 + 2);
+        assert!(lay.ribbon_capacity(LayoutMode::ThreeD, 0.5) > lay.ribbon_capacity(LayoutMode::ThreeD, 1.0));
+    }
+
+    #[test]
+    fn spiral_arms_wind_inward_without_touching() {
+        let arm = spiral_arm(300.0, 28.0, 0.0);
+        assert_eq!(arm[0], (0.0, 300.0));
+        assert!(arm.windows(2).all(|w| w[1].1 < w[0].1 && w[1].0 > w[0].0));
+        assert!(arm.last().unwrap().1 >= SPIRAL_R_MIN);
+        // A whole turn in, the arm is two spacings nearer the centre — room
+        // for the other arm in between.
+        let turn = arm.iter().find(|&&(a, _)| a >= std::f32::consts::TAU).unwrap();
+        assert!((300.0 - turn.1 - 56.0).abs() < 2.0);
+
+        let lay = Layout::new(WIN_W, WIN_H);
+        assert_eq!(lay.ribbon_capacity(LayoutMode::Spiral, 1.0), spiral_arm(lay.spiral_frame().2, 28.0, 0.0).len() + 2);
+        assert_eq!(LayoutMode::from_str("Spiral"), LayoutMode::Spiral);
+    }
+
+    #[test]
+    fn clicks_map_to_tray_rows() {
+        let lay = Layout::new(WIN_W, WIN_H);
+        let x = WIN_W - lay.tray_w / 2;
+        assert_eq!(lay.tray_row(x, TRAY_TOP), Some(0));
+        assert_eq!(lay.tray_row(x, TRAY_TOP + TRAY_ROW_H + 10), Some(1));
+        assert_eq!(lay.tray_row(x, TRAY_TOP + 52), None);
+        assert_eq!(lay.tray_row(10, TRAY_TOP), None);
+        assert_eq!(lay.tray_row(x, WIN_H - 20), None);
+    }
+
+    #[test]
+    fn wheel_pulls_the_ribbon_under_the_mouse() {
+        let lay = Layout::new(WIN_W, WIN_H);
+        assert_eq!(lay.ribbon_side_at(LayoutMode::Flat, 300, lay.flat_left_y + 10), Some(0));
+        assert_eq!(lay.ribbon_side_at(LayoutMode::Flat, 300, lay.flat_right_y + 10), Some(1));
+        assert_eq!(lay.ribbon_side_at(LayoutMode::TwoD, lay.td_left_x + 10, 300), Some(0));
+        assert_eq!(lay.ribbon_side_at(LayoutMode::TwoD, lay.td_right_x + 10, 300), Some(1));
+        assert_eq!(lay.ribbon_side_at(LayoutMode::ThreeD, 300, WIN_H / 2 + 100), Some(0));
+        assert_eq!(lay.ribbon_side_at(LayoutMode::Spiral, lay.ribbon_w - 10, 300), Some(1));
+        assert_eq!(lay.ribbon_side_at(LayoutMode::Flat, WIN_W - 10, 300), None);
     }
 }
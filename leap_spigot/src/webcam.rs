@@ -0,0 +1,215 @@
+//! Webcam hand tracking.
+//!
+//! [`WebcamGestureSource`] runs an external hand-tracking program and feeds
+//! the hands it reports to the same [`HandRecognizer`] as the LeapMotion
+//! source, so the app can be played with an ordinary camera.  The program is
+//! any command that writes one line per camera frame to stdout:
+//!
+//! ```text
+//! <time ms>[;<L|R> <x> <y> <z> <pose> [<roll>]]...
+//! ```
+//!
+//! with palm positions already converted to LeapMotion-style millimetres
+//! (X right, Y up, Z toward the user), the pose as 0 open, 1 scissors,
+//! 2 fist or 3 pinch, and optionally the palm's roll in degrees (positive
+//! turning right) — e.g. `1520;L -120 210 40 0;R 130 190 35 2 -15`.
+//! `tools/webcam_hands.py` is such a tracker, built on MediaPipe Hands.
+
+use std::io::{BufRead, BufReader, Lines};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::mpsc::Sender;
+use std::time::Instant;
+
+use crate::config::GestureConfig;
+use crate::gesture::{GestureEvent, GestureSource, HandPose, HandRecognizer, TrackedHand};
+
+/// Tracker run when none is given on the command line.
+pub const DEFAULT_TRACKER: &str = "python3 tools/webcam_hands.py";
+
+/// Weight of the newest frame in the smoothed palm velocity; camera
+/// positions are noisier than the Leap's.
+const VELOCITY_SMOOTHING: f32 = 0.5;
+
+/// One hand as reported by the tracker.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HandSample {
+    pub left: bool,
+    pub palm: [f32; 3],
+    pub pose: HandPose,
+    /// Palm roll (radians), 0 if not reported.
+    pub roll: f32,
+}
+
+/// Parse one tracker line into its timestamp (ms) and hands.
+pub fn parse_frame(line: &str) -> Result<(f64, Vec<HandSample>), String> {
+    let mut parts = line.trim().split(';');
+    let time = parts.next().unwrap_or_default().trim();
+    let time: f64 = time.parse().map_err(|_| format!("bad frame time {:?}", time))?;
+
+    let hands = parts.map(|part| {
+        let mut fields: Vec<&str> = part.split_whitespace().collect();
+        let roll = if fields.len() == 6 { fields.pop() } else { None };
+        let [side, x, y, z, pose] = fields[..] else {
+            return Err(format!("expected 5 or 6 fields in hand {:?}", part));
+        };
+        let left = match side {
+            "L" => true,
+            "R" => false,
+            _   => return Err(format!("unknown hand {:?}", side)),
+        };
+        let pose = match pose {
+            "0" => HandPose::Open,
+            "1" => HandPose::Scissors,
+            "2" => HandPose::Fist,
+            "3" => HandPose::Pinch,
+            _   => return Err(format!("unknown pose {:?}", pose)),
+        };
+        let coord = |s: &str| s.parse::<f32>().map_err(|_| format!("bad coordinate {:?}", s));
+        let roll = match roll {
+            Some(r) => r.parse::<f32>().map_err(|_| format!("bad roll {:?}", r))?.to_radians(),
+            None    => 0.0,
+        };
+        Ok(HandSample { left, palm: [coord(x)?, coord(y)?, coord(z)?], pose, roll })
+    }).collect::<Result<_, _>>()?;
+    Ok((time, hands))
+}
+
+/// Derives palm velocities from successive tracker frames.
+#[derive(Default)]
+pub struct HandTracker {
+    last:  Option<f64>,
+    left:  Option<TrackedHand>,
+    right: Option<TrackedHand>,
+}
+
+impl HandTracker {
+    /// Update with one frame; returns the tracked (left, right) hands.
+    pub fn update(&mut self, time_ms: f64, hands: &[HandSample])
+        -> (Option<TrackedHand>, Option<TrackedHand>)
+    {
+        let dt = self.last.map(|t| ((time_ms - t) / 1000.0) as f32).filter(|&dt| dt > 0.0);
+        self.last = Some(time_ms);
+        for is_left in [true, false] {
+            let slot = if is_left { &mut self.left } else { &mut self.right };
+            let sample = hands.iter().find(|h| h.left == is_left);
+            *slot = sample.map(|s| {
+                let velocity = match (*slot, dt) {
+                    (Some(prev), Some(dt)) => std::array::from_fn(|i| {
+                        let v = (s.palm[i] - prev.palm[i]) / dt;
+                        prev.velocity[i] + VELOCITY_SMOOTHING * (v - prev.velocity[i])
+                    }),
+                    _ => [0.0; 3],
+                };
+                TrackedHand { palm: s.palm, velocity, pose: s.pose, roll: s.roll }
+            });
+        }
+        (self.left, self.right)
+    }
+}
+
+/// Tracked hands, one `(left, right)` pair per camera frame, from a running
+/// tracker program.  Malformed lines are reported and skipped; iteration
+/// ends when the tracker exits.
+pub struct TrackerFrames {
+    child:   Child,
+    lines:   Lines<BufReader<ChildStdout>>,
+    tracker: HandTracker,
+}
+
+impl TrackerFrames {
+    /// Start the tracker `command` (split on whitespace).
+    pub fn spawn(command: &str) -> Result<Self, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("empty tracker command")?;
+        let mut child = Command::new(program).args(words)
+            .stdout(Stdio::piped()).spawn()
+            .map_err(|e| format!("failed to start {:?}: {}", command, e))?;
+        let stdout = child.stdout.take().expect("piped stdout");
+        Ok(TrackerFrames { child, lines: BufReader::new(stdout).lines(), tracker: HandTracker::default() })
+    }
+}
+
+impl Iterator for TrackerFrames {
+    type Item = (Option<TrackedHand>, Option<TrackedHand>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = self.lines.next()?.ok()?;
+            match parse_frame(&line) {
+                Ok((time, hands)) => return Some(self.tracker.update(time, &hands)),
+                Err(e)            => eprintln!("[webcam] {}", e),
+            }
+        }
+    }
+}
+
+impl Drop for TrackerFrames {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+/// Gesture source driven by a webcam hand tracker.
+pub struct WebcamGestureSource {
+    /// Tracker command line, split on whitespace.
+    pub command: String,
+    pub config:  GestureConfig,
+}
+
+impl GestureSource for WebcamGestureSource {
+    fn run(self: Box<Self>, tx: Sender<GestureEvent>) {
+        let frames = match TrackerFrames::spawn(&self.command) {
+            Ok(f)  => f,
+            Err(e) => {
+                eprintln!("[webcam] {} — webcam input disabled", e);
+                return;
+            }
+        };
+        eprintln!("[webcam] Tracking hands with: {}", self.command);
+
+        let mut recognizer = HandRecognizer::new(&self.config);
+        for (left, right) in frames {
+            let events = recognizer.update(left.as_ref(), right.as_ref(), Instant::now());
+            if !events.into_iter().all(|e| tx.send(e).is_ok()) { return; }
+        }
+        eprintln!("[webcam] Tracker stopped — webcam input disabled");
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tracker_lines() {
+        let (t, hands) = parse_frame("1520;L -120 210 40 0;R 130 190.5 35 3 -90").unwrap();
+        assert_eq!(t, 1520.0);
+        assert_eq!(hands, vec![
+            HandSample { left: true,  palm: [-120.0, 210.0, 40.0], pose: HandPose::Open,  roll: 0.0 },
+            HandSample { left: false, palm: [130.0, 190.5, 35.0],  pose: HandPose::Pinch, roll: -std::f32::consts::FRAC_PI_2 },
+        ]);
+        assert_eq!(parse_frame("7").unwrap().1, vec![]);
+        assert!(parse_frame("7;X 1 2 3 0").is_err());
+        assert!(parse_frame("7;L 1 2").is_err());
+        assert!(parse_frame("7;L 1 2 3 9").is_err());
+        assert!(parse_frame("7;L 1 2 3 0 flat").is_err());
+    }
+
+    #[test]
+    fn tracker_derives_velocity_for_a_pull() {
+        let mut tracker = HandTracker::default();
+        let hand = |z| HandSample { left: true, palm: [0.0, 200.0, z], pose: HandPose::Open, roll: 0.0 };
+        tracker.update(0.0, &[hand(0.0)]);
+        let (left, right) = tracker.update(100.0, &[hand(100.0)]);
+        assert!(right.is_none());
+        // 100 mm in 0.1 s = 1000 mm/s, half-weighted against the still start.
+        assert_eq!(left.unwrap().velocity, [0.0, 0.0, 500.0]);
+
+        let events = HandRecognizer::default().update(left.as_ref(), None, Instant::now());
+        assert!(matches!(events[0], GestureEvent::PullLeft { steps: 5, .. }));
+    }
+}
@@ -0,0 +1,88 @@
+#!/usr/bin/env python3
+"""Webcam hand tracker for `leap_spigot --webcam`.
+
+Tracks both hands with MediaPipe Hands and prints one line per frame in the
+format read by `src/webcam.rs`:
+
+    <time ms>[;<L|R> <x> <y> <z> <pose> <roll>]...
+
+where pose is 0 open, 1 scissors, 2 fist or 3 pinch, and roll is the tilt
+of the knuckles in degrees, positive as the palm turns to the right.
+
+Positions are rough LeapMotion-style millimetres: X to the user's right,
+Y up, Z toward the user (estimated from the apparent palm size).
+
+Requires:  pip install mediapipe opencv-python
+"""
+
+import math
+import sys
+import time
+
+import cv2
+import mediapipe as mp
+
+WIDTH_MM = 500.0    # camera view width mapped to X
+HEIGHT_MM = 400.0   # camera view height mapped to Y
+Y_FLOOR_MM = 80.0   # Y at the bottom of the frame
+DEPTH_SCALE = 40.0  # palm width 0.1 of the frame ≈ 400 mm away
+
+PALM = (0, 5, 9, 13, 17)
+
+
+def dist(a, b):
+    return ((a.x - b.x) ** 2 + (a.y - b.y) ** 2) ** 0.5
+
+
+def pose(lm):
+    wrist = lm[0]
+    reach = lambda tip, knuckle: dist(lm[tip], wrist) > 1.6 * dist(lm[knuckle], wrist)
+    if dist(lm[4], lm[8]) < 0.25 * dist(lm[5], lm[17]):
+        return 3  # pinch
+    extended = reach(8, 5) and reach(12, 9)
+    curled = not reach(16, 13) and not reach(20, 17)
+    spread = dist(lm[8], lm[12]) > 0.8 * dist(lm[5], lm[17])
+    if extended and curled and spread:
+        return 1  # scissors
+    if not any(reach(tip, tip - 3) for tip in (8, 12, 16, 20)):
+        return 2  # fist
+    return 0
+
+
+def roll(label, lm):
+    # Knuckle line pointing to the user's right when the hand is level;
+    # image Y grows downward, so a right-hand tilt is positive.
+    a, b = (lm[5], lm[17]) if label == "Right" else (lm[17], lm[5])
+    return math.degrees(math.atan2(b.y - a.y, b.x - a.x))
+
+
+def hand_fields(label, lm):
+    cx = sum(lm[i].x for i in PALM) / len(PALM)
+    cy = sum(lm[i].y for i in PALM) / len(PALM)
+    size = max(dist(lm[5], lm[17]), 1e-3)
+    x = (cx - 0.5) * WIDTH_MM
+    y = (1.0 - cy) * HEIGHT_MM + Y_FLOOR_MM
+    z = DEPTH_SCALE / size
+    side = "L" if label == "Left" else "R"
+    return f"{side} {x:.1f} {y:.1f} {z:.1f} {pose(lm)} {roll(label, lm):.1f}"
+
+
+def main():
+    camera = cv2.VideoCapture(int(sys.argv[1]) if len(sys.argv) > 1 else 0)
+    start = time.monotonic()
+    with mp.solutions.hands.Hands(max_num_hands=2) as hands:
+        while camera.isOpened():
+            ok, frame = camera.read()
+            if not ok:
+                break
+            # Mirror so MediaPipe's handedness matches the user's own hands.
+            frame = cv2.cvtColor(cv2.flip(frame, 1), cv2.COLOR_BGR2RGB)
+            result = hands.process(frame)
+            parts = [f"{(time.monotonic() - start) * 1000.0:.0f}"]
+            for lm, hd in zip(result.multi_hand_landmarks or [], result.multi_handedness or []):
+                parts.append(hand_fields(hd.classification[0].label, lm.landmark))
+            print(";".join(parts), flush=True)
+
+
+if __name__ == "__main__":
+    main()
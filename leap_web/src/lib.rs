@@ -0,0 +1,141 @@
+//! # leap_web
+//!
+//! The `leap_spigot` ribbon performance in a browser, so a workshop can
+//! play it with nothing installed: the visualizer's renderer draws on a
+//! canvas, the keyboard stands in for the Leap Motion as it does on the
+//! desktop, and notes go out through Web MIDI.  Build it with
+//! [wasm-pack](https://rustwasm.github.io/wasm-pack/) and serve this
+//! directory:
+//!
+//! ```text
+//! wasm-pack build --release --target web
+//! python3 -m http.server                  # then open /www/index.html
+//! ```
+//!
+//! `www/index.html` is the whole page; in short:
+//!
+//! ```js
+//! import init, { Performance } from "../pkg/leap_web.js";
+//!
+//! await init();
+//! const perf = new Performance(canvas, "pi", "e", "flat");
+//! const midi = await navigator.requestMIDIAccess();
+//! perf.setMidiOutput(midi.outputs.values().next().value);
+//! addEventListener("keydown", e => {
+//!     if (perf.keyDown(e.code, e.shiftKey, e.ctrlKey, e.repeat)) e.preventDefault();
+//! });
+//! requestAnimationFrame(function frame(now) { perf.frame(now); requestAnimationFrame(frame); });
+//! ```
+//!
+//! Keys are the default bindings of `gestures.toml`, found by their place
+//! on the keyboard.  What needs files, ports or windows — saving, the
+//! session log, choosing a port, more ribbon pairs, the detached tray,
+//! undo — stays on the desktop, as do the Leap Motion, the built-in synth
+//! and Ableton Link.
+
+mod stage;
+
+pub use stage::{key_name, MidiOut, Stage};
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData, MidiOutput};
+
+use leap_spigot::visualizer::LayoutMode;
+use spigot_midi::provenance::parse_stream;
+
+/// A ribbon performance drawn on a canvas.
+#[wasm_bindgen]
+pub struct Performance {
+    stage:   Stage,
+    canvas:  HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+    midi:    Option<MidiOutput>,
+    /// The frame as the canvas takes it, four bytes a pixel.
+    rgba:    Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Performance {
+    /// Streams `left` and `right` (`CONSTANT[:BASE]`, as `pi` or `e:16`)
+    /// on ribbons laid out `layout` (`flat`, `2d`, `3d` or `spiral`) on
+    /// `canvas`, at its size.
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement, left: &str, right: &str, layout: &str) -> Result<Performance, JsError> {
+        let left = parse_stream(left).map_err(js)?;
+        let right = parse_stream(right).map_err(js)?;
+        let layout = LayoutMode::from_str(layout);
+        let context = canvas.get_context("2d")
+            .map_err(|_| JsError::new("no 2d context on the canvas"))?
+            .ok_or_else(|| JsError::new("no 2d context on the canvas"))?
+            .dyn_into::<CanvasRenderingContext2d>()
+            .map_err(|_| JsError::new("no 2d context on the canvas"))?;
+        let stage = Stage::new(left, right, layout, canvas.width() as usize, canvas.height() as usize);
+        Ok(Performance { stage, canvas, context, midi: None, rgba: Vec::new() })
+    }
+
+    /// Play through `output`, or nowhere.
+    #[wasm_bindgen(js_name = setMidiOutput)]
+    pub fn set_midi_output(&mut self, output: Option<MidiOutput>) {
+        self.midi = output;
+        self.stage.send_program();
+    }
+
+    /// A key pressed, from a `keydown` event.  Returns whether it's bound,
+    /// for the page to `preventDefault()`.
+    #[wasm_bindgen(js_name = keyDown)]
+    pub fn key_down(&mut self, code: &str, shift: bool, ctrl: bool, repeat: bool) -> bool {
+        self.stage.key_down(code, shift, ctrl, repeat)
+    }
+
+    /// A click at canvas pixel `(x, y)`.
+    pub fn click(&mut self, x: u32, y: u32) {
+        self.stage.click(x as usize, y as usize);
+    }
+
+    /// The wheel turned `notches` at canvas pixel `(x, y)`.
+    pub fn wheel(&mut self, x: u32, y: u32, notches: f32) {
+        self.stage.wheel(x as usize, y as usize, notches);
+    }
+
+    /// Play up to `now` (`performance.now()`, as `requestAnimationFrame`
+    /// passes it) and draw the frame.
+    pub fn frame(&mut self, now: f64) -> Result<(), JsError> {
+        self.stage.advance(now);
+        let midi = self.stage.take_midi();
+        if let Some(output) = &self.midi {
+            if midi.clear { output.clear(); }
+            for (at, bytes) in midi.messages {
+                let message = js_sys::Uint8Array::from(&bytes[..]);
+                output.send_with_timestamp(&message, at)
+                    .map_err(|_| JsError::new("MIDI send failed"))?;
+            }
+        }
+
+        let (w, h) = (self.canvas.width() as usize, self.canvas.height() as usize);
+        self.stage.resize(w, h);
+        let (pixels, w, h) = self.stage.draw();
+        if (w, h) != (self.canvas.width() as usize, self.canvas.height() as usize) {
+            self.canvas.set_width(w as u32);
+            self.canvas.set_height(h as u32);
+        }
+        self.rgba.clear();
+        self.rgba.extend(pixels.iter().flat_map(|&p| {
+            let [_, r, g, b] = p.to_be_bytes();
+            [r, g, b, 0xFF]
+        }));
+        let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&self.rgba), w as u32, h as u32)
+            .map_err(|_| JsError::new("couldn't make the frame's image"))?;
+        self.context.put_image_data(&image, 0.0, 0.0)
+            .map_err(|_| JsError::new("couldn't draw the frame"))?;
+        Ok(())
+    }
+
+    /// The status line, as shown under the ribbons.
+    #[wasm_bindgen(getter)]
+    pub fn status(&self) -> String { self.stage.status().to_string() }
+}
+
+fn js(e: impl std::fmt::Display) -> JsError {
+    JsError::new(&e.to_string())
+}
@@ -0,0 +1,429 @@
+//! The ribbon performance without threads or a window.
+//!
+//! Gestures go to the same [`Performer`] as in the desktop app, which
+//! changes the streams and ribbons at once; [`Stage::advance`] takes the pairs due in the next moment off the
+//! stream and schedules their notes on the page clock, and [`Stage::draw`]
+//! animates and renders a frame.  Nothing here touches the browser, so the
+//! page decides when each runs — once per animation frame.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use dual_spigot::{DualStream, SpigotConfig};
+use leap_spigot::config::default_keys;
+use leap_spigot::gesture::{GestureEvent, SimKey};
+use leap_spigot::perform::{self, Cue, NoteEvent, Performer, PlayState, TPQ};
+use leap_spigot::visualizer::{key_legend, HandGesture, LayoutMode, Renderer, Scene};
+use spigot_midi::{DurationMap, PitchMap};
+
+/// How far ahead of the frame notes are sent, stamped with the time they're
+/// due, so they keep time between frames.
+const LOOKAHEAD_MS: f64 = 100.0;
+
+/// Further behind than this (the tab was hidden, say) playback starts
+/// afresh rather than rushing through the missed notes.
+const MAX_LAG_MS: f64 = 250.0;
+
+/// Wheel turn (in notches a frame) that pulls at full speed.
+const WHEEL_FULL_SPEED: f32 = 6.0;
+
+/// MIDI controllers `Panic` sends on every channel.
+const ALL_SOUND_OFF: u8 = 120;
+const ALL_NOTES_OFF: u8 = 123;
+
+/// Actions a page has no use for: files, MIDI ports, windows, more ribbon
+/// pairs, the undo history and the name prompt.
+const DESKTOP_ONLY: &[SimKey] = &[
+    SimKey::Save, SimKey::SaveSession, SimKey::NextPort, SimKey::TrayRename,
+    SimKey::Undo, SimKey::Redo, SimKey::NextPair, SimKey::Fullscreen,
+    SimKey::DetachTray, SimKey::Screenshot, SimKey::Quit,
+];
+
+/// What the page should tell its MIDI output.
+#[derive(Debug, Default, PartialEq)]
+pub struct MidiOut {
+    /// Drop everything sent ahead that hasn't gone out yet.
+    pub clear:    bool,
+    /// Messages, each with the page time (ms, as `performance.now()`) it's
+    /// due; those already past go out at once.
+    pub messages: Vec<(f64, Vec<u8>)>,
+}
+
+pub struct Stage {
+    // ── streams and ribbons ──────────────────────────────────────────────
+    dual:         DualStream,
+    /// The ribbons, the tray and the playing settings.
+    perf:         Performer,
+    renderer:     Renderer,
+
+    // ── keys and wheel ───────────────────────────────────────────────────
+    /// Bindings by (lower-case key name, with Ctrl).
+    keys:         BTreeMap<(String, bool), SimKey>,
+    /// Wheel turned over the (left, right) ribbon not yet pulled.
+    wheel:        [f32; 2],
+
+    // ── playback ─────────────────────────────────────────────────────────
+    pitch_map:    PitchMap,
+    duration_map: DurationMap,
+    channel:      u8,
+    /// When the next note starts, on the page clock, once scheduling.
+    next_on:      Option<f64>,
+    /// Notes sent ahead that haven't sounded yet, with their start times,
+    /// to show on the ribbons and piano roll when they sound.
+    queued:       VecDeque<(f64, NoteEvent)>,
+    /// (pitch, Note Off time) of every note sent that may still sound.
+    sounding:     Vec<(u8, f64)>,
+    midi:         MidiOut,
+}
+
+impl Stage {
+    /// Streams `left` and `right` on ribbons drawn in `layout` on a
+    /// `w`×`h` frame, played as the desktop app's defaults: C major on a
+    /// piano at 120 BPM, lengths from the left digits and pitches from the
+    /// right.
+    pub fn new(left: SpigotConfig, right: SpigotConfig, layout: LayoutMode, w: usize, h: usize) -> Self {
+        let mut renderer = Renderer::new(layout, w, h);
+        let (left_ribbon, right_ribbon) = perform::prefilled_ribbons(left, right, renderer.ribbon_capacity());
+
+        let bound: BTreeMap<String, SimKey> = default_keys().into_iter()
+            .filter(|(_, action)| !DESKTOP_ONLY.contains(action))
+            .collect();
+        renderer.set_legend(key_legend(&bound));
+        let keys = bound.into_iter().map(|(name, action)| {
+            let name = name.to_ascii_lowercase();
+            match name.strip_prefix("ctrl+") {
+                Some(key) => ((key.to_string(), true), action),
+                None      => ((name, false), action),
+            }
+        }).collect();
+
+        Stage {
+            dual:         DualStream::from_configs(left, right),
+            perf:         Performer::new(left_ribbon, right_ribbon),
+            renderer,
+            keys,
+            wheel:        [0.0; 2],
+            pitch_map:    PitchMap::major(60),
+            duration_map: DurationMap::musical(TPQ),
+            channel:      0,
+            next_on:      None,
+            queued:       VecDeque::new(),
+            sounding:     Vec::new(),
+            midi:         MidiOut::default(),
+        }
+    }
+
+    pub fn status(&self) -> &str { &self.perf.status }
+
+    /// Draw the next frames at `w`×`h` (at least 640×360).
+    pub fn resize(&mut self, w: usize, h: usize) {
+        self.renderer.resize(w, h);
+    }
+
+    /// The MIDI the page should send, taken since it last asked.
+    pub fn take_midi(&mut self) -> MidiOut {
+        std::mem::take(&mut self.midi)
+    }
+
+    /// Queue the instrument's Program Change, as for a newly chosen output.
+    pub fn send_program(&mut self) {
+        self.midi.messages.push((0.0, vec![0xC0 | self.channel, self.perf.instrument]));
+    }
+
+    // ── input from the page ───────────────────────────────────────────────
+
+    /// Act on a key pressed on the page, given by its `KeyboardEvent.code`
+    /// — the key's place on the keyboard, so the bindings sit where they
+    /// do on a QWERTY board whatever the layout.  Returns whether the key
+    /// is bound, for the page to keep it from the browser.
+    pub fn key_down(&mut self, code: &str, shift: bool, ctrl: bool, repeat: bool) -> bool {
+        let Some(name) = key_name(code) else { return false };
+        let Some(&action) = self.keys.get(&(name.to_ascii_lowercase(), ctrl)) else { return false };
+        if !repeat || action.repeats() {
+            let action = if shift { action.shifted() } else { action };
+            self.gesture(action.event());
+        }
+        true
+    }
+
+    /// A click at frame point `(x, y)`: a tray entry selects it, a flat
+    /// ribbon patch shows its digit and position.
+    pub fn click(&mut self, x: usize, y: usize) {
+        if let Some(index) = self.renderer.tray_entry_at(x, y) {
+            self.gesture(GestureEvent::PickSnippet { index });
+        } else if let Some(side) = self.renderer.flat_ribbon_at(y) {
+            if let Some(index) = self.renderer.flat_patch_at(side, x) {
+                self.gesture(GestureEvent::InspectPatch { right: side == 1, index });
+            }
+        }
+    }
+
+    /// The wheel turned `notches` over frame point `(x, y)`: over a ribbon
+    /// it pulls it, faster the faster it turns.
+    pub fn wheel(&mut self, x: usize, y: usize, notches: f32) {
+        let Some(side) = self.renderer.ribbon_at(x, y) else { return };
+        let turned = notches.abs();
+        self.wheel[side] += turned;
+        let steps = self.wheel[side] as usize;
+        if steps == 0 { return; }
+        self.wheel[side] -= steps as f32;
+        let velocity = (turned / WHEEL_FULL_SPEED).min(1.0);
+        self.gesture(match side {
+            0 => GestureEvent::PullLeft  { steps, velocity },
+            _ => GestureEvent::PullRight { steps, velocity },
+        });
+    }
+
+    // ── process one GestureEvent ─────────────────────────────────────────
+
+    pub fn gesture(&mut self, event: GestureEvent) {
+        self.renderer.notify_gesture(HandGesture::of(&event));
+        match event {
+            GestureEvent::SpiralView => self.renderer.toggle_spiral(),
+            GestureEvent::Histogram  => self.renderer.toggle_histogram(),
+            // There's no name prompt here; the performer numbers unnamed
+            // snips.  What needs files, ports, windows or hardware the page
+            // doesn't have (see `DESKTOP_ONLY`) it leaves alone.
+            event => match self.perf.gesture(&mut self.dual, event) {
+                Some(Cue::Play | Cue::Instrument(_)) => self.send_program(),
+                Some(Cue::Stop) => self.release(),
+                Some(Cue::Panic) => {
+                    self.release();
+                    for ch in 0..16 {
+                        self.midi.messages.push((0.0, vec![0xB0 | ch, ALL_NOTES_OFF, 0]));
+                        self.midi.messages.push((0.0, vec![0xB0 | ch, ALL_SOUND_OFF, 0]));
+                    }
+                }
+                Some(Cue::Splice(pairs)) => self.dual.splice(&pairs),
+                // Notes take the tempo and velocity as they're scheduled.
+                Some(Cue::Tempo(_) | Cue::Velocity(_)) | None => {}
+            },
+        }
+    }
+
+    /// Silence the notes sent ahead: drop those not yet out and release
+    /// those that are.
+    fn release(&mut self) {
+        self.midi.clear = true;
+        self.midi.messages.clear();
+        for (pitch, _) in self.sounding.drain(..) {
+            self.midi.messages.push((0.0, vec![0x80 | self.channel, pitch, 0]));
+        }
+        self.queued.clear();
+        self.next_on = None;
+        self.perf.sounding = None;
+    }
+
+    // ── per frame ─────────────────────────────────────────────────────────
+
+    /// Schedule the notes due before `now` + the lookahead (ms on the page
+    /// clock) and show those that have started.
+    pub fn advance(&mut self, now: f64) {
+        self.sounding.retain(|&(_, off)| off > now);
+
+        if self.perf.play_state == PlayState::Playing {
+            let (bpm, velocity) = (self.perf.tempo_bpm, self.perf.velocity);
+            let mut at = self.next_on.filter(|&t| t + MAX_LAG_MS >= now).unwrap_or(now);
+            while at < now + LOOKAHEAD_MS {
+                let spliced = self.dual.spliced_len() > 0;
+                let Some((left, right)) = self.dual.zip_next() else { break };
+                let pitch = self.pitch_map.note_for(right);
+                let ticks = self.duration_map.ticks_for(left);
+                let length = perform::ticks_to_duration(ticks, TPQ, bpm);
+                let off = at + ms(length - perform::release_gap(length));
+                self.midi.messages.push((at,  vec![0x90 | self.channel, pitch, velocity]));
+                self.midi.messages.push((off, vec![0x80 | self.channel, pitch, 0]));
+                self.sounding.push((pitch, off));
+                self.queued.push_back((at, NoteEvent {
+                    pitch, duration: ticks, velocity, bpm,
+                    left_pos: self.dual.left_pos(), right_pos: self.dual.right_pos(),
+                    pair: (left, right), spliced, first: true,
+                }));
+                at += ms(length);
+            }
+            self.next_on = Some(at);
+        }
+
+        while self.queued.front().is_some_and(|(at, _)| *at <= now) {
+            let Some((_, note)) = self.queued.pop_front() else { break };
+            self.perf.show_note(&note);
+        }
+    }
+
+    /// Animate one frame and draw it: pixels (0xAARRGGBB), width, height.
+    pub fn draw(&mut self) -> (&[u32], usize, usize) {
+        self.renderer.set_zoom(self.perf.zoom);
+        self.perf.fit_ribbons(&self.dual, self.renderer.ribbon_capacity());
+        self.perf.tick();
+
+        let perf = &self.perf;
+        self.renderer.render(&Scene {
+            left:    &perf.left_ribbon,
+            right:   &perf.right_ribbon,
+            stitch:  &perf.stitch,
+            tray:    &perf.tray,
+            scissor: &perf.scissor,
+            status:  &perf.status,
+            playing: perf.play_state == PlayState::Playing,
+            note_highlight: perf.note_highlight(),
+            roll:    &perf.roll,
+            stacked: &[],
+        });
+        self.renderer.frame()
+    }
+}
+
+/// `length` in milliseconds, as the page clock counts.
+fn ms(length: Duration) -> f64 {
+    length.as_secs_f64() * 1000.0
+}
+
+/// The `gestures.toml` name of the key with `KeyboardEvent.code` `code`.
+pub fn key_name(code: &str) -> Option<String> {
+    let single = code.strip_prefix("Key").or_else(|| code.strip_prefix("Digit"));
+    if let Some(c) = single.filter(|c| c.len() == 1) {
+        return Some(c.to_string());
+    }
+    if code.strip_prefix('F').is_some_and(|n| n.parse::<u8>().is_ok()) {
+        return Some(code.to_string());
+    }
+    let name = match code {
+        "Space"          => "Space",
+        "Escape"         => "Escape",
+        "Enter" | "NumpadEnter" => "Enter",
+        "Tab"            => "Tab",
+        "Backspace"      => "Backspace",
+        "Delete"         => "Delete",
+        "ArrowUp"        => "Up",
+        "ArrowDown"      => "Down",
+        "ArrowLeft"      => "Left",
+        "ArrowRight"     => "Right",
+        "PageUp"         => "PageUp",
+        "PageDown"       => "PageDown",
+        "Home"           => "Home",
+        "End"            => "End",
+        "BracketLeft"    => "[",
+        "BracketRight"   => "]",
+        "Minus"          => "-",
+        "Equal"          => "=",
+        "Comma"          => ",",
+        "Period"         => ".",
+        "Slash"          => "/",
+        "Semicolon"      => ";",
+        "Quote"          => "'",
+        "Backquote"      => "`",
+        "Backslash"      => "\\",
+        "NumpadAdd"      => "NumPad+",
+        "NumpadSubtract" => "NumPad-",
+        _                => return None,
+    };
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spigot_stream::Constant;
+
+    fn stage() -> Stage {
+        Stage::new(SpigotConfig::new(Constant::Pi, 10), SpigotConfig::new(Constant::E, 10),
+                   LayoutMode::Flat, 1280, 720)
+    }
+
+    #[test]
+    fn names_keys_by_their_place() {
+        assert_eq!(key_name("KeyA").as_deref(), Some("A"));
+        assert_eq!(key_name("Digit7").as_deref(), Some("7"));
+        assert_eq!(key_name("ArrowLeft").as_deref(), Some("Left"));
+        assert_eq!(key_name("BracketRight").as_deref(), Some("]"));
+        assert_eq!(key_name("NumpadAdd").as_deref(), Some("NumPad+"));
+        assert_eq!(key_name("F5").as_deref(), Some("F5"));
+        assert_eq!(key_name("Fn"), None);
+        assert_eq!(key_name("ShiftLeft"), None);
+    }
+
+    #[test]
+    fn keys_pull_the_ribbons() {
+        let mut s = stage();
+        assert!(s.key_down("KeyA", false, false, false));
+        assert!(s.key_down("KeyD", true, false, false));
+        assert_eq!((s.dual.left_pos(), s.dual.right_pos()), (1, 5));
+        // Held keys repeat only for the actions that do.
+        assert!(s.key_down("KeyA", false, false, true));
+        assert!(s.key_down("KeyT", false, false, true));
+        assert_eq!((s.dual.left_pos(), s.dual.right_pos()), (2, 5));
+        // Desktop-only and unbound keys are left to the browser.
+        assert!(!s.key_down("KeyQ", false, false, false));
+        assert!(!s.key_down("KeyZ", false, true, false));
+        assert!(!s.key_down("KeyK", false, false, false));
+    }
+
+    #[test]
+    fn playing_sends_notes_ahead_in_time() {
+        let mut s = stage();
+        s.gesture(GestureEvent::Clap);
+        s.advance(1000.0);
+        let midi = s.take_midi();
+        assert_eq!(midi.messages[0], (0.0, vec![0xC0, 0]));
+
+        // Each pair of π and e, lengths from the left digit, pitches from the right.
+        let mut dual = DualStream::from_configs(SpigotConfig::new(Constant::Pi, 10), SpigotConfig::new(Constant::E, 10));
+        let (pitch_map, duration_map) = (PitchMap::major(60), DurationMap::musical(TPQ));
+        let mut at = 1000.0;
+        for note in midi.messages[1..].chunks(2) {
+            let (left, right) = dual.zip_next().unwrap();
+            let pitch = pitch_map.note_for(right);
+            assert_eq!(note[0], (at, vec![0x90, pitch, 100]));
+            assert_eq!(note[1].1, vec![0x80, pitch, 0]);
+            at += ms(perform::ticks_to_duration(duration_map.ticks_for(left), TPQ, 120));
+            assert!(note[1].0 < at);
+        }
+        assert!(at >= 1000.0 + LOOKAHEAD_MS);
+        // Nothing more until the next note is near.
+        s.advance(1001.0);
+        assert!(s.take_midi().messages.is_empty());
+        assert!(s.status().starts_with("♪"));
+    }
+
+    #[test]
+    fn stopping_releases_what_was_sent() {
+        let mut s = stage();
+        s.gesture(GestureEvent::Clap);
+        s.advance(0.0);
+        s.take_midi();
+        s.gesture(GestureEvent::Unclap);
+        let midi = s.take_midi();
+        assert!(midi.clear);
+        assert!(!midi.messages.is_empty());
+        assert!(midi.messages.iter().all(|(at, m)| *at == 0.0 && m[0] == 0x80));
+        s.advance(5000.0);
+        assert!(s.take_midi().messages.is_empty());
+    }
+
+    #[test]
+    fn snips_splice_back_in() {
+        let mut s = stage();
+        s.gesture(GestureEvent::PullLeft { steps: 10, velocity: 0.5 });
+        s.key_down("KeyS", false, false, false);
+        assert_eq!(s.perf.tray.entries.len(), 1);
+        assert_eq!(s.perf.tray.entries[0].name, "snip 1");
+        assert!(s.dual.get_snippet("snip 1").is_some());
+
+        s.gesture(GestureEvent::Splice);
+        assert_eq!(s.dual.spliced_len(), s.perf.tray.entries[0].patches.len());
+    }
+
+    #[test]
+    fn draws_a_frame_the_size_asked() {
+        let mut s = stage();
+        let (pixels, w, h) = s.draw();
+        assert_eq!((w, h), (1280, 720));
+        assert_eq!(pixels.len(), w * h);
+        s.resize(800, 600);
+        let (_, w, h) = s.draw();
+        assert_eq!((w, h), (800, 600));
+        // A click on a flat ribbon patch inspects it.
+        s.click(10, 100);
+        assert!(s.status().starts_with("LEFT"), "{}", s.status());
+    }
+}
@@ -0,0 +1,120 @@
+#ifndef SPIGOT_MIDI_H
+#define SPIGOT_MIDI_H
+
+/* Generated by cbindgen from src/ffi.rs — do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// π
+#define SPIGOT_PI 0
+
+// e
+#define SPIGOT_E 1
+
+// ln 2
+#define SPIGOT_LN2 2
+
+// Liouville's constant
+#define SPIGOT_LIOUVILLE 3
+
+// Champernowne's constant
+#define SPIGOT_CHAMPERNOWNE 4
+
+// Prouhet–Thue–Morse constant
+#define SPIGOT_THUE_MORSE 5
+
+// √2
+#define SPIGOT_SQRT2 6
+
+// φ, the golden ratio
+#define SPIGOT_PHI 7
+
+// Opaque handle to a pair of streams.
+typedef struct SpigotDual SpigotDual;
+
+// How [`spigot_compose`] turns digit pairs into notes.
+typedef struct SpigotMidiOptions {
+  // Notes to compose (one digit pair each).
+  uint32_t notes;
+  uint32_t tempo_bpm;
+  // General MIDI program, 0–127.
+  uint8_t program;
+  // MIDI note for digit 0 of the scale.
+  uint8_t root;
+  uint8_t velocity;
+  // Channel 0–15.
+  uint8_t channel;
+  uint16_t ticks_per_quarter;
+  // Scale name (`"major"`, `"dorian"`, `"whole-tone"`, …); NULL for
+  // major.
+  const char *scale;
+} SpigotMidiOptions;
+
+// 64 notes of piano in C major from middle C at 120 BPM.
+struct SpigotMidiOptions spigot_midi_options_default(void);
+
+// A new pair of streams: `left` (durations) and `right` (pitches), each a
+// `SPIGOT_*` constant written in a base from 2 to 36.  NULL if either is
+// out of range.  Free it with [`spigot_dual_free`].
+struct SpigotDual *spigot_dual_new(uint32_t left,
+                                   uint8_t left_base,
+                                   uint32_t right,
+                                   uint8_t right_base);
+
+// Free a stream pair.  NULL is ignored.
+//
+// # Safety
+// `ds` must be NULL or a handle from [`spigot_dual_new`] not yet freed.
+void spigot_dual_free(struct SpigotDual *ds);
+
+// Pull the next `n` digits of the left stream into `out`.  Returns the
+// number written (0 if `ds` or `out` is NULL).
+//
+// # Safety
+// `ds` must be a live handle and `out` valid for `n` bytes.
+size_t spigot_dual_pull_left(struct SpigotDual *ds, uint8_t *out, size_t n);
+
+// Pull the next `n` digits of the right stream into `out`.  Returns the
+// number written.
+//
+// # Safety
+// As [`spigot_dual_pull_left`].
+size_t spigot_dual_pull_right(struct SpigotDual *ds, uint8_t *out, size_t n);
+
+// Pull the next `n` digit pairs, left digits into `left` and right ones
+// into `right`.  Returns the number of pairs written.
+//
+// # Safety
+// `ds` must be a live handle, and `left` and `right` valid for `n` bytes.
+size_t spigot_dual_zip(struct SpigotDual *ds, uint8_t *left, uint8_t *right, size_t n);
+
+// Digits pulled so far from the left stream (0 for NULL).
+//
+// # Safety
+// `ds` must be NULL or a live handle.
+size_t spigot_dual_left_pos(const struct SpigotDual *ds);
+
+// Digits pulled so far from the right stream (0 for NULL).
+//
+// # Safety
+// `ds` must be NULL or a live handle.
+size_t spigot_dual_right_pos(const struct SpigotDual *ds);
+
+// Compose `opts->notes` notes from the next digit pairs of `ds` as a
+// standard MIDI file into `buf`.
+//
+// Returns the file's length.  If that is more than `cap` (say `buf` is
+// NULL and `cap` 0), nothing is written and the streams don't move, so
+// the call can be repeated with a buffer big enough.  Returns -1 if `ds`
+// or `opts` is NULL or an option is out of range.
+//
+// # Safety
+// `ds` must be a live handle, `opts` valid with `scale` NULL or a
+// NUL-terminated string, and `buf` valid for `cap` bytes.
+ptrdiff_t spigot_compose(struct SpigotDual *ds,
+                         const struct SpigotMidiOptions *opts,
+                         uint8_t *buf,
+                         size_t cap);
+
+#endif  /* SPIGOT_MIDI_H */
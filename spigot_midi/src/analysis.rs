@@ -0,0 +1,150 @@
+//! Summary statistics for a composed [`MidiTrack`].
+//!
+//! [`MidiTrack::analysis`] gives a quick picture of a piece — its length,
+//! range, pitch and rhythm distributions — without opening it in a DAW.
+//! The [`Display`](fmt::Display) impl prints a short text report.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::MidiTrack;
+
+/// Report returned by [`MidiTrack::analysis`].
+///
+/// Tied notes are counted once, with their combined length.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Analysis {
+    /// Number of sounding notes.
+    pub notes:           usize,
+    pub ticks:           u64,
+    pub seconds:         f64,
+    /// Lowest and highest pitch, or `None` for an empty track.
+    pub ambitus:         Option<(u8, u8)>,
+    pub mean_velocity:   f64,
+    /// Pitch → number of notes.
+    pub pitch_histogram: BTreeMap<u8, usize>,
+    /// Note length in ticks → number of notes.
+    pub note_values:     BTreeMap<u32, usize>,
+    ticks_per_quarter:   u16,
+}
+
+impl MidiTrack {
+    /// Summarise the track: length, ambitus, mean velocity, and pitch and
+    /// note-value distributions.
+    pub fn analysis(&self) -> Analysis {
+        let mut pitch_histogram = BTreeMap::new();
+        let mut note_values     = BTreeMap::new();
+        let mut velocity_sum    = 0u64;
+        let mut notes           = 0usize;
+        let mut held            = false;
+        let mut chain: u32      = 0;
+
+        for (i, note) in self.notes.iter().enumerate() {
+            if note.rest { continue; }
+            let continuation = held;
+            held = self.ties_into_next(i);
+            chain = if continuation { chain.saturating_add(note.duration) } else { note.duration };
+            if !continuation {
+                notes += 1;
+                velocity_sum += note.velocity as u64;
+                *pitch_histogram.entry(note.pitch).or_insert(0) += 1;
+            }
+            if !held {
+                *note_values.entry(chain).or_insert(0) += 1;
+            }
+        }
+
+        let ticks = self.total_ticks();
+        Analysis {
+            notes,
+            ticks,
+            seconds: self.seconds_at(ticks),
+            ambitus: pitch_histogram.keys().next()
+                .zip(pitch_histogram.keys().next_back())
+                .map(|(&lo, &hi)| (lo, hi)),
+            mean_velocity: if notes == 0 { 0.0 } else { velocity_sum as f64 / notes as f64 },
+            pitch_histogram,
+            note_values,
+            ticks_per_quarter: self.ticks_per_quarter,
+        }
+    }
+}
+
+/// Scientific pitch name, middle C (60) = `C4`.
+fn pitch_name(pitch: u8) -> String {
+    const NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    format!("{}{}", NAMES[pitch as usize % 12], pitch as i32 / 12 - 1)
+}
+
+/// Note-value name for `ticks`, using the [`DurationMap::parse`](crate::DurationMap::parse)
+/// letters, or a tick count if it is not a plain, dotted or triplet value.
+fn value_name(ticks: u32, tpq: u16) -> String {
+    let whole = tpq as u32 * 4;
+    for (letter, div) in [("w", 1), ("h", 2), ("q", 4), ("e", 8), ("s", 16), ("32", 32), ("64", 64)] {
+        if !whole.is_multiple_of(div) { continue; }
+        let plain = whole / div;
+        if ticks == plain { return letter.to_string(); }
+        if plain.is_multiple_of(2) && ticks == plain * 3 / 2 { return format!("{}.", letter); }
+        if plain.is_multiple_of(3) && ticks == plain * 2 / 3 { return format!("{}t", letter); }
+    }
+    format!("{} ticks", ticks)
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} notes, {} ticks ({:.1} s)", self.notes, self.ticks, self.seconds)?;
+        if let Some((lo, hi)) = self.ambitus {
+            writeln!(f, "ambitus: {}–{} ({} semitones)", pitch_name(lo), pitch_name(hi), hi - lo)?;
+        }
+        writeln!(f, "mean velocity: {:.1}", self.mean_velocity)?;
+
+        let widest = self.pitch_histogram.values().copied().max().unwrap_or(1);
+        for (&pitch, &count) in self.pitch_histogram.iter().rev() {
+            let bar = "█".repeat((count * 30).div_ceil(widest));
+            writeln!(f, "  {:>4} {:<30} {}", pitch_name(pitch), bar, count)?;
+        }
+
+        let values: Vec<String> = self.note_values.iter()
+            .map(|(&t, &n)| format!("{} ×{}", value_name(t, self.ticks_per_quarter), n))
+            .collect();
+        write!(f, "note values: {}", values.join(", "))
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MidiComposer;
+
+    #[test]
+    fn counts_pitches_values_and_ties() {
+        // quarter C, dotted-quarter E, quarter C
+        let mut track = MidiComposer::from_pairs(&[(5, 0), (6, 2), (5, 0)])
+            .velocity(90)
+            .compose(3)
+            .unwrap();
+        track.notes[0].tied = true;
+        track.notes[1].pitch = 60;          // C tied into C sounds as one note
+
+        let a = track.analysis();
+        assert_eq!(a.notes, 2);
+        assert_eq!(a.ticks, 480 + 720 + 480);
+        assert_eq!(a.ambitus, Some((60, 60)));
+        assert_eq!(a.mean_velocity, 90.0);
+        assert_eq!(a.note_values, BTreeMap::from([(480, 1), (1200, 1)]));
+        assert!(a.to_string().contains("q ×1"));
+    }
+
+    #[test]
+    fn names_pitches_and_values() {
+        assert_eq!(pitch_name(60), "C4");
+        assert_eq!(pitch_name(69), "A4");
+        assert_eq!(value_name(720, 480), "q.");
+        assert_eq!(value_name(160, 480), "et");
+        assert_eq!(value_name(100, 480), "100 ticks");
+    }
+}
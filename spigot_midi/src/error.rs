@@ -0,0 +1,64 @@
+//! The error type of the library.
+//!
+//! [`MidiError`] says which kind of thing went wrong — a bad parameter, a
+//! stream that gave out, a file that can't be read or parsed, or a MIDI
+//! device — so callers can tell a typo in a scale name from a missing
+//! synthesiser.
+
+use std::fmt;
+use std::io;
+
+use dual_spigot::SpigotError;
+
+/// What went wrong in a composer, form or playback call.
+#[derive(Debug)]
+pub enum MidiError {
+    /// An argument out of range or a name that doesn't parse.
+    Parameter(String),
+    /// A stream call failed, e.g. no snippet by that name.
+    Stream(SpigotError),
+    /// The stream or filter left nothing to compose.
+    NoNotes(&'static str),
+    /// Reading or writing a file.
+    Io(io::Error),
+    /// A MIDI file that doesn't parse.
+    Format(String),
+    /// Opening or sending to a MIDI port.
+    Device(String),
+}
+
+impl MidiError {
+    /// A [`Parameter`](MidiError::Parameter) error.
+    pub(crate) fn param(msg: impl Into<String>) -> Self { MidiError::Parameter(msg.into()) }
+}
+
+impl fmt::Display for MidiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MidiError::Parameter(msg) => f.write_str(msg),
+            MidiError::Stream(e)      => write!(f, "{}", e),
+            MidiError::NoNotes(why)   => f.write_str(why),
+            MidiError::Io(e)          => write!(f, "{}", e),
+            MidiError::Format(msg)    => write!(f, "MIDI file: {}", msg),
+            MidiError::Device(msg)    => write!(f, "MIDI device: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MidiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MidiError::Stream(e) => Some(e),
+            MidiError::Io(e)     => Some(e),
+            _                    => None,
+        }
+    }
+}
+
+impl From<SpigotError> for MidiError {
+    fn from(e: SpigotError) -> Self { MidiError::Stream(e) }
+}
+
+impl From<io::Error> for MidiError {
+    fn from(e: io::Error) -> Self { MidiError::Io(e) }
+}
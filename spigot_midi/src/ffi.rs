@@ -0,0 +1,298 @@
+//! C interface, for embedding the engine in other hosts (Max/MSP externals,
+//! Unity plugins, …).
+//!
+//! The crate builds as a `cdylib` alongside the Rust library; the matching
+//! header is `include/spigot_midi.h`, generated from this module with
+//! `cbindgen --config cbindgen.toml --output include/spigot_midi.h`.
+//!
+//! ```c
+//! SpigotDual *ds = spigot_dual_new(SPIGOT_PI, 10, SPIGOT_E, 10);
+//! uint8_t digits[16];
+//! spigot_dual_pull_left(ds, digits, 16);          // skip 16 digits of π
+//!
+//! SpigotMidiOptions opts = spigot_midi_options_default();
+//! opts.notes = 64;
+//! opts.scale = "dorian";
+//! intptr_t len = spigot_compose(ds, &opts, NULL, 0);     // size needed
+//! uint8_t *smf = malloc(len);
+//! spigot_compose(ds, &opts, smf, len);            // a standard MIDI file
+//! spigot_dual_free(ds);
+//! ```
+//!
+//! Stream handles are not thread-safe: use each from one thread at a time.
+
+use std::ffi::{c_char, CStr};
+use std::ptr;
+
+use dual_spigot::{DualStream, SpigotConfig};
+use spigot_stream::Constant;
+
+use crate::{DurationMap, MidiComposer, PitchMap, Scale};
+
+/// Opaque handle to a pair of streams.
+pub struct SpigotDual(DualStream);
+
+/// π
+pub const SPIGOT_PI: u32 = 0;
+/// e
+pub const SPIGOT_E: u32 = 1;
+/// ln 2
+pub const SPIGOT_LN2: u32 = 2;
+/// Liouville's constant
+pub const SPIGOT_LIOUVILLE: u32 = 3;
+/// Champernowne's constant
+pub const SPIGOT_CHAMPERNOWNE: u32 = 4;
+/// Prouhet–Thue–Morse constant
+pub const SPIGOT_THUE_MORSE: u32 = 5;
+/// √2
+pub const SPIGOT_SQRT2: u32 = 6;
+/// φ, the golden ratio
+pub const SPIGOT_PHI: u32 = 7;
+
+fn constant(n: u32) -> Option<Constant> {
+    Some(match n {
+        SPIGOT_PI           => Constant::Pi,
+        SPIGOT_E            => Constant::E,
+        SPIGOT_LN2          => Constant::Ln2,
+        SPIGOT_LIOUVILLE    => Constant::Liouville,
+        SPIGOT_CHAMPERNOWNE => Constant::Champernowne,
+        SPIGOT_THUE_MORSE   => Constant::ThueMorse,
+        SPIGOT_SQRT2        => Constant::Sqrt2,
+        SPIGOT_PHI          => Constant::Phi,
+        _                   => return None,
+    })
+}
+
+/// How [`spigot_compose`] turns digit pairs into notes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SpigotMidiOptions {
+    /// Notes to compose (one digit pair each).
+    pub notes:             u32,
+    pub tempo_bpm:         u32,
+    /// General MIDI program, 0–127.
+    pub program:           u8,
+    /// MIDI note for digit 0 of the scale.
+    pub root:              u8,
+    pub velocity:          u8,
+    /// Channel 0–15.
+    pub channel:           u8,
+    pub ticks_per_quarter: u16,
+    /// Scale name (`"major"`, `"dorian"`, `"whole-tone"`, …); NULL for
+    /// major.
+    pub scale:             *const c_char,
+}
+
+/// 64 notes of piano in C major from middle C at 120 BPM.
+#[no_mangle]
+pub extern "C" fn spigot_midi_options_default() -> SpigotMidiOptions {
+    SpigotMidiOptions {
+        notes: 64, tempo_bpm: 120, program: 0, root: 60, velocity: 100, channel: 0,
+        ticks_per_quarter: 480, scale: ptr::null(),
+    }
+}
+
+/// A new pair of streams: `left` (durations) and `right` (pitches), each a
+/// `SPIGOT_*` constant written in a base from 2 to 36.  NULL if either is
+/// out of range.  Free it with [`spigot_dual_free`].
+#[no_mangle]
+pub extern "C" fn spigot_dual_new(left: u32, left_base: u8, right: u32, right_base: u8) -> *mut SpigotDual {
+    let config = |c: u32, base: u8| {
+        constant(c).filter(|_| (2..=36).contains(&base)).map(|c| SpigotConfig::new(c, base))
+    };
+    match (config(left, left_base), config(right, right_base)) {
+        (Some(l), Some(r)) => Box::into_raw(Box::new(SpigotDual(DualStream::from_configs(l, r)))),
+        _                  => ptr::null_mut(),
+    }
+}
+
+/// Free a stream pair.  NULL is ignored.
+///
+/// # Safety
+/// `ds` must be NULL or a handle from [`spigot_dual_new`] not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn spigot_dual_free(ds: *mut SpigotDual) {
+    if !ds.is_null() { drop(Box::from_raw(ds)); }
+}
+
+/// Pull the next `n` digits of the left stream into `out`.  Returns the
+/// number written (0 if `ds` or `out` is NULL).
+///
+/// # Safety
+/// `ds` must be a live handle and `out` valid for `n` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn spigot_dual_pull_left(ds: *mut SpigotDual, out: *mut u8, n: usize) -> usize {
+    match (ds.as_mut(), out.is_null()) {
+        (Some(ds), false) => fill(out, n, || ds.0.left().next()),
+        _                 => 0,
+    }
+}
+
+/// Pull the next `n` digits of the right stream into `out`.  Returns the
+/// number written.
+///
+/// # Safety
+/// As [`spigot_dual_pull_left`].
+#[no_mangle]
+pub unsafe extern "C" fn spigot_dual_pull_right(ds: *mut SpigotDual, out: *mut u8, n: usize) -> usize {
+    match (ds.as_mut(), out.is_null()) {
+        (Some(ds), false) => fill(out, n, || ds.0.right().next()),
+        _                 => 0,
+    }
+}
+
+/// Pull the next `n` digit pairs, left digits into `left` and right ones
+/// into `right`.  Returns the number of pairs written.
+///
+/// # Safety
+/// `ds` must be a live handle, and `left` and `right` valid for `n` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn spigot_dual_zip(ds: *mut SpigotDual, left: *mut u8, right: *mut u8, n: usize) -> usize {
+    let Some(SpigotDual(ds)) = ds.as_mut() else { return 0 };
+    if left.is_null() || right.is_null() { return 0; }
+    let mut written = 0;
+    while written < n {
+        let Some((l, r)) = ds.zip_next() else { break };
+        *left.add(written) = l;
+        *right.add(written) = r;
+        written += 1;
+    }
+    written
+}
+
+/// Digits pulled so far from the left stream (0 for NULL).
+///
+/// # Safety
+/// `ds` must be NULL or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn spigot_dual_left_pos(ds: *const SpigotDual) -> usize {
+    ds.as_ref().map_or(0, |ds| ds.0.left_pos())
+}
+
+/// Digits pulled so far from the right stream (0 for NULL).
+///
+/// # Safety
+/// `ds` must be NULL or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn spigot_dual_right_pos(ds: *const SpigotDual) -> usize {
+    ds.as_ref().map_or(0, |ds| ds.0.right_pos())
+}
+
+/// Compose `opts->notes` notes from the next digit pairs of `ds` as a
+/// standard MIDI file into `buf`.
+///
+/// Returns the file's length.  If that is more than `cap` (say `buf` is
+/// NULL and `cap` 0), nothing is written and the streams don't move, so
+/// the call can be repeated with a buffer big enough.  Returns -1 if `ds`
+/// or `opts` is NULL or an option is out of range.
+///
+/// # Safety
+/// `ds` must be a live handle, `opts` valid with `scale` NULL or a
+/// NUL-terminated string, and `buf` valid for `cap` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn spigot_compose(
+    ds:   *mut SpigotDual,
+    opts: *const SpigotMidiOptions,
+    buf:  *mut u8,
+    cap:  usize,
+) -> isize {
+    let (Some(SpigotDual(ds)), Some(opts)) = (ds.as_mut(), opts.as_ref()) else { return -1 };
+    let scale = match opts.scale.is_null() {
+        true  => Scale::major(),
+        false => match CStr::from_ptr(opts.scale).to_str().ok().and_then(|s| s.parse().ok()) {
+            Some(scale) => scale,
+            None        => return -1,
+        },
+    };
+    if opts.program > 127 || opts.root > 127 || opts.velocity > 127 || opts.channel > 15
+        || opts.tempo_bpm == 0 || opts.ticks_per_quarter == 0
+    {
+        return -1;
+    }
+
+    let start = ds.checkpoint();
+    let pairs = ds.zip_take(opts.notes as usize);
+    let track = MidiComposer::from_pairs(&pairs)
+        .tempo(opts.tempo_bpm)
+        .instrument_raw(opts.program)
+        .pitch_map(PitchMap::custom(opts.root, scale))
+        .ticks_per_quarter(opts.ticks_per_quarter)
+        .duration_map(DurationMap::musical(opts.ticks_per_quarter as u32))
+        .velocity(opts.velocity)
+        .channel(opts.channel)
+        .compose(pairs.len());
+    let Ok(track) = track else {
+        ds.rewind(&start);
+        return -1;
+    };
+    let bytes = track.to_bytes();
+    if bytes.len() > cap || buf.is_null() {
+        ds.rewind(&start);
+    } else {
+        ptr::copy_nonoverlapping(bytes.as_ptr(), buf, bytes.len());
+    }
+    bytes.len() as isize
+}
+
+/// Write up to `n` digits from `next` to `out`; returns how many.
+unsafe fn fill(out: *mut u8, n: usize, mut next: impl FnMut() -> Option<u8>) -> usize {
+    let mut written = 0;
+    while written < n {
+        let Some(d) = next() else { break };
+        *out.add(written) = d;
+        written += 1;
+    }
+    written
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pulls_digits_through_a_handle() {
+        assert!(spigot_dual_new(SPIGOT_PI, 10, 99, 10).is_null());
+        assert!(spigot_dual_new(SPIGOT_PI, 1, SPIGOT_E, 10).is_null());
+
+        let ds = spigot_dual_new(SPIGOT_CHAMPERNOWNE, 10, SPIGOT_THUE_MORSE, 2);
+        let mut digits = [0u8; 12];
+        unsafe {
+            assert_eq!(spigot_dual_pull_left(ds, digits.as_mut_ptr(), 12), 12);
+            assert_eq!(digits, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 1, 0]);
+            let (mut l, mut r) = ([0u8; 2], [0u8; 2]);
+            assert_eq!(spigot_dual_zip(ds, l.as_mut_ptr(), r.as_mut_ptr(), 2), 2);
+            assert_eq!(l, [1, 1]);
+            assert_eq!((spigot_dual_left_pos(ds), spigot_dual_right_pos(ds)), (14, 2));
+            assert_eq!(spigot_dual_pull_right(ptr::null_mut(), digits.as_mut_ptr(), 1), 0);
+            spigot_dual_free(ds);
+        }
+    }
+
+    #[test]
+    fn composes_into_the_callers_buffer() {
+        let ds = spigot_dual_new(SPIGOT_CHAMPERNOWNE, 10, SPIGOT_THUE_MORSE, 2);
+        let mut opts = spigot_midi_options_default();
+        opts.notes = 8;
+        opts.scale = c"dorian".as_ptr();
+        unsafe {
+            // Asking for the size leaves the streams where they were.
+            let len = spigot_compose(ds, &opts, ptr::null_mut(), 0);
+            assert!(len > 0);
+            assert_eq!(spigot_dual_left_pos(ds), 0);
+
+            let mut buf = vec![0u8; len as usize];
+            assert_eq!(spigot_compose(ds, &opts, buf.as_mut_ptr(), buf.len()), len);
+            assert_eq!(&buf[..4], b"MThd");
+            assert_eq!(spigot_dual_left_pos(ds), 8);
+
+            opts.scale = c"no such scale".as_ptr();
+            assert_eq!(spigot_compose(ds, &opts, buf.as_mut_ptr(), buf.len()), -1);
+            assert_eq!(spigot_compose(ptr::null_mut(), &opts, buf.as_mut_ptr(), 0), -1);
+            spigot_dual_free(ds);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Musical form — assembling a piece from named sections.
+//!
+//! A [`FormBuilder`] holds already-composed sections (each the result of its
+//! own [`MidiComposer`](crate::MidiComposer) call or snippet) and lays them
+//! out in a pattern such as `"AABA"` or `"ABACA"`.  A section that appears
+//! more than once repeats the same music.  Each section may set its own
+//! tempo and transposition; tempo changes are written as meta events where
+//! sections meet.
+
+use crate::{MidiError, MidiTrack, Note};
+
+struct Section {
+    name:      String,
+    /// One track per part; every section needs the same number of parts.
+    parts:     Vec<MidiTrack>,
+    tempo:     Option<f64>,
+    transpose: i8,
+}
+
+/// Builds a one- or multi-part piece from named sections.
+///
+/// ```rust,no_run
+/// use spigot_midi::{MidiComposer, PitchMap};
+/// use spigot_midi::form::FormBuilder;
+/// use dual_spigot::DualStream;
+/// use spigot_stream::Constant;
+///
+/// let a = MidiComposer::new(DualStream::new(Constant::Pi, Constant::E))
+///     .pitch_map(PitchMap::major(60)).compose_bars(4).unwrap();
+/// let b = MidiComposer::new(DualStream::new(Constant::E, Constant::Ln2))
+///     .pitch_map(PitchMap::minor(57)).compose_bars(4).unwrap();
+///
+/// let piece = FormBuilder::new()
+///     .section("A", a)
+///     .section("B", b)
+///     .section_tempo("B", 96)
+///     .pattern("AABA")
+///     .build()
+///     .unwrap();
+/// piece.write_file("aaba.mid").unwrap();
+/// ```
+#[derive(Default)]
+pub struct FormBuilder {
+    sections: Vec<Section>,
+    order:    Vec<String>,
+}
+
+impl FormBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define a single-part section.  Redefining a name replaces it.
+    pub fn section(self, name: &str, track: MidiTrack) -> Self {
+        self.section_parts(name, vec![track])
+    }
+
+    /// Define a section with one track per part, for multi-track output.
+    pub fn section_parts(mut self, name: &str, parts: Vec<MidiTrack>) -> Self {
+        self.sections.retain(|s| s.name != name);
+        self.sections.push(Section { name: name.to_string(), parts, tempo: None, transpose: 0 });
+        self
+    }
+
+    /// Play section `name` at `bpm` instead of the tempo it was composed at.
+    pub fn section_tempo<T: Into<f64>>(mut self, name: &str, bpm: T) -> Self {
+        if let Some(s) = self.sections.iter_mut().find(|s| s.name == name) {
+            s.tempo = Some(bpm.into());
+        }
+        self
+    }
+
+    /// Transpose section `name` by `semitones` (e.g. 7 for the dominant key).
+    pub fn section_transpose(mut self, name: &str, semitones: i8) -> Self {
+        if let Some(s) = self.sections.iter_mut().find(|s| s.name == name) {
+            s.transpose = semitones;
+        }
+        self
+    }
+
+    /// Order of sections.  `"AABA"` names one section per character; with
+    /// spaces or commas the pattern is split into words instead, so
+    /// `"intro verse chorus verse"` works too.
+    pub fn pattern(mut self, pattern: &str) -> Self {
+        self.order = if pattern.contains(|c: char| c.is_whitespace() || c == ',') {
+            pattern.split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|w| !w.is_empty())
+                .map(str::to_string)
+                .collect()
+        } else {
+            pattern.chars().map(String::from).collect()
+        };
+        self
+    }
+
+    /// Join the first part of every section into one track.
+    pub fn build(self) -> Result<MidiTrack, MidiError> {
+        let mut tracks = self.build_tracks()?;
+        Ok(tracks.swap_remove(0))
+    }
+
+    /// Join each part across sections, giving one track per part for
+    /// [`write_multi_track`](crate::write_multi_track).
+    pub fn build_tracks(self) -> Result<Vec<MidiTrack>, MidiError> {
+        if self.order.is_empty() {
+            return Err(MidiError::param("form pattern is empty"));
+        }
+        let order = self.order.iter()
+            .map(|name| self.sections.iter().find(|s| &s.name == name)
+                .ok_or_else(|| MidiError::param(format!("unknown section '{}' in form", name))))
+            .collect::<Result<Vec<&Section>, MidiError>>()?;
+
+        let n_parts = order[0].parts.len();
+        if n_parts == 0 {
+            return Err(MidiError::param(format!("section '{}' has no parts", order[0].name)));
+        }
+        let tpq = order[0].parts[0].ticks_per_quarter;
+        for s in &order {
+            if s.parts.len() != n_parts {
+                return Err(MidiError::param(format!("section '{}' has {} parts, expected {}",
+                    s.name, s.parts.len(), n_parts)));
+            }
+            if s.parts.iter().any(|p| p.ticks_per_quarter != tpq) {
+                return Err(MidiError::param(format!("section '{}' uses a different ticks-per-quarter", s.name)));
+            }
+        }
+
+        Ok((0..n_parts).map(|p| join_part(&order, p)).collect())
+    }
+}
+
+/// Concatenate part `p` of each section in `order`.
+fn join_part(order: &[&Section], p: usize) -> MidiTrack {
+    let first = &order[0].parts[p];
+    let any_pedal = order.iter().any(|s| !s.parts[p].pedal.is_empty());
+    let mut out = MidiTrack {
+        notes:             Vec::new(),
+        ticks_per_quarter: first.ticks_per_quarter,
+        tempo_bpm:         order[0].tempo.unwrap_or(first.tempo_bpm),
+        instrument:        first.instrument,
+        channel:           first.channel,
+        description:       first.description.clone(),
+        running_status:    first.running_status,
+        splits:            first.splits.clone(),
+        pedal:             Vec::new(),
+        bank:              first.bank,
+        tempo_changes:     Vec::new(),
+        time_signature:    first.time_signature,
+        // One section's recipe doesn't make the whole piece.
+        provenance:        None,
+    };
+
+    let mut tick: u64 = 0;
+    let mut bpm = out.tempo_bpm;
+    for s in order {
+        let part = &s.parts[p];
+        let section_bpm = s.tempo.unwrap_or(part.tempo_bpm);
+        if section_bpm != bpm {
+            out.tempo_changes.push((tick, section_bpm));
+            bpm = section_bpm;
+        }
+        // Changes inside a section keep their place; an overridden tempo
+        // replaces them.
+        if s.tempo.is_none() {
+            out.tempo_changes.extend(part.tempo_changes.iter().map(|&(at, b)| (tick + at, b)));
+            bpm = part.tempo_changes.last().map_or(bpm, |&(_, b)| b);
+        }
+
+        let start = out.notes.len();
+        out.notes.extend(part.notes.iter().map(|n| Note {
+            pitch: (n.pitch as i16 + s.transpose as i16).clamp(0, 127) as u8,
+            ..n.clone()
+        }));
+        // A tie must not run into the next section.
+        if let Some(last) = out.notes[start..].last_mut() {
+            last.tied = false;
+        }
+        if any_pedal {
+            out.pedal.extend((0..part.notes.len()).map(|i| part.pedal.get(i).copied().unwrap_or(false)));
+        }
+        tick += part.total_ticks();
+    }
+    out
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MidiComposer;
+
+    fn section(pairs: &[(u8, u8)], bpm: f64) -> MidiTrack {
+        MidiComposer::from_pairs(pairs).tempo(bpm).compose(pairs.len()).unwrap()
+    }
+
+    #[test]
+    fn aaba_repeats_and_changes_tempo() {
+        let a = section(&[(5, 0), (5, 2)], 120.0);
+        let b = section(&[(5, 4)], 120.0);
+        let a_ticks = a.total_ticks();
+        let piece = FormBuilder::new()
+            .section("A", a)
+            .section("B", b)
+            .section_tempo("B", 90)
+            .section_transpose("B", 7)
+            .pattern("AABA")
+            .build()
+            .unwrap();
+
+        let p: Vec<u8> = piece.notes.iter().map(|n| n.pitch).collect();
+        assert_eq!(p, vec![60, 64, 60, 64, 74, 60, 64]);
+        assert_eq!(piece.tempo_changes, vec![(2 * a_ticks, 90.0), (2 * a_ticks + 480, 120.0)]);
+        assert!(piece.seconds_at(piece.total_ticks()) > 3.5);
+        assert!(piece.is_valid());
+    }
+
+    #[test]
+    fn rejects_unknown_or_mismatched_sections() {
+        let err = FormBuilder::new()
+            .section("A", section(&[(5, 0)], 120.0))
+            .pattern("A B")
+            .build_tracks()
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("'B'"));
+
+        let err = FormBuilder::new()
+            .section_parts("A", vec![section(&[(5, 0)], 120.0), section(&[(5, 0)], 120.0)])
+            .section("B", section(&[(5, 0)], 120.0))
+            .pattern("AB")
+            .build_tracks()
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("1 parts"));
+    }
+}
@@ -7,6 +7,29 @@
 //! * **Right digit** → note **pitch**
 //!
 //! No external crates are required — MIDI bytes are written directly.
+//! A small built-in synthesizer ([`synth`]) can also render any track to a
+//! WAV file via [`MidiTrack::render_wav`], and [`MidiTrack::validate`]
+//! lints a track before it is sent to hardware; [`MidiTrack::analysis`]
+//! summarises its range and rhythm.  Longer pieces can be laid
+//! out from sections with [`form::FormBuilder`].
+//!
+//! With the `playback` feature (which pulls in `midir`), `MidiTrack::play`
+//! streams a track to a MIDI output port in real time.
+//!
+//! [`MidiTrack::to_ump_clip`] writes MIDI 2.0 instead: Universal MIDI
+//! Packets with 16-bit velocities, in a MIDI Clip File ([`ump`]).
+//!
+//! The digit-to-note maps are traits ([`PitchMapper`] and friends), so
+//! other crates can plug in their own; [`mapper::MapperRegistry`] picks
+//! one by name for a command line or config file.
+//!
+//! A [`Provenance`] embedded in the file records how to compose it again,
+//! byte for byte.  Pieces too long to hold in memory can be written as
+//! they are composed with [`MidiComposer::compose_streaming`].
+//! [`MidiTrack::from_bytes`] ([`reader`]) reads a file back into notes.
+//!
+//! The crate also builds as a C library ([`ffi`], header
+//! `include/spigot_midi.h`), so other hosts can pull digits and compose.
 //!
 //! ## Quick start
 //!
@@ -31,8 +54,29 @@
 //! midi.write_file("pi_e_major.mid").unwrap();
 //! ```
 
+use std::collections::VecDeque;
 use std::io::Write;
-use dual_spigot::{DualStream, SpigotConfig};
+use std::path::Path;
+use dual_spigot::{BoxedSpigot, DualStream, SpigotConfig, SpigotError};
+
+pub use error::MidiError;
+pub use mapper::{DurationMapper, PitchMapper, VelocityMapper};
+pub use provenance::Provenance;
+
+pub mod analysis;
+pub mod error;
+pub mod ffi;
+pub mod form;
+pub mod mapper;
+pub mod ornament;
+pub mod provenance;
+pub mod reader;
+mod streaming;
+#[cfg(feature = "playback")]
+pub mod playback;
+pub mod synth;
+pub mod ump;
+pub mod validate;
 
 // ════════════════════════════════════════════════════════════════════════════
 // General MIDI instrument numbers (Program 0–127)
@@ -192,56 +236,117 @@ pub enum GeneralMidi {
 }
 
 impl GeneralMidi {
+    /// Every program in order, so `ALL[p]` is program `p`.
+    pub const ALL: [GeneralMidi; 128] = [
+        Self::AcousticGrandPiano, Self::BrightAcousticPiano, Self::ElectricGrandPiano,
+        Self::HonkyTonkPiano, Self::ElectricPiano1, Self::ElectricPiano2,
+        Self::Harpsichord, Self::Clavinet, Self::Celesta, Self::Glockenspiel,
+        Self::MusicBox, Self::Vibraphone, Self::Marimba, Self::Xylophone,
+        Self::TubularBells, Self::Dulcimer, Self::DrawbarOrgan, Self::PercussiveOrgan,
+        Self::RockOrgan, Self::ChurchOrgan, Self::ReedOrgan, Self::Accordion,
+        Self::Harmonica, Self::TangoAccordion, Self::AcousticGuitarNylon,
+        Self::AcousticGuitarSteel, Self::ElectricGuitarJazz, Self::ElectricGuitarClean,
+        Self::ElectricGuitarMuted, Self::OverdrivenGuitar, Self::DistortionGuitar,
+        Self::GuitarHarmonics, Self::AcousticBass, Self::ElectricBassFinger,
+        Self::ElectricBassPick, Self::FretlessBass, Self::SlapBass1, Self::SlapBass2,
+        Self::SynthBass1, Self::SynthBass2, Self::Violin, Self::Viola, Self::Cello,
+        Self::Contrabass, Self::TremoloStrings, Self::PizzicatoStrings,
+        Self::OrchestralHarp, Self::Timpani, Self::StringEnsemble1,
+        Self::StringEnsemble2, Self::SynthStrings1, Self::SynthStrings2,
+        Self::ChoirAahs, Self::VoiceOohs, Self::SynthVoice, Self::OrchestraHit,
+        Self::Trumpet, Self::Trombone, Self::Tuba, Self::MutedTrumpet, Self::FrenchHorn,
+        Self::BrassSection, Self::SynthBrass1, Self::SynthBrass2, Self::SopranoSax,
+        Self::AltoSax, Self::TenorSax, Self::BaritoneSax, Self::Oboe, Self::EnglishHorn,
+        Self::Bassoon, Self::Clarinet, Self::Piccolo, Self::Flute, Self::Recorder,
+        Self::PanFlute, Self::BlownBottle, Self::Shakuhachi, Self::Whistle,
+        Self::Ocarina, Self::Lead1Square, Self::Lead2Sawtooth, Self::Lead3Calliope,
+        Self::Lead4Chiff, Self::Lead5Charang, Self::Lead6Voice, Self::Lead7Fifths,
+        Self::Lead8BassLead, Self::Pad1NewAge, Self::Pad2Warm, Self::Pad3Polysynth,
+        Self::Pad4Choir, Self::Pad5Bowed, Self::Pad6Metallic, Self::Pad7Halo,
+        Self::Pad8Sweep, Self::Fx1Rain, Self::Fx2Soundtrack, Self::Fx3Crystal,
+        Self::Fx4Atmosphere, Self::Fx5Brightness, Self::Fx6Goblins, Self::Fx7Echoes,
+        Self::Fx8Scifi, Self::Sitar, Self::Banjo, Self::Shamisen, Self::Koto,
+        Self::Kalimba, Self::BagPipe, Self::Fiddle, Self::Shanai, Self::TinkleBell,
+        Self::Agogo, Self::SteelDrums, Self::Woodblock, Self::TaikoDrum,
+        Self::MelodicTom, Self::SynthDrum, Self::ReverseCymbal, Self::GuitarFretNoise,
+        Self::BreathNoise, Self::Seashore, Self::BirdTweet, Self::TelephoneRing,
+        Self::Helicopter, Self::Applause, Self::Gunshot,
+    ];
+
     /// Raw MIDI program number (0–127).
     pub fn program(self) -> u8 { self as u8 }
 
+    /// Instrument for a raw program number, or `None` above 127.
+    pub fn from_program(program: u8) -> Option<Self> {
+        Self::ALL.get(program as usize).copied()
+    }
+
     /// Human-readable name.
     pub fn name(self) -> &'static str {
-        match self {
-            GeneralMidi::AcousticGrandPiano  => "Acoustic Grand Piano",
-            GeneralMidi::BrightAcousticPiano => "Bright Acoustic Piano",
-            GeneralMidi::ElectricGrandPiano  => "Electric Grand Piano",
-            GeneralMidi::HonkyTonkPiano      => "Honky-Tonk Piano",
-            GeneralMidi::ElectricPiano1      => "Electric Piano 1",
-            GeneralMidi::ElectricPiano2      => "Electric Piano 2",
-            GeneralMidi::Harpsichord         => "Harpsichord",
-            GeneralMidi::Clavinet            => "Clavinet",
-            GeneralMidi::Celesta             => "Celesta",
-            GeneralMidi::Glockenspiel        => "Glockenspiel",
-            GeneralMidi::MusicBox            => "Music Box",
-            GeneralMidi::Vibraphone          => "Vibraphone",
-            GeneralMidi::Marimba             => "Marimba",
-            GeneralMidi::Xylophone           => "Xylophone",
-            GeneralMidi::TubularBells        => "Tubular Bells",
-            GeneralMidi::Dulcimer            => "Dulcimer",
-            GeneralMidi::Violin              => "Violin",
-            GeneralMidi::Viola               => "Viola",
-            GeneralMidi::Cello               => "Cello",
-            GeneralMidi::Trumpet             => "Trumpet",
-            GeneralMidi::Trombone            => "Trombone",
-            GeneralMidi::FrenchHorn          => "French Horn",
-            GeneralMidi::AltoSax             => "Alto Sax",
-            GeneralMidi::TenorSax            => "Tenor Sax",
-            GeneralMidi::Flute               => "Flute",
-            GeneralMidi::Clarinet            => "Clarinet",
-            GeneralMidi::Oboe                => "Oboe",
-            GeneralMidi::AcousticGuitarNylon => "Acoustic Guitar (nylon)",
-            GeneralMidi::AcousticGuitarSteel => "Acoustic Guitar (steel)",
-            GeneralMidi::ElectricGuitarJazz  => "Electric Guitar (jazz)",
-            GeneralMidi::OverdrivenGuitar    => "Overdriven Guitar",
-            GeneralMidi::DistortionGuitar    => "Distortion Guitar",
-            GeneralMidi::AcousticBass        => "Acoustic Bass",
-            GeneralMidi::ElectricBassFinger  => "Electric Bass (finger)",
-            GeneralMidi::SynthBass1          => "Synth Bass 1",
-            GeneralMidi::Pad1NewAge          => "Pad 1 (New Age)",
-            GeneralMidi::Pad2Warm            => "Pad 2 (Warm)",
-            GeneralMidi::Pad4Choir           => "Pad 4 (Choir)",
-            GeneralMidi::Lead1Square         => "Lead 1 (Square)",
-            GeneralMidi::Lead2Sawtooth       => "Lead 2 (Sawtooth)",
-            GeneralMidi::Kalimba             => "Kalimba",
-            GeneralMidi::Sitar               => "Sitar",
-            GeneralMidi::SteelDrums          => "Steel Drums",
-            _                                => "General MIDI Instrument",
+        GM_NAMES[self as usize]
+    }
+}
+
+/// Standard General MIDI Level 1 program names, indexed by program number.
+const GM_NAMES: [&str; 128] = [
+    "Acoustic Grand Piano", "Bright Acoustic Piano", "Electric Grand Piano",
+    "Honky-Tonk Piano", "Electric Piano 1", "Electric Piano 2", "Harpsichord",
+    "Clavinet", "Celesta", "Glockenspiel", "Music Box", "Vibraphone", "Marimba",
+    "Xylophone", "Tubular Bells", "Dulcimer", "Drawbar Organ", "Percussive Organ",
+    "Rock Organ", "Church Organ", "Reed Organ", "Accordion", "Harmonica",
+    "Tango Accordion", "Acoustic Guitar (nylon)", "Acoustic Guitar (steel)",
+    "Electric Guitar (jazz)", "Electric Guitar (clean)", "Electric Guitar (muted)",
+    "Overdriven Guitar", "Distortion Guitar", "Guitar Harmonics", "Acoustic Bass",
+    "Electric Bass (finger)", "Electric Bass (pick)", "Fretless Bass", "Slap Bass 1",
+    "Slap Bass 2", "Synth Bass 1", "Synth Bass 2", "Violin", "Viola", "Cello",
+    "Contrabass", "Tremolo Strings", "Pizzicato Strings", "Orchestral Harp", "Timpani",
+    "String Ensemble 1", "String Ensemble 2", "Synth Strings 1", "Synth Strings 2",
+    "Choir Aahs", "Voice Oohs", "Synth Voice", "Orchestra Hit", "Trumpet", "Trombone",
+    "Tuba", "Muted Trumpet", "French Horn", "Brass Section", "Synth Brass 1",
+    "Synth Brass 2", "Soprano Sax", "Alto Sax", "Tenor Sax", "Baritone Sax", "Oboe",
+    "English Horn", "Bassoon", "Clarinet", "Piccolo", "Flute", "Recorder", "Pan Flute",
+    "Blown Bottle", "Shakuhachi", "Whistle", "Ocarina", "Lead 1 (Square)",
+    "Lead 2 (Sawtooth)", "Lead 3 (Calliope)", "Lead 4 (Chiff)", "Lead 5 (Charang)",
+    "Lead 6 (Voice)", "Lead 7 (Fifths)", "Lead 8 (Bass+Lead)", "Pad 1 (New Age)",
+    "Pad 2 (Warm)", "Pad 3 (Polysynth)", "Pad 4 (Choir)", "Pad 5 (Bowed)",
+    "Pad 6 (Metallic)", "Pad 7 (Halo)", "Pad 8 (Sweep)", "FX 1 (Rain)",
+    "FX 2 (Soundtrack)", "FX 3 (Crystal)", "FX 4 (Atmosphere)", "FX 5 (Brightness)",
+    "FX 6 (Goblins)", "FX 7 (Echoes)", "FX 8 (Sci-Fi)", "Sitar", "Banjo", "Shamisen",
+    "Koto", "Kalimba", "Bagpipe", "Fiddle", "Shanai", "Tinkle Bell", "Agogo",
+    "Steel Drums", "Woodblock", "Taiko Drum", "Melodic Tom", "Synth Drum",
+    "Reverse Cymbal", "Guitar Fret Noise", "Breath Noise", "Seashore", "Bird Tweet",
+    "Telephone Ring", "Helicopter", "Applause", "Gunshot",
+];
+
+/// Lower-case alphanumerics only, so "Lead 2 (Sawtooth)" ~ "lead2sawtooth".
+fn normalize_name(s: &str) -> String {
+    s.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+impl std::str::FromStr for GeneralMidi {
+    type Err = MidiError;
+
+    /// Parse a program number (`"11"`), a full name (`"Vibraphone"`), or an
+    /// unambiguous prefix of one (`"lead 2"`).  Case, spaces and punctuation
+    /// are ignored.
+    fn from_str(s: &str) -> Result<Self, MidiError> {
+        if let Ok(p) = s.trim().parse::<u8>() {
+            return Self::from_program(p)
+                .ok_or_else(|| MidiError::param(format!("program {} out of range 0–127", p)));
+        }
+        let key = normalize_name(s);
+        if key.is_empty() { return Err(MidiError::param("empty instrument name")); }
+
+        let names: Vec<String> = GM_NAMES.iter().map(|n| normalize_name(n)).collect();
+        if let Some(i) = names.iter().position(|n| *n == key) {
+            return Ok(Self::ALL[i]);
+        }
+        let hits: Vec<usize> = (0..128).filter(|&i| names[i].starts_with(&key)).collect();
+        match hits.as_slice() {
+            [i] => Ok(Self::ALL[*i]),
+            []  => Err(MidiError::param(format!("unknown instrument '{}'", s.trim()))),
+            _   => Err(MidiError::param(format!("ambiguous instrument '{}': {}", s.trim(),
+                       hits.iter().map(|&i| GM_NAMES[i]).collect::<Vec<_>>().join(", ")))),
         }
     }
 }
@@ -313,6 +418,28 @@ impl Scale {
     /// Number of pitches in the scale.
     pub fn len(&self) -> usize { self.intervals.len() }
     pub fn is_empty(&self) -> bool { self.intervals.is_empty() }
+
+    /// Every named scale, in menu order.
+    pub fn all() -> Vec<Scale> {
+        vec![
+            Self::major(), Self::minor(), Self::pentatonic_major(), Self::pentatonic_minor(),
+            Self::dorian(), Self::phrygian(), Self::lydian(), Self::mixolydian(),
+            Self::whole_tone(), Self::diminished(), Self::chromatic(),
+        ]
+    }
+}
+
+impl std::str::FromStr for Scale {
+    type Err = MidiError;
+
+    /// Parse a scale name such as `"dorian"` or `"whole-tone"`.  Case,
+    /// spaces and punctuation are ignored.
+    fn from_str(s: &str) -> Result<Self, MidiError> {
+        let key = normalize_name(s);
+        Self::all().into_iter()
+            .find(|scale| normalize_name(scale.name) == key)
+            .ok_or_else(|| MidiError::param(format!("unknown scale '{}'", s.trim())))
+    }
 }
 
 // ════════════════════════════════════════════════════════════════════════════
@@ -392,6 +519,24 @@ impl PitchMap {
         let note     = self.root as usize + octave * 12 + semitone;
         note.min(127) as u8
     }
+
+    /// Scale degree (0-based) of `pitch`, or `None` if it is not in the scale.
+    pub fn degree_of(&self, pitch: u8) -> Option<usize> {
+        let rel = (pitch as i32 - self.root as i32).rem_euclid(12) as u8;
+        self.scale.intervals.iter().position(|&i| i % 12 == rel)
+    }
+
+    /// Nearest scale tone strictly above (`up`) or below `pitch`, clamped
+    /// to 0–127.  Falls back to a semitone step if the scale is empty.
+    pub fn neighbor(&self, pitch: u8, up: bool) -> u8 {
+        let step: i32 = if up { 1 } else { -1 };
+        for k in 1..=12 {
+            let p = pitch as i32 + step * k;
+            if !(0..=127).contains(&p) { break; }
+            if self.degree_of(p as u8).is_some() { return p as u8; }
+        }
+        (pitch as i32 + step).clamp(0, 127) as u8
+    }
 }
 
 // ════════════════════════════════════════════════════════════════════════════
@@ -409,6 +554,7 @@ impl PitchMap {
 /// * [`DurationMap::fixed`] — every digit maps to the same duration
 ///   (useful for rhythmically uniform output).
 /// * [`DurationMap::custom`] — provide your own lookup table.
+/// * [`DurationMap::parse`] — build a table from note-value notation.
 #[derive(Clone, Debug)]
 pub struct DurationMap {
     /// Ticks per entry (indexed by digit value).
@@ -470,6 +616,30 @@ impl DurationMap {
         DurationMap { table, name: "Custom" }
     }
 
+    /// Build a table from whitespace-separated note values, one per digit.
+    ///
+    /// Each token is a note value — `w h q e s` or `1 2 4 8 16 32 64` —
+    /// optionally followed by dots (`q.` = dotted quarter, `h..`) and/or a
+    /// `t` for a triplet (`8t`).  Values joined with `+` are tied:
+    /// `"h+8"` is a half tied to an eighth.
+    ///
+    /// ```rust
+    /// use spigot_midi::DurationMap;
+    /// let dm = DurationMap::parse("8 8 q q. h", 480).unwrap();
+    /// assert_eq!(dm.table, vec![240, 240, 480, 720, 960]);
+    /// ```
+    pub fn parse(spec: &str, ticks_per_quarter: u32) -> Result<Self, MidiError> {
+        let table = spec.split_whitespace()
+            .map(|tok| {
+                tok.split('+')
+                    .map(|part| note_value_ticks(part, ticks_per_quarter))
+                    .sum::<Result<u32, MidiError>>()
+            })
+            .collect::<Result<Vec<u32>, MidiError>>()?;
+        if table.is_empty() { return Err(MidiError::param("empty duration spec")); }
+        Ok(DurationMap { table, name: "Parsed" })
+    }
+
     /// Ticks for digit `d`; wraps if `d >= table.len()`.
     pub fn ticks_for(&self, d: u8) -> u32 {
         if self.table.is_empty() { return 120; }
@@ -477,19 +647,281 @@ impl DurationMap {
     }
 }
 
+/// Ticks for one note-value token such as `q`, `8.`, `16t` (no ties).
+fn note_value_ticks(tok: &str, tpq: u32) -> Result<u32, MidiError> {
+    let body    = tok.trim_end_matches(['.', 't']);
+    let suffix  = &tok[body.len()..];
+    let dots    = suffix.matches('.').count() as u32;
+    let triplet = suffix.contains('t');
+
+    // Length in 64ths of a whole note, so dotted values stay integral.
+    let sixty_fourths: u32 = match body {
+        "w" | "1"  => 64,
+        "h" | "2"  => 32,
+        "q" | "4"  => 16,
+        "e" | "8"  => 8,
+        "s" | "16" => 4,
+        "32"       => 2,
+        "64"       => 1,
+        _ => return Err(MidiError::param(format!("unknown note value '{}'", tok))),
+    };
+    if suffix.matches('t').count() > 1 || dots > 6 {
+        return Err(MidiError::param(format!("malformed note value '{}'", tok)));
+    }
+    // Each dot adds half of the previous addition: 1 + 1/2 + 1/4 …
+    let scale = (1u64 << (dots + 1)) - 1;
+    let mut ticks = sixty_fourths as u64 * tpq as u64 * scale / (16 << dots);
+    if triplet { ticks = ticks * 2 / 3; }
+    Ok(ticks as u32)
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// VelocityMap / Curve — per-note and phrase-level dynamics
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Maps a digit pair to a note's base velocity (0–127).
+///
+/// With a [`MidiComposer::velocity_stream`] the map sees that stream's digit
+/// as both `left` and `right`, so any one-digit table follows the third
+/// constant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VelocityMap {
+    /// Every note at the same velocity.
+    Fixed(u8),
+    /// `table[left digit]`, wrapping.
+    Left(Vec<u8>),
+    /// `table[right digit]`, wrapping.
+    Right(Vec<u8>),
+}
+
+impl VelocityMap {
+    /// Linear: left digit `0..base` spread evenly over `lo..=hi`; with a
+    /// velocity stream, its digit instead.
+    pub fn linear_left(lo: u8, hi: u8, base: u8) -> Self {
+        VelocityMap::Left(linear_table(lo, hi, base))
+    }
+
+    /// Right digit `0..base` spread evenly over `lo..=hi`.
+    pub fn linear_right(lo: u8, hi: u8, base: u8) -> Self {
+        VelocityMap::Right(linear_table(lo, hi, base))
+    }
+
+    /// Exponential: digit `0..base` over `lo..=hi` in equal ratios, so each
+    /// step sounds about as much louder as the last.  Reads the left digit,
+    /// or the velocity stream's.
+    pub fn exponential(lo: u8, hi: u8, base: u8) -> Self {
+        let (lo, hi) = (lo.max(1) as f64, hi.max(1) as f64);
+        let n = base.max(2) as i32;
+        VelocityMap::Left((0..n)
+            .map(|d| (lo * (hi / lo).powf(d as f64 / (n - 1) as f64)).round().min(127.0) as u8)
+            .collect())
+    }
+
+    /// Custom lookup table: `table[d]` is the velocity for digit `d`,
+    /// wrapping.  Reads the left digit, or the velocity stream's.
+    pub fn custom(table: Vec<u8>) -> Self {
+        VelocityMap::Left(table)
+    }
+
+    /// Velocity for the pair `(left, right)`, clamped to 0–127.
+    pub fn velocity_for(&self, left: u8, right: u8) -> u8 {
+        let pick = |t: &Vec<u8>, d: u8| if t.is_empty() { 100 } else { t[d as usize % t.len()] };
+        match self {
+            VelocityMap::Fixed(v)  => *v,
+            VelocityMap::Left(t)   => pick(t, left),
+            VelocityMap::Right(t)  => pick(t, right),
+        }.min(127)
+    }
+}
+
+fn linear_table(lo: u8, hi: u8, base: u8) -> Vec<u8> {
+    let n = base.max(2) as i32;
+    (0..n).map(|d| (lo as i32 + (hi as i32 - lo as i32) * d / (n - 1)) as u8).collect()
+}
+
+/// Phrase-level dynamic shape applied over groups of notes, on top of the
+/// [`VelocityMap`].  Lengths are in notes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Curve {
+    /// No shaping (default).
+    Flat,
+    /// Soft to loud across each phrase of `n` notes.
+    Crescendo(usize),
+    /// Loud to soft across each phrase of `n` notes.
+    Decrescendo(usize),
+    /// Soft → loud → soft (hairpins) across each phrase of `n` notes.
+    Swell(usize),
+    /// Explicit velocity offsets, one per note, repeating.
+    Custom(Vec<i8>),
+}
+
+impl Curve {
+    /// Velocity offset for the `index`-th note; shaped curves swing over
+    /// `depth` velocity steps centred on the base velocity.
+    pub fn offset(&self, index: usize, depth: u8) -> i32 {
+        let phase = |len: usize| {
+            let len = len.max(2);
+            (index % len) as f32 / (len - 1) as f32
+        };
+        let shaped = |t: f32| ((t - 0.5) * depth as f32).round() as i32;
+        match self {
+            Curve::Flat           => 0,
+            Curve::Crescendo(n)   => shaped(phase(*n)),
+            Curve::Decrescendo(n) => shaped(1.0 - phase(*n)),
+            Curve::Swell(n)       => shaped(1.0 - (2.0 * phase(*n) - 1.0).abs()),
+            Curve::Custom(v)      => if v.is_empty() { 0 } else { v[index % v.len()] as i32 },
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// ChordShape — stacks of notes per digit pair
+// ════════════════════════════════════════════════════════════════════════════
+
+/// The notes [`MidiComposer::chords`] sounds for each digit pair.  The right
+/// digit picks the lowest note, and each step climbs that many degrees of
+/// the pitch map's scale, so a triad stays in key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChordShape {
+    /// Root, third and fifth: steps 0, 2, 4.
+    Triad,
+    /// A triad with its seventh: 0, 2, 4, 6.
+    Seventh,
+    /// Second in place of the third: 0, 1, 4.
+    Sus2,
+    /// Fourth in place of the third: 0, 3, 4.
+    Sus4,
+    /// Root and fifth: 0, 4.
+    Power,
+    /// Any stack of scale steps above the root, e.g. `vec![0, 4, 9]`.
+    Custom(Vec<u8>),
+}
+
+impl ChordShape {
+    /// Scale steps above the root, lowest first.
+    pub fn steps(&self) -> &[u8] {
+        match self {
+            ChordShape::Triad     => &[0, 2, 4],
+            ChordShape::Seventh   => &[0, 2, 4, 6],
+            ChordShape::Sus2      => &[0, 1, 4],
+            ChordShape::Sus4      => &[0, 3, 4],
+            ChordShape::Power     => &[0, 4],
+            ChordShape::Custom(v) => v,
+        }
+    }
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // Note — a single MIDI note event
 // ════════════════════════════════════════════════════════════════════════════
 
-/// A single resolved note: pitch, duration, and velocity.
+/// A single resolved note: pitch, duration, gate, and velocity.
+///
+/// `duration` is the time until the next note starts; `gate` is how long
+/// this one sounds.  A gate shorter than the duration leaves a rest, a
+/// longer one overlaps the following notes, and a duration of 0 starts the
+/// next note together with this one.  A [`rest`](Note::rest) keeps only its
+/// duration: nothing sounds until the next note.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Note {
     /// MIDI note number (0–127).
     pub pitch:    u8,
-    /// Duration in MIDI ticks.
+    /// Inter-onset time in MIDI ticks: from this note's start to the next.
     pub duration: u32,
+    /// Sounding length in MIDI ticks.
+    pub gate:     u32,
     /// MIDI velocity (0–127).
     pub velocity: u8,
+    /// Note Off (release) velocity (0–127); 0 unless an [`Expression`] sets it.
+    pub release:    u8,
+    /// Channel aftertouch sent midway through the note, if any.
+    pub aftertouch: Option<u8>,
+    /// Tied into the following note of the same pitch: the two sound as
+    /// one, with no re-attack at the join.
+    pub tied:       bool,
+    /// Silence for `duration`: no Note On or Off is sent, and pitch,
+    /// velocity and gate are ignored.
+    pub rest:       bool,
+}
+
+impl Note {
+    /// A plain note sounding for its whole duration: no release velocity,
+    /// aftertouch or tie.
+    pub fn new(pitch: u8, duration: u32, velocity: u8) -> Self {
+        Note { pitch, duration, gate: duration, velocity, release: 0, aftertouch: None, tied: false, rest: false }
+    }
+
+    /// A rest lasting `duration` ticks.
+    pub fn rest(duration: u32) -> Self {
+        Note { rest: true, gate: 0, ..Note::new(0, duration, 0) }
+    }
+
+    /// Change the duration, keeping a full-length gate full-length and
+    /// never letting the note sound past its new end.
+    pub(crate) fn resize(&mut self, duration: u32) {
+        self.gate = if self.gate == self.duration { duration } else { self.gate.min(duration) };
+        self.duration = duration;
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Expression — extra per-note dimensions beyond pitch and duration
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Source of a per-note 0–127 value such as release velocity or aftertouch.
+pub enum Expression {
+    /// The same value for every note.
+    Fixed(u8),
+    /// One digit per note from an additional spigot, scaled from
+    /// `0..base` onto `0..=127`.
+    Digit(BoxedSpigot),
+    /// A function of the note's `(left, right)` pair.
+    Pair(Box<dyn FnMut(u8, u8) -> u8 + Send>),
+}
+
+impl Expression {
+    /// Draw values from a third stream described by `cfg`.
+    pub fn digit(cfg: SpigotConfig) -> Self {
+        Expression::Digit(BoxedSpigot::from_config(cfg))
+    }
+
+    /// Derive values from the note's digit pair.
+    pub fn pair<F: FnMut(u8, u8) -> u8 + Send + 'static>(f: F) -> Self {
+        Expression::Pair(Box::new(f))
+    }
+
+    /// Value for the next note, clamped to 0–127.
+    fn next_value(&mut self, left: u8, right: u8) -> u8 {
+        match self {
+            Expression::Fixed(v) => (*v).min(127),
+            Expression::Digit(sp) => {
+                let max = sp.base().saturating_sub(1).max(1) as u32;
+                let d   = sp.next_digit().unwrap_or(0) as u32;
+                (d.min(max) * 127 / max) as u8
+            }
+            Expression::Pair(f) => f(left, right).min(127),
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// RegisterSplit — route low registers to another channel/instrument
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Sends notes below a pitch to a separate channel and instrument, so one
+/// stream can drive e.g. a bass line and a lead at once.
+///
+/// With several splits a note goes to the one with the lowest `below` that
+/// still exceeds its pitch; notes above every split stay on the track's own
+/// channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterSplit {
+    /// Notes with pitch strictly below this MIDI note number are routed here.
+    pub below:      u8,
+    /// MIDI channel (0–15).
+    pub channel:    u8,
+    /// General MIDI program (0–127).
+    pub instrument: u8,
 }
 
 // ════════════════════════════════════════════════════════════════════════════
@@ -502,19 +934,64 @@ pub struct Note {
 pub struct MidiTrack {
     pub notes:             Vec<Note>,
     pub ticks_per_quarter: u16,
-    pub tempo_bpm:         u32,
+    /// Tempo in beats per minute; fractional values are allowed.
+    pub tempo_bpm:         f64,
     pub instrument:        u8,
     pub channel:           u8,
     /// Source description for metadata.
     pub description:       String,
+    /// Use running-status compression when serialising (default true).
+    pub running_status:    bool,
+    /// Register splits routing low notes to other channels (may be empty).
+    pub splits:            Vec<RegisterSplit>,
+    /// Sustain pedal lane: pedal down during `notes[i]` if `pedal[i]`.
+    /// Empty means no CC64 events are written.
+    pub pedal:             Vec<bool>,
+    /// Bank select `(msb, lsb)` sent as CC0/CC32 before the program change.
+    pub bank:              Option<(u8, u8)>,
+    /// Tempo changes `(tick, bpm)` after the start, in tick order.  Empty
+    /// means the whole track plays at `tempo_bpm`.
+    pub tempo_changes:     Vec<(u64, f64)>,
+    /// Time signature `(numerator, denominator)` written at the start, or
+    /// `None` for no time signature event (players then assume 4/4).
+    pub time_signature:    Option<(u8, u8)>,
+    /// How to compose the track again, embedded after the track name.
+    pub provenance:        Option<Provenance>,
 }
 
 impl MidiTrack {
+    /// A track of `notes` on channel 0 with program 0 and no splits,
+    /// pedal, bank or tempo changes — for notes that come from somewhere
+    /// other than a [`MidiComposer`], such as a live recording.
+    pub fn new(notes: Vec<Note>, ticks_per_quarter: u16, tempo_bpm: f64) -> Self {
+        MidiTrack {
+            notes,
+            ticks_per_quarter,
+            tempo_bpm,
+            instrument:     0,
+            channel:        0,
+            description:    String::new(),
+            running_status: true,
+            splits:         Vec::new(),
+            pedal:          Vec::new(),
+            bank:           None,
+            tempo_changes:  Vec::new(),
+            time_signature: None,
+            provenance:     None,
+        }
+    }
+
     /// Serialise to a standard MIDI Type-0 file and write to `path`.
-    pub fn write_file(&self, path: &str) -> std::io::Result<()> {
-        let bytes = self.to_bytes();
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
         let mut f = std::fs::File::create(path)?;
-        f.write_all(&bytes)
+        self.write_to(&mut f)
+    }
+
+    /// Serialise to a standard MIDI Type-0 file and write it to any sink —
+    /// a socket, stdout or an in-memory buffer.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        w.write_all(&self.to_bytes())?;
+        w.flush()
     }
 
     /// Serialise to a `Vec<u8>` containing a valid MIDI Type-0 file.
@@ -538,56 +1015,316 @@ impl MidiTrack {
         out
     }
 
+    /// Channel and instrument a note of `pitch` plays on, after register
+    /// splits are applied.
+    pub fn route(&self, pitch: u8) -> (u8, u8) {
+        self.splits.iter()
+            .filter(|s| pitch < s.below)
+            .min_by_key(|s| s.below)
+            .map(|s| (s.channel & 0x0F, s.instrument))
+            .unwrap_or((self.channel & 0x0F, self.instrument))
+    }
+
+    /// True if `notes[i]` is tied to a following note of the same pitch.
+    pub(crate) fn ties_into_next(&self, i: usize) -> bool {
+        self.notes[i].tied && !self.notes[i].rest
+            && self.notes.get(i + 1).is_some_and(|n| !n.rest && n.pitch == self.notes[i].pitch)
+    }
+
+    /// Split every note that sounds across a barline into tied pieces, one
+    /// per measure, so notation exporters can draw ties.  Playback is
+    /// unchanged.
+    pub fn split_at_barlines(&mut self, bar_ticks: u64) {
+        if bar_ticks == 0 { return; }
+        let mut notes = Vec::with_capacity(self.notes.len());
+        let mut pedal = Vec::with_capacity(self.pedal.len());
+        let mut tick: u64 = 0;
+        for (i, note) in self.notes.iter().enumerate() {
+            let pieces = split_note(note, tick, bar_ticks, &mut notes);
+            if let Some(&p) = self.pedal.get(i) {
+                pedal.extend(std::iter::repeat_n(p, pieces));
+            }
+            tick += note.duration as u64;
+        }
+        self.notes = notes;
+        self.pedal = pedal;
+    }
+
+    /// Total length of the track in ticks.
+    pub fn total_ticks(&self) -> u64 {
+        self.notes.iter().map(|n| n.duration as u64).sum()
+    }
+
+    /// Start tick of every note.
+    pub fn onsets(&self) -> Vec<u64> {
+        self.notes.iter()
+            .scan(0u64, |tick, n| { let at = *tick; *tick += n.duration as u64; Some(at) })
+            .collect()
+    }
+
+    /// Tick at which the last sound stops: [`total_ticks`](Self::total_ticks)
+    /// or later if a gate rings past the final onset.
+    pub fn end_tick(&self) -> u64 {
+        self.onsets().iter().zip(&self.notes)
+            .map(|(&at, n)| at + n.gate as u64)
+            .fold(self.total_ticks(), u64::max)
+    }
+
+    /// Time in seconds at `tick`, following any [`tempo_changes`](Self::tempo_changes).
+    pub fn seconds_at(&self, tick: u64) -> f64 {
+        let tpq = self.ticks_per_quarter.max(1) as f64;
+        let mut secs = 0.0;
+        let mut from = 0u64;
+        let mut bpm  = self.tempo_bpm;
+        for &(at, next) in self.tempo_changes.iter().take_while(|&&(at, _)| at < tick) {
+            secs += (at - from) as f64 * 60.0 / (bpm * tpq);
+            from = at;
+            bpm  = next;
+        }
+        secs + (tick - from) as f64 * 60.0 / (bpm * tpq)
+    }
+
     fn build_track_chunk(&self) -> Vec<u8> {
-        let mut t: Vec<u8> = Vec::new();
+        encode_events(self.timed_events(), self.running_status)
+    }
+
+    /// Every event of the track at its absolute tick, in insertion order.
+    pub(crate) fn timed_events(&self) -> Vec<TimedEvent> {
+        let mut ev = self.head_events();
+
+        // ── Note events (with the sustain lane on every channel in use) ───
+        let pedal_channels = self.pedal_channels();
+        let mut pedal_down = false;
+        let mut held = false;      // previous note is tied into this one
+        let mut tick: u64 = 0;
+        let onsets = self.onsets();
+        for (i, note) in self.notes.iter().enumerate() {
+            let down = self.pedal.get(i).copied().unwrap_or(false);
+            if down != pedal_down {
+                for &pc in &pedal_channels {
+                    ev.push(TimedEvent::channel(tick, 0xB0 | pc, &[64, if down { 127 } else { 0 }]));
+                }
+                pedal_down = down;
+            }
+            if note.rest {
+                // Nothing to send; the next event's delta spans the rest.
+                held = false;
+                tick += note.duration as u64;
+                continue;
+            }
+            let (ch, _) = self.route(note.pitch);
+            if !held {
+                ev.push(TimedEvent::channel(tick, 0x90 | ch, &[note.pitch, note.velocity]));
+            }
+            if let Some(pressure) = note.aftertouch {
+                let mid = tick + note.gate as u64 / 2;
+                ev.push(TimedEvent::channel(mid, 0xD0 | ch, &[pressure]));
+            }
+            held = self.ties_into_next(i);
+            if !held {
+                // An overlapping gate is cut where the same key strikes again.
+                let restrike = (i + 1..self.notes.len())
+                    .find(|&j| !self.notes[j].rest && self.notes[j].pitch == note.pitch)
+                    .map_or(u64::MAX, |j| onsets[j]);
+                let off = (tick + note.gate as u64).min(restrike);
+                ev.push(TimedEvent::channel(off, 0x80 | ch, &[note.pitch, note.release]));
+            }
+            tick += note.duration as u64;
+        }
+
+        if pedal_down {
+            for &pc in &pedal_channels {
+                ev.push(TimedEvent::channel(tick, 0xB0 | pc, &[64, 0]));
+            }
+        }
+
+        // ── End of Track meta-event ───────────────────────────────────────
+        ev.push(TimedEvent::meta(self.end_tick(), 0x2F, &[]));
+        ev
+    }
+
+    /// The events ahead of the notes: tempo, name, provenance, bank and
+    /// program.
+    pub(crate) fn head_events(&self) -> Vec<TimedEvent> {
+        let mut ev: Vec<TimedEvent> = Vec::new();
         let ch = self.channel & 0x0F;
 
-        // ── Tempo meta-event (delta=0) ────────────────────────────────────
-        let micros = 60_000_000u32 / self.tempo_bpm;
-        t.push(0x00); // delta time = 0
-        t.push(0xFF); // meta
-        t.push(0x51); // tempo
-        t.push(0x03); // length 3
-        t.push(((micros >> 16) & 0xFF) as u8);
-        t.push(((micros >>  8) & 0xFF) as u8);
-        t.push(( micros        & 0xFF) as u8);
+        // ── Tempo meta-event ──────────────────────────────────────────────
+        ev.push(TimedEvent::meta(0, 0x51, &tempo_micros(self.tempo_bpm).to_be_bytes()[1..]));
+        for &(at, bpm) in &self.tempo_changes {
+            ev.push(TimedEvent::meta(at, 0x51, &tempo_micros(bpm).to_be_bytes()[1..]));
+        }
+
+        // ── Time signature meta-event ─────────────────────────────────────
+        if let Some((num, den)) = self.time_signature {
+            ev.push(TimedEvent::meta(0, 0x58, &time_signature_meta(num, den)));
+        }
 
         // ── Track name meta-event ─────────────────────────────────────────
-        let name = self.description.as_bytes();
-        t.push(0x00);
-        t.push(0xFF);
-        t.push(0x03); // track name
-        write_vlq(&mut t, name.len() as u32);
-        t.extend_from_slice(name);
-
-        // ── Program Change (instrument) ───────────────────────────────────
-        t.push(0x00); // delta = 0
-        t.push(0xC0 | ch);
-        t.push(self.instrument);
-
-        // ── Note events ───────────────────────────────────────────────────
-        for note in &self.notes {
-            // Note On (delta = 0 between consecutive notes)
-            t.push(0x00);
-            t.push(0x90 | ch);
-            t.push(note.pitch);
-            t.push(note.velocity);
-
-            // Note Off after `duration` ticks
-            write_vlq(&mut t, note.duration);
-            t.push(0x80 | ch);
-            t.push(note.pitch);
-            t.push(0x00);
+        ev.push(TimedEvent::meta(0, 0x03, self.description.as_bytes()));
+        if let Some(p) = &self.provenance {
+            ev.push(TimedEvent::meta(0, 0x7F, &p.to_meta()));
         }
 
-        // ── End of Track meta-event ───────────────────────────────────────
-        t.push(0x00);
-        t.push(0xFF);
-        t.push(0x2F);
-        t.push(0x00);
+        // ── Bank Select + Program Change, one per split channel ───────────
+        if let Some((msb, lsb)) = self.bank {
+            ev.push(TimedEvent::channel(0, 0xB0 | ch, &[0x00, msb & 0x7F]));
+            ev.push(TimedEvent::channel(0, 0xB0 | ch, &[0x20, lsb & 0x7F]));
+        }
+        ev.push(TimedEvent::channel(0, 0xC0 | ch, &[self.instrument]));
+        for split in &self.splits {
+            ev.push(TimedEvent::channel(0, 0xC0 | (split.channel & 0x0F), &[split.instrument]));
+        }
+        ev
+    }
+
+    /// Every channel the track's notes can sound on.
+    pub(crate) fn pedal_channels(&self) -> Vec<u8> {
+        let mut channels = vec![self.channel & 0x0F];
+        channels.extend(self.splits.iter().map(|s| s.channel & 0x0F));
+        channels.sort_unstable();
+        channels.dedup();
+        channels
+    }
+}
+
+/// FF 58 data for `num/den`: numerator, denominator as a power of two,
+/// MIDI clocks per metronome click, and 32nd notes per quarter.  Compound
+/// meters (6/8, 9/8, 12/8) click on the dotted beat.
+fn time_signature_meta(num: u8, den: u8) -> [u8; 4] {
+    let clocks = 96 / den.max(1) as u32;
+    let compound = num > 3 && num.is_multiple_of(3) && den >= 8;
+    let click = if compound { clocks * 3 } else { clocks };
+    [num, den.trailing_zeros() as u8, click.clamp(1, 255) as u8, 8]
+}
 
-        t
+/// Push `note`, starting at `tick`, onto `out` in tied pieces that break
+/// at each barline it sounds across; returns the number of pieces.
+pub(crate) fn split_note(note: &Note, tick: u64, bar_ticks: u64, out: &mut Vec<Note>) -> usize {
+    if note.duration == 0 {
+        // Sounds with the next note (a chord member); nothing to split.
+        out.push(note.clone());
+        return 1;
+    }
+    let end = tick + note.duration as u64;
+    let sounding_end = tick + note.gate as u64;
+    let mut start = tick;
+    let mut pieces = 0;
+    while start < end {
+        let barline = (start / bar_ticks + 1) * bar_ticks;
+        let split = barline < end && barline < sounding_end;
+        let piece_end = if split { barline } else { end };
+        let mut piece = note.clone();
+        piece.duration = (piece_end - start) as u32;
+        if split {
+            piece.gate = piece.duration;
+            piece.tied = true;
+            piece.release = 0;
+        } else {
+            piece.gate = (sounding_end - start) as u32;
+        }
+        if start > tick { piece.aftertouch = None; }
+        out.push(piece);
+        pieces += 1;
+        start = piece_end;
     }
+    pieces
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Event encoding — absolute-time events → delta-timed track bytes
+// ════════════════════════════════════════════════════════════════════════════
+
+/// A track event at an absolute tick, before delta-time encoding.
+///
+/// `bytes` is the complete message: status byte first for channel messages,
+/// `FF type len data…` for meta events.
+#[derive(Clone, Debug)]
+pub(crate) struct TimedEvent {
+    pub(crate) tick:  u64,
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl TimedEvent {
+    pub(crate) fn channel(tick: u64, status: u8, data: &[u8]) -> Self {
+        let mut bytes = vec![status];
+        bytes.extend_from_slice(data);
+        TimedEvent { tick, bytes }
+    }
+
+    pub(crate) fn meta(tick: u64, kind: u8, data: &[u8]) -> Self {
+        let mut bytes = vec![0xFF, kind];
+        write_vlq(&mut bytes, data.len() as u32);
+        bytes.extend_from_slice(data);
+        TimedEvent { tick, bytes }
+    }
+}
+
+/// Serialise events in time order (stable, so insertion order breaks ties).
+///
+/// With `running_status`, a channel message repeating the previous status
+/// byte omits it, and zero-velocity Note Offs are sent as Note On velocity 0
+/// so that note streams collapse onto a single status.  Meta events cancel
+/// running status.
+fn encode_events(mut events: Vec<TimedEvent>, running_status: bool) -> Vec<u8> {
+    events.sort_by_key(|e| e.tick);
+
+    let mut t: Vec<u8> = Vec::new();
+    let mut encoder = Encoder::new(running_status);
+    for e in events {
+        encoder.encode(e, &mut t);
+    }
+    t
+}
+
+/// Delta times and running status for events fed in time order.
+pub(crate) struct Encoder {
+    running_status: bool,
+    last_tick:      u64,
+    last_status:    Option<u8>,
+}
+
+impl Encoder {
+    pub(crate) fn new(running_status: bool) -> Self {
+        Encoder { running_status, last_tick: 0, last_status: None }
+    }
+
+    /// Append `e`, which must not be earlier than the event before it.
+    /// A gap longer than one delta time holds is bridged with empty text
+    /// events.
+    pub(crate) fn encode(&mut self, mut e: TimedEvent, t: &mut Vec<u8>) {
+        let mut gap = e.tick - self.last_tick;
+        while gap > validate::VLQ_MAX {
+            write_vlq(t, validate::VLQ_MAX as u32);
+            t.extend_from_slice(&[0xFF, 0x01, 0x00]);
+            self.last_status = None;
+            gap -= validate::VLQ_MAX;
+        }
+        write_vlq(t, gap as u32);
+        self.last_tick = e.tick;
+
+        let status = e.bytes[0];
+        if status >= 0xF0 {
+            self.last_status = None;
+            t.extend_from_slice(&e.bytes);
+            return;
+        }
+        if self.running_status && status & 0xF0 == 0x80 && e.bytes[2] == 0 {
+            e.bytes[0] = 0x90 | (status & 0x0F);
+        }
+        if self.running_status && self.last_status == Some(e.bytes[0]) {
+            t.extend_from_slice(&e.bytes[1..]);
+        } else {
+            t.extend_from_slice(&e.bytes);
+        }
+        self.last_status = Some(e.bytes[0]);
+    }
+}
+
+/// Microseconds per quarter note for `bpm`, rounded and clamped to the
+/// 24-bit range of the Set Tempo meta event.
+pub(crate) fn tempo_micros(bpm: f64) -> u32 {
+    (60_000_000.0 / bpm).round().clamp(1.0, 0xFF_FFFF as f64) as u32
 }
 
 /// Write a MIDI variable-length quantity (VLQ).
@@ -604,11 +1341,92 @@ fn write_vlq(buf: &mut Vec<u8>, mut value: u32) {
     buf.extend_from_slice(&bytes[i..]);
 }
 
+// ════════════════════════════════════════════════════════════════════════════
+// PairSource — anything that yields (left, right) digit pairs
+// ════════════════════════════════════════════════════════════════════════════
+
+/// A supply of `(left, right)` pairs for [`MidiComposer`].
+///
+/// [`DualStream`] is the usual source; [`IterSource`] adapts any iterator
+/// of pairs (recorded data, test fixtures, other generators) so MIDI
+/// generation does not depend on the spigot crates.  Each side must be
+/// advanceable on its own so that `drop_left`, `drop_right` and `twist`
+/// keep their meaning.
+pub trait PairSource: Send {
+    /// Next pair, or `None` when either side is exhausted.
+    fn next_pair(&mut self) -> Option<(u8, u8)>;
+    /// Skip `n` digits on the left side only.
+    fn drop_left(&mut self, n: usize);
+    /// Skip `n` digits on the right side only.
+    fn drop_right(&mut self, n: usize);
+    /// Swap the left and right sides.
+    fn twist(&mut self);
+}
+
+impl PairSource for DualStream {
+    fn next_pair(&mut self) -> Option<(u8, u8)> { self.zip_next() }
+    fn drop_left(&mut self, n: usize)  { self.left().drop(n); }
+    fn drop_right(&mut self, n: usize) { self.right().drop(n); }
+    fn twist(&mut self) { DualStream::twist(self) }
+}
+
+/// [`PairSource`] over any iterator of pairs.
+///
+/// Pairs are pulled lazily and split into per-side queues, so one side can
+/// be dropped or twisted independently of the other.
+pub struct IterSource<I> {
+    iter:    I,
+    sides:   [VecDeque<u8>; 2],
+    twisted: bool,
+}
+
+impl<I: Iterator<Item = (u8, u8)>> IterSource<I> {
+    pub fn new<T: IntoIterator<IntoIter = I>>(pairs: T) -> Self {
+        IterSource { iter: pairs.into_iter(), sides: [VecDeque::new(), VecDeque::new()], twisted: false }
+    }
+
+    /// Pull one pair from the iterator into the side queues.
+    fn pull(&mut self) -> bool {
+        match self.iter.next() {
+            Some((l, r)) => {
+                let (l, r) = if self.twisted { (r, l) } else { (l, r) };
+                self.sides[0].push_back(l);
+                self.sides[1].push_back(r);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn drop_side(&mut self, side: usize, n: usize) {
+        for _ in 0..n {
+            if self.sides[side].is_empty() && !self.pull() { break; }
+            self.sides[side].pop_front();
+        }
+    }
+}
+
+impl<I: Iterator<Item = (u8, u8)> + Send> PairSource for IterSource<I> {
+    fn next_pair(&mut self) -> Option<(u8, u8)> {
+        while self.sides[0].is_empty() || self.sides[1].is_empty() {
+            if !self.pull() { return None; }
+        }
+        Some((self.sides[0].pop_front()?, self.sides[1].pop_front()?))
+    }
+    fn drop_left(&mut self, n: usize)  { self.drop_side(0, n); }
+    fn drop_right(&mut self, n: usize) { self.drop_side(1, n); }
+    fn twist(&mut self) {
+        self.sides.swap(0, 1);
+        self.twisted = !self.twisted;
+    }
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // MidiComposer — the builder
 // ════════════════════════════════════════════════════════════════════════════
 
-/// Builder that consumes a [`DualStream`] zip to produce a [`MidiTrack`].
+/// Builder that consumes a [`DualStream`] zip (or any other [`PairSource`])
+/// to produce a [`MidiTrack`].
 ///
 /// Left digit  → duration (via [`DurationMap`])
 /// Right digit → pitch    (via [`PitchMap`])
@@ -634,41 +1452,119 @@ fn write_vlq(buf: &mut Vec<u8>, mut value: u32) {
 /// track.write_file("vibraphone.mid").unwrap();
 /// ```
 pub struct MidiComposer {
-    stream:       DualStream,
-    tempo_bpm:    u32,
+    stream:       Box<dyn PairSource>,
+    tempo_bpm:    f64,
     instrument:   u8,
-    pitch_map:    PitchMap,
-    duration_map: DurationMap,
-    velocity:     u8,
+    pitch_map:    Box<dyn PitchMapper>,
+    duration_map: Box<dyn DurationMapper>,
+    velocity_map: Box<dyn VelocityMapper>,
+    dynamics:     Curve,
+    dynamics_depth: u8,
+    note_index:   usize,
+    gate_ratio:   f32,
     channel:      u8,
     tpq:          u16,
+    /// `None` until set; measures are 4/4 either way.
+    time_sig:     Option<(u8, u8)>,
     description:  String,
+    running_status: bool,
+    splits:       Vec<RegisterSplit>,
+    release:      Option<Expression>,
+    aftertouch:   Option<Expression>,
+    sustain:      Option<SustainRule>,
+    pedal:        Vec<bool>,
+    rest:         Option<Box<dyn FnMut(u8, u8) -> bool + Send>>,
+    velocity_stream: Option<BoxedSpigot>,
+    chord:        Option<ChordShape>,
+    bank:         Option<(u8, u8)>,
+    tie_barlines: bool,
+    quantize_bars: bool,
+    /// Ticks into the current measure, kept while quantizing.
+    bar_pos:      u64,
+    provenance:   Option<Provenance>,
+}
+
+/// How [`MidiComposer`] decides the sustain pedal state for each note.
+enum SustainRule {
+    When(Box<dyn FnMut(u8, u8) -> bool + Send>),
+    Pattern(Vec<bool>),
 }
 
 impl MidiComposer {
     /// Create a new composer from a `DualStream`.
     ///
-    /// Defaults: 120 BPM, Acoustic Grand Piano, C major from middle C,
+    /// Defaults: 120 BPM, 4/4, Acoustic Grand Piano, C major from middle C,
     /// musical durations at 480 ticks/quarter, velocity 100, channel 0.
     pub fn new(stream: DualStream) -> Self {
+        Self::from_source(stream)
+    }
+
+    /// Compose from any [`PairSource`].
+    pub fn from_source<S: PairSource + 'static>(source: S) -> Self {
+        Self::with_source(Box::new(source))
+    }
+
+    /// Compose from any iterable of `(left, right)` pairs.
+    pub fn from_pair_iter<T>(pairs: T) -> Self
+    where T: IntoIterator<Item = (u8, u8)>, T::IntoIter: Send + 'static
+    {
+        Self::from_source(IterSource::new(pairs))
+    }
+
+    /// Compose from a fixed list of `(left, right)` pairs instead of a live
+    /// stream, e.g. material captured with [`DualStream::snip`].
+    pub fn from_pairs(pairs: &[(u8, u8)]) -> Self {
+        Self::from_pair_iter(pairs.to_vec())
+    }
+
+    /// Compose from the snippet stored under `key` in `ds`, without replaying
+    /// the constants to the snipped offset.
+    pub fn from_snippet(ds: &DualStream, key: &str) -> Result<Self, MidiError> {
+        ds.get_snippet(key)
+            .map(|pairs| Self::from_pairs(pairs))
+            .ok_or_else(|| SpigotError::NoSnippet(key.to_string()).into())
+    }
+
+    fn with_source(stream: Box<dyn PairSource>) -> Self {
         MidiComposer {
             stream,
-            tempo_bpm:    120,
+            tempo_bpm:    120.0,
             instrument:   GeneralMidi::AcousticGrandPiano.program(),
-            pitch_map:    PitchMap::major(60),
-            duration_map: DurationMap::musical(480),
-            velocity:     100,
+            pitch_map:    Box::new(PitchMap::major(60)),
+            duration_map: Box::new(DurationMap::musical(480)),
+            velocity_map: Box::new(VelocityMap::Fixed(100)),
+            dynamics:     Curve::Flat,
+            dynamics_depth: 24,
+            note_index:   0,
+            gate_ratio:   1.0,
             channel:      0,
             tpq:          480,
+            time_sig:     None,
             description:  "spigot_midi".to_string(),
+            running_status: true,
+            splits:       Vec::new(),
+            release:      None,
+            aftertouch:   None,
+            sustain:      None,
+            pedal:        Vec::new(),
+            rest:         None,
+            velocity_stream: None,
+            chord:        None,
+            bank:         None,
+            tie_barlines: false,
+            quantize_bars: false,
+            bar_pos:      0,
+            provenance:   None,
         }
     }
 
     // ── setters (builder pattern) ─────────────────────────────────────────
 
-    /// Set the tempo in BPM (beats per minute).
-    pub fn tempo(mut self, bpm: u32) -> Self {
-        assert!(bpm > 0 && bpm <= 300, "tempo must be 1–300 BPM");
+    /// Set the tempo in BPM (beats per minute).  Any positive value is
+    /// accepted, fractional or very fast, e.g. `.tempo(93.5)` or `.tempo(2000)`.
+    pub fn tempo<T: Into<f64>>(mut self, bpm: T) -> Self {
+        let bpm = bpm.into();
+        assert!(bpm.is_finite() && bpm > 0.0, "tempo must be a positive number of BPM");
         self.tempo_bpm = bpm;
         self
     }
@@ -685,16 +1581,24 @@ impl MidiComposer {
         self
     }
 
-    /// Set the pitch mapping (scale + root note).
-    pub fn pitch_map(mut self, pm: PitchMap) -> Self {
-        self.pitch_map = pm;
+    /// Select a sound bank (CC0 `msb`, CC32 `lsb`, each 0–127) ahead of the
+    /// program change, reaching GM2/GS/XG variations beyond the basic 128.
+    pub fn bank(mut self, msb: u8, lsb: u8) -> Self {
+        self.bank = Some((msb.min(127), lsb.min(127)));
         self
     }
 
-    /// Set the duration mapping.
-    pub fn duration_map(mut self, dm: DurationMap) -> Self {
-        self.duration_map = dm;
-        // Keep tpq consistent if the DurationMap was built with a specific tpq.
+    /// Set the pitch mapping: a [`PitchMap`] (scale + root note) or any
+    /// other [`PitchMapper`].
+    pub fn pitch_map<M: PitchMapper + 'static>(mut self, pm: M) -> Self {
+        self.pitch_map = Box::new(pm);
+        self
+    }
+
+    /// Set the duration mapping: a [`DurationMap`] or any other
+    /// [`DurationMapper`].
+    pub fn duration_map<M: DurationMapper + 'static>(mut self, dm: M) -> Self {
+        self.duration_map = Box::new(dm);
         self
     }
 
@@ -705,9 +1609,86 @@ impl MidiComposer {
         self
     }
 
-    /// Set note velocity (0–127). Default 100.
+    /// Set the time signature, e.g. `(3, 4)` or `(6, 8)`. Default 4/4.
+    ///
+    /// Written to the file as a time signature event, and used by
+    /// [`compose_bars`](Self::compose_bars) and
+    /// [`quantize_to_bars`](Self::quantize_to_bars) to size a measure.
+    pub fn time_signature(mut self, numerator: u8, denominator: u8) -> Self {
+        assert!(numerator > 0, "time signature numerator must be > 0");
+        assert!(denominator.is_power_of_two() && denominator <= 64,
+            "time signature denominator must be a power of two ≤ 64");
+        self.time_sig = Some((numerator, denominator));
+        self
+    }
+
+    /// Keep every measure whole (default off): a note that would cross a
+    /// barline is cut short to end on it, and the last note is padded to
+    /// the end of its measure, so phrases start on the downbeat.
+    /// [`compose_for_ticks`](Self::compose_for_ticks) and its kin still end
+    /// where they are told to.
+    pub fn quantize_to_bars(mut self, on: bool) -> Self {
+        self.quantize_bars = on;
+        self
+    }
+
+    /// Split notes that cross a barline into tied pairs (default off, which
+    /// keeps a single long note).  Sounds the same; matters for notation.
+    pub fn tie_at_barlines(mut self, on: bool) -> Self {
+        self.tie_barlines = on;
+        self
+    }
+
+    /// Set a fixed note velocity (0–127). Default 100.
     pub fn velocity(mut self, v: u8) -> Self {
-        self.velocity = v.min(127);
+        self.velocity_map = Box::new(VelocityMap::Fixed(v.min(127)));
+        self
+    }
+
+    /// Derive each note's base velocity from its digit pair, with a
+    /// [`VelocityMap`] or any other [`VelocityMapper`].
+    pub fn velocity_map<M: VelocityMapper + 'static>(mut self, vm: M) -> Self {
+        self.velocity_map = Box::new(vm);
+        self
+    }
+
+    /// Draw one digit per note from a third stream described by `cfg` and
+    /// let it set the velocity, spread over 40–120 by
+    /// [`VelocityMap::linear_left`].  Follow with
+    /// [`velocity_map`](Self::velocity_map) for another shape, e.g.
+    /// `.velocity_stream(cfg).velocity_map(VelocityMap::exponential(30, 127, cfg.base))`.
+    pub fn velocity_stream(mut self, cfg: SpigotConfig) -> Self {
+        self.velocity_stream = Some(BoxedSpigot::from_config(cfg));
+        self.velocity_map = Box::new(VelocityMap::linear_left(40, 120, cfg.base));
+        self
+    }
+
+    /// Shape velocities over phrases, e.g. `.dynamics(Curve::Crescendo(8))`.
+    /// Applied on top of the [`VelocityMap`]; results are kept within 1–127.
+    pub fn dynamics(mut self, curve: Curve) -> Self {
+        self.dynamics = curve;
+        self
+    }
+
+    /// Sounding length as a fraction of each note's duration: below 1.0
+    /// leaves rests between notes (staccato), above 1.0 overlaps them
+    /// (legato).  Default 1.0.
+    pub fn gate_ratio(mut self, ratio: f32) -> Self {
+        self.gate_ratio = ratio.max(0.0);
+        self
+    }
+
+    /// Articulation: the part of each note that sounds, 0.0–1.0, leaving
+    /// the rest of its duration silent before the next Note On — 0.5 is
+    /// staccato, 1.0 (the default) legato.  [`gate_ratio`](Self::gate_ratio)
+    /// without the overlap.
+    pub fn gate(self, fraction: f32) -> Self {
+        self.gate_ratio(fraction.clamp(0.0, 1.0))
+    }
+
+    /// Total velocity swing of shaped [`Curve`]s (default 24).
+    pub fn dynamics_depth(mut self, depth: u8) -> Self {
+        self.dynamics_depth = depth.min(127);
         self
     }
 
@@ -723,17 +1704,90 @@ impl MidiComposer {
         self
     }
 
+    /// Embed `p` in the composed file, saying how to compose it again (see
+    /// [`provenance`]).  The caller keeps it in step with the settings;
+    /// [`Provenance::composer`] does so.
+    pub fn provenance(mut self, p: Provenance) -> Self {
+        self.provenance = Some(p);
+        self
+    }
+
+    /// Enable or disable running-status compression in the output.
+    /// Default on; turn off for byte-for-byte explicit status bytes.
+    pub fn running_status(mut self, on: bool) -> Self {
+        self.running_status = on;
+        self
+    }
+
+    /// Route notes below `pitch` to `channel` playing `program`.
+    ///
+    /// May be called repeatedly to carve out several registers, e.g.
+    /// `.split_below(48, 1, 32)` for an acoustic bass under a lead on
+    /// channel 0.  See [`RegisterSplit`].
+    pub fn split_below(mut self, pitch: u8, channel: u8, program: u8) -> Self {
+        self.splits.push(RegisterSplit {
+            below:      pitch.min(127),
+            channel:    channel & 0x0F,
+            instrument: program.min(127),
+        });
+        self
+    }
+
+    /// Derive each note's Note Off velocity from `expr` (default 0).
+    pub fn release_velocity(mut self, expr: Expression) -> Self {
+        self.release = Some(expr);
+        self
+    }
+
+    /// Send a channel aftertouch value from `expr` halfway through each note.
+    pub fn aftertouch(mut self, expr: Expression) -> Self {
+        self.aftertouch = Some(expr);
+        self
+    }
+
+    /// Hold the sustain pedal (CC64) through every note whose digit pair
+    /// satisfies `down`, e.g. `.sustain_when(|l, _| l >= 5)`.
+    pub fn sustain_when<F: FnMut(u8, u8) -> bool + Send + 'static>(mut self, down: F) -> Self {
+        self.sustain = Some(SustainRule::When(Box::new(down)));
+        self
+    }
+
+    /// Cycle the sustain pedal through `pattern`, one entry per note,
+    /// e.g. `&[true, true, true, false]`.
+    pub fn sustain_pattern(mut self, pattern: &[bool]) -> Self {
+        assert!(!pattern.is_empty(), "sustain pattern must not be empty");
+        self.sustain = Some(SustainRule::Pattern(pattern.to_vec()));
+        self
+    }
+
+    /// Rest instead of sounding a note wherever the digit pair satisfies
+    /// `rest`, e.g. `.rest_on(|l, _| l == 0)`.  The rest lasts as long as
+    /// the note would have.
+    pub fn rest_on<F: FnMut(u8, u8) -> bool + Send + 'static>(mut self, rest: F) -> Self {
+        self.rest = Some(Box::new(rest));
+        self
+    }
+
+    /// Sound a chord of `shape` for each pair instead of a single note,
+    /// built up in the pitch map's scale from the right digit's note.  The
+    /// chord's notes share its onset, duration and velocity; rests stay
+    /// silent.
+    pub fn chords(mut self, shape: ChordShape) -> Self {
+        self.chord = Some(shape);
+        self
+    }
+
     // ── side-specific cursor operations (delegate to DualStream) ──────────
 
     /// Advance the Left cursor by `n` digits before composing.
     pub fn drop_left(mut self, n: usize) -> Self {
-        self.stream.left().drop(n);
+        self.stream.drop_left(n);
         self
     }
 
     /// Advance the Right cursor by `n` digits before composing.
     pub fn drop_right(mut self, n: usize) -> Self {
-        self.stream.right().drop(n);
+        self.stream.drop_right(n);
         self
     }
 
@@ -748,62 +1802,266 @@ impl MidiComposer {
     /// Consume `n` pairs from the zip stream and resolve them into a
     /// [`MidiTrack`].
     ///
-    /// Each pair `(left, right)` produces one [`Note`]:
+    /// Each pair `(left, right)` produces one [`Note`] (or, in
+    /// [`chords`](Self::chords) mode, one chord):
     /// * `left`  → duration via the [`DurationMap`]
     /// * `right` → pitch    via the [`PitchMap`]
-    pub fn compose(mut self, n: usize) -> Result<MidiTrack, String> {
-        if n == 0 { return Err("n must be > 0".to_string()); }
-
-        let pairs = self.stream.zip_take(n);
-        let notes: Vec<Note> = pairs.into_iter().map(|(left, right)| {
-            Note {
-                pitch:    self.pitch_map.note_for(right),
-                duration: self.duration_map.ticks_for(left),
-                velocity: self.velocity,
+    pub fn compose(mut self, n: usize) -> Result<MidiTrack, MidiError> {
+        if n == 0 { return Err(MidiError::param("n must be > 0")); }
+
+        let pairs = self.take_pairs(n);
+        let mut notes: Vec<Note> = pairs.into_iter()
+            .flat_map(|(left, right)| self.track_notes(left, right))
+            .collect();
+
+        self.pad_last_bar(&mut notes);
+        Ok(self.finish(notes))
+    }
+
+    /// Compose until `stop` returns true.
+    ///
+    /// `stop` sees each new note together with [`ComposeStats`] for the
+    /// track *including* that note; the note that satisfies it is the last
+    /// one kept (or the chord it belongs to is).  Composition also ends if the stream runs dry.  With an
+    /// infinite stream and a predicate that never fires this does not return.
+    pub fn compose_until<F>(mut self, stop: F) -> Result<MidiTrack, MidiError>
+    where F: FnMut(&Note, &ComposeStats) -> bool
+    {
+        let mut notes = self.collect_until(stop)?;
+        self.pad_last_bar(&mut notes);
+        Ok(self.finish(notes))
+    }
+
+    fn collect_until<F>(&mut self, mut stop: F) -> Result<Vec<Note>, MidiError>
+    where F: FnMut(&Note, &ComposeStats) -> bool
+    {
+        let mut notes = Vec::new();
+        let mut stats = ComposeStats::default();
+        let secs_per_tick = 60.0 / (self.tempo_bpm * self.tpq as f64);
+
+        while let Some((left, right)) = self.stream.next_pair() {
+            let mut done = false;
+            for note in self.track_notes(left, right) {
+                stats.notes   += 1;
+                stats.ticks   += note.duration as u64;
+                stats.seconds  = stats.ticks as f64 * secs_per_tick;
+                done |= stop(&note, &stats);
+                notes.push(note);
             }
-        }).collect();
+            if done { break; }
+        }
+
+        if notes.is_empty() {
+            return Err(MidiError::NoNotes("stream produced no notes"));
+        }
+        Ok(notes)
+    }
+
+    /// Compose exactly `total` ticks of music.
+    ///
+    /// The final note is shortened if it would overrun (or lengthened if the
+    /// stream runs dry first), so the track length always equals `total`.
+    pub fn compose_for_ticks(mut self, total: u64) -> Result<MidiTrack, MidiError> {
+        if total == 0 { return Err(MidiError::param("total ticks must be > 0")); }
+
+        let mut notes = self.collect_until(|_, stats| stats.ticks >= total)?;
+        let len: u64 = notes.iter().map(|n| n.duration as u64).sum();
+        if let Some(last) = notes.last_mut() {
+            last.resize((last.duration as i64 + total as i64 - len as i64) as u32);
+        }
+        Ok(self.finish(notes))
+    }
+
+    /// Compose exactly `bars` measures in the configured time signature.
+    ///
+    /// The final note is padded or truncated so the piece ends on a barline.
+    pub fn compose_bars(self, bars: usize) -> Result<MidiTrack, MidiError> {
+        if bars == 0 { return Err(MidiError::param("bars must be > 0")); }
+        let total = self.bar_ticks() * bars as u64;
+        self.compose_for_ticks(total)
+    }
+
+    /// Length of one measure in ticks for the current time signature.
+    pub fn bar_ticks(&self) -> u64 {
+        let (num, den) = self.time_sig.unwrap_or((4, 4));
+        self.tpq as u64 * 4 * num as u64 / den as u64
+    }
+
+    /// `duration` cut to the next barline when quantizing to bars.
+    fn fit_to_bar(&mut self, duration: u32) -> u32 {
+        let bar = self.bar_ticks();
+        if !self.quantize_bars || bar == 0 { return duration; }
+        let duration = duration.min((bar - self.bar_pos).min(u32::MAX as u64) as u32);
+        self.bar_pos = (self.bar_pos + duration as u64) % bar;
+        duration
+    }
+
+    /// Ticks left in the measure the last note ended in, when quantizing.
+    fn bar_padding(&self) -> u32 {
+        match self.quantize_bars && self.bar_pos > 0 {
+            true  => (self.bar_ticks() - self.bar_pos) as u32,
+            false => 0,
+        }
+    }
+
+    /// Pad the last note out to its barline when quantizing.
+    fn pad_last_bar(&self, notes: &mut [Note]) {
+        if let Some(last) = notes.last_mut() {
+            last.resize(last.duration + self.bar_padding());
+        }
+    }
+
+    /// Compose `secs` seconds of music at the configured tempo, rounded to
+    /// the nearest tick.
+    pub fn compose_for_seconds(self, secs: f64) -> Result<MidiTrack, MidiError> {
+        if secs.is_nan() || secs <= 0.0 { return Err(MidiError::param("seconds must be > 0")); }
 
-        Ok(MidiTrack {
+        let ticks_per_sec = self.tempo_bpm * self.tpq as f64 / 60.0;
+        let total = (secs * ticks_per_sec).round().max(1.0) as u64;
+        self.compose_for_ticks(total)
+    }
+
+    /// Lazily resolve the zip stream into [`Note`]s, one pair at a time.
+    ///
+    /// Unlike [`compose`](Self::compose) no note count is needed up front,
+    /// so this suits live playback and arbitrarily long pieces.  The
+    /// iterator ends only if the underlying stream does.
+    pub fn compose_iter(self) -> NoteIter {
+        NoteIter { composer: self, chord: VecDeque::new() }
+    }
+
+    /// Up to `n` pairs from the source (fewer if it runs dry).
+    fn take_pairs(&mut self, n: usize) -> Vec<(u8, u8)> {
+        (0..n).map_while(|_| self.stream.next_pair()).collect()
+    }
+
+    /// Wrap resolved notes in a [`MidiTrack`] carrying this composer's settings.
+    fn finish(self, notes: Vec<Note>) -> MidiTrack {
+        let tie_bars = if self.tie_barlines { Some(self.bar_ticks()) } else { None };
+        let mut track = MidiTrack {
             notes,
             ticks_per_quarter: self.tpq,
             tempo_bpm:         self.tempo_bpm,
             instrument:        self.instrument,
             channel:           self.channel,
             description:       self.description,
-        })
+            running_status:    self.running_status,
+            splits:            self.splits,
+            pedal:             self.pedal,
+            bank:              self.bank,
+            tempo_changes:     Vec::new(),
+            time_signature:    self.time_sig,
+            provenance:        self.provenance,
+        };
+        if let Some(bar) = tie_bars { track.split_at_barlines(bar); }
+        track
+    }
+
+    /// Resolve a pair for a composed track, also recording its pedal state.
+    fn track_notes(&mut self, left: u8, right: u8) -> Vec<Note> {
+        let down = self.sustain.as_mut().map(|rule| match rule {
+            SustainRule::When(f)    => f(left, right),
+            SustainRule::Pattern(p) => p[self.note_index % p.len()],
+        });
+        let notes = self.notes_for_pair(left, right);
+        if let Some(down) = down {
+            self.pedal.extend(std::iter::repeat_n(down, notes.len()));
+        }
+        notes
+    }
+
+    /// The note for a pair, or its chord: every note but the last lasts
+    /// 0 ticks, so they all start together.
+    fn notes_for_pair(&mut self, left: u8, right: u8) -> Vec<Note> {
+        let note  = self.note_for_pair(left, right);
+        let steps = match &self.chord {
+            Some(shape) if !note.rest && !shape.steps().is_empty() => shape.steps(),
+            _ => return vec![note],
+        };
+        steps.iter().enumerate().map(|(k, &step)| Note {
+            pitch:    self.pitch_map.note_for(right.saturating_add(step)),
+            duration: if k + 1 == steps.len() { note.duration } else { 0 },
+            ..note.clone()
+        }).collect()
+    }
+
+    /// Resolve one `(left, right)` pair into a [`Note`].
+    fn note_for_pair(&mut self, left: u8, right: u8) -> Note {
+        let base = match self.velocity_stream.as_mut() {
+            Some(sp) => {
+                let d = sp.next_digit().unwrap_or(0);
+                self.velocity_map.velocity_for(d, d)
+            }
+            None => self.velocity_map.velocity_for(left, right),
+        } as i32;
+        let shape = self.dynamics.offset(self.note_index, self.dynamics_depth);
+        self.note_index += 1;
+        let duration = self.fit_to_bar(self.duration_map.ticks_for(left));
+        if self.rest.as_mut().is_some_and(|rest| rest(left, right)) {
+            return Note::rest(duration);
+        }
+        Note {
+            pitch:      self.pitch_map.note_for(right),
+            duration,
+            gate:       (duration as f32 * self.gate_ratio).round() as u32,
+            velocity:   (base + shape).clamp(1, 127) as u8,
+            release:    self.release.as_mut().map_or(0, |e| e.next_value(left, right)),
+            aftertouch: self.aftertouch.as_mut().map(|e| e.next_value(left, right)),
+            tied:       false,
+            rest:       false,
+        }
     }
 
     /// Like [`compose`] but apply a filter to the zip stream first:
     /// only pairs where `pred` returns true contribute notes.
     /// Exactly `n` pairs are *consumed* from the stream regardless.
     pub fn compose_filtered<P>(mut self, n: usize, mut pred: P)
-        -> Result<MidiTrack, String>
+        -> Result<MidiTrack, MidiError>
     where P: FnMut(u8, u8) -> bool
     {
-        if n == 0 { return Err("n must be > 0".to_string()); }
+        if n == 0 { return Err(MidiError::param("n must be > 0")); }
 
-        let pairs = self.stream.zip_take(n);
-        let notes: Vec<Note> = pairs.into_iter()
+        let pairs = self.take_pairs(n);
+        let mut notes: Vec<Note> = pairs.into_iter()
             .filter(|(l, r)| pred(*l, *r))
-            .map(|(left, right)| Note {
-                pitch:    self.pitch_map.note_for(right),
-                duration: self.duration_map.ticks_for(left),
-                velocity: self.velocity,
-            })
+            .flat_map(|(left, right)| self.track_notes(left, right))
             .collect();
 
         if notes.is_empty() {
-            return Err("filter rejected all notes".to_string());
+            return Err(MidiError::NoNotes("filter rejected all notes"));
         }
+        self.pad_last_bar(&mut notes);
 
-        Ok(MidiTrack {
-            notes,
-            ticks_per_quarter: self.tpq,
-            tempo_bpm:         self.tempo_bpm,
-            instrument:        self.instrument,
-            channel:           self.channel,
-            description:       self.description,
-        })
+        Ok(self.finish(notes))
+    }
+}
+
+/// Running totals passed to the [`MidiComposer::compose_until`] predicate.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ComposeStats {
+    /// Notes composed so far.
+    pub notes:   usize,
+    /// Total length in MIDI ticks.
+    pub ticks:   u64,
+    /// Total length in seconds at the composer's tempo.
+    pub seconds: f64,
+}
+
+/// Lazy note stream returned by [`MidiComposer::compose_iter`].
+pub struct NoteIter {
+    composer: MidiComposer,
+    /// The rest of a chord already resolved.
+    chord:    VecDeque<Note>,
+}
+
+impl Iterator for NoteIter {
+    type Item = Note;
+    fn next(&mut self) -> Option<Note> {
+        if self.chord.is_empty() {
+            let (left, right) = self.composer.stream.next_pair()?;
+            self.chord.extend(self.composer.notes_for_pair(left, right));
+        }
+        self.chord.pop_front()
     }
 }
 
@@ -896,6 +2154,21 @@ mod tests {
         assert_eq!(b, [0xFF, 0x7F]);
     }
 
+    #[test]
+    fn gaps_too_long_for_a_delta_are_bridged() {
+        let far = 2 * validate::VLQ_MAX + 5;
+        let mut t = Vec::new();
+        let mut encoder = Encoder::new(true);
+        encoder.encode(TimedEvent::channel(0, 0x90, &[60, 100]), &mut t);
+        encoder.encode(TimedEvent::channel(far, 0x80, &[60, 0]), &mut t);
+        assert_eq!(t, [
+            0x00, 0x90, 60, 100,
+            0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0x01, 0x00,
+            0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0x01, 0x00,
+            0x05, 0x90, 60, 0,
+        ]);
+    }
+
     // ── PitchMap ─────────────────────────────────────────────────────────
     #[test]
     fn pitch_map_major_root() {
@@ -950,6 +2223,14 @@ mod tests {
         for d in 0..10 { assert_eq!(dm.ticks_for(d), 240); }
     }
 
+    #[test]
+    fn duration_map_parse() {
+        let dm = DurationMap::parse("w h.. 8t 16 h+8 32.", 480).unwrap();
+        assert_eq!(dm.table, vec![1920, 1680, 160, 120, 1200, 90]);
+        assert!(DurationMap::parse("q x", 480).is_err());
+        assert!(DurationMap::parse("   ", 480).is_err());
+    }
+
     #[test]
     fn duration_map_wraps() {
         let dm = DurationMap::custom(vec![100, 200, 300]);
@@ -1021,6 +2302,353 @@ mod tests {
         assert_eq!(&bytes[n-3..], &[0xFF, 0x2F, 0x00]);
     }
 
+    #[test]
+    fn write_to_matches_to_bytes() {
+        let track = literal_track(true);
+        let mut buf = Vec::new();
+        track.write_to(&mut buf).unwrap();
+        assert_eq!(buf, track.to_bytes());
+    }
+
+    fn literal_track(running_status: bool) -> MidiTrack {
+        MidiTrack {
+            notes: vec![
+                Note::new(60, 480, 100),
+                Note::new(62, 240, 100),
+            ],
+            ticks_per_quarter: 480,
+            tempo_bpm:         120.0,
+            instrument:        0,
+            channel:           0,
+            description:       String::new(),
+            running_status,
+            splits:            Vec::new(),
+            pedal:             Vec::new(),
+            bank:              None,
+            tempo_changes:     Vec::new(),
+            time_signature:    None,
+            provenance:        None,
+        }
+    }
+
+    /// Note events following tempo, empty name and program change.
+    fn note_bytes(track: &MidiTrack) -> Vec<u8> {
+        let chunk = track.build_track_chunk();
+        let eot = chunk.len() - 4;
+        chunk[7 + 4 + 3..eot].to_vec()
+    }
+
+    #[test]
+    fn explicit_status_bytes() {
+        assert_eq!(note_bytes(&literal_track(false)), vec![
+            0x00, 0x90, 60, 100,  0x83, 0x60, 0x80, 60, 0,
+            0x00, 0x90, 62, 100,  0x81, 0x70, 0x80, 62, 0,
+        ]);
+    }
+
+    #[test]
+    fn gate_leaves_rests_and_zero_duration_makes_chords() {
+        let mut t = literal_track(false);
+        t.notes[0].gate = 240;                  // staccato: an eighth rest before D
+        assert_eq!(note_bytes(&t), vec![
+            0x00, 0x90, 60, 100,  0x81, 0x70, 0x80, 60, 0,
+            0x81, 0x70, 0x90, 62, 100,  0x81, 0x70, 0x80, 62, 0,
+        ]);
+
+        t.notes[0].duration = 0;                // C and D start together
+        t.notes[0].gate = 480;
+        assert_eq!(t.end_tick(), 480);
+        assert_eq!(note_bytes(&t), vec![
+            0x00, 0x90, 60, 100,  0x00, 0x90, 62, 100,
+            0x81, 0x70, 0x80, 62, 0,  0x81, 0x70, 0x80, 60, 0,
+        ]);
+
+        let track = MidiComposer::from_pairs(&[(5, 0)]).gate_ratio(0.5).compose(1).unwrap();
+        assert_eq!((track.notes[0].duration, track.notes[0].gate), (480, 240));
+    }
+
+    #[test]
+    fn gate_leaves_silence_before_the_next_note() {
+        let mut track = MidiComposer::from_pairs(&[(5, 0), (5, 1)]).gate(0.75).description("").compose(2).unwrap();
+        track.running_status = false;
+        assert_eq!(note_bytes(&track), vec![
+            0x00, 0x90, 60, 100,  0x82, 0x68, 0x80, 60, 0,
+            0x78, 0x90, 62, 100,  0x82, 0x68, 0x80, 62, 0,
+        ]);
+        let over = MidiComposer::from_pairs(&[(5, 0)]).gate(1.5).compose(1).unwrap();
+        assert_eq!(over.notes[0].gate, 480);
+    }
+
+    #[test]
+    fn rests_send_nothing_and_stretch_the_next_delta() {
+        let mut t = literal_track(false);
+        t.notes.insert(1, Note::rest(480));
+        assert_eq!(note_bytes(&t), vec![
+            0x00, 0x90, 60, 100,  0x83, 0x60, 0x80, 60, 0,
+            0x83, 0x60, 0x90, 62, 100,  0x81, 0x70, 0x80, 62, 0,
+        ]);
+        assert_eq!(t.analysis().notes, 2);
+
+        let track = MidiComposer::from_pairs(&[(0, 3), (5, 4), (0, 5)])
+            .rest_on(|l, _| l == 0).compose(3).unwrap();
+        let shape: Vec<(bool, u32)> = track.notes.iter().map(|n| (n.rest, n.duration)).collect();
+        assert_eq!(shape, [(true, 60), (false, 480), (true, 60)]);
+    }
+
+    #[test]
+    fn chords_stack_scale_steps_on_one_onset() {
+        let track = MidiComposer::from_pairs(&[(5, 0), (5, 2)])
+            .chords(ChordShape::Triad).sustain_pattern(&[true, false]).compose(2).unwrap();
+        let shape: Vec<(u8, u32, u32)> = track.notes.iter().map(|n| (n.pitch, n.duration, n.gate)).collect();
+        assert_eq!(shape, [(60, 0, 480), (64, 0, 480), (67, 480, 480),
+                           (64, 0, 480), (67, 0, 480), (71, 480, 480)]);
+        assert_eq!(track.pedal, [true, true, true, false, false, false]);
+        assert_eq!(track.end_tick(), 960);
+
+        let mut tied = MidiComposer::from_pairs(&[(9, 0)]).chords(ChordShape::Power).compose(1).unwrap();
+        tied.split_at_barlines(960);
+        assert_eq!(tied.notes.len(), 3);
+        let iter: Vec<u8> = MidiComposer::from_pairs(&[(5, 0), (5, 1)]).chords(ChordShape::Power)
+            .compose_iter().map(|n| n.pitch).collect();
+        assert_eq!(iter, [60, 67, 62, 69]);
+    }
+
+    #[test]
+    fn running_status_bytes() {
+        assert_eq!(note_bytes(&literal_track(true)), vec![
+            0x00, 0x90, 60, 100,  0x83, 0x60, 60, 0,
+            0x00, 62, 100,        0x81, 0x70, 62, 0,
+        ]);
+        let long = |rs| MidiComposer::new(
+                DualStream::new(Constant::Champernowne, Constant::Champernowne))
+            .running_status(rs).compose(500).unwrap().to_bytes().len();
+        assert!(long(true) * 10 < long(false) * 8);
+    }
+
+    // ── release velocity / aftertouch ────────────────────────────────────
+    #[test]
+    fn expressions_reach_note_events() {
+        // Champernowne pairs: (0,0), (1,1), (2,2) …
+        let ds = DualStream::new(Constant::Champernowne, Constant::Champernowne);
+        let track = MidiComposer::new(ds)
+            .release_velocity(Expression::pair(|l, r| l * 10 + r))
+            .aftertouch(Expression::digit(SpigotConfig::new(Constant::ThueMorse, 2)))
+            .running_status(false)
+            .compose(3)
+            .unwrap();
+        let rel: Vec<u8> = track.notes.iter().map(|n| n.release).collect();
+        let at:  Vec<Option<u8>> = track.notes.iter().map(|n| n.aftertouch).collect();
+        assert_eq!(rel, vec![0, 11, 22]);
+        assert_eq!(at, vec![Some(0), Some(127), Some(127)]);   // Thue–Morse 0,1,1
+
+        let chunk = track.build_track_chunk();
+        let has = |seq: &[u8]| chunk.windows(seq.len()).any(|w| w == seq);
+        assert!(has(&[0xD0, 127]));
+        assert!(has(&[0x80, track.notes[2].pitch, 22]));
+    }
+
+    // ── sustain pedal ────────────────────────────────────────────────────
+    #[test]
+    fn sustain_lane_emits_cc64_on_changes() {
+        let ds = DualStream::new(Constant::Champernowne, Constant::Champernowne);
+        let track = MidiComposer::new(ds)
+            .sustain_pattern(&[true, true, false])
+            .running_status(false)
+            .compose(4)
+            .unwrap();
+        assert_eq!(track.pedal, vec![true, true, false, true]);
+
+        let chunk = track.build_track_chunk();
+        let cc: Vec<u8> = chunk.windows(3)
+            .filter(|w| w[0] == 0xB0 && w[1] == 64)
+            .map(|w| w[2])
+            .collect();
+        assert_eq!(cc, vec![127, 0, 127, 0]);   // down, up, down, final release
+    }
+
+    #[test]
+    fn sustain_when_uses_pair() {
+        let ds = DualStream::new(Constant::Champernowne, Constant::Champernowne);
+        let track = MidiComposer::new(ds)
+            .sustain_when(|l, _| l >= 5)
+            .compose(7)
+            .unwrap();
+        assert_eq!(track.pedal, vec![false, false, false, false, false, true, true]);
+    }
+
+    // ── bank select ──────────────────────────────────────────────────────
+    #[test]
+    fn bank_select_precedes_program_change() {
+        let mut t = literal_track(false);
+        t.instrument = 48;
+        t.bank = Some((121, 1));
+        let chunk = t.build_track_chunk();
+        let seq = [0xB0, 0x00, 121, 0x00, 0xB0, 0x20, 1, 0x00, 0xC0, 48];
+        assert!(chunk.windows(seq.len()).any(|w| w == seq));
+    }
+
+    // ── GeneralMidi names ────────────────────────────────────────────────
+    #[test]
+    fn gm_from_program_round_trips() {
+        for p in 0..128u8 {
+            let gm = GeneralMidi::from_program(p).unwrap();
+            assert_eq!(gm.program(), p);
+            assert_ne!(gm.name(), "");
+        }
+        assert_eq!(GeneralMidi::from_program(128), None);
+        assert_eq!(GeneralMidi::Tuba.name(), "Tuba");
+    }
+
+    #[test]
+    fn gm_from_str() {
+        assert_eq!("vibraphone".parse::<GeneralMidi>().ok(), Some(GeneralMidi::Vibraphone));
+        assert_eq!("Lead 2".parse::<GeneralMidi>().ok(), Some(GeneralMidi::Lead2Sawtooth));
+        assert_eq!("acoustic guitar (steel)".parse::<GeneralMidi>().ok(), Some(GeneralMidi::AcousticGuitarSteel));
+        assert_eq!("33".parse::<GeneralMidi>().ok(), Some(GeneralMidi::ElectricBassFinger));
+        assert!(matches!("pad".parse::<GeneralMidi>(), Err(MidiError::Parameter(_))));  // ambiguous
+        assert!("kazoo".parse::<GeneralMidi>().is_err());
+    }
+
+    #[test]
+    fn scale_from_str() {
+        assert_eq!("Dorian".parse::<Scale>().unwrap().intervals, Scale::dorian().intervals);
+        assert_eq!("whole-tone".parse::<Scale>().unwrap().intervals, Scale::whole_tone().intervals);
+        assert!("bebop".parse::<Scale>().is_err());
+    }
+
+    // ── snippets / fixed pairs ───────────────────────────────────────────
+    #[test]
+    fn compose_from_snippet() {
+        let mut ds = DualStream::new(Constant::Champernowne, Constant::Champernowne);
+        ds.snip("intro", 3, 7).unwrap();
+        let track = MidiComposer::from_snippet(&ds, "intro").unwrap()
+            .pitch_map(PitchMap::chromatic(60))
+            .compose(10)
+            .unwrap();
+        let pitches: Vec<u8> = track.notes.iter().map(|n| n.pitch).collect();
+        assert_eq!(pitches, vec![63, 64, 65, 66]);
+        assert!(matches!(MidiComposer::from_snippet(&ds, "missing"),
+                         Err(MidiError::Stream(SpigotError::NoSnippet(_)))));
+    }
+
+    #[test]
+    fn pairs_support_side_ops() {
+        let pairs = [(0, 5), (1, 6), (2, 7)];
+        let notes: Vec<(u32, u8)> = MidiComposer::from_pairs(&pairs)
+            .pitch_map(PitchMap::chromatic(0))
+            .duration_map(DurationMap::linear(1, 10))
+            .drop_right(1)
+            .twist()
+            .compose_iter()
+            .map(|n| (n.duration, n.pitch))
+            .collect();
+        // After drop_right(1): left 0,1,2 / right 6,7; twist swaps sides.
+        assert_eq!(notes, vec![(7, 0), (8, 1)]);
+    }
+
+    #[test]
+    fn iter_source_is_lazy_and_twists() {
+        // An infinite external source: left counts up, right counts down.
+        let src = (0u8..).map(|i| (i % 10, 9 - i % 10));
+        let notes: Vec<(u32, u8)> = MidiComposer::from_pair_iter(src)
+            .pitch_map(PitchMap::chromatic(0))
+            .duration_map(DurationMap::linear(1, 10))
+            .drop_left(2)
+            .twist()
+            .compose_iter()
+            .take(3)
+            .map(|n| (n.duration, n.pitch))
+            .collect();
+        // Left 2,3,4… / right 9,8,7…; after twist durations come from the right.
+        assert_eq!(notes, vec![(10, 2), (9, 3), (8, 4)]);
+    }
+
+    // ── ties at barlines ─────────────────────────────────────────────────
+    #[test]
+    fn long_notes_tie_across_barlines() {
+        // 3/4 bar = 1440 ticks; durations 1000, 1000, 2000 → pieces
+        // 1000 | 440+560 | 880+1440… (last truncated by compose_for_ticks).
+        let track = MidiComposer::from_pairs(&[(0, 0), (0, 1), (1, 2)])
+            .duration_map(DurationMap::custom(vec![1000, 2000]))
+            .time_signature(3, 4)
+            .tie_at_barlines(true)
+            .compose_for_ticks(4 * 1440)
+            .unwrap();
+        let d: Vec<(u32, bool)> = track.notes.iter().map(|n| (n.duration, n.tied)).collect();
+        assert_eq!(d, vec![
+            (1000, false), (440, true), (560, false),
+            (880, true), (1440, true), (1440, false),
+        ]);
+        assert_eq!(track.total_ticks(), 4 * 1440);
+
+        // Tied pieces are not re-attacked: one Note On per original note.
+        let chunk = MidiTrack { running_status: false, ..track }.build_track_chunk();
+        let ons = chunk.windows(2).filter(|w| w[0] == 0x90 && w[1] < 128).count();
+        assert_eq!(ons, 3);
+    }
+
+    // ── register splitting ───────────────────────────────────────────────
+    #[test]
+    fn split_routes_by_register() {
+        let mut t = literal_track(false);
+        t.splits = vec![
+            RegisterSplit { below: 61, channel: 1, instrument: 32 },
+            RegisterSplit { below: 40, channel: 2, instrument: 58 },
+        ];
+        assert_eq!(t.route(30), (2, 58));
+        assert_eq!(t.route(60), (1, 32));
+        assert_eq!(t.route(62), (0, 0));
+
+        let chunk = t.build_track_chunk();
+        let has = |seq: &[u8]| chunk.windows(seq.len()).any(|w| w == seq);
+        assert!(has(&[0xC1, 32]) && has(&[0xC2, 58]));
+        assert!(has(&[0x91, 60, 100]));
+        assert!(has(&[0x90, 62, 100]));
+    }
+
+    // ── velocity map / dynamics ──────────────────────────────────────────
+    #[test]
+    fn velocity_map_and_crescendo() {
+        let ds = DualStream::new(Constant::Champernowne, Constant::Champernowne);
+        let v: Vec<u8> = MidiComposer::new(ds)
+            .velocity_map(VelocityMap::linear_left(10, 100, 10))
+            .compose(4).unwrap()
+            .notes.iter().map(|n| n.velocity).collect();
+        assert_eq!(v, vec![10, 20, 30, 40]);
+
+        let ds = DualStream::new(Constant::Champernowne, Constant::Champernowne);
+        let v: Vec<u8> = MidiComposer::new(ds)
+            .velocity(80)
+            .dynamics(Curve::Crescendo(3))
+            .dynamics_depth(40)
+            .compose(6).unwrap()
+            .notes.iter().map(|n| n.velocity).collect();
+        assert_eq!(v, vec![60, 80, 100, 60, 80, 100]);
+    }
+
+    #[test]
+    fn third_stream_drives_velocity() {
+        let velocities = |c: MidiComposer| -> Vec<u8> {
+            c.compose(4).unwrap().notes.iter().map(|n| n.velocity).collect()
+        };
+        let pairs = [(9, 9); 4];
+        let cfg = SpigotConfig::new(Constant::Champernowne, 10);
+        assert_eq!(velocities(MidiComposer::from_pairs(&pairs).velocity_stream(cfg)), [40, 48, 57, 66]);
+        assert_eq!(velocities(MidiComposer::from_pairs(&pairs).velocity_stream(cfg)
+            .velocity_map(VelocityMap::custom(vec![1, 2, 3]))), [1, 2, 3, 1]);
+
+        assert_eq!(VelocityMap::exponential(10, 80, 4), VelocityMap::Left(vec![10, 20, 40, 80]));
+        assert_eq!(VelocityMap::linear_left(0, 90, 4), VelocityMap::Left(vec![0, 30, 60, 90]));
+    }
+
+    #[test]
+    fn swell_peaks_mid_phrase() {
+        let c = Curve::Swell(5);
+        let o: Vec<i32> = (0..5).map(|i| c.offset(i, 20)).collect();
+        assert_eq!(o, vec![-10, 0, 10, 0, -10]);
+        assert_eq!(Curve::Custom(vec![5, -5]).offset(3, 0), -5);
+    }
+
     // ── velocity and instrument propagate ─────────────────────────────────
     #[test]
     fn velocity_propagates() {
@@ -1069,6 +2697,108 @@ mod tests {
         assert!(track.notes.len() <= 20);
     }
 
+    // ── compose_iter ─────────────────────────────────────────────────────
+    #[test]
+    fn compose_iter_is_lazy_and_matches_pairs() {
+        // Champernowne base 10: 0,1,2,3,4,… on both sides.
+        let ds = DualStream::new(Constant::Champernowne, Constant::Champernowne);
+        let notes: Vec<Note> = MidiComposer::new(ds)
+            .pitch_map(PitchMap::chromatic(60))
+            .duration_map(DurationMap::linear(10, 10))
+            .compose_iter()
+            .skip(1000)
+            .take(3)
+            .collect();
+        assert_eq!(notes.len(), 3);
+        assert!(notes.iter().all(|n| n.velocity == 100));
+
+        let first = MidiComposer::new(
+                DualStream::new(Constant::Champernowne, Constant::Champernowne))
+            .pitch_map(PitchMap::chromatic(60))
+            .duration_map(DurationMap::linear(10, 10))
+            .compose_iter()
+            .nth(3)
+            .unwrap();
+        assert_eq!(first, Note::new(63, 40, 100));
+    }
+
+    // ── duration-bounded composition ─────────────────────────────────────
+    #[test]
+    fn compose_until_keeps_triggering_note() {
+        let ds = DualStream::new(Constant::Champernowne, Constant::Champernowne);
+        let track = MidiComposer::new(ds)
+            .compose_until(|_, stats| stats.notes == 5)
+            .unwrap();
+        assert_eq!(track.notes.len(), 5);
+    }
+
+    #[test]
+    fn compose_for_ticks_is_exact() {
+        let ds = DualStream::new(Constant::Champernowne, Constant::ThueMorse);
+        let track = MidiComposer::new(ds)
+            .duration_map(DurationMap::linear(100, 10))
+            .compose_for_ticks(1234)
+            .unwrap();
+        assert_eq!(track.total_ticks(), 1234);
+        assert!(track.notes.iter().all(|n| n.duration > 0));
+    }
+
+    #[test]
+    fn compose_for_seconds_uses_tempo() {
+        // 2 s at 120 BPM, 480 tpq = 4 quarters = 1920 ticks.
+        let ds = DualStream::new(Constant::Champernowne, Constant::ThueMorse);
+        let track = MidiComposer::new(ds)
+            .tempo(120)
+            .compose_for_seconds(2.0)
+            .unwrap();
+        assert_eq!(track.total_ticks(), 1920);
+    }
+
+    #[test]
+    fn fractional_and_fast_tempos_encode_exactly() {
+        assert_eq!(tempo_micros(120.0), 500_000);
+        assert_eq!(tempo_micros(93.75), 640_000);
+        assert_eq!(tempo_micros(2400.0), 25_000);
+        let track = MidiComposer::from_pairs(&[(5, 0)]).tempo(2400).compose(1).unwrap();
+        assert_eq!(&track.to_bytes()[26..29], &[0x00, 0x61, 0xA8]);
+        assert!((track.seconds_at(480) - 0.025).abs() < 1e-9);
+    }
+
+    #[test]
+    fn compose_bars_ends_on_barline() {
+        // 3 bars of 6/8 at 480 tpq = 3 × 1440 ticks.
+        let ds = DualStream::new(Constant::Champernowne, Constant::Liouville);
+        let track = MidiComposer::new(ds)
+            .time_signature(6, 8)
+            .compose_bars(3)
+            .unwrap();
+        assert_eq!(track.total_ticks(), 3 * 1440);
+    }
+
+    #[test]
+    fn time_signature_meta_event() {
+        let bytes = MidiComposer::from_pairs(&[(5, 0)]).time_signature(6, 8).compose(1).unwrap().to_bytes();
+        // After the tempo: FF 58 04, 6/2^3, a dotted-quarter click, 8 32nds.
+        assert_eq!(&bytes[29..37], &[0x00, 0xFF, 0x58, 0x04, 6, 3, 36, 8]);
+        let plain = MidiComposer::from_pairs(&[(5, 0)]).compose(1).unwrap().to_bytes();
+        assert!(!plain.windows(2).any(|w| w == [0xFF, 0x58]));
+    }
+
+    #[test]
+    fn quantize_to_bars_keeps_measures_whole() {
+        // 3/4 bar = 1440: 1000, then 2000 cut to the barline, then 1000
+        // padded out to the next.
+        let track = MidiComposer::from_pairs(&[(0, 0), (1, 1), (0, 2)])
+            .duration_map(DurationMap::custom(vec![1000, 2000]))
+            .time_signature(3, 4)
+            .quantize_to_bars(true)
+            .compose(3)
+            .unwrap();
+        let d: Vec<u32> = track.notes.iter().map(|n| n.duration).collect();
+        assert_eq!(d, vec![1000, 440, 1440]);
+        assert_eq!(track.notes[2].gate, 1440);
+    }
+
     // ── multi-track ───────────────────────────────────────────────────────
     #[test]
     fn multi_track_format1_header() {
@@ -1,14 +1,117 @@
 //! Interactive menu for generating MIDI files from transcendental spigot streams.
+//!
+//! Given any command-line flags it runs non-interactively instead, e.g.
+//! `spigot_midi --left pi:16 --right e:10 --scale dorian --root 62 --notes 128 --out x.mid`.
+//! The file carries its own provenance (also written to `x.json`), and
+//! `spigot_midi --replay x.mid --out y.mid` composes it again, and
+//! `spigot_midi --repl` reads live-coding commands one line at a time.
 
+mod preset;
+mod repl;
+
+use clap::Parser;
+use preset::{parse_stream, Preset};
 use spigot_midi::{
     MidiComposer, PitchMap, DurationMap, GeneralMidi, Scale,
-    write_multi_track,
+    Provenance, write_multi_track,
 };
 use dual_spigot::{DualStream, SpigotConfig};
 use spigot_stream::Constant;
 use std::io::{self, Write};
 
+/// Generate a MIDI file from two spigot streams: the left stream's digits
+/// pick durations, the right stream's pick pitches.  Run without flags for
+/// the interactive menu.
+#[derive(Parser)]
+#[command(name = "spigot_midi")]
+struct Args {
+    /// Duration stream as CONSTANT[:BASE], e.g. `pi:16`, or a fraction
+    /// such as `1/7`.
+    #[arg(long, default_value = "pi", value_parser = parse_stream)]
+    left:        SpigotConfig,
+    /// Pitch stream as CONSTANT[:BASE], e.g. `e:10`.
+    #[arg(long, default_value = "e", value_parser = parse_stream)]
+    right:       SpigotConfig,
+    /// Scale name, e.g. `major`, `dorian`, `whole-tone`.
+    #[arg(long, default_value = "major")]
+    scale:       Scale,
+    /// Root note as a MIDI number (60 = middle C).
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u8).range(0..=127))]
+    root:        u8,
+    /// Number of notes.
+    #[arg(long, default_value_t = 64)]
+    notes:       usize,
+    /// Tempo in BPM.
+    #[arg(long, default_value_t = 120.0)]
+    tempo:       f64,
+    /// General MIDI program, by number or name.
+    #[arg(long, default_value = "0")]
+    instrument:  GeneralMidi,
+    /// Note values, one per digit, e.g. `"s e q q. h"` (default: musical).
+    #[arg(long)]
+    durations:   Option<String>,
+    /// Note velocity 0–127.
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u8).range(0..=127))]
+    velocity:    u8,
+    /// Ticks per quarter note.
+    #[arg(long, default_value_t = 480)]
+    tpq:         u16,
+    /// Output MIDI file.
+    #[arg(long, short, default_value = "output.mid")]
+    out:         String,
+    /// Also render a WAV file next to the MIDI file.
+    #[arg(long)]
+    wav:         bool,
+    /// Also write a MIDI 2.0 clip (`.midi2`) next to the MIDI file.
+    #[arg(long)]
+    ump:         bool,
+    /// Pitch mapper as NAME[:PARAM], e.g. `dorian:62` (instead of --scale).
+    #[arg(long, conflicts_with = "scale")]
+    pitch_mapper:    Option<String>,
+    /// Duration mapper as NAME[:PARAM], e.g. `linear:120` (instead of
+    /// --durations).
+    #[arg(long, conflicts_with = "durations")]
+    duration_mapper: Option<String>,
+    /// Velocity mapper as NAME[:PARAM], e.g. `left:40-110` (instead of
+    /// --velocity).
+    #[arg(long, conflicts_with = "velocity")]
+    velocity_mapper: Option<String>,
+    /// Third stream as CONSTANT[:BASE] whose digits set each note's
+    /// velocity, 40–120 unless --velocity-mapper says otherwise.
+    #[arg(long, conflicts_with = "velocity", value_parser = parse_stream)]
+    velocity_stream: Option<SpigotConfig>,
+    /// Part of each note that sounds, 0.0–1.0: below 1 leaves a rest
+    /// before the next note (staccato).
+    #[arg(long, default_value_t = 1.0, value_parser = parse_gate)]
+    gate:            f32,
+    /// Digits of the left stream to skip before the first note.
+    #[arg(long, default_value_t = 0)]
+    left_offset:     usize,
+    /// Digits of the right stream to skip before the first note.
+    #[arg(long, default_value_t = 0)]
+    right_offset:    usize,
+    /// Compose again from the provenance embedded in a MIDI file written
+    /// by this tool; only --out, --wav and --ump still apply.
+    #[arg(long, value_name = "FILE")]
+    replay:          Option<String>,
+    /// Leave out the provenance block and its `.json` file.
+    #[arg(long)]
+    no_provenance:   bool,
+    /// Start the live-coding REPL on --left and --right instead of
+    /// writing a file.
+    #[arg(long)]
+    repl:            bool,
+}
+
 fn main() {
+    if std::env::args_os().len() > 1 {
+        if let Err(e) = run_batch(Args::parse()) {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     println!();
     println!("╔══════════════════════════════════════════════════════════╗");
     println!("║        Transcendental Spigot MIDI Composer               ║");
@@ -21,6 +124,8 @@ fn main() {
         println!("    1. Compose single-track MIDI");
         println!("    2. Compose two-track (duet) MIDI");
         println!("    3. Quick demo (π/e → piano, C major, 64 notes)");
+        println!("    4. Compose from a saved preset");
+        println!("    5. Live-coding REPL");
         println!("    q. Quit");
         println!();
 
@@ -28,96 +133,258 @@ fn main() {
             "1" => compose_single(),
             "2" => compose_duet(),
             "3" => quick_demo(),
+            "4" => compose_from_preset(),
+            "5" => repl::run(DualStream::new(Constant::Pi, Constant::E)),
             "q" | "quit" => { println!("\nGoodbye!\n"); break; }
-            _   => println!("  ⚠  Enter 1–3 or q.\n"),
+            _   => println!("  ⚠  Enter 1–5 or q.\n"),
         }
         println!();
     }
 }
 
+/// Compose and write one file from command-line flags.
+fn run_batch(args: Args) -> Result<(), String> {
+    if !(args.tempo.is_finite() && args.tempo > 0.0) {
+        return Err(format!("tempo must be positive, got {}", args.tempo));
+    }
+    if args.repl {
+        repl::run(DualStream::from_configs(args.left, args.right));
+        return Ok(());
+    }
+    let p = match &args.replay {
+        Some(file) => {
+            let p = Provenance::read_midi(file).map_err(|e| format!("{}: {}", file, e))?;
+            if !p.same_version() {
+                eprintln!("warning: {} was composed by spigot_midi {}; this is {}, so the notes may differ",
+                    file, p.version, env!("CARGO_PKG_VERSION"));
+            }
+            p
+        }
+        None => provenance(&args),
+    };
+    let mut track = p.compose().map_err(|e| e.to_string())?;
+    if args.no_provenance { track.provenance = None; }
+
+    for issue in track.validate() {
+        eprintln!("{}", issue);
+    }
+    track.write_file(&args.out).map_err(|e| format!("{}: {}", args.out, e))?;
+    println!("wrote {} notes to {}", track.notes.len(), args.out);
+
+    let sibling = |ext: &str| match args.out.strip_suffix(".mid") {
+        Some(stem) => format!("{}.{}", stem, ext),
+        None       => format!("{}.{}", args.out, ext),
+    };
+    if !args.no_provenance {
+        let json = sibling("json");
+        p.write_json(&json).map_err(|e| format!("{}: {}", json, e))?;
+        println!("wrote its provenance to {}", json);
+    }
+    if args.wav {
+        let wav = sibling("wav");
+        track.render_wav(&wav, 44_100).map_err(|e| format!("{}: {}", wav, e))?;
+        println!("rendered audio to {}", wav);
+    }
+    if args.ump {
+        let clip = sibling("midi2");
+        track.write_ump_file(&clip).map_err(|e| format!("{}: {}", clip, e))?;
+        println!("wrote a MIDI 2.0 clip to {}", clip);
+    }
+    Ok(())
+}
+
+/// The flags as a recipe: maps by name, so the file can say how it was made.
+fn provenance(args: &Args) -> Provenance {
+    let mut p = Provenance::new(args.left, args.right);
+    p.left_offset  = args.left_offset;
+    p.right_offset = args.right_offset;
+    p.notes        = args.notes;
+    p.tempo        = args.tempo;
+    p.program      = args.instrument.program();
+    p.ticks_per_quarter = args.tpq;
+    p.root         = args.root;
+    p.pitch        = args.pitch_mapper.clone().unwrap_or_else(|| args.scale.name.to_string());
+    p.duration     = match (&args.duration_mapper, &args.durations) {
+        (Some(spec), _)    => spec.clone(),
+        (None, Some(spec)) => format!("values:{}", spec),
+        (None, None)       => "musical".to_string(),
+    };
+    p.velocity     = match (&args.velocity_mapper, args.velocity_stream) {
+        (Some(spec), _) => spec.clone(),
+        (None, Some(_)) => "left".to_string(),
+        (None, None)    => format!("fixed:{}", args.velocity),
+    };
+    p.velocity_stream = args.velocity_stream;
+    p.gate         = args.gate;
+    p.description  = format!("{} / {} – {} notes @ {} BPM",
+        args.left.constant.name(), args.right.constant.name(), args.notes, args.tempo);
+    p
+}
+
+fn parse_gate(s: &str) -> Result<f32, String> {
+    s.parse().ok().filter(|g: &f32| (0.0..=1.0).contains(g))
+        .ok_or_else(|| format!("gate must be 0.0–1.0, got '{}'", s))
+}
+
 fn compose_single() {
     println!("\n  ── Single-track composer ──");
 
-    let left_cfg  = pick_config("LEFT  (duration)");
-    let right_cfg = pick_config("RIGHT (pitch)");
-    let ds = DualStream::from_configs(left_cfg, right_cfg);
+    let left  = pick_config("LEFT  (duration)");
+    let right = pick_config("RIGHT (pitch)");
 
-    let bpm: u32 = read_line("  Tempo BPM (default 120): ")
-        .trim().parse().unwrap_or(120);
-    let bpm = bpm.max(20).min(300);
+    let bpm: f64 = read_line("  Tempo BPM (default 120): ")
+        .trim().parse().unwrap_or(120.0);
+    let bpm = if bpm.is_finite() && bpm > 0.0 { bpm } else { 120.0 };
 
-    let instrument = pick_instrument();
-    let pitch_map  = pick_pitch_map();
-    let dur_map    = pick_duration_map();
+    let instrument        = pick_instrument();
+    let (root, pitch)     = pick_pitch_map();
+    let (tpq, duration)   = pick_duration_map();
 
     let n: usize = read_line("  Number of notes (default 64): ")
         .trim().parse().unwrap_or(64);
-    let n = n.max(1).min(10_000);
+    let n = n.clamp(1, 10_000);
 
     let velocity: u8 = read_line("  Velocity 0–127 (default 100): ")
         .trim().parse().unwrap_or(100);
 
+    let preset = Preset {
+        left, right, tempo: bpm, instrument, root, pitch, duration,
+        ticks_per_quarter: tpq, notes: n, velocity,
+    };
+    compose_preset(&preset);
+
+    let name = read_line("  Save these choices as a preset? Name (blank to skip): ");
+    let name = name.trim();
+    if !name.is_empty() {
+        match preset.save(name) {
+            Ok(path) => println!("  ✓  Saved preset to '{}'\n", path.display()),
+            Err(e)   => println!("  ⚠  File error: {}", e),
+        }
+    }
+}
+
+fn compose_from_preset() {
+    let names = Preset::list();
+    if names.is_empty() {
+        println!("  ⚠  No presets saved yet — compose a single track first.");
+        return;
+    }
+    println!("\n  Saved presets:");
+    for (i, name) in names.iter().enumerate() {
+        println!("    {}. {}", i + 1, name);
+    }
+    let choice = read_line("  Choice (number or name): ");
+    let choice = choice.trim();
+    let name = match choice.parse::<usize>() {
+        Ok(i) if (1..=names.len()).contains(&i) => names[i - 1].as_str(),
+        _ => choice,
+    };
+    match Preset::load(name) {
+        Ok(preset) => compose_preset(&preset),
+        Err(e)     => println!("  ⚠  {}", e),
+    }
+}
+
+/// Compose a single track from `preset` and write it where the user asks.
+fn compose_preset(preset: &Preset) {
     let filename = read_line("  Output filename (default: output.mid): ")
         .trim().to_string();
     let filename = if filename.is_empty() { "output.mid".to_string() } else { filename };
 
-    let desc = format!("{} / {} – {} notes @ {} BPM",
-        left_cfg.constant.name(), right_cfg.constant.name(), n, bpm);
-
-    let result = MidiComposer::new(ds)
-        .tempo(bpm)
-        .instrument_raw(instrument)
-        .pitch_map(pitch_map)
-        .duration_map(dur_map)
-        .velocity(velocity)
-        .description(&desc)
-        .compose(n);
-
-    match result {
+    match preset.composer().and_then(|c| c.compose(preset.notes)) {
         Err(e) => println!("  ⚠  Error: {}", e),
         Ok(track) => {
+            for issue in track.validate() {
+                println!("  ⚠  {}", issue);
+            }
+            println!();
+            for line in track.analysis().to_string().lines() {
+                println!("  {}", line);
+            }
             match track.write_file(&filename) {
-                Ok(_)  => println!("\n  ✓  Written {} notes to '{}'\n", n, filename),
+                Ok(_)  => println!("\n  ✓  Written {} notes to '{}'\n", preset.notes, filename),
                 Err(e) => println!("  ⚠  File error: {}", e),
             }
+            offer_wav(&track, &filename);
+            #[cfg(feature = "playback")]
+            offer_playback(&track);
         }
     }
 }
 
+/// Ask whether to also render the track to audio with the built-in synth.
+fn offer_wav(track: &spigot_midi::MidiTrack, midi_filename: &str) {
+    let answer = read_line("  Also render a WAV audio file? (y/N): ");
+    if !answer.trim().eq_ignore_ascii_case("y") { return; }
+    let wav = match midi_filename.strip_suffix(".mid") {
+        Some(stem) => format!("{}.wav", stem),
+        None       => format!("{}.wav", midi_filename),
+    };
+    match track.render_wav(&wav, 44_100) {
+        Ok(_)  => println!("  ✓  Rendered audio to '{}'\n", wav),
+        Err(e) => println!("  ⚠  File error: {}", e),
+    }
+}
+
+/// Ask whether to audition the track on a MIDI output port right away.
+#[cfg(feature = "playback")]
+fn offer_playback(track: &spigot_midi::MidiTrack) {
+    let ports = match spigot_midi::playback::output_ports() {
+        Ok(p) if !p.is_empty() => p,
+        _ => return,
+    };
+    let answer = read_line("  Play it now? (y/N): ");
+    if !answer.trim().eq_ignore_ascii_case("y") { return; }
+    for (i, name) in ports.iter().enumerate() {
+        println!("    {}. {}", i + 1, name);
+    }
+    let i: usize = read_line("  Port (default 1): ").trim().parse().unwrap_or(1);
+    println!("  ♪  Playing…");
+    match track.play(i.saturating_sub(1)) {
+        Ok(_)  => println!("  ✓  Done.\n"),
+        Err(e) => println!("  ⚠  Playback error: {}", e),
+    }
+}
+
 fn compose_duet() {
     println!("\n  ── Two-track duet composer ──");
     println!("  Track 1 (melody):");
     let l1 = pick_config("    LEFT  (duration)");
     let r1 = pick_config("    RIGHT (pitch)");
-    let ds1 = DualStream::from_configs(l1, r1);
     let inst1  = pick_instrument();
-    let pmap1  = pick_pitch_map();
-    let dmap1  = pick_duration_map();
+    let (root1, pitch1)    = pick_pitch_map();
+    let (tpq1, duration1)  = pick_duration_map();
 
     println!("\n  Track 2 (bass / accompaniment):");
     let l2 = pick_config("    LEFT  (duration)");
     let r2 = pick_config("    RIGHT (pitch)");
-    let ds2 = DualStream::from_configs(l2, r2);
     let inst2  = pick_instrument();
-    let pmap2  = pick_pitch_map();
-    let dmap2  = pick_duration_map();
+    let (root2, pitch2)    = pick_pitch_map();
+    let (tpq2, duration2)  = pick_duration_map();
 
-    let bpm: u32 = read_line("  Tempo BPM (default 100): ")
-        .trim().parse().unwrap_or(100);
+    let bpm: f64 = read_line("  Tempo BPM (default 100): ")
+        .trim().parse().unwrap_or(100.0);
+    let bpm = if bpm.is_finite() && bpm > 0.0 { bpm } else { 100.0 };
     let n: usize = read_line("  Notes per track (default 32): ")
         .trim().parse().unwrap_or(32);
     let filename = read_line("  Output filename (default: duet.mid): ")
         .trim().to_string();
     let filename = if filename.is_empty() { "duet.mid".to_string() } else { filename };
 
-    let t1 = MidiComposer::new(ds1)
-        .tempo(bpm).instrument_raw(inst1).pitch_map(pmap1)
-        .duration_map(dmap1).channel(0).description("Track 1")
-        .compose(n);
-    let t2 = MidiComposer::new(ds2)
-        .tempo(bpm).instrument_raw(inst2).pitch_map(pmap2)
-        .duration_map(dmap2).channel(1).description("Track 2")
-        .compose(n);
+    let track = |preset: Preset, channel: u8, description: &str| {
+        let mut p = preset.provenance();
+        p.channel = channel;
+        p.description = description.to_string();
+        p.compose()
+    };
+    let t1 = track(Preset {
+        left: l1, right: r1, tempo: bpm, instrument: inst1, root: root1, pitch: pitch1,
+        duration: duration1, ticks_per_quarter: tpq1, notes: n, velocity: 100,
+    }, 0, "Track 1");
+    let t2 = track(Preset {
+        left: l2, right: r2, tempo: bpm, instrument: inst2, root: root2, pitch: pitch2,
+        duration: duration2, ticks_per_quarter: tpq2, notes: n, velocity: 100,
+    }, 1, "Track 2");
 
     match (t1, t2) {
         (Ok(track1), Ok(track2)) => {
@@ -157,14 +424,16 @@ fn pick_config(label: &str) -> SpigotConfig {
         for (i, c) in Constant::all().iter().enumerate() {
             println!("    {}. {}", i+1, c.name());
         }
-        match read_line("  Choice (1–6): ").trim() {
+        match read_line("  Choice (1–8): ").trim() {
             "1" => break Constant::Pi,
             "2" => break Constant::E,
             "3" => break Constant::Ln2,
             "4" => break Constant::Liouville,
             "5" => break Constant::Champernowne,
             "6" => break Constant::ThueMorse,
-            _   => println!("  ⚠  Enter 1–6."),
+            "7" => break Constant::Sqrt2,
+            "8" => break Constant::Phi,
+            _   => println!("  ⚠  Enter 1–8."),
         }
     };
     let base: u8 = loop {
@@ -213,44 +482,11 @@ fn pick_from_range(label: &str, lo: u8, hi: u8) -> u8 {
 }
 
 fn gm_name(p: u8) -> &'static str {
-    match p {
-        0  => "Acoustic Grand Piano",  1  => "Bright Acoustic Piano",
-        2  => "Electric Grand Piano",  3  => "Honky-Tonk Piano",
-        4  => "Electric Piano 1",      5  => "Electric Piano 2",
-        6  => "Harpsichord",           7  => "Clavinet",
-        8  => "Celesta",               9  => "Glockenspiel",
-        10 => "Music Box",             11 => "Vibraphone",
-        12 => "Marimba",               13 => "Xylophone",
-        14 => "Tubular Bells",         15 => "Dulcimer",
-        40 => "Violin",                41 => "Viola",
-        42 => "Cello",                 43 => "Contrabass",
-        44 => "Tremolo Strings",       45 => "Pizzicato Strings",
-        46 => "Orchestral Harp",       47 => "Timpani",
-        56 => "Trumpet",               57 => "Trombone",
-        58 => "Tuba",                  59 => "Muted Trumpet",
-        60 => "French Horn",           61 => "Brass Section",
-        62 => "Synth Brass 1",         63 => "Synth Brass 2",
-        64 => "Soprano Sax",           65 => "Alto Sax",
-        66 => "Tenor Sax",             67 => "Baritone Sax",
-        68 => "Oboe",                  69 => "English Horn",
-        70 => "Bassoon",               71 => "Clarinet",
-        72 => "Piccolo",               73 => "Flute",
-        74 => "Recorder",              75 => "Pan Flute",
-        76 => "Blown Bottle",          77 => "Shakuhachi",
-        78 => "Whistle",               79 => "Ocarina",
-        80 => "Lead 1 (Square)",       81 => "Lead 2 (Sawtooth)",
-        82 => "Lead 3 (Calliope)",     83 => "Lead 4 (Chiff)",
-        84 => "Lead 5 (Charang)",      85 => "Lead 6 (Voice)",
-        86 => "Lead 7 (Fifths)",       87 => "Lead 8 (Bass+Lead)",
-        88 => "Pad 1 (New Age)",       89 => "Pad 2 (Warm)",
-        90 => "Pad 3 (Polysynth)",     91 => "Pad 4 (Choir)",
-        92 => "Pad 5 (Bowed)",         93 => "Pad 6 (Metallic)",
-        94 => "Pad 7 (Halo)",          95 => "Pad 8 (Sweep)",
-        _  => "—",
-    }
+    GeneralMidi::from_program(p).map_or("—", GeneralMidi::name)
 }
 
-fn pick_pitch_map() -> PitchMap {
+/// The root note and a pitch mapper spec.
+fn pick_pitch_map() -> (u8, String) {
     let root: u8 = {
         let r = read_line("  Root note MIDI# (0–127, default 60 = middle C): ")
             .trim().parse::<u8>().unwrap_or(60);
@@ -261,20 +497,22 @@ fn pick_pitch_map() -> PitchMap {
     println!("    2. Minor          6. Phrygian");
     println!("    3. Pentatonic Maj 7. Whole Tone");
     println!("    4. Pentatonic Min 8. Chromatic");
-    match read_line("  Choice (default 1): ").trim() {
-        "2" => PitchMap::minor(root),
-        "3" => PitchMap::pentatonic_major(root),
-        "4" => PitchMap::pentatonic_minor(root),
-        "5" => PitchMap::custom(root, Scale::dorian()),
-        "6" => PitchMap::custom(root, Scale::phrygian()),
-        "7" => PitchMap::custom(root, Scale::whole_tone()),
-        "8" => PitchMap::chromatic(root),
-        _   => PitchMap::major(root),
-    }
+    let scale = match read_line("  Choice (default 1): ").trim() {
+        "2" => Scale::minor(),
+        "3" => Scale::pentatonic_major(),
+        "4" => Scale::pentatonic_minor(),
+        "5" => Scale::dorian(),
+        "6" => Scale::phrygian(),
+        "7" => Scale::whole_tone(),
+        "8" => Scale::chromatic(),
+        _   => Scale::major(),
+    };
+    (root, scale.name.to_string())
 }
 
-fn pick_duration_map() -> DurationMap {
-    let tpq: u32 = read_line("  Ticks per quarter note (default 480): ")
+/// Ticks per quarter note and a duration mapper spec.
+fn pick_duration_map() -> (u16, String) {
+    let tpq: u16 = read_line("  Ticks per quarter note (default 480): ")
         .trim().parse().unwrap_or(480);
     let tpq = tpq.max(24).min(9600);
     println!("  Duration mapping:");
@@ -282,12 +520,24 @@ fn pick_duration_map() -> DurationMap {
     println!("    2. Linear (digit+1 × unit)");
     println!("    3. Exponential (unit × 2^digit)");
     println!("    4. Fixed (every note same length)");
-    match read_line("  Choice (default 1): ").trim() {
-        "2" => DurationMap::linear(tpq / 4, 10),
-        "3" => DurationMap::exponential(tpq / 8, 10),
-        "4" => DurationMap::fixed(tpq, 10),
-        _   => DurationMap::musical(tpq),
-    }
+    println!("    5. Custom note values (e.g. \"8 8 q q. h\")");
+    let spec = match read_line("  Choice (default 1): ").trim() {
+        "2" => "linear".to_string(),
+        "3" => "exponential".to_string(),
+        "4" => "fixed".to_string(),
+        "5" => {
+            let values = read_line("  Note values, one per digit: ");
+            match DurationMap::parse(&values, tpq as u32) {
+                Ok(_)  => format!("values:{}", values.trim()),
+                Err(e) => {
+                    println!("  ⚠  {} — using musical values.", e);
+                    "musical".to_string()
+                }
+            }
+        }
+        _   => "musical".to_string(),
+    };
+    (tpq, spec)
 }
 
 fn read_line(prompt: &str) -> String {
@@ -0,0 +1,288 @@
+//! Mapping strategies as traits, chosen by name at run time.
+//!
+//! [`MidiComposer`](crate::MidiComposer) turns each digit pair into a note
+//! through three mappers: a [`PitchMapper`] for the right digit, a
+//! [`DurationMapper`] for the left, and a [`VelocityMapper`] for both.
+//! [`PitchMap`], [`DurationMap`] and [`VelocityMap`] are the built-in
+//! implementations; another crate can supply its own without touching this
+//! one.
+//!
+//! A [`MapperRegistry`] names the strategies, so a command line or config
+//! file can pick them with a string `NAME[:PARAM]`:
+//!
+//! | Kind | Built-in names |
+//! |---|---|
+//! | pitch | every [`Scale`] name (`major`, `dorian:62`, `whole-tone`, …), `custom:0 3 5 7 10` |
+//! | duration | `musical`, `linear[:UNIT]`, `exponential[:UNIT]`, `fixed[:TICKS]`, `values:s e q q. h` |
+//! | velocity | `fixed[:V]`, `left[:LO-HI]`, `right[:LO-HI]` |
+//!
+//! A pitch parameter is the root note (the context's root otherwise);
+//! names ignore case, spaces and punctuation.
+//!
+//! ```rust
+//! use spigot_midi::mapper::{MapperArgs, MapperRegistry, PitchMapper};
+//!
+//! struct Octaves;
+//! impl PitchMapper for Octaves {
+//!     fn note_for(&self, digit: u8) -> u8 { 24 + 12 * (digit % 8) }
+//! }
+//!
+//! let mut registry = MapperRegistry::with_builtins();
+//! registry.register_pitch("octaves", |_, _| Ok(Box::new(Octaves)));
+//!
+//! let args = MapperArgs::default();
+//! assert_eq!(registry.pitch("octaves", &args).unwrap().note_for(2), 48);
+//! assert_eq!(registry.pitch("dorian:62", &args).unwrap().note_for(1), 64);
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::{normalize_name, DurationMap, MidiError, PitchMap, Scale, VelocityMap};
+
+/// Maps a right-hand digit to a MIDI note number (0–127).
+pub trait PitchMapper: Send {
+    fn note_for(&self, digit: u8) -> u8;
+}
+
+/// Maps a left-hand digit to a duration in MIDI ticks.
+pub trait DurationMapper: Send {
+    fn ticks_for(&self, digit: u8) -> u32;
+}
+
+/// Maps a digit pair to a note's base velocity (0–127).
+pub trait VelocityMapper: Send {
+    fn velocity_for(&self, left: u8, right: u8) -> u8;
+}
+
+impl PitchMapper for PitchMap {
+    fn note_for(&self, digit: u8) -> u8 { PitchMap::note_for(self, digit) }
+}
+
+impl DurationMapper for DurationMap {
+    fn ticks_for(&self, digit: u8) -> u32 { DurationMap::ticks_for(self, digit) }
+}
+
+impl VelocityMapper for VelocityMap {
+    fn velocity_for(&self, left: u8, right: u8) -> u8 { VelocityMap::velocity_for(self, left, right) }
+}
+
+impl<M: PitchMapper + ?Sized> PitchMapper for Box<M> {
+    fn note_for(&self, digit: u8) -> u8 { (**self).note_for(digit) }
+}
+
+impl<M: DurationMapper + ?Sized> DurationMapper for Box<M> {
+    fn ticks_for(&self, digit: u8) -> u32 { (**self).ticks_for(digit) }
+}
+
+impl<M: VelocityMapper + ?Sized> VelocityMapper for Box<M> {
+    fn velocity_for(&self, left: u8, right: u8) -> u8 { (**self).velocity_for(left, right) }
+}
+
+/// What a factory knows about the piece it builds a mapper for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MapperArgs {
+    /// Root note for pitch mappers.
+    pub root: u8,
+    /// Base of the left (duration) stream.
+    pub left_base: u8,
+    /// Base of the right (pitch) stream.
+    pub right_base: u8,
+    /// MIDI resolution, for duration mappers.
+    pub ticks_per_quarter: u32,
+}
+
+impl Default for MapperArgs {
+    /// Middle C, base 10 on both sides, 480 ticks per quarter.
+    fn default() -> Self {
+        MapperArgs { root: 60, left_base: 10, right_base: 10, ticks_per_quarter: 480 }
+    }
+}
+
+/// Builds a mapper from the context and the text after the name's `:`.
+pub type Factory<T> = Box<dyn Fn(&MapperArgs, Option<&str>) -> Result<T, MidiError> + Send + Sync>;
+
+/// Pitch, duration and velocity mappers by name.
+#[derive(Default)]
+pub struct MapperRegistry {
+    pitch:    BTreeMap<String, (String, Factory<Box<dyn PitchMapper>>)>,
+    duration: BTreeMap<String, (String, Factory<Box<dyn DurationMapper>>)>,
+    velocity: BTreeMap<String, (String, Factory<Box<dyn VelocityMapper>>)>,
+}
+
+impl MapperRegistry {
+    /// An empty registry.
+    pub fn new() -> Self { Self::default() }
+
+    /// A registry holding the built-in maps (see the module docs).
+    pub fn with_builtins() -> Self {
+        let mut r = Self::new();
+        for scale in Scale::all() {
+            r.register_pitch(scale.name, move |args, param| {
+                Ok(Box::new(PitchMap::custom(root(args, param)?, scale.clone())))
+            });
+        }
+        r.register_pitch("custom", |args, param| {
+            let intervals = param.and_then(|p| numbers(p).ok())
+                .filter(|v: &Vec<u8>| !v.is_empty() && v.iter().all(|&i| i < 128))
+                .ok_or_else(|| MidiError::param("custom needs semitone offsets, e.g. custom:0 3 5 7 10"))?;
+            Ok(Box::new(PitchMap::custom(args.root, Scale::custom(intervals))))
+        });
+
+        r.register_duration("musical", |args, _| Ok(Box::new(DurationMap::musical(args.ticks_per_quarter))));
+        r.register_duration("linear", |args, param| {
+            Ok(Box::new(DurationMap::linear(ticks(param, args.ticks_per_quarter / 4)?, args.left_base)))
+        });
+        r.register_duration("exponential", |args, param| {
+            Ok(Box::new(DurationMap::exponential(ticks(param, args.ticks_per_quarter / 8)?, args.left_base)))
+        });
+        r.register_duration("fixed", |args, param| {
+            Ok(Box::new(DurationMap::fixed(ticks(param, args.ticks_per_quarter)?, args.left_base)))
+        });
+        r.register_duration("values", |args, param| {
+            Ok(Box::new(DurationMap::parse(param.unwrap_or(""), args.ticks_per_quarter)?))
+        });
+
+        r.register_velocity("fixed", |_, param| {
+            let v = param.map_or(Ok(100), |p| p.trim().parse().map_err(|_| bad_param("velocity", p)))?;
+            Ok(Box::new(VelocityMap::Fixed(u8::min(v, 127))))
+        });
+        r.register_velocity("left", |args, param| {
+            let (lo, hi) = span(param)?;
+            Ok(Box::new(VelocityMap::linear_left(lo, hi, args.left_base)))
+        });
+        r.register_velocity("right", |args, param| {
+            let (lo, hi) = span(param)?;
+            Ok(Box::new(VelocityMap::linear_right(lo, hi, args.right_base)))
+        });
+        r
+    }
+
+    /// Add or replace the pitch mapper called `name`.
+    pub fn register_pitch<F>(&mut self, name: &str, factory: F)
+    where F: Fn(&MapperArgs, Option<&str>) -> Result<Box<dyn PitchMapper>, MidiError> + Send + Sync + 'static {
+        self.pitch.insert(normalize_name(name), (name.to_string(), Box::new(factory)));
+    }
+
+    /// Add or replace the duration mapper called `name`.
+    pub fn register_duration<F>(&mut self, name: &str, factory: F)
+    where F: Fn(&MapperArgs, Option<&str>) -> Result<Box<dyn DurationMapper>, MidiError> + Send + Sync + 'static {
+        self.duration.insert(normalize_name(name), (name.to_string(), Box::new(factory)));
+    }
+
+    /// Add or replace the velocity mapper called `name`.
+    pub fn register_velocity<F>(&mut self, name: &str, factory: F)
+    where F: Fn(&MapperArgs, Option<&str>) -> Result<Box<dyn VelocityMapper>, MidiError> + Send + Sync + 'static {
+        self.velocity.insert(normalize_name(name), (name.to_string(), Box::new(factory)));
+    }
+
+    /// The pitch mapper for `spec`, written `NAME[:PARAM]`.
+    pub fn pitch(&self, spec: &str, args: &MapperArgs) -> Result<Box<dyn PitchMapper>, MidiError> {
+        build(&self.pitch, "pitch", spec, args)
+    }
+
+    /// The duration mapper for `spec`, written `NAME[:PARAM]`.
+    pub fn duration(&self, spec: &str, args: &MapperArgs) -> Result<Box<dyn DurationMapper>, MidiError> {
+        build(&self.duration, "duration", spec, args)
+    }
+
+    /// The velocity mapper for `spec`, written `NAME[:PARAM]`.
+    pub fn velocity(&self, spec: &str, args: &MapperArgs) -> Result<Box<dyn VelocityMapper>, MidiError> {
+        build(&self.velocity, "velocity", spec, args)
+    }
+
+    /// Registered pitch mapper names, as registered.
+    pub fn pitch_names(&self) -> Vec<&str> { names(&self.pitch) }
+
+    /// Registered duration mapper names, as registered.
+    pub fn duration_names(&self) -> Vec<&str> { names(&self.duration) }
+
+    /// Registered velocity mapper names, as registered.
+    pub fn velocity_names(&self) -> Vec<&str> { names(&self.velocity) }
+}
+
+fn build<T>(map: &BTreeMap<String, (String, Factory<T>)>, kind: &str, spec: &str, args: &MapperArgs)
+    -> Result<T, MidiError>
+{
+    let (name, param) = match spec.split_once(':') {
+        Some((name, param)) => (name, Some(param)),
+        None                => (spec, None),
+    };
+    let (_, factory) = map.get(&normalize_name(name)).ok_or_else(|| MidiError::param(format!(
+        "unknown {} mapper '{}' (try: {})", kind, name.trim(), names(map).join(", "))))?;
+    factory(args, param)
+}
+
+fn names<T>(map: &BTreeMap<String, (String, T)>) -> Vec<&str> {
+    map.values().map(|(name, _)| name.as_str()).collect()
+}
+
+fn bad_param(what: &str, param: &str) -> MidiError {
+    MidiError::param(format!("bad {} '{}'", what, param.trim()))
+}
+
+/// The root note given as the parameter, or the context's.
+fn root(args: &MapperArgs, param: Option<&str>) -> Result<u8, MidiError> {
+    match param {
+        None    => Ok(args.root),
+        Some(p) => p.trim().parse().ok().filter(|&r| r <= 127).ok_or_else(|| bad_param("root", p)),
+    }
+}
+
+/// A tick count given as the parameter, or `default`.
+fn ticks(param: Option<&str>, default: u32) -> Result<u32, MidiError> {
+    match param {
+        None    => Ok(default.max(1)),
+        Some(p) => p.trim().parse().ok().filter(|&t| t > 0).ok_or_else(|| bad_param("tick count", p)),
+    }
+}
+
+/// A velocity range `LO-HI`, 40–120 if not given.
+fn span(param: Option<&str>) -> Result<(u8, u8), MidiError> {
+    let Some(p) = param else { return Ok((40, 120)) };
+    let (lo, hi) = p.split_once('-').ok_or_else(|| bad_param("velocity range", p))?;
+    match (lo.trim().parse::<u8>(), hi.trim().parse::<u8>()) {
+        (Ok(lo), Ok(hi)) if lo <= 127 && hi <= 127 => Ok((lo, hi)),
+        _ => Err(bad_param("velocity range", p)),
+    }
+}
+
+/// Whitespace- or comma-separated numbers.
+fn numbers(text: &str) -> Result<Vec<u8>, std::num::ParseIntError> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins_parse_names_and_parameters() {
+        let r = MapperRegistry::with_builtins();
+        let args = MapperArgs { root: 48, ..MapperArgs::default() };
+        assert_eq!(r.pitch("Pentatonic Major", &args).unwrap().note_for(3), 55);
+        assert_eq!(r.pitch("custom:0, 3, 7", &args).unwrap().note_for(4), 63);
+        assert_eq!(r.duration("linear:10", &args).unwrap().ticks_for(2), 30);
+        assert_eq!(r.duration("values:q h", &args).unwrap().ticks_for(1), 960);
+        assert_eq!(r.velocity("left:10-100", &args).unwrap().velocity_for(9, 0), 100);
+        assert_eq!(r.velocity("fixed", &args).unwrap().velocity_for(1, 2), 100);
+    }
+
+    #[test]
+    fn unknown_names_and_bad_parameters_are_errors() {
+        let r = MapperRegistry::with_builtins();
+        let args = MapperArgs::default();
+        let err = r.pitch("blues", &args).err().unwrap().to_string();
+        assert!(err.starts_with("unknown pitch mapper 'blues'") && err.contains("Dorian"), "{}", err);
+        assert!(r.pitch("major:200", &args).is_err());
+        assert!(r.duration("fixed:0", &args).is_err());
+        assert!(r.velocity("right:loud", &args).is_err());
+        assert!(MapperRegistry::new().duration("musical", &args).is_err());
+    }
+}
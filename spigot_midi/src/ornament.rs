@@ -0,0 +1,257 @@
+//! Ornamentation — grace notes, mordents and trills over a composed track.
+//!
+//! [`MidiTrack::ornament`] rewrites selected notes into short figures that
+//! fit inside the original duration, so bar positions and total length are
+//! unchanged.  Whether a note is decorated is decided per scale degree with
+//! a probability, drawn either from a seeded generator (repeatable) or from
+//! the digits of a further spigot stream.
+
+use dual_spigot::{BoxedSpigot, SpigotConfig};
+
+use crate::{MidiTrack, Note, PitchMap};
+
+/// An ornamental figure that replaces a single note.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ornament {
+    /// A short upper-neighbour grace note before the main note.
+    Grace,
+    /// Main note, lower neighbour, main note — the first two short.
+    Mordent,
+    /// Rapid alternation with the upper neighbour for the whole duration.
+    Trill,
+}
+
+/// Where the per-note random draws come from.
+enum Chance {
+    /// SplitMix64 state.
+    Seeded(u64),
+    /// Two digits of a spigot per draw.
+    Stream(BoxedSpigot),
+}
+
+impl Chance {
+    /// Next value in `0.0..1.0`.
+    fn next_unit(&mut self) -> f32 {
+        match self {
+            Chance::Seeded(state) => {
+                *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                let mut z = *state;
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                z ^= z >> 31;
+                (z >> 40) as f32 / (1u64 << 24) as f32
+            }
+            Chance::Stream(sp) => {
+                let b  = sp.base() as f32;
+                let hi = sp.next_digit().unwrap_or(0) as f32;
+                let lo = sp.next_digit().unwrap_or(0) as f32;
+                (hi * b + lo) / (b * b)
+            }
+        }
+    }
+}
+
+struct Rule {
+    degree:      Option<usize>,
+    ornament:    Ornament,
+    probability: f32,
+}
+
+/// Configuration for an ornament pass; apply with [`MidiTrack::ornament`].
+///
+/// ```rust
+/// use spigot_midi::PitchMap;
+/// use spigot_midi::ornament::{Ornament, Ornaments};
+///
+/// let orn = Ornaments::new(PitchMap::major(60), 60)
+///     .seed(7)
+///     .degree(4, Ornament::Trill, 0.5)           // trill half the dominants
+///     .every_degree(Ornament::Grace, 0.1);       // occasional grace notes
+/// ```
+pub struct Ornaments {
+    pitch_map: PitchMap,
+    rules:     Vec<Rule>,
+    chance:    Chance,
+    unit:      u32,
+}
+
+impl Ornaments {
+    /// Ornaments for a track composed with `pitch_map` (used for scale
+    /// degrees and neighbour notes).  `unit_ticks` is the length of each
+    /// ornamental note, e.g. a 32nd.  Draws are seeded with 0 by default.
+    pub fn new(pitch_map: PitchMap, unit_ticks: u32) -> Self {
+        Ornaments {
+            pitch_map,
+            rules:  Vec::new(),
+            chance: Chance::Seeded(0),
+            unit:   unit_ticks.max(1),
+        }
+    }
+
+    /// Use a seeded generator, so the same seed decorates the same notes.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.chance = Chance::Seeded(seed);
+        self
+    }
+
+    /// Draw decisions from the digits of another spigot instead.
+    pub fn stream(mut self, cfg: SpigotConfig) -> Self {
+        self.chance = Chance::Stream(BoxedSpigot::from_config(cfg));
+        self
+    }
+
+    /// Decorate notes on scale degree `degree` (0 = root) with `ornament`
+    /// with the given probability.  Takes precedence over
+    /// [`every_degree`](Self::every_degree).
+    pub fn degree(mut self, degree: usize, ornament: Ornament, probability: f32) -> Self {
+        self.rules.push(Rule { degree: Some(degree), ornament, probability });
+        self
+    }
+
+    /// Decorate notes on any degree without a specific rule.
+    pub fn every_degree(mut self, ornament: Ornament, probability: f32) -> Self {
+        self.rules.push(Rule { degree: None, ornament, probability });
+        self
+    }
+
+    fn rule_for(&self, pitch: u8) -> Option<&Rule> {
+        let degree = self.pitch_map.degree_of(pitch);
+        self.rules.iter().find(|r| r.degree.is_some() && r.degree == degree)
+            .or_else(|| self.rules.iter().find(|r| r.degree.is_none()))
+    }
+
+    /// Expand `note` into the notes of `ornament`, or `None` if too short.
+    fn realise(&self, note: &Note, ornament: Ornament) -> Option<Vec<Note>> {
+        let u = self.unit.min(note.duration / 3);
+        if u == 0 { return None; }
+
+        let upper = self.pitch_map.neighbor(note.pitch, true);
+        let lower = self.pitch_map.neighbor(note.pitch, false);
+        let grace = |pitch, duration| Note {
+            velocity: (note.velocity as u32 * 4 / 5) as u8,
+            ..Note::new(pitch, duration, 0)
+        };
+        let main = |duration| Note { duration, ..note.clone() };
+
+        let mut figure = match ornament {
+            Ornament::Grace   => vec![grace(upper, u), main(note.duration - u)],
+            Ornament::Mordent => vec![
+                Note { duration: u, release: 0, aftertouch: None, tied: false, rest: false, ..note.clone() },
+                grace(lower, u),
+                main(note.duration - 2 * u),
+            ],
+            Ornament::Trill => {
+                let n = (note.duration / u) as usize;
+                let mut out: Vec<Note> = (0..n - 1)
+                    .map(|k| if k % 2 == 0 { Note { tied: false, ..main(u) } } else { grace(upper, u) })
+                    .collect();
+                let rest  = note.duration - u * (n as u32 - 1);
+                let pitch = if n.is_multiple_of(2) { upper } else { note.pitch };
+                out.push(Note { pitch, ..main(rest) });
+                out
+            }
+        };
+
+        // Ornamental notes sound in full; the last keeps the original release.
+        let (last, lead) = figure.split_last_mut()?;
+        let lead_ticks: u32 = lead.iter().map(|n| n.duration).sum();
+        for n in lead { n.gate = n.duration; }
+        last.gate = note.gate.saturating_sub(lead_ticks).max(1);
+        Some(figure)
+    }
+}
+
+impl MidiTrack {
+    /// Apply an ornament pass in place.
+    ///
+    /// Each note whose scale degree has a rule is decorated with that rule's
+    /// probability.  Tied notes are left alone, and the sustain lane is
+    /// stretched to cover the new notes.
+    pub fn ornament(&mut self, orn: &mut Ornaments) {
+        let mut notes = Vec::with_capacity(self.notes.len());
+        let mut pedal = Vec::with_capacity(self.pedal.len());
+        let mut held  = false;
+
+        for (i, note) in self.notes.iter().enumerate() {
+            let continuation = held;
+            held = self.ties_into_next(i);
+
+            let figure = if continuation || note.tied || note.rest {
+                None
+            } else {
+                orn.rule_for(note.pitch)
+                    .map(|r| (r.ornament, r.probability))
+                    .filter(|&(_, p)| orn.chance.next_unit() < p)
+                    .and_then(|(o, _)| orn.realise(note, o))
+            };
+            let figure = figure.unwrap_or_else(|| vec![note.clone()]);
+
+            if let Some(&p) = self.pedal.get(i) {
+                pedal.extend(std::iter::repeat_n(p, figure.len()));
+            }
+            notes.extend(figure);
+        }
+        self.notes = notes;
+        self.pedal = pedal;
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(pitches: &[u8]) -> MidiTrack {
+        MidiTrack {
+            notes:             pitches.iter().map(|&p| Note::new(p, 480, 100)).collect(),
+            ticks_per_quarter: 480,
+            tempo_bpm:         120.0,
+            instrument:        0,
+            channel:           0,
+            description:       "test".to_string(),
+            running_status:    true,
+            splits:            Vec::new(),
+            pedal:             Vec::new(),
+            bank:              None,
+            tempo_changes:     Vec::new(),
+            time_signature:    None,
+            provenance:  None,
+        }
+    }
+
+    #[test]
+    fn figures_fill_the_original_duration() {
+        let mut t = track(&[60, 64, 67]);           // C E G in C major
+        let mut orn = Ornaments::new(PitchMap::major(60), 60)
+            .degree(0, Ornament::Grace, 1.0)
+            .degree(2, Ornament::Mordent, 1.0)
+            .degree(4, Ornament::Trill, 1.0);
+        t.ornament(&mut orn);
+
+        assert_eq!(t.total_ticks(), 3 * 480);
+        let p: Vec<u8> = t.notes.iter().map(|n| n.pitch).collect();
+        assert_eq!(&p[..5], &[62, 60, 64, 62, 64]);  // grace D→C, mordent E-D-E
+        assert_eq!(p[5..].len(), 8);                 // trill 480 / 60
+        assert_eq!(&p[5..7], &[67, 69]);
+    }
+
+    #[test]
+    fn seeded_draws_repeat() {
+        let run = |seed| {
+            let mut t = track(&[60, 62, 64, 65, 67, 69, 71, 72]);
+            let mut orn = Ornaments::new(PitchMap::major(60), 60)
+                .seed(seed)
+                .every_degree(Ornament::Grace, 0.5);
+            t.ornament(&mut orn);
+            t.notes.len()
+        };
+        assert_eq!(run(3), run(3));
+        let mut t = track(&[60, 62]);
+        t.ornament(&mut Ornaments::new(PitchMap::major(60), 60)
+            .every_degree(Ornament::Trill, 0.0));
+        assert_eq!(t.notes.len(), 2);
+    }
+}
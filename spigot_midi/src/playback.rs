@@ -0,0 +1,111 @@
+//! Real-time playback to a MIDI output port (feature `playback`).
+//!
+//! [`MidiTrack::play`] sends the same events that would be written to a
+//! file, at their wall-clock times, through [`midir`].  Timing follows the
+//! track tempo and any tempo changes; the call blocks until the last note
+//! has been released.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{MidiError, MidiTrack};
+
+/// Which MIDI output port [`MidiTrack::play`] connects to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PortSelector {
+    /// The first port reported by the system.
+    First,
+    /// Port by position in [`output_ports`].
+    Index(usize),
+    /// First port whose name contains this text (case-insensitive).
+    Name(String),
+}
+
+impl From<usize> for PortSelector {
+    fn from(i: usize) -> Self { PortSelector::Index(i) }
+}
+
+impl From<&str> for PortSelector {
+    fn from(s: &str) -> Self { PortSelector::Name(s.to_string()) }
+}
+
+/// Names of the available MIDI output ports, in selection order.
+pub fn output_ports() -> Result<Vec<String>, MidiError> {
+    let out = midir::MidiOutput::new("spigot_midi").map_err(device)?;
+    Ok(out.ports().iter()
+        .map(|p| out.port_name(p).unwrap_or_else(|_| "Unknown".to_string()))
+        .collect())
+}
+
+impl MidiTrack {
+    /// Play the track on a MIDI output port, blocking until it ends.
+    ///
+    /// ```rust,no_run
+    /// # use spigot_midi::MidiComposer;
+    /// # use spigot_midi::playback::PortSelector;
+    /// # let track = MidiComposer::from_pairs(&[(5, 0), (5, 2)]).compose(2).unwrap();
+    /// track.play(PortSelector::First).unwrap();
+    /// track.play("fluid").unwrap();          // FluidSynth, if running
+    /// ```
+    pub fn play<P: Into<PortSelector>>(&self, port: P) -> Result<(), MidiError> {
+        let out = midir::MidiOutput::new("spigot_midi").map_err(device)?;
+        let ports = out.ports();
+        let chosen = match port.into() {
+            PortSelector::First    => ports.first(),
+            PortSelector::Index(i) => ports.get(i),
+            PortSelector::Name(s)  => {
+                let s = s.to_lowercase();
+                ports.iter().find(|p| out.port_name(p)
+                    .is_ok_and(|n| n.to_lowercase().contains(&s)))
+            }
+        };
+        let port = chosen.ok_or_else(|| device("no matching MIDI output port"))?.clone();
+        let mut conn = out.connect(&port, "spigot-play").map_err(device)?;
+
+        let start = Instant::now();
+        for (at, msg) in self.schedule() {
+            if let Some(wait) = at.checked_sub(start.elapsed()) {
+                thread::sleep(wait);
+            }
+            conn.send(&msg).map_err(device)?;
+        }
+        conn.close();
+        Ok(())
+    }
+
+    /// Channel messages with their offsets from the start, in play order.
+    /// Meta events are dropped; tempo is already folded into the times.
+    pub(crate) fn schedule(&self) -> Vec<(Duration, Vec<u8>)> {
+        let mut events = self.timed_events();
+        events.sort_by_key(|e| e.tick);
+        events.into_iter()
+            .filter(|e| e.bytes[0] < 0xF0)
+            .map(|e| (Duration::from_secs_f64(self.seconds_at(e.tick)), e.bytes))
+            .collect()
+    }
+}
+
+fn device(e: impl ToString) -> MidiError { MidiError::Device(e.to_string()) }
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use crate::MidiComposer;
+    use std::time::Duration;
+
+    #[test]
+    fn schedule_follows_tempo_changes() {
+        let mut track = MidiComposer::from_pairs(&[(5, 0), (5, 2)]).compose(2).unwrap();
+        track.tempo_changes.push((480, 60.0));
+        let s = track.schedule();
+
+        assert_eq!(s[0], (Duration::ZERO, vec![0xC0, 0]));
+        assert_eq!(s[1], (Duration::ZERO, vec![0x90, 60, 100]));
+        let second_on = s.iter().find(|(_, m)| m[..2] == [0x90, 64]).unwrap();
+        assert_eq!(second_on.0, Duration::from_millis(500));
+        assert_eq!(s.last().unwrap(), &(Duration::from_millis(1500), vec![0x80, 64, 0]));
+    }
+}
@@ -0,0 +1,189 @@
+//! Saved composer choices for the interactive menu.
+//!
+//! A preset is a small `key = value` text file in `presets/`, holding
+//! everything the single-track composer asks for, so a setup can be
+//! re-run without answering every prompt again.  The pitch and duration
+//! maps are kept as [`MapperRegistry`] specs, `NAME[:PARAM]`, as the
+//! `--pitch-mapper` and `--duration-mapper` flags take them.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use dual_spigot::SpigotConfig;
+use spigot_midi::mapper::MapperRegistry;
+use spigot_midi::provenance::stream_text;
+use spigot_midi::{MidiComposer, MidiError, Provenance};
+use spigot_stream::Constant;
+
+/// Directory presets are saved to, relative to the working directory.
+const DIR: &str = "presets";
+
+/// The full set of single-track composer choices.
+#[derive(Clone, Debug)]
+pub struct Preset {
+    pub left:         SpigotConfig,
+    pub right:        SpigotConfig,
+    pub tempo:        f64,
+    pub instrument:   u8,
+    /// Root note handed to the pitch mapper.
+    pub root:         u8,
+    /// Pitch mapper spec, e.g. `dorian`.
+    pub pitch:        String,
+    /// Duration mapper spec, e.g. `musical` or `values:s e q q. h`.
+    pub duration:     String,
+    pub ticks_per_quarter: u16,
+    pub notes:        usize,
+    pub velocity:     u8,
+}
+
+impl Preset {
+    /// The choices as a recipe, to compose from or adjust first.
+    pub fn provenance(&self) -> Provenance {
+        let mut p = Provenance::new(self.left, self.right);
+        p.notes       = self.notes;
+        p.tempo       = self.tempo;
+        p.program     = self.instrument;
+        p.ticks_per_quarter = self.ticks_per_quarter;
+        p.root        = self.root;
+        p.pitch       = self.pitch.clone();
+        p.duration    = self.duration.clone();
+        p.velocity    = format!("fixed:{}", self.velocity);
+        p.description = format!("{} / {} – {} notes @ {} BPM",
+            self.left.constant.name(), self.right.constant.name(), self.notes, self.tempo);
+        p
+    }
+
+    /// A composer configured with every choice, the maps looked up in the
+    /// built-in registry.
+    pub fn composer(&self) -> Result<MidiComposer, MidiError> {
+        self.provenance().composer(&MapperRegistry::with_builtins())
+    }
+
+    /// Write to `presets/<name>.preset`, returning the path.
+    pub fn save(&self, name: &str) -> io::Result<PathBuf> {
+        fs::create_dir_all(DIR)?;
+        let path = path_for(name);
+        fs::write(&path, self.to_text())?;
+        Ok(path)
+    }
+
+    /// Read `presets/<name>.preset`.
+    pub fn load(name: &str) -> Result<Self, String> {
+        let path = path_for(name);
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text)
+    }
+
+    /// Names of the saved presets, sorted.
+    pub fn list() -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(DIR).into_iter().flatten().flatten()
+            .filter_map(|e| {
+                let name = e.file_name().into_string().ok()?;
+                name.strip_suffix(".preset").map(str::to_string)
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn to_text(&self) -> String {
+        format!(
+            "# spigot_midi preset\n\
+             left = {}\nright = {}\ntempo = {}\ninstrument = {}\n\
+             root = {}\npitch = {}\nduration = {}\ntpq = {}\nnotes = {}\nvelocity = {}\n",
+            stream_text(self.left), stream_text(self.right), self.tempo, self.instrument,
+            self.root, self.pitch, self.duration, self.ticks_per_quarter,
+            self.notes, self.velocity)
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut p = Preset {
+            left:         SpigotConfig::new(Constant::Pi, 10),
+            right:        SpigotConfig::new(Constant::E, 10),
+            tempo:        120.0,
+            instrument:   0,
+            root:         60,
+            pitch:        "major".to_string(),
+            duration:     "musical".to_string(),
+            ticks_per_quarter: 480,
+            notes:        64,
+            velocity:     100,
+        };
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| format!("line {}: expected key = value", n + 1))?;
+            let value = value.trim();
+            let bad = |what: &str| format!("line {}: bad {} '{}'", n + 1, what, value);
+            match key.trim() {
+                "left"       => p.left  = parse_stream(value)?,
+                "right"      => p.right = parse_stream(value)?,
+                "tempo"      => p.tempo = value.parse().ok()
+                    .filter(|t: &f64| t.is_finite() && *t > 0.0).ok_or_else(|| bad("tempo"))?,
+                "instrument" => p.instrument = value.parse().ok()
+                    .filter(|&i| i <= 127).ok_or_else(|| bad("instrument"))?,
+                "root"       => p.root = value.parse().ok()
+                    .filter(|&r| r <= 127).ok_or_else(|| bad("root"))?,
+                "pitch"      => p.pitch = value.to_string(),
+                "duration"   => p.duration = value.to_string(),
+                "tpq"        => p.ticks_per_quarter = value.parse().ok()
+                    .filter(|&t| t > 0).ok_or_else(|| bad("ticks per quarter"))?,
+                "notes"      => p.notes = value.parse().map_err(|_| bad("note count"))?,
+                "velocity"   => p.velocity = value.parse().ok()
+                    .filter(|&v| v <= 127).ok_or_else(|| bad("velocity"))?,
+                other        => return Err(format!("line {}: unknown key '{}'", n + 1, other)),
+            }
+        }
+        p.composer().map_err(|e| e.to_string())?;
+        Ok(p)
+    }
+}
+
+fn path_for(name: &str) -> PathBuf {
+    PathBuf::from(DIR).join(format!("{}.preset", name))
+}
+
+/// Parse `CONSTANT[:BASE]`, e.g. `pi:16`; the base defaults to 10.
+pub fn parse_stream(s: &str) -> Result<SpigotConfig, String> {
+    spigot_midi::provenance::parse_stream(s).map_err(|e| e.to_string())
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_round_trip() {
+        let p = Preset {
+            left:         SpigotConfig::new(Constant::Champernowne, 16),
+            right:        SpigotConfig::new(Constant::ThueMorse, 2),
+            tempo:        92.5,
+            instrument:   11,
+            root:         62,
+            pitch:        "dorian".to_string(),
+            duration:     "values:e q h".to_string(),
+            ticks_per_quarter: 96,
+            notes:        128,
+            velocity:     90,
+        };
+        let q = Preset::parse(&p.to_text()).unwrap();
+        assert_eq!((q.left, q.right, q.tempo, q.instrument), (p.left, p.right, 92.5, 11));
+        assert_eq!((q.root, q.pitch.as_str(), q.duration.as_str()), (62, "dorian", "values:e q h"));
+        assert_eq!((q.ticks_per_quarter, q.notes, q.velocity), (96, 128, 90));
+        assert_eq!(q.provenance(), p.provenance());
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        assert!(Preset::parse("tempo = fast").unwrap_err().contains("line 1"));
+        assert!(Preset::parse("left = pi:99").is_err());
+        assert!(Preset::parse("colour = blue").is_err());
+        assert!(Preset::parse("pitch = bagpipes").unwrap_err().contains("unknown pitch mapper"));
+    }
+}
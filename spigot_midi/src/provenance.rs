@@ -0,0 +1,386 @@
+//! Where a piece came from, written into the piece.
+//!
+//! The digits of π or e are the same every time, so a track is fixed by
+//! the streams, where they were started, and the settings that turned
+//! their digits into notes.  A [`Provenance`] holds exactly that, with the
+//! maps named as [`MapperRegistry`] specs and the crate version, and
+//! [`Provenance::compose`] builds the track from nothing else.
+//!
+//! A composer given one with [`MidiComposer::provenance`] embeds it in the
+//! file as a sequencer-specific meta event (`FF 7F`, under the
+//! non-commercial ID `7D`); [`Provenance::from_midi`] reads it back, and
+//! [`Provenance::to_json`] exports the same fields for other tools.
+//! Replayed by the same version, the file comes out byte for byte.
+//!
+//! ```rust
+//! use spigot_midi::provenance::{parse_stream, Provenance};
+//!
+//! let mut p = Provenance::new(parse_stream("champernowne").unwrap(), parse_stream("thuemorse:2").unwrap());
+//! p.right_offset = 1000;
+//! p.notes = 16;
+//! p.pitch = "dorian:62".to_string();
+//!
+//! let bytes = p.compose().unwrap().to_bytes();
+//! let again = Provenance::from_midi(&bytes).unwrap();
+//! assert_eq!(again, p);
+//! assert_eq!(again.compose().unwrap().to_bytes(), bytes);
+//! ```
+
+use std::path::Path;
+
+use dual_spigot::{DualStream, SpigotConfig};
+use spigot_stream::Constant;
+
+use crate::mapper::{MapperArgs, MapperRegistry};
+use crate::{MidiComposer, MidiError, MidiTrack};
+
+/// First line of the embedded text, after the manufacturer ID.
+const HEADER: &str = "spigot_midi provenance";
+
+/// Manufacturer ID for non-commercial use.
+const NON_COMMERCIAL: u8 = 0x7D;
+
+/// Everything needed to compose a track again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Provenance {
+    /// Version of spigot_midi that composed it.
+    pub version:           String,
+    /// Stream for durations.
+    pub left:              SpigotConfig,
+    /// Digits of the left stream skipped before the first note.
+    pub left_offset:       usize,
+    /// Stream for pitches.
+    pub right:             SpigotConfig,
+    pub right_offset:      usize,
+    pub notes:             usize,
+    pub tempo:             f64,
+    /// General MIDI program, 0–127.
+    pub program:           u8,
+    /// Channel 0–15.
+    pub channel:           u8,
+    pub ticks_per_quarter: u16,
+    /// Root note handed to the mappers.
+    pub root:              u8,
+    /// Mapper specs, `NAME[:PARAM]`.
+    pub pitch:             String,
+    pub duration:          String,
+    pub velocity:          String,
+    /// Third stream whose digits the velocity map reads, if any.
+    pub velocity_stream:   Option<SpigotConfig>,
+    /// Part of each note that sounds, 0.0–1.0.
+    pub gate:              f32,
+    /// Track name.
+    pub description:       String,
+}
+
+impl Provenance {
+    /// `left` against `right` from their first digits, with the
+    /// composer's defaults: 64 notes, piano in C major from middle C,
+    /// musical durations, velocity 100, 120 BPM.
+    pub fn new(left: SpigotConfig, right: SpigotConfig) -> Self {
+        Provenance {
+            version:           env!("CARGO_PKG_VERSION").to_string(),
+            left,
+            left_offset:       0,
+            right,
+            right_offset:      0,
+            notes:             64,
+            tempo:             120.0,
+            program:           0,
+            channel:           0,
+            ticks_per_quarter: 480,
+            root:              60,
+            pitch:             "major".to_string(),
+            duration:          "musical".to_string(),
+            velocity:          "fixed:100".to_string(),
+            velocity_stream:   None,
+            gate:              1.0,
+            description:       "spigot_midi".to_string(),
+        }
+    }
+
+    /// Whether this crate is the version that composed the piece, so a
+    /// replay will match byte for byte.
+    pub fn same_version(&self) -> bool { self.version == env!("CARGO_PKG_VERSION") }
+
+    /// A composer set up as recorded, with the maps looked up in
+    /// `registry`; it embeds this provenance in what it composes.
+    pub fn composer(&self, registry: &MapperRegistry) -> Result<MidiComposer, MidiError> {
+        if !(self.tempo.is_finite() && self.tempo > 0.0) || self.ticks_per_quarter == 0 {
+            return Err(MidiError::param("provenance: tempo and ticks_per_quarter must be positive"));
+        }
+        let args = MapperArgs {
+            root:       self.root,
+            left_base:  self.left.base,
+            right_base: self.right.base,
+            ticks_per_quarter: self.ticks_per_quarter as u32,
+        };
+        // A velocity map over a third stream is sized to that stream's base.
+        let velocity_args = match self.velocity_stream {
+            Some(vs) => MapperArgs { left_base: vs.base, right_base: vs.base, ..args },
+            None     => args,
+        };
+        let mut ds = DualStream::from_configs(self.left, self.right);
+        ds.left().drop(self.left_offset);
+        ds.right().drop(self.right_offset);
+        let mut composer = MidiComposer::new(ds);
+        if let Some(vs) = self.velocity_stream {
+            composer = composer.velocity_stream(vs);
+        }
+        Ok(composer
+            .tempo(self.tempo)
+            .instrument_raw(self.program)
+            .channel(self.channel)
+            .ticks_per_quarter(self.ticks_per_quarter)
+            .pitch_map(registry.pitch(&self.pitch, &args)?)
+            .duration_map(registry.duration(&self.duration, &args)?)
+            .velocity_map(registry.velocity(&self.velocity, &velocity_args)?)
+            .gate(self.gate)
+            .description(&self.description)
+            .provenance(self.clone()))
+    }
+
+    /// Compose the recorded number of notes with the built-in maps.
+    pub fn compose(&self) -> Result<MidiTrack, MidiError> {
+        self.composer(&MapperRegistry::with_builtins())?.compose(self.notes)
+    }
+
+    /// The fields as `key = value` lines under a header line.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", HEADER);
+        for (key, value) in self.fields() {
+            text.push_str(&format!("{} = {}\n", key, value));
+        }
+        text
+    }
+
+    /// Read [`to_text`](Self::to_text) back.
+    pub fn parse(text: &str) -> Result<Self, MidiError> {
+        let mut lines = text.lines();
+        if lines.next().map(str::trim) != Some(HEADER) {
+            return Err(MidiError::param("not a spigot_midi provenance block"));
+        }
+        let mut p = Provenance::new(SpigotConfig::decimal(Constant::Pi), SpigotConfig::decimal(Constant::E));
+        for line in lines.filter(|l| !l.trim().is_empty()) {
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| MidiError::param(format!("provenance: expected key = value, got '{}'", line)))?;
+            let (key, value) = (key.trim(), value.strip_prefix(' ').unwrap_or(value));
+            let bad = || MidiError::param(format!("provenance: bad {} '{}'", key, value));
+            match key {
+                "version"           => p.version = value.to_string(),
+                "left"              => p.left = parse_stream(value)?,
+                "left_offset"       => p.left_offset = value.parse().map_err(|_| bad())?,
+                "right"             => p.right = parse_stream(value)?,
+                "right_offset"      => p.right_offset = value.parse().map_err(|_| bad())?,
+                "notes"             => p.notes = value.parse().map_err(|_| bad())?,
+                "tempo"             => p.tempo = value.parse().map_err(|_| bad())?,
+                "program"           => p.program = value.parse().ok().filter(|&v| v <= 127).ok_or_else(bad)?,
+                "channel"           => p.channel = value.parse().ok().filter(|&v| v <= 15).ok_or_else(bad)?,
+                "ticks_per_quarter" => p.ticks_per_quarter = value.parse().map_err(|_| bad())?,
+                "root"              => p.root = value.parse().ok().filter(|&v| v <= 127).ok_or_else(bad)?,
+                "pitch"             => p.pitch = value.to_string(),
+                "duration"          => p.duration = value.to_string(),
+                "velocity"          => p.velocity = value.to_string(),
+                "velocity_stream"   => p.velocity_stream = match value.trim() {
+                    "none" => None,
+                    spec   => Some(parse_stream(spec)?),
+                },
+                "gate"              => p.gate = value.parse().ok().filter(|g: &f32| (0.0..=1.0).contains(g)).ok_or_else(bad)?,
+                "description"       => p.description = value.to_string(),
+                _ => return Err(MidiError::param(format!("provenance: unknown key '{}'", key))),
+            }
+        }
+        Ok(p)
+    }
+
+    /// The data of the `FF 7F` meta event: the ID, then the text.
+    pub fn to_meta(&self) -> Vec<u8> {
+        let mut data = vec![NON_COMMERCIAL];
+        data.extend_from_slice(self.to_text().as_bytes());
+        data
+    }
+
+    /// The provenance embedded in a Standard MIDI File's bytes.
+    pub fn from_midi(bytes: &[u8]) -> Result<Self, MidiError> {
+        let mut i = 0;
+        while let Some(at) = bytes[i..].windows(2).position(|w| w == [0xFF, 0x7F]) {
+            i += at + 2;
+            let (len, used) = read_vlq(&bytes[i..]);
+            let data = bytes.get(i + used..i + used + len as usize).unwrap_or(&[]);
+            if data.first() == Some(&NON_COMMERCIAL) && data[1..].starts_with(HEADER.as_bytes()) {
+                let text = std::str::from_utf8(&data[1..])
+                    .map_err(|_| MidiError::param("provenance is not UTF-8"))?;
+                return Self::parse(text);
+            }
+        }
+        Err(MidiError::param("no provenance in this file"))
+    }
+
+    /// Read the provenance embedded in the MIDI file at `path`.
+    pub fn read_midi<P: AsRef<Path>>(path: P) -> Result<Self, MidiError> {
+        Self::from_midi(&std::fs::read(path)?)
+    }
+
+    /// The fields as a flat JSON object, numbers as numbers.
+    pub fn to_json(&self) -> String {
+        let fields: Vec<String> = self.fields().into_iter().map(|(key, value)| {
+            let numeric = !matches!(key, "version" | "left" | "right" | "pitch" | "duration"
+                | "velocity" | "velocity_stream" | "description");
+            match numeric {
+                true  => format!("  \"{}\": {}", key, value),
+                false => format!("  \"{}\": \"{}\"", key, json_escape(&value)),
+            }
+        }).collect();
+        format!("{{\n{}\n}}\n", fields.join(",\n"))
+    }
+
+    /// Write [`to_json`](Self::to_json) to `path`.
+    pub fn write_json<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("version",           self.version.clone()),
+            ("left",              stream_text(self.left)),
+            ("left_offset",       self.left_offset.to_string()),
+            ("right",             stream_text(self.right)),
+            ("right_offset",      self.right_offset.to_string()),
+            ("notes",             self.notes.to_string()),
+            ("tempo",             self.tempo.to_string()),
+            ("program",           self.program.to_string()),
+            ("channel",           self.channel.to_string()),
+            ("ticks_per_quarter", self.ticks_per_quarter.to_string()),
+            ("root",              self.root.to_string()),
+            ("pitch",             one_line(&self.pitch)),
+            ("duration",          one_line(&self.duration)),
+            ("velocity",          one_line(&self.velocity)),
+            ("velocity_stream",   self.velocity_stream.map_or("none".to_string(), stream_text)),
+            ("gate",              self.gate.to_string()),
+            ("description",       one_line(&self.description)),
+        ]
+    }
+}
+
+/// Parse `CONSTANT[:BASE]`, e.g. `pi:16` or the fraction `1/7:2`; the
+/// base defaults to 10.
+pub fn parse_stream(s: &str) -> Result<SpigotConfig, MidiError> {
+    let (name, base) = match s.split_once(':') {
+        Some((name, base)) => (name, base.trim().parse::<u8>()
+            .map_err(|_| MidiError::param(format!("bad base '{}'", base)))?),
+        None => (s, 10),
+    };
+    if !(2..=36).contains(&base) {
+        return Err(MidiError::param(format!("base must be 2–36, got {}", base)));
+    }
+    if let Some((p, q)) = name.split_once('/') {
+        let n = |s: &str| s.trim().parse::<u64>().map_err(|_| MidiError::param(format!("bad fraction '{}'", name)));
+        return Ok(SpigotConfig::try_new(Constant::Rational(n(p)?, n(q)?), base)?);
+    }
+    let key: String = name.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+    let constant = match key.as_str() {
+        "pi" | "π"     => Constant::Pi,
+        "e"            => Constant::E,
+        "ln2"          => Constant::Ln2,
+        "liouville"    => Constant::Liouville,
+        "champernowne" => Constant::Champernowne,
+        "thuemorse"    => Constant::ThueMorse,
+        "sqrt2"        => Constant::Sqrt2,
+        "phi" | "φ"    => Constant::Phi,
+        _ => return Err(MidiError::param(format!(
+            "unknown constant '{}' (pi, e, ln2, liouville, champernowne, thuemorse, sqrt2, phi)", name))),
+    };
+    Ok(SpigotConfig::new(constant, base))
+}
+
+/// `CONSTANT:BASE`, the inverse of [`parse_stream`].
+pub fn stream_text(cfg: SpigotConfig) -> String {
+    let key = match cfg.constant {
+        Constant::Pi           => "pi",
+        Constant::E            => "e",
+        Constant::Ln2          => "ln2",
+        Constant::Liouville    => "liouville",
+        Constant::Champernowne => "champernowne",
+        Constant::ThueMorse    => "thuemorse",
+        Constant::Sqrt2        => "sqrt2",
+        Constant::Phi          => "phi",
+        Constant::Rational(p, q) => return format!("{}/{}:{}", p, q, cfg.base),
+    };
+    format!("{}:{}", key, cfg.base)
+}
+
+/// Line breaks would end a `key = value` line early.
+fn one_line(s: &str) -> String { s.replace(['\n', '\r'], " ") }
+
+fn json_escape(s: &str) -> String {
+    s.chars().map(|c| match c {
+        '"'  => "\\\"".to_string(),
+        '\\' => "\\\\".to_string(),
+        c if (c as u32) < 0x20 => format!("\\u{:04x}", c as u32),
+        c    => c.to_string(),
+    }).collect()
+}
+
+/// A variable-length quantity and the bytes it took.
+fn read_vlq(bytes: &[u8]) -> (u32, usize) {
+    let mut value = 0u32;
+    for (i, &b) in bytes.iter().take(4).enumerate() {
+        value = value << 7 | (b & 0x7F) as u32;
+        if b & 0x80 == 0 { return (value, i + 1); }
+    }
+    (value, bytes.len().min(4))
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Provenance {
+        let mut p = Provenance::new(SpigotConfig::new(Constant::Champernowne, 16),
+                                    SpigotConfig::new(Constant::ThueMorse, 2));
+        p.left_offset = 7;
+        p.notes = 12;
+        p.tempo = 93.25;
+        p.duration = "values:e q h".to_string();
+        p.velocity = "left:30-90".to_string();
+        p.velocity_stream = Some(SpigotConfig::new(Constant::Champernowne, 10));
+        p.gate = 0.6;
+        p.description = "a \"quoted\" name".to_string();
+        p
+    }
+
+    #[test]
+    fn embeds_and_replays_byte_for_byte() {
+        let p = sample();
+        let bytes = p.compose().unwrap().to_bytes();
+        assert_eq!(Provenance::from_midi(&bytes).unwrap(), p);
+        assert_eq!(Provenance::from_midi(&bytes).unwrap().compose().unwrap().to_bytes(), bytes);
+        assert!(p.same_version());
+
+        let plain = crate::MidiComposer::from_pairs(&[(1, 1)]).compose(1).unwrap().to_bytes();
+        assert!(Provenance::from_midi(&plain).is_err());
+    }
+
+    #[test]
+    fn text_and_json_carry_every_field() {
+        let p = sample();
+        assert_eq!(Provenance::parse(&p.to_text()).unwrap(), p);
+        let json = p.to_json();
+        assert!(json.contains("\"left\": \"champernowne:16\""), "{}", json);
+        assert!(json.contains("\"left_offset\": 7"));
+        assert!(json.contains("\"tempo\": 93.25"));
+        assert!(json.contains("\"gate\": 0.6"));
+        assert!(json.contains(r#""description": "a \"quoted\" name""#));
+        assert!(Provenance::parse("left = pi").is_err());
+        assert!(Provenance::parse(&format!("{}\nchannel = 16", HEADER)).is_err());
+        assert_eq!(parse_stream("φ:12").unwrap(), SpigotConfig::new(Constant::Phi, 12));
+        assert_eq!(stream_text(SpigotConfig::decimal(Constant::Phi)), "phi:10");
+        assert_eq!(parse_stream(&stream_text(SpigotConfig::new(Constant::Rational(3, 11), 2))).unwrap(),
+                   SpigotConfig::new(Constant::Rational(3, 11), 2));
+        assert!(parse_stream("1/0").is_err());
+    }
+}
@@ -0,0 +1,390 @@
+//! Reading MIDI files back into tracks.
+//!
+//! [`MidiTrack::from_bytes`] parses a Standard MIDI File — the Type-0 and
+//! Type-1 files this crate writes, or any other timed in ticks per quarter
+//! — into [`Note`]s and track settings, so output can be checked in code,
+//! snippets imported again, and editing tools built on top.
+//!
+//! A file keeps onsets and lengths rather than the notes that produced
+//! them, so a track comes back in the shape that writes the same bytes:
+//!
+//! * a gap after a note lengthens its duration; only silence before the
+//!   first note, or a pedal change with no note under it, becomes a
+//!   [`Note::rest`];
+//! * tied pieces come back as one note, with any pressure on the first
+//!   piece, and chords as notes of duration 0 before the last;
+//! * register splits get the lowest split points that route the notes
+//!   the same way.
+//!
+//! Notes on channels with no program change are read onto the track's own
+//! channel.  System exclusive messages, pitch bend, and controllers other
+//! than bank select and sustain are skipped.
+//!
+//! ```rust
+//! use spigot_midi::{MidiComposer, MidiTrack};
+//!
+//! let bytes = MidiComposer::from_pairs(&[(5, 0), (3, 4), (7, 2)]).compose(3).unwrap().to_bytes();
+//! let track = MidiTrack::from_bytes(&bytes).unwrap();
+//! assert_eq!(track.notes.iter().map(|n| n.pitch).collect::<Vec<_>>(), [60, 67, 64]);
+//! assert_eq!(track.to_bytes(), bytes);
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+use crate::{MidiError, MidiTrack, Note, Provenance, RegisterSplit};
+
+impl MidiTrack {
+    /// Parse a MIDI file holding a single track: Type 0, or Type 1 with
+    /// one track.  Use [`tracks_from_bytes`] for more.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MidiError> {
+        let mut tracks = tracks_from_bytes(bytes)?;
+        match tracks.len() {
+            1 => Ok(tracks.remove(0)),
+            n => Err(bad(format!("{} tracks; read them with tracks_from_bytes", n))),
+        }
+    }
+
+    /// Read the single-track MIDI file at `path`.
+    pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Self, MidiError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+/// Parse every track of a Type-0 or Type-1 MIDI file.
+pub fn tracks_from_bytes(bytes: &[u8]) -> Result<Vec<MidiTrack>, MidiError> {
+    let mut r = Reader { bytes, at: 0 };
+    if r.take(4)? != b"MThd" {
+        return Err(bad("not a MIDI file"));
+    }
+    let len = r.u32()? as usize;
+    let header = r.take(len)?;
+    if len < 6 {
+        return Err(bad("header too short"));
+    }
+    let format   = u16::from_be_bytes([header[0], header[1]]);
+    let count    = u16::from_be_bytes([header[2], header[3]]) as usize;
+    let division = u16::from_be_bytes([header[4], header[5]]);
+    if format > 1 {
+        return Err(bad(format!("format {} files are not supported", format)));
+    }
+    if division & 0x8000 != 0 || division == 0 {
+        return Err(bad("SMPTE time division is not supported"));
+    }
+
+    let mut tracks = Vec::with_capacity(count);
+    while tracks.len() < count {
+        let id  = r.take(4)?;
+        let len = r.u32()? as usize;
+        let data = r.take(len)?;
+        // Chunks of other types are skipped, as the standard asks.
+        if id == b"MTrk" {
+            tracks.push(parse_track(data, division)?);
+        }
+    }
+    Ok(tracks)
+}
+
+/// Read every track of the MIDI file at `path`; the counterpart of
+/// [`write_multi_track`](crate::write_multi_track).
+pub fn read_multi_track<P: AsRef<Path>>(path: P) -> Result<Vec<MidiTrack>, MidiError> {
+    tracks_from_bytes(&std::fs::read(path)?)
+}
+
+fn bad(msg: impl Into<String>) -> MidiError { MidiError::Format(msg.into()) }
+
+/// A cursor over the file's bytes.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at:    usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], MidiError> {
+        let end = self.at.checked_add(n).filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| bad(format!("truncated at byte {}", self.bytes.len())))?;
+        let data = &self.bytes[self.at..end];
+        self.at = end;
+        Ok(data)
+    }
+
+    fn u8(&mut self) -> Result<u8, MidiError> { Ok(self.take(1)?[0]) }
+
+    fn u32(&mut self) -> Result<u32, MidiError> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// A variable-length quantity of up to four bytes.
+    fn vlq(&mut self) -> Result<u32, MidiError> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let b = self.u8()?;
+            value = value << 7 | (b & 0x7F) as u32;
+            if b & 0x80 == 0 { return Ok(value); }
+        }
+        Err(bad("variable-length quantity longer than four bytes"))
+    }
+
+    fn done(&self) -> bool { self.at >= self.bytes.len() }
+}
+
+/// A note as the file has it, before durations are known.
+struct Heard {
+    onset:      u64,
+    off:        Option<u64>,
+    channel:    u8,
+    pitch:      u8,
+    velocity:   u8,
+    release:    u8,
+    /// The pressure and when it came.
+    aftertouch: Option<(u8, u64)>,
+}
+
+fn parse_track(data: &[u8], division: u16) -> Result<MidiTrack, MidiError> {
+    let mut r = Reader { bytes: data, at: 0 };
+    let mut track = MidiTrack::new(Vec::new(), division, 120.0);
+    let mut tick: u64 = 0;
+    let mut end: Option<u64> = None;
+    let mut status: Option<u8> = None;
+    // A repeated status byte, or a Note Off of velocity 0, means the
+    // writer did not use running status.
+    let mut running_status = true;
+    let mut named = false;
+    let mut programs: Vec<(u8, u8)> = Vec::new();
+    let mut banks: HashMap<u8, (u8, u8)> = HashMap::new();
+    let mut pedal: Vec<(u64, bool)> = Vec::new();
+    let mut heard: Vec<Heard> = Vec::new();
+    let mut sounding: HashMap<(u8, u8), VecDeque<usize>> = HashMap::new();
+    let mut struck: HashSet<(u8, u8, u64)> = HashSet::new();
+    let mut pressures: Vec<(u8, u64, u8)> = Vec::new();
+
+    while !r.done() {
+        tick += r.vlq()? as u64;
+        let first = r.u8()?;
+        match first {
+            0xFF => {
+                let kind = r.u8()?;
+                let len  = r.vlq()? as usize;
+                let data = r.take(len)?;
+                status = None;
+                match kind {
+                    0x51 if len == 3 => {
+                        let micros = u32::from_be_bytes([0, data[0], data[1], data[2]]).max(1);
+                        let bpm = 60_000_000.0 / micros as f64;
+                        match tick {
+                            0 => track.tempo_bpm = bpm,
+                            _ => track.tempo_changes.push((tick, bpm)),
+                        }
+                    }
+                    0x58 if len >= 2 && data[1] < 8 => {
+                        track.time_signature = Some((data[0], 1 << data[1]));
+                    }
+                    0x03 if !named => {
+                        track.description = String::from_utf8_lossy(data).into_owned();
+                        named = true;
+                    }
+                    0x7F if track.provenance.is_none() => {
+                        track.provenance = data.split_first()
+                            .filter(|&(&id, _)| id == 0x7D)
+                            .and_then(|(_, text)| std::str::from_utf8(text).ok())
+                            .and_then(|text| Provenance::parse(text).ok());
+                    }
+                    0x2F => { end = Some(tick); break; }
+                    _ => {}
+                }
+                continue;
+            }
+            0xF0 | 0xF7 => {
+                let len = r.vlq()? as usize;
+                r.take(len)?;
+                status = None;
+                continue;
+            }
+            _ => {}
+        }
+
+        let (st, d0) = if first & 0x80 != 0 {
+            if status == Some(first) { running_status = false; }
+            (first, r.u8()?)
+        } else {
+            (status.ok_or_else(|| bad("data byte with no status before it"))?, first)
+        };
+        status = Some(st);
+        let ch = st & 0x0F;
+        let d1 = match st & 0xF0 {
+            0xC0 | 0xD0 => 0,
+            _           => r.u8()?,
+        };
+        match st & 0xF0 {
+            0x90 if d1 > 0 => {
+                sounding.entry((ch, d0)).or_default().push_back(heard.len());
+                struck.insert((ch, d0, tick));
+                heard.push(Heard {
+                    onset: tick, off: None, channel: ch, pitch: d0, velocity: d1, release: 0, aftertouch: None,
+                });
+            }
+            0x80 | 0x90 => {
+                if st & 0xF0 == 0x80 && d1 == 0 { running_status = false; }
+                if let Some(i) = sounding.get_mut(&(ch, d0)).and_then(VecDeque::pop_front) {
+                    heard[i].off = Some(tick);
+                    heard[i].release = if st & 0xF0 == 0x80 { d1 } else { 0 };
+                }
+            }
+            0xB0 => match d0 {
+                0  if tick == 0 => banks.entry(ch).or_default().0 = d1,
+                32 if tick == 0 => banks.entry(ch).or_default().1 = d1,
+                64 => pedal.push((tick, d1 >= 64)),
+                _  => {}
+            },
+            0xC0 if tick == 0 => programs.push((ch, d0)),
+            0xD0 => pressures.push((ch, tick, d0)),
+            _ => {}
+        }
+    }
+
+    let end = end.unwrap_or_else(|| heard.iter().filter_map(|n| n.off).fold(tick, u64::max));
+    for n in heard.iter_mut().filter(|n| n.off.is_none()) {
+        n.off = Some(end);
+    }
+
+    // Each note's pressure comes midway through it, which finds its note
+    // once the Note Offs are in.  What's left goes to the oldest note on
+    // the channel still sounding — or cut short by its key striking again,
+    // so that its middle came after its end.
+    let mut by_middle: HashMap<(u8, u64), VecDeque<usize>> = HashMap::new();
+    for (i, n) in heard.iter().enumerate() {
+        let off = n.off.unwrap_or(end);
+        by_middle.entry((n.channel, n.onset + (off - n.onset) / 2)).or_default().push_back(i);
+    }
+    let mut unplaced = Vec::new();
+    for (ch, at, pressure) in pressures {
+        match by_middle.get_mut(&(ch, at)).and_then(VecDeque::pop_front) {
+            Some(i) => heard[i].aftertouch = Some((pressure, at)),
+            None    => unplaced.push((ch, at, pressure)),
+        }
+    }
+    for (ch, at, pressure) in unplaced {
+        if let Some(n) = heard.iter_mut().find(|n| {
+            let off = n.off.unwrap_or(end);
+            n.channel == ch && n.aftertouch.is_none() && n.onset <= at
+                && (off > at || struck.contains(&(ch, n.pitch, off)))
+        }) {
+            n.aftertouch = Some((pressure, at));
+        }
+    }
+
+    // ── channels, programs and splits ─────────────────────────────────────
+    let (channel, instrument) = programs.first().copied()
+        .or_else(|| heard.first().map(|n| (n.channel, 0)))
+        .unwrap_or((0, 0));
+    track.channel    = channel;
+    track.instrument = instrument;
+    track.bank       = banks.get(&channel).copied();
+    for &(ch, program) in &programs[1.min(programs.len())..] {
+        if ch == channel || track.splits.iter().any(|s| s.channel == ch) { continue; }
+        let below = heard.iter().filter(|n| n.channel == ch)
+            .map(|n| n.pitch.saturating_add(1)).max().unwrap_or(0);
+        track.splits.push(RegisterSplit { below, channel: ch, instrument: program });
+    }
+
+    // ── notes, with rests where silence or the pedal needs one ────────────
+    let onsets: Vec<u64> = heard.iter().map(|n| n.onset).collect();
+    let mut slots: Vec<(u64, Option<&Heard>)> = heard.iter().map(|n| (n.onset, Some(n))).collect();
+    if onsets.first().is_none_or(|&at| at > 0) && end > 0 {
+        slots.push((0, None));
+    }
+    for &(at, _) in &pedal {
+        if at > 0 && at < end && !onsets.contains(&at) && !slots.iter().any(|&(s, _)| s == at) {
+            slots.push((at, None));
+        }
+    }
+    slots.sort_by_key(|&(at, _)| at);
+
+    for (i, &(at, heard)) in slots.iter().enumerate() {
+        let next = slots.get(i + 1).map_or(end.max(at), |&(next, _)| next);
+        let duration = (next - at) as u32;
+        track.notes.push(match heard {
+            None    => Note::rest(duration),
+            Some(n) => Note {
+                // A gate the next strike cut short still shows in its pressure.
+                gate:       n.aftertouch.map_or(0, |(_, mid)| (mid - at) * 2).max(n.off.unwrap_or(end) - at) as u32,
+                release:    n.release,
+                aftertouch: n.aftertouch.map(|(pressure, _)| pressure),
+                ..Note::new(n.pitch, duration, n.velocity)
+            },
+        });
+        if !pedal.is_empty() {
+            let down = pedal.iter().take_while(|&&(t, _)| t <= at).last().is_some_and(|&(_, d)| d);
+            track.pedal.push(down);
+        }
+    }
+    track.running_status = running_status;
+    Ok(track)
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dual_spigot::DualStream;
+    use spigot_stream::Constant;
+    use crate::{multi_track_bytes, ChordShape, Expression, MidiComposer};
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        // Pressure on a tied note comes midway through its first piece,
+        // which a single note can't say, so ties are tried without it.
+        for (running_status, tied) in [(true, false), (false, false), (true, true)] {
+            let mut composer = MidiComposer::new(DualStream::new(Constant::Champernowne, Constant::ThueMorse))
+                .gate_ratio(1.4)
+                .chords(ChordShape::Triad)
+                .rest_on(|l, _| l == 3)
+                .sustain_pattern(&[true, false, false])
+                .release_velocity(Expression::Fixed(40))
+                .split_below(64, 2, 32)
+                .bank(1, 2)
+                .tie_at_barlines(tied)
+                .time_signature(6, 8)
+                .running_status(running_status)
+                .description("round trip");
+            if !tied {
+                composer = composer.aftertouch(Expression::pair(|l, r| l * 10 + r));
+            }
+            let bytes = composer.compose(200).unwrap().to_bytes();
+            let track = MidiTrack::from_bytes(&bytes).unwrap();
+            assert_eq!((track.channel, track.bank, track.running_status), (0, Some((1, 2)), running_status));
+            assert_eq!((track.description.as_str(), track.time_signature), ("round trip", Some((6, 8))));
+            assert_eq!(track.to_bytes(), bytes);
+        }
+
+        let p = Provenance::new(dual_spigot::SpigotConfig::decimal(Constant::Champernowne),
+                                dual_spigot::SpigotConfig::decimal(Constant::Champernowne));
+        let tracks = [p.compose().unwrap(), MidiComposer::from_pairs(&[(1, 1)]).channel(3).compose(1).unwrap()];
+        let read = tracks_from_bytes(&multi_track_bytes(&tracks)).unwrap();
+        assert_eq!(read[0].provenance.as_ref(), Some(&p));
+        assert_eq!(read[1].channel, 3);
+        assert_eq!(multi_track_bytes(&read), multi_track_bytes(&tracks));
+    }
+
+    #[test]
+    fn leading_silence_is_a_rest_and_bad_files_are_refused() {
+        let track = || {
+            let mut t = MidiTrack::new(vec![Note::rest(240), Note::new(62, 480, 90)], 96, 100.0);
+            t.notes[1].gate = 200;
+            t
+        };
+        let t = track();
+        let back = MidiTrack::from_bytes(&t.to_bytes()).unwrap();
+        assert_eq!(back.notes, t.notes);
+        assert_eq!((back.ticks_per_quarter, back.tempo_bpm), (96, 100.0));
+
+        let bytes = t.to_bytes();
+        assert!(matches!(MidiTrack::from_bytes(b"RIFF"), Err(MidiError::Format(_))));
+        assert!(matches!(MidiTrack::from_bytes(&bytes[..bytes.len() - 6]), Err(MidiError::Format(_))));
+        assert!(MidiTrack::from_bytes(&multi_track_bytes(&[t, track()])).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+//! Live-coding REPL: one command a line against a running session.
+//!
+//! The session keeps a [`DualStream`], its snippets, and the maps and
+//! settings notes are composed with, so each line picks up where the last
+//! left off:
+//!
+//! ```text
+//! > left pi 16
+//! > drop l 30
+//! > map pitch dorian 62
+//! > play 32
+//! > snip chorus 0 16
+//! > write chorus.mid
+//! ```
+//!
+//! `help` lists the commands.  Notes come from the live zip, so `play`
+//! moves both cursors on; without the `playback` feature `play` composes
+//! and summarises instead of sounding.
+
+use std::io::{self, BufRead, Write};
+
+use dual_spigot::{DualStream, SpigotConfig};
+use spigot_midi::mapper::{MapperArgs, MapperRegistry};
+use spigot_midi::{GeneralMidi, MidiComposer, MidiTrack};
+use spigot_stream::digit_char;
+
+use crate::preset::parse_stream;
+
+const HELP: &str = "\
+  left CONST [BASE]        set the left (duration) stream, from its start
+  right CONST [BASE]       set the right (pitch) stream
+  drop l|r|both N          skip N digits
+  take l|r N               show the next N digits of one side
+  zip N                    show the next N pairs
+  twist                    swap the sides
+  map pitch|duration|velocity NAME [PARAM]
+                           e.g. map pitch dorian 62, map duration linear 120
+  tempo BPM | program P | tpq N
+  play [N]                 compose N notes (default 16) and play them
+  compose N                compose N notes without playing
+  write FILE               write the last notes as a MIDI file
+  snip NAME FROM TO        keep pairs FROM..TO as a snippet
+  splice NAME              play a snippet before the live digits
+  snippets | status | help | quit";
+
+/// Everything a REPL line can see and change.
+pub struct Session {
+    ds:       DualStream,
+    registry: MapperRegistry,
+    pitch:    String,
+    duration: String,
+    velocity: String,
+    tempo:    f64,
+    program:  u8,
+    tpq:      u16,
+    /// The notes last composed, for `write`.
+    last:     Option<MidiTrack>,
+    #[cfg(feature = "playback")]
+    port:     String,
+}
+
+/// What to do after a line.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    /// Print this and read the next line.
+    Say(String),
+    /// Compose done; play (or summarise) the last track.
+    Play,
+    Quit,
+}
+
+impl Session {
+    pub fn new(ds: DualStream) -> Self {
+        Session {
+            ds,
+            registry: MapperRegistry::with_builtins(),
+            pitch:    "major:60".to_string(),
+            duration: "musical".to_string(),
+            velocity: "fixed:100".to_string(),
+            tempo:    120.0,
+            program:  0,
+            tpq:      480,
+            last:     None,
+            #[cfg(feature = "playback")]
+            port:     String::new(),
+        }
+    }
+
+    /// Run one command line.
+    pub fn eval(&mut self, line: &str) -> Result<Outcome, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&cmd, args)) = words.split_first() else { return Ok(Outcome::Say(String::new())) };
+        let num = |i: usize, default: Option<usize>| -> Result<usize, String> {
+            match (args.get(i), default) {
+                (Some(s), _)     => s.parse().map_err(|_| format!("'{}' is not a number", s)),
+                (None, Some(d))  => Ok(d),
+                (None, None)     => Err(format!("{} needs a number; try help", cmd)),
+            }
+        };
+        let say = |s: String| Ok(Outcome::Say(s));
+
+        match cmd {
+            "left" | "right" => {
+                let spec = match args {
+                    [name]       => name.to_string(),
+                    [name, base] => format!("{}:{}", name, base),
+                    _            => return Err(format!("usage: {} CONST [BASE]", cmd)),
+                };
+                let cfg = parse_stream(&spec)?;
+                let other = |c: Option<SpigotConfig>| c.ok_or_else(||
+                    "the other side streams a custom source, which can't be restarted".to_string());
+                let (left, right) = match cmd {
+                    "left" => (cfg, other(self.ds.right_config())?),
+                    _      => (other(self.ds.left_config())?, cfg),
+                };
+                self.replace_stream(left, right);
+                say(self.ds.status())
+            }
+            "drop" => {
+                let n = num(1, None)?;
+                match args.first().copied() {
+                    Some("l")    => { self.ds.left().drop(n); }
+                    Some("r")    => { self.ds.right().drop(n); }
+                    Some("both") => self.ds.zip_drop(n),
+                    _            => return Err("usage: drop l|r|both N".to_string()),
+                }
+                say(format!("left @ {}, right @ {}", self.ds.left_pos(), self.ds.right_pos()))
+            }
+            "take" => {
+                let n = num(1, Some(8))?;
+                let (digits, base) = match args.first().copied() {
+                    Some("l") => (self.ds.left().take(n), self.ds.left_base()),
+                    Some("r") => (self.ds.right().take(n), self.ds.right_base()),
+                    _         => return Err("usage: take l|r N".to_string()),
+                };
+                say(format!("{} (base {})", digits.iter().map(|&d| digit_char(d)).collect::<String>(), base))
+            }
+            "zip" => {
+                let pairs = self.ds.zip_take(num(0, Some(8))?);
+                say(pairs.iter().map(|&(l, r)| format!("{}{}", digit_char(l), digit_char(r)))
+                    .collect::<Vec<_>>().join(" "))
+            }
+            "twist" => { self.ds.twist(); say(self.ds.status()) }
+            "map" => {
+                let (kind, name) = match args {
+                    [kind, name, ..] => (*kind, *name),
+                    _ => return Err("usage: map pitch|duration|velocity NAME [PARAM]".to_string()),
+                };
+                let spec = match &args[2..] {
+                    []    => name.to_string(),
+                    param => format!("{}:{}", name, param.join(" ")),
+                };
+                // Build it now, so a typo is reported on its own line.
+                let ctx = self.context();
+                match kind {
+                    "pitch"    => { self.registry.pitch(&spec, &ctx).map_err(|e| e.to_string())?; self.pitch = spec.clone(); }
+                    "duration" => { self.registry.duration(&spec, &ctx).map_err(|e| e.to_string())?; self.duration = spec.clone(); }
+                    "velocity" => { self.registry.velocity(&spec, &ctx).map_err(|e| e.to_string())?; self.velocity = spec.clone(); }
+                    _ => return Err(format!("no '{}' map; pitch, duration or velocity", kind)),
+                }
+                say(format!("{} map: {}", kind, spec))
+            }
+            "tempo" => {
+                let bpm: f64 = args.first().and_then(|s| s.parse().ok())
+                    .filter(|b: &f64| b.is_finite() && *b > 0.0)
+                    .ok_or("usage: tempo BPM (above 0)")?;
+                self.tempo = bpm;
+                say(format!("tempo {}", bpm))
+            }
+            "program" => {
+                let gm: GeneralMidi = args.join(" ").parse().map_err(|e: spigot_midi::MidiError| e.to_string())?;
+                self.program = gm.program();
+                say(format!("program {} ({:?})", self.program, gm))
+            }
+            "tpq" => {
+                self.tpq = args.first().and_then(|s| s.parse().ok()).filter(|&t| t > 0)
+                    .ok_or("usage: tpq N (above 0)")?;
+                say(format!("{} ticks per quarter", self.tpq))
+            }
+            "compose" | "play" => {
+                let default = if cmd == "play" && self.last.is_some() && args.is_empty() { 0 } else { 16 };
+                let n = num(0, Some(default))?;
+                if n > 0 { self.last = Some(self.compose(n)?); }
+                if self.last.is_none() { return Err("nothing composed yet".to_string()); }
+                match cmd {
+                    "play" => Ok(Outcome::Play),
+                    _      => say(self.summary()),
+                }
+            }
+            "write" => {
+                let [path] = args else { return Err("usage: write FILE".to_string()) };
+                let track = self.last.as_ref().ok_or("nothing composed yet; try compose 16")?;
+                track.write_file(path).map_err(|e| format!("{}: {}", path, e))?;
+                say(format!("wrote {} notes to {}", track.notes.len(), path))
+            }
+            "snip" => {
+                let [key, _, _] = args else { return Err("usage: snip NAME FROM TO".to_string()) };
+                let (from, to) = (num(1, None)?, num(2, None)?);
+                self.ds.snip(key, from, to).map_err(|e| e.to_string())?;
+                say(format!("snippet '{}': {} pairs", key, to - from))
+            }
+            "splice" => {
+                let [key] = args else { return Err("usage: splice NAME".to_string()) };
+                self.ds.splice_snippet(key).map_err(|e| e.to_string())?;
+                say(format!("{} spliced pairs to come", self.ds.spliced_len()))
+            }
+            "snippets" => say(match self.ds.snippet_keys() {
+                keys if keys.is_empty() => "no snippets".to_string(),
+                keys => keys.join(", "),
+            }),
+            #[cfg(feature = "playback")]
+            "port" => {
+                self.port = args.join(" ");
+                say(format!("playing to {}", if self.port.is_empty() { "the first port" } else { &self.port }))
+            }
+            "status" => say(format!("{}\npitch {}, duration {}, velocity {}, tempo {}, program {}",
+                self.ds.status(), self.pitch, self.duration, self.velocity, self.tempo, self.program)),
+            "help" | "?" => say(HELP.to_string()),
+            "quit" | "exit" | "q" => Ok(Outcome::Quit),
+            _ => Err(format!("unknown command '{}'; try help", cmd)),
+        }
+    }
+
+    /// Notes for the next `n` pairs of the live zip.
+    fn compose(&mut self, n: usize) -> Result<MidiTrack, String> {
+        let ctx = self.context();
+        let err = |e: spigot_midi::MidiError| e.to_string();
+        let pairs = self.ds.zip_take(n);
+        MidiComposer::from_pairs(&pairs)
+            .tempo(self.tempo)
+            .instrument_raw(self.program)
+            .ticks_per_quarter(self.tpq)
+            .pitch_map(self.registry.pitch(&self.pitch, &ctx).map_err(err)?)
+            .duration_map(self.registry.duration(&self.duration, &ctx).map_err(err)?)
+            .velocity_map(self.registry.velocity(&self.velocity, &ctx).map_err(err)?)
+            .description("spigot_midi repl")
+            .compose(pairs.len())
+            .map_err(err)
+    }
+
+    fn context(&self) -> MapperArgs {
+        MapperArgs {
+            left_base:  self.ds.left_base(),
+            right_base: self.ds.right_base(),
+            ticks_per_quarter: self.tpq as u32,
+            ..MapperArgs::default()
+        }
+    }
+
+    /// A fresh stream on `left` and `right`, keeping the snippets.
+    fn replace_stream(&mut self, left: SpigotConfig, right: SpigotConfig) {
+        let mut ds = DualStream::from_configs(left, right);
+        for key in self.ds.snippet_keys() {
+            if let Some(pairs) = self.ds.get_snippet(key) {
+                ds.insert_snippet(key, pairs.clone());
+            }
+        }
+        self.ds = ds;
+    }
+
+    /// The last track's length and pitches, for when it can't be heard.
+    fn summary(&self) -> String {
+        let Some(track) = &self.last else { return String::new() };
+        let pitches: Vec<String> = track.notes.iter().map(|n| n.pitch.to_string()).collect();
+        format!("{} notes, {:.1} s: {}", track.notes.len(), track.seconds_at(track.end_tick()), pitches.join(" "))
+    }
+
+    /// Sound the last track, or describe it if playback isn't built in.
+    fn play(&self) -> Result<String, String> {
+        #[cfg(feature = "playback")]
+        if let Some(track) = &self.last {
+            let port = match self.port.as_str() {
+                ""   => spigot_midi::playback::PortSelector::First,
+                name => name.into(),
+            };
+            track.play(port).map_err(|e| e.to_string())?;
+        }
+        #[cfg(not(feature = "playback"))]
+        return Ok(format!("{}  (no playback built in; use --features playback)", self.summary()));
+        #[cfg(feature = "playback")]
+        Ok(self.summary())
+    }
+}
+
+/// Read commands from stdin until `quit` or end of input.
+pub fn run(ds: DualStream) {
+    println!("  spigot_midi live REPL — `help` lists commands.\n");
+    let mut session = Session::new(ds);
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().ok();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 { break; }
+        let result = session.eval(&line).and_then(|outcome| match outcome {
+            Outcome::Say(text) => Ok(text),
+            Outcome::Play      => session.play(),
+            Outcome::Quit      => Err(String::new()),
+        });
+        match result {
+            Ok(text) if text.is_empty() => {}
+            Ok(text)                   => println!("{}", text),
+            Err(e) if e.is_empty()     => break,
+            Err(e)                     => println!("  ⚠  {}", e),
+        }
+    }
+    println!();
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spigot_stream::Constant;
+
+    fn say(s: &mut Session, line: &str) -> String {
+        match s.eval(line) {
+            Ok(Outcome::Say(text)) => text,
+            other => panic!("{}: {:?}", line, other),
+        }
+    }
+
+    #[test]
+    fn commands_act_on_the_session() {
+        let mut s = Session::new(DualStream::new(Constant::Pi, Constant::E));
+        say(&mut s, "left champernowne");
+        say(&mut s, "right champernowne 16");
+        assert_eq!(say(&mut s, "drop l 10"), "left @ 10, right @ 0");
+        assert_eq!(say(&mut s, "zip 3"), "10 01 12");
+        say(&mut s, "map pitch custom 0 7");
+        say(&mut s, "map duration fixed 240");
+        assert_eq!(say(&mut s, "compose 4"), "4 notes, 1.0 s: 79 84 91 96");
+        assert_eq!(s.eval("play"), Ok(Outcome::Play));
+        assert_eq!(s.eval("quit"), Ok(Outcome::Quit));
+    }
+
+    #[test]
+    fn snippets_survive_a_new_stream_and_errors_explain() {
+        let mut s = Session::new(DualStream::new(Constant::Pi, Constant::E));
+        say(&mut s, "left thuemorse 2");
+        say(&mut s, "snip a 0 4");
+        say(&mut s, "right champernowne");
+        assert_eq!(say(&mut s, "snippets"), "a");
+        assert_eq!(say(&mut s, "splice a"), "4 spliced pairs to come");
+
+        assert!(s.eval("map pitch blues").unwrap_err().contains("unknown pitch mapper"));
+        assert!(s.eval("drop x 3").is_err());
+        assert!(s.eval("write out.mid").unwrap_err().contains("nothing composed"));
+        assert!(s.eval("frobnicate").unwrap_err().contains("help"));
+    }
+}
@@ -0,0 +1,309 @@
+//! Writing a long composition straight to its file, a pair at a time.
+//!
+//! [`MidiComposer::compose`] keeps every note until the track is done,
+//! which is fine for a song and wasteful for millions of notes.
+//! [`MidiComposer::compose_streaming`] resolves each pair, turns it into
+//! events, and encodes them through a fixed-size buffer once nothing still
+//! to come can land before them; only the notes still sounding are held.
+//! The bytes are exactly those of `compose(n)?.to_bytes()`.
+//!
+//! A track chunk starts with its length, so the writer has to seek back
+//! and fill it in.  For a pipe or socket,
+//! [`MidiComposer::compose_streaming_two_pass`] composes twice instead:
+//! once to count the bytes, once to send them.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+
+use crate::{split_note, Encoder, MidiComposer, MidiError, MidiTrack, Note, TimedEvent};
+
+/// Bytes buffered between the encoder and the writer.
+const BUFFER: usize = 64 * 1024;
+
+/// Where the MTrk length sits: after the 14-byte header and `MTrk`.
+const TRACK_LENGTH_AT: u64 = 14 + 4;
+
+impl MidiComposer {
+    /// Compose `n` pairs into `writer` as a Type-0 MIDI file while they
+    /// are resolved, without holding the track in memory.  The track
+    /// length is patched in at the end, so the writer must seek; see
+    /// [`compose_streaming_two_pass`](Self::compose_streaming_two_pass)
+    /// for one that can't.
+    ///
+    /// ```rust,no_run
+    /// use std::fs::File;
+    /// use spigot_midi::MidiComposer;
+    /// use dual_spigot::DualStream;
+    /// use spigot_stream::Constant;
+    ///
+    /// MidiComposer::new(DualStream::new(Constant::Pi, Constant::E))
+    ///     .compose_streaming(5_000_000, File::create("long.mid").unwrap())
+    ///     .unwrap();
+    /// ```
+    pub fn compose_streaming<W: Write + Seek>(self, n: usize, mut writer: W) -> Result<(), MidiError> {
+        let start = writer.stream_position()?;
+        let len = self.write_streamed(n, &mut writer, 0)?;
+        writer.seek(SeekFrom::Start(start + TRACK_LENGTH_AT))?;
+        writer.write_all(&len.to_be_bytes())?;
+        writer.seek(SeekFrom::Start(start + TRACK_LENGTH_AT + 4 + len as u64))?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Like [`compose_streaming`](Self::compose_streaming) for a writer
+    /// that can't seek: `make` is called twice and must build the same
+    /// composer both times, the first to measure the track and the second
+    /// to write it.
+    pub fn compose_streaming_two_pass<F, W>(mut make: F, n: usize, mut writer: W) -> Result<(), MidiError>
+    where F: FnMut() -> MidiComposer, W: Write
+    {
+        let len = make().write_streamed(n, &mut io::sink(), 0)?;
+        if make().write_streamed(n, &mut writer, len)? != len {
+            return Err(MidiError::param("the second composer wrote a different track than the first"));
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the header and a track chunk claiming `length` bytes; returns
+    /// the track's actual length.
+    fn write_streamed<W: Write>(mut self, n: usize, writer: &mut W, length: u32) -> Result<u32, MidiError> {
+        if n == 0 { return Err(MidiError::param("n must be > 0")); }
+
+        let shell = self.shell();
+        let mut out = TrackWriter {
+            out:     BufWriter::with_capacity(BUFFER, writer),
+            encoder: Encoder::new(shell.running_status),
+            scratch: Vec::new(),
+            len:     0,
+        };
+        out.out.write_all(b"MThd")?;
+        out.out.write_all(&6u32.to_be_bytes())?;
+        out.out.write_all(&0u16.to_be_bytes())?;
+        out.out.write_all(&1u16.to_be_bytes())?;
+        out.out.write_all(&shell.ticks_per_quarter.to_be_bytes())?;
+        out.out.write_all(b"MTrk")?;
+        out.out.write_all(&length.to_be_bytes())?;
+        for e in shell.head_events() {
+            out.write(e)?;
+        }
+
+        // Each note is sent once the next is known, which decides its tie.
+        let bar = self.tie_barlines.then(|| self.bar_ticks());
+        let mut events = NoteEvents::new(&shell);
+        let mut pieces = Vec::new();
+        let mut tick: u64 = 0;
+        let mut prev: Option<(Note, bool)> = None;
+        for _ in 0..n {
+            let Some((left, right)) = self.stream.next_pair() else { break };
+            let notes = self.track_notes(left, right);
+            let downs: Vec<bool> = self.pedal.drain(..).collect();
+            for (i, note) in notes.iter().enumerate() {
+                let down = downs.get(i).copied().unwrap_or(false);
+                match bar {
+                    Some(bar) => { split_note(note, tick, bar, &mut pieces); }
+                    None      => pieces.push(note.clone()),
+                }
+                tick += note.duration as u64;
+                for piece in pieces.drain(..) {
+                    if let Some((note, down)) = prev.replace((piece, down)) {
+                        events.note(&note, prev.as_ref().map(|(next, _)| next), down);
+                    }
+                }
+            }
+            for e in events.ready() {
+                out.write(e)?;
+            }
+        }
+        if let Some((mut note, down)) = prev {
+            note.resize(note.duration + self.bar_padding());
+            events.note(&note, None, down);
+        }
+        for e in events.finish() {
+            out.write(e)?;
+        }
+        out.out.flush()?;
+        u32::try_from(out.len).map_err(|_| MidiError::param(format!(
+            "track of {} bytes is too long for a MIDI file", out.len)))
+    }
+
+    /// A track with this composer's settings and no notes.
+    fn shell(&self) -> MidiTrack {
+        MidiTrack {
+            notes:             Vec::new(),
+            ticks_per_quarter: self.tpq,
+            tempo_bpm:         self.tempo_bpm,
+            instrument:        self.instrument,
+            channel:           self.channel,
+            description:       self.description.clone(),
+            running_status:    self.running_status,
+            splits:            self.splits.clone(),
+            pedal:             Vec::new(),
+            bank:              self.bank,
+            tempo_changes:     Vec::new(),
+            time_signature:    self.time_sig,
+            provenance:        self.provenance.clone(),
+        }
+    }
+}
+
+/// Encodes events through the buffer, counting the track's bytes.
+struct TrackWriter<W: Write> {
+    out:     BufWriter<W>,
+    encoder: Encoder,
+    scratch: Vec<u8>,
+    len:     u64,
+}
+
+impl<W: Write> TrackWriter<W> {
+    fn write(&mut self, e: TimedEvent) -> io::Result<()> {
+        self.scratch.clear();
+        self.encoder.encode(e, &mut self.scratch);
+        self.len += self.scratch.len() as u64;
+        self.out.write_all(&self.scratch)
+    }
+}
+
+/// [`MidiTrack::timed_events`] one note at a time.  Events wait, keyed by
+/// tick and then order of creation as the sort there would leave them,
+/// until the notes have moved past them; a Note Off still waiting is
+/// brought forward if its key strikes again first.
+struct NoteEvents<'a> {
+    track:          &'a MidiTrack,
+    pedal_channels: Vec<u8>,
+    pending:        BTreeMap<(u64, u64), TimedEvent>,
+    /// The waiting Note Off of each pitch.
+    sounding:       [Option<(u64, u64)>; 256],
+    seq:            u64,
+    tick:           u64,
+    end:            u64,
+    held:           bool,
+    pedal_down:     bool,
+}
+
+impl<'a> NoteEvents<'a> {
+    fn new(track: &'a MidiTrack) -> Self {
+        NoteEvents {
+            track,
+            pedal_channels: track.pedal_channels(),
+            pending:        BTreeMap::new(),
+            sounding:       [None; 256],
+            seq:            0,
+            tick:           0,
+            end:            0,
+            held:           false,
+            pedal_down:     false,
+        }
+    }
+
+    fn push(&mut self, e: TimedEvent) -> (u64, u64) {
+        let key = (e.tick, self.seq);
+        self.seq += 1;
+        self.pending.insert(key, e);
+        key
+    }
+
+    /// Add `note`, followed by `next` if there is one.
+    fn note(&mut self, note: &Note, next: Option<&Note>, down: bool) {
+        if down != self.pedal_down {
+            for pc in self.pedal_channels.clone() {
+                self.push(TimedEvent::channel(self.tick, 0xB0 | pc, &[64, if down { 127 } else { 0 }]));
+            }
+            self.pedal_down = down;
+        }
+        self.end = self.end.max(self.tick + note.gate as u64);
+        if note.rest {
+            self.held = false;
+            self.tick += note.duration as u64;
+            return;
+        }
+        if let Some(key) = self.sounding[note.pitch as usize].take() {
+            if key.0 > self.tick {
+                if let Some(mut off) = self.pending.remove(&key) {
+                    off.tick = self.tick;
+                    self.pending.insert((self.tick, key.1), off);
+                }
+            }
+        }
+        let (ch, _) = self.track.route(note.pitch);
+        if !self.held {
+            self.push(TimedEvent::channel(self.tick, 0x90 | ch, &[note.pitch, note.velocity]));
+        }
+        if let Some(pressure) = note.aftertouch {
+            let mid = self.tick + note.gate as u64 / 2;
+            self.push(TimedEvent::channel(mid, 0xD0 | ch, &[pressure]));
+        }
+        self.held = note.tied && next.is_some_and(|n| !n.rest && n.pitch == note.pitch);
+        if !self.held {
+            let off = self.tick + note.gate as u64;
+            let key = self.push(TimedEvent::channel(off, 0x80 | ch, &[note.pitch, note.release]));
+            self.sounding[note.pitch as usize] = Some(key);
+        }
+        self.tick += note.duration as u64;
+    }
+
+    /// Events before the next note's onset, which nothing can now precede.
+    fn ready(&mut self) -> Vec<TimedEvent> {
+        let later = self.pending.split_off(&(self.tick, 0));
+        std::mem::replace(&mut self.pending, later).into_values().collect()
+    }
+
+    /// Everything left, with the pedal lifted and the End of Track.
+    fn finish(mut self) -> Vec<TimedEvent> {
+        if self.pedal_down {
+            for pc in self.pedal_channels.clone() {
+                self.push(TimedEvent::channel(self.tick, 0xB0 | pc, &[64, 0]));
+            }
+        }
+        self.push(TimedEvent::meta(self.end.max(self.tick), 0x2F, &[]));
+        self.pending.into_values().collect()
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use dual_spigot::DualStream;
+    use spigot_stream::Constant;
+    use crate::{ChordShape, Expression};
+    use super::*;
+
+    /// Overlapping gates, chords, rests, pedal, aftertouch and ties at once.
+    fn busy() -> MidiComposer {
+        MidiComposer::new(DualStream::new(Constant::Champernowne, Constant::ThueMorse))
+            .gate_ratio(1.7)
+            .chords(ChordShape::Sus4)
+            .rest_on(|l, _| l == 3)
+            .sustain_pattern(&[true, true, false])
+            .aftertouch(Expression::pair(|l, r| l * 10 + r))
+            .split_below(64, 1, 32)
+            .tie_at_barlines(true)
+    }
+
+    #[test]
+    fn streams_the_same_bytes_as_compose() {
+        for running_status in [true, false] {
+            let mut file = Cursor::new(Vec::new());
+            busy().running_status(running_status).compose_streaming(300, &mut file).unwrap();
+            assert_eq!(file.into_inner(), busy().running_status(running_status).compose(300).unwrap().to_bytes());
+        }
+        let plain = || MidiComposer::new(DualStream::new(Constant::Champernowne, Constant::Champernowne))
+            .time_signature(3, 4)
+            .quantize_to_bars(true);
+        let mut file = Cursor::new(Vec::new());
+        plain().compose_streaming(1000, &mut file).unwrap();
+        assert_eq!(file.into_inner(), plain().compose(1000).unwrap().to_bytes());
+    }
+
+    #[test]
+    fn two_pass_writes_to_a_plain_writer() {
+        let mut out = Vec::new();
+        MidiComposer::compose_streaming_two_pass(busy, 200, &mut out).unwrap();
+        assert_eq!(out, busy().compose(200).unwrap().to_bytes());
+        assert!(busy().compose_streaming(0, Cursor::new(Vec::new())).is_err());
+    }
+}
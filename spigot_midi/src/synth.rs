@@ -0,0 +1,297 @@
+//! Built-in audio rendering — a tiny software synthesizer.
+//!
+//! [`MidiTrack::render_wav`] turns a composed track into a 16-bit mono WAV
+//! file, so a piece can be auditioned without a softsynth installed.  Each
+//! General MIDI family gets one simple [`Voice`]: two-operator FM for
+//! keyboards and mallets, filtered sawtooth for strings and brass, square
+//! waves for reeds, and so on.  The aim is a recognisable sketch of the
+//! orchestration, not realism.
+
+use std::io::Write;
+
+use crate::MidiTrack;
+
+// ════════════════════════════════════════════════════════════════════════════
+// Voice — per-family timbre recipe
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Raw oscillator shape used by a [`Voice`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+    /// Two-operator FM: a sine carrier phase-modulated by a sine modulator.
+    Fm,
+}
+
+/// Timbre recipe: oscillator, ADSR envelope, and optional low-pass filter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Voice {
+    pub waveform: Waveform,
+    /// Attack time in seconds.
+    pub attack:   f32,
+    /// Decay time in seconds (attack peak → sustain level).
+    pub decay:    f32,
+    /// Sustain level 0.0–1.0.
+    pub sustain:  f32,
+    /// Release time in seconds after the note ends.
+    pub release:  f32,
+    /// FM modulator frequency as a multiple of the carrier (FM only).
+    pub fm_ratio: f32,
+    /// FM modulation index (FM only); decays with the envelope.
+    pub fm_index: f32,
+    /// One-pole low-pass cutoff as a multiple of the fundamental; 0 = off.
+    pub cutoff:   f32,
+}
+
+impl Voice {
+    /// Pick a voice for a General MIDI program by its family of eight.
+    pub fn for_program(program: u8) -> Self {
+        let v = |waveform, attack, decay, sustain, release, fm_ratio, fm_index, cutoff| Voice {
+            waveform, attack, decay, sustain, release, fm_ratio, fm_index, cutoff,
+        };
+        match program.min(127) / 8 {
+            0  => v(Waveform::Fm,       0.005, 0.60, 0.25, 0.30, 1.0,  2.5, 0.0), // piano
+            1  => v(Waveform::Fm,       0.002, 0.80, 0.00, 0.40, 3.5,  3.0, 0.0), // mallets
+            2  => v(Waveform::Sine,     0.020, 0.05, 0.90, 0.08, 0.0,  0.0, 0.0), // organ
+            3  => v(Waveform::Saw,      0.003, 0.35, 0.10, 0.15, 0.0,  0.0, 4.0), // guitar
+            4  => v(Waveform::Saw,      0.005, 0.25, 0.50, 0.10, 0.0,  0.0, 2.0), // bass
+            5  => v(Waveform::Saw,      0.080, 0.20, 0.80, 0.30, 0.0,  0.0, 6.0), // strings
+            6  => v(Waveform::Saw,      0.120, 0.20, 0.85, 0.50, 0.0,  0.0, 5.0), // ensemble
+            7  => v(Waveform::Saw,      0.030, 0.10, 0.80, 0.15, 0.0,  0.0, 8.0), // brass
+            8  => v(Waveform::Square,   0.030, 0.10, 0.75, 0.12, 0.0,  0.0, 5.0), // reed
+            9  => v(Waveform::Triangle, 0.040, 0.10, 0.80, 0.12, 0.0,  0.0, 0.0), // pipe
+            10 => v(Waveform::Square,   0.005, 0.10, 0.70, 0.10, 0.0,  0.0, 12.0), // synth lead
+            11 => v(Waveform::Saw,      0.300, 0.40, 0.70, 0.80, 0.0,  0.0, 3.0), // synth pad
+            12 => v(Waveform::Fm,       0.200, 0.50, 0.60, 0.90, 1.41, 4.0, 0.0), // synth fx
+            13 => v(Waveform::Fm,       0.003, 0.50, 0.15, 0.30, 2.0,  2.0, 0.0), // ethnic
+            14 => v(Waveform::Fm,       0.001, 0.25, 0.00, 0.10, 1.47, 6.0, 0.0), // percussive
+            _  => v(Waveform::Triangle, 0.010, 0.20, 0.40, 0.20, 0.0,  0.0, 0.0), // sound fx
+        }
+    }
+
+    /// Envelope level at `t` seconds after note-on, for a note held `gate` seconds.
+    fn envelope(&self, t: f32, gate: f32) -> f32 {
+        let held = |t: f32| {
+            if t < self.attack {
+                t / self.attack.max(1e-4)
+            } else if t < self.attack + self.decay {
+                let x = (t - self.attack) / self.decay.max(1e-4);
+                1.0 + (self.sustain - 1.0) * x
+            } else {
+                self.sustain
+            }
+        };
+        if t < gate {
+            held(t)
+        } else {
+            let x = (t - gate) / self.release.max(1e-4);
+            if x >= 1.0 { 0.0 } else { held(gate) * (1.0 - x) }
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Rendering
+// ════════════════════════════════════════════════════════════════════════════
+
+/// MIDI note number → frequency in Hz (A4 = 440 Hz).
+fn note_freq(pitch: u8) -> f32 {
+    440.0 * 2f32.powf((pitch as f32 - 69.0) / 12.0)
+}
+
+/// Render one note into `out`, starting at sample `start`.
+fn render_note(
+    out: &mut [f32], start: usize, voice: &Voice,
+    pitch: u8, velocity: u8, gate: f32, sample_rate: u32,
+) {
+    let sr    = sample_rate as f32;
+    let freq  = note_freq(pitch);
+    let amp   = velocity as f32 / 127.0 * 0.3;
+    let len   = ((gate + voice.release) * sr) as usize;
+    let alpha = if voice.cutoff > 0.0 {
+        let fc = (freq * voice.cutoff).min(sr * 0.45);
+        1.0 - (-2.0 * std::f32::consts::PI * fc / sr).exp()
+    } else {
+        1.0
+    };
+    let tau = 2.0 * std::f32::consts::PI;
+
+    let mut phase = 0.0f32;
+    let mut lp    = 0.0f32;
+    for i in 0..len {
+        let idx = start + i;
+        if idx >= out.len() { break; }
+        let t   = i as f32 / sr;
+        let env = voice.envelope(t, gate);
+        let raw = match voice.waveform {
+            Waveform::Sine     => (tau * phase).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Saw      => 2.0 * phase - 1.0,
+            Waveform::Square   => if phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Fm       => {
+                let m = (tau * phase * voice.fm_ratio).sin() * voice.fm_index * env;
+                (tau * phase + m).sin()
+            }
+        };
+        lp += alpha * (raw - lp);
+        out[idx] += lp * env * amp;
+        phase = (phase + freq / sr).fract();
+    }
+}
+
+impl MidiTrack {
+    /// Render the track to mono `f32` samples in −1.0..=1.0 with the
+    /// built-in synthesizer.
+    ///
+    /// Notes are voiced according to the instrument family of the track (or
+    /// of their [`RegisterSplit`](crate::RegisterSplit)); release
+    /// tails are allowed to overlap the following note.  The mix is scaled
+    /// down if it would clip.
+    pub fn render_samples(&self, sample_rate: u32) -> Vec<f32> {
+        let sample_rate = sample_rate.max(1);
+        let voice = Voice::for_program(self.instrument);
+        let longest_release = self.splits.iter()
+            .map(|s| Voice::for_program(s.instrument).release)
+            .fold(voice.release, f32::max);
+        let total_secs = self.seconds_at(self.end_tick()) + longest_release as f64;
+        let mut out = vec![0.0f32; (total_secs * sample_rate as f64).ceil() as usize + 1];
+
+        let mut tick: u64 = 0;
+        let mut held = false;
+        for (i, note) in self.notes.iter().enumerate() {
+            // A tied chain sounds once, for the chain's combined length.
+            let continuation = held;
+            held = self.ties_into_next(i);
+            if continuation || note.rest {
+                tick += note.duration as u64;
+                continue;
+            }
+            let mut ticks = 0;
+            let mut j = i;
+            while self.ties_into_next(j) {
+                ticks += self.notes[j].duration as u64;
+                j += 1;
+            }
+            ticks += self.notes[j].gate as u64;
+            let onset = self.seconds_at(tick);
+            let start = (onset * sample_rate as f64) as usize;
+            let gate  = (self.seconds_at(tick + ticks) - onset) as f32;
+            let (_, program) = self.route(note.pitch);
+            let voice = if program == self.instrument {
+                voice
+            } else {
+                Voice::for_program(program)
+            };
+            render_note(&mut out, start, &voice, note.pitch, note.velocity, gate, sample_rate);
+            tick += note.duration as u64;
+        }
+
+        let peak = out.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        if peak > 1.0 {
+            for s in &mut out { *s /= peak; }
+        }
+        out
+    }
+
+    /// Render the track with the built-in synthesizer and write a 16-bit
+    /// mono PCM WAV file to `path`.
+    pub fn render_wav(&self, path: &str, sample_rate: u32) -> std::io::Result<()> {
+        let bytes = wav_bytes(&self.render_samples(sample_rate), sample_rate);
+        let mut f = std::fs::File::create(path)?;
+        f.write_all(&bytes)
+    }
+}
+
+/// Encode mono samples as a 16-bit PCM RIFF/WAVE file.
+pub fn wav_bytes(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+
+    // ── RIFF header ───────────────────────────────────────────────────────
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+
+    // ── fmt chunk: PCM, mono, 16-bit ──────────────────────────────────────
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());               // PCM
+    out.extend_from_slice(&1u16.to_le_bytes());               // mono
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * 2).to_le_bytes());  // byte rate
+    out.extend_from_slice(&2u16.to_le_bytes());               // block align
+    out.extend_from_slice(&16u16.to_le_bytes());              // bits/sample
+
+    // ── data chunk ────────────────────────────────────────────────────────
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for &s in samples {
+        let v = (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    out
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Note;
+
+    fn track(notes: Vec<Note>, instrument: u8) -> MidiTrack {
+        MidiTrack {
+            notes,
+            ticks_per_quarter: 480,
+            tempo_bpm:         120.0,
+            instrument,
+            channel:           0,
+            description:       "test".to_string(),
+            running_status:    true,
+            splits:            Vec::new(),
+            pedal:             Vec::new(),
+            bank:              None,
+            tempo_changes:     Vec::new(),
+            time_signature:    None,
+            provenance:  None,
+        }
+    }
+
+    #[test]
+    fn wav_header_layout() {
+        let bytes = wav_bytes(&[0.0, 0.5, -0.5], 8000);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(&bytes[8..12], b"WAVE");
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(u32::from_le_bytes(bytes[24..28].try_into().unwrap()), 8000);
+        assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), 6);
+        assert_eq!(bytes.len(), 44 + 6);
+    }
+
+    #[test]
+    fn render_length_covers_notes_and_release() {
+        // Two quarter notes at 120 BPM = 1.0 s, plus the piano release tail.
+        let t = track(vec![
+            Note::new(60, 480, 100),
+            Note::new(64, 480, 100),
+        ], 0);
+        let s = t.render_samples(8000);
+        let release = Voice::for_program(0).release;
+        assert!(s.len() as f32 >= 8000.0 * (1.0 + release));
+        assert!(s.iter().any(|x| x.abs() > 0.01), "should not be silent");
+        assert!(s.iter().all(|x| x.abs() <= 1.0));
+    }
+
+    #[test]
+    fn every_family_has_a_voice() {
+        for p in (0..128).step_by(8) {
+            let v = Voice::for_program(p as u8);
+            assert!(v.release > 0.0 && v.sustain <= 1.0);
+        }
+    }
+}
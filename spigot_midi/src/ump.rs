@@ -0,0 +1,219 @@
+//! MIDI 2.0 output as Universal MIDI Packets.
+//!
+//! [`MidiTrack::to_ump`] writes the same events as [`MidiTrack::to_bytes`],
+//! as MIDI 2.0 Channel Voice messages for hosts that speak MIDI 2.0:
+//!
+//! * velocities are 16-bit and controllers 32-bit, scaled up from the
+//!   7-bit values with the specification's min-center-max rule, so 64 sits
+//!   exactly at the midpoint and 127 at full scale;
+//! * a note's aftertouch is sent as per-note pressure on that note rather
+//!   than channel pressure;
+//! * bank select folds into the Program Change message;
+//! * tempo and the track name go out as Flex Data, and timing as Delta
+//!   Clockstamps in the track's ticks.
+//!
+//! [`MidiTrack::to_ump_clip`] wraps the packets in a MIDI Clip File
+//! (`SMF2CLIP`, usually saved as `.midi2`).  Everything is on group 0.
+
+use std::io::Write;
+use std::path::Path;
+
+use crate::{MidiTrack, TimedEvent};
+
+/// Magic bytes opening a MIDI Clip File.
+pub const CLIP_MAGIC: &[u8; 8] = b"SMF2CLIP";
+
+/// Largest tick count one Delta Clockstamp holds (20 bits).
+const CLOCKSTAMP_MAX: u64 = 0xF_FFFF;
+
+impl MidiTrack {
+    /// The track as a UMP sequence: Delta Clockstamp resolution, Start of
+    /// Clip, every event after the ticks since the one before, End of Clip.
+    pub fn to_ump(&self) -> Vec<u32> {
+        // Translate in insertion order, where each aftertouch follows its
+        // own Note On, then put in time order as `to_bytes` does.
+        let mut packets = Vec::new();
+        let mut bank = [None::<u8>; 16];
+        let mut bank_lsb = [0u8; 16];
+        let mut last_note = [0u8; 16];
+        for TimedEvent { tick, bytes } in self.timed_events() {
+            let status = bytes[0];
+            let ch = status & 0x0F;
+            let words = match status & 0xF0 {
+                0xF0 => match (bytes[1], meta_data(&bytes)) {
+                    (0x51, micros) => flex_tempo(micros),
+                    (0x03, []) | (0x2F, _) => continue,
+                    (0x03, text) => flex_text(text),
+                    _ => continue,
+                },
+                0x90 => {
+                    last_note[ch as usize] = bytes[1];
+                    voice(0x9, ch, bytes[1], 0, scale_up(bytes[2], 16) << 16)
+                }
+                0x80 => voice(0x8, ch, bytes[1], 0, scale_up(bytes[2], 16) << 16),
+                0xD0 => voice(0xA, ch, last_note[ch as usize], 0, scale_up(bytes[1], 32)),
+                0xB0 if bytes[1] == 0x00 => { bank[ch as usize] = Some(bytes[2]); continue; }
+                0xB0 if bytes[1] == 0x20 => { bank_lsb[ch as usize] = bytes[2]; continue; }
+                0xB0 => voice(0xB, ch, bytes[1], 0, scale_up(bytes[2], 32)),
+                0xC0 => {
+                    let (flag, msb) = bank[ch as usize].map_or((0, 0), |msb| (1, msb));
+                    let data = (bytes[1] as u32) << 24 | (msb as u32) << 8 | bank_lsb[ch as usize] as u32;
+                    voice(0xC, ch, 0, flag, data)
+                }
+                _ => continue,
+            };
+            packets.push((tick, words));
+        }
+        packets.sort_by_key(|&(tick, _)| tick);
+
+        let mut ump = vec![0x0030_0000 | self.ticks_per_quarter as u32];
+        clockstamp(&mut ump, 0);
+        ump.extend_from_slice(&[0xF020_0000, 0, 0, 0]);
+        let mut last_tick = 0;
+        for (tick, words) in packets {
+            clockstamp(&mut ump, tick - last_tick);
+            ump.extend(words);
+            last_tick = tick;
+        }
+        clockstamp(&mut ump, self.end_tick().saturating_sub(last_tick));
+        ump.extend_from_slice(&[0xF021_0000, 0, 0, 0]);
+        ump
+    }
+
+    /// The track as a MIDI Clip File: `SMF2CLIP` and then
+    /// [`to_ump`](Self::to_ump), big-endian.
+    pub fn to_ump_clip(&self) -> Vec<u8> {
+        let mut out = CLIP_MAGIC.to_vec();
+        for word in self.to_ump() {
+            out.extend_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+
+    /// Write the MIDI Clip File to `path`.
+    pub fn write_ump_file<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut f = std::fs::File::create(path)?;
+        f.write_all(&self.to_ump_clip())?;
+        f.flush()
+    }
+}
+
+/// A 64-bit MIDI 2.0 Channel Voice message on group 0.
+fn voice(opcode: u8, channel: u8, index: u8, flags: u8, data: u32) -> Vec<u32> {
+    let head = 0x4000_0000 | (opcode as u32) << 20 | (channel as u32) << 16
+        | (index as u32 & 0x7F) << 8 | flags as u32;
+    vec![head, data]
+}
+
+/// Delta Clockstamps covering `ticks`, split where one can't hold them.
+fn clockstamp(ump: &mut Vec<u32>, mut ticks: u64) {
+    while ticks > CLOCKSTAMP_MAX {
+        ump.push(0x0040_0000 | CLOCKSTAMP_MAX as u32);
+        ticks -= CLOCKSTAMP_MAX;
+    }
+    ump.push(0x0040_0000 | ticks as u32);
+}
+
+/// The data of a meta event, after its type and length.
+fn meta_data(bytes: &[u8]) -> &[u8] {
+    let len_bytes = bytes[2..].iter().take_while(|&&b| b & 0x80 != 0).count() + 1;
+    &bytes[2 + len_bytes..]
+}
+
+/// Set Tempo as Flex Data, from the 24-bit microseconds of the meta event.
+fn flex_tempo(micros: &[u8]) -> Vec<u32> {
+    let micros = micros.iter().fold(0u32, |acc, &b| acc << 8 | b as u32);
+    vec![0xD010_0000, micros.saturating_mul(100), 0, 0]
+}
+
+/// The track name as Flex Data "MIDI Clip Name" text, 12 bytes a packet.
+fn flex_text(text: &[u8]) -> Vec<u32> {
+    let chunks: Vec<&[u8]> = text.chunks(12).collect();
+    let mut words = Vec::with_capacity(chunks.len() * 4);
+    for (i, chunk) in chunks.iter().enumerate() {
+        // Complete, start, continue or end of a multi-packet text.
+        let form = match (i == 0, i + 1 == chunks.len()) {
+            (true, true)   => 0,
+            (true, false)  => 1,
+            (false, false) => 2,
+            (false, true)  => 3,
+        };
+        let mut padded = [0u8; 12];
+        padded[..chunk.len()].copy_from_slice(chunk);
+        words.push(0xD010_0000 | form << 22 | 0x01 << 8 | 0x03);
+        words.extend(padded.chunks(4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]])));
+    }
+    words
+}
+
+/// A 7-bit value widened to `bits` (16 or 32) by min-center-max scaling:
+/// 0 stays 0, 64 lands on the midpoint and 127 on the top, with the upper
+/// half filled by repeating the low bits.
+pub fn scale_up(value: u8, bits: u32) -> u32 {
+    let value = value.min(127) as u32;
+    let shift = bits - 7;
+    let mut scaled = value << shift;
+    if value <= 64 { return scaled; }
+    let mut repeat = (value & 0x3F) << (shift - 6);
+    while repeat != 0 {
+        scaled |= repeat;
+        repeat >>= 6;
+    }
+    scaled
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Expression, MidiComposer};
+
+    #[test]
+    fn scales_seven_bit_values_min_center_max() {
+        assert_eq!(scale_up(0, 16), 0);
+        assert_eq!(scale_up(64, 16), 0x8000);
+        assert_eq!(scale_up(127, 16), 0xFFFF);
+        assert_eq!(scale_up(127, 32), 0xFFFF_FFFF);
+        assert!(scale_up(100, 16) > scale_up(99, 16));
+    }
+
+    #[test]
+    fn writes_notes_as_midi2_channel_voice() {
+        let track = MidiComposer::from_pairs(&[(5, 0), (5, 2)])
+            .bank(1, 2)
+            .aftertouch(Expression::Fixed(127))
+            .description("pi / e")
+            .compose(2).unwrap();
+        let ump = track.to_ump();
+
+        assert_eq!(ump[0], 0x0030_0000 | 480);
+        assert_eq!(&ump[2..6], &[0xF020_0000, 0, 0, 0]);
+        assert_eq!(&ump[ump.len() - 4..], &[0xF021_0000, 0, 0, 0]);
+        let find = |head: u32| ump.windows(2).find(|w| w[0] == head).map(|w| w[1]);
+        // Note On 60, velocity 100 at 16 bits.
+        assert_eq!(find(0x4090_3C00), Some(scale_up(100, 16) << 16));
+        // Program 0 with bank 1/2.
+        assert_eq!(find(0x40C0_0001), Some(0x0000_0102));
+        // Aftertouch is pressure on the note, not the channel.
+        assert_eq!(find(0x40A0_4000), Some(0xFFFF_FFFF));
+        // Tempo in 10 ns units, and the name as text.
+        assert!(ump.contains(&(crate::tempo_micros(120.0) * 100)));
+        assert!(ump.contains(&u32::from_be_bytes(*b"pi /")));
+    }
+
+    #[test]
+    fn clip_file_is_magic_then_big_endian_packets() {
+        let track = MidiComposer::from_pairs(&[(1, 1)]).compose(1).unwrap();
+        let clip = track.to_ump_clip();
+        assert_eq!(&clip[..8], CLIP_MAGIC);
+        assert_eq!(clip.len(), 8 + 4 * track.to_ump().len());
+        assert_eq!(&clip[8..12], &[0x00, 0x30, 0x01, 0xE0]);
+
+        let mut long = Vec::new();
+        clockstamp(&mut long, CLOCKSTAMP_MAX + 5);
+        assert_eq!(long, [0x004F_FFFF, 0x0040_0005]);
+    }
+}
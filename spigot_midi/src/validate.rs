@@ -0,0 +1,213 @@
+//! Pre-flight checks for a [`MidiTrack`].
+//!
+//! [`MidiTrack::validate`] reports anything that would produce a malformed
+//! file or surprise a hardware synth: data bytes above 127, zero-length or
+//! overlapping notes, and lengths that do not fit the MIDI encoding.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::MidiTrack;
+
+/// Largest value a MIDI variable-length quantity can hold (28 bits).
+pub(crate) const VLQ_MAX: u64 = 0x0FFF_FFFF;
+
+/// How serious a [`Issue`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Legal MIDI, but probably not what was intended.
+    Warning,
+    /// The file would be malformed or misread.
+    Error,
+}
+
+/// One finding from [`MidiTrack::validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    /// Index into [`MidiTrack::notes`], or `None` for track-level problems.
+    pub note:     Option<usize>,
+    pub message:  String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error   => "error",
+        };
+        match self.note {
+            Some(i) => write!(f, "{}: note {}: {}", level, i, self.message),
+            None    => write!(f, "{}: {}", level, self.message),
+        }
+    }
+}
+
+impl MidiTrack {
+    /// Check the track for problems before it is written or sent to a device.
+    ///
+    /// Returns every issue found, errors and warnings mixed, in track order;
+    /// an empty list means the track is clean.
+    pub fn validate(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        let mut track = |severity, message: String| {
+            issues.push(Issue { severity, note: None, message });
+        };
+
+        // ── track-level fields ────────────────────────────────────────────
+        if let Some(problem) = tempo_problem(self.tempo_bpm) {
+            track(Severity::Error, problem);
+        }
+        for &(at, bpm) in &self.tempo_changes {
+            if let Some(problem) = tempo_problem(bpm) {
+                track(Severity::Error, format!("{} (change at tick {})", problem, at));
+            }
+        }
+        if self.tempo_changes.windows(2).any(|w| w[1].0 < w[0].0) {
+            track(Severity::Warning, "tempo changes are not in tick order".to_string());
+        }
+        if self.ticks_per_quarter == 0 || self.ticks_per_quarter > 0x7FFF {
+            track(Severity::Error,
+                format!("ticks per quarter {} outside 1–32767", self.ticks_per_quarter));
+        }
+        if self.channel > 15 {
+            track(Severity::Error, format!("channel {} outside 0–15", self.channel));
+        }
+        if self.instrument > 127 {
+            track(Severity::Error, format!("instrument {} outside 0–127", self.instrument));
+        }
+        if self.notes.is_empty() {
+            track(Severity::Warning, "track has no notes".to_string());
+        }
+        let total = self.total_ticks();
+        if total > u32::MAX as u64 {
+            track(Severity::Error, format!("track length {} ticks exceeds u32", total));
+        }
+
+        // ── per-note checks ───────────────────────────────────────────────
+        let mut sounding: HashMap<(u8, u8), (usize, u64)> = HashMap::new();
+        let mut tick: u64 = 0;
+        for (i, note) in self.notes.iter().enumerate() {
+            let mut push = |severity, message: String| {
+                issues.push(Issue { severity, note: Some(i), message });
+            };
+            if note.rest {
+                if note.duration as u64 > VLQ_MAX {
+                    push(Severity::Error,
+                        format!("rest of {} ticks exceeds the MIDI delta-time limit", note.duration));
+                }
+                tick += note.duration as u64;
+                continue;
+            }
+            if note.pitch > 127 {
+                push(Severity::Error, format!("pitch {} outside 0–127", note.pitch));
+            }
+            if note.velocity > 127 {
+                push(Severity::Error, format!("velocity {} outside 0–127", note.velocity));
+            } else if note.velocity == 0 {
+                push(Severity::Warning, "velocity 0 is read as a Note Off".to_string());
+            }
+            if note.release > 127 {
+                push(Severity::Error, format!("release velocity {} outside 0–127", note.release));
+            }
+            if note.aftertouch.is_some_and(|a| a > 127) {
+                push(Severity::Error, "aftertouch outside 0–127".to_string());
+            }
+            if note.gate == 0 {
+                push(Severity::Warning, "zero-length note".to_string());
+            }
+            if note.duration.max(note.gate) as u64 > VLQ_MAX {
+                push(Severity::Error,
+                    format!("length {} exceeds the MIDI delta-time limit",
+                        note.duration.max(note.gate)));
+            }
+
+            if note.tied && !self.ties_into_next(i) {
+                push(Severity::Warning, "tie has no following note of the same pitch".to_string());
+            }
+
+            let key = (self.route(note.pitch).0, note.pitch);
+            let sounds = if self.ties_into_next(i) { note.duration } else { note.gate };
+            let end = tick + sounds as u64;
+            if let Some(&(prev, prev_end)) = sounding.get(&key) {
+                if prev_end > tick {
+                    push(Severity::Warning,
+                        format!("overlaps note {} on the same pitch and channel", prev));
+                }
+            }
+            sounding.insert(key, (i, end));
+            tick += note.duration as u64;
+        }
+
+        issues
+    }
+
+    /// True if [`validate`](Self::validate) finds no errors (warnings allowed).
+    pub fn is_valid(&self) -> bool {
+        self.validate().iter().all(|i| i.severity < Severity::Error)
+    }
+}
+
+/// Why `bpm` cannot be written as a Set Tempo event, if it cannot.
+fn tempo_problem(bpm: f64) -> Option<String> {
+    let micros = 60_000_000.0 / bpm;
+    if !bpm.is_finite() || bpm <= 0.0 {
+        Some(format!("tempo {} BPM is not a positive number", bpm))
+    } else if micros < 0.5 {
+        Some(format!("tempo {} BPM is too fast to encode", bpm))
+    } else if micros.round() > 0xFF_FFFF as f64 {
+        Some(format!("tempo {} BPM is too slow to encode", bpm))
+    } else {
+        None
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Note;
+
+    fn note(pitch: u8, duration: u32) -> Note {
+        Note::new(pitch, duration, 100)
+    }
+
+    fn track(notes: Vec<Note>) -> MidiTrack {
+        MidiTrack {
+            notes,
+            ticks_per_quarter: 480,
+            tempo_bpm:         120.0,
+            instrument:        0,
+            channel:           0,
+            description:       "test".to_string(),
+            running_status:    true,
+            splits:            Vec::new(),
+            pedal:             Vec::new(),
+            bank:              None,
+            tempo_changes:     Vec::new(),
+            time_signature:    None,
+            provenance:  None,
+        }
+    }
+
+    #[test]
+    fn clean_track_has_no_issues() {
+        let t = track(vec![note(60, 480), note(60, 480), note(64, 240)]);
+        assert!(t.validate().is_empty());
+        assert!(t.is_valid());
+    }
+
+    #[test]
+    fn reports_bad_notes_and_fields() {
+        let mut t = track(vec![note(200, 480), note(60, 0)]);
+        t.tempo_bpm = 0.0;
+        let issues = t.validate();
+        assert!(!t.is_valid());
+        assert!(issues.iter().any(|i| i.note.is_none() && i.message.contains("tempo")));
+        assert!(issues.iter().any(|i| i.note == Some(0) && i.severity == Severity::Error));
+        assert!(issues.iter().any(|i| i.note == Some(1) && i.message.contains("zero-length")));
+    }
+}
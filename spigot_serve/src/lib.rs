@@ -0,0 +1,418 @@
+//! # spigot_serve
+//!
+//! The spigot streams and MIDI composer over HTTP, so web frontends and
+//! installations can use the engine from another machine or language.
+//!
+//! | Endpoint | Answer |
+//! |---|---|
+//! | `GET /digits/{constant}?base=16&n=100` | JSON: the first `n` digits |
+//! | `POST /compose` | a standard MIDI file (`audio/midi`) |
+//! | `GET /zip?left=pi:16&right=e&rate=8` | WebSocket: one JSON digit pair per message |
+//!
+//! Streams are named `CONSTANT[:BASE]` as on the `spigot_midi` command line
+//! (`pi`, `e:16`, `thuemorse:2`, …).  `/compose` takes a JSON body whose
+//! fields all have defaults (see [`ComposeRequest`]):
+//!
+//! ```text
+//! curl -d '{"left":"pi","right":"e:12","notes":64,"scale":"dorian"}' \
+//!      http://127.0.0.1:8080/compose > pi_e.mid
+//! ```
+//!
+//! The WebSocket sends `{"index":0,"left":1,"right":7}` messages at `rate`
+//! pairs a second, `n` of them (without `n`, until the client goes away).
+//! Errors are JSON too: `{"error":"…"}` with status 400, 404 or 405.
+
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tungstenite::protocol::{Role, WebSocket};
+use tungstenite::Message;
+
+use dual_spigot::{BoxedSpigot, DualStream, SpigotConfig};
+use spigot_midi::provenance::parse_stream;
+use spigot_midi::{MidiError, Provenance};
+
+/// Most digits `/digits` returns, and most notes `/compose` writes, in one
+/// request.
+pub const MAX_DIGITS: usize = 100_000;
+
+// ════════════════════════════════════════════════════════════════════════════
+// Replies
+// ════════════════════════════════════════════════════════════════════════════
+
+/// An HTTP answer, before it meets the socket.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reply {
+    pub status:       u16,
+    pub content_type: &'static str,
+    pub body:         Vec<u8>,
+}
+
+impl Reply {
+    fn json(status: u16, value: &impl Serialize) -> Self {
+        let body = serde_json::to_vec(value).unwrap_or_default();
+        Reply { status, content_type: "application/json", body }
+    }
+
+    /// `{"error": message}` with `status`.
+    pub fn error(status: u16, message: impl ToString) -> Self {
+        Reply::json(status, &ErrorBody { error: message.to_string() })
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Routing
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Answer one plain (non-WebSocket) request.
+pub fn route(method: &str, url: &str, body: &[u8]) -> Reply {
+    let (path, query) = split_url(url);
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let result = match (method, segments.as_slice()) {
+        ("GET", ["digits", constant]) => digits(&decode(constant), &query),
+        ("POST", ["compose"])         => compose(body),
+        (_, ["digits", _] | ["compose"] | ["zip"]) => {
+            return Reply::error(405, format!("{} is not allowed on {}", method, path));
+        }
+        _ => return Reply::error(404, format!("no endpoint {}", path)),
+    };
+    result.unwrap_or_else(|e| Reply::error(400, e))
+}
+
+/// Whether `url` is the WebSocket endpoint; its query has the stream
+/// parameters for [`ZipParams::parse`].
+pub fn is_zip(url: &str) -> bool {
+    split_url(url).0.trim_end_matches('/') == "/zip"
+}
+
+#[derive(Serialize)]
+struct DigitsBody {
+    name:     &'static str,
+    base:     u8,
+    digits:   Vec<u8>,
+    /// The same digits as characters, `0-9a-z`.
+    text:     String,
+}
+
+fn digits(constant: &str, query: &[(String, String)]) -> Result<Reply, MidiError> {
+    let base = param(query, "base").unwrap_or("10");
+    let n = number(query, "n", 100)?;
+    if n > MAX_DIGITS {
+        return Err(MidiError::Parameter(format!("n must be at most {}, got {}", MAX_DIGITS, n)));
+    }
+    let cfg = parse_stream(&format!("{}:{}", constant, base))?;
+    let digits = BoxedSpigot::from_config(cfg).take_n(n);
+    let text = digits.iter().map(|&d| spigot_stream::digit_char(d)).collect();
+    Ok(Reply::json(200, &DigitsBody { name: cfg.constant.name(), base: cfg.base, digits, text }))
+}
+
+/// The JSON body of `POST /compose`.  Every field may be left out.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ComposeRequest {
+    /// Stream for durations, `CONSTANT[:BASE]`.
+    pub left:              String,
+    /// Stream for pitches.
+    pub right:             String,
+    pub notes:             usize,
+    pub tempo:             f64,
+    /// General MIDI program, 0–127.
+    pub program:           u8,
+    /// MIDI note for digit 0 of the scale.
+    pub root:              u8,
+    /// Scale name: `major`, `dorian`, `whole-tone`, …
+    pub scale:             String,
+    pub velocity:          u8,
+    /// Channel 0–15.
+    pub channel:           u8,
+    pub ticks_per_quarter: u16,
+    /// Note values per left digit, as `--durations` takes them; musical
+    /// values if left out.
+    pub durations:         Option<String>,
+    /// Mappers by name, `NAME[:PARAM]`, in place of `scale`, `durations`
+    /// and `velocity` (see `spigot_midi::mapper`).
+    pub pitch_mapper:      Option<String>,
+    pub duration_mapper:   Option<String>,
+    pub velocity_mapper:   Option<String>,
+}
+
+impl Default for ComposeRequest {
+    /// 64 notes of π against e, piano in C major from middle C at 120 BPM.
+    fn default() -> Self {
+        ComposeRequest {
+            left: "pi".to_string(), right: "e".to_string(), notes: 64, tempo: 120.0,
+            program: 0, root: 60, scale: "major".to_string(), velocity: 100, channel: 0,
+            ticks_per_quarter: 480, durations: None,
+            pitch_mapper: None, duration_mapper: None, velocity_mapper: None,
+        }
+    }
+}
+
+fn compose(body: &[u8]) -> Result<Reply, MidiError> {
+    let req: ComposeRequest = match body.iter().all(u8::is_ascii_whitespace) {
+        true  => ComposeRequest::default(),
+        false => serde_json::from_slice(body)
+            .map_err(|e| MidiError::Parameter(format!("bad request body: {}", e)))?,
+    };
+    if req.notes > MAX_DIGITS {
+        return Err(MidiError::Parameter(format!("notes must be at most {}, got {}", MAX_DIGITS, req.notes)));
+    }
+    if !(req.tempo.is_finite() && req.tempo > 0.0) {
+        return Err(MidiError::Parameter(format!("tempo must be a positive number of BPM, got {}", req.tempo)));
+    }
+    if req.program > 127 || req.root > 127 || req.velocity > 127 || req.channel > 15
+        || req.ticks_per_quarter == 0
+    {
+        return Err(MidiError::Parameter(
+            "program, root and velocity must be 0–127, channel 0–15, ticks_per_quarter above 0".to_string()));
+    }
+    // Composed from its provenance, so the file says how to make it again.
+    let mut p = Provenance::new(parse_stream(&req.left)?, parse_stream(&req.right)?);
+    p.notes = req.notes;
+    p.tempo = req.tempo;
+    p.program = req.program;
+    p.channel = req.channel;
+    p.ticks_per_quarter = req.ticks_per_quarter;
+    p.root = req.root;
+    p.pitch = req.pitch_mapper.unwrap_or(req.scale);
+    p.duration = match (req.duration_mapper, req.durations) {
+        (Some(spec), _)    => spec,
+        (None, Some(spec)) => format!("values:{}", spec),
+        (None, None)       => "musical".to_string(),
+    };
+    p.velocity = req.velocity_mapper.unwrap_or_else(|| format!("fixed:{}", req.velocity));
+    Ok(Reply { status: 200, content_type: "audio/midi", body: p.compose()?.to_bytes() })
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// WebSocket — /zip
+// ════════════════════════════════════════════════════════════════════════════
+
+/// What a `/zip` socket streams.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZipParams {
+    pub left:  SpigotConfig,
+    pub right: SpigotConfig,
+    /// Pairs per second.
+    pub rate:  f64,
+    /// Pairs to send before closing; `None` for no end.
+    pub count: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct PairBody {
+    index: usize,
+    left:  u8,
+    right: u8,
+}
+
+impl ZipParams {
+    /// From the query of a `/zip` URL: `left` and `right` streams (π and e
+    /// by default), `rate` (8 a second) and optionally `n`.
+    pub fn parse(url: &str) -> Result<Self, MidiError> {
+        let (_, query) = split_url(url);
+        let rate: f64 = param(&query, "rate").unwrap_or("8").parse()
+            .map_err(|_| MidiError::Parameter("rate must be a number".to_string()))?;
+        if !(0.1..=1000.0).contains(&rate) {
+            return Err(MidiError::Parameter(format!("rate must be 0.1–1000 pairs a second, got {}", rate)));
+        }
+        Ok(ZipParams {
+            left:  parse_stream(param(&query, "left").unwrap_or("pi"))?,
+            right: parse_stream(param(&query, "right").unwrap_or("e"))?,
+            rate,
+            count: param(&query, "n").map(|_| number(&query, "n", 0)).transpose()?,
+        })
+    }
+
+    /// Send the pairs down `socket`, paced at the rate, until the count is
+    /// reached or the client leaves.  The close handshake waits on the
+    /// client, so give the stream a read timeout (see [`time_out_reads`])
+    /// or a client that never answers keeps this thread for good.
+    pub fn stream_to<S: Read + Write>(self, socket: &mut WebSocket<S>) -> tungstenite::Result<()> {
+        let mut ds = DualStream::from_configs(self.left, self.right);
+        let period = Duration::from_secs_f64(1.0 / self.rate);
+        let start = Instant::now();
+        for index in 0..self.count.unwrap_or(usize::MAX) {
+            let Some((left, right)) = ds.zip_next() else { break };
+            if let Some(wait) = period.mul_f64(index as f64).checked_sub(start.elapsed()) {
+                thread::sleep(wait);
+            }
+            let text = serde_json::to_string(&PairBody { index, left, right }).unwrap_or_default();
+            socket.send(Message::text(text))?;
+        }
+        socket.close(None)?;
+        // Let the close handshake finish.
+        while socket.read().is_ok() {}
+        Ok(())
+    }
+}
+
+/// Value of the `Sec-WebSocket-Accept` header answering `key`.
+pub fn accept_key(key: &str) -> String {
+    tungstenite::handshake::derive_accept_key(key.trim().as_bytes())
+}
+
+/// Speak WebSocket, as the server, on an upgraded connection.
+pub fn server_socket<S: Read + Write>(stream: S) -> WebSocket<S> {
+    WebSocket::from_raw_socket(stream, Role::Server, None)
+}
+
+/// Give every connection `listener` accepts a read timeout: a request that
+/// stalls, or a `/zip` client that never answers the close, then frees its
+/// thread after `timeout`.  tiny_http keeps the accepted sockets to itself
+/// (an upgraded one is only `Read + Write`), so the timeout goes on the
+/// listening socket, which accepted sockets inherit on Linux and the BSDs.
+#[cfg(unix)]
+pub fn time_out_reads(listener: &TcpListener, timeout: Duration) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    let tv = libc::timeval {
+        tv_sec:  timeout.as_secs() as libc::time_t,
+        tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+    };
+    // SAFETY: the descriptor is the listener's own, open for the call, and
+    // `tv` is a timeval of the size given.
+    let set = unsafe {
+        libc::setsockopt(
+            listener.as_raw_fd(), libc::SOL_SOCKET, libc::SO_RCVTIMEO,
+            (&tv as *const libc::timeval).cast(), std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+        )
+    };
+    if set == 0 { Ok(()) } else { Err(io::Error::last_os_error()) }
+}
+
+/// Elsewhere accepted sockets don't inherit the listener's timeout; reads
+/// wait as long as the client does.
+#[cfg(not(unix))]
+pub fn time_out_reads(_listener: &TcpListener, _timeout: Duration) -> io::Result<()> {
+    Ok(())
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Parameters
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Path, and the decoded `key=value` pairs of the query.
+fn split_url(url: &str) -> (&str, Vec<(String, String)>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let pairs = query.split('&').filter(|p| !p.is_empty())
+        .map(|p| {
+            let (k, v) = p.split_once('=').unwrap_or((p, ""));
+            (decode(k), decode(v))
+        })
+        .collect();
+    (path, pairs)
+}
+
+fn param<'a>(query: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    query.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+fn number(query: &[(String, String)], key: &str, default: usize) -> Result<usize, MidiError> {
+    param(query, key).map_or(Ok(default), |v| v.parse()
+        .map_err(|_| MidiError::Parameter(format!("{} must be a whole number, got '{}'", key, v))))
+}
+
+/// Undo URL percent-encoding (`%CF%80` → `π`, `+` → space).
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => { out.push(b); i += 3; }
+            (b'+', _)       => { out.push(b' '); i += 1; }
+            (b, _)          => { out.push(b); i += 1; }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spigot_stream::Constant;
+
+    fn json(reply: &Reply) -> serde_json::Value {
+        serde_json::from_slice(&reply.body).unwrap()
+    }
+
+    #[test]
+    fn serves_digits_as_json() {
+        let reply = route("GET", "/digits/champernowne?n=12", b"");
+        assert_eq!((reply.status, reply.content_type), (200, "application/json"));
+        let body = json(&reply);
+        assert_eq!(body["text"], "012345678910");
+        assert_eq!(body["digits"][11], 0);
+
+        let reply = route("GET", "/digits/%CF%80?base=16&n=4", b"");
+        assert_eq!(json(&reply)["base"], 16);
+
+        assert_eq!(route("GET", "/digits/tau", b"").status, 400);
+        assert_eq!(route("GET", "/digits/pi?base=40", b"").status, 400);
+        assert_eq!(route("GET", "/digits/pi?n=lots", b"").status, 400);
+        assert_eq!(route("POST", "/digits/pi", b"").status, 405);
+        assert_eq!(route("GET", "/nowhere", b"").status, 404);
+    }
+
+    #[test]
+    fn composes_a_midi_file_from_json() {
+        let reply = route("POST", "/compose",
+            br#"{"left":"champernowne","right":"thuemorse:2","notes":8,"scale":"dorian"}"#);
+        assert_eq!((reply.status, reply.content_type), (200, "audio/midi"));
+        assert_eq!(&reply.body[..4], b"MThd");
+        assert_eq!(Provenance::from_midi(&reply.body).unwrap().compose().unwrap().to_bytes(), reply.body);
+
+        assert_eq!(route("POST", "/compose", b"").status, 200);
+        let bad = route("POST", "/compose", br#"{"scale":"no such scale"}"#);
+        assert_eq!(bad.status, 400);
+        assert!(json(&bad)["error"].as_str().unwrap().contains("scale"));
+        assert_eq!(route("POST", "/compose", br#"{"tempo":0}"#).status, 400);
+        assert_eq!(route("POST", "/compose", br#"{"notez":8}"#).status, 400);
+
+        let mapped = route("POST", "/compose",
+            br#"{"pitch_mapper":"custom:0 7","duration_mapper":"fixed:240","velocity_mapper":"left"}"#);
+        assert_eq!(mapped.status, 200);
+        assert_eq!(route("POST", "/compose", br#"{"duration_mapper":"swing"}"#).status, 400);
+    }
+
+    #[test]
+    fn parses_zip_parameters() {
+        assert!(is_zip("/zip?left=pi:16") && !is_zip("/zipper"));
+        let p = ZipParams::parse("/zip?left=thuemorse:2&rate=20&n=5").unwrap();
+        assert_eq!(p.left, SpigotConfig::new(Constant::ThueMorse, 2));
+        assert_eq!(p.right, SpigotConfig::new(Constant::E, 10));
+        assert_eq!((p.rate, p.count), (20.0, Some(5)));
+        assert_eq!(ZipParams::parse("/zip").unwrap().count, None);
+        assert!(ZipParams::parse("/zip?rate=0").is_err());
+        assert!(ZipParams::parse("/zip?right=tau").is_err());
+    }
+
+    #[test]
+    fn zip_gives_up_on_a_client_that_never_closes() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        time_out_reads(&listener, Duration::from_millis(200)).unwrap();
+        let client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        let params = ZipParams::parse("/zip?rate=1000&n=3").unwrap();
+        let start = Instant::now();
+        let ended = params.stream_to(&mut server_socket(stream));
+        assert!(ended.is_ok() && start.elapsed() < Duration::from_secs(5), "{:?}", ended);
+        drop(client);
+    }
+}
@@ -0,0 +1,110 @@
+//! spigot_serve — the spigot engine over HTTP and WebSocket.
+//!
+//! ```text
+//! cargo run --release -- --addr 0.0.0.0:8080
+//! curl 'http://127.0.0.1:8080/digits/pi?base=16&n=32'
+//! ```
+//!
+//! Endpoints are listed in the library docs.  Each request is answered on
+//! its own thread, so a long `/zip` stream doesn't hold up the others.
+
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+use clap::Parser;
+use tiny_http::{Header, Request, Response, Server, StatusCode};
+
+use spigot_serve::{accept_key, is_zip, route, server_socket, time_out_reads, Reply, ZipParams};
+
+#[derive(Parser)]
+#[command(about = "Serve spigot digits, MIDI files and digit-pair streams over HTTP")]
+struct Args {
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    addr: String,
+}
+
+/// How long a connection may go quiet mid-request, or a `/zip` client take
+/// to answer the close, before its thread lets it go.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn main() {
+    let args = Args::parse();
+    let server = TcpListener::bind(&args.addr)
+        .and_then(|listener| time_out_reads(&listener, READ_TIMEOUT).map(|()| listener))
+        .map_err(Into::into)
+        .and_then(|listener| Server::from_listener(listener, None));
+    let server = match server {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Error: cannot listen on {}: {}", args.addr, e);
+            std::process::exit(1);
+        }
+    };
+    println!("Listening on http://{}", args.addr);
+    for request in server.incoming_requests() {
+        thread::spawn(move || handle(request));
+    }
+}
+
+fn handle(mut request: Request) {
+    let url = request.url().to_string();
+    let method = request.method().as_str().to_string();
+    if method == "GET" && is_zip(&url) {
+        if let Some(key) = header(&request, "Sec-WebSocket-Key") {
+            return zip(request, &url, &key);
+        }
+    }
+    let mut body = Vec::new();
+    if let Err(e) = request.as_reader().read_to_end(&mut body) {
+        eprintln!("{} {}: cannot read the body: {}", method, url, e);
+        return;
+    }
+    let reply = route(&method, &url, &body);
+    println!("{} {} → {}", method, url, reply.status);
+    respond(request, reply);
+}
+
+fn respond(request: Request, reply: Reply) {
+    let response = Response::from_data(reply.body)
+        .with_status_code(reply.status)
+        .with_header(raw_header("Content-Type", reply.content_type));
+    if let Err(e) = request.respond(response) {
+        eprintln!("cannot answer: {}", e);
+    }
+}
+
+/// Upgrade to a WebSocket and stream digit pairs down it.
+fn zip(request: Request, url: &str, key: &str) {
+    let params = match ZipParams::parse(url) {
+        Ok(params) => params,
+        Err(e) => {
+            println!("GET {} → 400", url);
+            respond(request, Reply::error(400, e));
+            return;
+        }
+    };
+    println!("GET {} → 101", url);
+    let response = Response::empty(StatusCode(101))
+        .with_header(raw_header("Connection", "Upgrade"))
+        .with_header(raw_header("Sec-WebSocket-Accept", &accept_key(key)));
+    let stream = request.upgrade("websocket", response);
+    let mut socket = server_socket(stream);
+    if let Err(e) = params.stream_to(&mut socket) {
+        match e {
+            tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {}
+            e => eprintln!("GET {}: {}", url, e),
+        }
+    }
+}
+
+fn header(request: &Request, name: &'static str) -> Option<String> {
+    request.headers().iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str().to_string())
+}
+
+fn raw_header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+}
@@ -0,0 +1,155 @@
+//! Digit streams as [`futures::Stream`]s — with the `async` feature.
+//!
+//! A spigot computes each digit on demand, and so does [`AsyncDigits`]: a
+//! digit is worked out only when the consumer polls for it, so a slow
+//! consumer holds the spigot back instead of piling up digits in a buffer.
+//! Big-integer arithmetic is still arithmetic, though, so after a *budget*
+//! of digits in one go the stream hands the task back to the executor
+//! (waking itself first) rather than hog the thread.
+//!
+//! [`AsyncBatches`] gathers digits into `Vec`s of a set size, for a socket
+//! or a GUI that would rather take one message than hundreds, and keeps
+//! its part-built batch across those hand-backs.
+//!
+//! Any iterator works, digits or the pairs of a `dual_spigot` zip:
+//!
+//! ```rust
+//! use futures::{executor::block_on, StreamExt};
+//! use spigot_stream::{ChampernowneStream, async_stream::IntoAsync};
+//!
+//! let digits: Vec<u8> = block_on(ChampernowneStream::new().into_async().take(5).collect());
+//! assert_eq!(digits, [0, 1, 2, 3, 4]);
+//!
+//! let mut batches = ChampernowneStream::new().drop(9).into_async().batches(4);
+//! assert_eq!(block_on(batches.next()), Some(vec![9, 1, 0, 1]));
+//! ```
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+
+/// Digits computed per poll before a stream yields to the executor.
+pub const DEFAULT_BUDGET: usize = 64;
+
+/// Turn an iterator into an [`AsyncDigits`] stream.
+pub trait IntoAsync: Iterator + Sized {
+    /// The iterator as a stream with the [`DEFAULT_BUDGET`].
+    fn into_async(self) -> AsyncDigits<Self> { AsyncDigits::new(self) }
+}
+
+impl<I: Iterator> IntoAsync for I {}
+
+/// An iterator polled as a [`Stream`], yielding to the executor after each
+/// budget of items.
+#[derive(Clone, Debug)]
+pub struct AsyncDigits<I> {
+    digits: I,
+    budget: usize,
+    run:    usize,
+}
+
+impl<I: Iterator> AsyncDigits<I> {
+    pub fn new(digits: I) -> Self {
+        AsyncDigits { digits, budget: DEFAULT_BUDGET, run: 0 }
+    }
+
+    /// Yield to the executor after every `budget` items (at least 1).
+    pub fn with_budget(mut self, budget: usize) -> Self {
+        self.budget = budget.max(1);
+        self
+    }
+
+    /// Gather the items into batches of `size` (at least 1).
+    pub fn batches(self, size: usize) -> AsyncBatches<I> {
+        let size = size.max(1);
+        AsyncBatches { inner: self, size, batch: Vec::with_capacity(size) }
+    }
+
+    /// The iterator back, where the stream left it.
+    pub fn into_inner(self) -> I { self.digits }
+
+    /// The next item, or `None` when this poll's budget is spent.
+    fn step(&mut self, cx: &mut Context<'_>) -> Option<Option<I::Item>> {
+        if self.run == self.budget {
+            self.run = 0;
+            cx.waker().wake_by_ref();
+            return None;
+        }
+        self.run += 1;
+        Some(self.digits.next())
+    }
+}
+
+impl<I: Iterator + Unpin> Stream for AsyncDigits<I> {
+    type Item = I::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<I::Item>> {
+        match self.get_mut().step(cx) {
+            Some(item) => Poll::Ready(item),
+            None       => Poll::Pending,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) { self.digits.size_hint() }
+}
+
+/// Items gathered into `Vec`s; the last may be short if the iterator ends.
+#[derive(Clone, Debug)]
+pub struct AsyncBatches<I: Iterator> {
+    inner: AsyncDigits<I>,
+    size:  usize,
+    batch: Vec<I::Item>,
+}
+
+impl<I: Iterator + Unpin> Stream for AsyncBatches<I>
+where I::Item: Unpin {
+    type Item = Vec<I::Item>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<I::Item>>> {
+        let this = self.get_mut();
+        while this.batch.len() < this.size {
+            match this.inner.step(cx) {
+                Some(Some(item)) => this.batch.push(item),
+                Some(None) if this.batch.is_empty() => return Poll::Ready(None),
+                Some(None) => break,
+                None => return Poll::Pending,
+            }
+        }
+        let next = Vec::with_capacity(this.size);
+        Poll::Ready(Some(std::mem::replace(&mut this.batch, next)))
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor::block_on, StreamExt};
+    use futures::task::noop_waker;
+    use crate::ChampernowneStream;
+
+    #[test]
+    fn yields_to_the_executor_after_each_budget() {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut digits = ChampernowneStream::new().into_async().with_budget(2);
+        let mut polls = Vec::new();
+        for _ in 0..5 {
+            polls.push(Pin::new(&mut digits).poll_next(&mut cx));
+        }
+        assert_eq!(polls, [Poll::Ready(Some(0)), Poll::Ready(Some(1)), Poll::Pending,
+                           Poll::Ready(Some(2)), Poll::Ready(Some(3))]);
+    }
+
+    #[test]
+    fn batches_survive_a_yield_and_end_short() {
+        let mut batches = ChampernowneStream::new().take(7).into_async().with_budget(3).batches(5);
+        assert_eq!(block_on(batches.next()), Some(vec![0, 1, 2, 3, 4]));
+        assert_eq!(block_on(batches.next()), Some(vec![5, 6]));
+        assert_eq!(block_on(batches.next()), None);
+    }
+}
@@ -1,7 +1,8 @@
 //! # spigot_stream
 //!
-//! Lazy, infinite digit streams for six transcendental constants, all
-//! implemented as pure spigot algorithms — no floating-point, no
+//! Lazy, infinite digit streams for six transcendental constants, √2 and
+//! the golden ratio φ,
+//! all implemented as pure spigot algorithms — no floating-point, no
 //! arbitrary-precision library required.
 //!
 //! Every stream supports an arbitrary **output base** from 2 to 36.
@@ -17,6 +18,18 @@
 //! | Liouville's constant | any (digits always 0 or 1) | [`LiouvilleStream`] |
 //! | Champernowne's constant | any | [`ChampernowneStream`] |
 //! | Prouhet–Thue–Morse | 2 (digits always 0 or 1) | [`ThueMorseStream`] |
+//! | √2 (algebraic, not transcendental) | any | [`Sqrt2Stream`] |
+//! | φ, the golden ratio (algebraic) | any | [`PhiStream`] |
+//!
+//! [`RationalStream`] expands any fraction `p/q` the same way, so a
+//! repeating pattern can be set against the constants above.
+//!
+//! ## Continued fractions
+//!
+//! [`ContinuedFractionStream`] turns any of these streams into the terms
+//! `[a₀; a₁, a₂, …]` of the constant's continued fraction, which don't
+//! depend on a base and follow the Gauss–Kuzmin law (1 about 41% of the
+//! time, 2 about 17%, …) for most constants; see [`Constant::cf_terms`].
 //!
 //! ## Base semantics
 //!
@@ -30,6 +43,24 @@
 //! elsewhere regardless of base, so its digit *stream* is base-invariant
 //! (only the *value* of the constant changes).  ThueMorse always emits bits.
 //!
+//! ## Digit statistics
+//!
+//! The [`stats`] module tallies how often each digit turns up, for watching
+//! whether a constant looks *normal* as its digits stream by, along with
+//! runs of repeated digits and autocorrelation.
+//!
+//! ## Bulk digits
+//!
+//! [`Constant::digits_in_base_parallel`] works out long runs of digits in
+//! chunks on several threads, for the constants whose digits can be found
+//! from any position on; see [`parallel`].
+//!
+//! ## Async streams
+//!
+//! With the `async` feature, [`async_stream`] polls any of these streams as
+//! a `futures::Stream`, computing digits only as they are asked for and
+//! yielding to the executor between batches of work.
+//!
 //! ## Quick start
 //!
 //! ```rust
@@ -51,9 +82,16 @@
 //! assert_eq!(bin_e[0], 1);
 //! ```
 
-use num_bigint::BigInt;
+use std::collections::VecDeque;
+
+use num_bigint::{BigInt, BigUint};
 use num_traits::{Zero, One};
 
+pub mod stats;
+pub mod parallel;
+#[cfg(feature = "async")]
+pub mod async_stream;
+
 // ── digit rendering ──────────────────────────────────────────────────────────
 
 /// Convert a digit value `0..=35` to its character representation.
@@ -73,6 +111,26 @@ pub fn digit_char(d: u8) -> char {
     }
 }
 
+/// `b^e mod m`.
+fn mod_pow(b: u64, mut e: u64, m: u64) -> u64 {
+    let m = m as u128;
+    let (mut result, mut b) = (1 % m, b as u128 % m);
+    while e > 0 {
+        if e & 1 == 1 { result = result * b % m; }
+        b = b * b % m;
+        e >>= 1;
+    }
+    result as u64
+}
+
+/// `n` as exactly `len` base-`base` digits, most significant first.
+fn padded_digits(n: &BigUint, base: u8, len: usize) -> Vec<u8> {
+    let digits = if n.is_zero() { Vec::new() } else { n.to_radix_be(base as u32) };
+    let mut out = vec![0; len.saturating_sub(digits.len())];
+    out.extend(&digits[digits.len().saturating_sub(len)..]);
+    out
+}
+
 /// Validate a base, panicking with a helpful message if out of range.
 fn check_base(base: u8) {
     assert!(base >= 2 && base <= 36,
@@ -90,7 +148,7 @@ macro_rules! impl_stream_combinators {
             }
             /// Skip the first `n` digits.  Scala: `stream.drop(n)`.
             pub fn drop(mut self, n: usize) -> Self {
-                for _ in 0..n { self.next(); }
+                if n > 0 { self.nth(n - 1); }
                 self
             }
             /// Keep only digits satisfying `p`.  Scala: `stream.filter(p)`.
@@ -158,10 +216,73 @@ macro_rules! impl_stream_combinators {
 /// `::with_base(16)` gives hex digits: 3, 2, 4, 3, 15, 6, …  (i.e. 3.243f6…)
 ///
 /// Uses Gosper's unbounded LFT spigot with `BigInt` arithmetic so the
-/// internal state can grow without bound without overflowing.
+/// internal state can grow without bound without overflowing.  In base 16,
+/// `drop(n)` (and [`Iterator::nth`]) jump straight to digit `n` by the
+/// Bailey–Borwein–Plouffe formula instead, and the stream carries on from
+/// there by BBP; see [`hex_digit_at`](Self::hex_digit_at).
 #[derive(Clone, Debug)]
 pub struct PiStream {
     q: BigInt, r: BigInt, t: BigInt, k: BigInt, base: BigInt,
+    /// Digits of the integer part 3 still to give, last first, in bases
+    /// where it takes more than one.
+    whole:   Vec<u8>,
+    /// Digits given so far.
+    emitted: usize,
+    /// Where a base-16 stream has jumped to, once it has.
+    jump:    Option<HexJump>,
+}
+
+/// Hex digits after a jump, worked out [`JUMP_BLOCK`] at a time.
+#[derive(Clone, Debug)]
+struct HexJump {
+    /// Place after the point of the first digit not worked out yet.
+    next:  usize,
+    ahead: VecDeque<u8>,
+}
+
+impl HexJump {
+    fn next_digit(&mut self) -> u8 {
+        if self.ahead.is_empty() {
+            self.ahead.extend(pi_hex(self.next, JUMP_BLOCK));
+            self.next += JUMP_BLOCK;
+        }
+        self.ahead.pop_front().expect("just refilled")
+    }
+}
+
+/// Hex digits worked out by each BBP evaluation after a jump.
+const JUMP_BLOCK: usize = 32;
+
+/// Hex digits worked out past the end of a BBP block, to absorb the
+/// rounding in its sums.
+const GUARD_HEX: usize = 8;
+
+/// The `len` hex digits of π starting `from` places after the point, by
+/// the BBP formula π = Σ 16^−k (4/(8k+1) − 2/(8k+4) − 1/(8k+5) − 1/(8k+6)):
+/// the fraction of `16^from · π` needs only `16^(from−k) mod (8k+j)` for
+/// the terms up to `from`, in fixed point with [`GUARD_HEX`] digits to
+/// spare.
+fn pi_hex(from: usize, len: usize) -> Vec<u8> {
+    let bits = 4 * (len + GUARD_HEX);
+    let one = BigInt::one() << bits;
+    // The fraction of 16^from · Σ 16^−k / (8k + j).
+    let series = |j: u64| {
+        let mut sum = BigInt::zero();
+        for k in 0..=from as u64 {
+            let m = 8 * k + j;
+            sum += (BigInt::from(mod_pow(16, from as u64 - k, m)) << bits) / m;
+        }
+        for k in from as u64 + 1.. {
+            let shift = 4 * (k as usize - from);
+            if shift > bits { break; }
+            sum += (&one >> shift) / (8 * k + j);
+        }
+        sum
+    };
+    let sum: BigInt = 4 * series(1) - 2 * series(4) - series(5) - series(6);
+    let fraction: BigInt = ((sum % &one) + &one) % &one;
+    let fraction = fraction.to_biguint().expect("reduced to 0..1");
+    padded_digits(&(fraction >> (4 * GUARD_HEX)), 16, len)
 }
 
 impl PiStream {
@@ -177,15 +298,33 @@ impl PiStream {
             t:    BigInt::one(),
             k:    BigInt::one(),
             base: BigInt::from(base),
+            whole:   Vec::new(),
+            emitted: 0,
+            jump:    None,
         }
     }
 
+    /// Hex digit `pos` of π, counting the integer part 3 as digit 0 as the
+    /// base-16 stream does, by the Bailey–Borwein–Plouffe formula: about
+    /// `pos` modular powers, and no digits streamed before it.
+    ///
+    /// ```rust
+    /// use spigot_stream::PiStream;
+    ///
+    /// assert_eq!(PiStream::hex_digit_at(0), 3);
+    /// assert_eq!(PiStream::hex_digit_at(4), 0xf);   // 3.243f…
+    /// ```
+    pub fn hex_digit_at(pos: usize) -> u8 {
+        if pos == 0 { 3 } else { pi_hex(pos - 1, 1)[0] }
+    }
+
+    /// Take in the next term of π = 2 + 1/3·(2 + 2/5·(2 + 3/7·(2 + …))),
+    /// the map x ↦ (k·x + 4k + 2) / (2k + 1).
     fn compose(&mut self) {
-        let l   = &self.k * 2 - 1;
-        let new_r = &self.q * &l * &l + &self.r * 6 * &l;
-        self.q  = &self.q * &l;
-        self.r  = new_r;
-        self.t  = &self.t * 6 * &l;
+        let l   = &self.k * 2 + 1;
+        self.r  = &self.q * (&self.k * 4 + 2) + &self.r * &l;
+        self.q  = &self.q * &self.k;
+        self.t  = &self.t * &l;
         self.k += 1;
     }
 
@@ -209,17 +348,37 @@ impl Default for PiStream { fn default() -> Self { Self::new() } }
 impl Iterator for PiStream {
     type Item = u8;
     fn next(&mut self) -> Option<u8> {
+        self.emitted += 1;
+        if let Some(d) = self.whole.pop() { return Some(d); }
+        if let Some(jump) = &mut self.jump {
+            return Some(jump.next_digit());
+        }
         loop {
             let d = self.extract();
             if self.safe(&d) {
-                self.emit(&d.clone());
-                // d is guaranteed to be in 0..base
+                self.emit(&d);
                 use num_traits::ToPrimitive;
+                // Only the integer part 3 can be out of 0..base, in base 2 or 3.
+                if d >= self.base {
+                    self.whole = d.to_radix_le(self.base.to_u32()?).1;
+                    return self.whole.pop();
+                }
                 return d.to_u8();
             }
             self.compose();
         }
     }
+
+    /// In base 16, one BBP evaluation rather than `n` spigot steps.
+    fn nth(&mut self, n: usize) -> Option<u8> {
+        if n > 0 && self.base == BigInt::from(16) {
+            self.emitted += n;
+            self.jump = Some(HexJump { next: self.emitted - 1, ahead: VecDeque::new() });
+            return self.next();
+        }
+        for _ in 0..n { self.next()?; }
+        self.next()
+    }
 }
 impl_stream_combinators!(PiStream);
 
@@ -522,14 +681,322 @@ impl Iterator for ThueMorseStream {
 }
 impl_stream_combinators!(ThueMorseStream);
 
+// ════════════════════════════════════════════════════════════════════════════
+// 7. √2  — digit-by-digit square root, parameterised by base
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Square root of a small integer `n`, one base-`b` digit at a time — the
+/// schoolbook method.  With `root` the root so far and
+/// `rem = n·b^(2k) − root²`, the next digit is the largest `d` with
+/// `(2·b·root + d)·d ≤ rem·b²`.  Exact `BigInt` arithmetic, so every digit
+/// is final as soon as it is found.
+#[derive(Clone, Debug)]
+struct RootDigits {
+    root: BigInt,
+    rem:  BigInt,
+    base: BigInt,
+    led:  bool,
+}
+
+impl RootDigits {
+    fn new(n: u32, base: u8) -> Self {
+        RootDigits { root: BigInt::zero(), rem: BigInt::from(n), base: BigInt::from(base), led: false }
+    }
+
+    /// Find the next digit, extending `root` by it.
+    fn next_digit(&mut self) -> BigInt {
+        let mut d = BigInt::zero();
+        if !self.led {
+            // The whole integer part, which needn't be a single digit.
+            self.led = true;
+            while (&d + 1) * (&d + 1) <= self.rem { d += 1; }
+            self.rem -= &d * &d;
+            self.root = d.clone();
+            return d;
+        }
+        // Bring down the next pair of (zero) digits.
+        self.rem = &self.rem * &self.base * &self.base;
+        let twice: BigInt = &self.root * &self.base * 2;
+        // Estimate from the leading term, then step down to the true digit.
+        if !twice.is_zero() { d = &self.rem / &twice; }
+        let top = &self.base - 1;
+        if d > top { d = top; }
+        while (&twice + &d) * &d > self.rem { d -= 1; }
+        self.rem  -= (&twice + &d) * &d;
+        self.root  = &self.root * &self.base + &d;
+        d
+    }
+}
+
+/// Infinite stream of the base-`b` digits of **√2**.
+///
+/// Default (`::new()`) is base 10: 1, 4, 1, 4, 2, 1, …
+/// `::with_base(2)` gives binary: 1, 0, 1, 1, 0, 1, …  (1.0110101…₂)
+///
+/// The schoolbook square root, one digit at a time.
+#[derive(Clone, Debug)]
+pub struct Sqrt2Stream {
+    root: RootDigits,
+}
+
+impl Sqrt2Stream {
+    /// Base-10 stream.
+    pub fn new() -> Self { Self::with_base(10) }
+
+    /// Stream in the given base (2–36).
+    pub fn with_base(base: u8) -> Self {
+        check_base(base);
+        Sqrt2Stream { root: RootDigits::new(2, base) }
+    }
+
+    fn compute_next_digit(&mut self) -> u8 {
+        use num_traits::ToPrimitive;
+        self.root.next_digit().to_u8().unwrap_or(0)
+    }
+}
+
+impl Default for Sqrt2Stream { fn default() -> Self { Self::new() } }
+
+impl Iterator for Sqrt2Stream {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> { Some(self.compute_next_digit()) }
+}
+impl_stream_combinators!(Sqrt2Stream);
+
+// ════════════════════════════════════════════════════════════════════════════
+// 8. φ  — the golden ratio from a digit-by-digit √5, parameterised by base
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Infinite stream of the base-`b` digits of the golden ratio
+/// **φ = (1 + √5) / 2**.
+///
+/// Default (`::new()`) is base 10: 1, 6, 1, 8, 0, 3, …
+/// `::with_base(2)` gives binary: 1, 1, 0, 0, 1, 1, …  (1.1001111…₂)
+///
+/// Its continued fraction is all 1s, making it the hardest number to
+/// approximate by fractions.  The first `k` digits after the point are
+/// `⌊φ·b^k⌋ = ⌊(b^k + ⌊√(5·b^(2k))⌋) / 2⌋`, so each digit falls out of the
+/// next digit of √5.
+#[derive(Clone, Debug)]
+pub struct PhiStream {
+    sqrt5:  RootDigits,
+    /// `b^k` for the digit about to be emitted.
+    scale:  BigInt,
+    /// `⌊φ·b^(k−1)⌋`, the digits so far.
+    prefix: BigInt,
+}
+
+impl PhiStream {
+    /// Base-10 stream.
+    pub fn new() -> Self { Self::with_base(10) }
+
+    /// Stream in the given base (2–36).
+    pub fn with_base(base: u8) -> Self {
+        check_base(base);
+        PhiStream { sqrt5: RootDigits::new(5, base), scale: BigInt::one(), prefix: BigInt::zero() }
+    }
+
+    fn compute_next_digit(&mut self) -> u8 {
+        self.sqrt5.next_digit();
+        let prefix: BigInt = (&self.scale + &self.sqrt5.root) / 2;
+        let d = &prefix - &self.prefix * &self.sqrt5.base;
+        self.scale  = &self.scale * &self.sqrt5.base;
+        self.prefix = prefix;
+        use num_traits::ToPrimitive;
+        d.to_u8().unwrap_or(0)
+    }
+}
+
+impl Default for PhiStream { fn default() -> Self { Self::new() } }
+
+impl Iterator for PhiStream {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> { Some(self.compute_next_digit()) }
+}
+impl_stream_combinators!(PhiStream);
+
+// ════════════════════════════════════════════════════════════════════════════
+// 9. p/q  — long division, parameterised by base
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Infinite stream of the base-`b` digits of a rational **p/q**: the
+/// integer part, then the fraction, which is eventually periodic with a
+/// period below `q` (and ends in 0s if `q` divides a power of `b`).
+///
+/// `RationalStream::new(1, 7, 10)` gives 0, 1, 4, 2, 8, 5, 7, 1, 4, …
+/// An integer part of `b` or more comes out one digit at a time, so
+/// `22/7` in base 2 starts 1, 1, 0, 0, 1, … (11.001…₂).
+#[derive(Clone, Debug)]
+pub struct RationalStream {
+    /// Digits of the integer part still to come, least significant first.
+    whole: Vec<u8>,
+    rem:   u64,
+    q:     u64,
+    base:  u64,
+}
+
+impl RationalStream {
+    /// Stream of `p/q` in `base` (2–36).  Panics if `q` is 0.
+    pub fn new(p: u64, q: u64, base: u8) -> Self {
+        check_base(base);
+        assert!(q > 0, "rational denominator must be > 0");
+        let mut whole = Vec::new();
+        let mut n = p / q;
+        loop {
+            whole.push((n % base as u64) as u8);
+            n /= base as u64;
+            if n == 0 { break; }
+        }
+        RationalStream { whole, rem: p % q, q, base: base as u64 }
+    }
+}
+
+impl Iterator for RationalStream {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> {
+        if let Some(d) = self.whole.pop() { return Some(d); }
+        // rem < q, so rem · base fits in 128 bits whatever q is.
+        let scaled = self.rem as u128 * self.base as u128;
+        self.rem = (scaled % self.q as u128) as u64;
+        Some((scaled / self.q as u128) as u8)
+    }
+}
+impl_stream_combinators!(RationalStream);
+
+// ════════════════════════════════════════════════════════════════════════════
+// 10. Continued fractions — terms read off any digit stream
+// ════════════════════════════════════════════════════════════════════════════
+
+/// The continued-fraction terms `[a₀; a₁, a₂, …]` of a number, read off its
+/// base-`b` digits: the integer part first, as every stream here gives it,
+/// then the fraction.
+///
+/// `ContinuedFractionStream::of(Constant::Sqrt2, 10)` gives 1, 2, 2, 2, …
+/// and `ContinuedFractionStream::rational(415, 93)` gives 4, 2, 6, 7 and
+/// then ends.
+///
+/// With `t` the digits not read yet, as a fraction in [0, 1], the number
+/// is `(p·t + q) / (r·t + s)`.  A term is given once it is the same at
+/// both ends of that range, and taken off by `x → 1/(x − a)`; otherwise
+/// another digit is read.  A stream that ends is taken to be exact, and
+/// finishes by Euclid's algorithm.  Terms past `u64::MAX` — Liouville's
+/// and Champernowne's constants have some — come out as `u64::MAX`.
+pub struct ContinuedFractionStream {
+    digits: Box<dyn Iterator<Item = u8> + Send>,
+    p: BigInt, q: BigInt, r: BigInt, s: BigInt,
+    /// What the next digit is worth against the last: 1 for the first
+    /// fractional digit after the integer part, then `base`.
+    radix: BigInt,
+    base:  BigInt,
+    /// The digits have run out, so the number is `q/s` exactly.
+    exact: bool,
+}
+
+impl ContinuedFractionStream {
+    /// Terms of the number with base-`base` (2–36) `digits`.
+    pub fn new(digits: impl Iterator<Item = u8> + Send + 'static, base: u8) -> Self {
+        check_base(base);
+        ContinuedFractionStream {
+            digits: Box::new(digits),
+            p: BigInt::one(), q: BigInt::zero(), r: BigInt::zero(), s: BigInt::one(),
+            radix:  BigInt::one(),
+            base:   BigInt::from(base),
+            exact:  false,
+        }
+    }
+
+    /// Terms of `constant`, read from its digits in `base`.  A rational's
+    /// come from [`rational`](Self::rational) instead, and end.
+    pub fn of(constant: Constant, base: u8) -> Self {
+        match constant {
+            Constant::Pi           => Self::new(PiStream::with_base(base), base),
+            Constant::E            => Self::new(EStream::with_base(base), base),
+            Constant::Ln2          => Self::new(Ln2Stream::with_base(base), base),
+            Constant::Liouville    => Self::new(LiouvilleStream::with_base(base), base),
+            Constant::Champernowne => Self::new(ChampernowneStream::with_base(base), base),
+            Constant::ThueMorse    => Self::new(ThueMorseStream::with_base(base), base),
+            Constant::Sqrt2        => Self::new(Sqrt2Stream::with_base(base), base),
+            Constant::Phi          => Self::new(PhiStream::with_base(base), base),
+            Constant::Rational(p, q) => Self::rational(p, q),
+        }
+    }
+
+    /// The finitely many terms of `p/q`.  Panics if `q` is 0.
+    pub fn rational(p: u64, q: u64) -> Self {
+        assert!(q > 0, "rational denominator must be > 0");
+        let mut cf = Self::new(std::iter::empty(), 2);
+        cf.q = BigInt::from(p);
+        cf.s = BigInt::from(q);
+        cf.settle_exact();
+        cf
+    }
+
+    /// The number is `q/s` whatever the digits still to come.
+    fn settle_exact(&mut self) {
+        self.exact = true;
+        self.p = self.q.clone();
+        self.r = self.s.clone();
+    }
+
+    /// The next term, if both ends of the range agree on it.
+    fn settled(&self) -> Option<BigInt> {
+        let top = &self.p + &self.q;
+        let bottom = &self.r + &self.s;
+        if self.s.is_zero() || bottom.is_zero() || self.s.sign() != bottom.sign() { return None; }
+        let a = floor_div(&self.q, &self.s);
+        (a == floor_div(&top, &bottom)).then_some(a)
+    }
+}
+
+/// `n / d` rounded down, where `BigInt` division rounds toward 0.
+fn floor_div(n: &BigInt, d: &BigInt) -> BigInt {
+    let (quot, rem) = (n / d, n % d);
+    if !rem.is_zero() && rem.sign() != d.sign() { quot - 1 } else { quot }
+}
+
+impl Iterator for ContinuedFractionStream {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            if let Some(a) = self.settled() {
+                let r = &self.p - &a * &self.r;
+                let s = &self.q - &a * &self.s;
+                self.p = std::mem::replace(&mut self.r, r);
+                self.q = std::mem::replace(&mut self.s, s);
+                use num_traits::ToPrimitive;
+                return Some(a.to_u64().unwrap_or(u64::MAX));
+            }
+            if self.exact { return None; }
+            match self.digits.next() {
+                // t = (d + t′) / radix
+                Some(d) => {
+                    self.q = &self.p * d + &self.q * &self.radix;
+                    self.s = &self.r * d + &self.s * &self.radix;
+                    self.radix = self.base.clone();
+                }
+                None => self.settle_exact(),
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ContinuedFractionStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ContinuedFractionStream {{ base {}, exact: {} }}", self.base, self.exact)
+    }
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // Runtime dispatch — Constant enum
 // ════════════════════════════════════════════════════════════════════════════
 
-/// The six transcendental constants available as spigot streams.
+/// The constants available as spigot streams: six transcendentals, √2,
+/// φ, and any rational `p/q`.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Constant {
-    Pi, E, Ln2, Liouville, Champernowne, ThueMorse,
+    Pi, E, Ln2, Liouville, Champernowne, ThueMorse, Sqrt2, Phi,
+    /// The fraction `p/q`; `q` must not be 0.
+    Rational(u64, u64),
 }
 
 impl Constant {
@@ -541,6 +1008,9 @@ impl Constant {
             Constant::Liouville    => "Liouville's constant",
             Constant::Champernowne => "Champernowne's constant",
             Constant::ThueMorse    => "Prouhet–Thue–Morse constant",
+            Constant::Sqrt2        => "√2 (square root of 2)",
+            Constant::Phi          => "φ  (golden ratio)",
+            Constant::Rational(..) => "p/q (a rational)",
         }
     }
 
@@ -552,6 +1022,9 @@ impl Constant {
             Constant::Liouville    => "0.110001000…  (1s at k! positions)",
             Constant::Champernowne => "0.123456789101112… (base 10)",
             Constant::ThueMorse    => "0.0110100110010110… (binary)",
+            Constant::Sqrt2        => "1.41421356237309… (base 10)",
+            Constant::Phi          => "1.61803398874989… (base 10)",
+            Constant::Rational(..) => "eventually periodic",
         }
     }
 
@@ -569,6 +1042,9 @@ impl Constant {
             Constant::Liouville    => LiouvilleStream::with_base(base).take(n).collect(),
             Constant::Champernowne => ChampernowneStream::with_base(base).take(n).collect(),
             Constant::ThueMorse    => ThueMorseStream::with_base(base).take(n).collect(),
+            Constant::Sqrt2        => Sqrt2Stream::with_base(base).take(n).collect(),
+            Constant::Phi          => PhiStream::with_base(base).take(n).collect(),
+            Constant::Rational(p, q) => RationalStream::new(p, q, base).take(n).collect(),
         }
     }
 
@@ -586,12 +1062,31 @@ impl Constant {
             Constant::Liouville    => LiouvilleStream::with_base(base).format_in_base(n),
             Constant::Champernowne => ChampernowneStream::with_base(base).format_in_base(n),
             Constant::ThueMorse    => ThueMorseStream::with_base(base).format_in_base(n),
+            Constant::Sqrt2        => Sqrt2Stream::with_base(base).format_in_base(n),
+            Constant::Phi          => PhiStream::with_base(base).format_in_base(n),
+            Constant::Rational(p, q) => RationalStream::new(p, q, base).format_in_base(n),
         }
     }
 
-    pub fn all() -> [Constant; 6] {
+    /// The first `n` continued-fraction terms, read from the base-10
+    /// digits; fewer for a rational, whose terms run out.
+    pub fn cf_terms(self, n: usize) -> Vec<u64> {
+        self.cf_terms_in_base(10, n)
+    }
+
+    /// The first `n` continued-fraction terms, read from the digits in the
+    /// given base — which matters only where the base changes the
+    /// constant, as for Champernowne's.
+    pub fn cf_terms_in_base(self, base: u8, n: usize) -> Vec<u64> {
+        ContinuedFractionStream::of(self, base).take(n).collect()
+    }
+
+    /// Every named constant; rationals are made with
+    /// [`Constant::Rational`].
+    pub fn all() -> [Constant; 8] {
         [Constant::Pi, Constant::E, Constant::Ln2,
-         Constant::Liouville, Constant::Champernowne, Constant::ThueMorse]
+         Constant::Liouville, Constant::Champernowne, Constant::ThueMorse,
+         Constant::Sqrt2, Constant::Phi]
     }
 }
 
@@ -633,6 +1128,26 @@ mod tests {
         assert_eq!(&s[2..3], "2", "got: {}", s);
     }
 
+    #[test]
+    fn pi_hex_digit_at_jumps_by_bbp() {
+        let known = [3, 2, 4, 3, 15, 6, 10, 8, 8, 8, 5, 10, 3, 0, 8, 13, 3];
+        assert_eq!((0..17).map(PiStream::hex_digit_at).collect::<Vec<_>>(), known);
+        // Bailey, Borwein & Plouffe's check: the digits from place 10⁴ on.
+        let far: String = PiStream::with_base(16).drop(10_000).take(10).map(digit_char).collect();
+        assert_eq!(far, "68ac8fcfb8");
+        let block: Vec<u8> = PiStream::with_base(16).drop(2).take(40).collect();
+        assert_eq!(block, (2..42).map(PiStream::hex_digit_at).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn pi_hex_jump_matches_the_spigot() {
+        let streamed: Vec<u8> = PiStream::with_base(16).take(300).collect();
+        for k in [1, 2, 3, 31, 32, 33, 200] {
+            let jumped: Vec<u8> = PiStream::with_base(16).drop(k).take(100).collect();
+            assert_eq!(jumped, streamed[k..k + 100], "drop({})", k);
+        }
+    }
+
     // ── π base 2 ─────────────────────────────────────────────────────────
     // π in binary = 11.001001000011111101101010100010001000010110100...
     // integer part digits: 1,1 then frac: 0,0,1,0,0,1,0,0,0,0,1,1,1,1,1,1...
@@ -727,6 +1242,53 @@ mod tests {
         assert_eq!(got, [0,1,1,0,1,0,0,1,1,0,0,1,0,1,1,0]);
     }
 
+    // ── √2 ───────────────────────────────────────────────────────────────
+    // √2 = 1.41421356237309504880… = 1.6a09e667f3bcc908…₁₆
+    #[test]
+    fn sqrt2_base10_and_base16() {
+        let got: Vec<u8> = Sqrt2Stream::new().take(21).collect();
+        assert_eq!(got, [1,4,1,4,2,1,3,5,6,2,3,7,3,0,9,5,0,4,8,8,0]);
+        assert_eq!(Sqrt2Stream::with_base(16).format_in_base(17), "1.6a09e667f3bcc908");
+        assert_eq!(Constant::Sqrt2.format_in_base(2, 10), "1.011010100");
+    }
+
+    // ── φ ────────────────────────────────────────────────────────────────
+    // φ = 1.61803398874989484820… = 1.9e3779b97f4a7c15…₁₆
+    #[test]
+    fn phi_base10_and_base16() {
+        let got: Vec<u8> = PhiStream::new().take(21).collect();
+        assert_eq!(got, [1,6,1,8,0,3,3,9,8,8,7,4,9,8,9,4,8,4,8,2,0]);
+        assert_eq!(PhiStream::with_base(16).format_in_base(17), "1.9e3779b97f4a7c15");
+        assert_eq!(Constant::Phi.format_in_base(2, 10), "1.100111100");
+    }
+
+    // ── p/q ──────────────────────────────────────────────────────────────
+    #[test]
+    fn rational_repeats_and_ends() {
+        let got: Vec<u8> = RationalStream::new(1, 7, 10).take(14).collect();
+        assert_eq!(got, [0,1,4,2,8,5,7,1,4,2,8,5,7,1]);
+        assert_eq!(RationalStream::new(3, 8, 2).format_in_base(6), "0.01100");
+        assert_eq!(Constant::Rational(22, 7).digits_in_base(2, 5), [1,1,0,0,1]);
+        assert_eq!(RationalStream::new(u64::MAX - 1, u64::MAX, 36).take(3).count(), 3);
+    }
+
+    // ── continued fractions ──────────────────────────────────────────────
+    #[test]
+    fn cf_terms_of_algebraics_and_rationals() {
+        assert_eq!(Constant::Sqrt2.cf_terms(8), [1,2,2,2,2,2,2,2]);
+        assert_eq!(Constant::Phi.cf_terms_in_base(3, 8), [1; 8]);
+        assert_eq!(Constant::Rational(415, 93).cf_terms(10), [4,2,6,7]);
+        assert_eq!(Constant::Rational(7, 7).cf_terms(10), [1]);
+    }
+
+    #[test]
+    fn cf_terms_of_champernowne() {
+        let terms = Constant::Champernowne.cf_terms(19);
+        assert_eq!(terms[..18], [0,8,9,1,149083,1,1,1,4,1,1,1,3,4,1,1,1,15]);
+        // The next term has 166 digits.
+        assert_eq!(terms[18], u64::MAX);
+    }
+
     // ── Constant enum ────────────────────────────────────────────────────
     #[test]
     fn constant_digits_in_base() {
@@ -1,7 +1,8 @@
-//! Interactive menu for exploring the six transcendental spigot streams.
-//! Supports base selection (2–36) for every constant.
+//! Interactive menu for exploring the eight spigot streams.
+//! Supports base selection (2–36) for every constant, and statistics on
+//! the digits shown.
 
-use spigot_stream::{Constant, digit_char};
+use spigot_stream::{Constant, digit_char, stats::DigitReport};
 use std::io::{self, Write};
 
 fn main() {
@@ -13,7 +14,7 @@ fn main() {
 
     loop {
         print_menu();
-        let choice = read_line("Select a constant (1–6, or q to quit): ");
+        let choice = read_line("Select a constant (1–8, or q to quit): ");
 
         if choice.trim().eq_ignore_ascii_case("q") {
             println!("\nGoodbye!\n");
@@ -27,7 +28,9 @@ fn main() {
             "4" => Constant::Liouville,
             "5" => Constant::Champernowne,
             "6" => Constant::ThueMorse,
-            _   => { println!("  ⚠  Please enter 1–6 or q.\n"); continue; }
+            "7" => Constant::Sqrt2,
+            "8" => Constant::Phi,
+            _   => { println!("  ⚠  Please enter 1–8 or q.\n"); continue; }
         };
 
         // Base selection
@@ -80,6 +83,13 @@ fn main() {
             println!();
             println!("  Raw digit vec : {:?}", &digits);
         }
+
+        if read_line("\n  Show statistics for these digits? (y/N): ").trim().eq_ignore_ascii_case("y") {
+            println!();
+            for line in DigitReport::new(base, &digits, 5).to_string().lines() {
+                println!("  {}", line);
+            }
+        }
         println!();
     }
 }
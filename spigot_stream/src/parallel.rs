@@ -0,0 +1,182 @@
+//! Many digits at once, in chunks worked out on separate threads.
+//!
+//! The streams make each digit from the state the last one left, so a
+//! million digits take one thread a long time.  Some constants don't need
+//! the digits before a chunk to work it out:
+//!
+//! * Liouville's, Champernowne's and Thue–Morse follow a rule on the
+//!   position, and a rational's `k`-th digit comes from `p·b^k mod q`.
+//! * π in a power-of-two base comes from the Bailey–Borwein–Plouffe
+//!   formula, which gives the hex digits from any position on.
+//! * √2 and φ recompute each chunk as the last digits of `⌊x·b^k⌋`, from an
+//!   integer square root.
+//!
+//! [`Constant::digits_in_base_parallel`] splits the digits into one chunk
+//! per thread for these and stitches the chunks back together.  e, ln 2
+//! and π in other bases have no such shortcut and stream as usual.
+
+use std::thread;
+
+use num_bigint::BigUint;
+
+use crate::{check_base, mod_pow, padded_digits, pi_hex, ChampernowneStream, Constant, RationalStream};
+
+impl Constant {
+    /// The first `n` digits in `base`, as [`digits_in_base`](Self::digits_in_base)
+    /// gives them, worked out in up to `threads` chunks at once where the
+    /// constant allows; see the [module docs](crate::parallel).
+    ///
+    /// ```rust
+    /// use spigot_stream::Constant;
+    ///
+    /// let digits = Constant::Champernowne.digits_in_base_parallel(10, 100_000, 4);
+    /// assert_eq!(digits, Constant::Champernowne.digits_in_base(10, 100_000));
+    /// ```
+    pub fn digits_in_base_parallel(self, base: u8, n: usize, threads: usize) -> Vec<u8> {
+        check_base(base);
+        if n == 0 { return Vec::new(); }
+        if !has_shortcut(self, base) { return self.digits_in_base(base, n); }
+        let size = n.div_ceil(threads.clamp(1, n));
+        thread::scope(|s| {
+            let chunks: Vec<_> = (0..n).step_by(size)
+                .map(|from| s.spawn(move || digits_between(self, base, from, (from + size).min(n))))
+                .collect();
+            chunks.into_iter()
+                .flat_map(|chunk| chunk.join().expect("digit chunk panicked"))
+                .collect()
+        })
+    }
+}
+
+/// Whether the digits of `constant` in `base` can be worked out from any
+/// position on.
+fn has_shortcut(constant: Constant, base: u8) -> bool {
+    match constant {
+        Constant::E | Constant::Ln2 => false,
+        Constant::Pi                => base.is_power_of_two(),
+        _                           => true,
+    }
+}
+
+/// Digits `from..to`, for a constant with a shortcut.
+fn digits_between(constant: Constant, base: u8, from: usize, to: usize) -> Vec<u8> {
+    let len = to - from;
+    match constant {
+        Constant::Liouville      => (from..to).map(|i| is_factorial(i) as u8).collect(),
+        Constant::ThueMorse      => (from..to).map(|i| (i.count_ones() % 2) as u8).collect(),
+        Constant::Champernowne   => champernowne_from(base, from).take(len).collect(),
+        Constant::Rational(p, q) => rational_from(p, q, base, from).take(len).collect(),
+        Constant::Sqrt2          => root_digits(base, from, to, |scale| (scale * scale * 2u32).sqrt()),
+        // ⌊φ·b^m⌋ = ⌊(b^m + ⌊√(5·b^(2m))⌋) / 2⌋
+        Constant::Phi            => root_digits(base, from, to, |scale| ((scale * scale * 5u32).sqrt() + scale) >> 1),
+        Constant::Pi             => pi_digits(base, from, to),
+        Constant::E | Constant::Ln2 => unreachable!("no shortcut for {:?}", constant),
+    }
+}
+
+/// Liouville's constant has a 1 at positions 1!, 2!, 3!, …
+fn is_factorial(i: usize) -> bool {
+    let (mut f, mut k) = (1, 1);
+    while f < i {
+        k += 1;
+        f *= k;
+    }
+    i > 0 && f == i
+}
+
+/// Champernowne's stream picked up at digit `from`.
+fn champernowne_from(base: u8, from: usize) -> ChampernowneStream {
+    let mut stream = ChampernowneStream::with_base(base);
+    if from == 0 { return stream; }
+    // Skip the integers of each length whole: (b − 1)·b^(len−1) of them.
+    let b = base as u64;
+    let (mut skip, mut len, mut first) = (from as u64 - 1, 1, 1);
+    while skip >= len * (b - 1) * first {
+        skip  -= len * (b - 1) * first;
+        len   += 1;
+        first *= b;
+    }
+    let n = first + skip / len;
+    stream.led         = true;
+    stream.digit_buf   = ChampernowneStream::int_to_digits(n, base).split_off((skip % len) as usize);
+    stream.current_int = n + 1;
+    stream
+}
+
+/// The stream of `p/q` picked up at digit `from`: after the integer part,
+/// the remainder `k` digits in is `p·b^k mod q`.
+fn rational_from(p: u64, q: u64, base: u8, from: usize) -> RationalStream {
+    let mut stream = RationalStream::new(p, q, base);
+    let whole = stream.whole.len();
+    if from < whole {
+        stream.whole.truncate(whole - from);
+    } else {
+        stream.whole.clear();
+        let shift = mod_pow(base as u64, (from - whole) as u64, q);
+        stream.rem = ((stream.rem as u128 * shift as u128) % q as u128) as u64;
+    }
+    stream
+}
+
+/// Digits `from..to` of a number below `base`, given `⌊x·s⌋` for `s` a
+/// power of the base: the last `to − from` digits of `⌊x·b^(to−1)⌋`.
+fn root_digits(base: u8, from: usize, to: usize, scaled: impl Fn(&BigUint) -> BigUint) -> Vec<u8> {
+    let b = BigUint::from(base);
+    let whole = scaled(&b.pow(to as u32 - 1));
+    let tail = whole % b.pow((to - from) as u32);
+    padded_digits(&tail, base, to - from)
+}
+
+/// Digits `from..to` of π in a power-of-two base: the integer part 3, then
+/// the fraction cut from its bits.
+fn pi_digits(base: u8, from: usize, to: usize) -> Vec<u8> {
+    let whole = padded_digits(&BigUint::from(3u32), base, if base == 2 { 2 } else { 1 });
+    let bits = base.trailing_zeros() as usize;
+    let mut out: Vec<u8> = whole.iter().copied().skip(from).take(to - from).collect();
+    let (start, end) = (from.max(whole.len()) - whole.len(), to.saturating_sub(whole.len()));
+    if start >= end { return out; }
+    // The hex digits holding bits start·k .. end·k of the fraction.
+    let hex_from = start * bits / 4;
+    let hex_to = (end * bits).div_ceil(4);
+    let hex = pi_hex(hex_from, hex_to - hex_from);
+    let bit = |j: usize| (hex[j / 4] >> (3 - j % 4)) & 1;
+    for i in start..end {
+        let offset = i * bits - 4 * hex_from;
+        out.push((offset..offset + bits).fold(0, |d, j| d << 1 | bit(j)));
+    }
+    out
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_stitch_to_the_stream() {
+        let constants = [Constant::Pi, Constant::Liouville, Constant::Champernowne,
+                         Constant::ThueMorse, Constant::Sqrt2, Constant::Phi,
+                         Constant::Rational(22, 7), Constant::E];
+        for constant in constants {
+            for base in [2, 8, 10, 16] {
+                let streamed = constant.digits_in_base(base, 300);
+                for threads in [1, 7, 300] {
+                    assert_eq!(constant.digits_in_base_parallel(base, 300, threads), streamed,
+                               "{:?} in base {} on {} threads", constant, base, threads);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pi_in_hex_and_binary() {
+        let hex: String = Constant::Pi.digits_in_base_parallel(16, 65, 3)
+            .into_iter().map(crate::digit_char).collect();
+        assert_eq!(hex, "3243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89");
+        assert_eq!(Constant::Pi.digits_in_base_parallel(2, 12, 5), [1,1,0,0,1,0,0,1,0,0,0,0]);
+        assert_eq!(Constant::Pi.digits_in_base_parallel(8, 6, 2), [3,1,1,0,3,7]);
+    }
+}
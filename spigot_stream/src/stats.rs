@@ -0,0 +1,352 @@
+//! Digit statistics — how often each digit turns up in a stream.
+//!
+//! A constant is *normal* in base `b` if, in the limit, every digit turns up
+//! `1/b` of the time.  Nobody has proved that π, e or ln 2 are normal, but
+//! their digits look it; Champernowne's constant is provably normal in base
+//! 10, while Liouville's and Thue–Morse are plainly not.  [`DigitStats`]
+//! tallies a prefix of a stream so the difference can be watched as it grows.
+//!
+//! Even counts aren't the whole story — 0123456789 repeated has perfectly
+//! even ones.  [`RunLengths`] tallies runs of a repeated digit, and
+//! [`autocorrelation`] says how much a digit predicts the one `lag` places
+//! on.  [`DigitReport`] puts all of it together for one window of digits,
+//! as the explorer binary prints it.
+//!
+//! ```rust
+//! use spigot_stream::{ChampernowneStream, stats::DigitStats};
+//!
+//! // 0.123…9899: every digit but 0 appears 20 times, 0 only 10
+//! let stats = DigitStats::from_digits(10, ChampernowneStream::new().take(190));
+//! assert_eq!(stats.count(1), 20);
+//! assert_eq!(stats.count(0), 10);
+//! ```
+
+use std::fmt;
+
+use crate::{check_base, digit_char};
+
+/// Running per-digit counts for a stream in a given base.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigitStats {
+    base:   u8,
+    counts: Vec<u64>,
+    total:  u64,
+}
+
+impl DigitStats {
+    /// Empty counts for digits `0..base`.
+    pub fn new(base: u8) -> Self {
+        check_base(base);
+        DigitStats { base, counts: vec![0; base as usize], total: 0 }
+    }
+
+    /// Counts for every digit of `digits`.
+    pub fn from_digits<I: IntoIterator<Item = u8>>(base: u8, digits: I) -> Self {
+        let mut stats = DigitStats::new(base);
+        stats.extend(digits);
+        stats
+    }
+
+    /// Count one more digit.  Digits outside `0..base` are ignored.
+    pub fn add(&mut self, d: u8) {
+        if let Some(c) = self.counts.get_mut(d as usize) {
+            *c += 1;
+            self.total += 1;
+        }
+    }
+
+    pub fn base(&self)   -> u8     { self.base }
+    pub fn total(&self)  -> u64    { self.total }
+    pub fn counts(&self) -> &[u64] { &self.counts }
+
+    /// Times `d` has been seen.
+    pub fn count(&self, d: u8) -> u64 {
+        self.counts.get(d as usize).copied().unwrap_or(0)
+    }
+
+    /// Share of the digits seen that were `d`, 0.0–1.0 (0.0 before any).
+    pub fn frequency(&self, d: u8) -> f64 {
+        if self.total == 0 { return 0.0; }
+        self.count(d) as f64 / self.total as f64
+    }
+
+    /// The share every digit would have in a normal number, `1/base`.
+    pub fn expected(&self) -> f64 {
+        1.0 / self.base as f64
+    }
+
+    /// Pearson's χ² against the uniform distribution — near `base − 1` for
+    /// digits that look random, far larger for ones that don't.
+    pub fn chi_squared(&self) -> f64 {
+        if self.total == 0 { return 0.0; }
+        let expected = self.total as f64 * self.expected();
+        self.counts.iter()
+            .map(|&c| (c as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    /// The chance that uniformly random digits would give a χ² at least
+    /// this large (`base − 1` degrees of freedom).  Below 0.01 or so, the
+    /// digits are lopsided; 1.0 before any.
+    pub fn uniformity_p(&self) -> f64 {
+        if self.total == 0 { return 1.0; }
+        upper_gamma((self.base - 1) as f64 / 2.0, self.chi_squared() / 2.0)
+    }
+
+    /// A text bar chart, one line per digit, the commonest digit's bar
+    /// `width` characters long.
+    pub fn histogram(&self, width: usize) -> String {
+        let most = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        self.counts.iter().enumerate()
+            .map(|(d, &c)| {
+                let bar = (c as f64 / most as f64 * width as f64).round() as usize;
+                format!("{} {:<width$} {}\n", digit_char(d as u8), "█".repeat(bar), c)
+            })
+            .collect()
+    }
+}
+
+impl Extend<u8> for DigitStats {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, digits: I) {
+        for d in digits { self.add(d); }
+    }
+}
+
+/// Q(a, x), the regularized upper incomplete gamma function: a series
+/// below `x = a + 1`, Lentz's continued fraction above.
+fn upper_gamma(a: f64, x: f64) -> f64 {
+    if x <= 0.0 { return 1.0; }
+    let scale = (a * x.ln() - x - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        let (mut term, mut sum, mut n) = (1.0 / a, 1.0 / a, a);
+        while term.abs() > sum.abs() * 1e-15 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+        }
+        (1.0 - sum * scale).max(0.0)
+    } else {
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let (mut c, mut d) = (1.0 / tiny, 1.0 / b);
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny { d = tiny; }
+            c = b + an / c;
+            if c.abs() < tiny { c = tiny; }
+            d = 1.0 / d;
+            let step = d * c;
+            h *= step;
+            if (step - 1.0).abs() < 1e-15 { break; }
+        }
+        scale * h
+    }
+}
+
+/// ln Γ(x) for x > 0, by Lanczos' approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const G: [f64; 6] = [76.180_091_729_471_46, -86.505_320_329_416_77, 24.014_098_240_830_91,
+                         -1.231_739_572_450_155, 0.001_208_650_973_866_179, -0.000_005_395_239_384_953];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let series: f64 = G.iter().enumerate().map(|(j, g)| g / (x + 1.0 + j as f64)).sum();
+    -tmp + (2.506_628_274_631_000_5 * (1.000_000_000_190_015 + series) / x).ln()
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Runs
+// ════════════════════════════════════════════════════════════════════════════
+
+/// One digit repeated: `len` copies of `digit` starting at `start`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Run {
+    pub digit: u8,
+    pub start: usize,
+    pub len:   usize,
+}
+
+/// How many runs of each length a window of digits has.  Random digits in
+/// base `b` average `b/(b − 1)` to a run, and a run of `k` turns up about
+/// once in `b^(k−1)` digits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunLengths {
+    base:    u8,
+    /// Runs of each length, indexed by length; `counts[0]` is unused.
+    counts:  Vec<u64>,
+    runs:    u64,
+    digits:  u64,
+    longest: Option<Run>,
+}
+
+impl RunLengths {
+    /// The runs of `digits`, in base `base`.
+    pub fn from_digits<I: IntoIterator<Item = u8>>(base: u8, digits: I) -> Self {
+        check_base(base);
+        let mut tally = RunLengths { base, counts: vec![0], runs: 0, digits: 0, longest: None };
+        let mut current: Option<Run> = None;
+        for (i, d) in digits.into_iter().enumerate() {
+            match &mut current {
+                Some(run) if run.digit == d => run.len += 1,
+                _ => {
+                    if let Some(run) = current.replace(Run { digit: d, start: i, len: 1 }) {
+                        tally.close(run);
+                    }
+                }
+            }
+        }
+        if let Some(run) = current { tally.close(run); }
+        tally
+    }
+
+    fn close(&mut self, run: Run) {
+        if self.counts.len() <= run.len { self.counts.resize(run.len + 1, 0); }
+        self.counts[run.len] += 1;
+        self.runs   += 1;
+        self.digits += run.len as u64;
+        if self.longest.is_none_or(|l| run.len > l.len) { self.longest = Some(run); }
+    }
+
+    pub fn base(&self) -> u8  { self.base }
+    pub fn runs(&self) -> u64 { self.runs }
+
+    /// Runs exactly `len` digits long.
+    pub fn count(&self, len: usize) -> u64 {
+        self.counts.get(len).copied().unwrap_or(0)
+    }
+
+    /// The first of the longest runs, if there were any digits.
+    pub fn longest(&self) -> Option<Run> { self.longest }
+
+    /// Digits to a run on average (0.0 before any).
+    pub fn mean(&self) -> f64 {
+        if self.runs == 0 { return 0.0; }
+        self.digits as f64 / self.runs as f64
+    }
+
+    /// The average for random digits, `b/(b − 1)`.
+    pub fn expected_mean(&self) -> f64 {
+        self.base as f64 / (self.base - 1) as f64
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Autocorrelation
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Pearson's correlation between each digit and the one `lag` places on,
+/// −1.0–1.0: near 0 for random digits, near 1 where a digit tends to come
+/// back after `lag`, and 0.0 when there are too few digits or they never
+/// vary.
+pub fn autocorrelation(digits: &[u8], lag: usize) -> f64 {
+    if lag >= digits.len() { return 0.0; }
+    let n = digits.len() as f64;
+    let mean = digits.iter().map(|&d| d as f64).sum::<f64>() / n;
+    let variance: f64 = digits.iter().map(|&d| (d as f64 - mean).powi(2)).sum();
+    if variance == 0.0 { return 0.0; }
+    let covariance: f64 = digits.iter().zip(&digits[lag..])
+        .map(|(&a, &b)| (a as f64 - mean) * (b as f64 - mean))
+        .sum();
+    covariance / variance
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// DigitReport
+// ════════════════════════════════════════════════════════════════════════════
+
+/// Counts, uniformity, runs and autocorrelation of one window of digits,
+/// for choosing a constant and base before composing with them.
+///
+/// ```rust
+/// use spigot_stream::{Constant, stats::DigitReport};
+///
+/// let report = DigitReport::new(2, &Constant::ThueMorse.digits_in_base(2, 512), 4);
+/// assert_eq!(report.runs.longest().unwrap().len, 2);   // no bit thrice running
+/// assert!(report.autocorrelation[0] < -0.3);            // and bits tend to alternate
+/// println!("{}", report);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DigitReport {
+    pub stats: DigitStats,
+    pub runs:  RunLengths,
+    /// At lags 1, 2, …
+    pub autocorrelation: Vec<f64>,
+}
+
+impl DigitReport {
+    /// Everything about `digits` (in `base`), with the autocorrelation up
+    /// to `lags` places on.
+    pub fn new(base: u8, digits: &[u8], lags: usize) -> Self {
+        DigitReport {
+            stats: DigitStats::from_digits(base, digits.iter().copied()),
+            runs:  RunLengths::from_digits(base, digits.iter().copied()),
+            autocorrelation: (1..=lags).map(|lag| autocorrelation(digits, lag)).collect(),
+        }
+    }
+}
+
+impl fmt::Display for DigitReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = &self.stats;
+        writeln!(f, "digits   : {} in base {}", s.total(), s.base())?;
+        writeln!(f, "χ²       : {:.2} ({} degrees of freedom, p = {:.3})",
+                 s.chi_squared(), s.base() - 1, s.uniformity_p())?;
+        write!(f, "runs     : {}, {:.3} digits each ({:.3} if random)",
+               self.runs.runs(), self.runs.mean(), self.runs.expected_mean())?;
+        if let Some(run) = self.runs.longest() {
+            write!(f, ", longest {} × '{}' at {}", run.len, digit_char(run.digit), run.start)?;
+        }
+        writeln!(f)?;
+        let lags: Vec<String> = self.autocorrelation.iter().enumerate()
+            .map(|(i, r)| format!("{}: {:+.3}", i + 1, r))
+            .collect();
+        writeln!(f, "autocorr : {}", lags.join("  "))?;
+        write!(f, "{}", s.histogram(40))
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LiouvilleStream, ThueMorseStream};
+
+    #[test]
+    fn counts_and_frequencies() {
+        let mut stats = DigitStats::from_digits(10, [3, 1, 4, 1, 5]);
+        assert_eq!((stats.total(), stats.count(1)), (5, 2));
+        assert_eq!(stats.frequency(1), 0.4);
+        stats.add(12);
+        assert_eq!(stats.total(), 5);
+        assert_eq!(DigitStats::new(16).frequency(0), 0.0);
+    }
+
+    #[test]
+    fn chi_squared_tells_even_from_lopsided() {
+        let even     = DigitStats::from_digits(2, ThueMorseStream::new().take(256));
+        let lopsided = DigitStats::from_digits(2, LiouvilleStream::new().take(256));
+        assert_eq!(even.chi_squared(), 0.0);
+        assert!(lopsided.chi_squared() > 200.0);
+        assert_eq!(even.uniformity_p(), 1.0);
+        assert!(lopsided.uniformity_p() < 1e-10);
+        // χ² = 16.919 is the 5% point with 9 degrees of freedom, 3.841
+        // the one with 1.
+        assert!((upper_gamma(4.5, 16.919 / 2.0) - 0.05).abs() < 1e-4);
+        assert!((upper_gamma(0.5, 3.841 / 2.0) - 0.05).abs() < 1e-4);
+    }
+
+    #[test]
+    fn runs_and_autocorrelation() {
+        let runs = RunLengths::from_digits(10, [1, 1, 2, 3, 3, 3, 1]);
+        assert_eq!((runs.runs(), runs.count(1), runs.count(3)), (4, 2, 1));
+        assert_eq!(runs.longest(), Some(Run { digit: 3, start: 3, len: 3 }));
+        assert_eq!(runs.mean(), 7.0 / 4.0);
+        let steps: Vec<u8> = (0..100).map(|i| i % 10).collect();
+        assert!(autocorrelation(&steps, 10) > 0.85);
+        assert_eq!(autocorrelation(&[4, 4, 4], 1), 0.0);
+    }
+}
@@ -0,0 +1,189 @@
+//! # spigot_wasm
+//!
+//! The spigot streams and MIDI composer for JavaScript, so web music toys
+//! can make spigot MIDI entirely in the browser.  Build the npm package
+//! with [wasm-pack](https://rustwasm.github.io/wasm-pack/):
+//!
+//! ```text
+//! wasm-pack build --release --target bundler    # or web, nodejs
+//! wasm-pack publish                             # from pkg/
+//! ```
+//!
+//! Streams are named `CONSTANT[:BASE]` as on the `spigot_midi` command line
+//! (`pi`, `e:16`, `thuemorse:2`, …):
+//!
+//! ```js
+//! import { digitsInBase, zipTake, composeMidiBytes, ComposeOptions } from "spigot_wasm";
+//!
+//! digitsInBase("pi", 16, 8);                 // Uint8Array [3, 2, 4, 3, 15, 6, 10, 8]
+//! const pairs = zipTake("pi", "e", 32);      // pairs.left, pairs.right
+//!
+//! const opts = new ComposeOptions();
+//! opts.tempo = 96;
+//! opts.scale = "dorian";
+//! const smf = composeMidiBytes("pi", "e", 64, opts);   // a standard MIDI file
+//! const url = URL.createObjectURL(new Blob([smf], { type: "audio/midi" }));
+//! ```
+//!
+//! Each call gives at most [`MAX_DIGITS`] digits, pairs or notes.
+
+use wasm_bindgen::prelude::*;
+
+use dual_spigot::{BoxedSpigot, DualStream};
+use spigot_midi::provenance::parse_stream;
+use spigot_midi::{DurationMap, MidiComposer, MidiError, PitchMap, Scale};
+
+/// Most digits, pairs or notes one call gives.
+pub const MAX_DIGITS: usize = 100_000;
+
+/// The first `n` digits of `constant` (a name such as `"pi"`) in `base`
+/// 2–36.
+#[wasm_bindgen(js_name = digitsInBase)]
+pub fn digits_in_base(constant: &str, base: u8, n: usize) -> Result<Vec<u8>, JsError> {
+    digits(constant, base, n).map_err(js)
+}
+
+/// Digit pairs, the left stream's digits and the right's, side by side.
+#[wasm_bindgen]
+pub struct Pairs {
+    left:  Vec<u8>,
+    right: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Pairs {
+    #[wasm_bindgen(getter)]
+    pub fn left(&self) -> Vec<u8> { self.left.clone() }
+
+    #[wasm_bindgen(getter)]
+    pub fn right(&self) -> Vec<u8> { self.right.clone() }
+
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize { self.left.len() }
+}
+
+/// The first `n` digit pairs of streams `left` and `right`.
+#[wasm_bindgen(js_name = zipTake)]
+pub fn zip_take(left: &str, right: &str, n: usize) -> Result<Pairs, JsError> {
+    let (left, right) = zip(left, right, n).map_err(js)?.into_iter().unzip();
+    Ok(Pairs { left, right })
+}
+
+/// How [`compose_midi_bytes`] turns digit pairs into notes.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct ComposeOptions {
+    pub tempo:             f64,
+    /// General MIDI program, 0–127.
+    pub program:           u8,
+    /// MIDI note for digit 0 of the scale.
+    pub root:              u8,
+    /// Scale name: `major`, `dorian`, `whole-tone`, …
+    pub scale:             String,
+    pub velocity:          u8,
+    /// Channel 0–15.
+    pub channel:           u8,
+    #[wasm_bindgen(js_name = ticksPerQuarter)]
+    pub ticks_per_quarter: u16,
+}
+
+#[wasm_bindgen]
+impl ComposeOptions {
+    /// Piano in C major from middle C at 120 BPM.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        ComposeOptions {
+            tempo: 120.0, program: 0, root: 60, scale: "major".to_string(),
+            velocity: 100, channel: 0, ticks_per_quarter: 480,
+        }
+    }
+}
+
+impl Default for ComposeOptions {
+    fn default() -> Self { ComposeOptions::new() }
+}
+
+/// A standard MIDI file of `notes` notes from streams `left` (durations)
+/// and `right` (pitches), as bytes.
+#[wasm_bindgen(js_name = composeMidiBytes)]
+pub fn compose_midi_bytes(left: &str, right: &str, notes: usize, options: Option<ComposeOptions>)
+    -> Result<Vec<u8>, JsError>
+{
+    compose(left, right, notes, &options.unwrap_or_default()).map_err(js)
+}
+
+// ── the same, with Rust errors ─────────────────────────────────────────────
+
+fn js(e: MidiError) -> JsError { JsError::new(&e.to_string()) }
+
+fn at_most(what: &str, n: usize) -> Result<(), MidiError> {
+    if n > MAX_DIGITS {
+        return Err(MidiError::Parameter(format!("{} must be at most {}, got {}", what, MAX_DIGITS, n)));
+    }
+    Ok(())
+}
+
+fn digits(constant: &str, base: u8, n: usize) -> Result<Vec<u8>, MidiError> {
+    at_most("n", n)?;
+    let cfg = parse_stream(&format!("{}:{}", constant, base))?;
+    Ok(BoxedSpigot::from_config(cfg).take_n(n))
+}
+
+fn zip(left: &str, right: &str, n: usize) -> Result<Vec<(u8, u8)>, MidiError> {
+    at_most("n", n)?;
+    Ok(DualStream::from_configs(parse_stream(left)?, parse_stream(right)?).zip_take(n))
+}
+
+fn compose(left: &str, right: &str, notes: usize, opts: &ComposeOptions) -> Result<Vec<u8>, MidiError> {
+    at_most("notes", notes)?;
+    let scale: Scale = opts.scale.parse()?;
+    if !(opts.tempo.is_finite() && opts.tempo > 0.0) {
+        return Err(MidiError::Parameter(format!("tempo must be a positive number of BPM, got {}", opts.tempo)));
+    }
+    if opts.program > 127 || opts.root > 127 || opts.velocity > 127 || opts.channel > 15
+        || opts.ticks_per_quarter == 0
+    {
+        return Err(MidiError::Parameter(
+            "program, root and velocity must be 0–127, channel 0–15, ticksPerQuarter above 0".to_string()));
+    }
+    let track = MidiComposer::from_pairs(&zip(left, right, notes)?)
+        .tempo(opts.tempo)
+        .instrument_raw(opts.program)
+        .pitch_map(PitchMap::custom(opts.root, scale))
+        .ticks_per_quarter(opts.ticks_per_quarter)
+        .duration_map(DurationMap::musical(opts.ticks_per_quarter as u32))
+        .velocity(opts.velocity)
+        .channel(opts.channel)
+        .compose(notes)?;
+    Ok(track.to_bytes())
+}
+
+// ════════════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gives_the_documented_digits() {
+        assert_eq!(digits("pi", 16, 8).unwrap(), [3, 2, 4, 3, 15, 6, 10, 8]);
+        assert_eq!(digits("1/7", 10, 4).unwrap(), [0, 1, 4, 2]);
+        assert!(digits("tau", 10, 4).is_err());
+        assert!(digits("pi", 10, MAX_DIGITS + 1).is_err());
+    }
+
+    #[test]
+    fn composes_a_midi_file() {
+        assert_eq!(zip("champernowne", "thuemorse:2", 3).unwrap(), [(0, 0), (1, 1), (2, 1)]);
+        let smf = compose("champernowne", "thuemorse:2", 8, &ComposeOptions::default()).unwrap();
+        assert_eq!(&smf[..4], b"MThd");
+        let bad = ComposeOptions { scale: "no such scale".to_string(), ..ComposeOptions::default() };
+        assert!(compose("pi", "e", 8, &bad).is_err());
+        let bad = ComposeOptions { program: 128, ..ComposeOptions::default() };
+        assert!(compose("pi", "e", 8, &bad).is_err());
+        assert!(compose("pi", "e", usize::MAX, &ComposeOptions::default()).is_err());
+        assert!(zip("pi", "e", usize::MAX).is_err());
+    }
+}